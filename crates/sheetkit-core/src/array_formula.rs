@@ -0,0 +1,179 @@
+//! Array formula support.
+//!
+//! An array formula is stored once, on its anchor (top-left) cell, as
+//! `<f t="array" ref="A1:B3">...</f>`. Every cell of `ref` holds part of the
+//! result: the anchor caches the top-left value and the other cells carry
+//! plain cached values without a formula element.
+//!
+//! Dynamic array formulas (FILTER, SEQUENCE, ...) use the same layout; their
+//! range "spills" to fit the result and is recomputed on every calculation.
+
+use sheetkit_xml::worksheet::{CellFormula, WorksheetXml};
+
+use crate::error::Result;
+use crate::utils::cell_ref::{cell_name_to_coordinates, coordinates_to_cell_name};
+use crate::utils::constants::{MAX_COLUMNS, MAX_ROWS};
+
+/// A rectangular block of cells occupied by an array formula result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ArrayArea {
+    pub min_col: u32,
+    pub min_row: u32,
+    pub max_col: u32,
+    pub max_row: u32,
+}
+
+impl ArrayArea {
+    /// Parse an A1-style reference such as `"B2:C4"` or `"B2"`.
+    pub fn parse(reference: &str) -> Option<Self> {
+        let (first, last) = reference.split_once(':').unwrap_or((reference, reference));
+        let (c1, r1) = cell_name_to_coordinates(first.trim()).ok()?;
+        let (c2, r2) = cell_name_to_coordinates(last.trim()).ok()?;
+        Some(Self {
+            min_col: c1.min(c2),
+            min_row: r1.min(r2),
+            max_col: c1.max(c2),
+            max_row: r1.max(r2),
+        })
+    }
+
+    /// The area a `rows` x `cols` result occupies when anchored at
+    /// `(col, row)`, or `None` if it would extend past the sheet edge.
+    pub fn spill(col: u32, row: u32, rows: usize, cols: usize) -> Option<Self> {
+        let max_col = col as u64 + cols as u64 - 1;
+        let max_row = row as u64 + rows as u64 - 1;
+        if rows == 0 || cols == 0 || max_col > MAX_COLUMNS as u64 || max_row > MAX_ROWS as u64 {
+            return None;
+        }
+        Some(Self {
+            min_col: col,
+            min_row: row,
+            max_col: max_col as u32,
+            max_row: max_row as u32,
+        })
+    }
+
+    /// Return `true` if `(col, row)` lies inside the area.
+    pub fn contains(&self, col: u32, row: u32) -> bool {
+        (self.min_col..=self.max_col).contains(&col) && (self.min_row..=self.max_row).contains(&row)
+    }
+
+    /// Iterate over the `(col, row)` coordinates of the area in row-major order.
+    pub fn cells(&self) -> impl Iterator<Item = (u32, u32)> {
+        let area = *self;
+        (area.min_row..=area.max_row)
+            .flat_map(move |r| (area.min_col..=area.max_col).map(move |c| (c, r)))
+    }
+
+    /// Format the area as an A1-style reference, e.g. `"B2:C4"`.
+    pub fn to_reference(self) -> Result<String> {
+        let first = coordinates_to_cell_name(self.min_col, self.min_row)?;
+        if self.min_col == self.max_col && self.min_row == self.max_row {
+            return Ok(first);
+        }
+        let last = coordinates_to_cell_name(self.max_col, self.max_row)?;
+        Ok(format!("{first}:{last}"))
+    }
+}
+
+/// Return the range covered by an array formula element (`t="array"`).
+pub(crate) fn array_area(f: &CellFormula) -> Option<ArrayArea> {
+    if f.t.as_deref() != Some("array") {
+        return None;
+    }
+    ArrayArea::parse(f.reference.as_deref()?)
+}
+
+/// Return the ranges of the legacy array formulas on `ws`, the ones
+/// entered with Ctrl+Shift+Enter, leaving out dynamic array spills.
+pub(crate) fn legacy_array_areas(ws: &WorksheetXml) -> Vec<ArrayArea> {
+    ws.sheet_data
+        .rows
+        .iter()
+        .flat_map(|r| &r.cells)
+        .filter_map(|c| {
+            let f = c.f.as_deref()?;
+            let area = array_area(f)?;
+            let expr = crate::formula::parser::parse_formula(f.value.as_deref()?).ok()?;
+            (!crate::formula::eval::is_array_expr(&expr)).then_some(area)
+        })
+        .collect()
+}
+
+/// Find the array formula whose range covers `(col, row)`, returning the
+/// anchor coordinates and the range.
+pub(crate) fn find_array_formula(
+    ws: &WorksheetXml,
+    col: u32,
+    row: u32,
+) -> Option<((u32, u32), ArrayArea)> {
+    // The anchor is the top-left cell, so only rows up to `row` can hold it.
+    ws.sheet_data
+        .rows
+        .iter()
+        .take_while(|r| r.r <= row)
+        .flat_map(|r| r.cells.iter().map(move |c| (r.r, c)))
+        .filter(|(_, c)| c.col <= col)
+        .find_map(|(r, c)| {
+            let area = array_area(c.f.as_deref()?)?;
+            area.contains(col, row).then_some(((c.col, r), area))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format_area() {
+        let area = ArrayArea::parse("C4:B2").unwrap();
+        assert_eq!(
+            (area.min_col, area.min_row, area.max_col, area.max_row),
+            (2, 2, 3, 4)
+        );
+        assert_eq!(area.to_reference().unwrap(), "B2:C4");
+        assert_eq!(
+            ArrayArea::parse("D7").unwrap().to_reference().unwrap(),
+            "D7"
+        );
+        assert!(ArrayArea::parse("nope").is_none());
+    }
+
+    #[test]
+    fn test_spill_area() {
+        let area = ArrayArea::spill(2, 3, 2, 2).unwrap();
+        assert_eq!(
+            area.cells().collect::<Vec<_>>(),
+            vec![(2, 3), (3, 3), (2, 4), (3, 4)]
+        );
+        assert!(area.contains(3, 4));
+        assert!(!area.contains(4, 4));
+        assert!(ArrayArea::spill(MAX_COLUMNS, 1, 1, 2).is_none());
+    }
+
+    #[test]
+    fn test_array_area_requires_array_type() {
+        let mut f = CellFormula {
+            t: Some("array".to_string()),
+            reference: Some("A1:A3".to_string()),
+            si: None,
+            value: Some("SEQUENCE(3)".to_string()),
+        };
+        assert_eq!(array_area(&f).unwrap().max_row, 3);
+        f.t = Some("shared".to_string());
+        assert!(array_area(&f).is_none());
+    }
+
+    #[test]
+    fn test_find_array_formula_covers_members() {
+        let mut wb = crate::workbook::Workbook::new();
+        wb.set_cell_array_formula("Sheet1", "B2:C3", "A1:B2*2")
+            .unwrap();
+        let ws = wb.worksheet_ref("Sheet1").unwrap();
+        let (anchor, area) = find_array_formula(ws, 3, 3).unwrap();
+        assert_eq!(anchor, (2, 2));
+        assert_eq!(area.to_reference().unwrap(), "B2:C3");
+        assert!(find_array_formula(ws, 4, 3).is_none());
+        assert!(find_array_formula(ws, 1, 1).is_none());
+    }
+}
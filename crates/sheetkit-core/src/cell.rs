@@ -6,7 +6,7 @@
 
 use std::fmt;
 
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
 
 /// Represents the value of a cell.
 #[derive(Debug, Clone, PartialEq, Default)]
@@ -25,7 +25,11 @@ pub enum CellValue {
         expr: String,
         result: Option<Box<CellValue>>,
     },
-    /// A date/time value stored as an Excel serial number.
+    /// A date/time value stored as an Excel serial number in the 1900 date
+    /// system, whatever the date system of the workbook holding it. The
+    /// workbook converts dates when cells are read and written and when it
+    /// returns formula results; the formula evaluator itself works in the
+    /// date system of the workbook.
     /// Integer part = days since 1899-12-30 (Excel epoch).
     /// Fractional part = time of day (0.5 = noon).
     Date(f64),
@@ -48,18 +52,18 @@ impl fmt::Display for CellValue {
                     write!(f, "{n}")
                 }
             }
-            CellValue::Date(serial) => {
-                if let Some(dt) = serial_to_datetime(*serial) {
-                    if serial.fract() == 0.0 {
+            CellValue::Date(serial) => match DateSystem::Date1900.serial_to_ymd(*serial) {
+                Some((year, month, day)) => {
+                    write!(f, "{year:04}-{month:02}-{day:02}")?;
+                    match serial_time(*serial) {
                         // Date only, no time component.
-                        write!(f, "{}", dt.format("%Y-%m-%d"))
-                    } else {
-                        write!(f, "{}", dt.format("%Y-%m-%d %H:%M:%S"))
+                        _ if serial.fract() == 0.0 => Ok(()),
+                        Some(time) => write!(f, " {}", time.format("%H:%M:%S")),
+                        None => Ok(()),
                     }
-                } else {
-                    write!(f, "{serial}")
                 }
-            }
+                None => write!(f, "{serial}"),
+            },
             CellValue::String(s) => write!(f, "{s}"),
             CellValue::Formula { result, expr, .. } => {
                 if let Some(result) = result {
@@ -127,7 +131,8 @@ impl From<NaiveDateTime> for CellValue {
 /// Number of seconds in a day.
 const SECONDS_PER_DAY: f64 = 86_400.0;
 
-/// Convert a `NaiveDate` to an Excel serial number.
+/// Convert a `NaiveDate` to an Excel serial number in the 1900 date system.
+/// Use [`DateSystem::date_to_serial`] for workbooks in the 1904 date system.
 ///
 /// Serial number 1 = January 1, 1900. Accounts for the Excel 1900 leap year
 /// bug (serial 60 = the non-existent February 29, 1900).
@@ -156,20 +161,17 @@ pub fn datetime_to_serial(datetime: NaiveDateTime) -> f64 {
     date_part + seconds_since_midnight / SECONDS_PER_DAY
 }
 
-/// Convert an Excel serial number to a `NaiveDate`.
+/// Convert an Excel serial number in the 1900 date system to a `NaiveDate`.
 ///
-/// Returns `None` for invalid serial numbers (< 1).
+/// Returns `None` for invalid serial numbers (< 1) and for serial 60, the
+/// non-existent February 29, 1900, which [`DateSystem::serial_to_ymd`]
+/// still converts.
 pub fn serial_to_date(serial: f64) -> Option<NaiveDate> {
     let serial_int = serial.floor() as i64;
-    if serial_int < 1 {
+    if serial_int < 1 || serial_int == 60 {
         return None;
     }
     let epoch = NaiveDate::from_ymd_opt(1899, 12, 31).expect("valid epoch date 1899-12-31");
-    // Serial 60 = February 29, 1900 (Excel bug -- this date does not exist).
-    // Map it to February 28, 1900 for practical purposes.
-    if serial_int == 60 {
-        return NaiveDate::from_ymd_opt(1900, 2, 28);
-    }
     // For serial >= 61, subtract 1 to compensate for the phantom leap day.
     let adjusted = if serial_int >= 61 {
         serial_int - 1
@@ -184,13 +186,106 @@ pub fn serial_to_date(serial: f64) -> Option<NaiveDate> {
 /// Returns `None` for invalid serial numbers (< 1).
 pub fn serial_to_datetime(serial: f64) -> Option<NaiveDateTime> {
     let date = serial_to_date(serial)?;
+    Some(NaiveDateTime::new(date, serial_time(serial)?))
+}
+
+/// Return the time of day held in the fractional part of a serial number.
+pub(crate) fn serial_time(serial: f64) -> Option<NaiveTime> {
     let frac = serial.fract().abs();
     let total_seconds = (frac * SECONDS_PER_DAY).round() as u32;
     let hours = total_seconds / 3600;
     let minutes = (total_seconds % 3600) / 60;
     let seconds = total_seconds % 60;
-    let time = NaiveTime::from_hms_opt(hours, minutes, seconds)?;
-    Some(NaiveDateTime::new(date, time))
+    NaiveTime::from_hms_opt(hours, minutes, seconds)
+}
+
+/// Number of days between the 1900 and 1904 epochs: serial 0 in the 1904
+/// date system (1904-01-01) is serial 1462 in the 1900 date system.
+const DATE1904_OFFSET: f64 = 1462.0;
+
+/// The date system a workbook uses to map serial numbers to dates, set by
+/// the `date1904` attribute of `workbookPr`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateSystem {
+    /// Serial 1 is 1900-01-01, and serial 60 is the non-existent
+    /// 1900-02-29 that Excel keeps for Lotus 1-2-3 compatibility.
+    #[default]
+    Date1900,
+    /// Serial 0 is 1904-01-01. Used by workbooks created on older
+    /// versions of Excel for Mac.
+    Date1904,
+}
+
+impl DateSystem {
+    /// Return the date system selected by a `date1904` attribute.
+    pub fn from_date1904(date1904: bool) -> Self {
+        if date1904 {
+            DateSystem::Date1904
+        } else {
+            DateSystem::Date1900
+        }
+    }
+
+    /// Return `true` for the 1904 date system.
+    pub fn is_date1904(self) -> bool {
+        self == DateSystem::Date1904
+    }
+
+    /// Convert a `NaiveDate` to a serial number in this date system.
+    pub fn date_to_serial(self, date: NaiveDate) -> f64 {
+        self.from_1900_serial(date_to_serial(date))
+    }
+
+    /// Convert a `NaiveDateTime` to a serial number with fractional time in
+    /// this date system.
+    pub fn datetime_to_serial(self, datetime: NaiveDateTime) -> f64 {
+        self.from_1900_serial(datetime_to_serial(datetime))
+    }
+
+    /// Convert a serial number in this date system to a `NaiveDate`.
+    ///
+    /// Returns `None` for serial numbers before the first date of the
+    /// system (< 1 for 1900, < 0 for 1904).
+    pub fn serial_to_date(self, serial: f64) -> Option<NaiveDate> {
+        match self {
+            DateSystem::Date1900 => serial_to_date(serial),
+            DateSystem::Date1904 if serial.floor() < 0.0 => None,
+            DateSystem::Date1904 => serial_to_date(self.to_1900_serial(serial)),
+        }
+    }
+
+    /// Convert a serial number in this date system to the year, month, and
+    /// day that Excel shows for it. Unlike [`serial_to_date`](Self::serial_to_date),
+    /// this converts serial 60 of the 1900 date system to (1900, 2, 29).
+    pub fn serial_to_ymd(self, serial: f64) -> Option<(i32, u32, u32)> {
+        if self == DateSystem::Date1900 && serial.floor() == 60.0 {
+            return Some((1900, 2, 29));
+        }
+        let date = self.serial_to_date(serial)?;
+        Some((date.year(), date.month(), date.day()))
+    }
+
+    /// Convert a serial number in this date system to a `NaiveDateTime`.
+    pub fn serial_to_datetime(self, serial: f64) -> Option<NaiveDateTime> {
+        let date = self.serial_to_date(serial)?;
+        Some(NaiveDateTime::new(date, serial_time(serial)?))
+    }
+
+    /// Convert a serial number in this date system to the 1900 date system.
+    pub fn to_1900_serial(self, serial: f64) -> f64 {
+        match self {
+            DateSystem::Date1900 => serial,
+            DateSystem::Date1904 => serial + DATE1904_OFFSET,
+        }
+    }
+
+    /// Convert a serial number in the 1900 date system to this date system.
+    pub fn from_1900_serial(self, serial: f64) -> f64 {
+        match self {
+            DateSystem::Date1900 => serial,
+            DateSystem::Date1904 => serial - DATE1904_OFFSET,
+        }
+    }
 }
 
 /// Returns `true` if the given number format ID is a built-in date or time format.
@@ -364,9 +459,10 @@ mod tests {
 
     #[test]
     fn test_serial_to_date_60_phantom_leap_day() {
-        // Serial 60 is the phantom Feb 29 1900. We map it to Feb 28.
-        let date = serial_to_date(60.0).unwrap();
-        assert_eq!(date, NaiveDate::from_ymd_opt(1900, 2, 28).unwrap());
+        // Serial 60 is the phantom Feb 29 1900, which has no NaiveDate.
+        assert!(serial_to_date(60.0).is_none());
+        assert_eq!(CellValue::Date(60.0).to_string(), "1900-02-29");
+        assert_eq!(CellValue::Date(60.5).to_string(), "1900-02-29 12:00:00");
     }
 
     #[test]
@@ -381,6 +477,42 @@ mod tests {
         assert_eq!(date, NaiveDate::from_ymd_opt(2000, 1, 1).unwrap());
     }
 
+    #[test]
+    fn test_date_system_1904() {
+        let system = DateSystem::Date1904;
+        let jan_1_1904 = NaiveDate::from_ymd_opt(1904, 1, 1).unwrap();
+        assert_eq!(system.date_to_serial(jan_1_1904), 0.0);
+        assert_eq!(system.serial_to_date(0.0), Some(jan_1_1904));
+        assert!(system.serial_to_date(-1.0).is_none());
+
+        let date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        let serial = system.date_to_serial(date);
+        assert_eq!(serial, date_to_serial(date) - 1462.0);
+        assert_eq!(system.serial_to_date(serial), Some(date));
+        assert_eq!(system.to_1900_serial(serial), date_to_serial(date));
+        assert_eq!(system.from_1900_serial(date_to_serial(date)), serial);
+
+        let noon = date.and_hms_opt(12, 0, 0).unwrap();
+        assert_eq!(system.datetime_to_serial(noon), serial + 0.5);
+        assert_eq!(system.serial_to_datetime(serial + 0.5), Some(noon));
+    }
+
+    #[test]
+    fn test_date_system_1900_keeps_leap_year_bug() {
+        let system = DateSystem::default();
+        assert!(!system.is_date1904());
+        assert_eq!(system.serial_to_ymd(59.0), Some((1900, 2, 28)));
+        assert_eq!(system.serial_to_ymd(60.0), Some((1900, 2, 29)));
+        assert_eq!(system.serial_to_ymd(61.0), Some((1900, 3, 1)));
+        assert_eq!(system.serial_to_ymd(1.0), Some((1900, 1, 1)));
+        assert!(system.serial_to_date(60.0).is_none());
+        assert_eq!(DateSystem::Date1904.serial_to_ymd(60.0), Some((1904, 3, 1)));
+        let mar_1_1900 = NaiveDate::from_ymd_opt(1900, 3, 1).unwrap();
+        assert_eq!(system.date_to_serial(mar_1_1900), 61.0);
+        assert!(system.serial_to_date(0.0).is_none());
+        assert_eq!(DateSystem::from_date1904(true), DateSystem::Date1904);
+    }
+
     #[test]
     fn test_serial_to_date_invalid() {
         assert!(serial_to_date(0.0).is_none());
@@ -1,5 +1,12 @@
+use crate::cell_shift::CellRange;
 use crate::error::Result;
-use crate::utils::cell_ref::{column_name_to_number, column_number_to_name};
+use crate::formula::ast::{CellReference, Expr};
+use crate::formula::visit::{fold_children, Fold};
+use crate::utils::cell_ref::{
+    cell_name_to_coordinates, column_name_to_number, column_number_to_name,
+    coordinates_to_cell_name,
+};
+use crate::utils::constants::{MAX_COLUMNS, MAX_ROWS};
 
 #[derive(Debug, Clone, Copy)]
 struct ParsedCellRef {
@@ -62,7 +69,8 @@ fn parse_cell_ref_at(s: &str, start: usize) -> Option<ParsedCellRef> {
         return None;
     }
 
-    if i < len && !is_ref_boundary_byte(bytes[i]) {
+    // Function names such as LOG10 and ATAN2 look like cell references.
+    if i < len && (bytes[i] == b'(' || !is_ref_boundary_byte(bytes[i])) {
         return None;
     }
 
@@ -81,6 +89,23 @@ fn parse_cell_ref_at(s: &str, start: usize) -> Option<ParsedCellRef> {
     })
 }
 
+/// Return the byte index just past the string literal that opens at `start`.
+/// Doubled quotes (`""`) inside the literal are treated as escapes.
+fn string_literal_end(bytes: &[u8], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if bytes[i] == b'"' {
+            if bytes.get(i + 1) == Some(&b'"') {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    bytes.len()
+}
+
 fn format_shifted_ref(col: u32, row: u32, abs_col: bool, abs_row: bool) -> Result<String> {
     let col_name = column_number_to_name(col)?;
     Ok(format!(
@@ -110,7 +135,11 @@ where
     let bytes = text.as_bytes();
 
     while i < bytes.len() {
-        if let Some(parsed) = parse_cell_ref_at(text, i) {
+        if bytes[i] == b'"' {
+            let end = string_literal_end(bytes, i);
+            out.push_str(&text[i..end]);
+            i = end;
+        } else if let Some(parsed) = parse_cell_ref_at(text, i) {
             let (new_col, new_row) =
                 shift_cell(parsed.col, parsed.row, parsed.abs_col, parsed.abs_row);
             out.push_str(&format_shifted_ref(
@@ -142,7 +171,11 @@ where
     let bytes = text.as_bytes();
 
     while i < bytes.len() {
-        if let Some(parsed) = parse_cell_ref_at(text, i) {
+        if bytes[i] == b'"' {
+            let end = string_literal_end(bytes, i);
+            out.push_str(&text[i..end]);
+            i = end;
+        } else if let Some(parsed) = parse_cell_ref_at(text, i) {
             let (new_col, new_row) = shift_cell(parsed.col, parsed.row);
             out.push_str(&format_shifted_ref(
                 new_col,
@@ -160,6 +193,757 @@ where
     Ok(out)
 }
 
+/// Shift the relative references in `formula` by a column/row offset,
+/// leaving absolute components (`$A`, `$1`) untouched.
+///
+/// This is how a shared formula's master text is translated to one of its
+/// child cells, and how relative references follow a copied formula.
+/// References pushed before column A or row 1, or past the last column or
+/// row, become `#REF!`. Whole-column and whole-row references keep their
+/// full extent.
+pub(crate) fn shift_formula_by_offset(
+    formula: &str,
+    col_offset: i64,
+    row_offset: i64,
+) -> Result<String> {
+    if col_offset == 0 && row_offset == 0 {
+        return Ok(formula.to_string());
+    }
+    let Ok(expr) = crate::formula::parser::parse_formula(formula) else {
+        // Formulas the parser does not support keep the text-based shift,
+        // clamped to the sheet.
+        return shift_cell_references_with_abs(formula, |col, row, abs_col, abs_row| {
+            let shift = |i: u32, abs: bool, offset: i64, max: u32| {
+                if abs {
+                    i
+                } else {
+                    (i as i64 + offset).clamp(1, max as i64) as u32
+                }
+            };
+            (
+                shift(col, abs_col, col_offset, MAX_COLUMNS),
+                shift(row, abs_row, row_offset, MAX_ROWS),
+            )
+        });
+    };
+    let shifted = RefOffset {
+        cols: col_offset,
+        rows: row_offset,
+    }
+    .fold_expr(expr.clone());
+    if shifted == expr {
+        Ok(formula.to_string())
+    } else {
+        Ok(shifted.to_string())
+    }
+}
+
+/// An insertion or deletion of whole rows or columns on one sheet, used to
+/// move the references that point into the sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RefShift {
+    /// `true` for rows, `false` for columns.
+    rows: bool,
+    /// First inserted or deleted row or column (1-based).
+    at: u32,
+    /// Number of rows or columns inserted or deleted.
+    count: u32,
+    /// `true` for a deletion.
+    delete: bool,
+    /// Columns (for a row shift) or rows (for a column shift) the shift is
+    /// limited to, for cells inserted or deleted within a range. `None`
+    /// for whole rows or columns.
+    span: Option<(u32, u32)>,
+}
+
+impl RefShift {
+    /// `count` rows inserted before row `at`.
+    pub(crate) fn insert_rows(at: u32, count: u32) -> Self {
+        Self {
+            rows: true,
+            at,
+            count,
+            delete: false,
+            span: None,
+        }
+    }
+
+    /// Rows `at..at + count` deleted.
+    pub(crate) fn delete_rows(at: u32, count: u32) -> Self {
+        Self {
+            rows: true,
+            at,
+            count,
+            delete: true,
+            span: None,
+        }
+    }
+
+    /// `count` columns inserted before column `at`.
+    pub(crate) fn insert_cols(at: u32, count: u32) -> Self {
+        Self {
+            rows: false,
+            at,
+            count,
+            delete: false,
+            span: None,
+        }
+    }
+
+    /// Columns `at..at + count` deleted.
+    pub(crate) fn delete_cols(at: u32, count: u32) -> Self {
+        Self {
+            rows: false,
+            at,
+            count,
+            delete: true,
+            span: None,
+        }
+    }
+
+    /// Limit the shift to the columns (for a row shift) or rows (for a
+    /// column shift) `first..=last`, as when cells are inserted or deleted
+    /// within a range and the neighbours shift down, up, right, or left.
+    pub(crate) fn within(self, first: u32, last: u32) -> Self {
+        Self {
+            span: Some((first, last)),
+            ..self
+        }
+    }
+
+    /// Whether the cells from `first` to `last` on the other axis are all
+    /// inside the span. References that straddle its edge do not move.
+    fn covers(&self, first: u32, last: u32) -> bool {
+        self.span.is_none_or(|(lo, hi)| first >= lo && last <= hi)
+    }
+
+    fn max(&self) -> u32 {
+        if self.rows {
+            MAX_ROWS
+        } else {
+            MAX_COLUMNS
+        }
+    }
+
+    /// Move a row or column index, or return `None` if it was deleted or
+    /// pushed off the sheet.
+    fn index(&self, i: u32) -> Option<u32> {
+        if i < self.at {
+            Some(i)
+        } else if !self.delete {
+            Some(i + self.count).filter(|&i| i <= self.max())
+        } else if i < self.at + self.count {
+            None
+        } else {
+            Some(i - self.count)
+        }
+    }
+
+    /// Move the cell at `(col, row)`, or return `None` if it was deleted.
+    pub(crate) fn cell(&self, col: u32, row: u32) -> Option<(u32, u32)> {
+        let other = if self.rows { col } else { row };
+        if !self.covers(other, other) {
+            return Some((col, row));
+        }
+        if self.rows {
+            self.index(row).map(|row| (col, row))
+        } else {
+            self.index(col).map(|col| (col, row))
+        }
+    }
+
+    /// Move the cell at `(col, row)`, or to the first row or column after
+    /// the deleted ones if it was deleted. Used for positions that must
+    /// stay on the sheet, such as drawing anchors and the selection.
+    pub(crate) fn clamp_cell(&self, col: u32, row: u32) -> (u32, u32) {
+        self.cell(col, row).unwrap_or(if self.rows {
+            (col, self.at.min(MAX_ROWS))
+        } else {
+            (self.at.min(MAX_COLUMNS), row)
+        })
+    }
+
+    /// Move the area from `start` to `end`, `(col, row)` pairs. Rows or
+    /// columns inserted inside the area widen it and deleted ones narrow
+    /// it. Returns `None` if the whole area was deleted. Whole columns are
+    /// unaffected by row shifts and whole rows by column shifts, and areas
+    /// that reach outside a limited shift's span are left as they are.
+    pub(crate) fn area(
+        &self,
+        start: (u32, u32),
+        end: (u32, u32),
+    ) -> Option<((u32, u32), (u32, u32))> {
+        let (c1, c2) = (start.0.min(end.0), start.0.max(end.0));
+        let (r1, r2) = (start.1.min(end.1), start.1.max(end.1));
+        let (first, last, other) = if self.rows {
+            (r1, r2, (c1, c2))
+        } else {
+            (c1, c2, (r1, r2))
+        };
+        if (first == 1 && last == self.max()) || !self.covers(other.0, other.1) {
+            return Some(((c1, r1), (c2, r2)));
+        }
+        let (first, last) = if self.delete {
+            let deleted_end = self.at + self.count;
+            let first = if first < self.at {
+                first
+            } else if first < deleted_end {
+                self.at
+            } else {
+                first - self.count
+            };
+            let last = if last < self.at {
+                last
+            } else if last < deleted_end {
+                self.at.checked_sub(1)?
+            } else {
+                last - self.count
+            };
+            (first, last)
+        } else {
+            let first = self.index(first)?;
+            let last = self.index(last).unwrap_or(self.max());
+            (first, last)
+        };
+        if first > last {
+            return None;
+        }
+        Some(if self.rows {
+            ((c1, first), (c2, last))
+        } else {
+            ((first, r1), (last, r2))
+        })
+    }
+}
+
+/// Move the references to `sheet` in `formula` for a row or column shift
+/// on that sheet. References without a sheet name belong to `host`, the
+/// sheet holding the formula (`None` for defined names and charts).
+/// References into deleted cells become `#REF!`. Formulas with no
+/// affected references are returned unchanged.
+pub(crate) fn shift_formula_references(
+    formula: &str,
+    host: Option<&str>,
+    sheet: &str,
+    shift: RefShift,
+) -> Result<String> {
+    let Ok(expr) = crate::formula::parser::parse_formula(formula) else {
+        // Formulas the parser does not support keep the text-based shift
+        // of their unqualified references.
+        if host.is_some_and(|host| same_sheet(host, sheet)) {
+            return shift_cell_references_in_text(formula, |col, row| shift.clamp_cell(col, row));
+        }
+        return Ok(formula.to_string());
+    };
+    let shifted = RefShifter { host, sheet, shift }.fold_expr(expr.clone());
+    if shifted == expr {
+        Ok(formula.to_string())
+    } else {
+        Ok(shifted.to_string())
+    }
+}
+
+/// Move the areas of a space-separated reference list such as a `sqref`
+/// (`A1:B2 D4`) on the shifted sheet. Deleted areas are dropped; returns
+/// `None` if none remain.
+pub(crate) fn shift_sqref(sqref: &str, shift: RefShift) -> Option<String> {
+    let areas: Vec<String> = sqref
+        .split_whitespace()
+        .filter_map(|area| shift_area_text(area, shift))
+        .collect();
+    (!areas.is_empty()).then(|| areas.join(" "))
+}
+
+/// Move one `A1` or `A1:B2` area, keeping `$` markers. Returns `None` if it
+/// was deleted; text that is not an area is returned unchanged.
+fn shift_area_text(area: &str, shift: RefShift) -> Option<String> {
+    let (first, last) = area.split_once(':').unwrap_or((area, area));
+    let parse = |cell: &str| cell_name_to_coordinates(&cell.replace('$', "")).ok();
+    let (Some(start), Some(end)) = (parse(first), parse(last)) else {
+        return Some(area.to_string());
+    };
+    let (start, end) = shift.area(start, end)?;
+    let format = |text: &str, (col, row): (u32, u32)| -> Option<String> {
+        let abs_col = text.starts_with('$');
+        let abs_row = text[usize::from(abs_col)..].contains('$');
+        format_shifted_ref(col, row, abs_col, abs_row).ok()
+    };
+    if first == last && start == end {
+        format(first, start)
+    } else {
+        Some(format!("{}:{}", format(first, start)?, format(last, end)?))
+    }
+}
+
+/// Move a single `A1` cell reference, or return `None` if it was deleted.
+/// Text that is not a cell reference is returned unchanged.
+pub(crate) fn shift_cell_name(cell: &str, shift: RefShift) -> Option<String> {
+    let Ok((col, row)) = cell_name_to_coordinates(cell) else {
+        return Some(cell.to_string());
+    };
+    let (col, row) = shift.cell(col, row)?;
+    coordinates_to_cell_name(col, row).ok()
+}
+
+/// A block of cells cut from the area `from` on one sheet and pasted over
+/// the same-sized area `to`, possibly on another sheet.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RefMove<'a> {
+    pub(crate) from_sheet: &'a str,
+    pub(crate) from: CellRange,
+    pub(crate) to_sheet: &'a str,
+    pub(crate) to: CellRange,
+}
+
+impl RefMove<'_> {
+    fn moved(&self, col: u32, row: u32) -> (u32, u32) {
+        (
+            col - self.from.min_col + self.to.min_col,
+            row - self.from.min_row + self.to.min_row,
+        )
+    }
+}
+
+/// Redirect the references in `formula` for a cut and paste: references
+/// into the moved block follow it, and references to the cells it was
+/// pasted over become `#REF!`. References that only partly cover either
+/// area are unchanged. `host` is as for [`shift_formula_references`].
+pub(crate) fn move_formula_references(
+    formula: &str,
+    host: Option<&str>,
+    mv: RefMove<'_>,
+) -> Result<String> {
+    let Ok(expr) = crate::formula::parser::parse_formula(formula) else {
+        return Ok(formula.to_string());
+    };
+    let moved = RefMover { host, mv }.fold_expr(expr.clone());
+    if moved == expr {
+        Ok(formula.to_string())
+    } else {
+        Ok(moved.to_string())
+    }
+}
+
+/// Add the sheet name `sheet` to the references in `formula` that have
+/// none, so that they keep pointing at `sheet` when the formula moves to
+/// another sheet.
+pub(crate) fn qualify_references(formula: &str, sheet: &str) -> Result<String> {
+    let Ok(expr) = crate::formula::parser::parse_formula(formula) else {
+        return Ok(formula.to_string());
+    };
+    let qualified = expr.clone().rewrite(|e| match e {
+        Expr::CellRef(cell) if cell.sheet.is_none() => Expr::CellRef(CellReference {
+            sheet: Some(sheet.to_string()),
+            ..cell
+        }),
+        Expr::SpillRef(cell) if cell.sheet.is_none() => Expr::SpillRef(CellReference {
+            sheet: Some(sheet.to_string()),
+            ..cell
+        }),
+        Expr::Range { start, end } if start.sheet.is_none() => Expr::Range {
+            start: CellReference {
+                sheet: Some(sheet.to_string()),
+                ..start
+            },
+            end,
+        },
+        other => other,
+    });
+    if qualified == expr {
+        Ok(formula.to_string())
+    } else {
+        Ok(qualified.to_string())
+    }
+}
+
+/// Rename the sheet `from` to `to`, and each table in `tables` from its
+/// first to its second name, in the references of `formula`.
+pub(crate) fn rename_references(
+    formula: &str,
+    from: &str,
+    to: &str,
+    tables: &[(String, String)],
+) -> Result<String> {
+    let Ok(expr) = crate::formula::parser::parse_formula(formula) else {
+        return Ok(formula.to_string());
+    };
+    let rename_sheet = |sheet: Option<String>| match sheet {
+        Some(name) if same_sheet(&name, from) => Some(to.to_string()),
+        other => other,
+    };
+    let renamed = expr.clone().rewrite(|e| match e {
+        Expr::CellRef(cell) => Expr::CellRef(CellReference {
+            sheet: rename_sheet(cell.sheet.clone()),
+            ..cell
+        }),
+        Expr::SpillRef(cell) => Expr::SpillRef(CellReference {
+            sheet: rename_sheet(cell.sheet.clone()),
+            ..cell
+        }),
+        Expr::Range { start, end } => Expr::Range {
+            start: CellReference {
+                sheet: rename_sheet(start.sheet.clone()),
+                ..start
+            },
+            end: CellReference {
+                sheet: rename_sheet(end.sheet.clone()),
+                ..end
+            },
+        },
+        Expr::Ref3D {
+            first_sheet,
+            last_sheet,
+            start,
+            end,
+        } => Expr::Ref3D {
+            first_sheet: rename_sheet(Some(first_sheet)).unwrap_or_default(),
+            last_sheet: rename_sheet(Some(last_sheet)).unwrap_or_default(),
+            start,
+            end,
+        },
+        Expr::Name { name, sheet } => Expr::Name {
+            name,
+            sheet: rename_sheet(sheet),
+        },
+        Expr::StructuredRef(mut reference) => {
+            if let Some(table) = reference.table.as_mut() {
+                if let Some((_, new)) = tables
+                    .iter()
+                    .find(|(old, _)| old.eq_ignore_ascii_case(table))
+                {
+                    *table = new.clone();
+                }
+            }
+            Expr::StructuredRef(reference)
+        }
+        other => other,
+    });
+    if renamed == expr {
+        Ok(formula.to_string())
+    } else {
+        Ok(renamed.to_string())
+    }
+}
+
+/// Replace the references in `formula` that name a sheet other than
+/// `sheet` with `#REF!`, as when the formula moves to a workbook that does
+/// not have those sheets.
+pub(crate) fn detach_references(formula: &str, sheet: &str) -> Result<String> {
+    let Ok(expr) = crate::formula::parser::parse_formula(formula) else {
+        return Ok(formula.to_string());
+    };
+    let other = |name: Option<&str>| name.is_some_and(|name| !same_sheet(name, sheet));
+    let detached = expr.clone().rewrite(|e| match e {
+        Expr::CellRef(ref cell) | Expr::SpillRef(ref cell) if other(cell.sheet.as_deref()) => {
+            ref_error()
+        }
+        Expr::Range { ref start, .. } if other(start.sheet.as_deref()) => ref_error(),
+        Expr::Ref3D {
+            ref first_sheet,
+            ref last_sheet,
+            ..
+        } if other(Some(first_sheet)) || other(Some(last_sheet)) => ref_error(),
+        Expr::Name { ref sheet, .. } if other(sheet.as_deref()) => ref_error(),
+        other => other,
+    });
+    if detached == expr {
+        Ok(formula.to_string())
+    } else {
+        Ok(detached.to_string())
+    }
+}
+
+/// The workbook-level defined names that `formula` refers to.
+pub(crate) fn referenced_names(formula: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    if let Ok(expr) = crate::formula::parser::parse_formula(formula) {
+        expr.walk(|e| {
+            if let Expr::Name { name, sheet: None } = e {
+                names.push(name.clone());
+            }
+        });
+    }
+    names
+}
+
+/// Return `true` if `formula` may refer to cells of `sheet`: it names the
+/// sheet or spans sheets with a 3D reference. A formula that does not parse
+/// counts as referring to it.
+pub(crate) fn refers_to_sheet(formula: &str, sheet: &str) -> bool {
+    let Ok(expr) = crate::formula::parser::parse_formula(formula) else {
+        return true;
+    };
+    let names_sheet =
+        |cell: &CellReference| cell.sheet.as_deref().is_some_and(|s| same_sheet(s, sheet));
+    let mut found = false;
+    expr.walk(|e| {
+        found |= match e {
+            Expr::CellRef(cell) | Expr::SpillRef(cell) => names_sheet(cell),
+            Expr::Range { start, .. } => names_sheet(start),
+            Expr::Ref3D { .. } => true,
+            _ => false,
+        };
+    });
+    found
+}
+
+/// Compare sheet names the way Excel does, ignoring case.
+fn same_sheet(a: &str, b: &str) -> bool {
+    a == b || a.to_lowercase() == b.to_lowercase()
+}
+
+/// Applies a [`RefShift`] to the references of a formula AST.
+struct RefShifter<'a> {
+    host: Option<&'a str>,
+    sheet: &'a str,
+    shift: RefShift,
+}
+
+impl RefShifter<'_> {
+    /// Return `true` if a reference with the given sheet name points into
+    /// the shifted sheet.
+    fn targets(&self, sheet: Option<&str>) -> bool {
+        sheet
+            .or(self.host)
+            .is_some_and(|name| same_sheet(name, self.sheet))
+    }
+}
+
+fn ref_error() -> Expr {
+    Expr::Error("#REF!".to_string())
+}
+
+fn with_position(cell: CellReference, (col, row): (u32, u32)) -> Option<CellReference> {
+    Some(CellReference {
+        col: column_number_to_name(col).ok()?,
+        row,
+        ..cell
+    })
+}
+
+/// Fold a spill reference such as `E1#` the way `folder` folds its anchor
+/// cell.
+fn fold_spill_ref<F: Fold>(folder: &mut F, anchor: CellReference) -> Expr {
+    match folder.fold_expr(Expr::CellRef(anchor)) {
+        Expr::CellRef(anchor) => Expr::SpillRef(anchor),
+        other => other,
+    }
+}
+
+impl Fold for RefShifter<'_> {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        match expr {
+            Expr::SpillRef(cell) => fold_spill_ref(self, cell),
+            Expr::CellRef(cell) if self.targets(cell.sheet.as_deref()) => {
+                let Ok(col) = column_name_to_number(&cell.col) else {
+                    return Expr::CellRef(cell);
+                };
+                self.shift
+                    .cell(col, cell.row)
+                    .and_then(|position| with_position(cell, position))
+                    .map_or_else(ref_error, Expr::CellRef)
+            }
+            Expr::Range { start, end } if self.targets(start.sheet.as_deref()) => {
+                let (Ok(c1), Ok(c2)) = (
+                    column_name_to_number(&start.col),
+                    column_name_to_number(&end.col),
+                ) else {
+                    return Expr::Range { start, end };
+                };
+                let Some((first, last)) = self.shift.area((c1, start.row), (c2, end.row)) else {
+                    return ref_error();
+                };
+                match (with_position(start, first), with_position(end, last)) {
+                    (Some(start), Some(end)) => Expr::Range { start, end },
+                    _ => ref_error(),
+                }
+            }
+            other => fold_children(self, other),
+        }
+    }
+}
+
+/// Moves the relative references of a formula AST by a fixed offset, as
+/// when a formula is filled or copied to another cell.
+struct RefOffset {
+    cols: i64,
+    rows: i64,
+}
+
+impl RefOffset {
+    /// Move one reference, or return `None` if it leaves the sheet.
+    /// `whole_cols` and `whole_rows` mark references that span every row
+    /// (`A:A`) or every column (`1:1`), which keep that extent.
+    fn cell(
+        &self,
+        cell: CellReference,
+        whole_cols: bool,
+        whole_rows: bool,
+    ) -> Option<CellReference> {
+        let offset = |i: u32, abs: bool, by: i64, max: u32| -> Option<u32> {
+            if abs || by == 0 {
+                return Some(i);
+            }
+            let moved = i as i64 + by;
+            (1..=max as i64).contains(&moved).then_some(moved as u32)
+        };
+        let col = column_name_to_number(&cell.col).ok()?;
+        let col = if whole_rows {
+            col
+        } else {
+            offset(col, cell.abs_col, self.cols, MAX_COLUMNS)?
+        };
+        let row = if whole_cols {
+            cell.row
+        } else {
+            offset(cell.row, cell.abs_row, self.rows, MAX_ROWS)?
+        };
+        with_position(cell, (col, row))
+    }
+
+    fn area(
+        &self,
+        start: CellReference,
+        end: CellReference,
+    ) -> Option<(CellReference, CellReference)> {
+        let whole_cols = start.row == 1 && end.row == MAX_ROWS;
+        let whole_rows = matches!(
+            (
+                column_name_to_number(&start.col),
+                column_name_to_number(&end.col)
+            ),
+            (Ok(1), Ok(MAX_COLUMNS))
+        );
+        Some((
+            self.cell(start, whole_cols, whole_rows)?,
+            self.cell(end, whole_cols, whole_rows)?,
+        ))
+    }
+}
+
+impl Fold for RefOffset {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        match expr {
+            Expr::SpillRef(cell) => fold_spill_ref(self, cell),
+            Expr::CellRef(cell) => self
+                .cell(cell, false, false)
+                .map_or_else(ref_error, Expr::CellRef),
+            Expr::Range { start, end } => self
+                .area(start, end)
+                .map_or_else(ref_error, |(start, end)| Expr::Range { start, end }),
+            Expr::Ref3D {
+                first_sheet,
+                last_sheet,
+                start,
+                end,
+            } => self
+                .area(start, end)
+                .map_or_else(ref_error, |(start, end)| Expr::Ref3D {
+                    first_sheet,
+                    last_sheet,
+                    start,
+                    end,
+                }),
+            other => fold_children(self, other),
+        }
+    }
+}
+
+/// Applies a [`RefMove`] to the references of a formula AST.
+struct RefMover<'a> {
+    host: Option<&'a str>,
+    mv: RefMove<'a>,
+}
+
+/// Where a reference lies relative to a cut and paste.
+enum MoveTarget {
+    /// Inside the moved block.
+    Moved,
+    /// Inside the cells the block was pasted over.
+    Overwritten,
+    Other,
+}
+
+impl RefMover<'_> {
+    fn classify(&self, sheet: Option<&str>, area: CellRange) -> MoveTarget {
+        let Some(target) = sheet.or(self.host) else {
+            return MoveTarget::Other;
+        };
+        let inside = |sheet: &str, block: &CellRange| {
+            same_sheet(target, sheet)
+                && block.contains(area.min_col, area.min_row)
+                && block.contains(area.max_col, area.max_row)
+        };
+        if inside(self.mv.from_sheet, &self.mv.from) {
+            MoveTarget::Moved
+        } else if inside(self.mv.to_sheet, &self.mv.to) {
+            MoveTarget::Overwritten
+        } else {
+            MoveTarget::Other
+        }
+    }
+
+    /// The sheet name of a reference that followed the block.
+    fn moved_sheet(&self, sheet: Option<String>) -> Option<String> {
+        if same_sheet(self.mv.from_sheet, self.mv.to_sheet) {
+            sheet
+        } else {
+            Some(self.mv.to_sheet.to_string())
+        }
+    }
+}
+
+impl Fold for RefMover<'_> {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        match expr {
+            Expr::SpillRef(cell) => fold_spill_ref(self, cell),
+            Expr::CellRef(cell) => {
+                let Ok(col) = column_name_to_number(&cell.col) else {
+                    return Expr::CellRef(cell);
+                };
+                let area = CellRange::from_corners((col, cell.row), (col, cell.row));
+                match self.classify(cell.sheet.as_deref(), area) {
+                    MoveTarget::Moved => {
+                        let sheet = self.moved_sheet(cell.sheet.clone());
+                        with_position(cell, self.mv.moved(col, area.min_row))
+                            .map_or_else(ref_error, |cell| {
+                                Expr::CellRef(CellReference { sheet, ..cell })
+                            })
+                    }
+                    MoveTarget::Overwritten => ref_error(),
+                    MoveTarget::Other => Expr::CellRef(cell),
+                }
+            }
+            Expr::Range { start, end } => {
+                let (Ok(c1), Ok(c2)) = (
+                    column_name_to_number(&start.col),
+                    column_name_to_number(&end.col),
+                ) else {
+                    return Expr::Range { start, end };
+                };
+                let area = CellRange::from_corners((c1, start.row), (c2, end.row));
+                match self.classify(start.sheet.as_deref(), area) {
+                    MoveTarget::Moved => {
+                        let sheet = self.moved_sheet(start.sheet.clone());
+                        let first = self.mv.moved(area.min_col, area.min_row);
+                        let last = self.mv.moved(area.max_col, area.max_row);
+                        match (with_position(start, first), with_position(end, last)) {
+                            (Some(start), Some(end)) => Expr::Range {
+                                start: CellReference { sheet, ..start },
+                                end,
+                            },
+                            _ => ref_error(),
+                        }
+                    }
+                    MoveTarget::Overwritten => ref_error(),
+                    MoveTarget::Other => Expr::Range { start, end },
+                }
+            }
+            other => fold_children(self, other),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -184,4 +968,199 @@ mod tests {
                 .unwrap();
         assert_eq!(shifted, "Sheet1!B2+Sheet2!C3");
     }
+
+    #[test]
+    fn test_shift_cell_refs_skips_string_literals() {
+        let shifted =
+            shift_cell_references_in_text("IF(A1=\"Q1\",B1,\"\"\"C1\"\"\")", |col, row| {
+                (col, row + 1)
+            })
+            .unwrap();
+        assert_eq!(shifted, "IF(A2=\"Q1\",B2,\"\"\"C1\"\"\")");
+    }
+
+    #[test]
+    fn test_shift_formula_by_offset_respects_absolute() {
+        let shifted = shift_formula_by_offset("A1+$B1+C$1+$D$1", 2, 3).unwrap();
+        assert_eq!(shifted, "C4+$B4+E$1+$D$1");
+    }
+
+    #[test]
+    fn test_shift_formula_by_offset_keeps_function_names() {
+        assert_eq!(
+            shift_formula_by_offset("LOG10(A1)+ATAN2(B1,C1)", 0, 2).unwrap(),
+            "LOG10(A3)+ATAN2(B3,C3)"
+        );
+        assert_eq!(
+            shift_formula_by_offset("SUM(A:A,2:2,Sheet2!B1:C2)", 1, 1).unwrap(),
+            "SUM(B:B,3:3,Sheet2!C2:D3)"
+        );
+    }
+
+    #[test]
+    fn test_shift_formula_by_offset_off_sheet_is_ref_error() {
+        assert_eq!(shift_formula_by_offset("A5+B1", 0, -4).unwrap(), "A1+#REF!");
+        assert_eq!(
+            shift_formula_by_offset("SUM(A2:B3)", -1, 0).unwrap(),
+            "SUM(#REF!)"
+        );
+        assert_eq!(
+            shift_formula_by_offset("$A$1+A1", -1, 0).unwrap(),
+            "$A$1+#REF!"
+        );
+    }
+
+    #[test]
+    fn test_shift_formula_references_only_moves_target_sheet() {
+        let shift = RefShift::delete_rows(3, 1);
+        let shifted =
+            shift_formula_references("A4+Data!A4+Other!A4", Some("Data"), "Data", shift).unwrap();
+        assert_eq!(shifted, "A3+Data!A3+Other!A4");
+        let shifted =
+            shift_formula_references("A4+Data!A4+Other!A4", Some("Other"), "Data", shift).unwrap();
+        assert_eq!(shifted, "A4+Data!A3+Other!A4");
+    }
+
+    #[test]
+    fn test_shift_formula_references_deleted_cell_becomes_ref_error() {
+        let shift = RefShift::delete_cols(2, 2);
+        let shifted =
+            shift_formula_references("SUM(A1:D1)+C1+$E$1", Some("Sheet1"), "Sheet1", shift)
+                .unwrap();
+        assert_eq!(shifted, "SUM(A1:B1)+#REF!+$C$1");
+        let shifted =
+            shift_formula_references("SUM(B1:C1)", Some("Sheet1"), "Sheet1", shift).unwrap();
+        assert_eq!(shifted, "SUM(#REF!)");
+    }
+
+    #[test]
+    fn test_shift_formula_references_keeps_unchanged_text() {
+        let shift = RefShift::insert_rows(10, 1);
+        let shifted =
+            shift_formula_references("sum( A1 , 2 )", Some("Sheet1"), "Sheet1", shift).unwrap();
+        assert_eq!(shifted, "sum( A1 , 2 )");
+    }
+
+    #[test]
+    fn test_shift_sqref() {
+        let shift = RefShift::delete_rows(2, 2);
+        assert_eq!(
+            shift_sqref("A1:B5 C2:C3 $D$6", shift).as_deref(),
+            Some("A1:B3 $D$4")
+        );
+        assert_eq!(shift_sqref("A2:A3", shift), None);
+        assert_eq!(
+            shift_sqref("B1:B1048576", shift).as_deref(),
+            Some("B1:B1048576")
+        );
+        let shift = RefShift::insert_cols(1, 1);
+        assert_eq!(
+            shift_sqref("A1:A1048576", shift).as_deref(),
+            Some("B1:B1048576")
+        );
+    }
+
+    #[test]
+    fn test_shift_within_span_leaves_other_columns() {
+        // Cells B3:B4 deleted, cells below shift up.
+        let shift = RefShift::delete_rows(3, 2).within(2, 2);
+        let shifted = shift_formula_references(
+            "B5+C5+SUM(B1:B10)+SUM(A1:B10)+B3",
+            Some("Sheet1"),
+            "Sheet1",
+            shift,
+        )
+        .unwrap();
+        assert_eq!(shifted, "B3+C5+SUM(B1:B8)+SUM(A1:B10)+#REF!");
+    }
+
+    #[test]
+    fn test_move_formula_references() {
+        let mv = RefMove {
+            from_sheet: "Sheet1",
+            from: CellRange::parse("A1:A3").unwrap(),
+            to_sheet: "Sheet1",
+            to: CellRange::parse("C2:C4").unwrap(),
+        };
+        let moved = move_formula_references(
+            "SUM(A1:A3)+$A$2+A4+C4+SUM(A1:A4)+Other!A1",
+            Some("Sheet1"),
+            mv,
+        )
+        .unwrap();
+        assert_eq!(moved, "SUM(C2:C4)+$C$3+A4+#REF!+SUM(A1:A4)+Other!A1");
+    }
+
+    #[test]
+    fn test_move_formula_references_to_other_sheet() {
+        let mv = RefMove {
+            from_sheet: "Data",
+            from: CellRange::parse("B2:C3").unwrap(),
+            to_sheet: "Archive",
+            to: CellRange::parse("A1:B2").unwrap(),
+        };
+        let moved = move_formula_references("B2*Data!C3+Archive!B2", Some("Data"), mv).unwrap();
+        assert_eq!(moved, "Archive!A1*Archive!B2+#REF!");
+        let moved = move_formula_references("Data!B2:C3", None, mv).unwrap();
+        assert_eq!(moved, "Archive!A1:B2");
+    }
+
+    #[test]
+    fn test_qualify_references() {
+        let qualified = qualify_references("SUM(A1:B2)+Other!C3+Name+D1#", "My Sheet").unwrap();
+        assert_eq!(
+            qualified,
+            "SUM('My Sheet'!A1:B2)+Other!C3+Name+'My Sheet'!D1#"
+        );
+    }
+
+    #[test]
+    fn test_rename_references() {
+        let tables = vec![("Sales".to_string(), "Sales_2".to_string())];
+        let renamed = rename_references(
+            "SUM(template!A1:B2)+Other!C3+SUM(Sales[Amount])+A1+Template!D1#",
+            "Template",
+            "Q1 Report",
+            &tables,
+        )
+        .unwrap();
+        assert_eq!(
+            renamed,
+            "SUM('Q1 Report'!A1:B2)+Other!C3+SUM(Sales_2[Amount])+A1+'Q1 Report'!D1#"
+        );
+        assert_eq!(
+            rename_references("A1+1", "Template", "Report", &[]).unwrap(),
+            "A1+1"
+        );
+    }
+
+    #[test]
+    fn test_detach_references() {
+        let detached = detach_references(
+            "SUM(Other!A1:B2)+template!C3+A1+Other!D4#+SUM(Jan:Mar!A1)+Other!Rate+Total",
+            "Template",
+        )
+        .unwrap();
+        assert_eq!(
+            detached,
+            "SUM(#REF!)+template!C3+A1+#REF!+SUM(#REF!)+#REF!+Total"
+        );
+        assert_eq!(detach_references("A1+1", "Template").unwrap(), "A1+1");
+    }
+
+    #[test]
+    fn test_refers_to_sheet() {
+        assert!(refers_to_sheet("A1+data!B2", "Data"));
+        assert!(refers_to_sheet("SUM('Data'!A1:A3)", "Data"));
+        assert!(refers_to_sheet("SUM(Jan:Mar!A1)", "Data"));
+        assert!(!refers_to_sheet("A1+Other!B2+Data", "Data"));
+    }
+
+    #[test]
+    fn test_referenced_names() {
+        assert_eq!(
+            referenced_names("TaxRate*Sheet1!Local+SUM(Rates)"),
+            vec!["TaxRate", "Rates"]
+        );
+    }
 }
@@ -0,0 +1,422 @@
+//! Inserting and deleting cells within a range.
+//!
+//! These are the worksheet-level halves of Excel's "Insert cells" and
+//! "Delete cells" commands: the cells next to the range shift down, right,
+//! up, or left, while the rest of the sheet stays in place. References to
+//! the moved cells are updated by the
+//! [`Workbook`](crate::workbook::Workbook) wrapper.
+
+use sheetkit_xml::worksheet::{Cell, Row, WorksheetXml};
+
+use crate::error::{Error, Result};
+use crate::row::find_or_create_row;
+use crate::utils::cell_ref::{cell_name_to_coordinates, coordinates_to_cell_name};
+use crate::utils::constants::{MAX_COLUMNS, MAX_ROWS};
+
+/// Which way existing cells move when cells are inserted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertShift {
+    /// Cells in the rows of the range, at and to the right of it, move right.
+    Right,
+    /// Cells in the columns of the range, at and below it, move down.
+    Down,
+}
+
+/// Which way existing cells move when cells are deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeleteShift {
+    /// Cells in the rows of the range, to the right of it, move left.
+    Left,
+    /// Cells in the columns of the range, below it, move up.
+    Up,
+}
+
+/// A normalized rectangular range, 1-based and inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CellRange {
+    pub(crate) min_col: u32,
+    pub(crate) min_row: u32,
+    pub(crate) max_col: u32,
+    pub(crate) max_row: u32,
+}
+
+impl CellRange {
+    /// Parse an A1-style reference such as `"B2:C4"` or `"B2"`.
+    pub(crate) fn parse(range: &str) -> Result<Self> {
+        let (first, last) = range.split_once(':').unwrap_or((range, range));
+        let (c1, r1) = cell_name_to_coordinates(first.trim())?;
+        let (c2, r2) = cell_name_to_coordinates(last.trim())?;
+        Ok(Self {
+            min_col: c1.min(c2),
+            min_row: r1.min(r2),
+            max_col: c1.max(c2),
+            max_row: r1.max(r2),
+        })
+    }
+
+    pub(crate) fn width(&self) -> u32 {
+        self.max_col - self.min_col + 1
+    }
+
+    pub(crate) fn height(&self) -> u32 {
+        self.max_row - self.min_row + 1
+    }
+
+    /// The range with corners `(c1, r1)` and `(c2, r2)`, in either order.
+    pub(crate) fn from_corners((c1, r1): (u32, u32), (c2, r2): (u32, u32)) -> Self {
+        Self {
+            min_col: c1.min(c2),
+            min_row: r1.min(r2),
+            max_col: c1.max(c2),
+            max_row: r1.max(r2),
+        }
+    }
+
+    pub(crate) fn contains(&self, col: u32, row: u32) -> bool {
+        (self.min_col..=self.max_col).contains(&col) && (self.min_row..=self.max_row).contains(&row)
+    }
+
+    /// The cells this range shares with `other`, if any.
+    pub(crate) fn intersect(&self, other: &CellRange) -> Option<CellRange> {
+        let area = CellRange {
+            min_col: self.min_col.max(other.min_col),
+            min_row: self.min_row.max(other.min_row),
+            max_col: self.max_col.min(other.max_col),
+            max_row: self.max_row.min(other.max_row),
+        };
+        (area.min_col <= area.max_col && area.min_row <= area.max_row).then_some(area)
+    }
+
+    /// The parts of this range outside `other`, as up to four ranges: the
+    /// rows above and below it, then the columns left and right of it.
+    pub(crate) fn subtract(&self, other: &CellRange) -> Vec<CellRange> {
+        let Some(cut) = self.intersect(other) else {
+            return vec![*self];
+        };
+        let mut parts = Vec::new();
+        if self.min_row < cut.min_row {
+            parts.push(CellRange {
+                max_row: cut.min_row - 1,
+                ..*self
+            });
+        }
+        if cut.max_row < self.max_row {
+            parts.push(CellRange {
+                min_row: cut.max_row + 1,
+                ..*self
+            });
+        }
+        if self.min_col < cut.min_col {
+            parts.push(CellRange {
+                min_col: self.min_col,
+                max_col: cut.min_col - 1,
+                ..cut
+            });
+        }
+        if cut.max_col < self.max_col {
+            parts.push(CellRange {
+                min_col: cut.max_col + 1,
+                max_col: self.max_col,
+                ..cut
+            });
+        }
+        parts
+    }
+
+    /// Format as `"B2:C4"`, or `"B2"` for a single cell.
+    pub(crate) fn to_reference(self) -> Result<String> {
+        let first = coordinates_to_cell_name(self.min_col, self.min_row)?;
+        if self.min_col == self.max_col && self.min_row == self.max_row {
+            return Ok(first);
+        }
+        let last = coordinates_to_cell_name(self.max_col, self.max_row)?;
+        Ok(format!("{first}:{last}"))
+    }
+}
+
+/// Insert empty cells over `range`, shifting the cells in their way right or
+/// down by the width or height of the range.
+pub fn insert_cells(ws: &mut WorksheetXml, range: &str, shift: InsertShift) -> Result<()> {
+    let area = CellRange::parse(range)?;
+    match shift {
+        InsertShift::Down => {
+            let count = area.height();
+            let furthest = max_row_in_columns(ws, area.min_col, area.max_col);
+            if furthest >= area.min_row && furthest + count > MAX_ROWS {
+                return Err(Error::InvalidRowNumber(furthest + count));
+            }
+            move_cells_vertically(ws, area.min_col, area.max_col, area.min_row, |row| {
+                Some(row + count)
+            })
+        }
+        InsertShift::Right => {
+            let count = area.width();
+            for row in rows_in(ws, area.min_row, area.max_row) {
+                let furthest = row.cells.last().map_or(0, cell_col);
+                if furthest >= area.min_col && furthest + count > MAX_COLUMNS {
+                    return Err(Error::InvalidColumnNumber(furthest + count));
+                }
+            }
+            move_cells_horizontally(ws, area.min_row, area.max_row, |col| {
+                Some(if col >= area.min_col {
+                    col + count
+                } else {
+                    col
+                })
+            })
+        }
+    }
+}
+
+/// Delete the cells in `range`, shifting the cells below it up or to its
+/// right left to close the gap.
+pub fn delete_cells(ws: &mut WorksheetXml, range: &str, shift: DeleteShift) -> Result<()> {
+    let area = CellRange::parse(range)?;
+    match shift {
+        DeleteShift::Up => {
+            let count = area.height();
+            move_cells_vertically(ws, area.min_col, area.max_col, area.min_row, |row| {
+                if row > area.max_row {
+                    Some(row - count)
+                } else {
+                    None
+                }
+            })
+        }
+        DeleteShift::Left => {
+            let count = area.width();
+            move_cells_horizontally(ws, area.min_row, area.max_row, |col| {
+                if col < area.min_col {
+                    Some(col)
+                } else if col > area.max_col {
+                    Some(col - count)
+                } else {
+                    None
+                }
+            })
+        }
+    }
+}
+
+fn cell_col(cell: &Cell) -> u32 {
+    cell_name_to_coordinates(cell.r.as_str()).map_or(cell.col, |(col, _)| col)
+}
+
+fn rows_in(ws: &mut WorksheetXml, first: u32, last: u32) -> impl Iterator<Item = &mut Row> {
+    ws.sheet_data
+        .rows
+        .iter_mut()
+        .filter(move |row| row.r >= first && row.r <= last)
+}
+
+/// The last row holding a cell in the columns `first_col..=last_col`.
+fn max_row_in_columns(ws: &WorksheetXml, first_col: u32, last_col: u32) -> u32 {
+    ws.sheet_data
+        .rows
+        .iter()
+        .rev()
+        .find(|row| {
+            row.cells
+                .iter()
+                .any(|c| (first_col..=last_col).contains(&cell_col(c)))
+        })
+        .map_or(0, |row| row.r)
+}
+
+/// Move the cells in rows `first_row..=last_row` to the columns `new_col`
+/// returns for their current one, dropping those it maps to `None`.
+fn move_cells_horizontally(
+    ws: &mut WorksheetXml,
+    first_row: u32,
+    last_row: u32,
+    new_col: impl Fn(u32) -> Option<u32>,
+) -> Result<()> {
+    for row in rows_in(ws, first_row, last_row) {
+        let r = row.r;
+        let mut cells = Vec::with_capacity(row.cells.len());
+        for mut cell in row.cells.drain(..) {
+            let col = cell_col(&cell);
+            let Some(col) = new_col(col) else {
+                continue;
+            };
+            cell.r = coordinates_to_cell_name(col, r)?.into();
+            cell.col = col;
+            cells.push(cell);
+        }
+        row.cells = cells;
+    }
+    Ok(())
+}
+
+/// Move the cells in columns `first_col..=last_col`, from row `from_row`
+/// down, to the rows `new_row` returns for their current one, dropping
+/// those it maps to `None`. Row attributes such as height stay in place.
+fn move_cells_vertically(
+    ws: &mut WorksheetXml,
+    first_col: u32,
+    last_col: u32,
+    from_row: u32,
+    new_row: impl Fn(u32) -> Option<u32>,
+) -> Result<()> {
+    let mut moved: Vec<(u32, Cell)> = Vec::new();
+    for row in ws.sheet_data.rows.iter_mut().filter(|r| r.r >= from_row) {
+        let r = row.r;
+        let (taken, kept): (Vec<Cell>, Vec<Cell>) = row
+            .cells
+            .drain(..)
+            .partition(|c| (first_col..=last_col).contains(&cell_col(c)));
+        row.cells = kept;
+        moved.extend(
+            taken
+                .into_iter()
+                .filter_map(|cell| new_row(r).map(|r| (r, cell))),
+        );
+    }
+
+    for (r, mut cell) in moved {
+        let col = cell_col(&cell);
+        cell.r = coordinates_to_cell_name(col, r)?.into();
+        cell.col = col;
+        let row = find_or_create_row(ws, r);
+        let pos = row.cells.partition_point(|c| cell_col(c) < col);
+        row.cells.insert(pos, cell);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::CellValue;
+    use crate::row::get_rows;
+    use crate::sst::SharedStringTable;
+    use sheetkit_xml::worksheet::CellTypeTag;
+
+    /// A sheet whose cells hold their own original address as a number:
+    /// `B3` holds 23, `C1` holds 31.
+    fn sheet(cells: &[&str]) -> WorksheetXml {
+        let mut ws = WorksheetXml::default();
+        for name in cells {
+            let (col, row) = cell_name_to_coordinates(name).unwrap();
+            let cell = Cell {
+                r: (*name).into(),
+                col,
+                s: None,
+                t: CellTypeTag::None,
+                v: Some((col * 10 + row).to_string()),
+                f: None,
+                is: None,
+            };
+            match ws.sheet_data.rows.binary_search_by_key(&row, |r| r.r) {
+                Ok(idx) => ws.sheet_data.rows[idx].cells.push(cell),
+                Err(pos) => ws.sheet_data.rows.insert(
+                    pos,
+                    Row {
+                        r: row,
+                        spans: None,
+                        s: None,
+                        custom_format: None,
+                        ht: None,
+                        hidden: None,
+                        custom_height: None,
+                        outline_level: None,
+                        cells: vec![cell],
+                    },
+                ),
+            }
+        }
+        ws
+    }
+
+    /// Every cell as `(address, original address value)`, in sheet order.
+    fn contents(ws: &WorksheetXml) -> Vec<(String, u32)> {
+        let sst = SharedStringTable::new();
+        get_rows(ws, &sst)
+            .unwrap()
+            .into_iter()
+            .flat_map(|(row, cells)| {
+                cells.into_iter().map(move |(col, value)| {
+                    let CellValue::Number(n) = value else {
+                        panic!("expected number, got {value:?}");
+                    };
+                    (coordinates_to_cell_name(col, row).unwrap(), n as u32)
+                })
+            })
+            .collect()
+    }
+
+    fn cells(list: &[(&str, u32)]) -> Vec<(String, u32)> {
+        list.iter().map(|(c, v)| (c.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn test_insert_cells_shift_down() {
+        let mut ws = sheet(&["A1", "B1", "B2", "C2", "B3"]);
+        insert_cells(&mut ws, "B2:B3", InsertShift::Down).unwrap();
+        assert_eq!(
+            contents(&ws),
+            cells(&[("A1", 11), ("B1", 21), ("C2", 32), ("B4", 22), ("B5", 23)])
+        );
+    }
+
+    #[test]
+    fn test_insert_cells_shift_right() {
+        let mut ws = sheet(&["A1", "B1", "C1", "B2"]);
+        insert_cells(&mut ws, "B1:C1", InsertShift::Right).unwrap();
+        assert_eq!(
+            contents(&ws),
+            cells(&[("A1", 11), ("D1", 21), ("E1", 31), ("B2", 22)])
+        );
+    }
+
+    #[test]
+    fn test_delete_cells_shift_up() {
+        let mut ws = sheet(&["A1", "B1", "B2", "B3", "B4", "C3"]);
+        delete_cells(&mut ws, "B2:B3", DeleteShift::Up).unwrap();
+        assert_eq!(
+            contents(&ws),
+            cells(&[("A1", 11), ("B1", 21), ("B2", 24), ("C3", 33)])
+        );
+    }
+
+    #[test]
+    fn test_delete_cells_shift_left() {
+        let mut ws = sheet(&["A1", "B1", "C1", "D1", "B2"]);
+        delete_cells(&mut ws, "B1", DeleteShift::Left).unwrap();
+        assert_eq!(
+            contents(&ws),
+            cells(&[("A1", 11), ("B1", 31), ("C1", 41), ("B2", 22)])
+        );
+    }
+
+    #[test]
+    fn test_insert_cells_rejects_overflow() {
+        let mut ws = sheet(&["A1048576"]);
+        let result = insert_cells(&mut ws, "A1", InsertShift::Down);
+        assert!(matches!(result, Err(Error::InvalidRowNumber(_))));
+        // Columns outside the range are not affected.
+        insert_cells(&mut ws, "B1", InsertShift::Down).unwrap();
+    }
+
+    #[test]
+    fn test_cell_range_subtract() {
+        let area = CellRange::parse("A1:D4").unwrap();
+        let parts: Vec<String> = area
+            .subtract(&CellRange::parse("B2:C3").unwrap())
+            .into_iter()
+            .map(|part| part.to_reference().unwrap())
+            .collect();
+        assert_eq!(parts, vec!["A1:D1", "A4:D4", "A2:A3", "D2:D3"]);
+        let outside = CellRange::parse("F1").unwrap();
+        assert_eq!(area.subtract(&outside), vec![area]);
+        assert!(area
+            .subtract(&CellRange::parse("A1:Z9").unwrap())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_insert_cells_invalid_range() {
+        let mut ws = WorksheetXml::default();
+        assert!(insert_cells(&mut ws, "not a range", InsertShift::Right).is_err());
+    }
+}
@@ -208,6 +208,77 @@ pub fn build_chart_xml(config: &ChartConfig) -> ChartSpace {
     }
 }
 
+/// Return the cell-reference formulas (`c:f`) of every series of a chart:
+/// series names, categories, values, and bubble sizes.
+pub(crate) fn series_formulas_mut(cs: &mut ChartSpace) -> Vec<&mut String> {
+    fn text(tx: &mut Option<SeriesText>) -> impl Iterator<Item = &mut String> {
+        tx.iter_mut()
+            .filter_map(|tx| tx.str_ref.as_mut().map(|r| &mut r.f))
+    }
+    fn category(cat: &mut Option<CategoryRef>) -> impl Iterator<Item = &mut String> {
+        cat.iter_mut().flat_map(|cat| {
+            let str_ref = cat.str_ref.as_mut().map(|r| &mut r.f);
+            let num_ref = cat.num_ref.as_mut().map(|r| &mut r.f);
+            str_ref.into_iter().chain(num_ref)
+        })
+    }
+    fn value(val: &mut Option<ValueRef>) -> impl Iterator<Item = &mut String> {
+        val.iter_mut()
+            .filter_map(|val| val.num_ref.as_mut().map(|r| &mut r.f))
+    }
+    fn series(series: &mut [Series]) -> impl Iterator<Item = &mut String> {
+        series.iter_mut().flat_map(|ser| {
+            text(&mut ser.tx)
+                .chain(category(&mut ser.cat))
+                .chain(value(&mut ser.val))
+        })
+    }
+
+    let plot_area = &mut cs.chart.plot_area;
+    let mut formulas: Vec<&mut String> = Vec::new();
+    macro_rules! collect {
+        ($($chart:ident),*) => {
+            $(
+                if let Some(chart) = plot_area.$chart.as_mut() {
+                    formulas.extend(series(&mut chart.series));
+                }
+            )*
+        };
+    }
+    collect!(
+        bar_chart,
+        bar_3d_chart,
+        line_chart,
+        line_3d_chart,
+        pie_chart,
+        pie_3d_chart,
+        doughnut_chart,
+        area_chart,
+        area_3d_chart,
+        radar_chart,
+        stock_chart,
+        surface_chart,
+        surface_3d_chart,
+        of_pie_chart
+    );
+    if let Some(chart) = plot_area.scatter_chart.as_mut() {
+        for ser in &mut chart.series {
+            formulas.extend(text(&mut ser.tx));
+            formulas.extend(category(&mut ser.x_val));
+            formulas.extend(value(&mut ser.y_val));
+        }
+    }
+    if let Some(chart) = plot_area.bubble_chart.as_mut() {
+        for ser in &mut chart.series {
+            formulas.extend(text(&mut ser.tx));
+            formulas.extend(category(&mut ser.x_val));
+            formulas.extend(value(&mut ser.y_val));
+            formulas.extend(value(&mut ser.bubble_size));
+        }
+    }
+    formulas
+}
+
 /// Build a drawing XML structure containing a chart reference.
 pub fn build_drawing_with_chart(chart_ref_id: &str, from: MarkerType, to: MarkerType) -> WsDr {
     let graphic_frame = GraphicFrame {
@@ -195,42 +195,53 @@ pub fn insert_cols(ws: &mut WorksheetXml, col: &str, count: u32) -> Result<()> {
 
 /// Remove a single column, shifting columns to its right leftward by one.
 pub fn remove_col(ws: &mut WorksheetXml, col: &str) -> Result<()> {
-    let col_num = column_name_to_number(col)?;
+    remove_cols(ws, col, 1)
+}
+
+/// Remove `count` columns starting at `col`, shifting columns to their
+/// right leftward by `count` in a single pass.
+pub fn remove_cols(ws: &mut WorksheetXml, col: &str, count: u32) -> Result<()> {
+    let start_col = column_name_to_number(col)?;
+    if count == 0 {
+        return Ok(());
+    }
+    let end_col = start_col.saturating_add(count);
 
-    // Remove cells in the target column and shift cells to the right.
+    // Remove cells in the target columns and shift cells to their right.
     for row in ws.sheet_data.rows.iter_mut() {
-        // Remove cells at the target column.
         row.cells.retain(|cell| {
             cell_name_to_coordinates(cell.r.as_str())
-                .map(|(c, _)| c != col_num)
+                .map(|(c, _)| c < start_col || c >= end_col)
                 .unwrap_or(true)
         });
 
-        // Shift cells that are to the right of the removed column.
         for cell in row.cells.iter_mut() {
             let (c, r) = cell_name_to_coordinates(cell.r.as_str())?;
-            if c > col_num {
-                let new_col = c - 1;
+            if c >= end_col {
+                let new_col = c - count;
                 cell.r = coordinates_to_cell_name(new_col, r)?.into();
                 cell.col = new_col;
             }
         }
     }
 
-    // Shift Col definitions.
+    // Shrink Col definitions, dropping those that only spanned removed
+    // columns.
     if let Some(ref mut cols) = ws.cols {
-        // Remove col entries that exactly span the removed column only.
-        cols.cols
-            .retain(|c| !(c.min == col_num && c.max == col_num));
-
-        for c in cols.cols.iter_mut() {
-            if c.min > col_num {
-                c.min -= 1;
-            }
-            if c.max >= col_num {
-                c.max -= 1;
-            }
-        }
+        cols.cols.retain_mut(|c| {
+            let shift = |n: u32, removed: u32| {
+                if n < start_col {
+                    n
+                } else if n < end_col {
+                    removed
+                } else {
+                    n - count
+                }
+            };
+            c.min = shift(c.min, start_col);
+            c.max = shift(c.max, start_col - 1);
+            c.min <= c.max
+        });
 
         // Remove the Cols wrapper if it's now empty.
         if cols.cols.is_empty() {
@@ -557,6 +568,36 @@ mod tests {
         assert_eq!(r1.cells[1].v, Some("40".to_string())); // was D1
     }
 
+    #[test]
+    fn test_remove_cols_shifts_cells_left_by_count() {
+        let mut ws = sample_ws();
+        remove_cols(&mut ws, "B", 2).unwrap();
+
+        // Row 1: A1 stays, B1 removed, D1->B1.
+        let r1 = &ws.sheet_data.rows[0];
+        assert_eq!(r1.cells.len(), 2);
+        assert_eq!(r1.cells[1].r, "B1");
+        assert_eq!(r1.cells[1].v, Some("40".to_string()));
+
+        // Row 2: C2 removed.
+        let r2 = &ws.sheet_data.rows[1];
+        assert_eq!(r2.cells.len(), 1);
+        assert_eq!(r2.cells[0].r, "A2");
+    }
+
+    #[test]
+    fn test_remove_cols_shrinks_col_definitions() {
+        // Range B:E (min=2, max=5); removing C:D should shrink to B:C.
+        let mut ws = WorksheetXml::default();
+        set_col_width(&mut ws, "B", 15.0).unwrap();
+        ws.cols.as_mut().unwrap().cols[0].max = 5;
+        set_col_width(&mut ws, "H", 9.0).unwrap();
+        remove_cols(&mut ws, "C", 2).unwrap();
+        let cols = &ws.cols.as_ref().unwrap().cols;
+        assert_eq!((cols[0].min, cols[0].max), (2, 3));
+        assert_eq!((cols[1].min, cols[1].max), (6, 6));
+    }
+
     #[test]
     fn test_remove_col_with_col_definitions() {
         let mut ws = WorksheetXml::default();
@@ -433,7 +433,7 @@ fn parse_border_line_style(s: &str) -> Option<BorderLineStyle> {
 }
 
 /// Compute the next priority across all existing conditional formatting rules.
-fn next_priority(ws: &WorksheetXml) -> u32 {
+pub(crate) fn next_priority(ws: &WorksheetXml) -> u32 {
     let max = ws
         .conditional_formatting
         .iter()
@@ -100,6 +100,11 @@ pub enum Error {
     #[error("circular reference detected at {cell}")]
     CircularReference { cell: String },
 
+    /// A cell inside a legacy array formula range was changed, copied, or
+    /// moved on its own; only the whole array formula can be.
+    #[error("cannot change part of the array formula in {range}")]
+    ArrayFormulaPart { range: String },
+
     /// The formula references an unknown function.
     #[error("unknown function: {name}")]
     UnknownFunction { name: String },
@@ -11,15 +11,39 @@ pub enum Expr {
     Bool(bool),
     /// Error literal (#N/A, #VALUE!, #REF!, #DIV/0!, #NUM!, #NAME?, #NULL!)
     Error(String),
+    /// Omitted function argument (e.g., the second argument of IF(A1,,1))
+    Missing,
     /// Cell reference (e.g., A1, $B$2)
     CellRef(CellReference),
-    /// Range reference (e.g., A1:B10)
+    /// Range reference (e.g., A1:B10). Whole-column (A:A) and whole-row
+    /// (2:2) references span rows 1..=1048576 or columns A..=XFD.
     Range {
         start: CellReference,
         end: CellReference,
     },
+    /// 3D reference across a span of sheets (e.g., Jan:Dec!B5,
+    /// 'Q1:Q4'!A1:C3). `start` and `end` carry no sheet name and are equal
+    /// for a single cell.
+    Ref3D {
+        first_sheet: String,
+        last_sheet: String,
+        start: CellReference,
+        end: CellReference,
+    },
+    /// Spill range reference: the whole result of the dynamic array formula
+    /// anchored at a cell (e.g., E1#, Sheet2!E1#)
+    SpillRef(CellReference),
+    /// Array constant, as rows of literal elements (e.g., {1,2;3,4})
+    Array(Vec<Vec<Expr>>),
+    /// Defined name (e.g., TaxRate, Sheet1!Local_Name)
+    Name { name: String, sheet: Option<String> },
+    /// Structured table reference (e.g., Sales[Amount], [@Qty])
+    StructuredRef(StructuredReference),
     /// Function call (e.g., SUM(A1:A10))
     Function { name: String, args: Vec<Expr> },
+    /// Call of a function value, such as an immediately invoked LAMBDA
+    /// (e.g., LAMBDA(x, x + 1)(2))
+    Call { callee: Box<Expr>, args: Vec<Expr> },
     /// Binary operation (e.g., A1 + B1)
     BinaryOp {
         op: BinaryOperator,
@@ -50,6 +74,33 @@ pub struct CellReference {
     pub sheet: Option<String>,
 }
 
+/// A structured reference to (part of) an Excel table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructuredReference {
+    /// Table name, or `None` for a reference inside the table itself
+    /// (e.g., `[@Qty]`).
+    pub table: Option<String>,
+    /// Special items such as `#Headers`. Empty means the data rows.
+    pub items: Vec<TableItem>,
+    /// First and last column names. `None` means all columns.
+    pub columns: Option<(String, String)>,
+}
+
+/// Special item specifiers of a structured reference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableItem {
+    /// The whole table (#All)
+    All,
+    /// The data rows (#Data)
+    Data,
+    /// The header row (#Headers)
+    Headers,
+    /// The totals row (#Totals)
+    Totals,
+    /// The row of the formula cell (#This Row or @)
+    ThisRow,
+}
+
 /// Binary operators.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BinaryOperator {
@@ -77,6 +128,20 @@ pub enum BinaryOperator {
     Gt,
     /// Greater than or equal (>=)
     Ge,
+    /// Range between two references (e.g., A1:INDEX(B1:B10, 3))
+    Range,
+    /// Intersection of two references (a space, e.g., B1:D4 C2:C9)
+    Intersect,
+    /// Union of two references (a comma inside parentheses, e.g., (A1,C3))
+    Union,
+}
+
+impl BinaryOperator {
+    /// Return `true` for the reference operators `:`, space, and `,`, whose
+    /// operands are references rather than values.
+    pub fn is_reference(self) -> bool {
+        matches!(self, Self::Range | Self::Intersect | Self::Union)
+    }
 }
 
 /// Unary operators.
@@ -3,14 +3,27 @@
 //! Evaluates parsed formula ASTs against cell data provided through the
 //! [`CellDataProvider`] trait. Supports arithmetic, comparison, string
 //! concatenation, cell/range references, and built-in function calls.
+//!
+//! Expressions are normally evaluated to a single [`CellValue`]. Dynamic
+//! array formulas are evaluated with [`Evaluator::eval_array`], which yields
+//! an [`ArrayValue`] that the caller spills into neighbouring cells. Legacy
+//! (CSE) array formulas use [`Evaluator::eval_array_formula`], which also
+//! applies operators on ranges element-wise inside function arguments.
 
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 
-use crate::cell::CellValue;
+use crate::cell::{CellValue, DateSystem};
 use crate::error::{Error, Result};
-use crate::formula::ast::{BinaryOperator, CellReference, Expr, UnaryOperator};
+use crate::formula::ast::{
+    BinaryOperator, CellReference, Expr, StructuredReference, TableItem, UnaryOperator,
+};
 use crate::formula::functions;
-use crate::utils::cell_ref::{column_name_to_number, column_number_to_name};
+use crate::formula::registry::FunctionRegistry;
+use crate::utils::cell_ref::{
+    cell_name_to_coordinates, column_name_to_number, column_number_to_name,
+};
+use crate::utils::constants::{MAX_COLUMNS, MAX_ROWS};
 
 /// Maximum recursion depth for nested formula evaluation.
 const MAX_EVAL_DEPTH: usize = 256;
@@ -21,12 +34,215 @@ pub trait CellDataProvider {
     fn get_cell(&self, sheet: &str, col: u32, row: u32) -> CellValue;
     /// Return the name of the sheet that owns the formula being evaluated.
     fn current_sheet(&self) -> &str;
+    /// Return the formula text of the defined name `name` as seen from
+    /// `sheet`: a name scoped to that sheet wins over a workbook-level name.
+    /// Names are matched case-insensitively.
+    fn defined_name(&self, _name: &str, _sheet: &str) -> Option<String> {
+        None
+    }
+    /// Return the `(col, row)` of the formula cell being evaluated, if known.
+    /// Needed for this-row structured references such as `[@Qty]`.
+    fn current_cell(&self) -> Option<(u32, u32)> {
+        None
+    }
+    /// Return the table with the given name (case-insensitive).
+    fn table(&self, _name: &str) -> Option<TableRange> {
+        None
+    }
+    /// Return the table containing the given cell, if any.
+    fn table_at(&self, _sheet: &str, _col: u32, _row: u32) -> Option<TableRange> {
+        None
+    }
+    /// Return the sheet names in workbook order. Needed for 3D references
+    /// such as `Jan:Dec!B5`.
+    fn sheet_names(&self) -> Vec<String> {
+        Vec::new()
+    }
+    /// Return the last used `(col, row)` on `sheet`, or `(0, 0)` for an
+    /// empty sheet. Whole-column and whole-row references are bounded by it.
+    /// `None` means the extent is unknown and references are not bounded.
+    fn used_extent(&self, _sheet: &str) -> Option<(u32, u32)> {
+        None
+    }
+    /// Return the pivot table whose output area contains the given cell, if
+    /// any. Needed for GETPIVOTDATA.
+    fn pivot_table_at(&self, _sheet: &str, _col: u32, _row: u32) -> Option<PivotTableSource> {
+        None
+    }
+    /// Return the caller-registered functions, which take precedence over
+    /// built-in functions of the same name.
+    fn functions(&self) -> Option<&FunctionRegistry> {
+        None
+    }
+    /// Return the date system serial numbers are interpreted in by date
+    /// functions such as DATE, YEAR, and TEXT.
+    fn date_system(&self) -> DateSystem {
+        DateSystem::Date1900
+    }
+    /// Return the last `(col, row)` of the spill range of the dynamic array
+    /// formula anchored at the given cell, if its result spilled. Needed for
+    /// spill references such as `E1#`.
+    fn spill_range(&self, _sheet: &str, _col: u32, _row: u32) -> Option<(u32, u32)> {
+        None
+    }
+}
+
+/// The location of an Excel table, used to resolve structured references.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableRange {
+    /// Table name (e.g., "Sales").
+    pub name: String,
+    /// Sheet holding the table.
+    pub sheet: String,
+    /// First column of the table (1-based).
+    pub min_col: u32,
+    /// First row of the table, including the header row (1-based).
+    pub min_row: u32,
+    /// Last column of the table (1-based).
+    pub max_col: u32,
+    /// Last row of the table, including the totals row (1-based).
+    pub max_row: u32,
+    /// Whether the first row is a header row.
+    pub has_headers: bool,
+    /// Whether the last row is a totals row.
+    pub has_totals: bool,
+    /// Column names, left to right.
+    pub columns: Vec<String>,
+}
+
+impl TableRange {
+    /// Return `true` if `(col, row)` lies inside the table on `sheet`.
+    pub fn contains(&self, sheet: &str, col: u32, row: u32) -> bool {
+        self.sheet == sheet
+            && (self.min_col..=self.max_col).contains(&col)
+            && (self.min_row..=self.max_row).contains(&row)
+    }
+
+    /// Resolve a structured reference against this table to a cell or range
+    /// expression. `row` is the formula cell's row, used by this-row
+    /// references. Returns `#REF!` for unknown columns or missing rows and
+    /// `#VALUE!` for a this-row reference outside the data rows.
+    pub fn resolve(&self, sref: &StructuredReference, row: Option<u32>) -> Expr {
+        let ref_error = || Expr::Error("#REF!".to_string());
+        let header_row = self.has_headers.then_some(self.min_row);
+        let totals_row = self.has_totals.then_some(self.max_row);
+        let data_first = self.min_row + u32::from(self.has_headers);
+        let data_last = self.max_row - u32::from(self.has_totals);
+        let data = (data_first <= data_last).then_some((data_first, data_last));
+
+        // Combine the row spans of all items; no item means the data rows.
+        let mut span: Option<(u32, u32)> = None;
+        let items: &[TableItem] = if sref.items.is_empty() {
+            &[TableItem::Data]
+        } else {
+            &sref.items
+        };
+        for item in items {
+            let part = match item {
+                TableItem::All => Some((self.min_row, self.max_row)),
+                TableItem::Data => data,
+                TableItem::Headers => header_row.map(|r| (r, r)),
+                TableItem::Totals => totals_row.map(|r| (r, r)),
+                TableItem::ThisRow => match row {
+                    Some(r) if data.is_some_and(|(first, last)| (first..=last).contains(&r)) => {
+                        Some((r, r))
+                    }
+                    _ => return Expr::Error("#VALUE!".to_string()),
+                },
+            };
+            let Some((first, last)) = part else {
+                return ref_error();
+            };
+            span = Some(match span {
+                Some((a, b)) => (a.min(first), b.max(last)),
+                None => (first, last),
+            });
+        }
+        let Some((first_row, last_row)) = span else {
+            return ref_error();
+        };
+
+        let (first_col, last_col) = match &sref.columns {
+            None => (self.min_col, self.max_col),
+            Some((first, last)) => {
+                let position = |name: &str| {
+                    self.columns
+                        .iter()
+                        .position(|c| c.eq_ignore_ascii_case(name))
+                        .map(|i| self.min_col + i as u32)
+                };
+                match (position(first), position(last)) {
+                    (Some(a), Some(b)) => (a.min(b), a.max(b)),
+                    _ => return ref_error(),
+                }
+            }
+        };
+
+        let cell = |col: u32, row: u32| -> Option<CellReference> {
+            Some(CellReference {
+                col: column_number_to_name(col).ok()?,
+                row,
+                abs_col: true,
+                abs_row: true,
+                sheet: Some(self.sheet.clone()),
+            })
+        };
+        match (cell(first_col, first_row), cell(last_col, last_row)) {
+            (Some(start), _) if first_col == last_col && first_row == last_row => {
+                Expr::CellRef(start)
+            }
+            (Some(start), Some(end)) => Expr::Range { start, end },
+            _ => ref_error(),
+        }
+    }
+}
+
+/// The layout and source data of a pivot table, used to evaluate
+/// GETPIVOTDATA.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PivotTableSource {
+    /// Pivot table name.
+    pub name: String,
+    /// The pivot table's output area.
+    pub location: RefArea,
+    /// The source data, including its header row of field names.
+    pub source: RefArea,
+    /// Names of the fields on the row axis.
+    pub row_fields: Vec<String>,
+    /// Names of the fields on the column axis.
+    pub col_fields: Vec<String>,
+    /// The summarized value fields.
+    pub data_fields: Vec<PivotValueField>,
+}
+
+/// A value field of a [`PivotTableSource`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PivotValueField {
+    /// Display name, e.g. "Sum of Sales".
+    pub name: String,
+    /// Name of the summarized source field, e.g. "Sales".
+    pub field: String,
+    /// How the field is summarized.
+    pub function: crate::pivot::AggregateFunction,
 }
 
 /// In-memory snapshot of cell data, decoupled from any Workbook borrow.
 pub struct CellSnapshot {
     cells: std::collections::HashMap<(String, u32, u32), CellValue>,
     current_sheet: String,
+    /// Defined names keyed by (scope sheet, uppercase name); `None` is
+    /// workbook scope.
+    names: HashMap<(Option<String>, String), String>,
+    current_cell: Option<(u32, u32)>,
+    tables: Vec<TableRange>,
+    pivot_tables: Vec<PivotTableSource>,
+    sheets: Vec<String>,
+    /// Last used `(col, row)` per sheet.
+    extents: HashMap<String, (u32, u32)>,
+    functions: FunctionRegistry,
+    date_system: DateSystem,
+    /// Last `(col, row)` of each spill range, keyed by anchor.
+    spills: HashMap<(String, u32, u32), (u32, u32)>,
 }
 
 impl CellSnapshot {
@@ -35,11 +251,62 @@ impl CellSnapshot {
         Self {
             cells: std::collections::HashMap::new(),
             current_sheet,
+            names: HashMap::new(),
+            current_cell: None,
+            tables: Vec::new(),
+            pivot_tables: Vec::new(),
+            sheets: Vec::new(),
+            extents: HashMap::new(),
+            functions: FunctionRegistry::default(),
+            date_system: DateSystem::Date1900,
+            spills: HashMap::new(),
         }
     }
 
+    /// Set the caller-registered functions available to formulas.
+    pub fn set_functions(&mut self, functions: FunctionRegistry) {
+        self.functions = functions;
+    }
+
+    /// Set the date system of the workbook the cells belong to.
+    pub fn set_date_system(&mut self, date_system: DateSystem) {
+        self.date_system = date_system;
+    }
+
+    /// Set the sheet names in workbook order, used by 3D references.
+    pub fn set_sheet_names(&mut self, sheets: Vec<String>) {
+        self.sheets = sheets;
+    }
+
+    /// Set the `(col, row)` of the formula cell being evaluated.
+    pub fn set_current_cell(&mut self, cell: Option<(u32, u32)>) {
+        self.current_cell = cell;
+    }
+
+    /// Register a table for structured reference resolution.
+    pub fn add_table(&mut self, table: TableRange) {
+        self.tables.push(table);
+    }
+
+    /// Register a pivot table for GETPIVOTDATA.
+    pub fn add_pivot_table(&mut self, pivot_table: PivotTableSource) {
+        self.pivot_tables.push(pivot_table);
+    }
+
+    /// Register a defined name. `scope` is the sheet the name is local to,
+    /// or `None` for a workbook-level name. `value` is the name's formula
+    /// text, e.g. `"Sheet1!$A$1:$A$10"` or `"0.07"`.
+    pub fn set_defined_name(&mut self, name: &str, scope: Option<&str>, value: &str) {
+        self.names.insert(
+            (scope.map(str::to_string), name.to_uppercase()),
+            value.to_string(),
+        );
+    }
+
     /// Insert a cell value into the snapshot.
     pub fn set_cell(&mut self, sheet: &str, col: u32, row: u32, value: CellValue) {
+        let extent = self.extents.entry(sheet.to_string()).or_insert((0, 0));
+        *extent = (extent.0.max(col), extent.1.max(row));
         self.cells.insert((sheet.to_string(), col, row), value);
     }
 
@@ -47,6 +314,20 @@ impl CellSnapshot {
     pub fn set_current_sheet(&mut self, sheet: &str) {
         self.current_sheet = sheet.to_string();
     }
+
+    /// Record that the dynamic array formula at `(col, row)` spilled up to
+    /// `end`, or with `None`, that it no longer spills.
+    pub fn set_spill(&mut self, sheet: &str, col: u32, row: u32, end: Option<(u32, u32)>) {
+        let key = (sheet.to_string(), col, row);
+        match end {
+            Some(end) => {
+                self.spills.insert(key, end);
+            }
+            None => {
+                self.spills.remove(&key);
+            }
+        }
+    }
 }
 
 impl CellDataProvider for CellSnapshot {
@@ -60,6 +341,373 @@ impl CellDataProvider for CellSnapshot {
     fn current_sheet(&self) -> &str {
         &self.current_sheet
     }
+
+    fn defined_name(&self, name: &str, sheet: &str) -> Option<String> {
+        let name = name.to_uppercase();
+        self.names
+            .get(&(Some(sheet.to_string()), name.clone()))
+            .or_else(|| self.names.get(&(None, name)))
+            .cloned()
+    }
+
+    fn current_cell(&self) -> Option<(u32, u32)> {
+        self.current_cell
+    }
+
+    fn table(&self, name: &str) -> Option<TableRange> {
+        self.tables
+            .iter()
+            .find(|t| t.name.eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    fn table_at(&self, sheet: &str, col: u32, row: u32) -> Option<TableRange> {
+        self.tables
+            .iter()
+            .find(|t| t.contains(sheet, col, row))
+            .cloned()
+    }
+
+    fn sheet_names(&self) -> Vec<String> {
+        self.sheets.clone()
+    }
+
+    fn used_extent(&self, sheet: &str) -> Option<(u32, u32)> {
+        Some(self.extents.get(sheet).copied().unwrap_or((0, 0)))
+    }
+
+    fn pivot_table_at(&self, sheet: &str, col: u32, row: u32) -> Option<PivotTableSource> {
+        self.pivot_tables
+            .iter()
+            .find(|pt| pt.location.contains(sheet, col, row))
+            .cloned()
+    }
+
+    fn functions(&self) -> Option<&FunctionRegistry> {
+        Some(&self.functions)
+    }
+
+    fn date_system(&self) -> DateSystem {
+        self.date_system
+    }
+
+    fn spill_range(&self, sheet: &str, col: u32, row: u32) -> Option<(u32, u32)> {
+        self.spills.get(&(sheet.to_string(), col, row)).copied()
+    }
+}
+
+/// A rectangular block of values produced by a range or an array formula.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrayValue {
+    /// Number of rows (at least 1).
+    pub rows: usize,
+    /// Number of columns (at least 1).
+    pub cols: usize,
+    /// Values in row-major order; `values.len() == rows * cols`.
+    pub values: Vec<CellValue>,
+}
+
+impl ArrayValue {
+    /// Create an array from row-major values.
+    pub fn new(rows: usize, cols: usize, values: Vec<CellValue>) -> Self {
+        debug_assert_eq!(rows * cols, values.len());
+        Self { rows, cols, values }
+    }
+
+    /// Wrap a single value as a 1x1 array.
+    pub fn scalar(value: CellValue) -> Self {
+        Self {
+            rows: 1,
+            cols: 1,
+            values: vec![value],
+        }
+    }
+
+    /// Return `true` if the array holds exactly one value.
+    pub fn is_scalar(&self) -> bool {
+        self.rows == 1 && self.cols == 1
+    }
+
+    /// Return the value at the given 0-based row and column.
+    pub fn get(&self, row: usize, col: usize) -> &CellValue {
+        &self.values[row * self.cols + col]
+    }
+
+    /// Return the values of the given 0-based row.
+    pub fn row(&self, row: usize) -> &[CellValue] {
+        &self.values[row * self.cols..(row + 1) * self.cols]
+    }
+
+    /// Collapse the array to its top-left value, as Excel does when an
+    /// array is used where a single value is expected.
+    pub fn into_scalar(self) -> CellValue {
+        self.values.into_iter().next().unwrap_or(CellValue::Empty)
+    }
+
+    /// Return the value at `(row, col)` after broadcasting single rows and
+    /// columns, or `#N/A` if the position lies outside the array.
+    pub fn broadcast_get(&self, row: usize, col: usize) -> CellValue {
+        let r = if self.rows == 1 { 0 } else { row };
+        let c = if self.cols == 1 { 0 } else { col };
+        if r < self.rows && c < self.cols {
+            self.get(r, c).clone()
+        } else {
+            CellValue::Error("#N/A".to_string())
+        }
+    }
+}
+
+/// Return `true` if `expr` produces an array when evaluated in array
+/// context: a dynamic array function call, or an operator applied to one.
+///
+/// Plain ranges are not considered array-producing on their own so that
+/// legacy formulas such as `=A1:A3` keep their single-value semantics.
+pub fn is_array_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::SpillRef(_) | Expr::Array(_) => true,
+        Expr::Function { name, .. } => {
+            functions::lookup_array_function(name).is_some()
+                || functions::strip_future_function_prefix(name).eq_ignore_ascii_case("ANCHORARRAY")
+        }
+        // Lambdas may return arrays.
+        Expr::Call { .. } => true,
+        Expr::BinaryOp { left, right, .. } => is_array_expr(left) || is_array_expr(right),
+        Expr::UnaryOp { operand, .. } => is_array_expr(operand),
+        Expr::Paren(inner) => is_array_expr(inner),
+        _ => false,
+    }
+}
+
+/// Return `true` if `expr` is a call of `LAMBDA` itself, as opposed to a
+/// call of a lambda value.
+fn is_lambda_literal(expr: &Expr) -> bool {
+    matches!(expr, Expr::Function { name, .. }
+        if functions::strip_future_function_prefix(name).eq_ignore_ascii_case("LAMBDA"))
+}
+
+/// Strip the `_xlpm.` prefix that files put on LET and LAMBDA parameter
+/// names.
+fn parameter_name(name: &str) -> &str {
+    match name.get(..6) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("_xlpm.") => &name[6..],
+        _ => name,
+    }
+}
+
+/// Return `true` if `expr` applies an operator to a range, such as
+/// `A1:A3*B1:B3`. Names and structured references may denote ranges too.
+fn has_range_operand(expr: &Expr) -> bool {
+    match expr {
+        Expr::Range { .. } | Expr::Name { .. } | Expr::StructuredRef(_) => true,
+        Expr::BinaryOp { op, .. } if op.is_reference() => true,
+        Expr::BinaryOp { left, right, .. } => has_range_operand(left) || has_range_operand(right),
+        Expr::UnaryOp { operand, .. } => has_range_operand(operand),
+        Expr::Paren(inner) => has_range_operand(inner),
+        _ => false,
+    }
+}
+
+/// Return `true` if `expr` is a resolved reference: a cell, a range, a 3D
+/// reference, or a union of references.
+pub(crate) fn is_reference(expr: &Expr) -> bool {
+    match expr {
+        Expr::CellRef(_) | Expr::Range { .. } | Expr::Ref3D { .. } => true,
+        Expr::BinaryOp {
+            op: BinaryOperator::Union,
+            left,
+            right,
+        } => is_reference(left) && is_reference(right),
+        _ => false,
+    }
+}
+
+/// Parse the definition of a defined name as seen from `current_sheet`, or
+/// from `sheet` for a sheet-qualified name. Unknown names and definitions
+/// that cannot be parsed yield `#NAME?`.
+fn name_definition(
+    provider: &dyn CellDataProvider,
+    name: &str,
+    sheet: Option<&str>,
+    current_sheet: &str,
+) -> Expr {
+    provider
+        .defined_name(name, sheet.unwrap_or(current_sheet))
+        .and_then(|text| crate::formula::parser::parse_formula(text.trim_start_matches('=')).ok())
+        .unwrap_or_else(|| Expr::Error("#NAME?".to_string()))
+}
+
+/// A rectangular area of cells on one sheet: the value of a reference.
+///
+/// Reference operators (`:`, space, and `,`) and reference functions such
+/// as OFFSET and INDIRECT compute areas, which convert back to
+/// [`Expr::CellRef`] or [`Expr::Range`] with [`RefArea::to_expr`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RefArea {
+    /// Sheet holding the area.
+    pub sheet: String,
+    /// First column (1-based).
+    pub min_col: u32,
+    /// First row (1-based).
+    pub min_row: u32,
+    /// Last column (1-based).
+    pub max_col: u32,
+    /// Last row (1-based).
+    pub max_row: u32,
+}
+
+impl RefArea {
+    /// Create the area spanned by `start:end` on `sheet`.
+    pub fn new(sheet: &str, start: &CellReference, end: &CellReference) -> Result<Self> {
+        let start_col = column_name_to_number(&start.col)?;
+        let end_col = column_name_to_number(&end.col)?;
+        Ok(Self {
+            sheet: sheet.to_string(),
+            min_col: start_col.min(end_col),
+            min_row: start.row.min(end.row),
+            max_col: start_col.max(end_col),
+            max_row: start.row.max(end.row),
+        })
+    }
+
+    /// Return the area of a cell or range expression. Unqualified
+    /// references belong to `current_sheet`. Other expressions yield `None`.
+    pub fn from_expr(expr: &Expr, current_sheet: &str) -> Option<Self> {
+        let (start, end) = match expr {
+            Expr::CellRef(cell) => (cell, cell),
+            Expr::Range { start, end } => (start, end),
+            _ => return None,
+        };
+        let sheet = start.sheet.as_deref().unwrap_or(current_sheet);
+        Self::new(sheet, start, end).ok()
+    }
+
+    /// Return the area of an A1-style cell or range such as `B2:D10` on
+    /// `sheet`, or `None` if it cannot be parsed.
+    pub fn from_a1(sheet: &str, reference: &str) -> Option<Self> {
+        let (start, end) = reference.split_once(':').unwrap_or((reference, reference));
+        let (c1, r1) = cell_name_to_coordinates(start).ok()?;
+        let (c2, r2) = cell_name_to_coordinates(end).ok()?;
+        Some(Self {
+            sheet: sheet.to_string(),
+            min_col: c1.min(c2),
+            min_row: r1.min(r2),
+            max_col: c1.max(c2),
+            max_row: r1.max(r2),
+        })
+    }
+
+    /// Number of rows in the area.
+    pub fn rows(&self) -> u32 {
+        self.max_row - self.min_row + 1
+    }
+
+    /// Number of columns in the area.
+    pub fn cols(&self) -> u32 {
+        self.max_col - self.min_col + 1
+    }
+
+    /// Return `true` if `(col, row)` lies inside the area on `sheet`.
+    pub fn contains(&self, sheet: &str, col: u32, row: u32) -> bool {
+        self.sheet.eq_ignore_ascii_case(sheet)
+            && (self.min_col..=self.max_col).contains(&col)
+            && (self.min_row..=self.max_row).contains(&row)
+    }
+
+    /// Return the overlap of two areas, if they share any cell.
+    pub fn intersect(&self, other: &RefArea) -> Option<RefArea> {
+        let area = RefArea {
+            sheet: self.sheet.clone(),
+            min_col: self.min_col.max(other.min_col),
+            min_row: self.min_row.max(other.min_row),
+            max_col: self.max_col.min(other.max_col),
+            max_row: self.max_row.min(other.max_row),
+        };
+        (self.sheet.eq_ignore_ascii_case(&other.sheet)
+            && area.min_col <= area.max_col
+            && area.min_row <= area.max_row)
+            .then_some(area)
+    }
+
+    /// Convert the area to an absolute, sheet-qualified cell or range
+    /// expression, or `#REF!` if it lies outside the sheet.
+    pub fn to_expr(&self) -> Expr {
+        let cell = |col: u32, row: u32| -> Option<CellReference> {
+            if !(1..=MAX_ROWS).contains(&row) {
+                return None;
+            }
+            Some(CellReference {
+                col: column_number_to_name(col).ok()?,
+                row,
+                abs_col: true,
+                abs_row: true,
+                sheet: Some(self.sheet.clone()),
+            })
+        };
+        match (
+            cell(self.min_col, self.min_row),
+            cell(self.max_col, self.max_row),
+        ) {
+            (Some(start), Some(_)) if self.rows() == 1 && self.cols() == 1 => Expr::CellRef(start),
+            (Some(start), Some(end)) => Expr::Range { start, end },
+            _ => Expr::Error("#REF!".to_string()),
+        }
+    }
+
+    /// Cut off an area reaching the last row or column of the sheet, such
+    /// as `A:A` or `2:2`, at the sheet's used extent, keeping at least one
+    /// row and column.
+    fn bounded(mut self, provider: &dyn CellDataProvider) -> Self {
+        if self.max_col == MAX_COLUMNS || self.max_row == MAX_ROWS {
+            if let Some((used_col, used_row)) = provider.used_extent(&self.sheet) {
+                if self.max_col == MAX_COLUMNS {
+                    self.max_col = used_col.max(self.min_col);
+                }
+                if self.max_row == MAX_ROWS {
+                    self.max_row = used_row.max(self.min_row);
+                }
+            }
+        }
+        self
+    }
+}
+
+/// Compute the area of `start:end` on `sheet`, bounding whole-column and
+/// whole-row references by the used range.
+fn range_bounds(
+    provider: &dyn CellDataProvider,
+    sheet: &str,
+    start: &CellReference,
+    end: &CellReference,
+) -> Result<RefArea> {
+    Ok(RefArea::new(sheet, start, end)?.bounded(provider))
+}
+
+/// Return the sheets from `first` to `last` (in either order) in workbook
+/// order, or `None` if either sheet does not exist.
+fn sheet_span(provider: &dyn CellDataProvider, first: &str, last: &str) -> Option<Vec<String>> {
+    let sheets = provider.sheet_names();
+    let position = |name: &str| sheets.iter().position(|s| s.eq_ignore_ascii_case(name));
+    let (a, b) = (position(first)?, position(last)?);
+    Some(sheets[a.min(b)..=a.max(b)].to_vec())
+}
+
+/// Resolve a structured reference made from `(sheet, cell)` to a cell or
+/// range expression. A reference without a table name uses the table that
+/// contains the formula cell. Unknown tables yield `#REF!`.
+fn structured_ref_target(
+    provider: &dyn CellDataProvider,
+    sref: &StructuredReference,
+    sheet: &str,
+    cell: Option<(u32, u32)>,
+) -> Expr {
+    let table = match &sref.table {
+        Some(name) => provider.table(name),
+        None => cell.and_then(|(col, row)| provider.table_at(sheet, col, row)),
+    };
+    match table {
+        Some(table) => table.resolve(sref, cell.map(|(_, row)| row)),
+        None => Expr::Error("#REF!".to_string()),
+    }
 }
 
 /// Evaluate a parsed formula expression against the given cell data provider.
@@ -68,11 +716,77 @@ pub fn evaluate(expr: &Expr, provider: &dyn CellDataProvider) -> Result<CellValu
     evaluator.eval_expr(expr)
 }
 
+/// Excel's error values.
+const ERROR_VALUES: [&str; 9] = [
+    "#NULL!", "#DIV/0!", "#VALUE!", "#REF!", "#NAME?", "#NUM!", "#N/A", "#SPILL!", "#CALC!",
+];
+
+/// Turn an error value that a function passed up as
+/// [`Error::FormulaError`], such as the `#DIV/0!` of `SUM(1/0)`, back into
+/// the result of the formula with `value`, so that the formula shows the
+/// error instead of failing the calculation.
+pub fn recover_error_value<T>(result: Result<T>, value: impl FnOnce(CellValue) -> T) -> Result<T> {
+    match result {
+        Err(Error::FormulaError(code)) if ERROR_VALUES.contains(&code.as_str()) => {
+            Ok(value(CellValue::Error(code)))
+        }
+        other => other,
+    }
+}
+
+/// The value of a name bound by LET or by a LAMBDA parameter.
+#[derive(Debug, Clone)]
+pub enum Binding {
+    /// A reference, kept unevaluated so that functions expecting a
+    /// reference accept the name too.
+    Reference(Expr),
+    /// A single value or an array.
+    Value(ArrayValue),
+    /// A function value created by LAMBDA.
+    Lambda(Lambda),
+}
+
+/// A function value created by LAMBDA. It closes over the names bound
+/// where it was created.
+#[derive(Debug, Clone)]
+pub struct Lambda {
+    /// Parameter names, without the `_xlpm.` prefix.
+    pub params: Vec<String>,
+    /// The expression evaluated when the lambda is called.
+    pub body: Expr,
+    captured: Vec<(String, Binding)>,
+}
+
 /// Stateful evaluator that tracks recursion depth and circular references.
 pub struct Evaluator<'a> {
     provider: &'a dyn CellDataProvider,
     eval_stack: HashSet<(String, u32, u32)>,
     depth: usize,
+    array_context: bool,
+    /// `(col, row)` of the formula cell being evaluated, if known.
+    cell: Option<(u32, u32)>,
+    /// Names bound by LET and LAMBDA, innermost last.
+    scope: Vec<(String, Binding)>,
+    functions: Option<&'a FunctionRegistry>,
+    /// Steps recorded since [`start_trace`](Self::start_trace), with the
+    /// evaluation depth at which recording started.
+    trace: Option<(usize, Vec<EvalStep>)>,
+}
+
+/// One step of an evaluation trace: a subexpression and the value it
+/// evaluated to. See [`Evaluator::start_trace`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalStep {
+    /// Nesting depth of the subexpression; the traced expression itself is
+    /// at depth 0.
+    pub depth: usize,
+    /// The subexpression, printed as formula text.
+    pub expr: String,
+    /// The value of the subexpression. For an array, its top-left value.
+    pub value: CellValue,
+    /// The full value of a subexpression evaluated as an array with more
+    /// than one value, such as a range passed to a function.
+    pub array: Option<ArrayValue>,
 }
 
 impl<'a> Evaluator<'a> {
@@ -82,6 +796,23 @@ impl<'a> Evaluator<'a> {
             provider,
             eval_stack: HashSet::new(),
             depth: 0,
+            array_context: false,
+            cell: provider.current_cell(),
+            scope: Vec::new(),
+            functions: provider.functions(),
+            trace: None,
+        }
+    }
+
+    /// Create an evaluator that calls the functions registered in
+    /// `functions` instead of those of the data provider.
+    pub fn with_functions(
+        provider: &'a dyn CellDataProvider,
+        functions: &'a FunctionRegistry,
+    ) -> Self {
+        Self {
+            functions: Some(functions),
+            ..Self::new(provider)
         }
     }
 
@@ -95,10 +826,254 @@ impl<'a> Evaluator<'a> {
             ));
         }
         let result = self.eval_inner(expr);
+        if let Ok(value) = &result {
+            self.record_step(expr, value, None);
+        }
+        self.depth -= 1;
+        result
+    }
+
+    /// Start recording an evaluation trace: every subexpression evaluated
+    /// from now on, other than literals and parentheses, is recorded with
+    /// its value once it has been evaluated, so inner subexpressions come
+    /// before the expressions containing them. Defined names, structured
+    /// references, and calls of named lambdas also record the expressions
+    /// they resolve to.
+    pub fn start_trace(&mut self) {
+        self.trace = Some((self.depth, Vec::new()));
+    }
+
+    /// Stop recording and return the steps recorded since
+    /// [`start_trace`](Self::start_trace).
+    pub fn take_trace(&mut self) -> Vec<EvalStep> {
+        self.trace
+            .take()
+            .map(|(_, steps)| steps)
+            .unwrap_or_default()
+    }
+
+    fn record_step(&mut self, expr: &Expr, value: &CellValue, array: Option<&ArrayValue>) {
+        let Some((base, steps)) = &mut self.trace else {
+            return;
+        };
+        let depth = self.depth.saturating_sub(*base + 1);
+        let literal = matches!(
+            expr,
+            Expr::Number(_)
+                | Expr::String(_)
+                | Expr::Bool(_)
+                | Expr::Error(_)
+                | Expr::Missing
+                | Expr::Paren(_)
+        );
+        if depth > 0 && literal {
+            return;
+        }
+        steps.push(EvalStep {
+            depth,
+            expr: expr.to_string(),
+            value: value.clone(),
+            array: array.cloned(),
+        });
+    }
+
+    /// Evaluate an expression in array context.
+    ///
+    /// Ranges yield all of their cells, dynamic array functions yield their
+    /// full result, and operators are applied element-wise with single rows
+    /// and columns broadcast across the other operand. Any other expression
+    /// yields a 1x1 array.
+    pub fn eval_array(&mut self, expr: &Expr) -> Result<ArrayValue> {
+        self.depth += 1;
+        if self.depth > MAX_EVAL_DEPTH {
+            self.depth -= 1;
+            return Err(Error::FormulaError(
+                "maximum evaluation depth exceeded".to_string(),
+            ));
+        }
+        let result = self.eval_array_inner(expr);
+        // Scalar results are recorded by `eval_expr`.
+        if let Some(array) = result.as_ref().ok().filter(|array| !array.is_scalar()) {
+            let value = array.values[0].clone();
+            self.record_step(expr, &value, Some(array));
+        }
         self.depth -= 1;
         result
     }
 
+    /// Evaluate a legacy (CSE) array formula such as `{=SUM(A1:A3*B1:B3)}`.
+    ///
+    /// Works like [`eval_array`](Self::eval_array), but operators applied to
+    /// ranges inside function arguments are also evaluated element-wise, so
+    /// `SUM(A1:A3*B1:B3)` sums the pairwise products.
+    pub fn eval_array_formula(&mut self, expr: &Expr) -> Result<ArrayValue> {
+        let previous = std::mem::replace(&mut self.array_context, true);
+        let result = self.eval_array(expr);
+        self.array_context = previous;
+        result
+    }
+
+    /// Return `true` if a function argument should be expanded to all of its
+    /// values rather than evaluated to a single value.
+    ///
+    /// This holds for dynamic array expressions (see [`is_array_expr`]) and,
+    /// while evaluating an array formula, for operators applied to ranges.
+    pub fn is_array_arg(&self, expr: &Expr) -> bool {
+        is_array_expr(expr)
+            || (self.array_context && has_range_operand(expr))
+            || match expr {
+                Expr::Name { name, sheet: None } => {
+                    matches!(self.binding(name), Some(Binding::Value(array)) if !array.is_scalar())
+                }
+                Expr::Function { name, .. } => self.is_user_function(name),
+                _ => false,
+            }
+    }
+
+    /// Look up a name bound by LET or by a LAMBDA parameter, innermost
+    /// binding first.
+    pub fn binding(&self, name: &str) -> Option<&Binding> {
+        let name = parameter_name(name);
+        self.scope
+            .iter()
+            .rev()
+            .find(|(bound, _)| bound.eq_ignore_ascii_case(name))
+            .map(|(_, binding)| binding)
+    }
+
+    /// Return a mark to pass to [`restore_scope`](Self::restore_scope) to
+    /// drop the names bound after this point.
+    pub fn scope_mark(&self) -> usize {
+        self.scope.len()
+    }
+
+    /// Bind `name` for the expressions evaluated until the scope is
+    /// restored.
+    pub fn bind(&mut self, name: &str, binding: Binding) {
+        self.scope.push((parameter_name(name).to_string(), binding));
+    }
+
+    /// Drop the names bound since [`scope_mark`](Self::scope_mark) returned
+    /// `mark`.
+    pub fn restore_scope(&mut self, mark: usize) {
+        self.scope.truncate(mark);
+    }
+
+    /// Evaluate `expr` to the value a LET name or LAMBDA parameter is bound
+    /// to: a lambda, a reference, or else a value.
+    pub fn bind_value(&mut self, expr: &Expr) -> Result<Binding> {
+        if let Some(lambda) = self.lambda_value(expr)? {
+            return Ok(Binding::Lambda(lambda));
+        }
+        let resolved = self.resolve_ref(expr)?;
+        if is_reference(&resolved) {
+            return Ok(Binding::Reference(resolved.into_owned()));
+        }
+        Ok(Binding::Value(self.eval_array(&resolved)?))
+    }
+
+    /// Return the lambda denoted by `expr`: a `LAMBDA(...)` call, a name
+    /// bound to a lambda, or a defined name whose definition is a LAMBDA.
+    /// Returns `None` for other expressions.
+    pub fn lambda_value(&mut self, expr: &Expr) -> Result<Option<Lambda>> {
+        match expr {
+            Expr::Paren(inner) => self.lambda_value(inner),
+            Expr::Function { args, .. } if is_lambda_literal(expr) => {
+                let Some((body, params)) = args.split_last() else {
+                    return Ok(None);
+                };
+                let mut names = Vec::with_capacity(params.len());
+                for param in params {
+                    match param {
+                        Expr::Name { name, sheet: None } => {
+                            names.push(parameter_name(name).to_string());
+                        }
+                        _ => return Ok(None),
+                    }
+                }
+                Ok(Some(Lambda {
+                    params: names,
+                    body: body.clone(),
+                    captured: self.scope.clone(),
+                }))
+            }
+            Expr::Name { name, sheet } => {
+                if sheet.is_none() {
+                    if let Some(binding) = self.binding(name) {
+                        return Ok(match binding {
+                            Binding::Lambda(lambda) => Some(lambda.clone()),
+                            _ => None,
+                        });
+                    }
+                }
+                Ok(self.defined_lambda(name, sheet.as_deref()))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Call `lambda` with its parameters bound to `args`. The body sees the
+    /// names bound where the lambda was created, not those of the caller.
+    /// A wrong number of arguments yields `#VALUE!`.
+    pub fn call_lambda(&mut self, lambda: &Lambda, args: Vec<Binding>) -> Result<ArrayValue> {
+        if args.len() != lambda.params.len() {
+            return Ok(ArrayValue::scalar(CellValue::Error("#VALUE!".to_string())));
+        }
+        let caller = std::mem::replace(&mut self.scope, lambda.captured.clone());
+        self.scope.extend(lambda.params.iter().cloned().zip(args));
+        let result = self.eval_array(&lambda.body);
+        self.scope = caller;
+        result
+    }
+
+    /// Resolve an expression that yields a reference to the cell, range,
+    /// or union it refers to, so that functions expecting a reference accept
+    /// it as well. This covers defined names, structured table references,
+    /// the reference operators (`:`, space, and `,`), and reference
+    /// functions such as OFFSET, INDIRECT, and INDEX. The result is an
+    /// [`Expr::CellRef`], [`Expr::Range`], [`Expr::Ref3D`], a union of
+    /// those, or an [`Expr::Error`]. Other expressions are returned
+    /// unchanged.
+    pub fn resolve_ref<'e>(&mut self, expr: &'e Expr) -> Result<Cow<'e, Expr>> {
+        let mut resolved = Cow::Borrowed(expr);
+        // Names may refer to other names; give up on chains that loop.
+        for _ in 0..MAX_EVAL_DEPTH {
+            let target = match resolved.as_ref() {
+                Expr::Name { name, sheet: None } if self.binding(name).is_some() => {
+                    match self.binding(name) {
+                        Some(Binding::Reference(reference)) => reference.clone(),
+                        _ => return Ok(resolved),
+                    }
+                }
+                Expr::Name { name, sheet } => name_definition(
+                    self.provider,
+                    name,
+                    sheet.as_deref(),
+                    self.provider.current_sheet(),
+                ),
+                Expr::StructuredRef(sref) => structured_ref_target(
+                    self.provider,
+                    sref,
+                    self.provider.current_sheet(),
+                    self.cell,
+                ),
+                other => {
+                    return Ok(match self.reference_target(other)? {
+                        Some(target) => Cow::Owned(target),
+                        None => resolved,
+                    });
+                }
+            };
+            resolved = Cow::Owned(target);
+        }
+        Ok(Cow::Owned(Expr::Error("#NAME?".to_string())))
+    }
+
+    /// Return the `(col, row)` of the formula cell being evaluated, if known.
+    pub fn current_cell(&self) -> Option<(u32, u32)> {
+        self.cell
+    }
+
     /// Evaluate a single argument expression at `index`, returning an error if
     /// the index is out of bounds.
     pub fn eval_arg(&mut self, args: &[Expr], index: usize) -> Result<CellValue> {
@@ -115,91 +1090,444 @@ impl<'a> Evaluator<'a> {
     pub fn collect_numbers(&mut self, args: &[Expr]) -> Result<Vec<f64>> {
         let mut nums = Vec::new();
         for arg in args {
-            match arg {
-                Expr::Range { start, end } => {
-                    let values = self.expand_range(start, end)?;
-                    for v in values {
+            let arg = self.resolve_ref(arg)?;
+            match arg.as_ref() {
+                Expr::Range { .. } | Expr::Ref3D { .. } | Expr::BinaryOp { .. }
+                    if is_reference(&arg) =>
+                {
+                    for v in self.reference_values(&arg)? {
+                        if let Ok(n) = coerce_to_number(&v) {
+                            nums.push(n);
+                        }
+                    }
+                }
+                _ if self.is_array_arg(&arg) => {
+                    let array = self.eval_array(&arg)?;
+                    for v in array.values {
                         if let Ok(n) = coerce_to_number(&v) {
                             nums.push(n);
                         }
                     }
                 }
-                _ => {
-                    let v = self.eval_expr(arg)?;
-                    nums.push(coerce_to_number(&v)?);
+                _ => {
+                    let v = self.eval_expr(&arg)?;
+                    nums.push(coerce_to_number(&v)?);
+                }
+            }
+        }
+        Ok(nums)
+    }
+
+    /// Flatten all arguments into a Vec of CellValues, expanding ranges.
+    pub fn flatten_args_to_values(&mut self, args: &[Expr]) -> Result<Vec<CellValue>> {
+        let mut values = Vec::new();
+        for arg in args {
+            let arg = self.resolve_ref(arg)?;
+            match arg.as_ref() {
+                Expr::Range { .. } | Expr::Ref3D { .. } | Expr::BinaryOp { .. }
+                    if is_reference(&arg) =>
+                {
+                    values.extend(self.reference_values(&arg)?);
+                }
+                _ if self.is_array_arg(&arg) => {
+                    values.extend(self.eval_array(&arg)?.values);
+                }
+                _ => {
+                    values.push(self.eval_expr(&arg)?);
+                }
+            }
+        }
+        Ok(values)
+    }
+
+    /// Expand a rectangular cell range into individual CellValues (row-major).
+    /// Whole-column and whole-row ranges are bounded by the used range.
+    pub fn expand_range(
+        &mut self,
+        start: &CellReference,
+        end: &CellReference,
+    ) -> Result<Vec<CellValue>> {
+        Ok(self.range_array(start, end)?.values)
+    }
+
+    /// Expand a rectangular cell range into an [`ArrayValue`].
+    /// Whole-column and whole-row ranges are bounded by the used range.
+    pub fn range_array(
+        &mut self,
+        start: &CellReference,
+        end: &CellReference,
+    ) -> Result<ArrayValue> {
+        let sheet = start
+            .sheet
+            .as_deref()
+            .unwrap_or_else(|| self.provider.current_sheet());
+        let bounds = range_bounds(self.provider, sheet, start, end)?;
+        self.bounds_array(&bounds)
+    }
+
+    /// Expand a 3D reference such as `Jan:Dec!B5` into the values of the
+    /// range on every sheet of the span, sheet by sheet. A span naming an
+    /// unknown sheet yields a single `#REF!`.
+    pub fn expand_3d(
+        &mut self,
+        first_sheet: &str,
+        last_sheet: &str,
+        start: &CellReference,
+        end: &CellReference,
+    ) -> Result<Vec<CellValue>> {
+        let Some(sheets) = sheet_span(self.provider, first_sheet, last_sheet) else {
+            return Ok(vec![CellValue::Error("#REF!".to_string())]);
+        };
+        let mut values = Vec::new();
+        for sheet in sheets {
+            let bounds = range_bounds(self.provider, &sheet, start, end)?;
+            values.extend(self.bounds_array(&bounds)?.values);
+        }
+        Ok(values)
+    }
+
+    /// Expand a resolved reference (see [`resolve_ref`](Self::resolve_ref))
+    /// into the values of all of its cells, area by area.
+    pub fn reference_values(&mut self, reference: &Expr) -> Result<Vec<CellValue>> {
+        match reference {
+            Expr::CellRef(cell) => Ok(vec![self.eval_cell_ref(cell)?]),
+            Expr::Range { start, end } => self.expand_range(start, end),
+            Expr::Ref3D {
+                first_sheet,
+                last_sheet,
+                start,
+                end,
+            } => self.expand_3d(first_sheet, last_sheet, start, end),
+            Expr::BinaryOp {
+                op: BinaryOperator::Union,
+                left,
+                right,
+            } => {
+                let mut values = self.reference_values(left)?;
+                values.extend(self.reference_values(right)?);
+                Ok(values)
+            }
+            other => Ok(vec![self.eval_expr(other)?]),
+        }
+    }
+
+    /// Return the current sheet name from the provider.
+    pub fn current_sheet(&self) -> &str {
+        self.provider.current_sheet()
+    }
+
+    /// Return the date system serial numbers are interpreted in.
+    pub fn date_system(&self) -> DateSystem {
+        self.provider.date_system()
+    }
+
+    /// Return the pivot table whose output area contains the given cell.
+    pub fn pivot_table_at(&self, sheet: &str, col: u32, row: u32) -> Option<PivotTableSource> {
+        self.provider.pivot_table_at(sheet, col, row)
+    }
+
+    // -- Private helpers --
+
+    /// Return the lambda a defined name is defined as, if any.
+    fn defined_lambda(&self, name: &str, sheet: Option<&str>) -> Option<Lambda> {
+        let definition = name_definition(self.provider, name, sheet, self.provider.current_sheet());
+        match definition {
+            Expr::Function { mut args, .. } if is_lambda_literal(&definition) => {
+                let body = args.pop()?;
+                let params = args
+                    .iter()
+                    .map(|param| match param {
+                        Expr::Name { name, sheet: None } => Some(parameter_name(name).to_string()),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Lambda {
+                    params,
+                    body,
+                    captured: Vec::new(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Return `true` if calling `name` calls a lambda: one bound by LET or
+    /// a LAMBDA parameter, or a defined name that is neither a registered
+    /// nor a built-in function.
+    fn is_user_function(&self, name: &str) -> bool {
+        if matches!(self.binding(name), Some(Binding::Lambda(_))) {
+            return true;
+        }
+        !self.functions.is_some_and(|f| f.contains(name))
+            && !functions::is_builtin_function(name)
+            && self.defined_lambda(name, None).is_some()
+    }
+
+    /// Call `name` if it is not a built-in function. Names bound by LET and
+    /// LAMBDA take precedence over registered functions, which take
+    /// precedence over built-in functions, which take precedence over
+    /// defined names holding a LAMBDA.
+    fn call_user_function(&mut self, name: &str, args: &[Expr]) -> Result<Option<ArrayValue>> {
+        let lambda = match self.binding(name) {
+            Some(Binding::Lambda(lambda)) => lambda.clone(),
+            _ => {
+                if let Some(func) = self.functions.and_then(|f| f.get(name)).cloned() {
+                    return Ok(Some(ArrayValue::scalar(func(args, self)?)));
+                }
+                if functions::is_builtin_function(name) {
+                    return Ok(None);
+                }
+                match self.defined_lambda(name, None) {
+                    Some(lambda) => lambda,
+                    None => return Ok(None),
+                }
+            }
+        };
+        self.call_with_args(&lambda, args).map(Some)
+    }
+
+    /// Bind each argument expression and call `lambda` with them.
+    fn call_with_args(&mut self, lambda: &Lambda, args: &[Expr]) -> Result<ArrayValue> {
+        let args = args
+            .iter()
+            .map(|arg| self.bind_value(arg))
+            .collect::<Result<Vec<_>>>()?;
+        self.call_lambda(lambda, args)
+    }
+
+    /// Evaluate a call of the function value `callee`, such as
+    /// `LAMBDA(x, x + 1)(2)`. A callee that is not a lambda yields
+    /// `#VALUE!`.
+    fn eval_call(&mut self, callee: &Expr, args: &[Expr]) -> Result<ArrayValue> {
+        match self.lambda_value(callee)? {
+            Some(lambda) => self.call_with_args(&lambda, args),
+            None => Ok(ArrayValue::scalar(CellValue::Error("#VALUE!".to_string()))),
+        }
+    }
+
+    fn eval_inner(&mut self, expr: &Expr) -> Result<CellValue> {
+        match expr {
+            Expr::Number(n) => Ok(CellValue::Number(*n)),
+            Expr::String(s) => Ok(CellValue::String(s.clone())),
+            Expr::Bool(b) => Ok(CellValue::Bool(*b)),
+            Expr::Error(e) => Ok(CellValue::Error(e.clone())),
+            // An omitted argument counts as 0, FALSE, or "" where it is used.
+            Expr::Missing => Ok(CellValue::Empty),
+            Expr::CellRef(cell_ref) => self.eval_cell_ref(cell_ref),
+            Expr::Range { start, end } => {
+                // When a range appears in a scalar context, return the first cell.
+                let vals = self.expand_range(start, end)?;
+                Ok(vals.into_iter().next().unwrap_or(CellValue::Empty))
+            }
+            // A 3D reference only makes sense as a function argument.
+            Expr::Ref3D { .. } => Ok(CellValue::Error("#VALUE!".to_string())),
+            Expr::Paren(inner) => self.eval_expr(inner),
+            Expr::Name { name, sheet: None } if self.binding(name).is_some() => {
+                match self.binding(name).cloned() {
+                    Some(Binding::Reference(reference)) => self.eval_expr(&reference),
+                    Some(Binding::Value(array)) => Ok(array.into_scalar()),
+                    // A lambda that is not called has no value.
+                    _ => Ok(CellValue::Error("#CALC!".to_string())),
+                }
+            }
+            Expr::Name { .. } | Expr::StructuredRef(_) | Expr::SpillRef(_) => {
+                let resolved = self.resolve_ref(expr)?;
+                self.eval_expr(&resolved)
+            }
+            // An array constant in a scalar context yields its first element.
+            Expr::Array(rows) => self.eval_expr(&rows[0][0]),
+            Expr::BinaryOp { op, .. } if op.is_reference() => {
+                let resolved = self.resolve_ref(expr)?;
+                match resolved.as_ref() {
+                    Expr::CellRef(_) | Expr::Range { .. } | Expr::Error(_) => {
+                        self.eval_expr(&resolved)
+                    }
+                    // A union has no single value.
+                    _ => Ok(CellValue::Error("#VALUE!".to_string())),
+                }
+            }
+            Expr::BinaryOp { op, left, right } => self.eval_binary(*op, left, right),
+            Expr::UnaryOp { op, operand } => self.eval_unary(*op, operand),
+            Expr::Function { name, args } => self.eval_function(name, args),
+            Expr::Call { callee, args } => Ok(self.eval_call(callee, args)?.into_scalar()),
+        }
+    }
+
+    fn eval_array_inner(&mut self, expr: &Expr) -> Result<ArrayValue> {
+        match expr {
+            Expr::Range { start, end } => self.range_array(start, end),
+            Expr::Paren(inner) => self.eval_array(inner),
+            Expr::Name { name, sheet: None } if self.binding(name).is_some() => {
+                match self.binding(name).cloned() {
+                    Some(Binding::Reference(reference)) => self.eval_array(&reference),
+                    Some(Binding::Value(array)) => Ok(array),
+                    _ => Ok(ArrayValue::scalar(CellValue::Error("#CALC!".to_string()))),
+                }
+            }
+            Expr::Name { .. } | Expr::StructuredRef(_) | Expr::SpillRef(_) => {
+                let resolved = self.resolve_ref(expr)?;
+                self.eval_array(&resolved)
+            }
+            Expr::Array(rows) => {
+                let mut values = Vec::with_capacity(rows.len() * rows[0].len());
+                for element in rows.iter().flatten() {
+                    values.push(self.eval_expr(element)?);
+                }
+                Ok(ArrayValue::new(rows.len(), rows[0].len(), values))
+            }
+            Expr::BinaryOp { op, .. } if op.is_reference() => {
+                let resolved = self.resolve_ref(expr)?;
+                match resolved.as_ref() {
+                    Expr::CellRef(_) | Expr::Range { .. } | Expr::Error(_) => {
+                        self.eval_array(&resolved)
+                    }
+                    _ => Ok(ArrayValue::scalar(CellValue::Error("#VALUE!".to_string()))),
+                }
+            }
+            Expr::Function { name, args } => {
+                if let Some(result) = self.call_user_function(name, args)? {
+                    return Ok(result);
+                }
+                if let Some(func) = functions::lookup_reference_function(name) {
+                    if let Some(reference) = func(args, self)? {
+                        return self.eval_array(&reference);
+                    }
+                }
+                match functions::lookup_array_function(name) {
+                    Some(func) => func(args, self),
+                    None => Ok(ArrayValue::scalar(self.eval_function(name, args)?)),
+                }
+            }
+            Expr::BinaryOp { op, left, right } => {
+                let lhs = self.eval_array(left)?;
+                let rhs = self.eval_array(right)?;
+                let rows = lhs.rows.max(rhs.rows);
+                let cols = lhs.cols.max(rhs.cols);
+                let mut values = Vec::with_capacity(rows * cols);
+                for r in 0..rows {
+                    for c in 0..cols {
+                        values.push(apply_binary(
+                            *op,
+                            lhs.broadcast_get(r, c),
+                            rhs.broadcast_get(r, c),
+                        )?);
+                    }
                 }
+                Ok(ArrayValue::new(rows, cols, values))
             }
+            Expr::Call { callee, args } => self.eval_call(callee, args),
+            Expr::UnaryOp { op, operand } => {
+                let array = self.eval_array(operand)?;
+                let values = array
+                    .values
+                    .into_iter()
+                    .map(|v| apply_unary(*op, v))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(ArrayValue::new(array.rows, array.cols, values))
+            }
+            _ => Ok(ArrayValue::scalar(self.eval_expr(expr)?)),
         }
-        Ok(nums)
     }
 
-    /// Flatten all arguments into a Vec of CellValues, expanding ranges.
-    pub fn flatten_args_to_values(&mut self, args: &[Expr]) -> Result<Vec<CellValue>> {
-        let mut values = Vec::new();
-        for arg in args {
-            match arg {
-                Expr::Range { start, end } => {
-                    values.extend(self.expand_range(start, end)?);
-                }
-                _ => {
-                    values.push(self.eval_expr(arg)?);
-                }
+    /// Compute the reference denoted by a parenthesized reference, a
+    /// reference operator, or a reference function call. Returns `None`
+    /// for expressions that do not yield references.
+    fn reference_target(&mut self, expr: &Expr) -> Result<Option<Expr>> {
+        match expr {
+            Expr::Paren(inner) => {
+                let resolved = self.resolve_ref(inner)?;
+                Ok(is_reference(&resolved).then(|| resolved.into_owned()))
             }
+            Expr::BinaryOp { op, left, right } if op.is_reference() => {
+                self.reference_op(*op, left, right).map(Some)
+            }
+            Expr::Function { name, args } => match functions::lookup_reference_function(name) {
+                Some(func) => func(args, self),
+                None => Ok(None),
+            },
+            Expr::SpillRef(anchor) => Ok(Some(self.spill_target(anchor))),
+            _ => Ok(None),
         }
-        Ok(values)
     }
 
-    /// Expand a rectangular cell range into individual CellValues (row-major).
-    pub fn expand_range(
-        &mut self,
-        start: &CellReference,
-        end: &CellReference,
-    ) -> Result<Vec<CellValue>> {
-        let sheet = start
+    /// Return the spill range of the dynamic array formula anchored at
+    /// `anchor` as a reference, or `#REF!` if the cell holds no spilled
+    /// result.
+    pub fn spill_target(&self, anchor: &CellReference) -> Expr {
+        let sheet = anchor
             .sheet
             .as_deref()
-            .unwrap_or_else(|| self.provider.current_sheet());
-        let start_col = column_name_to_number(&start.col)?;
-        let end_col = column_name_to_number(&end.col)?;
-        let start_row = start.row;
-        let end_row = end.row;
-        let min_col = start_col.min(end_col);
-        let max_col = start_col.max(end_col);
-        let min_row = start_row.min(end_row);
-        let max_row = start_row.max(end_row);
-        let mut values = Vec::new();
-        for r in min_row..=max_row {
-            for c in min_col..=max_col {
-                values.push(self.resolve_cell(sheet, c, r)?);
+            .unwrap_or(self.provider.current_sheet());
+        let spill = column_name_to_number(&anchor.col)
+            .ok()
+            .and_then(|col| Some((col, self.provider.spill_range(sheet, col, anchor.row)?)));
+        match spill {
+            Some((min_col, (max_col, max_row))) => RefArea {
+                sheet: sheet.to_string(),
+                min_col,
+                min_row: anchor.row,
+                max_col,
+                max_row,
             }
+            .to_expr(),
+            None => Expr::Error("#REF!".to_string()),
         }
-        Ok(values)
     }
 
-    /// Return the current sheet name from the provider.
-    pub fn current_sheet(&self) -> &str {
-        self.provider.current_sheet()
+    /// Apply a reference operator. Operands that are not references yield
+    /// `#VALUE!`; disjoint intersections yield `#NULL!`.
+    fn reference_op(&mut self, op: BinaryOperator, left: &Expr, right: &Expr) -> Result<Expr> {
+        let lhs = self.resolve_ref(left)?.into_owned();
+        let rhs = self.resolve_ref(right)?.into_owned();
+        for operand in [&lhs, &rhs] {
+            if let Expr::Error(_) = operand {
+                return Ok(operand.clone());
+            }
+            if !is_reference(operand) {
+                return Ok(Expr::Error("#VALUE!".to_string()));
+            }
+        }
+        if op == BinaryOperator::Union {
+            return Ok(Expr::BinaryOp {
+                op,
+                left: Box::new(lhs),
+                right: Box::new(rhs),
+            });
+        }
+        let sheet = self.provider.current_sheet();
+        let (Some(a), Some(b)) = (
+            RefArea::from_expr(&lhs, sheet),
+            RefArea::from_expr(&rhs, sheet),
+        ) else {
+            return Ok(Expr::Error("#VALUE!".to_string()));
+        };
+        Ok(match op {
+            BinaryOperator::Intersect => match a.intersect(&b) {
+                Some(area) => area.to_expr(),
+                None => Expr::Error("#NULL!".to_string()),
+            },
+            _ if !a.sheet.eq_ignore_ascii_case(&b.sheet) => Expr::Error("#VALUE!".to_string()),
+            _ => RefArea {
+                sheet: a.sheet,
+                min_col: a.min_col.min(b.min_col),
+                min_row: a.min_row.min(b.min_row),
+                max_col: a.max_col.max(b.max_col),
+                max_row: a.max_row.max(b.max_row),
+            }
+            .to_expr(),
+        })
     }
 
-    // -- Private helpers --
-
-    fn eval_inner(&mut self, expr: &Expr) -> Result<CellValue> {
-        match expr {
-            Expr::Number(n) => Ok(CellValue::Number(*n)),
-            Expr::String(s) => Ok(CellValue::String(s.clone())),
-            Expr::Bool(b) => Ok(CellValue::Bool(*b)),
-            Expr::Error(e) => Ok(CellValue::Error(e.clone())),
-            Expr::CellRef(cell_ref) => self.eval_cell_ref(cell_ref),
-            Expr::Range { start, end } => {
-                // When a range appears in a scalar context, return the first cell.
-                let vals = self.expand_range(start, end)?;
-                Ok(vals.into_iter().next().unwrap_or(CellValue::Empty))
+    fn bounds_array(&mut self, bounds: &RefArea) -> Result<ArrayValue> {
+        let rows = (bounds.max_row - bounds.min_row + 1) as usize;
+        let cols = (bounds.max_col - bounds.min_col + 1) as usize;
+        let mut values = Vec::with_capacity(rows * cols);
+        for r in bounds.min_row..=bounds.max_row {
+            for c in bounds.min_col..=bounds.max_col {
+                values.push(self.resolve_cell(&bounds.sheet, c, r)?);
             }
-            Expr::Paren(inner) => self.eval_expr(inner),
-            Expr::BinaryOp { op, left, right } => self.eval_binary(*op, left, right),
-            Expr::UnaryOp { op, operand } => self.eval_unary(*op, operand),
-            Expr::Function { name, args } => self.eval_function(name, args),
         }
+        Ok(ArrayValue::new(rows, cols, values))
     }
 
     fn eval_cell_ref(&mut self, cell_ref: &CellReference) -> Result<CellValue> {
@@ -227,7 +1555,9 @@ impl<'a> Evaluator<'a> {
             CellValue::Formula { ref expr, .. } => {
                 self.eval_stack.insert(key.clone());
                 let parsed = crate::formula::parser::parse_formula(expr)?;
+                let outer_cell = self.cell.replace((col, row));
                 let result = self.eval_expr(&parsed);
+                self.cell = outer_cell;
                 self.eval_stack.remove(&key);
                 result
             }
@@ -238,82 +1568,26 @@ impl<'a> Evaluator<'a> {
     fn eval_binary(&mut self, op: BinaryOperator, left: &Expr, right: &Expr) -> Result<CellValue> {
         let lhs = self.eval_expr(left)?;
         let rhs = self.eval_expr(right)?;
-
-        // Propagate errors.
-        if let CellValue::Error(ref e) = lhs {
-            return Ok(CellValue::Error(e.clone()));
-        }
-        if let CellValue::Error(ref e) = rhs {
-            return Ok(CellValue::Error(e.clone()));
-        }
-
-        match op {
-            BinaryOperator::Concat => {
-                let ls = coerce_to_string(&lhs);
-                let rs = coerce_to_string(&rhs);
-                Ok(CellValue::String(format!("{ls}{rs}")))
-            }
-            BinaryOperator::Add
-            | BinaryOperator::Sub
-            | BinaryOperator::Mul
-            | BinaryOperator::Div
-            | BinaryOperator::Pow => {
-                let ln = coerce_to_number(&lhs)?;
-                let rn = coerce_to_number(&rhs)?;
-                let result = match op {
-                    BinaryOperator::Add => ln + rn,
-                    BinaryOperator::Sub => ln - rn,
-                    BinaryOperator::Mul => ln * rn,
-                    BinaryOperator::Div => {
-                        if rn == 0.0 {
-                            return Ok(CellValue::Error("#DIV/0!".to_string()));
-                        }
-                        ln / rn
-                    }
-                    BinaryOperator::Pow => ln.powf(rn),
-                    _ => unreachable!(),
-                };
-                Ok(CellValue::Number(result))
-            }
-            BinaryOperator::Eq
-            | BinaryOperator::Ne
-            | BinaryOperator::Lt
-            | BinaryOperator::Le
-            | BinaryOperator::Gt
-            | BinaryOperator::Ge => {
-                let ord = compare_values(&lhs, &rhs);
-                let result = match op {
-                    BinaryOperator::Eq => ord == std::cmp::Ordering::Equal,
-                    BinaryOperator::Ne => ord != std::cmp::Ordering::Equal,
-                    BinaryOperator::Lt => ord == std::cmp::Ordering::Less,
-                    BinaryOperator::Le => {
-                        ord == std::cmp::Ordering::Less || ord == std::cmp::Ordering::Equal
-                    }
-                    BinaryOperator::Gt => ord == std::cmp::Ordering::Greater,
-                    BinaryOperator::Ge => {
-                        ord == std::cmp::Ordering::Greater || ord == std::cmp::Ordering::Equal
-                    }
-                    _ => unreachable!(),
-                };
-                Ok(CellValue::Bool(result))
-            }
-        }
+        apply_binary(op, lhs, rhs)
     }
 
     fn eval_unary(&mut self, op: UnaryOperator, operand: &Expr) -> Result<CellValue> {
         let val = self.eval_expr(operand)?;
-        if let CellValue::Error(ref e) = val {
-            return Ok(CellValue::Error(e.clone()));
-        }
-        let n = coerce_to_number(&val)?;
-        match op {
-            UnaryOperator::Neg => Ok(CellValue::Number(-n)),
-            UnaryOperator::Pos => Ok(CellValue::Number(n)),
-            UnaryOperator::Percent => Ok(CellValue::Number(n / 100.0)),
-        }
+        apply_unary(op, val)
     }
 
     fn eval_function(&mut self, name: &str, args: &[Expr]) -> Result<CellValue> {
+        if let Some(result) = self.call_user_function(name, args)? {
+            return Ok(result.into_scalar());
+        }
+        if let Some(func) = functions::lookup_reference_function(name) {
+            if let Some(reference) = func(args, self)? {
+                return self.eval_expr(&reference);
+            }
+        }
+        if let Some(func) = functions::lookup_array_function(name) {
+            return Ok(func(args, self)?.into_scalar());
+        }
         let func = functions::lookup_function(name).ok_or_else(|| Error::UnknownFunction {
             name: name.to_string(),
         })?;
@@ -321,6 +1595,93 @@ impl<'a> Evaluator<'a> {
     }
 }
 
+/// Apply a binary operator to two already-evaluated operands.
+fn apply_binary(op: BinaryOperator, lhs: CellValue, rhs: CellValue) -> Result<CellValue> {
+    // Propagate errors.
+    if let CellValue::Error(ref e) = lhs {
+        return Ok(CellValue::Error(e.clone()));
+    }
+    if let CellValue::Error(ref e) = rhs {
+        return Ok(CellValue::Error(e.clone()));
+    }
+
+    match op {
+        // Reference operators need references, not values.
+        BinaryOperator::Range | BinaryOperator::Intersect | BinaryOperator::Union => {
+            Ok(CellValue::Error("#VALUE!".to_string()))
+        }
+        BinaryOperator::Concat => {
+            let ls = coerce_to_string(&lhs);
+            let rs = coerce_to_string(&rhs);
+            Ok(CellValue::String(format!("{ls}{rs}")))
+        }
+        BinaryOperator::Add
+        | BinaryOperator::Sub
+        | BinaryOperator::Mul
+        | BinaryOperator::Div
+        | BinaryOperator::Pow => {
+            let ln = coerce_to_number(&lhs)?;
+            let rn = coerce_to_number(&rhs)?;
+            let result = match op {
+                BinaryOperator::Add => ln + rn,
+                BinaryOperator::Sub => ln - rn,
+                BinaryOperator::Mul => ln * rn,
+                BinaryOperator::Div => {
+                    if rn == 0.0 {
+                        return Ok(CellValue::Error("#DIV/0!".to_string()));
+                    }
+                    ln / rn
+                }
+                BinaryOperator::Pow if ln == 0.0 && rn < 0.0 => {
+                    return Ok(CellValue::Error("#DIV/0!".to_string()));
+                }
+                BinaryOperator::Pow => ln.powf(rn),
+                _ => unreachable!(),
+            };
+            // Results that overflow, or powers with no real value, are #NUM!.
+            if !result.is_finite() {
+                return Ok(CellValue::Error("#NUM!".to_string()));
+            }
+            Ok(CellValue::Number(result))
+        }
+        BinaryOperator::Eq
+        | BinaryOperator::Ne
+        | BinaryOperator::Lt
+        | BinaryOperator::Le
+        | BinaryOperator::Gt
+        | BinaryOperator::Ge => {
+            let ord = compare_values(&lhs, &rhs);
+            let result = match op {
+                BinaryOperator::Eq => ord == std::cmp::Ordering::Equal,
+                BinaryOperator::Ne => ord != std::cmp::Ordering::Equal,
+                BinaryOperator::Lt => ord == std::cmp::Ordering::Less,
+                BinaryOperator::Le => {
+                    ord == std::cmp::Ordering::Less || ord == std::cmp::Ordering::Equal
+                }
+                BinaryOperator::Gt => ord == std::cmp::Ordering::Greater,
+                BinaryOperator::Ge => {
+                    ord == std::cmp::Ordering::Greater || ord == std::cmp::Ordering::Equal
+                }
+                _ => unreachable!(),
+            };
+            Ok(CellValue::Bool(result))
+        }
+    }
+}
+
+/// Apply a unary operator to an already-evaluated operand.
+fn apply_unary(op: UnaryOperator, val: CellValue) -> Result<CellValue> {
+    if let CellValue::Error(ref e) = val {
+        return Ok(CellValue::Error(e.clone()));
+    }
+    let n = coerce_to_number(&val)?;
+    match op {
+        UnaryOperator::Neg => Ok(CellValue::Number(-n)),
+        UnaryOperator::Pos => Ok(CellValue::Number(n)),
+        UnaryOperator::Percent => Ok(CellValue::Number(n / 100.0)),
+    }
+}
+
 // -- Type coercion helpers (pub for use by function implementations) --
 
 /// Coerce a CellValue to f64. Booleans become 0/1, empty becomes 0,
@@ -464,30 +1825,131 @@ pub struct CellCoord {
     pub row: u32,
 }
 
-/// Build a dependency graph from formula cells.
-/// Returns a map from each formula cell to the cells it depends on.
+/// Dependencies between formula cells, as built by
+/// [`build_dependency_graph`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DependencyGraph {
+    /// The cells each formula cell reads.
+    pub deps: HashMap<CellCoord, Vec<CellCoord>>,
+    /// The formula cells that read each cell, the reverse of `deps`.
+    pub dependents: HashMap<CellCoord, Vec<CellCoord>>,
+    /// Formula cells that call a volatile function (see
+    /// [`functions::is_volatile_function`] and
+    /// [`FunctionRegistry::register_volatile`]), directly or through a
+    /// defined name. Their precedents are not fully known until they are evaluated,
+    /// so they must be recalculated on every calculation.
+    pub volatile: HashSet<CellCoord>,
+}
+
+/// Build a dependency graph from formula cells: the cells each formula
+/// depends on and which formulas are volatile. Defined names are looked up
+/// through `provider` and contribute the cells they refer to.
 pub fn build_dependency_graph(
     formula_cells: &[(CellCoord, String)],
-) -> Result<HashMap<CellCoord, Vec<CellCoord>>> {
-    let mut deps: HashMap<CellCoord, Vec<CellCoord>> = HashMap::new();
+    provider: &dyn CellDataProvider,
+) -> Result<DependencyGraph> {
+    let mut graph = DependencyGraph::default();
     for (coord, formula_str) in formula_cells {
         let expr = crate::formula::parser::parse_formula(formula_str)?;
-        let refs = extract_cell_refs(&expr, &coord.sheet);
-        deps.insert(coord.clone(), refs);
+        graph.add_formula(coord.clone(), &expr, provider);
+    }
+    Ok(graph)
+}
+
+impl DependencyGraph {
+    /// Add the formula cell `coord` with the parsed formula `expr`,
+    /// replacing any previous entry for the cell.
+    pub fn add_formula(&mut self, coord: CellCoord, expr: &Expr, provider: &dyn CellDataProvider) {
+        self.remove_formula(&coord);
+        let (refs, volatile) =
+            extract_cell_refs(expr, &coord.sheet, Some((coord.col, coord.row)), provider);
+        if volatile {
+            self.volatile.insert(coord.clone());
+        }
+        for dep in &refs {
+            self.dependents
+                .entry(dep.clone())
+                .or_default()
+                .push(coord.clone());
+        }
+        self.deps.insert(coord, refs);
+    }
+
+    /// Record that the formula cell `cell` also reads `dep`.
+    pub fn add_dependency(&mut self, cell: &CellCoord, dep: CellCoord) {
+        self.dependents
+            .entry(dep.clone())
+            .or_default()
+            .push(cell.clone());
+        self.deps.entry(cell.clone()).or_default().push(dep);
+    }
+
+    /// Remove the formula cell `coord` from the graph.
+    pub fn remove_formula(&mut self, coord: &CellCoord) {
+        self.volatile.remove(coord);
+        let Some(refs) = self.deps.remove(coord) else {
+            return;
+        };
+        for dep in refs {
+            if let Some(cells) = self.dependents.get_mut(&dep) {
+                cells.retain(|c| c != coord);
+                if cells.is_empty() {
+                    self.dependents.remove(&dep);
+                }
+            }
+        }
+    }
+
+    /// Return the formula cells to recalculate after the cells in `changed`
+    /// were edited: the formulas among them, every formula that reads them
+    /// directly or indirectly, and the volatile formulas and their
+    /// dependents.
+    pub fn affected_cells(&self, changed: &[CellCoord]) -> HashSet<CellCoord> {
+        let mut affected = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut stack: Vec<&CellCoord> = changed.iter().chain(&self.volatile).collect();
+        while let Some(cell) = stack.pop() {
+            if !visited.insert(cell) {
+                continue;
+            }
+            if self.deps.contains_key(cell) {
+                affected.insert(cell.clone());
+            }
+            if let Some(cells) = self.dependents.get(cell) {
+                stack.extend(cells);
+            }
+        }
+        affected
     }
-    Ok(deps)
 }
 
-/// Extract all cell references from a parsed expression.
-fn extract_cell_refs(expr: &Expr, current_sheet: &str) -> Vec<CellCoord> {
+/// Extract all cell references from a parsed expression, and whether it
+/// calls a volatile function.
+fn extract_cell_refs(
+    expr: &Expr,
+    current_sheet: &str,
+    current_cell: Option<(u32, u32)>,
+    provider: &dyn CellDataProvider,
+) -> (Vec<CellCoord>, bool) {
     let mut refs = Vec::new();
-    collect_refs(expr, current_sheet, &mut refs);
-    refs
+    let mut volatile = false;
+    let origin = (current_sheet, current_cell);
+    collect_refs(expr, origin, provider, 0, &mut refs, &mut volatile);
+    (refs, volatile)
 }
 
-fn collect_refs(expr: &Expr, current_sheet: &str, refs: &mut Vec<CellCoord>) {
+fn collect_refs(
+    expr: &Expr,
+    origin: (&str, Option<(u32, u32)>),
+    provider: &dyn CellDataProvider,
+    depth: usize,
+    refs: &mut Vec<CellCoord>,
+    volatile: &mut bool,
+) {
+    let current_sheet = origin.0;
     match expr {
-        Expr::CellRef(cell_ref) => {
+        // A spill reference depends on its anchor, which writes the range.
+        Expr::CellRef(cell_ref) | Expr::SpillRef(cell_ref) => {
             let sheet = cell_ref
                 .sheet
                 .as_deref()
@@ -502,45 +1964,82 @@ fn collect_refs(expr: &Expr, current_sheet: &str, refs: &mut Vec<CellCoord>) {
             }
         }
         Expr::Range { start, end } => {
-            let sheet = start.sheet.as_deref().unwrap_or(current_sheet).to_string();
-            if let (Ok(start_col), Ok(end_col)) = (
-                column_name_to_number(&start.col),
-                column_name_to_number(&end.col),
-            ) {
-                let min_col = start_col.min(end_col);
-                let max_col = start_col.max(end_col);
-                let min_row = start.row.min(end.row);
-                let max_row = start.row.max(end.row);
-                for r in min_row..=max_row {
-                    for c in min_col..=max_col {
-                        refs.push(CellCoord {
-                            sheet: sheet.clone(),
-                            col: c,
-                            row: r,
-                        });
+            let sheet = start.sheet.as_deref().unwrap_or(current_sheet);
+            if let Ok(bounds) = range_bounds(provider, sheet, start, end) {
+                push_range_refs(&bounds, refs);
+            }
+        }
+        Expr::Ref3D {
+            first_sheet,
+            last_sheet,
+            start,
+            end,
+        } => {
+            for sheet in sheet_span(provider, first_sheet, last_sheet).unwrap_or_default() {
+                if let Ok(bounds) = range_bounds(provider, &sheet, start, end) {
+                    push_range_refs(&bounds, refs);
+                }
+            }
+        }
+        Expr::Function { name, args } => {
+            let registered = provider.functions().and_then(|f| f.is_volatile(name));
+            *volatile |= registered.unwrap_or_else(|| functions::is_volatile_function(name));
+            // A call of a named lambda depends on the cells its body reads.
+            if registered.is_none()
+                && !functions::is_builtin_function(name)
+                && depth < MAX_EVAL_DEPTH
+            {
+                let definition = name_definition(provider, name, None, current_sheet);
+                if let Expr::Function { args: lambda, .. } = &definition {
+                    if let Some(body) = lambda.last().filter(|_| is_lambda_literal(&definition)) {
+                        collect_refs(body, origin, provider, depth + 1, refs, volatile);
                     }
                 }
             }
+            for arg in args {
+                collect_refs(arg, origin, provider, depth, refs, volatile);
+            }
         }
-        Expr::Function { args, .. } => {
+        Expr::Call { callee, args } => {
+            collect_refs(callee, origin, provider, depth, refs, volatile);
             for arg in args {
-                collect_refs(arg, current_sheet, refs);
+                collect_refs(arg, origin, provider, depth, refs, volatile);
             }
         }
         Expr::BinaryOp { left, right, .. } => {
-            collect_refs(left, current_sheet, refs);
-            collect_refs(right, current_sheet, refs);
+            collect_refs(left, origin, provider, depth, refs, volatile);
+            collect_refs(right, origin, provider, depth, refs, volatile);
         }
         Expr::UnaryOp { operand, .. } => {
-            collect_refs(operand, current_sheet, refs);
+            collect_refs(operand, origin, provider, depth, refs, volatile);
         }
         Expr::Paren(inner) => {
-            collect_refs(inner, current_sheet, refs);
+            collect_refs(inner, origin, provider, depth, refs, volatile);
+        }
+        Expr::Name { name, sheet } if depth < MAX_EVAL_DEPTH => {
+            let definition = name_definition(provider, name, sheet.as_deref(), current_sheet);
+            collect_refs(&definition, origin, provider, depth + 1, refs, volatile);
+        }
+        Expr::StructuredRef(sref) => {
+            let target = structured_ref_target(provider, sref, current_sheet, origin.1);
+            collect_refs(&target, origin, provider, depth, refs, volatile);
         }
         _ => {}
     }
 }
 
+fn push_range_refs(bounds: &RefArea, refs: &mut Vec<CellCoord>) {
+    for r in bounds.min_row..=bounds.max_row {
+        for c in bounds.min_col..=bounds.max_col {
+            refs.push(CellCoord {
+                sheet: bounds.sheet.clone(),
+                col: c,
+                row: r,
+            });
+        }
+    }
+}
+
 /// Topological sort of formula cells based on dependencies.
 /// Returns cells in evaluation order (dependencies first).
 /// Detects cycles and returns an error if found.
@@ -606,6 +2105,86 @@ pub fn topological_sort(
     Ok(sorted)
 }
 
+/// Group formula cells into the strongly connected components of their
+/// dependency graph, in evaluation order (dependencies first). A component
+/// with more than one cell, or a single cell that reads itself, is a
+/// circular reference. Cells within a component are ordered by sheet, row
+/// and column.
+pub fn strongly_connected_components(
+    formula_cells: &[CellCoord],
+    deps: &HashMap<CellCoord, Vec<CellCoord>>,
+) -> Vec<Vec<CellCoord>> {
+    let mut cells: Vec<&CellCoord> = formula_cells.iter().collect();
+    cells.sort_by(|a, b| (&a.sheet, a.row, a.col).cmp(&(&b.sheet, b.row, b.col)));
+    cells.dedup();
+    let position: HashMap<&CellCoord, usize> =
+        cells.iter().enumerate().map(|(i, c)| (*c, i)).collect();
+    let edges: Vec<Vec<usize>> = cells
+        .iter()
+        .map(|cell| {
+            deps.get(*cell)
+                .into_iter()
+                .flatten()
+                .filter_map(|dep| position.get(dep).copied())
+                .collect()
+        })
+        .collect();
+
+    // Tarjan's algorithm with an explicit stack, since dependency chains
+    // can be far deeper than the call stack allows.
+    const UNVISITED: usize = usize::MAX;
+    let mut index = vec![UNVISITED; cells.len()];
+    let mut low = vec![0; cells.len()];
+    let mut on_stack = vec![false; cells.len()];
+    let mut stack = Vec::new();
+    let mut next_index = 0;
+    let mut components = Vec::new();
+    for root in 0..cells.len() {
+        if index[root] != UNVISITED {
+            continue;
+        }
+        let mut calls = vec![(root, 0)];
+        index[root] = next_index;
+        low[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+        while let Some(&(v, edge)) = calls.last() {
+            if let Some(&w) = edges[v].get(edge) {
+                calls.last_mut().unwrap().1 += 1;
+                if index[w] == UNVISITED {
+                    index[w] = next_index;
+                    low[w] = next_index;
+                    next_index += 1;
+                    stack.push(w);
+                    on_stack[w] = true;
+                    calls.push((w, 0));
+                } else if on_stack[w] {
+                    low[v] = low[v].min(index[w]);
+                }
+                continue;
+            }
+            calls.pop();
+            if let Some(&(parent, _)) = calls.last() {
+                low[parent] = low[parent].min(low[v]);
+            }
+            if low[v] == index[v] {
+                let mut component = Vec::new();
+                while let Some(w) = stack.pop() {
+                    on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                component.sort_unstable();
+                components.push(component.into_iter().map(|i| cells[i].clone()).collect());
+            }
+        }
+    }
+    components
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1169,6 +2748,258 @@ mod tests {
         );
     }
 
+    #[test]
+    fn eval_array_broadcasts_row_and_column() {
+        let snap = make_snapshot();
+        let expr = parse_formula("SEQUENCE(1,3)*10+SEQUENCE(2)").unwrap();
+        let result = Evaluator::new(&snap).eval_array(&expr).unwrap();
+        assert_eq!((result.rows, result.cols), (2, 3));
+        assert_eq!(result.get(0, 2), &CellValue::Number(31.0));
+        assert_eq!(result.get(1, 0), &CellValue::Number(12.0));
+        assert!(is_array_expr(&expr));
+        assert!(!is_array_expr(&parse_formula("A1:A3*2").unwrap()));
+    }
+
+    #[test]
+    fn eval_array_formula_expands_range_operands() {
+        let mut snap = make_snapshot();
+        for row in 1..=3 {
+            snap.set_cell("Sheet1", 1, row, CellValue::Number(row as f64));
+            snap.set_cell("Sheet1", 2, row, CellValue::Number(10.0));
+        }
+        let expr = parse_formula("SUM(A1:A3*B1:B3)").unwrap();
+        let result = Evaluator::new(&snap).eval_array_formula(&expr).unwrap();
+        assert_eq!(result.into_scalar(), CellValue::Number(60.0));
+        // Outside an array formula the product uses the first cells only.
+        assert_eq!(evaluate(&expr, &snap).unwrap(), CellValue::Number(10.0));
+    }
+
+    #[test]
+    fn eval_defined_names() {
+        let mut snap = make_snapshot();
+        for row in 1..=3 {
+            snap.set_cell("Sheet1", 1, row, CellValue::Number(row as f64));
+        }
+        snap.set_cell("Sheet2", 2, 1, CellValue::Number(0.5));
+        snap.set_defined_name("Sales_2024", None, "Sheet1!$A$1:$A$3");
+        snap.set_defined_name("TaxRate", None, "0.25");
+        snap.set_defined_name("TaxRate", Some("Sheet2"), "Sheet2!$B$1");
+        snap.set_defined_name("Alias", None, "sales_2024");
+
+        let eval = |formula: &str, snap: &CellSnapshot| {
+            evaluate(&parse_formula(formula).unwrap(), snap).unwrap()
+        };
+        assert_eq!(
+            eval("SUM(Sales_2024)*TaxRate", &snap),
+            CellValue::Number(1.5)
+        );
+        assert_eq!(eval("ROWS(Alias)", &snap), CellValue::Number(3.0));
+        assert_eq!(eval("Sheet2!TaxRate", &snap), CellValue::Number(0.5));
+        snap.set_current_sheet("Sheet2");
+        assert_eq!(eval("TaxRate", &snap), CellValue::Number(0.5));
+        assert_eq!(
+            eval("Missing+1", &snap),
+            CellValue::Error("#NAME?".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_whole_column_row_and_3d_refs() {
+        let mut snap = make_snapshot();
+        snap.set_sheet_names(vec!["Jan".into(), "Feb".into(), "Mar".into()]);
+        for (i, sheet) in ["Jan", "Feb", "Mar"].iter().enumerate() {
+            snap.set_cell(sheet, 2, 5, CellValue::Number((i + 1) as f64));
+        }
+        snap.set_cell("Jan", 1, 3, CellValue::Number(10.0));
+        snap.set_cell("Jan", 4, 5, CellValue::Number(100.0));
+
+        let eval = |formula: &str, snap: &CellSnapshot| {
+            evaluate(&parse_formula(formula).unwrap(), snap).unwrap()
+        };
+        assert_eq!(eval("SUM(Jan:Mar!B5)", &snap), CellValue::Number(6.0));
+        assert_eq!(eval("SUM(Mar:Feb!B5)", &snap), CellValue::Number(5.0));
+        assert_eq!(eval("COUNT(Jan:Mar!A1:B5)", &snap), CellValue::Number(4.0));
+        assert_eq!(eval("SUM(Jan!A:A)", &snap), CellValue::Number(10.0));
+        assert_eq!(eval("SUM(Jan!5:5)", &snap), CellValue::Number(101.0));
+        assert_eq!(eval("SUM(Jan:Mar!B:B)", &snap), CellValue::Number(6.0));
+        assert_eq!(eval("ROWS(A:A)", &snap), CellValue::Number(1_048_576.0));
+        assert_eq!(eval("SUM(Jan:Apr!B5)", &snap), CellValue::Number(0.0));
+        assert_eq!(
+            eval("Jan:Mar!B5", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_reference_operators() {
+        let mut snap = make_snapshot();
+        for row in 1..=4 {
+            for col in 1..=4 {
+                snap.set_cell("Sheet1", col, row, CellValue::Number((row * col) as f64));
+            }
+        }
+        let eval = |formula: &str, snap: &CellSnapshot| {
+            evaluate(&parse_formula(formula).unwrap(), snap).unwrap()
+        };
+        // B2:D2 B1:B4 meet at B2.
+        assert_eq!(eval("B2:D2 B1:B4", &snap), CellValue::Number(4.0));
+        assert_eq!(eval("SUM(A1:C3 B2:D4)", &snap), CellValue::Number(25.0));
+        assert_eq!(
+            eval("A1:A2 C1:C2", &snap),
+            CellValue::Error("#NULL!".to_string())
+        );
+        assert_eq!(eval("SUM((A1,B2,C3:C4))", &snap), CellValue::Number(26.0));
+        assert_eq!(eval("COUNT((A1:A4,D1))", &snap), CellValue::Number(5.0));
+        assert_eq!(
+            eval("(A1,B2)", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+        assert_eq!(eval("SUM(B1:B2:C3)", &snap), CellValue::Number(30.0));
+        assert_eq!(eval("ROWS(A1:B2:C4)", &snap), CellValue::Number(4.0));
+    }
+
+    #[test]
+    fn test_build_dependency_graph_volatile() {
+        let mut snap = make_snapshot();
+        snap.set_defined_name("Dynamic", None, "OFFSET(Sheet1!$A$1,0,0,3)");
+        let formula_cells = vec![
+            (coord("Sheet1", 2, 1), "INDIRECT(\"A1\")".to_string()),
+            (coord("Sheet1", 2, 2), "SUM(Dynamic)".to_string()),
+            (coord("Sheet1", 2, 3), "SUM(A1:A3)".to_string()),
+            (coord("Sheet1", 2, 4), "NOW()".to_string()),
+        ];
+        let graph = build_dependency_graph(&formula_cells, &snap).unwrap();
+        let expected: HashSet<CellCoord> = [
+            coord("Sheet1", 2, 1),
+            coord("Sheet1", 2, 2),
+            coord("Sheet1", 2, 4),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(graph.volatile, expected);
+        // The base reference of OFFSET is still a precedent.
+        assert_eq!(
+            graph.deps[&coord("Sheet1", 2, 2)],
+            vec![coord("Sheet1", 1, 1)]
+        );
+    }
+
+    #[test]
+    fn test_strongly_connected_components() {
+        let mut deps = HashMap::new();
+        // A1 -> B1 <-> C1, D1 reads itself, E1 is independent.
+        deps.insert(coord("Sheet1", 1, 1), vec![coord("Sheet1", 2, 1)]);
+        deps.insert(coord("Sheet1", 2, 1), vec![coord("Sheet1", 3, 1)]);
+        deps.insert(coord("Sheet1", 3, 1), vec![coord("Sheet1", 2, 1)]);
+        deps.insert(coord("Sheet1", 4, 1), vec![coord("Sheet1", 4, 1)]);
+        deps.insert(coord("Sheet1", 5, 1), vec![coord("Sheet1", 9, 9)]);
+        let cells: Vec<CellCoord> = (1..=5).map(|c| coord("Sheet1", c, 1)).collect();
+        let components = strongly_connected_components(&cells, &deps);
+        assert_eq!(
+            components,
+            vec![
+                vec![coord("Sheet1", 2, 1), coord("Sheet1", 3, 1)],
+                vec![coord("Sheet1", 1, 1)],
+                vec![coord("Sheet1", 4, 1)],
+                vec![coord("Sheet1", 5, 1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dependency_graph_affected_cells() {
+        let snap = make_snapshot();
+        let formula_cells = vec![
+            (coord("Sheet1", 2, 1), "A1*2".to_string()),
+            (coord("Sheet1", 3, 1), "B1+1".to_string()),
+            (coord("Sheet1", 2, 2), "A2".to_string()),
+            (coord("Sheet1", 3, 2), "RAND()".to_string()),
+        ];
+        let mut graph = build_dependency_graph(&formula_cells, &snap).unwrap();
+        assert_eq!(
+            graph.dependents[&coord("Sheet1", 2, 1)],
+            vec![coord("Sheet1", 3, 1)]
+        );
+
+        let expected: HashSet<CellCoord> = [
+            coord("Sheet1", 2, 1),
+            coord("Sheet1", 3, 1),
+            coord("Sheet1", 3, 2),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(graph.affected_cells(&[coord("Sheet1", 1, 1)]), expected);
+
+        // Once B1 no longer reads A1, editing A1 only affects volatile cells.
+        graph.remove_formula(&coord("Sheet1", 2, 1));
+        assert!(!graph.dependents.contains_key(&coord("Sheet1", 1, 1)));
+        let expected: HashSet<CellCoord> = [coord("Sheet1", 3, 2)].into_iter().collect();
+        assert_eq!(graph.affected_cells(&[coord("Sheet1", 1, 1)]), expected);
+    }
+
+    #[test]
+    fn eval_structured_refs() {
+        // Sales occupies B2:D6: a header row, three data rows and totals.
+        let mut snap = make_snapshot();
+        snap.add_table(TableRange {
+            name: "Sales".to_string(),
+            sheet: "Sheet1".to_string(),
+            min_col: 2,
+            min_row: 2,
+            max_col: 4,
+            max_row: 6,
+            has_headers: true,
+            has_totals: true,
+            columns: vec!["Qty".into(), "Price".into(), "Amount".into()],
+        });
+        for (col, name) in [(2, "Qty"), (3, "Price"), (4, "Amount")] {
+            snap.set_cell("Sheet1", col, 2, CellValue::String(name.to_string()));
+        }
+        for row in 3..=5 {
+            let qty = (row - 2) as f64;
+            snap.set_cell("Sheet1", 2, row, CellValue::Number(qty));
+            snap.set_cell("Sheet1", 3, row, CellValue::Number(10.0));
+            snap.set_cell("Sheet1", 4, row, CellValue::Number(qty * 10.0));
+        }
+        snap.set_cell("Sheet1", 4, 6, CellValue::Number(60.0));
+
+        let eval = |formula: &str, snap: &CellSnapshot| {
+            evaluate(&parse_formula(formula).unwrap(), snap).unwrap()
+        };
+        assert_eq!(eval("SUM(Sales[Amount])", &snap), CellValue::Number(60.0));
+        assert_eq!(eval("ROWS(Sales[#All])", &snap), CellValue::Number(5.0));
+        assert_eq!(eval("COLUMNS(sales[])", &snap), CellValue::Number(3.0));
+        assert_eq!(
+            eval("Sales[[#Totals],[Amount]]", &snap),
+            CellValue::Number(60.0)
+        );
+        assert_eq!(
+            eval("Sales[[#Headers],[Price]]", &snap),
+            CellValue::String("Price".to_string())
+        );
+        assert_eq!(
+            eval("SUM(Sales[[Qty]:[Price]])", &snap),
+            CellValue::Number(36.0)
+        );
+        assert_eq!(
+            eval("Sales[Missing]", &snap),
+            CellValue::Error("#REF!".to_string())
+        );
+
+        // This-row references use the row of the formula cell.
+        // Unqualified references need the formula cell inside the table.
+        snap.set_current_cell(Some((4, 4)));
+        assert_eq!(eval("[@Qty]*[@Price]", &snap), CellValue::Number(20.0));
+        snap.set_current_cell(Some((5, 4)));
+        assert_eq!(eval("Sales[@Amount]", &snap), CellValue::Number(20.0));
+        assert_eq!(eval("[@Qty]", &snap), CellValue::Error("#REF!".to_string()));
+        snap.set_current_cell(Some((5, 6)));
+        assert_eq!(
+            eval("Sales[@Amount]", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
+
     fn coord(sheet: &str, col: u32, row: u32) -> CellCoord {
         CellCoord {
             sheet: sheet.to_string(),
@@ -1181,7 +3012,9 @@ mod tests {
     fn test_build_dependency_graph_simple() {
         // A1 = B1 + C1
         let formula_cells = vec![(coord("Sheet1", 1, 1), "B1+C1".to_string())];
-        let deps = build_dependency_graph(&formula_cells).unwrap();
+        let deps = build_dependency_graph(&formula_cells, &make_snapshot())
+            .unwrap()
+            .deps;
         let a1_deps = deps.get(&coord("Sheet1", 1, 1)).unwrap();
         assert!(a1_deps.contains(&coord("Sheet1", 2, 1))); // B1
         assert!(a1_deps.contains(&coord("Sheet1", 3, 1))); // C1
@@ -1192,7 +3025,9 @@ mod tests {
     fn test_build_dependency_graph_range() {
         // A1 = SUM(B1:B5)
         let formula_cells = vec![(coord("Sheet1", 1, 1), "SUM(B1:B5)".to_string())];
-        let deps = build_dependency_graph(&formula_cells).unwrap();
+        let deps = build_dependency_graph(&formula_cells, &make_snapshot())
+            .unwrap()
+            .deps;
         let a1_deps = deps.get(&coord("Sheet1", 1, 1)).unwrap();
         assert_eq!(a1_deps.len(), 5);
         for r in 1..=5 {
@@ -1204,7 +3039,9 @@ mod tests {
     fn test_build_dependency_graph_cross_sheet() {
         // Sheet1!A1 = Sheet2!B1
         let formula_cells = vec![(coord("Sheet1", 1, 1), "Sheet2!B1".to_string())];
-        let deps = build_dependency_graph(&formula_cells).unwrap();
+        let deps = build_dependency_graph(&formula_cells, &make_snapshot())
+            .unwrap()
+            .deps;
         let a1_deps = deps.get(&coord("Sheet1", 1, 1)).unwrap();
         assert_eq!(a1_deps.len(), 1);
         assert_eq!(a1_deps[0], coord("Sheet2", 2, 1));
@@ -1213,7 +3050,8 @@ mod tests {
     #[test]
     fn test_extract_cell_refs_from_nested_expr() {
         let expr = parse_formula("A1+SUM(B1:B3,C1)").unwrap();
-        let refs = extract_cell_refs(&expr, "Sheet1");
+        let (refs, volatile) = extract_cell_refs(&expr, "Sheet1", None, &make_snapshot());
+        assert!(!volatile);
         assert!(refs.contains(&coord("Sheet1", 1, 1))); // A1
         assert!(refs.contains(&coord("Sheet1", 2, 1))); // B1
         assert!(refs.contains(&coord("Sheet1", 2, 2))); // B2
@@ -1222,6 +3060,28 @@ mod tests {
         assert_eq!(refs.len(), 5);
     }
 
+    #[test]
+    fn test_build_dependency_graph_defined_name() {
+        let mut snap = make_snapshot();
+        snap.set_defined_name("Sales", None, "Sheet2!$A$1:$A$2");
+        snap.set_defined_name("Rate", Some("Sheet1"), "Sheet2!$B$1");
+        let formula_cells = vec![
+            (coord("Sheet1", 1, 1), "SUM(Sales)*Rate".to_string()),
+            (coord("Sheet3", 1, 1), "Rate".to_string()),
+        ];
+        let deps = build_dependency_graph(&formula_cells, &snap).unwrap().deps;
+        assert_eq!(
+            deps[&coord("Sheet1", 1, 1)],
+            vec![
+                coord("Sheet2", 1, 1),
+                coord("Sheet2", 1, 2),
+                coord("Sheet2", 2, 1)
+            ]
+        );
+        // The sheet-scoped name is not visible from Sheet3.
+        assert!(deps[&coord("Sheet3", 1, 1)].is_empty());
+    }
+
     #[test]
     fn test_topological_sort_linear_chain() {
         // A2 = A1 + 1, A3 = A2 + 1 (A1 is a value, not a formula)
@@ -1314,4 +3174,32 @@ mod tests {
         snap.set_current_sheet("Sheet2");
         assert_eq!(snap.current_sheet(), "Sheet2");
     }
+
+    #[test]
+    fn test_evaluation_trace() {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        snap.set_cell("Sheet1", 1, 1, CellValue::Number(2.0));
+        snap.set_cell("Sheet1", 1, 2, CellValue::Number(3.0));
+        snap.set_defined_name("Rate", None, "Sheet1!$A$1");
+        let expr = parse_formula("SUMPRODUCT(A1:A2,A1:A2)+Rate").unwrap();
+        let mut evaluator = Evaluator::new(&snap);
+        evaluator.start_trace();
+        assert_eq!(evaluator.eval_expr(&expr).unwrap(), CellValue::Number(15.0));
+        let steps = evaluator.take_trace();
+        let last = steps.last().unwrap();
+        assert_eq!(last.depth, 0);
+        assert_eq!(last.expr, "SUMPRODUCT(A1:A2,A1:A2)+Rate");
+        assert_eq!(last.value, CellValue::Number(15.0));
+        // The name records the reference it resolves to.
+        let rate = steps.iter().position(|s| s.expr == "Rate").unwrap();
+        assert_eq!(steps[rate - 1].expr, "Sheet1!$A$1");
+        assert_eq!(steps[rate].value, CellValue::Number(2.0));
+        // Ranges evaluated as arrays record all their values.
+        let range = steps.iter().find(|s| s.expr == "A1:A2").unwrap();
+        assert_eq!(range.array.as_ref().unwrap().values.len(), 2);
+
+        // Recording stops once the trace is taken.
+        evaluator.eval_expr(&expr).unwrap();
+        assert!(evaluator.take_trace().is_empty());
+    }
 }
@@ -0,0 +1,386 @@
+//! Database functions: DAVERAGE, DCOUNT, DCOUNTA, DGET, DMAX, DMIN,
+//! DPRODUCT, DSTDEV, DSTDEVP, DSUM, DVAR, DVARP.
+//!
+//! Each function takes a database range whose first row holds column
+//! labels, a field (a label or a 1-based column number), and a criteria
+//! range. The first row of the criteria range names database columns and
+//! each following row is a set of conditions that must all hold; a record
+//! matches if any criteria row holds. Conditions use the same syntax as
+//! SUMIFS and COUNTIFS (see [`matches_criteria`]), and empty criteria
+//! cells match anything.
+
+use crate::cell::CellValue;
+use crate::error::Result;
+use crate::formula::ast::Expr;
+use crate::formula::eval::{coerce_to_string, ArrayValue, Evaluator};
+use crate::formula::functions::{check_arg_count, matches_criteria};
+
+/// Return the index of the database column labelled `label`, ignoring case
+/// and surrounding spaces.
+fn column_index(database: &ArrayValue, label: &str) -> Option<usize> {
+    let label = label.trim();
+    database
+        .row(0)
+        .iter()
+        .position(|v| coerce_to_string(v).trim().eq_ignore_ascii_case(label))
+}
+
+/// Resolve the field argument: a column label or a 1-based column number.
+fn field_index(database: &ArrayValue, field: &CellValue) -> Option<usize> {
+    match field {
+        CellValue::Number(n) => {
+            let n = n.trunc();
+            (n >= 1.0 && n <= database.cols as f64).then(|| n as usize - 1)
+        }
+        CellValue::String(label) => column_index(database, label),
+        _ => None,
+    }
+}
+
+/// Return `true` if database row `row` satisfies the criteria range.
+/// Returns `None` if a criteria label names no database column.
+fn record_matches(database: &ArrayValue, criteria: &ArrayValue, row: usize) -> Option<bool> {
+    if criteria.rows < 2 {
+        return Some(true);
+    }
+    let columns = criteria
+        .row(0)
+        .iter()
+        .map(|label| column_index(database, &coerce_to_string(label)))
+        .collect::<Vec<_>>();
+    for conditions in 1..criteria.rows {
+        let mut all = true;
+        for (col, condition) in criteria.row(conditions).iter().enumerate() {
+            if matches!(condition, CellValue::Empty) {
+                continue;
+            }
+            let value = database.get(row, columns[col]?);
+            if !matches_criteria(value, &coerce_to_string(condition)) {
+                all = false;
+                break;
+            }
+        }
+        if all {
+            return Some(true);
+        }
+    }
+    Some(false)
+}
+
+/// Evaluate the arguments shared by all database functions and return the
+/// field values of the matching records, or an error code.
+fn matching_values(
+    name: &str,
+    args: &[Expr],
+    ctx: &mut Evaluator,
+) -> Result<std::result::Result<Vec<CellValue>, String>> {
+    check_arg_count(name, args, 3, 3)?;
+    let database = ctx.eval_array(&args[0])?;
+    let criteria = ctx.eval_array(&args[2])?;
+    let field = match &args[1] {
+        // DCOUNT and DCOUNTA count the matching records when the field is
+        // omitted.
+        Expr::Missing if matches!(name, "DCOUNT" | "DCOUNTA") => None,
+        arg => match ctx.eval_expr(arg)? {
+            CellValue::Error(e) => return Ok(Err(e)),
+            field => match field_index(&database, &field) {
+                Some(field) => Some(field),
+                None => return Ok(Err("#VALUE!".to_string())),
+            },
+        },
+    };
+    let mut values = Vec::new();
+    for row in 1..database.rows {
+        match record_matches(&database, &criteria, row) {
+            Some(true) => values.push(match field {
+                Some(field) => database.get(row, field).clone(),
+                None => CellValue::Number(row as f64),
+            }),
+            Some(false) => {}
+            None => return Ok(Err("#VALUE!".to_string())),
+        }
+    }
+    Ok(Ok(values))
+}
+
+/// Evaluate the matching field values that are numbers.
+fn matching_numbers(
+    name: &str,
+    args: &[Expr],
+    ctx: &mut Evaluator,
+) -> Result<std::result::Result<Vec<f64>, String>> {
+    Ok(matching_values(name, args, ctx)?.map(|values| {
+        values
+            .iter()
+            .filter_map(|v| match v {
+                CellValue::Number(n) | CellValue::Date(n) => Some(*n),
+                _ => None,
+            })
+            .collect()
+    }))
+}
+
+/// Apply `f` to the matching numbers, passing errors through.
+fn with_numbers(
+    name: &str,
+    args: &[Expr],
+    ctx: &mut Evaluator,
+    f: impl FnOnce(&[f64]) -> CellValue,
+) -> Result<CellValue> {
+    Ok(match matching_numbers(name, args, ctx)? {
+        Ok(nums) => f(&nums),
+        Err(e) => CellValue::Error(e),
+    })
+}
+
+/// Sample (`sample = true`) or population variance, or `None` when there
+/// are too few numbers.
+fn variance(nums: &[f64], sample: bool) -> Option<f64> {
+    let n = nums.len() as f64;
+    let divisor = if sample { n - 1.0 } else { n };
+    if divisor < 1.0 {
+        return None;
+    }
+    let mean = nums.iter().sum::<f64>() / n;
+    Some(nums.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / divisor)
+}
+
+fn number_or_div0(value: Option<f64>) -> CellValue {
+    value.map_or_else(
+        || CellValue::Error("#DIV/0!".to_string()),
+        CellValue::Number,
+    )
+}
+
+/// DSUM(database, field, criteria)
+pub fn fn_dsum(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    with_numbers("DSUM", args, ctx, |nums| {
+        CellValue::Number(nums.iter().sum())
+    })
+}
+
+/// DAVERAGE(database, field, criteria)
+pub fn fn_daverage(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    with_numbers("DAVERAGE", args, ctx, |nums| {
+        number_or_div0((!nums.is_empty()).then(|| nums.iter().sum::<f64>() / nums.len() as f64))
+    })
+}
+
+/// DCOUNT(database, field, criteria) - count matching numbers
+pub fn fn_dcount(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    with_numbers("DCOUNT", args, ctx, |nums| {
+        CellValue::Number(nums.len() as f64)
+    })
+}
+
+/// DCOUNTA(database, field, criteria) - count matching non-empty values
+pub fn fn_dcounta(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    Ok(match matching_values("DCOUNTA", args, ctx)? {
+        Ok(values) => CellValue::Number(
+            values
+                .iter()
+                .filter(|v| !matches!(v, CellValue::Empty))
+                .count() as f64,
+        ),
+        Err(e) => CellValue::Error(e),
+    })
+}
+
+/// DGET(database, field, criteria) - the field of the single matching
+/// record: `#VALUE!` if none matches, `#NUM!` if several do
+pub fn fn_dget(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    Ok(match matching_values("DGET", args, ctx)? {
+        Ok(values) => match values.len() {
+            0 => CellValue::Error("#VALUE!".to_string()),
+            1 => values.into_iter().next().unwrap_or(CellValue::Empty),
+            _ => CellValue::Error("#NUM!".to_string()),
+        },
+        Err(e) => CellValue::Error(e),
+    })
+}
+
+/// DMAX(database, field, criteria)
+pub fn fn_dmax(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    with_numbers("DMAX", args, ctx, |nums| {
+        CellValue::Number(nums.iter().copied().reduce(f64::max).unwrap_or(0.0))
+    })
+}
+
+/// DMIN(database, field, criteria)
+pub fn fn_dmin(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    with_numbers("DMIN", args, ctx, |nums| {
+        CellValue::Number(nums.iter().copied().reduce(f64::min).unwrap_or(0.0))
+    })
+}
+
+/// DPRODUCT(database, field, criteria)
+pub fn fn_dproduct(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    with_numbers("DPRODUCT", args, ctx, |nums| {
+        CellValue::Number(if nums.is_empty() {
+            0.0
+        } else {
+            nums.iter().product()
+        })
+    })
+}
+
+/// DSTDEV(database, field, criteria) - sample standard deviation
+pub fn fn_dstdev(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    with_numbers("DSTDEV", args, ctx, |nums| {
+        number_or_div0(variance(nums, true).map(f64::sqrt))
+    })
+}
+
+/// DSTDEVP(database, field, criteria) - population standard deviation
+pub fn fn_dstdevp(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    with_numbers("DSTDEVP", args, ctx, |nums| {
+        number_or_div0(variance(nums, false).map(f64::sqrt))
+    })
+}
+
+/// DVAR(database, field, criteria) - sample variance
+pub fn fn_dvar(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    with_numbers("DVAR", args, ctx, |nums| {
+        number_or_div0(variance(nums, true))
+    })
+}
+
+/// DVARP(database, field, criteria) - population variance
+pub fn fn_dvarp(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    with_numbers("DVARP", args, ctx, |nums| {
+        number_or_div0(variance(nums, false))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cell::CellValue;
+    use crate::formula::eval::{evaluate, CellSnapshot};
+    use crate::formula::parser::parse_formula;
+
+    fn eval(formula: &str, snap: &CellSnapshot) -> CellValue {
+        evaluate(&parse_formula(formula).unwrap(), snap).unwrap()
+    }
+
+    fn assert_approx(value: CellValue, expected: f64) {
+        match value {
+            CellValue::Number(n) => assert!((n - expected).abs() < 1e-2, "{n} != {expected}"),
+            other => panic!("expected {expected}, got {other:?}"),
+        }
+    }
+
+    /// Criteria in A1:F3 and the orchard database in A5:E11.
+    fn orchard() -> CellSnapshot {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        let text = |s: &str| CellValue::String(s.to_string());
+        let criteria = [
+            vec!["Tree", "Height", "Age", "Yield", "Profit", "Height"],
+            vec!["=Apple", ">10", "", "", "", "<16"],
+            vec!["=Pear", "", "", "", "", ""],
+        ];
+        for (r, row) in criteria.iter().enumerate() {
+            for (c, value) in row.iter().enumerate() {
+                if !value.is_empty() {
+                    snap.set_cell("Sheet1", c as u32 + 1, r as u32 + 1, text(value));
+                }
+            }
+        }
+        for (c, label) in ["Tree", "Height", "Age", "Yield", "Profit"]
+            .iter()
+            .enumerate()
+        {
+            snap.set_cell("Sheet1", c as u32 + 1, 5, text(label));
+        }
+        let records = [
+            ("Apple", [18.0, 20.0, 14.0, 105.0]),
+            ("Pear", [12.0, 12.0, 10.0, 96.0]),
+            ("Cherry", [13.0, 14.0, 9.0, 105.0]),
+            ("Apple", [14.0, 15.0, 10.0, 75.0]),
+            ("Pear", [9.0, 8.0, 8.0, 76.8]),
+            ("Apple", [8.0, 9.0, 6.0, 45.0]),
+        ];
+        for (i, (tree, numbers)) in records.iter().enumerate() {
+            let row = i as u32 + 6;
+            snap.set_cell("Sheet1", 1, row, text(tree));
+            for (c, n) in numbers.iter().enumerate() {
+                snap.set_cell("Sheet1", c as u32 + 2, row, CellValue::Number(*n));
+            }
+        }
+        snap
+    }
+
+    #[test]
+    fn test_dcount_dcounta() {
+        let snap = orchard();
+        assert_approx(eval("DCOUNT(A5:E11,\"Age\",A1:F2)", &snap), 1.0);
+        assert_approx(eval("DCOUNTA(A5:E11,\"Profit\",A1:F2)", &snap), 1.0);
+        assert_approx(eval("DCOUNTA(A5:E11,1,A1:A3)", &snap), 5.0);
+        // Without a field, the matching records are counted.
+        assert_approx(eval("DCOUNT(A5:E11,,A1:A3)", &snap), 5.0);
+        assert_approx(eval("DCOUNTA(A5:E11,,A1:F2)", &snap), 1.0);
+        assert_eq!(
+            eval("DSUM(A5:E11,,A1:A3)", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_dsum_dproduct_daverage() {
+        let snap = orchard();
+        assert_approx(eval("DSUM(A5:E11,\"Profit\",A1:A2)", &snap), 225.0);
+        assert_approx(eval("DSUM(A5:E11,\"profit\",A1:F2)", &snap), 75.0);
+        assert_approx(eval("DPRODUCT(A5:E11,\"Yield\",A1:F2)", &snap), 10.0);
+        assert_approx(eval("DAVERAGE(A5:E11,\"Yield\",A1:B2)", &snap), 12.0);
+        // Each record matches its own row when the database is the criteria.
+        assert_approx(eval("DAVERAGE(A5:E11,3,A5:E11)", &snap), 13.0);
+    }
+
+    #[test]
+    fn test_dmax_dmin() {
+        let snap = orchard();
+        // Rows of the criteria range are alternatives: apples or pears.
+        assert_approx(eval("DMAX(A5:E11,\"Profit\",A1:A3)", &snap), 105.0);
+        assert_approx(eval("DMIN(A5:E11,\"Profit\",A1:B2)", &snap), 75.0);
+    }
+
+    #[test]
+    fn test_dstdev_dvar() {
+        let snap = orchard();
+        assert_approx(eval("DSTDEV(A5:E11,\"Yield\",A1:A3)", &snap), 2.97);
+        assert_approx(eval("DSTDEVP(A5:E11,\"Yield\",A1:A3)", &snap), 2.65);
+        assert_approx(eval("DVAR(A5:E11,\"Yield\",A1:A3)", &snap), 8.8);
+        assert_approx(eval("DVARP(A5:E11,\"Yield\",A1:A3)", &snap), 7.04);
+        assert_eq!(
+            eval("DVAR(A5:E11,\"Yield\",A1:F2)", &snap),
+            CellValue::Error("#DIV/0!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_dget() {
+        let snap = orchard();
+        assert_approx(eval("DGET(A5:E11,\"Yield\",A1:F2)", &snap), 10.0);
+        assert_eq!(
+            eval("DGET(A5:E11,\"Yield\",A1:A3)", &snap),
+            CellValue::Error("#NUM!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_database_errors() {
+        let snap = orchard();
+        assert_eq!(
+            eval("DSUM(A5:E11,\"Weight\",A1:A2)", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+        assert_eq!(
+            eval("DSUM(A5:E11,6,A1:A2)", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+        // The criteria label "Tree" names no column of B5:E11.
+        assert_eq!(
+            eval("DSUM(B5:E11,\"Age\",A5:E6)", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
+}
@@ -2,18 +2,16 @@
 //! MINUTE, SECOND, DATEDIF, EDATE, EOMONTH, DATEVALUE, WEEKDAY, WEEKNUM,
 //! NETWORKDAYS, WORKDAY.
 
-use chrono::{Datelike, Duration, Local, NaiveDate, Timelike};
+use chrono::{Local, NaiveDate, Timelike};
 
-use crate::cell::{
-    date_to_serial, datetime_to_serial, serial_to_date, serial_to_datetime, CellValue,
-};
+use crate::cell::{serial_time, CellValue, DateSystem};
 use crate::error::Result;
 use crate::formula::ast::Expr;
 use crate::formula::eval::{coerce_to_number, coerce_to_string, Evaluator};
 use crate::formula::functions::check_arg_count;
 
-/// Convert a CellValue to an Excel serial number.
-fn to_serial(v: &CellValue) -> std::result::Result<f64, CellValue> {
+/// Convert a CellValue to an Excel serial number in `system`.
+fn to_serial(v: &CellValue, system: DateSystem) -> std::result::Result<f64, CellValue> {
     match v {
         CellValue::Number(n) => Ok(*n),
         CellValue::Date(n) => Ok(*n),
@@ -21,7 +19,7 @@ fn to_serial(v: &CellValue) -> std::result::Result<f64, CellValue> {
             if let Ok(n) = s.parse::<f64>() {
                 Ok(n)
             } else if let Some(d) = parse_date_string(s) {
-                Ok(date_to_serial(d))
+                Ok(system.date_to_serial(d))
             } else {
                 Err(CellValue::Error("#VALUE!".to_string()))
             }
@@ -43,23 +41,24 @@ fn parse_date_string(s: &str) -> Option<NaiveDate> {
     None
 }
 
-/// Add months to a date, clamping to end of month if needed.
-fn add_months_to_date(date: NaiveDate, months: i32) -> Option<NaiveDate> {
-    let total_months = date.year() * 12 + date.month() as i32 - 1 + months;
+/// Add months to a (year, month, day) date, clamping the day to the end of
+/// the month if needed.
+fn add_months((year, month, day): (i32, u32, u32), months: i32) -> (i32, u32, u32) {
+    let total_months = year * 12 + month as i32 - 1 + months;
     let new_year = total_months.div_euclid(12);
     let new_month = (total_months.rem_euclid(12) + 1) as u32;
-    let max_day = last_day_of_month(new_year, new_month);
-    let new_day = date.day().min(max_day);
-    NaiveDate::from_ymd_opt(new_year, new_month, new_day)
+    let new_day = day.min(last_day_of_month(new_year, new_month));
+    (new_year, new_month, new_day)
 }
 
-/// Return the last day of the given month.
+/// Return the last day of the given month. February 1900 has 29 days, as
+/// Excel treats 1900 as a leap year.
 fn last_day_of_month(year: i32, month: u32) -> u32 {
     match month {
         1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
         4 | 6 | 9 | 11 => 30,
         2 => {
-            if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 {
+            if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 || year == 1900 {
                 29
             } else {
                 28
@@ -69,9 +68,25 @@ fn last_day_of_month(year: i32, month: u32) -> u32 {
     }
 }
 
+/// Convert a (year, month, day) date to a serial number in `system`. Days
+/// are added to the serial number of the first of the month, so that
+/// 1900-02-29 is serial 60 like in Excel.
+fn ymd_to_serial(system: DateSystem, year: i32, month: u32, day: u32) -> Option<f64> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    Some(system.date_to_serial(first) + f64::from(day) - 1.0)
+}
+
+/// Return the day of the week of a serial number in `system`, Monday = 0.
+/// Days are counted from serial 0 in the 1900 date system, a Saturday, so
+/// that serials before the phantom 1900-02-29 give Excel's weekdays.
+fn weekday_from_monday(system: DateSystem, serial: f64) -> u32 {
+    (system.to_1900_serial(serial).floor() as i64 + 5).rem_euclid(7) as u32
+}
+
 /// DATE(year, month, day) - constructs a date serial number.
 pub fn fn_date(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("DATE", args, 3, 3)?;
+    let system = ctx.date_system();
     let year = coerce_to_number(&ctx.eval_expr(&args[0])?)? as i32;
     let month = coerce_to_number(&ctx.eval_expr(&args[1])?)? as i32;
     let day = coerce_to_number(&ctx.eval_expr(&args[2])?)? as i32;
@@ -79,41 +94,43 @@ pub fn fn_date(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     let adj_year = if year < 1900 { year + 1900 } else { year };
 
     // Handle month overflow/underflow
-    let base = NaiveDate::from_ymd_opt(adj_year, 1, 1);
-    let base = match base {
-        Some(d) => d,
-        None => return Ok(CellValue::Error("#VALUE!".to_string())),
-    };
-    let with_months = add_months_to_date(base, month - 1);
-    let with_months = match with_months {
-        Some(d) => d,
+    let (year, month, _) = add_months((adj_year, 1, 1), month - 1);
+    // Days are added to the serial number rather than to the date, so that
+    // DATE(1900,3,0) is serial 60 like in Excel.
+    let serial = match ymd_to_serial(system, year, month, 1) {
+        Some(first) => first + f64::from(day) - 1.0,
         None => return Ok(CellValue::Error("#VALUE!".to_string())),
     };
-    let result = with_months + Duration::days(i64::from(day) - 1);
-    Ok(CellValue::Date(date_to_serial(result)))
+    if serial < 0.0 {
+        return Ok(CellValue::Error("#NUM!".to_string()));
+    }
+    Ok(CellValue::Date(serial))
 }
 
 /// TODAY() - returns today's date serial number.
-pub fn fn_today(args: &[Expr], _ctx: &mut Evaluator) -> Result<CellValue> {
+pub fn fn_today(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("TODAY", args, 0, 0)?;
+    let system = ctx.date_system();
     let today = Local::now().date_naive();
-    Ok(CellValue::Date(date_to_serial(today)))
+    Ok(CellValue::Date(system.date_to_serial(today)))
 }
 
 /// NOW() - returns current date and time serial number.
-pub fn fn_now(args: &[Expr], _ctx: &mut Evaluator) -> Result<CellValue> {
+pub fn fn_now(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("NOW", args, 0, 0)?;
+    let system = ctx.date_system();
     let now = Local::now().naive_local();
-    Ok(CellValue::Date(datetime_to_serial(now)))
+    Ok(CellValue::Date(system.datetime_to_serial(now)))
 }
 
 /// YEAR(serial_number) - extracts the year from a date serial.
 pub fn fn_year(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("YEAR", args, 1, 1)?;
+    let system = ctx.date_system();
     let v = ctx.eval_expr(&args[0])?;
-    match to_serial(&v) {
-        Ok(serial) => match serial_to_date(serial) {
-            Some(d) => Ok(CellValue::Number(d.year() as f64)),
+    match to_serial(&v, system) {
+        Ok(serial) => match system.serial_to_ymd(serial) {
+            Some((year, _, _)) => Ok(CellValue::Number(year as f64)),
             None => Ok(CellValue::Error("#VALUE!".to_string())),
         },
         Err(e) => Ok(e),
@@ -123,10 +140,11 @@ pub fn fn_year(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 /// MONTH(serial_number) - extracts the month from a date serial.
 pub fn fn_month(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("MONTH", args, 1, 1)?;
+    let system = ctx.date_system();
     let v = ctx.eval_expr(&args[0])?;
-    match to_serial(&v) {
-        Ok(serial) => match serial_to_date(serial) {
-            Some(d) => Ok(CellValue::Number(d.month() as f64)),
+    match to_serial(&v, system) {
+        Ok(serial) => match system.serial_to_ymd(serial) {
+            Some((_, month, _)) => Ok(CellValue::Number(month as f64)),
             None => Ok(CellValue::Error("#VALUE!".to_string())),
         },
         Err(e) => Ok(e),
@@ -136,10 +154,11 @@ pub fn fn_month(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 /// DAY(serial_number) - extracts the day from a date serial.
 pub fn fn_day(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("DAY", args, 1, 1)?;
+    let system = ctx.date_system();
     let v = ctx.eval_expr(&args[0])?;
-    match to_serial(&v) {
-        Ok(serial) => match serial_to_date(serial) {
-            Some(d) => Ok(CellValue::Number(d.day() as f64)),
+    match to_serial(&v, system) {
+        Ok(serial) => match system.serial_to_ymd(serial) {
+            Some((_, _, day)) => Ok(CellValue::Number(day as f64)),
             None => Ok(CellValue::Error("#VALUE!".to_string())),
         },
         Err(e) => Ok(e),
@@ -149,10 +168,11 @@ pub fn fn_day(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 /// HOUR(serial_number) - extracts the hour from a datetime serial.
 pub fn fn_hour(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("HOUR", args, 1, 1)?;
+    let system = ctx.date_system();
     let v = ctx.eval_expr(&args[0])?;
-    match to_serial(&v) {
-        Ok(serial) => match serial_to_datetime(serial) {
-            Some(dt) => Ok(CellValue::Number(dt.hour() as f64)),
+    match to_serial(&v, system) {
+        Ok(serial) => match system.serial_to_ymd(serial).and(serial_time(serial)) {
+            Some(time) => Ok(CellValue::Number(time.hour() as f64)),
             None => Ok(CellValue::Error("#VALUE!".to_string())),
         },
         Err(e) => Ok(e),
@@ -162,10 +182,11 @@ pub fn fn_hour(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 /// MINUTE(serial_number) - extracts the minute from a datetime serial.
 pub fn fn_minute(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("MINUTE", args, 1, 1)?;
+    let system = ctx.date_system();
     let v = ctx.eval_expr(&args[0])?;
-    match to_serial(&v) {
-        Ok(serial) => match serial_to_datetime(serial) {
-            Some(dt) => Ok(CellValue::Number(dt.minute() as f64)),
+    match to_serial(&v, system) {
+        Ok(serial) => match system.serial_to_ymd(serial).and(serial_time(serial)) {
+            Some(time) => Ok(CellValue::Number(time.minute() as f64)),
             None => Ok(CellValue::Error("#VALUE!".to_string())),
         },
         Err(e) => Ok(e),
@@ -175,10 +196,11 @@ pub fn fn_minute(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 /// SECOND(serial_number) - extracts the second from a datetime serial.
 pub fn fn_second(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("SECOND", args, 1, 1)?;
+    let system = ctx.date_system();
     let v = ctx.eval_expr(&args[0])?;
-    match to_serial(&v) {
-        Ok(serial) => match serial_to_datetime(serial) {
-            Some(dt) => Ok(CellValue::Number(dt.second() as f64)),
+    match to_serial(&v, system) {
+        Ok(serial) => match system.serial_to_ymd(serial).and(serial_time(serial)) {
+            Some(time) => Ok(CellValue::Number(time.second() as f64)),
             None => Ok(CellValue::Error("#VALUE!".to_string())),
         },
         Err(e) => Ok(e),
@@ -188,15 +210,16 @@ pub fn fn_second(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 /// DATEDIF(start_date, end_date, unit) - difference between two dates.
 pub fn fn_datedif(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("DATEDIF", args, 3, 3)?;
+    let system = ctx.date_system();
     let v1 = ctx.eval_expr(&args[0])?;
     let v2 = ctx.eval_expr(&args[1])?;
     let unit = coerce_to_string(&ctx.eval_expr(&args[2])?).to_ascii_uppercase();
 
-    let s1 = match to_serial(&v1) {
+    let s1 = match to_serial(&v1, system) {
         Ok(n) => n,
         Err(e) => return Ok(e),
     };
-    let s2 = match to_serial(&v2) {
+    let s2 = match to_serial(&v2, system) {
         Ok(n) => n,
         Err(e) => return Ok(e),
     };
@@ -204,34 +227,34 @@ pub fn fn_datedif(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
         return Ok(CellValue::Error("#NUM!".to_string()));
     }
 
-    let d1 = match serial_to_date(s1) {
+    let (y1, m1, d1) = match system.serial_to_ymd(s1) {
         Some(d) => d,
         None => return Ok(CellValue::Error("#VALUE!".to_string())),
     };
-    let d2 = match serial_to_date(s2) {
+    let (y2, m2, d2) = match system.serial_to_ymd(s2) {
         Some(d) => d,
         None => return Ok(CellValue::Error("#VALUE!".to_string())),
     };
 
     let result = match unit.as_str() {
         "Y" => {
-            let mut years = d2.year() - d1.year();
-            if (d2.month(), d2.day()) < (d1.month(), d1.day()) {
+            let mut years = y2 - y1;
+            if (m2, d2) < (m1, d1) {
                 years -= 1;
             }
             years as f64
         }
         "M" => {
-            let mut months = (d2.year() - d1.year()) * 12 + d2.month() as i32 - d1.month() as i32;
-            if d2.day() < d1.day() {
+            let mut months = (y2 - y1) * 12 + m2 as i32 - m1 as i32;
+            if d2 < d1 {
                 months -= 1;
             }
             months as f64
         }
         "D" => s2.floor() - s1.floor(),
         "YM" => {
-            let mut months = d2.month() as i32 - d1.month() as i32;
-            if d2.day() < d1.day() {
+            let mut months = m2 as i32 - m1 as i32;
+            if d2 < d1 {
                 months -= 1;
             }
             if months < 0 {
@@ -240,35 +263,26 @@ pub fn fn_datedif(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
             months as f64
         }
         "YD" => {
-            let mut d1_this_year = NaiveDate::from_ymd_opt(
-                d2.year(),
-                d1.month(),
-                d1.day().min(last_day_of_month(d2.year(), d1.month())),
-            );
-            let d1_this_year = match d1_this_year.take() {
-                Some(d) => d,
-                None => return Ok(CellValue::Error("#VALUE!".to_string())),
+            // The start date moved into the year of the end date, or the
+            // year before if it falls after the end date.
+            let anniversary =
+                |year: i32| ymd_to_serial(system, year, m1, d1.min(last_day_of_month(year, m1)));
+            let start = match anniversary(y2) {
+                Some(start) if start <= s2.floor() => Some(start),
+                _ => anniversary(y2 - 1),
             };
-            let days = if d2 >= d1_this_year {
-                (d2 - d1_this_year).num_days()
-            } else {
-                let d1_last_year = NaiveDate::from_ymd_opt(
-                    d2.year() - 1,
-                    d1.month(),
-                    d1.day().min(last_day_of_month(d2.year() - 1, d1.month())),
-                )
-                .unwrap();
-                (d2 - d1_last_year).num_days()
-            };
-            days as f64
+            match start {
+                Some(start) => s2.floor() - start,
+                None => return Ok(CellValue::Error("#VALUE!".to_string())),
+            }
         }
         "MD" => {
-            let mut days = d2.day() as i32 - d1.day() as i32;
+            let mut days = d2 as i32 - d1 as i32;
             if days < 0 {
-                let prev_month_end = if d2.month() == 1 {
-                    last_day_of_month(d2.year() - 1, 12)
+                let prev_month_end = if m2 == 1 {
+                    last_day_of_month(y2 - 1, 12)
                 } else {
-                    last_day_of_month(d2.year(), d2.month() - 1)
+                    last_day_of_month(y2, m2 - 1)
                 };
                 days += prev_month_end as i32;
             }
@@ -282,18 +296,20 @@ pub fn fn_datedif(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 /// EDATE(start_date, months) - date that is N months before/after start_date.
 pub fn fn_edate(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("EDATE", args, 2, 2)?;
+    let system = ctx.date_system();
     let v = ctx.eval_expr(&args[0])?;
     let months = coerce_to_number(&ctx.eval_expr(&args[1])?)? as i32;
-    let serial = match to_serial(&v) {
+    let serial = match to_serial(&v, system) {
         Ok(n) => n,
         Err(e) => return Ok(e),
     };
-    let date = match serial_to_date(serial) {
+    let date = match system.serial_to_ymd(serial) {
         Some(d) => d,
         None => return Ok(CellValue::Error("#VALUE!".to_string())),
     };
-    match add_months_to_date(date, months) {
-        Some(d) => Ok(CellValue::Date(date_to_serial(d))),
+    let (year, month, day) = add_months(date, months);
+    match ymd_to_serial(system, year, month, day) {
+        Some(serial) => Ok(CellValue::Date(serial)),
         None => Ok(CellValue::Error("#VALUE!".to_string())),
     }
 }
@@ -301,22 +317,20 @@ pub fn fn_edate(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 /// EOMONTH(start_date, months) - last day of the month N months away.
 pub fn fn_eomonth(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("EOMONTH", args, 2, 2)?;
+    let system = ctx.date_system();
     let v = ctx.eval_expr(&args[0])?;
     let months = coerce_to_number(&ctx.eval_expr(&args[1])?)? as i32;
-    let serial = match to_serial(&v) {
+    let serial = match to_serial(&v, system) {
         Ok(n) => n,
         Err(e) => return Ok(e),
     };
-    let date = match serial_to_date(serial) {
+    let date = match system.serial_to_ymd(serial) {
         Some(d) => d,
         None => return Ok(CellValue::Error("#VALUE!".to_string())),
     };
-    match add_months_to_date(date, months) {
-        Some(d) => {
-            let eom = last_day_of_month(d.year(), d.month());
-            let result = NaiveDate::from_ymd_opt(d.year(), d.month(), eom).unwrap();
-            Ok(CellValue::Date(date_to_serial(result)))
-        }
+    let (year, month, _) = add_months(date, months);
+    match ymd_to_serial(system, year, month, last_day_of_month(year, month)) {
+        Some(serial) => Ok(CellValue::Date(serial)),
         None => Ok(CellValue::Error("#VALUE!".to_string())),
     }
 }
@@ -324,9 +338,10 @@ pub fn fn_eomonth(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 /// DATEVALUE(date_text) - converts a date string to a serial number.
 pub fn fn_datevalue(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("DATEVALUE", args, 1, 1)?;
+    let system = ctx.date_system();
     let text = coerce_to_string(&ctx.eval_expr(&args[0])?);
     match parse_date_string(&text) {
-        Some(d) => Ok(CellValue::Date(date_to_serial(d))),
+        Some(d) => Ok(CellValue::Date(system.date_to_serial(d))),
         None => Ok(CellValue::Error("#VALUE!".to_string())),
     }
 }
@@ -334,22 +349,21 @@ pub fn fn_datevalue(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 /// WEEKDAY(serial_number, [return_type]) - day of the week.
 pub fn fn_weekday(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("WEEKDAY", args, 1, 2)?;
+    let system = ctx.date_system();
     let v = ctx.eval_expr(&args[0])?;
     let return_type = if args.len() > 1 {
         coerce_to_number(&ctx.eval_expr(&args[1])?)? as i32
     } else {
         1
     };
-    let serial = match to_serial(&v) {
+    let serial = match to_serial(&v, system) {
         Ok(n) => n,
         Err(e) => return Ok(e),
     };
-    let date = match serial_to_date(serial) {
-        Some(d) => d,
-        None => return Ok(CellValue::Error("#VALUE!".to_string())),
-    };
-    // chrono: Mon=1 .. Sun=7
-    let weekday_num = date.weekday().num_days_from_monday(); // Mon=0 .. Sun=6
+    if system.to_1900_serial(serial).floor() < 0.0 {
+        return Ok(CellValue::Error("#NUM!".to_string()));
+    }
+    let weekday_num = weekday_from_monday(system, serial); // Mon=0 .. Sun=6
     let result = match return_type {
         1 => ((weekday_num + 1) % 7) + 1, // Sun=1, Mon=2, ..., Sat=7
         2 => weekday_num + 1,             // Mon=1, ..., Sun=7
@@ -362,23 +376,23 @@ pub fn fn_weekday(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 /// WEEKNUM(serial_number, [return_type]) - week number of the year.
 pub fn fn_weeknum(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("WEEKNUM", args, 1, 2)?;
+    let system = ctx.date_system();
     let v = ctx.eval_expr(&args[0])?;
     let return_type = if args.len() > 1 {
         coerce_to_number(&ctx.eval_expr(&args[1])?)? as i32
     } else {
         1
     };
-    let serial = match to_serial(&v) {
+    let serial = match to_serial(&v, system) {
         Ok(n) => n,
         Err(e) => return Ok(e),
     };
-    let date = match serial_to_date(serial) {
-        Some(d) => d,
+    let jan1 = match system.serial_to_ymd(serial) {
+        Some((year, _, _)) => ymd_to_serial(system, year, 1, 1).unwrap_or_default(),
         None => return Ok(CellValue::Error("#VALUE!".to_string())),
     };
-    let jan1 = NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap();
-    let jan1_weekday = jan1.weekday().num_days_from_monday(); // Mon=0..Sun=6
-    let day_of_year = date.ordinal() as i32;
+    let jan1_weekday = weekday_from_monday(system, jan1); // Mon=0..Sun=6
+    let day_of_year = (serial.floor() - jan1) as i32 + 1;
 
     let week_start_offset = match return_type {
         1 => (jan1_weekday + 1) % 7, // Sunday-based
@@ -392,24 +406,21 @@ pub fn fn_weeknum(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 /// NETWORKDAYS(start_date, end_date, [holidays]) - working days between two dates.
 pub fn fn_networkdays(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("NETWORKDAYS", args, 2, 3)?;
+    let system = ctx.date_system();
     let v1 = ctx.eval_expr(&args[0])?;
     let v2 = ctx.eval_expr(&args[1])?;
-    let s1 = match to_serial(&v1) {
+    let s1 = match to_serial(&v1, system) {
         Ok(n) => n,
         Err(e) => return Ok(e),
     };
-    let s2 = match to_serial(&v2) {
+    let s2 = match to_serial(&v2, system) {
         Ok(n) => n,
         Err(e) => return Ok(e),
     };
-    let d1 = match serial_to_date(s1) {
-        Some(d) => d,
-        None => return Ok(CellValue::Error("#VALUE!".to_string())),
-    };
-    let d2 = match serial_to_date(s2) {
-        Some(d) => d,
-        None => return Ok(CellValue::Error("#VALUE!".to_string())),
-    };
+    if system.serial_to_ymd(s1).is_none() || system.serial_to_ymd(s2).is_none() {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    let (d1, d2) = (s1.floor() as i64, s2.floor() as i64);
 
     let (start, end, sign) = if d1 <= d2 {
         (d1, d2, 1i32)
@@ -418,13 +429,11 @@ pub fn fn_networkdays(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     };
 
     let mut count = 0i32;
-    let mut current = start;
-    while current <= end {
-        let wd = current.weekday().num_days_from_monday();
+    for current in start..=end {
+        let wd = weekday_from_monday(system, current as f64);
         if wd < 5 {
             count += 1;
         }
-        current += Duration::days(1);
     }
     Ok(CellValue::Number((count * sign) as f64))
 }
@@ -432,28 +441,28 @@ pub fn fn_networkdays(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 /// WORKDAY(start_date, days, [holidays]) - date after N working days.
 pub fn fn_workday(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("WORKDAY", args, 2, 3)?;
+    let system = ctx.date_system();
     let v = ctx.eval_expr(&args[0])?;
     let days = coerce_to_number(&ctx.eval_expr(&args[1])?)? as i32;
-    let serial = match to_serial(&v) {
+    let serial = match to_serial(&v, system) {
         Ok(n) => n,
         Err(e) => return Ok(e),
     };
-    let start = match serial_to_date(serial) {
-        Some(d) => d,
-        None => return Ok(CellValue::Error("#VALUE!".to_string())),
-    };
+    if system.serial_to_ymd(serial).is_none() {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
 
-    let step = if days >= 0 { 1i64 } else { -1i64 };
+    let step = if days >= 0 { 1.0 } else { -1.0 };
     let mut remaining = days.unsigned_abs() as i32;
-    let mut current = start;
+    let mut current = serial.floor();
     while remaining > 0 {
-        current += Duration::days(step);
-        let wd = current.weekday().num_days_from_monday();
+        current += step;
+        let wd = weekday_from_monday(system, current);
         if wd < 5 {
             remaining -= 1;
         }
     }
-    Ok(CellValue::Date(date_to_serial(current)))
+    Ok(CellValue::Date(current))
 }
 
 #[cfg(test)]
@@ -483,6 +492,65 @@ mod tests {
         assert_eq!(result, CellValue::Date(expected));
     }
 
+    fn eval_1904(formula: &str) -> CellValue {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        snap.set_date_system(DateSystem::Date1904);
+        let expr = parse_formula(formula).unwrap();
+        evaluate(&expr, &snap).unwrap()
+    }
+
+    #[test]
+    fn test_date_leap_year_bug_boundary() {
+        assert_eq!(eval("DATE(1900,2,28)"), CellValue::Date(59.0));
+        assert_eq!(eval("DATE(1900,2,29)"), CellValue::Date(60.0));
+        assert_eq!(eval("DATE(1900,3,1)"), CellValue::Date(61.0));
+        assert_eq!(eval("DATE(1900,3,0)"), CellValue::Date(60.0));
+        assert_eq!(eval("DATE(1900,1,0)"), CellValue::Date(0.0));
+        assert_eq!(eval("DAY(59)"), CellValue::Number(28.0));
+        assert_eq!(eval("DAY(60)"), CellValue::Number(29.0));
+        assert_eq!(eval("MONTH(60)"), CellValue::Number(2.0));
+        assert_eq!(eval("DAY(61)"), CellValue::Number(1.0));
+        assert_eq!(
+            eval("TEXT(60,\"yyyy-mm-dd ddd\")"),
+            CellValue::String("1900-02-29 Wed".to_string())
+        );
+        assert_eq!(eval("EOMONTH(DATE(1900,2,1),0)"), CellValue::Date(60.0));
+        assert_eq!(eval("EDATE(DATE(1900,1,31),1)"), CellValue::Date(60.0));
+        assert_eq!(eval("EDATE(60,1)"), CellValue::Date(89.0));
+        assert_eq!(eval("DATEDIF(59,61,\"D\")"), CellValue::Number(2.0));
+        assert_eq!(eval("NETWORKDAYS(59,61)"), CellValue::Number(3.0));
+        assert_eq!(
+            eval("DATE(1900,0,1)"),
+            CellValue::Error("#NUM!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_date_1904_date_system() {
+        assert_eq!(eval_1904("DATE(1904,1,1)"), CellValue::Date(0.0));
+        assert_eq!(eval_1904("DATE(2024,1,15)"), CellValue::Date(43844.0));
+        assert_eq!(
+            eval_1904("DATE(1903,12,31)"),
+            CellValue::Error("#NUM!".to_string())
+        );
+        assert_eq!(eval_1904("YEAR(43844)"), CellValue::Number(2024.0));
+        assert_eq!(eval_1904("DAY(0)"), CellValue::Number(1.0));
+        assert_eq!(
+            eval_1904("DATEVALUE(\"2024-01-15\")"),
+            CellValue::Date(43844.0)
+        );
+        assert_eq!(
+            eval_1904("EOMONTH(DATE(2024,2,10),0)-DATE(2024,2,1)"),
+            CellValue::Number(28.0)
+        );
+        // 1904-01-01 was a Friday.
+        assert_eq!(eval_1904("WEEKDAY(0)"), CellValue::Number(6.0));
+        assert_eq!(
+            eval_1904("TEXT(43844,\"yyyy-mm-dd\")"),
+            CellValue::String("2024-01-15".to_string())
+        );
+    }
+
     #[test]
     fn test_year() {
         assert_eq!(eval("YEAR(DATE(2024,6,15))"), CellValue::Number(2024.0));
@@ -572,6 +640,15 @@ mod tests {
         assert_eq!(eval("WEEKDAY(DATE(2024,1,15),1)"), CellValue::Number(2.0));
     }
 
+    #[test]
+    fn test_weekday_before_phantom_leap_day() {
+        // Excel counts 1900-01-01 (serial 1) as a Sunday.
+        assert_eq!(eval("WEEKDAY(1)"), CellValue::Number(1.0));
+        assert_eq!(eval("WEEKDAY(0)"), CellValue::Number(7.0));
+        // 1900-03-01 (serial 61) was a Thursday.
+        assert_eq!(eval("WEEKDAY(61)"), CellValue::Number(5.0));
+    }
+
     #[test]
     fn test_weekday_type2() {
         // Monday = 1 in type 2
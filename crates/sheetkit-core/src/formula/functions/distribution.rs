@@ -0,0 +1,1231 @@
+//! Statistical distribution functions: the normal, log-normal, Student's t,
+//! chi-squared, F, binomial, negative binomial, hypergeometric, Poisson,
+//! exponential, gamma, beta and Weibull distributions and their inverses,
+//! together with GAMMA, GAMMALN, CONFIDENCE, PHI, GAUSS, FISHER and the
+//! legacy (pre-2010) names of these functions.
+
+use std::f64::consts::PI;
+
+use crate::cell::CellValue;
+use crate::error::Result;
+use crate::formula::ast::Expr;
+use crate::formula::eval::{coerce_to_bool, coerce_to_number, Evaluator};
+use crate::formula::functions::check_arg_count;
+
+/// Relative precision of the series and continued fractions below.
+const EPSILON: f64 = 1e-15;
+/// Smallest magnitude allowed in the continued fractions, to avoid division
+/// by zero.
+const TINY: f64 = 1e-300;
+/// Iteration limit of the series and continued fractions below.
+const MAX_ITERATIONS: usize = 10_000;
+
+fn num_error() -> CellValue {
+    CellValue::Error("#NUM!".to_string())
+}
+
+/// A finite result, or `#NUM!`.
+fn number(value: f64) -> CellValue {
+    if value.is_finite() {
+        CellValue::Number(value)
+    } else {
+        num_error()
+    }
+}
+
+fn number_arg(args: &[Expr], index: usize, ctx: &mut Evaluator) -> Result<f64> {
+    coerce_to_number(&ctx.eval_expr(&args[index])?)
+}
+
+fn opt_number_arg(args: &[Expr], index: usize, default: f64, ctx: &mut Evaluator) -> Result<f64> {
+    if index < args.len() {
+        number_arg(args, index, ctx)
+    } else {
+        Ok(default)
+    }
+}
+
+fn bool_arg(args: &[Expr], index: usize, ctx: &mut Evaluator) -> Result<bool> {
+    coerce_to_bool(&ctx.eval_expr(&args[index])?)
+}
+
+// -- Special functions --
+
+/// Natural logarithm of the absolute value of the gamma function, using the
+/// Lanczos approximation (g = 7, n = 9).
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection formula.
+        return (PI / (PI * x).sin()).abs().ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let sum = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .skip(1)
+        .fold(COEFFICIENTS[0], |acc, (i, c)| acc + c / (x + i as f64));
+    0.5 * (2.0 * PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
+
+/// The gamma function. Positive integers are computed exactly as
+/// factorials.
+pub(crate) fn gamma(x: f64) -> f64 {
+    if x == x.trunc() && (1.0..=171.0).contains(&x) {
+        return (2..x as u64).fold(1.0, |acc, i| acc * i as f64);
+    }
+    if x < 0.5 {
+        return PI / ((PI * x).sin() * gamma(1.0 - x));
+    }
+    ln_gamma(x).exp()
+}
+
+/// Natural logarithm of the beta function.
+fn ln_beta(a: f64, b: f64) -> f64 {
+    ln_gamma(a) + ln_gamma(b) - ln_gamma(a + b)
+}
+
+/// Natural logarithm of the binomial coefficient `n` choose `k`.
+fn ln_choose(n: f64, k: f64) -> f64 {
+    ln_gamma(n + 1.0) - ln_gamma(k + 1.0) - ln_gamma(n - k + 1.0)
+}
+
+/// Regularized lower incomplete gamma function P(a, x).
+pub(crate) fn gamma_p(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        0.0
+    } else if x < a + 1.0 {
+        gamma_series(a, x)
+    } else {
+        1.0 - gamma_continued_fraction(a, x)
+    }
+}
+
+/// Regularized upper incomplete gamma function Q(a, x) = 1 - P(a, x).
+pub(crate) fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        1.0
+    } else if x < a + 1.0 {
+        1.0 - gamma_series(a, x)
+    } else {
+        gamma_continued_fraction(a, x)
+    }
+}
+
+/// P(a, x) by its series expansion, which converges quickly for x < a + 1.
+fn gamma_series(a: f64, x: f64) -> f64 {
+    let mut term = 1.0 / a;
+    let mut sum = term;
+    let mut ap = a;
+    for _ in 0..MAX_ITERATIONS {
+        ap += 1.0;
+        term *= x / ap;
+        sum += term;
+        if term.abs() < sum.abs() * EPSILON {
+            break;
+        }
+    }
+    sum * (-x + a * x.ln() - ln_gamma(a)).exp()
+}
+
+/// Q(a, x) by its continued fraction (modified Lentz's method), which
+/// converges quickly for x >= a + 1.
+fn gamma_continued_fraction(a: f64, x: f64) -> f64 {
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / TINY;
+    let mut d = 1.0 / b;
+    let mut h = d;
+    for i in 1..MAX_ITERATIONS {
+        let an = -(i as f64) * (i as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = b + an / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    (-x + a * x.ln() - ln_gamma(a)).exp() * h
+}
+
+/// Regularized incomplete beta function I_x(a, b).
+pub(crate) fn beta_inc(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front = (a * x.ln() + b * (1.0 - x).ln() - ln_beta(a, b)).exp();
+    // The continued fraction converges quickly on this side of the mean;
+    // use the symmetry I_x(a, b) = 1 - I_(1-x)(b, a) on the other.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+/// The continued fraction of the incomplete beta function (modified Lentz's
+/// method).
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    let qab = a + b;
+    let qap = a + 1.0;
+    let qam = a - 1.0;
+    let mut c = 1.0;
+    let mut d = 1.0 - qab * x / qap;
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..MAX_ITERATIONS {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        let aa = m * (b - m) * x / ((qam + m2) * (a + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        h *= d * c;
+        let aa = -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2));
+        d = 1.0 + aa * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + aa / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    h
+}
+
+/// Standard normal probability density.
+fn norm_pdf(z: f64) -> f64 {
+    (-0.5 * z * z).exp() / (2.0 * PI).sqrt()
+}
+
+/// Standard normal cumulative distribution, via the complementary error
+/// function erfc(x) = Q(1/2, x^2) to keep precision in the tails.
+pub(crate) fn norm_cdf(z: f64) -> f64 {
+    let tail = 0.5 * gamma_q(0.5, 0.5 * z * z);
+    if z < 0.0 {
+        tail
+    } else {
+        1.0 - tail
+    }
+}
+
+/// Inverse of the standard normal cumulative distribution for p in (0, 1):
+/// Acklam's rational approximation refined with one step of Halley's
+/// method.
+pub(crate) fn norm_s_inv(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const LOW: f64 = 0.024_25;
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    let x = if p < LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    };
+    let e = norm_cdf(x) - p;
+    let u = e * (2.0 * PI).sqrt() * (0.5 * x * x).exp();
+    x - u / (1.0 + 0.5 * x * u)
+}
+
+/// Find x >= 0 with `cdf(x) = p` by bisection, for a continuous increasing
+/// `cdf` with `cdf(0) = 0`. The upper end of the search starts at `hi` (or
+/// at 1 when `hi` is infinite) and doubles until it brackets p.
+fn invert_cdf(p: f64, hi: f64, cdf: impl Fn(f64) -> f64) -> f64 {
+    let mut lo = 0.0;
+    let mut hi = if hi.is_finite() {
+        hi
+    } else {
+        let mut hi = 1.0;
+        while cdf(hi) < p && hi < 1e300 {
+            lo = hi;
+            hi *= 2.0;
+        }
+        hi
+    };
+    for _ in 0..1100 {
+        let mid = 0.5 * (lo + hi);
+        if mid <= lo || mid >= hi {
+            break;
+        }
+        if cdf(mid) < p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    0.5 * (lo + hi)
+}
+
+/// Student's t cumulative distribution with `df` degrees of freedom.
+fn t_cdf(x: f64, df: f64) -> f64 {
+    let tail = 0.5 * beta_inc(0.5 * df, 0.5, df / (df + x * x));
+    if x < 0.0 {
+        tail
+    } else {
+        1.0 - tail
+    }
+}
+
+/// Left-tailed inverse of Student's t distribution for p in (0, 1).
+fn t_inv(p: f64, df: f64) -> f64 {
+    if p < 0.5 {
+        -t_inv(1.0 - p, df)
+    } else if p == 0.5 {
+        0.0
+    } else {
+        invert_cdf(p, f64::INFINITY, |x| t_cdf(x, df))
+    }
+}
+
+fn chisq_cdf(x: f64, df: f64) -> f64 {
+    gamma_p(0.5 * df, 0.5 * x)
+}
+
+fn chisq_inv(p: f64, df: f64) -> f64 {
+    invert_cdf(p, f64::INFINITY, |x| chisq_cdf(x, df))
+}
+
+fn f_cdf(x: f64, d1: f64, d2: f64) -> f64 {
+    beta_inc(0.5 * d1, 0.5 * d2, d1 * x / (d1 * x + d2))
+}
+
+fn f_inv(p: f64, d1: f64, d2: f64) -> f64 {
+    invert_cdf(p, f64::INFINITY, |x| f_cdf(x, d1, d2))
+}
+
+// -- Normal and log-normal --
+
+/// NORM.DIST(x, mean, standard_dev, cumulative) and NORMDIST
+pub fn fn_norm_dist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("NORM.DIST", args, 4, 4)?;
+    let x = number_arg(args, 0, ctx)?;
+    let mean = number_arg(args, 1, ctx)?;
+    let sd = number_arg(args, 2, ctx)?;
+    let cumulative = bool_arg(args, 3, ctx)?;
+    if sd <= 0.0 {
+        return Ok(num_error());
+    }
+    let z = (x - mean) / sd;
+    Ok(number(if cumulative {
+        norm_cdf(z)
+    } else {
+        norm_pdf(z) / sd
+    }))
+}
+
+/// NORM.INV(probability, mean, standard_dev) and NORMINV
+pub fn fn_norm_inv(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("NORM.INV", args, 3, 3)?;
+    let p = number_arg(args, 0, ctx)?;
+    let mean = number_arg(args, 1, ctx)?;
+    let sd = number_arg(args, 2, ctx)?;
+    if p <= 0.0 || p >= 1.0 || sd <= 0.0 {
+        return Ok(num_error());
+    }
+    Ok(number(mean + sd * norm_s_inv(p)))
+}
+
+/// NORM.S.DIST(z, cumulative)
+pub fn fn_norm_s_dist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("NORM.S.DIST", args, 2, 2)?;
+    let z = number_arg(args, 0, ctx)?;
+    let cumulative = bool_arg(args, 1, ctx)?;
+    Ok(number(if cumulative { norm_cdf(z) } else { norm_pdf(z) }))
+}
+
+/// NORMSDIST(z) - standard normal cumulative distribution
+pub fn fn_normsdist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("NORMSDIST", args, 1, 1)?;
+    let z = number_arg(args, 0, ctx)?;
+    Ok(number(norm_cdf(z)))
+}
+
+/// NORM.S.INV(probability) and NORMSINV
+pub fn fn_norm_s_inv(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("NORM.S.INV", args, 1, 1)?;
+    let p = number_arg(args, 0, ctx)?;
+    if p <= 0.0 || p >= 1.0 {
+        return Ok(num_error());
+    }
+    Ok(number(norm_s_inv(p)))
+}
+
+/// LOGNORM.DIST(x, mean, standard_dev, cumulative)
+pub fn fn_lognorm_dist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("LOGNORM.DIST", args, 4, 4)?;
+    let x = number_arg(args, 0, ctx)?;
+    let mean = number_arg(args, 1, ctx)?;
+    let sd = number_arg(args, 2, ctx)?;
+    let cumulative = bool_arg(args, 3, ctx)?;
+    if x <= 0.0 || sd <= 0.0 {
+        return Ok(num_error());
+    }
+    let z = (x.ln() - mean) / sd;
+    Ok(number(if cumulative {
+        norm_cdf(z)
+    } else {
+        norm_pdf(z) / (x * sd)
+    }))
+}
+
+/// LOGNORMDIST(x, mean, standard_dev) - cumulative log-normal distribution
+pub fn fn_lognormdist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("LOGNORMDIST", args, 3, 3)?;
+    let x = number_arg(args, 0, ctx)?;
+    let mean = number_arg(args, 1, ctx)?;
+    let sd = number_arg(args, 2, ctx)?;
+    if x <= 0.0 || sd <= 0.0 {
+        return Ok(num_error());
+    }
+    Ok(number(norm_cdf((x.ln() - mean) / sd)))
+}
+
+/// LOGNORM.INV(probability, mean, standard_dev) and LOGINV
+pub fn fn_lognorm_inv(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("LOGNORM.INV", args, 3, 3)?;
+    let p = number_arg(args, 0, ctx)?;
+    let mean = number_arg(args, 1, ctx)?;
+    let sd = number_arg(args, 2, ctx)?;
+    if p <= 0.0 || p >= 1.0 || sd <= 0.0 {
+        return Ok(num_error());
+    }
+    Ok(number((mean + sd * norm_s_inv(p)).exp()))
+}
+
+/// PHI(x) - standard normal probability density
+pub fn fn_phi(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("PHI", args, 1, 1)?;
+    let x = number_arg(args, 0, ctx)?;
+    Ok(number(norm_pdf(x)))
+}
+
+/// GAUSS(z) - probability that a standard normal value lies between the
+/// mean and z standard deviations from it
+pub fn fn_gauss(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("GAUSS", args, 1, 1)?;
+    let z = number_arg(args, 0, ctx)?;
+    Ok(number(norm_cdf(z) - 0.5))
+}
+
+/// CONFIDENCE.NORM(alpha, standard_dev, size) and CONFIDENCE
+pub fn fn_confidence_norm(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("CONFIDENCE.NORM", args, 3, 3)?;
+    let alpha = number_arg(args, 0, ctx)?;
+    let sd = number_arg(args, 1, ctx)?;
+    let size = number_arg(args, 2, ctx)?.trunc();
+    if alpha <= 0.0 || alpha >= 1.0 || sd <= 0.0 || size < 1.0 {
+        return Ok(num_error());
+    }
+    Ok(number(norm_s_inv(1.0 - alpha / 2.0) * sd / size.sqrt()))
+}
+
+/// CONFIDENCE.T(alpha, standard_dev, size)
+pub fn fn_confidence_t(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("CONFIDENCE.T", args, 3, 3)?;
+    let alpha = number_arg(args, 0, ctx)?;
+    let sd = number_arg(args, 1, ctx)?;
+    let size = number_arg(args, 2, ctx)?.trunc();
+    if alpha <= 0.0 || alpha >= 1.0 || sd <= 0.0 || size < 1.0 {
+        return Ok(num_error());
+    }
+    if size == 1.0 {
+        return Ok(CellValue::Error("#DIV/0!".to_string()));
+    }
+    Ok(number(
+        t_inv(1.0 - alpha / 2.0, size - 1.0) * sd / size.sqrt(),
+    ))
+}
+
+/// FISHER(x) - Fisher transformation
+pub fn fn_fisher(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("FISHER", args, 1, 1)?;
+    let x = number_arg(args, 0, ctx)?;
+    if x.abs() >= 1.0 {
+        return Ok(num_error());
+    }
+    Ok(number(x.atanh()))
+}
+
+/// FISHERINV(y) - inverse of the Fisher transformation
+pub fn fn_fisherinv(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("FISHERINV", args, 1, 1)?;
+    let y = number_arg(args, 0, ctx)?;
+    Ok(number(y.tanh()))
+}
+
+// -- Student's t --
+
+/// Evaluate a degrees-of-freedom argument, truncated. Returns `None` below
+/// 1.
+fn df_arg(args: &[Expr], index: usize, ctx: &mut Evaluator) -> Result<Option<f64>> {
+    let df = number_arg(args, index, ctx)?.trunc();
+    Ok((df >= 1.0).then_some(df))
+}
+
+/// T.DIST(x, deg_freedom, cumulative)
+pub fn fn_t_dist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("T.DIST", args, 3, 3)?;
+    let x = number_arg(args, 0, ctx)?;
+    let Some(df) = df_arg(args, 1, ctx)? else {
+        return Ok(num_error());
+    };
+    let cumulative = bool_arg(args, 2, ctx)?;
+    if cumulative {
+        return Ok(number(t_cdf(x, df)));
+    }
+    let ln_pdf = ln_gamma(0.5 * (df + 1.0))
+        - ln_gamma(0.5 * df)
+        - 0.5 * (df * PI).ln()
+        - 0.5 * (df + 1.0) * (1.0 + x * x / df).ln();
+    Ok(number(ln_pdf.exp()))
+}
+
+/// T.DIST.2T(x, deg_freedom) - two-tailed t distribution
+pub fn fn_t_dist_2t(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("T.DIST.2T", args, 2, 2)?;
+    let x = number_arg(args, 0, ctx)?;
+    let Some(df) = df_arg(args, 1, ctx)? else {
+        return Ok(num_error());
+    };
+    if x < 0.0 {
+        return Ok(num_error());
+    }
+    Ok(number(beta_inc(0.5 * df, 0.5, df / (df + x * x))))
+}
+
+/// T.DIST.RT(x, deg_freedom) - right-tailed t distribution
+pub fn fn_t_dist_rt(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("T.DIST.RT", args, 2, 2)?;
+    let x = number_arg(args, 0, ctx)?;
+    let Some(df) = df_arg(args, 1, ctx)? else {
+        return Ok(num_error());
+    };
+    Ok(number(t_cdf(-x, df)))
+}
+
+/// TDIST(x, deg_freedom, tails) - one- or two-tailed t distribution
+pub fn fn_tdist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("TDIST", args, 3, 3)?;
+    let x = number_arg(args, 0, ctx)?;
+    let Some(df) = df_arg(args, 1, ctx)? else {
+        return Ok(num_error());
+    };
+    let tails = number_arg(args, 2, ctx)?.trunc();
+    if x < 0.0 || !(tails == 1.0 || tails == 2.0) {
+        return Ok(num_error());
+    }
+    Ok(number(tails * t_cdf(-x, df)))
+}
+
+/// T.INV(probability, deg_freedom) - left-tailed inverse
+pub fn fn_t_inv(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("T.INV", args, 2, 2)?;
+    let p = number_arg(args, 0, ctx)?;
+    let Some(df) = df_arg(args, 1, ctx)? else {
+        return Ok(num_error());
+    };
+    if p <= 0.0 || p >= 1.0 {
+        return Ok(num_error());
+    }
+    Ok(number(t_inv(p, df)))
+}
+
+/// T.INV.2T(probability, deg_freedom) and TINV - two-tailed inverse
+pub fn fn_t_inv_2t(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("T.INV.2T", args, 2, 2)?;
+    let p = number_arg(args, 0, ctx)?;
+    let Some(df) = df_arg(args, 1, ctx)? else {
+        return Ok(num_error());
+    };
+    if p <= 0.0 || p > 1.0 {
+        return Ok(num_error());
+    }
+    Ok(number(t_inv(1.0 - p / 2.0, df)))
+}
+
+// -- Chi-squared and F --
+
+/// CHISQ.DIST(x, deg_freedom, cumulative)
+pub fn fn_chisq_dist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("CHISQ.DIST", args, 3, 3)?;
+    let x = number_arg(args, 0, ctx)?;
+    let Some(df) = df_arg(args, 1, ctx)? else {
+        return Ok(num_error());
+    };
+    let cumulative = bool_arg(args, 2, ctx)?;
+    if x < 0.0 {
+        return Ok(num_error());
+    }
+    if cumulative {
+        return Ok(number(chisq_cdf(x, df)));
+    }
+    let k = 0.5 * df;
+    let ln_pdf = (k - 1.0) * x.ln() - 0.5 * x - k * 2f64.ln() - ln_gamma(k);
+    Ok(number(if x == 0.0 && df == 2.0 {
+        0.5
+    } else {
+        ln_pdf.exp()
+    }))
+}
+
+/// CHISQ.DIST.RT(x, deg_freedom) and CHIDIST - right-tailed chi-squared
+/// distribution
+pub fn fn_chisq_dist_rt(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("CHISQ.DIST.RT", args, 2, 2)?;
+    let x = number_arg(args, 0, ctx)?;
+    let Some(df) = df_arg(args, 1, ctx)? else {
+        return Ok(num_error());
+    };
+    if x < 0.0 {
+        return Ok(num_error());
+    }
+    Ok(number(gamma_q(0.5 * df, 0.5 * x)))
+}
+
+/// CHISQ.INV(probability, deg_freedom) - left-tailed inverse
+pub fn fn_chisq_inv(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("CHISQ.INV", args, 2, 2)?;
+    let p = number_arg(args, 0, ctx)?;
+    let Some(df) = df_arg(args, 1, ctx)? else {
+        return Ok(num_error());
+    };
+    if !(0.0..1.0).contains(&p) {
+        return Ok(num_error());
+    }
+    Ok(number(chisq_inv(p, df)))
+}
+
+/// CHISQ.INV.RT(probability, deg_freedom) and CHIINV - right-tailed inverse
+pub fn fn_chisq_inv_rt(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("CHISQ.INV.RT", args, 2, 2)?;
+    let p = number_arg(args, 0, ctx)?;
+    let Some(df) = df_arg(args, 1, ctx)? else {
+        return Ok(num_error());
+    };
+    if p <= 0.0 || p > 1.0 {
+        return Ok(num_error());
+    }
+    Ok(number(chisq_inv(1.0 - p, df)))
+}
+
+/// Evaluate the x and degrees-of-freedom arguments of the F distribution
+/// functions. Returns `None` for a negative x or degrees of freedom below 1.
+fn f_args(args: &[Expr], ctx: &mut Evaluator) -> Result<Option<(f64, f64, f64)>> {
+    let x = number_arg(args, 0, ctx)?;
+    let d1 = number_arg(args, 1, ctx)?.trunc();
+    let d2 = number_arg(args, 2, ctx)?.trunc();
+    Ok((x >= 0.0 && d1 >= 1.0 && d2 >= 1.0).then_some((x, d1, d2)))
+}
+
+/// F.DIST(x, deg_freedom1, deg_freedom2, cumulative)
+pub fn fn_f_dist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("F.DIST", args, 4, 4)?;
+    let Some((x, d1, d2)) = f_args(args, ctx)? else {
+        return Ok(num_error());
+    };
+    let cumulative = bool_arg(args, 3, ctx)?;
+    if cumulative {
+        return Ok(number(f_cdf(x, d1, d2)));
+    }
+    if x == 0.0 {
+        return Ok(number(if d1 == 2.0 { 1.0 } else { 0.0 }));
+    }
+    let ln_pdf = 0.5 * (d1 * (d1 * x).ln() + d2 * d2.ln() - (d1 + d2) * (d1 * x + d2).ln())
+        - x.ln()
+        - ln_beta(0.5 * d1, 0.5 * d2);
+    Ok(number(ln_pdf.exp()))
+}
+
+/// F.DIST.RT(x, deg_freedom1, deg_freedom2) and FDIST - right-tailed F
+/// distribution
+pub fn fn_f_dist_rt(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("F.DIST.RT", args, 3, 3)?;
+    let Some((x, d1, d2)) = f_args(args, ctx)? else {
+        return Ok(num_error());
+    };
+    Ok(number(beta_inc(0.5 * d2, 0.5 * d1, d2 / (d2 + d1 * x))))
+}
+
+/// F.INV(probability, deg_freedom1, deg_freedom2) - left-tailed inverse
+pub fn fn_f_inv(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("F.INV", args, 3, 3)?;
+    let Some((p, d1, d2)) = f_args(args, ctx)? else {
+        return Ok(num_error());
+    };
+    if p >= 1.0 {
+        return Ok(num_error());
+    }
+    Ok(number(f_inv(p, d1, d2)))
+}
+
+/// F.INV.RT(probability, deg_freedom1, deg_freedom2) and FINV -
+/// right-tailed inverse
+pub fn fn_f_inv_rt(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("F.INV.RT", args, 3, 3)?;
+    let Some((p, d1, d2)) = f_args(args, ctx)? else {
+        return Ok(num_error());
+    };
+    if p == 0.0 || p > 1.0 {
+        return Ok(num_error());
+    }
+    Ok(number(f_inv(1.0 - p, d1, d2)))
+}
+
+// -- Discrete distributions --
+
+/// Binomial probability of exactly `k` successes in `n` trials.
+fn binom_pmf(k: f64, n: f64, p: f64) -> f64 {
+    if p == 0.0 {
+        return if k == 0.0 { 1.0 } else { 0.0 };
+    }
+    if p == 1.0 {
+        return if k == n { 1.0 } else { 0.0 };
+    }
+    (ln_choose(n, k) + k * p.ln() + (n - k) * (1.0 - p).ln()).exp()
+}
+
+/// Binomial probability of at most `k` successes in `n` trials.
+fn binom_cdf(k: f64, n: f64, p: f64) -> f64 {
+    if k >= n {
+        1.0
+    } else {
+        beta_inc(n - k, k + 1.0, 1.0 - p)
+    }
+}
+
+/// BINOM.DIST(number_s, trials, probability_s, cumulative) and BINOMDIST
+pub fn fn_binom_dist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("BINOM.DIST", args, 4, 4)?;
+    let k = number_arg(args, 0, ctx)?.trunc();
+    let n = number_arg(args, 1, ctx)?.trunc();
+    let p = number_arg(args, 2, ctx)?;
+    let cumulative = bool_arg(args, 3, ctx)?;
+    if k < 0.0 || k > n || !(0.0..=1.0).contains(&p) {
+        return Ok(num_error());
+    }
+    Ok(number(if cumulative {
+        binom_cdf(k, n, p)
+    } else {
+        binom_pmf(k, n, p)
+    }))
+}
+
+/// BINOM.INV(trials, probability_s, alpha) and CRITBINOM - smallest number
+/// of successes whose cumulative probability is at least alpha
+pub fn fn_binom_inv(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("BINOM.INV", args, 3, 3)?;
+    let n = number_arg(args, 0, ctx)?.trunc();
+    let p = number_arg(args, 1, ctx)?;
+    let alpha = number_arg(args, 2, ctx)?;
+    if n < 0.0 || !(0.0..=1.0).contains(&p) || alpha <= 0.0 || alpha >= 1.0 {
+        return Ok(num_error());
+    }
+    let mut cumulative = 0.0;
+    let mut k = 0.0;
+    while k < n {
+        cumulative += binom_pmf(k, n, p);
+        if cumulative >= alpha * (1.0 - EPSILON) {
+            break;
+        }
+        k += 1.0;
+    }
+    Ok(CellValue::Number(k))
+}
+
+/// NEGBINOM.DIST(number_f, number_s, probability_s, cumulative)
+pub fn fn_negbinom_dist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("NEGBINOM.DIST", args, 4, 4)?;
+    let f = number_arg(args, 0, ctx)?.trunc();
+    let s = number_arg(args, 1, ctx)?.trunc();
+    let p = number_arg(args, 2, ctx)?;
+    let cumulative = bool_arg(args, 3, ctx)?;
+    if f < 0.0 || s < 1.0 || !(0.0..=1.0).contains(&p) {
+        return Ok(num_error());
+    }
+    if cumulative {
+        return Ok(number(beta_inc(s, f + 1.0, p)));
+    }
+    Ok(number(negbinom_pmf(f, s, p)))
+}
+
+/// NEGBINOMDIST(number_f, number_s, probability_s) - probability mass only
+pub fn fn_negbinomdist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("NEGBINOMDIST", args, 3, 3)?;
+    let f = number_arg(args, 0, ctx)?.trunc();
+    let s = number_arg(args, 1, ctx)?.trunc();
+    let p = number_arg(args, 2, ctx)?;
+    if f < 0.0 || s < 1.0 || !(0.0..=1.0).contains(&p) {
+        return Ok(num_error());
+    }
+    Ok(number(negbinom_pmf(f, s, p)))
+}
+
+/// Probability of `f` failures before the `s`-th success.
+fn negbinom_pmf(f: f64, s: f64, p: f64) -> f64 {
+    if p == 0.0 {
+        return 0.0;
+    }
+    if p == 1.0 {
+        return if f == 0.0 { 1.0 } else { 0.0 };
+    }
+    (ln_choose(f + s - 1.0, f) + s * p.ln() + f * (1.0 - p).ln()).exp()
+}
+
+/// HYPGEOM.DIST(sample_s, number_sample, population_s, number_pop,
+/// [cumulative]) and HYPGEOMDIST
+pub fn fn_hypgeom_dist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("HYPGEOM.DIST", args, 4, 5)?;
+    let x = number_arg(args, 0, ctx)?.trunc();
+    let n = number_arg(args, 1, ctx)?.trunc();
+    let successes = number_arg(args, 2, ctx)?.trunc();
+    let population = number_arg(args, 3, ctx)?.trunc();
+    let cumulative = args.len() > 4 && bool_arg(args, 4, ctx)?;
+    let min = (n - (population - successes)).max(0.0);
+    if n <= 0.0
+        || successes <= 0.0
+        || n > population
+        || successes > population
+        || x < min
+        || x > n.min(successes)
+    {
+        return Ok(num_error());
+    }
+    let pmf = |k: f64| {
+        (ln_choose(successes, k) + ln_choose(population - successes, n - k)
+            - ln_choose(population, n))
+        .exp()
+    };
+    if !cumulative {
+        return Ok(number(pmf(x)));
+    }
+    let mut total = 0.0;
+    let mut k = min;
+    while k <= x {
+        total += pmf(k);
+        k += 1.0;
+    }
+    Ok(number(total.min(1.0)))
+}
+
+/// POISSON.DIST(x, mean, cumulative) and POISSON
+pub fn fn_poisson_dist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("POISSON.DIST", args, 3, 3)?;
+    let x = number_arg(args, 0, ctx)?.trunc();
+    let mean = number_arg(args, 1, ctx)?;
+    let cumulative = bool_arg(args, 2, ctx)?;
+    if x < 0.0 || mean < 0.0 {
+        return Ok(num_error());
+    }
+    if mean == 0.0 {
+        return Ok(CellValue::Number(if cumulative || x == 0.0 {
+            1.0
+        } else {
+            0.0
+        }));
+    }
+    Ok(number(if cumulative {
+        gamma_q(x + 1.0, mean)
+    } else {
+        (-mean + x * mean.ln() - ln_gamma(x + 1.0)).exp()
+    }))
+}
+
+// -- Continuous distributions --
+
+/// EXPON.DIST(x, lambda, cumulative) and EXPONDIST
+pub fn fn_expon_dist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("EXPON.DIST", args, 3, 3)?;
+    let x = number_arg(args, 0, ctx)?;
+    let lambda = number_arg(args, 1, ctx)?;
+    let cumulative = bool_arg(args, 2, ctx)?;
+    if x < 0.0 || lambda <= 0.0 {
+        return Ok(num_error());
+    }
+    Ok(number(if cumulative {
+        -(-lambda * x).exp_m1()
+    } else {
+        lambda * (-lambda * x).exp()
+    }))
+}
+
+/// GAMMA(number) - the gamma function
+pub fn fn_gamma(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("GAMMA", args, 1, 1)?;
+    let x = number_arg(args, 0, ctx)?;
+    if x <= 0.0 && x == x.trunc() {
+        return Ok(num_error());
+    }
+    Ok(number(gamma(x)))
+}
+
+/// GAMMALN(x) and GAMMALN.PRECISE - natural logarithm of the gamma function
+pub fn fn_gammaln(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("GAMMALN", args, 1, 1)?;
+    let x = number_arg(args, 0, ctx)?;
+    if x <= 0.0 {
+        return Ok(num_error());
+    }
+    Ok(number(ln_gamma(x)))
+}
+
+/// GAMMA.DIST(x, alpha, beta, cumulative) and GAMMADIST
+pub fn fn_gamma_dist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("GAMMA.DIST", args, 4, 4)?;
+    let x = number_arg(args, 0, ctx)?;
+    let alpha = number_arg(args, 1, ctx)?;
+    let beta = number_arg(args, 2, ctx)?;
+    let cumulative = bool_arg(args, 3, ctx)?;
+    if x < 0.0 || alpha <= 0.0 || beta <= 0.0 {
+        return Ok(num_error());
+    }
+    if cumulative {
+        return Ok(number(gamma_p(alpha, x / beta)));
+    }
+    if x == 0.0 {
+        return Ok(number(if alpha == 1.0 { 1.0 / beta } else { 0.0 }));
+    }
+    let ln_pdf = (alpha - 1.0) * x.ln() - x / beta - alpha * beta.ln() - ln_gamma(alpha);
+    Ok(number(ln_pdf.exp()))
+}
+
+/// GAMMA.INV(probability, alpha, beta) and GAMMAINV
+pub fn fn_gamma_inv(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("GAMMA.INV", args, 3, 3)?;
+    let p = number_arg(args, 0, ctx)?;
+    let alpha = number_arg(args, 1, ctx)?;
+    let beta = number_arg(args, 2, ctx)?;
+    if !(0.0..1.0).contains(&p) || alpha <= 0.0 || beta <= 0.0 {
+        return Ok(num_error());
+    }
+    Ok(number(
+        beta * invert_cdf(p, f64::INFINITY, |x| gamma_p(alpha, x)),
+    ))
+}
+
+/// Evaluate the shape parameters and the optional bounds of the beta
+/// distribution functions, starting at argument `first`. Returns `None` for
+/// non-positive shapes or empty bounds.
+fn beta_args(
+    args: &[Expr],
+    first: usize,
+    ctx: &mut Evaluator,
+) -> Result<Option<(f64, f64, f64, f64)>> {
+    let alpha = number_arg(args, first, ctx)?;
+    let beta = number_arg(args, first + 1, ctx)?;
+    let lower = opt_number_arg(args, first + 2, 0.0, ctx)?;
+    let upper = opt_number_arg(args, first + 3, 1.0, ctx)?;
+    Ok((alpha > 0.0 && beta > 0.0 && lower < upper).then_some((alpha, beta, lower, upper)))
+}
+
+/// BETA.DIST(x, alpha, beta, cumulative, [A], [B])
+pub fn fn_beta_dist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("BETA.DIST", args, 4, 6)?;
+    let x = number_arg(args, 0, ctx)?;
+    let alpha = number_arg(args, 1, ctx)?;
+    let beta = number_arg(args, 2, ctx)?;
+    let cumulative = bool_arg(args, 3, ctx)?;
+    let lower = opt_number_arg(args, 4, 0.0, ctx)?;
+    let upper = opt_number_arg(args, 5, 1.0, ctx)?;
+    if alpha <= 0.0 || beta <= 0.0 || lower >= upper || x < lower || x > upper {
+        return Ok(num_error());
+    }
+    let z = (x - lower) / (upper - lower);
+    if cumulative {
+        return Ok(number(beta_inc(alpha, beta, z)));
+    }
+    let ln_pdf = (alpha - 1.0) * z.ln() + (beta - 1.0) * (1.0 - z).ln() - ln_beta(alpha, beta);
+    Ok(number(ln_pdf.exp() / (upper - lower)))
+}
+
+/// BETADIST(x, alpha, beta, [A], [B]) - cumulative beta distribution
+pub fn fn_betadist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("BETADIST", args, 3, 5)?;
+    let x = number_arg(args, 0, ctx)?;
+    let Some((alpha, beta, lower, upper)) = beta_args(args, 1, ctx)? else {
+        return Ok(num_error());
+    };
+    if x < lower || x > upper {
+        return Ok(num_error());
+    }
+    Ok(number(beta_inc(alpha, beta, (x - lower) / (upper - lower))))
+}
+
+/// BETA.INV(probability, alpha, beta, [A], [B]) and BETAINV
+pub fn fn_beta_inv(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("BETA.INV", args, 3, 5)?;
+    let p = number_arg(args, 0, ctx)?;
+    let Some((alpha, beta, lower, upper)) = beta_args(args, 1, ctx)? else {
+        return Ok(num_error());
+    };
+    if !(0.0..=1.0).contains(&p) {
+        return Ok(num_error());
+    }
+    let z = invert_cdf(p, 1.0, |z| beta_inc(alpha, beta, z));
+    Ok(number(lower + z * (upper - lower)))
+}
+
+/// WEIBULL.DIST(x, alpha, beta, cumulative) and WEIBULL
+pub fn fn_weibull_dist(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("WEIBULL.DIST", args, 4, 4)?;
+    let x = number_arg(args, 0, ctx)?;
+    let alpha = number_arg(args, 1, ctx)?;
+    let beta = number_arg(args, 2, ctx)?;
+    let cumulative = bool_arg(args, 3, ctx)?;
+    if x < 0.0 || alpha <= 0.0 || beta <= 0.0 {
+        return Ok(num_error());
+    }
+    let scaled = (x / beta).powf(alpha);
+    Ok(number(if cumulative {
+        -(-scaled).exp_m1()
+    } else {
+        alpha / beta.powf(alpha) * x.powf(alpha - 1.0) * (-scaled).exp()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cell::CellValue;
+    use crate::formula::eval::{evaluate, CellSnapshot};
+    use crate::formula::parser::parse_formula;
+
+    fn eval(formula: &str) -> CellValue {
+        let snap = CellSnapshot::new("Sheet1".to_string());
+        let expr = parse_formula(formula).unwrap();
+        evaluate(&expr, &snap).unwrap()
+    }
+
+    /// Assert that `formula` evaluates to `expected` within a relative
+    /// tolerance of 1e-9 (Excel's published results have up to 10 digits).
+    fn assert_close(formula: &str, expected: f64) {
+        match eval(formula) {
+            CellValue::Number(n) => assert!(
+                (n - expected).abs() <= 1e-9 * expected.abs().max(1.0),
+                "{formula}: expected {expected}, got {n}"
+            ),
+            other => panic!("{formula}: expected number, got {other:?}"),
+        }
+    }
+
+    fn assert_num_error(formula: &str) {
+        assert_eq!(
+            eval(formula),
+            CellValue::Error("#NUM!".to_string()),
+            "{formula}"
+        );
+    }
+
+    #[test]
+    fn normal_distribution() {
+        assert_close("NORM.DIST(42,40,1.5,TRUE)", 0.908_788_780_274_132);
+        assert_close("NORM.DIST(42,40,1.5,FALSE)", 0.109_340_049_783_996);
+        assert_close("NORMDIST(42,40,1.5,TRUE)", 0.908_788_780_274_132);
+        assert_close("NORM.S.DIST(1.333333,TRUE)", 0.908_788_725_604_095);
+        assert_close("NORM.S.DIST(0,FALSE)", 0.398_942_280_401_433);
+        assert_close("NORMSDIST(-3)", 0.001_349_898_031_630);
+        assert_num_error("NORM.DIST(42,40,0,TRUE)");
+    }
+
+    #[test]
+    fn normal_inverse() {
+        assert_close("NORM.INV(0.908789,40,1.5)", 42.000_002_009_566_2);
+        assert_close("NORM.S.INV(0.908789)", 1.333_334_673_044_11);
+        assert_close("NORMSINV(0.5)", 0.0);
+        assert_close("NORM.S.INV(0.001)", -3.090_232_306_167_81);
+        assert_num_error("NORM.S.INV(0)");
+        assert_num_error("NORM.INV(1,0,1)");
+    }
+
+    #[test]
+    fn lognormal_distribution() {
+        assert_close("LOGNORM.DIST(4,3.5,1.2,TRUE)", 0.039_083_555_706_800_5);
+        assert_close("LOGNORM.DIST(4,3.5,1.2,FALSE)", 0.017_617_596_681_819_2);
+        assert_close("LOGNORMDIST(4,3.5,1.2)", 0.039_083_555_706_800_5);
+        assert_close("LOGNORM.INV(0.039084,3.5,1.2)", 4.000_025_218_680_64);
+        assert_num_error("LOGNORM.DIST(0,3.5,1.2,TRUE)");
+    }
+
+    #[test]
+    fn t_distribution() {
+        assert_close("T.DIST(60,1,TRUE)", 0.994_695_326_367_377);
+        assert_close("T.DIST(8,3,FALSE)", 0.000_736_906_520_946_926);
+        assert_close("T.DIST.2T(1.959999998,60)", 0.054_644_929_975_920_9);
+        assert_close("T.DIST.RT(1.959999998,60)", 0.027_322_464_987_960_4);
+        assert_close("TDIST(1.959999998,60,2)", 0.054_644_929_975_920_9);
+        assert_close("T.INV(0.75,2)", 0.816_496_580_927_726);
+        assert_close("T.INV.2T(0.546449,60)", 0.606_533_075_825_755);
+        assert_close("TINV(0.546449,60)", 0.606_533_075_825_755);
+        assert_num_error("T.DIST(1,0,TRUE)");
+        assert_num_error("T.DIST.2T(-1,2)");
+    }
+
+    #[test]
+    fn chi_squared_distribution() {
+        assert_close("CHISQ.DIST(0.5,1,TRUE)", 0.520_499_877_813_047);
+        assert_close("CHISQ.DIST(2,3,FALSE)", 0.207_553_748_710_297);
+        assert_close("CHISQ.DIST.RT(18.307,10)", 0.050_000_589_091_398_1);
+        assert_close("CHIDIST(18.307,10)", 0.050_000_589_091_398_1);
+        assert_close("CHISQ.INV(0.93,1)", 3.283_020_286_759_54);
+        assert_close("CHISQ.INV.RT(0.050001,10)", 18.306_973_456_961_1);
+        assert_num_error("CHISQ.DIST(-1,2,TRUE)");
+    }
+
+    #[test]
+    fn f_distribution() {
+        assert_close("F.DIST(15.2069,6,4,TRUE)", 0.990_000_043_002_763);
+        assert_close("F.DIST(15.2069,6,4,FALSE)", 0.001_223_791_708_783_17);
+        assert_close("F.DIST.RT(15.2069,6,4)", 0.009_999_956_997_237_3);
+        assert_close("FDIST(15.2069,6,4)", 0.009_999_956_997_237_3);
+        assert_close("F.INV(0.01,6,4)", 0.109_309_914_124_579);
+        assert_close("F.INV.RT(0.01,6,4)", 15.206_864_861_157_5);
+        assert_num_error("F.DIST(1,0,4,TRUE)");
+    }
+
+    #[test]
+    fn binomial_distribution() {
+        assert_close("BINOM.DIST(6,10,0.5,FALSE)", 0.205_078_125);
+        assert_close("BINOM.DIST(6,10,0.5,TRUE)", 0.828_125);
+        assert_close("BINOMDIST(0,10,0,FALSE)", 1.0);
+        assert_eq!(eval("BINOM.INV(6,0.5,0.75)"), CellValue::Number(4.0));
+        assert_eq!(eval("CRITBINOM(6,0.5,0.01)"), CellValue::Number(0.0));
+        assert_num_error("BINOM.DIST(11,10,0.5,FALSE)");
+        assert_num_error("BINOM.INV(6,1.5,0.75)");
+        assert_num_error("BINOM.INV(6,0.5,0)");
+        assert_num_error("CRITBINOM(6,0.5,1)");
+    }
+
+    #[test]
+    fn other_discrete_distributions() {
+        assert_close("NEGBINOM.DIST(10,5,0.25,TRUE)", 0.313_514_058_478_177);
+        assert_close("NEGBINOM.DIST(10,5,0.25,FALSE)", 0.055_048_660_375_177_9);
+        assert_close("NEGBINOMDIST(10,5,0.25)", 0.055_048_660_375_177_9);
+        assert_close("HYPGEOM.DIST(1,4,8,20,TRUE)", 0.465_428_276_573_787);
+        assert_close("HYPGEOM.DIST(1,4,8,20,FALSE)", 0.363_261_093_911_249);
+        assert_close("POISSON.DIST(2,5,TRUE)", 0.124_652_019_483_081);
+        assert_close("POISSON.DIST(2,5,FALSE)", 0.084_224_337_488_568);
+        assert_close("POISSON(0,0,FALSE)", 1.0);
+        assert_num_error("HYPGEOM.DIST(5,4,8,20,TRUE)");
+        assert_num_error("POISSON.DIST(-1,5,TRUE)");
+    }
+
+    #[test]
+    fn exponential_and_weibull() {
+        assert_close("EXPON.DIST(0.2,10,TRUE)", 0.864_664_716_763_387);
+        assert_close("EXPON.DIST(0.2,10,FALSE)", 1.353_352_832_366_13);
+        assert_close("WEIBULL.DIST(105,20,100,TRUE)", 0.929_581_390_069_277);
+        assert_close("WEIBULL.DIST(105,20,100,FALSE)", 0.035_588_864_024_504_4);
+        assert_num_error("EXPON.DIST(1,0,TRUE)");
+    }
+
+    #[test]
+    fn gamma_functions() {
+        assert_close("GAMMA(2.5)", 1.329_340_388_179_14);
+        assert_close("GAMMA(-0.75)", -4.834_146_544_295_88);
+        assert_eq!(eval("GAMMA(5)"), CellValue::Number(24.0));
+        assert_close("GAMMALN(4)", 1.791_759_469_228_05);
+        assert_close("GAMMALN.PRECISE(0.5)", 0.572_364_942_924_7);
+        assert_close("GAMMA.DIST(10.00001131,9,2,TRUE)", 0.068_094_003_869_787_3);
+        assert_close("GAMMA.DIST(10.00001131,9,2,FALSE)", 0.032_639_130_418_294);
+        assert_close("GAMMA.INV(0.068094,9,2)", 10.000_011_191_437_2);
+        assert_num_error("GAMMA(0)");
+        assert_num_error("GAMMA(-2)");
+        assert_num_error("GAMMALN(0)");
+    }
+
+    #[test]
+    fn beta_distribution() {
+        assert_close("BETA.DIST(2,8,10,TRUE,1,3)", 0.685_470_581_054_687);
+        assert_close("BETA.DIST(2,8,10,FALSE,1,3)", 1.483_764_648_437_5);
+        assert_close("BETADIST(2,8,10,1,3)", 0.685_470_581_054_687);
+        assert_close("BETA.INV(0.685470581,8,10,1,3)", 1.999_999_999_963_14);
+        assert_num_error("BETA.DIST(4,8,10,TRUE,1,3)");
+    }
+
+    #[test]
+    fn confidence_and_transforms() {
+        assert_close("CONFIDENCE.NORM(0.05,2.5,50)", 0.692_951_912_174_839);
+        assert_close("CONFIDENCE(0.05,2.5,50)", 0.692_951_912_174_839);
+        assert_close("CONFIDENCE.T(0.05,1,50)", 0.284_196_855_495_73);
+        assert_eq!(
+            eval("CONFIDENCE.T(0.05,1,1)"),
+            CellValue::Error("#DIV/0!".to_string())
+        );
+        assert_close("PHI(0.75)", 0.301_137_432_154_804);
+        assert_close("GAUSS(2)", 0.477_249_868_051_821);
+        assert_close("FISHER(0.75)", 0.972_955_074_527_657);
+        assert_close("FISHERINV(0.972955)", 0.749_999_967_394_148);
+        assert_num_error("FISHER(1)");
+    }
+}
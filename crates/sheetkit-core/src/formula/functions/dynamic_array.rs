@@ -0,0 +1,457 @@
+//! Dynamic array formula functions: FILTER, SORT, SORTBY, UNIQUE, SEQUENCE,
+//! RANDARRAY.
+//!
+//! These functions return an [`ArrayValue`] which `calculate_all` spills
+//! into the cells below and to the right of the formula cell.
+
+use std::cmp::Ordering;
+
+use crate::cell::CellValue;
+use crate::error::Result;
+use crate::formula::ast::Expr;
+use crate::formula::eval::{
+    coerce_to_bool, coerce_to_number, compare_values, ArrayValue, Evaluator,
+};
+use crate::formula::functions::check_arg_count;
+
+fn error(code: &str) -> ArrayValue {
+    ArrayValue::scalar(CellValue::Error(code.to_string()))
+}
+
+/// Evaluate an optional numeric argument, falling back to `default` when it
+/// is omitted.
+fn opt_number(args: &[Expr], index: usize, default: f64, ctx: &mut Evaluator) -> Result<f64> {
+    match args.get(index) {
+        Some(Expr::Missing) | None => Ok(default),
+        Some(arg) => coerce_to_number(&ctx.eval_expr(arg)?),
+    }
+}
+
+/// Evaluate an optional boolean argument, falling back to `default` when it
+/// is omitted.
+fn opt_bool(args: &[Expr], index: usize, default: bool, ctx: &mut Evaluator) -> Result<bool> {
+    match args.get(index) {
+        Some(Expr::Missing) | None => Ok(default),
+        Some(arg) => coerce_to_bool(&ctx.eval_expr(arg)?),
+    }
+}
+
+/// Build an array from the given rows (or columns when `by_col` is set).
+fn from_lines(lines: Vec<Vec<CellValue>>, by_col: bool) -> ArrayValue {
+    let outer = lines.len();
+    let inner = lines.first().map_or(0, Vec::len);
+    if by_col {
+        let mut values = Vec::with_capacity(outer * inner);
+        for r in 0..inner {
+            for line in &lines {
+                values.push(line[r].clone());
+            }
+        }
+        ArrayValue::new(inner, outer, values)
+    } else {
+        ArrayValue::new(outer, inner, lines.into_iter().flatten().collect())
+    }
+}
+
+/// Split an array into its rows (or columns when `by_col` is set).
+fn to_lines(array: &ArrayValue, by_col: bool) -> Vec<Vec<CellValue>> {
+    if by_col {
+        (0..array.cols)
+            .map(|c| (0..array.rows).map(|r| array.get(r, c).clone()).collect())
+            .collect()
+    } else {
+        (0..array.rows).map(|r| array.row(r).to_vec()).collect()
+    }
+}
+
+/// FILTER(array, include, [if_empty])
+pub fn fn_filter(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("FILTER", args, 2, 3)?;
+    let array = ctx.eval_array(&args[0])?;
+    let include = ctx.eval_array(&args[1])?;
+
+    // The include array must be a single column matching the rows, or a
+    // single row matching the columns.
+    let by_col = if include.cols == 1 && include.rows == array.rows {
+        false
+    } else if include.rows == 1 && include.cols == array.cols {
+        true
+    } else {
+        return Ok(error("#VALUE!"));
+    };
+
+    let mut kept = Vec::new();
+    for (i, line) in to_lines(&array, by_col).into_iter().enumerate() {
+        let flag = &include.values[i];
+        if let CellValue::Error(e) = flag {
+            return Ok(error(e));
+        }
+        match coerce_to_bool(flag) {
+            Ok(true) => kept.push(line),
+            Ok(false) => {}
+            Err(_) => return Ok(error("#VALUE!")),
+        }
+    }
+
+    if kept.is_empty() {
+        return match args.get(2) {
+            Some(Expr::Missing) | None => Ok(error("#CALC!")),
+            Some(if_empty) => ctx.eval_array(if_empty),
+        };
+    }
+    Ok(from_lines(kept, by_col))
+}
+
+/// Compare two values for sorting, placing empty cells last regardless of
+/// direction.
+fn sort_compare(a: &CellValue, b: &CellValue, descending: bool) -> Ordering {
+    match (a, b) {
+        (CellValue::Empty, CellValue::Empty) => Ordering::Equal,
+        (CellValue::Empty, _) => Ordering::Greater,
+        (_, CellValue::Empty) => Ordering::Less,
+        _ if descending => compare_values(b, a),
+        _ => compare_values(a, b),
+    }
+}
+
+/// Parse a sort order argument: 1 for ascending, -1 for descending.
+fn sort_descending(order: f64) -> Option<bool> {
+    match order as i64 {
+        1 => Some(false),
+        -1 => Some(true),
+        _ => None,
+    }
+}
+
+/// SORT(array, [sort_index], [sort_order], [by_col])
+pub fn fn_sort(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("SORT", args, 1, 4)?;
+    let array = ctx.eval_array(&args[0])?;
+    let sort_index = opt_number(args, 1, 1.0, ctx)? as usize;
+    let Some(descending) = sort_descending(opt_number(args, 2, 1.0, ctx)?) else {
+        return Ok(error("#VALUE!"));
+    };
+    let by_col = opt_bool(args, 3, false, ctx)?;
+
+    let mut lines = to_lines(&array, by_col);
+    let key_len = lines.first().map_or(0, Vec::len);
+    if sort_index < 1 || sort_index > key_len {
+        return Ok(error("#VALUE!"));
+    }
+    lines.sort_by(|a, b| sort_compare(&a[sort_index - 1], &b[sort_index - 1], descending));
+    Ok(from_lines(lines, by_col))
+}
+
+/// SORTBY(array, by_array1, [sort_order1], [by_array2, sort_order2], ...)
+pub fn fn_sortby(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("SORTBY", args, 2, 255)?;
+    let array = ctx.eval_array(&args[0])?;
+
+    // Each key is (values, descending). All keys must share an orientation.
+    let mut keys: Vec<(Vec<CellValue>, bool)> = Vec::new();
+    let mut by_col: Option<bool> = None;
+    let mut i = 1;
+    while i < args.len() {
+        let by = ctx.eval_array(&args[i])?;
+        let order = if i + 1 < args.len() {
+            coerce_to_number(&ctx.eval_expr(&args[i + 1])?)?
+        } else {
+            1.0
+        };
+        let Some(descending) = sort_descending(order) else {
+            return Ok(error("#VALUE!"));
+        };
+        let key_by_col = if by.cols == 1 && by.rows == array.rows {
+            false
+        } else if by.rows == 1 && by.cols == array.cols {
+            true
+        } else {
+            return Ok(error("#VALUE!"));
+        };
+        if by_col.is_some_and(|b| b != key_by_col) {
+            return Ok(error("#VALUE!"));
+        }
+        by_col = Some(key_by_col);
+        keys.push((by.values, descending));
+        i += 2;
+    }
+
+    let by_col = by_col.unwrap_or(false);
+    let lines = to_lines(&array, by_col);
+    let mut order: Vec<usize> = (0..lines.len()).collect();
+    order.sort_by(|&a, &b| {
+        keys.iter()
+            .map(|(values, descending)| sort_compare(&values[a], &values[b], *descending))
+            .find(|ord| *ord != Ordering::Equal)
+            .unwrap_or(Ordering::Equal)
+    });
+    let sorted = order.into_iter().map(|i| lines[i].clone()).collect();
+    Ok(from_lines(sorted, by_col))
+}
+
+fn lines_equal(a: &[CellValue], b: &[CellValue]) -> bool {
+    a.iter()
+        .zip(b)
+        .all(|(x, y)| compare_values(x, y) == Ordering::Equal)
+}
+
+/// UNIQUE(array, [by_col], [exactly_once])
+pub fn fn_unique(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("UNIQUE", args, 1, 3)?;
+    let array = ctx.eval_array(&args[0])?;
+    let by_col = opt_bool(args, 1, false, ctx)?;
+    let exactly_once = opt_bool(args, 2, false, ctx)?;
+
+    // Distinct lines in first-seen order, with their occurrence counts.
+    let mut distinct: Vec<(Vec<CellValue>, usize)> = Vec::new();
+    for line in to_lines(&array, by_col) {
+        match distinct
+            .iter_mut()
+            .find(|(seen, _)| lines_equal(seen, &line))
+        {
+            Some((_, count)) => *count += 1,
+            None => distinct.push((line, 1)),
+        }
+    }
+
+    let kept: Vec<Vec<CellValue>> = distinct
+        .into_iter()
+        .filter(|(_, count)| !exactly_once || *count == 1)
+        .map(|(line, _)| line)
+        .collect();
+    if kept.is_empty() {
+        return Ok(error("#CALC!"));
+    }
+    Ok(from_lines(kept, by_col))
+}
+
+/// Validate a rows/columns argument of SEQUENCE or RANDARRAY.
+fn dimension(n: f64) -> std::result::Result<usize, &'static str> {
+    let n = n.trunc();
+    if n < 0.0 {
+        Err("#VALUE!")
+    } else if n == 0.0 {
+        Err("#CALC!")
+    } else if n > crate::utils::constants::MAX_ROWS as f64 {
+        Err("#NUM!")
+    } else {
+        Ok(n as usize)
+    }
+}
+
+/// SEQUENCE(rows, [columns], [start], [step])
+pub fn fn_sequence(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("SEQUENCE", args, 1, 4)?;
+    let rows = coerce_to_number(&ctx.eval_expr(&args[0])?)?;
+    let cols = opt_number(args, 1, 1.0, ctx)?;
+    let start = opt_number(args, 2, 1.0, ctx)?;
+    let step = opt_number(args, 3, 1.0, ctx)?;
+    let (rows, cols) = match (dimension(rows), dimension(cols)) {
+        (Ok(r), Ok(c)) => (r, c),
+        (Err(e), _) | (_, Err(e)) => return Ok(error(e)),
+    };
+    let values = (0..rows * cols)
+        .map(|i| CellValue::Number(start + step * i as f64))
+        .collect();
+    Ok(ArrayValue::new(rows, cols, values))
+}
+
+/// Minimal xorshift generator; spreadsheet randomness does not need to be
+/// cryptographically strong.
+struct XorShift(u64);
+
+impl XorShift {
+    fn from_time() -> Self {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        Self(nanos | 1)
+    }
+
+    /// Return a value in [0, 1).
+    fn next_f64(&mut self) -> f64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// RANDARRAY([rows], [columns], [min], [max], [whole_number])
+pub fn fn_randarray(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("RANDARRAY", args, 0, 5)?;
+    let rows = opt_number(args, 0, 1.0, ctx)?;
+    let cols = opt_number(args, 1, 1.0, ctx)?;
+    let min = opt_number(args, 2, 0.0, ctx)?;
+    let max = opt_number(args, 3, 1.0, ctx)?;
+    let whole = opt_bool(args, 4, false, ctx)?;
+    let (rows, cols) = match (dimension(rows), dimension(cols)) {
+        (Ok(r), Ok(c)) => (r, c),
+        (Err(e), _) | (_, Err(e)) => return Ok(error(e)),
+    };
+    if min > max {
+        return Ok(error("#VALUE!"));
+    }
+    let (lo, hi) = if whole {
+        (min.ceil(), max.floor())
+    } else {
+        (min, max)
+    };
+    if whole && lo > hi {
+        return Ok(error("#VALUE!"));
+    }
+
+    let mut rng = XorShift::from_time();
+    let values = (0..rows * cols)
+        .map(|_| {
+            let r = rng.next_f64();
+            let n = if whole {
+                (lo + (r * (hi - lo + 1.0)).floor()).min(hi)
+            } else {
+                lo + r * (hi - lo)
+            };
+            CellValue::Number(n)
+        })
+        .collect();
+    Ok(ArrayValue::new(rows, cols, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formula::eval::{evaluate, CellSnapshot};
+    use crate::formula::parser::parse_formula;
+
+    fn eval_array(formula: &str, snap: &CellSnapshot) -> ArrayValue {
+        let expr = parse_formula(formula).unwrap();
+        Evaluator::new(snap).eval_array(&expr).unwrap()
+    }
+
+    fn numbers(array: &ArrayValue) -> Vec<f64> {
+        array
+            .values
+            .iter()
+            .map(|v| match v {
+                CellValue::Number(n) => *n,
+                other => panic!("expected number, got {other:?}"),
+            })
+            .collect()
+    }
+
+    /// A1:B4 = (name, score) pairs.
+    fn scores() -> CellSnapshot {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        for (i, (name, score)) in [("b", 3.0), ("a", 1.0), ("c", 2.0), ("a", 5.0)]
+            .iter()
+            .enumerate()
+        {
+            let row = i as u32 + 1;
+            snap.set_cell("Sheet1", 1, row, CellValue::String(name.to_string()));
+            snap.set_cell("Sheet1", 2, row, CellValue::Number(*score));
+        }
+        snap
+    }
+
+    #[test]
+    fn test_filter_rows() {
+        let snap = scores();
+        let result = eval_array("FILTER(A1:B4,B1:B4>=3)", &snap);
+        assert_eq!((result.rows, result.cols), (2, 2));
+        assert_eq!(result.get(0, 0), &CellValue::String("b".to_string()));
+        assert_eq!(result.get(1, 1), &CellValue::Number(5.0));
+    }
+
+    #[test]
+    fn test_filter_empty_result() {
+        let snap = scores();
+        assert_eq!(
+            eval_array("FILTER(A1:A4,B1:B4>10)", &snap).into_scalar(),
+            CellValue::Error("#CALC!".to_string())
+        );
+        assert_eq!(
+            eval_array("FILTER(A1:A4,B1:B4>10,\"none\")", &snap).into_scalar(),
+            CellValue::String("none".to_string())
+        );
+        assert_eq!(
+            eval_array("FILTER(A1:A4,B1:B2>1)", &snap).into_scalar(),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_sort_and_sortby() {
+        let snap = scores();
+        let sorted = eval_array("SORT(A1:B4,2,-1)", &snap);
+        assert_eq!(numbers(&eval_array("INDEX(SORT(B1:B4),1)", &snap)), [1.0]);
+        assert_eq!(sorted.get(0, 1), &CellValue::Number(5.0));
+        assert_eq!(sorted.get(3, 1), &CellValue::Number(1.0));
+
+        let by = eval_array("SORTBY(B1:B4,A1:A4,1,B1:B4,-1)", &snap);
+        assert_eq!(numbers(&by), [5.0, 1.0, 3.0, 2.0]);
+    }
+
+    #[test]
+    fn test_sort_array_constant() {
+        let snap = CellSnapshot::new("Sheet1".to_string());
+        let sorted = eval_array("SORT({3,1,2})", &snap);
+        assert_eq!((sorted.rows, sorted.cols), (1, 3));
+        assert_eq!(numbers(&sorted), [3.0, 1.0, 2.0]);
+        let sorted = eval_array("SORT({3;1;2},1,-1)", &snap);
+        assert_eq!((sorted.rows, sorted.cols), (3, 1));
+        assert_eq!(numbers(&sorted), [3.0, 2.0, 1.0]);
+        let sorted = eval_array("SORT({3,1,2},1,1,TRUE)", &snap);
+        assert_eq!(numbers(&sorted), [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_unique() {
+        let snap = scores();
+        let unique = eval_array("UNIQUE(A1:A4)", &snap);
+        assert_eq!(unique.rows, 3);
+        let once = eval_array("UNIQUE(A1:A4,FALSE,TRUE)", &snap);
+        assert_eq!(
+            once.values,
+            vec![
+                CellValue::String("b".to_string()),
+                CellValue::String("c".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sequence() {
+        let snap = CellSnapshot::new("Sheet1".to_string());
+        let seq = eval_array("SEQUENCE(2,3,10,5)", &snap);
+        assert_eq!((seq.rows, seq.cols), (2, 3));
+        assert_eq!(numbers(&seq), [10.0, 15.0, 20.0, 25.0, 30.0, 35.0]);
+        assert_eq!(
+            eval_array("SEQUENCE(0)", &snap).into_scalar(),
+            CellValue::Error("#CALC!".to_string())
+        );
+        let doubled = eval_array("SEQUENCE(3)*2", &snap);
+        assert_eq!(numbers(&doubled), [2.0, 4.0, 6.0]);
+    }
+
+    #[test]
+    fn test_randarray_bounds() {
+        let snap = CellSnapshot::new("Sheet1".to_string());
+        let arr = eval_array("RANDARRAY(4,5,1,6,TRUE)", &snap);
+        assert_eq!((arr.rows, arr.cols), (4, 5));
+        for n in numbers(&arr) {
+            assert!((1.0..=6.0).contains(&n) && n.fract() == 0.0);
+        }
+    }
+
+    #[test]
+    fn test_array_functions_in_scalar_context() {
+        let snap = scores();
+        let sum = evaluate(
+            &parse_formula("SUM(FILTER(B1:B4,A1:A4=\"a\"))").unwrap(),
+            &snap,
+        );
+        assert_eq!(sum.unwrap(), CellValue::Number(6.0));
+        let first = evaluate(&parse_formula("_xlfn._xlws.SORT(A1:A4)").unwrap(), &snap);
+        assert_eq!(first.unwrap(), CellValue::String("a".to_string()));
+    }
+}
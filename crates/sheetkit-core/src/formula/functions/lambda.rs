@@ -0,0 +1,416 @@
+//! Name binding and lambda functions: LET, LAMBDA, MAP, REDUCE, SCAN,
+//! BYROW, BYCOL, MAKEARRAY.
+//!
+//! LET and LAMBDA parameters bind names in the evaluator's scope (see
+//! [`Evaluator::bind`]). A LAMBDA is a function value: it is called
+//! directly, as in `LAMBDA(x, x + 1)(2)`, through a name bound to it, or
+//! through a defined name whose definition is a LAMBDA. The helper
+//! functions call a lambda for each element, row, or column of an array.
+
+use crate::cell::CellValue;
+use crate::error::Result;
+use crate::formula::ast::Expr;
+use crate::formula::eval::{coerce_to_number, ArrayValue, Binding, Evaluator, Lambda};
+use crate::formula::functions::check_arg_count;
+
+fn error(code: &str) -> ArrayValue {
+    ArrayValue::scalar(CellValue::Error(code.to_string()))
+}
+
+/// Bind each `name, value` pair in turn, so that later values may use
+/// earlier names. Returns `false` if a name is not a plain identifier.
+fn bind_names(pairs: &[Expr], ctx: &mut Evaluator) -> Result<bool> {
+    for pair in pairs.chunks(2) {
+        let Expr::Name { name, sheet: None } = &pair[0] else {
+            return Ok(false);
+        };
+        let value = ctx.bind_value(&pair[1])?;
+        ctx.bind(name, value);
+    }
+    Ok(true)
+}
+
+/// LET(name1, value1, [name2, value2, ...], calculation)
+pub fn fn_let(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("LET", args, 3, 253)?;
+    let (calculation, pairs) = args.split_last().expect("at least three arguments");
+    if !pairs.len().is_multiple_of(2) {
+        return Ok(error("#VALUE!"));
+    }
+    let mark = ctx.scope_mark();
+    let result = match bind_names(pairs, ctx) {
+        Ok(true) => ctx.eval_array(calculation),
+        Ok(false) => Ok(error("#VALUE!")),
+        Err(e) => Err(e),
+    };
+    ctx.restore_scope(mark);
+    result
+}
+
+/// LAMBDA([parameter1, ...], calculation)
+///
+/// Only reached when a lambda is evaluated without being called.
+pub fn fn_lambda(args: &[Expr], _ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("LAMBDA", args, 1, 254)?;
+    Ok(CellValue::Error("#CALC!".to_string()))
+}
+
+/// Evaluate the last argument to a lambda taking `params` parameters.
+fn lambda_arg(args: &[Expr], params: usize, ctx: &mut Evaluator) -> Result<Option<Lambda>> {
+    let lambda = ctx.lambda_value(&args[args.len() - 1])?;
+    Ok(lambda.filter(|lambda| lambda.params.len() == params))
+}
+
+/// Call `lambda` with single values, requiring a single value back.
+fn call_scalar(lambda: &Lambda, args: Vec<ArrayValue>, ctx: &mut Evaluator) -> Result<CellValue> {
+    let args = args.into_iter().map(Binding::Value).collect();
+    let result = ctx.call_lambda(lambda, args)?;
+    if result.is_scalar() {
+        Ok(result.into_scalar())
+    } else {
+        // Arrays of arrays are not supported.
+        Ok(CellValue::Error("#CALC!".to_string()))
+    }
+}
+
+/// MAP(array1, [array2, ...], lambda)
+pub fn fn_map(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("MAP", args, 2, 254)?;
+    let Some(lambda) = lambda_arg(args, args.len() - 1, ctx)? else {
+        return Ok(error("#VALUE!"));
+    };
+    let arrays = args[..args.len() - 1]
+        .iter()
+        .map(|arg| ctx.eval_array(arg))
+        .collect::<Result<Vec<_>>>()?;
+    let rows = arrays.iter().map(|a| a.rows).max().unwrap_or(0);
+    let cols = arrays.iter().map(|a| a.cols).max().unwrap_or(0);
+    let mut values = Vec::with_capacity(rows * cols);
+    for r in 0..rows {
+        for c in 0..cols {
+            let elements = arrays
+                .iter()
+                .map(|a| ArrayValue::scalar(a.broadcast_get(r, c)))
+                .collect();
+            values.push(call_scalar(&lambda, elements, ctx)?);
+        }
+    }
+    Ok(ArrayValue::new(rows, cols, values))
+}
+
+/// Evaluate the `[initial_value], array, lambda` arguments shared by
+/// REDUCE and SCAN. An omitted initial value is empty.
+fn accumulation_args(
+    name: &str,
+    args: &[Expr],
+    ctx: &mut Evaluator,
+) -> Result<Option<(ArrayValue, ArrayValue, Lambda)>> {
+    check_arg_count(name, args, 2, 3)?;
+    let Some(lambda) = lambda_arg(args, 2, ctx)? else {
+        return Ok(None);
+    };
+    let initial = if args.len() == 3 {
+        ctx.eval_array(&args[0])?
+    } else {
+        ArrayValue::scalar(CellValue::Empty)
+    };
+    let array = ctx.eval_array(&args[args.len() - 2])?;
+    Ok(Some((initial, array, lambda)))
+}
+
+/// REDUCE([initial_value], array, lambda)
+pub fn fn_reduce(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    let Some((mut accumulator, array, lambda)) = accumulation_args("REDUCE", args, ctx)? else {
+        return Ok(error("#VALUE!"));
+    };
+    for value in array.values {
+        let args = vec![
+            Binding::Value(accumulator),
+            Binding::Value(ArrayValue::scalar(value)),
+        ];
+        accumulator = ctx.call_lambda(&lambda, args)?;
+    }
+    Ok(accumulator)
+}
+
+/// SCAN([initial_value], array, lambda)
+pub fn fn_scan(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    let Some((initial, array, lambda)) = accumulation_args("SCAN", args, ctx)? else {
+        return Ok(error("#VALUE!"));
+    };
+    let mut accumulator = initial.into_scalar();
+    let mut values = Vec::with_capacity(array.values.len());
+    for value in array.values {
+        let args = vec![ArrayValue::scalar(accumulator), ArrayValue::scalar(value)];
+        accumulator = call_scalar(&lambda, args, ctx)?;
+        values.push(accumulator.clone());
+    }
+    Ok(ArrayValue::new(array.rows, array.cols, values))
+}
+
+/// Shared implementation of BYROW and BYCOL: call the lambda with each row
+/// (or column) of the array as a 1-row (or 1-column) array.
+fn by_line(name: &str, args: &[Expr], by_col: bool, ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count(name, args, 2, 2)?;
+    let Some(lambda) = lambda_arg(args, 1, ctx)? else {
+        return Ok(error("#VALUE!"));
+    };
+    let array = ctx.eval_array(&args[0])?;
+    let (lines, len) = if by_col {
+        (array.cols, array.rows)
+    } else {
+        (array.rows, array.cols)
+    };
+    let mut values = Vec::with_capacity(lines);
+    for i in 0..lines {
+        let line = if by_col {
+            let column = (0..len).map(|r| array.get(r, i).clone()).collect();
+            ArrayValue::new(len, 1, column)
+        } else {
+            ArrayValue::new(1, len, array.row(i).to_vec())
+        };
+        values.push(call_scalar(&lambda, vec![line], ctx)?);
+    }
+    Ok(if by_col {
+        ArrayValue::new(1, lines, values)
+    } else {
+        ArrayValue::new(lines, 1, values)
+    })
+}
+
+/// BYROW(array, lambda)
+pub fn fn_byrow(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    by_line("BYROW", args, false, ctx)
+}
+
+/// BYCOL(array, lambda)
+pub fn fn_bycol(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    by_line("BYCOL", args, true, ctx)
+}
+
+/// MAKEARRAY(rows, columns, lambda)
+pub fn fn_makearray(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("MAKEARRAY", args, 3, 3)?;
+    let rows = coerce_to_number(&ctx.eval_expr(&args[0])?)?.trunc();
+    let cols = coerce_to_number(&ctx.eval_expr(&args[1])?)?.trunc();
+    if rows < 1.0 || cols < 1.0 {
+        return Ok(error("#VALUE!"));
+    }
+    if rows > crate::utils::constants::MAX_ROWS as f64
+        || cols > crate::utils::constants::MAX_COLUMNS as f64
+    {
+        return Ok(error("#NUM!"));
+    }
+    let Some(lambda) = lambda_arg(args, 2, ctx)? else {
+        return Ok(error("#VALUE!"));
+    };
+    let (rows, cols) = (rows as usize, cols as usize);
+    let mut values = Vec::with_capacity(rows * cols);
+    for r in 1..=rows {
+        for c in 1..=cols {
+            let args = vec![
+                ArrayValue::scalar(CellValue::Number(r as f64)),
+                ArrayValue::scalar(CellValue::Number(c as f64)),
+            ];
+            values.push(call_scalar(&lambda, args, ctx)?);
+        }
+    }
+    Ok(ArrayValue::new(rows, cols, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formula::eval::{evaluate, CellSnapshot};
+    use crate::formula::parser::parse_formula;
+
+    fn eval(formula: &str, snap: &CellSnapshot) -> CellValue {
+        evaluate(&parse_formula(formula).unwrap(), snap).unwrap()
+    }
+
+    fn eval_array(formula: &str, snap: &CellSnapshot) -> ArrayValue {
+        let expr = parse_formula(formula).unwrap();
+        Evaluator::new(snap).eval_array(&expr).unwrap()
+    }
+
+    fn numbers(array: &ArrayValue) -> Vec<f64> {
+        array
+            .values
+            .iter()
+            .map(|v| match v {
+                CellValue::Number(n) => *n,
+                other => panic!("expected number, got {other:?}"),
+            })
+            .collect()
+    }
+
+    /// A1:B3 = 1..6, row by row.
+    fn grid() -> CellSnapshot {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        for i in 0..6u32 {
+            snap.set_cell(
+                "Sheet1",
+                i % 2 + 1,
+                i / 2 + 1,
+                CellValue::Number(f64::from(i + 1)),
+            );
+        }
+        snap
+    }
+
+    // LET tests
+
+    #[test]
+    fn test_let_binds_names_in_order() {
+        let snap = grid();
+        assert_eq!(eval("LET(x,2,y,x*3,x+y)", &snap), CellValue::Number(8.0));
+        assert_eq!(
+            eval("_xlfn.LET(_xlpm.x,A2,_xlpm.x*10)", &snap),
+            CellValue::Number(30.0)
+        );
+        // Inner bindings shadow outer ones and end with their LET.
+        assert_eq!(eval("LET(x,1,LET(x,5,x)+x)", &snap), CellValue::Number(6.0));
+        assert_eq!(
+            eval("LET(1,2,3)", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_let_keeps_references_and_arrays() {
+        let snap = grid();
+        assert_eq!(
+            eval("LET(area,A1:B3,SUM(area))", &snap),
+            CellValue::Number(21.0)
+        );
+        assert_eq!(
+            eval("LET(area,A1:A3,ROWS(area))", &snap),
+            CellValue::Number(3.0)
+        );
+        let doubled = eval_array("LET(area,A1:A3,area*2)", &snap);
+        assert_eq!(numbers(&doubled), vec![2.0, 6.0, 10.0]);
+        assert_eq!(
+            eval("LET(s,SEQUENCE(4),SUM(s))", &snap),
+            CellValue::Number(10.0)
+        );
+    }
+
+    // LAMBDA tests
+
+    #[test]
+    fn test_lambda_calls() {
+        let snap = grid();
+        assert_eq!(eval("LAMBDA(x,x+1)(2)", &snap), CellValue::Number(3.0));
+        assert_eq!(eval("LAMBDA(x,y,x*y)(3,4)", &snap), CellValue::Number(12.0));
+        assert_eq!(
+            eval("LET(f,LAMBDA(x,x*x),f(5)+f(2))", &snap),
+            CellValue::Number(29.0)
+        );
+        // Lambdas close over the names in scope where they are created.
+        assert_eq!(
+            eval("LET(n,10,f,LAMBDA(x,x+n),LET(n,100,f(1)))", &snap),
+            CellValue::Number(11.0)
+        );
+        // Lambdas may be passed to other lambdas.
+        assert_eq!(
+            eval(
+                "LET(twice,LAMBDA(f,x,f(f(x))),twice(LAMBDA(v,v*3),2))",
+                &snap
+            ),
+            CellValue::Number(18.0)
+        );
+    }
+
+    #[test]
+    fn test_lambda_errors() {
+        let snap = grid();
+        assert_eq!(
+            eval("LAMBDA(x,x+1)", &snap),
+            CellValue::Error("#CALC!".to_string())
+        );
+        assert_eq!(
+            eval("LAMBDA(x,x+1)(1,2)", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+        assert_eq!(
+            eval("LET(f,LAMBDA(x,x),f)", &snap),
+            CellValue::Error("#CALC!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_defined_name_lambda() {
+        let mut snap = grid();
+        snap.set_defined_name(
+            "MYHYPOT",
+            None,
+            "_xlfn.LAMBDA(_xlpm.a,_xlpm.b,SQRT(_xlpm.a^2+_xlpm.b^2))",
+        );
+        snap.set_defined_name("MYFACT", None, "LAMBDA(n,IF(n<=1,1,n*MYFACT(n-1)))");
+        assert_eq!(eval("MYHYPOT(3,4)", &snap), CellValue::Number(5.0));
+        assert_eq!(eval("MYFACT(5)", &snap), CellValue::Number(120.0));
+        assert_eq!(
+            eval("MAP(A1:A3,MYHYPOT)", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+        let mapped = eval_array("MAP(A1:A3,B1:B3,MYHYPOT)", &snap);
+        assert_eq!(mapped.values[0], CellValue::Number(5f64.sqrt()));
+    }
+
+    // Helper function tests
+
+    #[test]
+    fn test_map() {
+        let snap = grid();
+        let result = eval_array("MAP(A1:B3,LAMBDA(x,x*10))", &snap);
+        assert_eq!((result.rows, result.cols), (3, 2));
+        assert_eq!(numbers(&result), vec![10.0, 20.0, 30.0, 40.0, 50.0, 60.0]);
+        let pairs = eval_array("MAP(A1:A3,B1:B3,LAMBDA(a,b,a+b))", &snap);
+        assert_eq!(numbers(&pairs), vec![3.0, 7.0, 11.0]);
+    }
+
+    #[test]
+    fn test_reduce_and_scan() {
+        let snap = grid();
+        assert_eq!(
+            eval("REDUCE(0,A1:B3,LAMBDA(acc,v,acc+v))", &snap),
+            CellValue::Number(21.0)
+        );
+        assert_eq!(
+            eval("REDUCE(A1:B3,LAMBDA(acc,v,MAX(acc,v)))", &snap),
+            CellValue::Number(6.0)
+        );
+        let running = eval_array("SCAN(1,A1:A3,LAMBDA(acc,v,acc*v))", &snap);
+        assert_eq!((running.rows, running.cols), (3, 1));
+        assert_eq!(numbers(&running), vec![1.0, 3.0, 15.0]);
+        assert_eq!(
+            eval("REDUCE(0,A1:A3,LAMBDA(x,x))", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_byrow_bycol() {
+        let snap = grid();
+        let rows = eval_array("BYROW(A1:B3,LAMBDA(line,SUM(line)))", &snap);
+        assert_eq!((rows.rows, rows.cols), (3, 1));
+        assert_eq!(numbers(&rows), vec![3.0, 7.0, 11.0]);
+        let cols = eval_array("BYCOL(A1:B3,LAMBDA(line,MAX(line)))", &snap);
+        assert_eq!((cols.rows, cols.cols), (1, 2));
+        assert_eq!(numbers(&cols), vec![5.0, 6.0]);
+        let nested = eval_array("BYROW(A1:B3,LAMBDA(line,line))", &snap);
+        assert_eq!(nested.values[0], CellValue::Error("#CALC!".to_string()));
+    }
+
+    #[test]
+    fn test_makearray() {
+        let snap = grid();
+        let table = eval_array("MAKEARRAY(2,3,LAMBDA(i,j,i*j))", &snap);
+        assert_eq!((table.rows, table.cols), (2, 3));
+        assert_eq!(numbers(&table), vec![1.0, 2.0, 3.0, 2.0, 4.0, 6.0]);
+        assert_eq!(
+            eval("MAKEARRAY(0,3,LAMBDA(i,j,i))", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
+}
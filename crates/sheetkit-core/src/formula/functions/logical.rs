@@ -152,6 +152,16 @@ mod tests {
         assert_eq!(result, CellValue::Bool(false));
     }
 
+    #[test]
+    fn test_if_omitted_branch() {
+        assert_eq!(eval("IF(1>0,,1)"), CellValue::Empty);
+        assert_eq!(eval("IF(1>0,,1)+2"), CellValue::Number(2.0));
+        assert_eq!(
+            eval(r#"CONCAT("a",,"b")"#),
+            CellValue::String("ab".to_string())
+        );
+    }
+
     #[test]
     fn test_iferror_no_error() {
         assert_eq!(eval(r#"IFERROR(42,"error")"#), CellValue::Number(42.0));
@@ -1,36 +1,53 @@
 //! Lookup and reference formula functions: VLOOKUP, HLOOKUP, INDEX, MATCH,
-//! LOOKUP, ROW, COLUMN, ROWS, COLUMNS, CHOOSE, ADDRESS.
+//! LOOKUP, ROW, COLUMN, ROWS, COLUMNS, CHOOSE, ADDRESS, OFFSET, INDIRECT,
+//! XLOOKUP, XMATCH, TRANSPOSE, HYPERLINK, AREAS, GETPIVOTDATA, ANCHORARRAY.
 
 use crate::cell::CellValue;
 use crate::error::{Error, Result};
-use crate::formula::ast::{CellReference, Expr};
-use crate::formula::eval::{coerce_to_number, coerce_to_string, compare_values, Evaluator};
-use crate::formula::functions::check_arg_count;
-use crate::utils::cell_ref::{column_name_to_number, column_number_to_name};
+use crate::formula::ast::{BinaryOperator, Expr};
+use crate::formula::eval::{
+    coerce_to_bool, coerce_to_number, coerce_to_string, compare_values, ArrayValue, Evaluator,
+    RefArea,
+};
+use crate::formula::functions::{check_arg_count, wildcard_match};
+use crate::pivot::AggregateFunction;
+use crate::utils::cell_ref::column_number_to_name;
+use crate::utils::constants::{MAX_COLUMNS, MAX_ROWS};
 
-/// Extract (start, end) CellReference pair from a Range expression.
-fn extract_range(expr: &Expr) -> Result<(&CellReference, &CellReference)> {
-    match expr {
-        Expr::Range { start, end } => Ok((start, end)),
-        _ => Err(Error::FormulaError(
-            "expected a range reference".to_string(),
-        )),
-    }
+/// Resolve `expr` to a single-area reference, or to the Excel error to
+/// return instead: the error itself for error values, `#VALUE!` for
+/// anything that is not a single-area reference.
+fn reference_area(
+    expr: &Expr,
+    ctx: &mut Evaluator,
+) -> Result<std::result::Result<RefArea, String>> {
+    let resolved = ctx.resolve_ref(expr)?;
+    Ok(match resolved.as_ref() {
+        Expr::Error(e) => Err(e.clone()),
+        other => {
+            RefArea::from_expr(other, ctx.current_sheet()).ok_or_else(|| "#VALUE!".to_string())
+        }
+    })
 }
 
 /// Read a range into a flat row-major Vec and return (values, num_cols, num_rows).
+/// Array-producing expressions such as `SORT(A1:B5)` are accepted as well.
 fn read_range(expr: &Expr, ctx: &mut Evaluator) -> Result<(Vec<CellValue>, usize, usize)> {
-    let (start, end) = extract_range(expr)?;
-    let start_col = column_name_to_number(&start.col)?;
-    let end_col = column_name_to_number(&end.col)?;
-    let min_col = start_col.min(end_col);
-    let max_col = start_col.max(end_col);
-    let min_row = start.row.min(end.row);
-    let max_row = start.row.max(end.row);
-    let num_cols = (max_col - min_col + 1) as usize;
-    let num_rows = (max_row - min_row + 1) as usize;
-    let values = ctx.expand_range(start, end)?;
-    Ok((values, num_cols, num_rows))
+    let expr = ctx.resolve_ref(expr)?;
+    if ctx.is_array_arg(&expr) {
+        let array = ctx.eval_array(&expr)?;
+        return Ok((array.values, array.cols, array.rows));
+    }
+    match expr.as_ref() {
+        Expr::Range { start, end } => {
+            let array = ctx.range_array(start, end)?;
+            Ok((array.values, array.cols, array.rows))
+        }
+        Expr::CellRef(_) => Ok((vec![ctx.eval_expr(&expr)?], 1, 1)),
+        _ => Err(Error::FormulaError(
+            "expected a range reference".to_string(),
+        )),
+    }
 }
 
 /// VLOOKUP(lookup_value, table_array, col_index_num, [range_lookup])
@@ -227,63 +244,45 @@ pub fn fn_lookup(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 }
 
 /// ROW([reference]) - returns the row number of a reference.
-pub fn fn_row(args: &[Expr], _ctx: &mut Evaluator) -> Result<CellValue> {
+pub fn fn_row(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("ROW", args, 0, 1)?;
     if args.is_empty() {
         return Ok(CellValue::Number(1.0));
     }
-    match &args[0] {
-        Expr::CellRef(cell_ref) => Ok(CellValue::Number(cell_ref.row as f64)),
-        Expr::Range { start, .. } => Ok(CellValue::Number(start.row as f64)),
-        _ => Ok(CellValue::Error("#VALUE!".to_string())),
-    }
+    Ok(match reference_area(&args[0], ctx)? {
+        Ok(area) => CellValue::Number(area.min_row as f64),
+        Err(e) => CellValue::Error(e),
+    })
 }
 
 /// COLUMN([reference]) - returns the column number of a reference.
-pub fn fn_column(args: &[Expr], _ctx: &mut Evaluator) -> Result<CellValue> {
+pub fn fn_column(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("COLUMN", args, 0, 1)?;
     if args.is_empty() {
         return Ok(CellValue::Number(1.0));
     }
-    match &args[0] {
-        Expr::CellRef(cell_ref) => {
-            let col = column_name_to_number(&cell_ref.col)?;
-            Ok(CellValue::Number(col as f64))
-        }
-        Expr::Range { start, .. } => {
-            let col = column_name_to_number(&start.col)?;
-            Ok(CellValue::Number(col as f64))
-        }
-        _ => Ok(CellValue::Error("#VALUE!".to_string())),
-    }
+    Ok(match reference_area(&args[0], ctx)? {
+        Ok(area) => CellValue::Number(area.min_col as f64),
+        Err(e) => CellValue::Error(e),
+    })
 }
 
 /// ROWS(array) - returns the number of rows in a reference.
-pub fn fn_rows(args: &[Expr], _ctx: &mut Evaluator) -> Result<CellValue> {
+pub fn fn_rows(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("ROWS", args, 1, 1)?;
-    match &args[0] {
-        Expr::Range { start, end } => {
-            let rows = (end.row.max(start.row) - end.row.min(start.row) + 1) as f64;
-            Ok(CellValue::Number(rows))
-        }
-        Expr::CellRef(_) => Ok(CellValue::Number(1.0)),
-        _ => Ok(CellValue::Error("#VALUE!".to_string())),
-    }
+    Ok(match reference_area(&args[0], ctx)? {
+        Ok(area) => CellValue::Number(area.rows() as f64),
+        Err(e) => CellValue::Error(e),
+    })
 }
 
 /// COLUMNS(array) - returns the number of columns in a reference.
-pub fn fn_columns(args: &[Expr], _ctx: &mut Evaluator) -> Result<CellValue> {
+pub fn fn_columns(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("COLUMNS", args, 1, 1)?;
-    match &args[0] {
-        Expr::Range { start, end } => {
-            let start_col = column_name_to_number(&start.col)?;
-            let end_col = column_name_to_number(&end.col)?;
-            let cols = (start_col.max(end_col) - start_col.min(end_col) + 1) as f64;
-            Ok(CellValue::Number(cols))
-        }
-        Expr::CellRef(_) => Ok(CellValue::Number(1.0)),
-        _ => Ok(CellValue::Error("#VALUE!".to_string())),
-    }
+    Ok(match reference_area(&args[0], ctx)? {
+        Ok(area) => CellValue::Number(area.cols() as f64),
+        Err(e) => CellValue::Error(e),
+    })
 }
 
 /// CHOOSE(index_num, value1, [value2], ...) - returns value at given index.
@@ -353,10 +352,538 @@ pub fn fn_address(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     }
 }
 
+/// OFFSET(reference, rows, cols, [height], [width]) - returns the reference
+/// `rows` and `cols` away from `reference`, optionally resized.
+pub fn fn_offset(args: &[Expr], ctx: &mut Evaluator) -> Result<Option<Expr>> {
+    check_arg_count("OFFSET", args, 3, 5)?;
+    let base = match reference_area(&args[0], ctx)? {
+        Ok(area) => area,
+        Err(e) => return Ok(Some(Expr::Error(e))),
+    };
+    let mut numbers = Vec::with_capacity(4);
+    for arg in &args[1..] {
+        numbers.push(coerce_to_number(&ctx.eval_expr(arg)?)?.trunc() as i64);
+    }
+    let height = numbers.get(2).copied().unwrap_or(i64::from(base.rows()));
+    let width = numbers.get(3).copied().unwrap_or(i64::from(base.cols()));
+    let min_row = i64::from(base.min_row) + numbers[0];
+    let min_col = i64::from(base.min_col) + numbers[1];
+    let max_row = min_row + height - 1;
+    let max_col = min_col + width - 1;
+    if height < 1
+        || width < 1
+        || min_row < 1
+        || min_col < 1
+        || max_row > i64::from(MAX_ROWS)
+        || max_col > i64::from(MAX_COLUMNS)
+    {
+        return Ok(Some(Expr::Error("#REF!".to_string())));
+    }
+    let area = RefArea {
+        sheet: base.sheet,
+        min_col: min_col as u32,
+        min_row: min_row as u32,
+        max_col: max_col as u32,
+        max_row: max_row as u32,
+    };
+    Ok(Some(area.to_expr()))
+}
+
+/// ANCHORARRAY(reference) - the spill range of the dynamic array formula
+/// in the referenced cell. Files store the spill reference `E1#` this way.
+pub fn fn_anchorarray(args: &[Expr], ctx: &mut Evaluator) -> Result<Option<Expr>> {
+    check_arg_count("ANCHORARRAY", args, 1, 1)?;
+    let reference = ctx.resolve_ref(&args[0])?.into_owned();
+    Ok(Some(match &reference {
+        Expr::CellRef(cell) => ctx.spill_target(cell),
+        Expr::Error(_) => reference,
+        _ => Expr::Error("#REF!".to_string()),
+    }))
+}
+
+/// INDIRECT(ref_text, [a1]) - returns the reference named by a text string
+/// in A1 style (`"Sheet2!B3"`, `"A1:C3"`, a defined name) or, when `a1` is
+/// FALSE, R1C1 style (`"R2C3"`, `"R[-1]C"`).
+pub fn fn_indirect(args: &[Expr], ctx: &mut Evaluator) -> Result<Option<Expr>> {
+    check_arg_count("INDIRECT", args, 1, 2)?;
+    let text = coerce_to_string(&ctx.eval_expr(&args[0])?);
+    let a1 = if args.len() > 1 {
+        coerce_to_bool(&ctx.eval_expr(&args[1])?)?
+    } else {
+        true
+    };
+    let ref_error = || Ok(Some(Expr::Error("#REF!".to_string())));
+    let area = if a1 {
+        let Ok(parsed) = crate::formula::parser::parse_formula(&text) else {
+            return ref_error();
+        };
+        let resolved = ctx.resolve_ref(&parsed)?;
+        RefArea::from_expr(&resolved, ctx.current_sheet())
+    } else {
+        parse_r1c1(&text, ctx.current_sheet(), ctx.current_cell())
+    };
+    match area {
+        Some(area) => Ok(Some(area.to_expr())),
+        None => ref_error(),
+    }
+}
+
+/// Parse an R1C1-style reference such as `R2C3`, `R[-1]C`, or
+/// `'My Sheet'!R1C1:R2C2`. Relative parts are taken from `origin`, the
+/// `(col, row)` of the formula cell; unqualified references are on
+/// `current_sheet`.
+fn parse_r1c1(text: &str, current_sheet: &str, origin: Option<(u32, u32)>) -> Option<RefArea> {
+    let (sheet, body) = match text.rsplit_once('!') {
+        Some((sheet, body)) => {
+            let sheet = match sheet.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+                Some(quoted) => quoted.replace("''", "'"),
+                None => sheet.to_string(),
+            };
+            (sheet, body)
+        }
+        None => (current_sheet.to_string(), text),
+    };
+    let mut corners = body.split(':').map(|part| r1c1_cell(part, origin));
+    let start = corners.next()??;
+    let end = match corners.next() {
+        Some(corner) => corner?,
+        None => start,
+    };
+    if corners.next().is_some() {
+        return None;
+    }
+    let ((c1, r1), (c2, r2)) = (start, end);
+    Some(RefArea {
+        sheet,
+        min_col: c1.min(c2),
+        min_row: r1.min(r2),
+        max_col: c1.max(c2),
+        max_row: r1.max(r2),
+    })
+}
+
+/// Parse one R1C1 cell such as `R2C3` or `R[1]C[-2]` into `(col, row)`.
+fn r1c1_cell(part: &str, origin: Option<(u32, u32)>) -> Option<(u32, u32)> {
+    let upper = part.trim().to_ascii_uppercase();
+    let rest = upper.strip_prefix('R')?;
+    let (row, rest) = r1c1_index(rest, origin.map(|(_, row)| row))?;
+    let rest = rest.strip_prefix('C')?;
+    let (col, rest) = r1c1_index(rest, origin.map(|(col, _)| col))?;
+    rest.is_empty().then_some((col, row))
+}
+
+/// Parse the index after `R` or `C`: `n` is absolute, `[n]` is relative to
+/// `base`, and nothing means `base` itself.
+fn r1c1_index(text: &str, base: Option<u32>) -> Option<(u32, &str)> {
+    if let Some(rest) = text.strip_prefix('[') {
+        let end = rest.find(']')?;
+        let offset: i64 = rest[..end].parse().ok()?;
+        let index = u32::try_from(i64::from(base?) + offset).ok()?;
+        return (index >= 1).then_some((index, &rest[end + 1..]));
+    }
+    let digits = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return Some((base?, text));
+    }
+    let index: u32 = text[..digits].parse().ok()?;
+    (index >= 1).then_some((index, &text[digits..]))
+}
+
+/// INDEX(reference, row_num, [col_num], [area_num]) - reference form.
+///
+/// Returns the cell at `row_num` and `col_num` of the selected area of
+/// `reference`; a `row_num` or `col_num` of 0 selects a whole column or row.
+/// Yields `None` when the first argument is not a reference, so that INDEX
+/// over an array is evaluated by [`fn_index`].
+pub fn fn_index_ref(args: &[Expr], ctx: &mut Evaluator) -> Result<Option<Expr>> {
+    check_arg_count("INDEX", args, 2, 4)?;
+    let reference = ctx.resolve_ref(&args[0])?.into_owned();
+    let mut areas = Vec::new();
+    if !union_areas(&reference, &mut areas) {
+        return Ok(match reference {
+            Expr::Error(_) => Some(reference),
+            _ => None,
+        });
+    }
+    let ref_error = || Ok(Some(Expr::Error("#REF!".to_string())));
+    let area_num = match args.get(3) {
+        Some(arg) => coerce_to_number(&ctx.eval_expr(arg)?)?.trunc() as i64,
+        None => 1,
+    };
+    let Some(area) = usize::try_from(area_num - 1)
+        .ok()
+        .and_then(|i| areas.get(i))
+        .and_then(|expr| RefArea::from_expr(expr, ctx.current_sheet()))
+    else {
+        return ref_error();
+    };
+    let row_num = coerce_to_number(&ctx.eval_expr(&args[1])?)?.trunc() as i64;
+    let col_num = match args.get(2) {
+        Some(arg) => Some(coerce_to_number(&ctx.eval_expr(arg)?)?.trunc() as i64),
+        None => None,
+    };
+    // With one index, a single row is indexed by column; otherwise the
+    // index selects a row, across all columns unless there is only one.
+    let (row_num, col_num) = match col_num {
+        Some(col_num) => (row_num, col_num),
+        None if area.rows() == 1 && area.cols() > 1 => (1, row_num),
+        None if area.cols() == 1 => (row_num, 1),
+        None => (row_num, 0),
+    };
+    let select = |index: i64, first: u32, len: u32| match index {
+        0 => Some((first, first + len - 1)),
+        i if (1..=i64::from(len)).contains(&i) => {
+            let at = first + i as u32 - 1;
+            Some((at, at))
+        }
+        _ => None,
+    };
+    let (Some((min_row, max_row)), Some((min_col, max_col))) = (
+        select(row_num, area.min_row, area.rows()),
+        select(col_num, area.min_col, area.cols()),
+    ) else {
+        return ref_error();
+    };
+    let area = RefArea {
+        min_col,
+        min_row,
+        max_col,
+        max_row,
+        ..area
+    };
+    Ok(Some(area.to_expr()))
+}
+
+/// Collect the areas of a resolved cell, range, or union reference into
+/// `areas`. Returns `false` if `expr` is not such a reference.
+fn union_areas(expr: &Expr, areas: &mut Vec<Expr>) -> bool {
+    match expr {
+        Expr::CellRef(_) | Expr::Range { .. } => {
+            areas.push(expr.clone());
+            true
+        }
+        Expr::BinaryOp {
+            op: BinaryOperator::Union,
+            left,
+            right,
+        } => union_areas(left, areas) && union_areas(right, areas),
+        _ => false,
+    }
+}
+
+/// XLOOKUP(lookup_value, lookup_array, return_array, [if_not_found],
+/// [match_mode], [search_mode]) - returns the row (or, for a horizontal
+/// lookup array, the column) of `return_array` at the match position.
+pub fn fn_xlookup(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("XLOOKUP", args, 3, 6)?;
+    let lookup_val = ctx.eval_expr(&args[0])?;
+    let lookup = ctx.eval_array(&args[1])?;
+    let returns = ctx.eval_array(&args[2])?;
+    let match_mode = opt_int(args, 4, 0, ctx)?;
+    let search_mode = opt_int(args, 5, 1, ctx)?;
+    let vertical = lookup.cols == 1 && returns.rows == lookup.rows;
+    let horizontal = lookup.rows == 1 && returns.cols == lookup.cols;
+    if !vertical && !horizontal {
+        return Ok(ArrayValue::scalar(CellValue::Error("#VALUE!".to_string())));
+    }
+    let pos = match find_position(&lookup_val, &lookup.values, match_mode, search_mode) {
+        Ok(pos) => pos,
+        Err(e) => return Ok(ArrayValue::scalar(CellValue::Error(e))),
+    };
+    Ok(match pos {
+        Some(i) if vertical => ArrayValue::new(
+            1,
+            returns.cols,
+            returns.values[i * returns.cols..(i + 1) * returns.cols].to_vec(),
+        ),
+        Some(i) => ArrayValue::new(
+            returns.rows,
+            1,
+            (0..returns.rows)
+                .map(|r| returns.values[r * returns.cols + i].clone())
+                .collect(),
+        ),
+        None => match args.get(3) {
+            Some(Expr::Missing) | None => ArrayValue::scalar(CellValue::Error("#N/A".to_string())),
+            Some(if_not_found) => ArrayValue::scalar(ctx.eval_expr(if_not_found)?),
+        },
+    })
+}
+
+/// XMATCH(lookup_value, lookup_array, [match_mode], [search_mode]) - returns
+/// the 1-based position of a value in a row or column.
+pub fn fn_xmatch(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("XMATCH", args, 2, 4)?;
+    let lookup_val = ctx.eval_expr(&args[0])?;
+    let lookup = ctx.eval_array(&args[1])?;
+    let match_mode = opt_int(args, 2, 0, ctx)?;
+    let search_mode = opt_int(args, 3, 1, ctx)?;
+    if lookup.rows != 1 && lookup.cols != 1 {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    Ok(
+        match find_position(&lookup_val, &lookup.values, match_mode, search_mode) {
+            Ok(Some(i)) => CellValue::Number((i + 1) as f64),
+            Ok(None) => CellValue::Error("#N/A".to_string()),
+            Err(e) => CellValue::Error(e),
+        },
+    )
+}
+
+/// Evaluate the optional integer argument at `index`, or return `default`
+/// when it is omitted.
+fn opt_int(args: &[Expr], index: usize, default: i32, ctx: &mut Evaluator) -> Result<i32> {
+    match args.get(index) {
+        Some(Expr::Missing) | None => Ok(default),
+        Some(arg) => Ok(coerce_to_number(&ctx.eval_expr(arg)?)?.trunc() as i32),
+    }
+}
+
+/// Find the position of `lookup` in `items` for XLOOKUP and XMATCH.
+///
+/// `match_mode` is 0 (exact), -1 (exact or next smaller), 1 (exact or next
+/// larger), or 2 (wildcard); `search_mode` is 1 (first to last), -1 (last to
+/// first), 2 (binary search over ascending items), or -2 (binary search
+/// over descending items). Invalid modes yield `#VALUE!`.
+fn find_position(
+    lookup: &CellValue,
+    items: &[CellValue],
+    match_mode: i32,
+    search_mode: i32,
+) -> std::result::Result<Option<usize>, String> {
+    use std::cmp::Ordering;
+
+    if !matches!(match_mode, -1..=2) || !matches!(search_mode, -2 | -1 | 1 | 2) {
+        return Err("#VALUE!".to_string());
+    }
+    if search_mode.abs() == 2 {
+        if match_mode == 2 {
+            return Err("#VALUE!".to_string());
+        }
+        return Ok(binary_position(lookup, items, match_mode, search_mode > 0));
+    }
+
+    let order: Box<dyn Iterator<Item = usize>> = if search_mode > 0 {
+        Box::new(0..items.len())
+    } else {
+        Box::new((0..items.len()).rev())
+    };
+    let pattern = match (match_mode, lookup) {
+        (2, CellValue::String(s)) => Some(s.to_lowercase()),
+        _ => None,
+    };
+    let mut best: Option<usize> = None;
+    for i in order {
+        let item = &items[i];
+        if let Some(pattern) = &pattern {
+            if matches!(item, CellValue::String(s) if wildcard_match(&s.to_lowercase(), pattern)) {
+                return Ok(Some(i));
+            }
+            continue;
+        }
+        let ord = compare_values(item, lookup);
+        if ord == Ordering::Equal {
+            return Ok(Some(i));
+        }
+        if std::mem::discriminant(item) != std::mem::discriminant(lookup) {
+            continue;
+        }
+        // Next smaller keeps the largest item below the value; next larger
+        // keeps the smallest item above it.
+        let wanted = match match_mode {
+            -1 => Ordering::Less,
+            1 => Ordering::Greater,
+            _ => continue,
+        };
+        if ord == wanted && best.is_none_or(|b| compare_values(item, &items[b]) == wanted.reverse())
+        {
+            best = Some(i);
+        }
+    }
+    Ok(best)
+}
+
+/// Binary search for [`find_position`] over items sorted ascending or
+/// descending.
+fn binary_position(
+    lookup: &CellValue,
+    items: &[CellValue],
+    match_mode: i32,
+    ascending: bool,
+) -> Option<usize> {
+    use std::cmp::Ordering;
+
+    // Items before `split` are on the near side of the value: at most the
+    // value when ascending, at least the value when descending.
+    let far = if ascending {
+        Ordering::Greater
+    } else {
+        Ordering::Less
+    };
+    let split = items.partition_point(|item| compare_values(item, lookup) != far);
+    let last_near = split.checked_sub(1);
+    let exact = last_near.filter(|&i| compare_values(&items[i], lookup) == Ordering::Equal);
+    let next_far = (split < items.len()).then_some(split);
+    // Ascending items grow past the value; descending items shrink past it.
+    let (toward_smaller, toward_larger) = if ascending {
+        (last_near, exact.or(next_far))
+    } else {
+        (exact.or(next_far), last_near)
+    };
+    match match_mode {
+        0 => exact,
+        -1 => toward_smaller,
+        _ => toward_larger,
+    }
+}
+
+/// TRANSPOSE(array) - swaps the rows and columns of an array.
+pub fn fn_transpose(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("TRANSPOSE", args, 1, 1)?;
+    let array = ctx.eval_array(&args[0])?;
+    let values = (0..array.cols)
+        .flat_map(|c| (0..array.rows).map(move |r| (r, c)))
+        .map(|(r, c)| array.values[r * array.cols + c].clone())
+        .collect();
+    Ok(ArrayValue::new(array.cols, array.rows, values))
+}
+
+/// HYPERLINK(link_location, [friendly_name]) - returns the text displayed
+/// for a link: `friendly_name` if given, otherwise the link itself.
+pub fn fn_hyperlink(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("HYPERLINK", args, 1, 2)?;
+    let link = ctx.eval_expr(&args[0])?;
+    if args.len() > 1 {
+        return ctx.eval_expr(&args[1]);
+    }
+    Ok(match link {
+        CellValue::Error(_) => link,
+        other => CellValue::String(coerce_to_string(&other)),
+    })
+}
+
+/// AREAS(reference) - returns the number of areas in a reference.
+pub fn fn_areas(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("AREAS", args, 1, 1)?;
+    let reference = ctx.resolve_ref(&args[0])?.into_owned();
+    let mut areas = Vec::new();
+    if union_areas(&reference, &mut areas) {
+        return Ok(CellValue::Number(areas.len() as f64));
+    }
+    Ok(match reference {
+        Expr::Error(e) => CellValue::Error(e),
+        _ => CellValue::Error("#VALUE!".to_string()),
+    })
+}
+
+/// GETPIVOTDATA(data_field, pivot_table, [field1, item1], ...) - returns the
+/// summarized value of `data_field` in the pivot table containing the
+/// `pivot_table` cell, restricted to the source rows whose row or column
+/// fields equal the given items.
+pub fn fn_getpivotdata(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("GETPIVOTDATA", args, 2, 254)?;
+    let ref_error = || Ok(CellValue::Error("#REF!".to_string()));
+    if !args.len().is_multiple_of(2) {
+        return ref_error();
+    }
+    let data_name = coerce_to_string(&ctx.eval_expr(&args[0])?);
+    let anchor = match reference_area(&args[1], ctx)? {
+        Ok(area) => area,
+        Err(e) => return Ok(CellValue::Error(e)),
+    };
+    let Some(pivot) = ctx.pivot_table_at(&anchor.sheet, anchor.min_col, anchor.min_row) else {
+        return ref_error();
+    };
+    let Some(data_field) = pivot.data_fields.iter().find(|d| {
+        d.name.eq_ignore_ascii_case(&data_name) || d.field.eq_ignore_ascii_case(&data_name)
+    }) else {
+        return ref_error();
+    };
+
+    let (values, num_cols, num_rows) = read_range(&pivot.source.to_expr(), ctx)?;
+    let column_of = |name: &str| {
+        values[..num_cols]
+            .iter()
+            .position(|v| coerce_to_string(v).eq_ignore_ascii_case(name))
+    };
+    let Some(data_col) = column_of(&data_field.field) else {
+        return ref_error();
+    };
+    let mut filters = Vec::new();
+    for pair in args[2..].chunks(2) {
+        let field = coerce_to_string(&ctx.eval_expr(&pair[0])?);
+        let on_axis = pivot
+            .row_fields
+            .iter()
+            .chain(&pivot.col_fields)
+            .any(|f| f.eq_ignore_ascii_case(&field));
+        let Some(col) = column_of(&field).filter(|_| on_axis) else {
+            return ref_error();
+        };
+        filters.push((col, ctx.eval_expr(&pair[1])?));
+    }
+
+    let mut matched = 0usize;
+    let mut non_empty = 0usize;
+    let mut nums = Vec::new();
+    for row in values.chunks(num_cols).take(num_rows).skip(1) {
+        if !filters
+            .iter()
+            .all(|(col, item)| compare_values(&row[*col], item) == std::cmp::Ordering::Equal)
+        {
+            continue;
+        }
+        matched += 1;
+        match &row[data_col] {
+            CellValue::Empty => {}
+            CellValue::Number(n) => {
+                non_empty += 1;
+                nums.push(*n);
+            }
+            _ => non_empty += 1,
+        }
+    }
+    if matched == 0 {
+        return ref_error();
+    }
+    Ok(aggregate(&data_field.function, &nums, non_empty))
+}
+
+/// Summarize a pivot value field: `nums` are its numeric values and
+/// `non_empty` counts all of its non-empty values.
+fn aggregate(function: &AggregateFunction, nums: &[f64], non_empty: usize) -> CellValue {
+    let n = nums.len() as f64;
+    let mean = nums.iter().sum::<f64>() / n;
+    let variance = |sample: bool| {
+        let divisor = if sample { n - 1.0 } else { n };
+        if divisor < 1.0 {
+            return None;
+        }
+        Some(nums.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / divisor)
+    };
+    let result = match function {
+        AggregateFunction::Sum => Some(nums.iter().sum()),
+        AggregateFunction::Count => Some(non_empty as f64),
+        AggregateFunction::CountNums => Some(n),
+        AggregateFunction::Average => (n > 0.0).then_some(mean),
+        AggregateFunction::Max => Some(nums.iter().copied().reduce(f64::max).unwrap_or(0.0)),
+        AggregateFunction::Min => Some(nums.iter().copied().reduce(f64::min).unwrap_or(0.0)),
+        AggregateFunction::Product => Some(nums.iter().product()),
+        AggregateFunction::StdDev => variance(true).map(f64::sqrt),
+        AggregateFunction::StdDevP => variance(false).map(f64::sqrt),
+        AggregateFunction::Var => variance(true),
+        AggregateFunction::VarP => variance(false),
+    };
+    match result {
+        Some(v) => CellValue::Number(v),
+        None => CellValue::Error("#DIV/0!".to_string()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::formula::eval::{evaluate, CellSnapshot};
+    use crate::formula::eval::{evaluate, CellSnapshot, PivotTableSource, PivotValueField};
     use crate::formula::parser::parse_formula;
 
     fn eval(formula: &str) -> CellValue {
@@ -370,6 +897,22 @@ mod tests {
         evaluate(&expr, snap).unwrap()
     }
 
+    fn eval_array(formula: &str, snap: &CellSnapshot) -> ArrayValue {
+        let expr = parse_formula(formula).unwrap();
+        Evaluator::new(snap).eval_array(&expr).unwrap()
+    }
+
+    /// Keys 10, 20, 30, 40 in A1:A4 with names in B1:B4.
+    fn keyed_snapshot() -> CellSnapshot {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        for (i, name) in ["ten", "twenty", "thirty", "forty"].iter().enumerate() {
+            let row = i as u32 + 1;
+            snap.set_cell("Sheet1", 1, row, CellValue::Number(10.0 * f64::from(row)));
+            snap.set_cell("Sheet1", 2, row, CellValue::String(name.to_string()));
+        }
+        snap
+    }
+
     #[test]
     fn test_vlookup_exact() {
         let mut snap = CellSnapshot::new("Sheet1".to_string());
@@ -446,6 +989,106 @@ mod tests {
         );
     }
 
+    fn grid_snapshot() -> CellSnapshot {
+        // A1:C3 holds 1..=9 row by row.
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        for row in 1..=3 {
+            for col in 1..=3 {
+                let n = ((row - 1) * 3 + col) as f64;
+                snap.set_cell("Sheet1", col, row, CellValue::Number(n));
+            }
+        }
+        snap
+    }
+
+    #[test]
+    fn test_index_reference_form() {
+        let snap = grid_snapshot();
+        assert_eq!(
+            eval_with_data("SUM(INDEX(A1:C3,0,2))", &snap),
+            CellValue::Number(15.0)
+        );
+        assert_eq!(
+            eval_with_data("SUM(INDEX(A1:C3,3,0))", &snap),
+            CellValue::Number(24.0)
+        );
+        assert_eq!(
+            eval_with_data("SUM(A1:INDEX(A1:C3,2,2))", &snap),
+            CellValue::Number(12.0)
+        );
+        assert_eq!(
+            eval_with_data("INDEX((A1:A3,C1:C3),2,1,2)", &snap),
+            CellValue::Number(6.0)
+        );
+        assert_eq!(
+            eval_with_data("INDEX(A2:C2,3)", &snap),
+            CellValue::Number(6.0)
+        );
+        assert_eq!(
+            eval_with_data("INDEX(A1:C3,4,1)", &snap),
+            CellValue::Error("#REF!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_offset() {
+        let snap = grid_snapshot();
+        assert_eq!(
+            eval_with_data("OFFSET(A1,1,2)", &snap),
+            CellValue::Number(6.0)
+        );
+        assert_eq!(
+            eval_with_data("SUM(OFFSET(A1,1,0,2,3))", &snap),
+            CellValue::Number(39.0)
+        );
+        assert_eq!(
+            eval_with_data("ROWS(OFFSET(A1:B2,0,0,3))", &snap),
+            CellValue::Number(3.0)
+        );
+        assert_eq!(
+            eval_with_data("OFFSET(A1,-1,0)", &snap),
+            CellValue::Error("#REF!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_indirect() {
+        let mut snap = grid_snapshot();
+        snap.set_cell("Sheet 2", 2, 3, CellValue::Number(42.0));
+        snap.set_cell("Sheet1", 4, 1, CellValue::String("B2".to_string()));
+        assert_eq!(
+            eval_with_data("INDIRECT(D1)", &snap),
+            CellValue::Number(5.0)
+        );
+        assert_eq!(
+            eval_with_data("SUM(INDIRECT(\"A1:\"&\"C1\"))", &snap),
+            CellValue::Number(6.0)
+        );
+        assert_eq!(
+            eval_with_data("INDIRECT(\"'Sheet 2'!B3\")", &snap),
+            CellValue::Number(42.0)
+        );
+        assert_eq!(
+            eval_with_data("SUM(INDIRECT(\"R1C1:R2C2\",FALSE))", &snap),
+            CellValue::Number(12.0)
+        );
+        assert_eq!(
+            eval_with_data("INDIRECT(\"not a ref\")", &snap),
+            CellValue::Error("#REF!".to_string())
+        );
+
+        // Relative R1C1 references are taken from the formula cell.
+        snap.set_current_cell(Some((2, 2)));
+        assert_eq!(
+            eval_with_data("INDIRECT(\"R[1]C[-1]\",FALSE)", &snap),
+            CellValue::Number(7.0)
+        );
+        assert_eq!(
+            eval_with_data("INDIRECT(\"RC[1]\",FALSE)", &snap),
+            CellValue::Number(6.0)
+        );
+    }
+
     #[test]
     fn test_match_exact() {
         let mut snap = CellSnapshot::new("Sheet1".to_string());
@@ -550,4 +1193,299 @@ mod tests {
     fn test_column_no_args() {
         assert_eq!(eval("COLUMN()"), CellValue::Number(1.0));
     }
+
+    // XLOOKUP / XMATCH tests
+
+    #[test]
+    fn test_xlookup_exact() {
+        let snap = keyed_snapshot();
+        assert_eq!(
+            eval_with_data("XLOOKUP(30,A1:A4,B1:B4)", &snap),
+            CellValue::String("thirty".to_string())
+        );
+        assert_eq!(
+            eval_with_data("XLOOKUP(35,A1:A4,B1:B4)", &snap),
+            CellValue::Error("#N/A".to_string())
+        );
+        assert_eq!(
+            eval_with_data("XLOOKUP(35,A1:A4,B1:B4,\"none\")", &snap),
+            CellValue::String("none".to_string())
+        );
+    }
+
+    #[test]
+    fn test_xlookup_xmatch_omitted_arguments() {
+        let snap = keyed_snapshot();
+        assert_eq!(
+            eval_with_data("XLOOKUP(35,A1:A4,B1:B4,,1)", &snap),
+            CellValue::String("forty".to_string())
+        );
+        assert_eq!(
+            eval_with_data("XLOOKUP(99,A1:A4,B1:B4,,1)", &snap),
+            CellValue::Error("#N/A".to_string())
+        );
+        assert_eq!(
+            eval_with_data("XMATCH(20,A1:A4,,-1)", &snap),
+            CellValue::Number(2.0)
+        );
+        assert_eq!(
+            eval_with_data("XMATCH(25,A1:A4,,2)", &snap),
+            CellValue::Error("#N/A".to_string())
+        );
+    }
+
+    #[test]
+    fn test_xlookup_returns_row() {
+        let snap = keyed_snapshot();
+        let array = eval_array("XLOOKUP(20,A1:A4,A1:B4)", &snap);
+        assert_eq!((array.rows, array.cols), (1, 2));
+        assert_eq!(
+            array.values,
+            vec![
+                CellValue::Number(20.0),
+                CellValue::String("twenty".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_xlookup_horizontal() {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        for col in 1..=3 {
+            snap.set_cell("Sheet1", col, 1, CellValue::Number(f64::from(col)));
+            snap.set_cell("Sheet1", col, 2, CellValue::Number(f64::from(col) * 100.0));
+        }
+        assert_eq!(
+            eval_with_data("XLOOKUP(2,A1:C1,A2:C2)", &snap),
+            CellValue::Number(200.0)
+        );
+        assert_eq!(
+            eval_with_data("XLOOKUP(2,A1:C1,A2:B2)", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_xlookup_next_smaller_and_larger() {
+        let snap = keyed_snapshot();
+        assert_eq!(
+            eval_with_data("XLOOKUP(35,A1:A4,B1:B4,\"\",-1)", &snap),
+            CellValue::String("thirty".to_string())
+        );
+        assert_eq!(
+            eval_with_data("XLOOKUP(35,A1:A4,B1:B4,\"\",1)", &snap),
+            CellValue::String("forty".to_string())
+        );
+        assert_eq!(
+            eval_with_data("XLOOKUP(5,A1:A4,B1:B4,\"low\",-1)", &snap),
+            CellValue::String("low".to_string())
+        );
+    }
+
+    #[test]
+    fn test_xlookup_wildcard() {
+        let snap = keyed_snapshot();
+        assert_eq!(
+            eval_with_data("XLOOKUP(\"th*\",B1:B4,A1:A4,\"\",2)", &snap),
+            CellValue::Number(30.0)
+        );
+        assert_eq!(
+            eval_with_data("XLOOKUP(\"?en\",B1:B4,A1:A4,\"\",2)", &snap),
+            CellValue::Number(10.0)
+        );
+        assert_eq!(
+            eval_with_data("XLOOKUP(\"t*\",B1:B4,A1:A4,\"\",2,-1)", &snap),
+            CellValue::Number(30.0)
+        );
+    }
+
+    #[test]
+    fn test_xmatch_search_modes() {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        for (row, v) in [1.0, 2.0, 2.0, 3.0].iter().enumerate() {
+            snap.set_cell("Sheet1", 1, row as u32 + 1, CellValue::Number(*v));
+        }
+        assert_eq!(
+            eval_with_data("XMATCH(2,A1:A4)", &snap),
+            CellValue::Number(2.0)
+        );
+        assert_eq!(
+            eval_with_data("XMATCH(2,A1:A4,0,-1)", &snap),
+            CellValue::Number(3.0)
+        );
+        assert_eq!(
+            eval_with_data("XMATCH(2.5,A1:A4,1,2)", &snap),
+            CellValue::Number(4.0)
+        );
+        assert_eq!(
+            eval_with_data("XMATCH(2.5,A1:A4,-1,2)", &snap),
+            CellValue::Number(3.0)
+        );
+        assert_eq!(
+            eval_with_data("XMATCH(9,A1:A4,0,2)", &snap),
+            CellValue::Error("#N/A".to_string())
+        );
+        assert_eq!(
+            eval_with_data("XMATCH(2,A1:A4,0,3)", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_xmatch_binary_descending() {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        for (row, v) in [40.0, 30.0, 20.0, 10.0].iter().enumerate() {
+            snap.set_cell("Sheet1", 1, row as u32 + 1, CellValue::Number(*v));
+        }
+        assert_eq!(
+            eval_with_data("XMATCH(20,A1:A4,0,-2)", &snap),
+            CellValue::Number(3.0)
+        );
+        assert_eq!(
+            eval_with_data("XMATCH(25,A1:A4,1,-2)", &snap),
+            CellValue::Number(2.0)
+        );
+        assert_eq!(
+            eval_with_data("XMATCH(25,A1:A4,-1,-2)", &snap),
+            CellValue::Number(3.0)
+        );
+        assert_eq!(
+            eval_with_data("XMATCH(\"a*\",A1:A4,2,-2)", &snap),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
+
+    // TRANSPOSE / HYPERLINK / AREAS tests
+
+    #[test]
+    fn test_transpose() {
+        let snap = keyed_snapshot();
+        let array = eval_array("TRANSPOSE(A1:B2)", &snap);
+        assert_eq!((array.rows, array.cols), (2, 2));
+        assert_eq!(
+            array.values,
+            vec![
+                CellValue::Number(10.0),
+                CellValue::Number(20.0),
+                CellValue::String("ten".to_string()),
+                CellValue::String("twenty".to_string()),
+            ]
+        );
+        let array = eval_array("TRANSPOSE(A1:A4)", &snap);
+        assert_eq!((array.rows, array.cols), (1, 4));
+    }
+
+    #[test]
+    fn test_hyperlink() {
+        assert_eq!(
+            eval("HYPERLINK(\"https://example.com\")"),
+            CellValue::String("https://example.com".to_string())
+        );
+        assert_eq!(
+            eval("HYPERLINK(\"https://example.com\",\"Example\")"),
+            CellValue::String("Example".to_string())
+        );
+    }
+
+    #[test]
+    fn test_areas() {
+        assert_eq!(eval("AREAS(A1:B2)"), CellValue::Number(1.0));
+        assert_eq!(eval("AREAS((A1:B2,C3,D4:E5))"), CellValue::Number(3.0));
+        assert_eq!(eval("AREAS(1)"), CellValue::Error("#VALUE!".to_string()));
+    }
+
+    // GETPIVOTDATA tests
+
+    fn pivot_snapshot() -> CellSnapshot {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        let rows = [
+            ("Region", "Product", CellValue::String("Sales".to_string())),
+            ("North", "Apple", CellValue::Number(10.0)),
+            ("North", "Pear", CellValue::Number(20.0)),
+            ("South", "Apple", CellValue::Number(30.0)),
+            ("South", "Apple", CellValue::Number(40.0)),
+        ];
+        for (i, (region, product, sales)) in rows.into_iter().enumerate() {
+            let row = i as u32 + 1;
+            snap.set_cell("Sheet1", 1, row, CellValue::String(region.to_string()));
+            snap.set_cell("Sheet1", 2, row, CellValue::String(product.to_string()));
+            snap.set_cell("Sheet1", 3, row, sales);
+        }
+        snap.add_pivot_table(PivotTableSource {
+            name: "PivotTable1".to_string(),
+            location: RefArea::from_a1("Sheet1", "E1:H4").unwrap(),
+            source: RefArea::from_a1("Sheet1", "A1:C5").unwrap(),
+            row_fields: vec!["Region".to_string()],
+            col_fields: vec!["Product".to_string()],
+            data_fields: vec![
+                PivotValueField {
+                    name: "Sum of Sales".to_string(),
+                    field: "Sales".to_string(),
+                    function: AggregateFunction::Sum,
+                },
+                PivotValueField {
+                    name: "Max of Sales".to_string(),
+                    field: "Sales".to_string(),
+                    function: AggregateFunction::Max,
+                },
+            ],
+        });
+        snap
+    }
+
+    #[test]
+    fn test_getpivotdata_totals_and_items() {
+        let snap = pivot_snapshot();
+        assert_eq!(
+            eval_with_data("GETPIVOTDATA(\"Sales\",F2)", &snap),
+            CellValue::Number(100.0)
+        );
+        assert_eq!(
+            eval_with_data(
+                "GETPIVOTDATA(\"Sum of Sales\",E1,\"Region\",\"South\")",
+                &snap
+            ),
+            CellValue::Number(70.0)
+        );
+        assert_eq!(
+            eval_with_data(
+                "GETPIVOTDATA(\"Sales\",E1,\"Region\",\"North\",\"Product\",\"apple\")",
+                &snap
+            ),
+            CellValue::Number(10.0)
+        );
+        assert_eq!(
+            eval_with_data(
+                "GETPIVOTDATA(\"Max of Sales\",E1,\"Product\",\"Apple\")",
+                &snap
+            ),
+            CellValue::Number(40.0)
+        );
+    }
+
+    #[test]
+    fn test_getpivotdata_errors() {
+        let snap = pivot_snapshot();
+        let ref_error = CellValue::Error("#REF!".to_string());
+        // Not inside a pivot table.
+        assert_eq!(
+            eval_with_data("GETPIVOTDATA(\"Sales\",A1)", &snap),
+            ref_error
+        );
+        // Unknown data field.
+        assert_eq!(
+            eval_with_data("GETPIVOTDATA(\"Cost\",E1)", &snap),
+            ref_error
+        );
+        // Item not present.
+        assert_eq!(
+            eval_with_data("GETPIVOTDATA(\"Sales\",E1,\"Region\",\"East\")", &snap),
+            ref_error
+        );
+        // Field and item must come in pairs.
+        assert_eq!(
+            eval_with_data("GETPIVOTDATA(\"Sales\",E1,\"Region\")", &snap),
+            ref_error
+        );
+    }
 }
@@ -1,11 +1,19 @@
 //! Math formula functions: SUMIF, SUMIFS, ROUNDUP, ROUNDDOWN, CEILING, FLOOR,
-//! SIGN, RAND, RANDBETWEEN, PI, LOG, LOG10, LN, EXP, PRODUCT, QUOTIENT, FACT.
+//! SIGN, RAND, RANDBETWEEN, PI, LOG, LOG10, LN, EXP, PRODUCT, QUOTIENT, FACT,
+//! the trigonometric and hyperbolic functions, rounding variants such as
+//! MROUND and CEILING.MATH, combinatorics (GCD, LCM, COMBIN, PERMUT, ...),
+//! sums of products and squares, base conversion, the matrix functions
+//! MMULT, MDETERM, MINVERSE and MUNIT, and SUBTOTAL and AGGREGATE.
+
+use std::collections::HashMap;
 
 use crate::cell::CellValue;
 use crate::error::Result;
 use crate::formula::ast::Expr;
-use crate::formula::eval::{coerce_to_number, coerce_to_string, Evaluator};
-use crate::formula::functions::{check_arg_count, collect_criteria_range_values, matches_criteria};
+use crate::formula::eval::{coerce_to_number, coerce_to_string, ArrayValue, Evaluator};
+use crate::formula::functions::{
+    check_arg_count, collect_criteria_range_values, matches_criteria, statistical,
+};
 
 /// SUMIF(range, criteria, [sum_range])
 pub fn fn_sumif(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
@@ -213,7 +221,7 @@ pub fn fn_ln(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 pub fn fn_exp(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("EXP", args, 1, 1)?;
     let n = coerce_to_number(&ctx.eval_expr(&args[0])?)?;
-    Ok(CellValue::Number(n.exp()))
+    Ok(finite_number(n.exp()))
 }
 
 /// PRODUCT(args...) - product of all numbers
@@ -251,14 +259,923 @@ pub fn fn_fact(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     for i in 2..=n_int {
         result *= i as f64;
     }
-    Ok(CellValue::Number(result))
+    Ok(finite_number(result))
+}
+
+fn num_error() -> CellValue {
+    CellValue::Error("#NUM!".to_string())
+}
+
+/// Wrap a result as a number, or `#NUM!` if it overflowed to infinity or is
+/// NaN.
+pub(crate) fn finite_number(value: f64) -> CellValue {
+    if value.is_finite() {
+        CellValue::Number(value)
+    } else {
+        num_error()
+    }
+}
+
+/// Evaluate the argument at `index` as a number.
+fn number_arg(args: &[Expr], index: usize, ctx: &mut Evaluator) -> Result<f64> {
+    coerce_to_number(&ctx.eval_expr(&args[index])?)
+}
+
+/// Evaluate an optional numeric argument, falling back to `default`.
+fn opt_number_arg(args: &[Expr], index: usize, default: f64, ctx: &mut Evaluator) -> Result<f64> {
+    if index < args.len() {
+        number_arg(args, index, ctx)
+    } else {
+        Ok(default)
+    }
+}
+
+/// Apply a one-argument numeric function. `f` returns `None` for arguments
+/// outside its domain, which yield `err`.
+fn unary(
+    name: &str,
+    args: &[Expr],
+    ctx: &mut Evaluator,
+    err: &str,
+    f: impl Fn(f64) -> Option<f64>,
+) -> Result<CellValue> {
+    check_arg_count(name, args, 1, 1)?;
+    let x = number_arg(args, 0, ctx)?;
+    Ok(match f(x) {
+        Some(v) if v.is_finite() => CellValue::Number(v),
+        _ => CellValue::Error(err.to_string()),
+    })
+}
+
+/// SIN(number)
+pub fn fn_sin(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("SIN", args, ctx, "#NUM!", |x| Some(x.sin()))
+}
+
+/// COS(number)
+pub fn fn_cos(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("COS", args, ctx, "#NUM!", |x| Some(x.cos()))
+}
+
+/// TAN(number)
+pub fn fn_tan(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("TAN", args, ctx, "#NUM!", |x| Some(x.tan()))
+}
+
+/// ASIN(number) - arcsine, for numbers in [-1, 1]
+pub fn fn_asin(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("ASIN", args, ctx, "#NUM!", |x| {
+        (-1.0..=1.0).contains(&x).then(|| x.asin())
+    })
+}
+
+/// ACOS(number) - arccosine, for numbers in [-1, 1]
+pub fn fn_acos(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("ACOS", args, ctx, "#NUM!", |x| {
+        (-1.0..=1.0).contains(&x).then(|| x.acos())
+    })
+}
+
+/// ATAN(number)
+pub fn fn_atan(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("ATAN", args, ctx, "#NUM!", |x| Some(x.atan()))
+}
+
+/// ATAN2(x_num, y_num) - angle of the point (x, y) from the x-axis
+pub fn fn_atan2(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("ATAN2", args, 2, 2)?;
+    let x = number_arg(args, 0, ctx)?;
+    let y = number_arg(args, 1, ctx)?;
+    if x == 0.0 && y == 0.0 {
+        return Ok(CellValue::Error("#DIV/0!".to_string()));
+    }
+    Ok(CellValue::Number(y.atan2(x)))
+}
+
+/// ACOT(number) - arccotangent, in (0, pi)
+pub fn fn_acot(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("ACOT", args, ctx, "#NUM!", |x| {
+        Some(std::f64::consts::FRAC_PI_2 - x.atan())
+    })
+}
+
+/// COT(number)
+pub fn fn_cot(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("COT", args, ctx, "#DIV/0!", |x| {
+        (x != 0.0).then(|| 1.0 / x.tan())
+    })
+}
+
+/// CSC(number)
+pub fn fn_csc(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("CSC", args, ctx, "#DIV/0!", |x| {
+        (x != 0.0).then(|| 1.0 / x.sin())
+    })
+}
+
+/// SEC(number)
+pub fn fn_sec(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("SEC", args, ctx, "#NUM!", |x| Some(1.0 / x.cos()))
+}
+
+/// SINH(number)
+pub fn fn_sinh(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("SINH", args, ctx, "#NUM!", |x| Some(x.sinh()))
+}
+
+/// COSH(number)
+pub fn fn_cosh(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("COSH", args, ctx, "#NUM!", |x| Some(x.cosh()))
+}
+
+/// TANH(number)
+pub fn fn_tanh(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("TANH", args, ctx, "#NUM!", |x| Some(x.tanh()))
+}
+
+/// ASINH(number)
+pub fn fn_asinh(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("ASINH", args, ctx, "#NUM!", |x| Some(x.asinh()))
+}
+
+/// ACOSH(number) - for numbers >= 1
+pub fn fn_acosh(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("ACOSH", args, ctx, "#NUM!", |x| {
+        (x >= 1.0).then(|| x.acosh())
+    })
+}
+
+/// ATANH(number) - for numbers in (-1, 1)
+pub fn fn_atanh(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("ATANH", args, ctx, "#NUM!", |x| {
+        (x.abs() < 1.0).then(|| x.atanh())
+    })
+}
+
+/// ACOTH(number) - for numbers with an absolute value greater than 1
+pub fn fn_acoth(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("ACOTH", args, ctx, "#NUM!", |x| {
+        (x.abs() > 1.0).then(|| 0.5 * ((x + 1.0) / (x - 1.0)).ln())
+    })
+}
+
+/// COTH(number)
+pub fn fn_coth(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("COTH", args, ctx, "#DIV/0!", |x| {
+        (x != 0.0).then(|| 1.0 / x.tanh())
+    })
+}
+
+/// CSCH(number)
+pub fn fn_csch(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("CSCH", args, ctx, "#DIV/0!", |x| {
+        (x != 0.0).then(|| 1.0 / x.sinh())
+    })
+}
+
+/// SECH(number)
+pub fn fn_sech(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("SECH", args, ctx, "#NUM!", |x| Some(1.0 / x.cosh()))
+}
+
+/// DEGREES(angle) - convert radians to degrees
+pub fn fn_degrees(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("DEGREES", args, ctx, "#NUM!", |x| Some(x.to_degrees()))
+}
+
+/// RADIANS(angle) - convert degrees to radians
+pub fn fn_radians(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("RADIANS", args, ctx, "#NUM!", |x| Some(x.to_radians()))
+}
+
+/// SQRTPI(number) - square root of number * pi
+pub fn fn_sqrtpi(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("SQRTPI", args, ctx, "#NUM!", |x| {
+        (x >= 0.0).then(|| (x * std::f64::consts::PI).sqrt())
+    })
+}
+
+/// TRUNC(number, [digits]) - truncate toward zero
+pub fn fn_trunc(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("TRUNC", args, 1, 2)?;
+    let n = number_arg(args, 0, ctx)?;
+    let digits = opt_number_arg(args, 1, 0.0, ctx)?.trunc() as i32;
+    let factor = 10f64.powi(digits);
+    Ok(CellValue::Number((n * factor).trunc() / factor))
+}
+
+/// EVEN(number) - round away from zero to the nearest even integer
+pub fn fn_even(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("EVEN", args, ctx, "#NUM!", |x| {
+        Some(((x.abs() / 2.0).ceil() * 2.0).copysign(x))
+    })
+}
+
+/// ODD(number) - round away from zero to the nearest odd integer
+pub fn fn_odd(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    unary("ODD", args, ctx, "#NUM!", |x| {
+        let n = x.abs().ceil();
+        let odd = if n % 2.0 == 0.0 { n + 1.0 } else { n };
+        Some(if x < 0.0 { -odd } else { odd })
+    })
+}
+
+/// MROUND(number, multiple) - round to the nearest multiple, halves away
+/// from zero
+pub fn fn_mround(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("MROUND", args, 2, 2)?;
+    let n = number_arg(args, 0, ctx)?;
+    let multiple = number_arg(args, 1, ctx)?;
+    if multiple == 0.0 {
+        return Ok(CellValue::Number(0.0));
+    }
+    if n * multiple < 0.0 {
+        return Ok(num_error());
+    }
+    Ok(CellValue::Number((n / multiple).round() * multiple))
+}
+
+/// Round `n` to a multiple of `significance` with `round` (ceil or floor).
+fn round_to(n: f64, significance: f64, round: fn(f64) -> f64) -> f64 {
+    if significance == 0.0 {
+        0.0
+    } else {
+        round(n / significance) * significance
+    }
+}
+
+/// CEILING.MATH(number, [significance], [mode]) - round up; with a non-zero
+/// mode, negative numbers are rounded away from zero
+pub fn fn_ceiling_math(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("CEILING.MATH", args, 1, 3)?;
+    let n = number_arg(args, 0, ctx)?;
+    let sig = opt_number_arg(args, 1, 1.0, ctx)?.abs();
+    let mode = opt_number_arg(args, 2, 0.0, ctx)?;
+    let round = if n < 0.0 && mode != 0.0 {
+        f64::floor
+    } else {
+        f64::ceil
+    };
+    Ok(CellValue::Number(round_to(n, sig, round)))
+}
+
+/// FLOOR.MATH(number, [significance], [mode]) - round down; with a non-zero
+/// mode, negative numbers are rounded toward zero
+pub fn fn_floor_math(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("FLOOR.MATH", args, 1, 3)?;
+    let n = number_arg(args, 0, ctx)?;
+    let sig = opt_number_arg(args, 1, 1.0, ctx)?.abs();
+    let mode = opt_number_arg(args, 2, 0.0, ctx)?;
+    let round = if n < 0.0 && mode != 0.0 {
+        f64::ceil
+    } else {
+        f64::floor
+    };
+    Ok(CellValue::Number(round_to(n, sig, round)))
+}
+
+/// CEILING.PRECISE(number, [significance]) and ISO.CEILING - round up
+/// regardless of the signs
+pub fn fn_ceiling_precise(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("CEILING.PRECISE", args, 1, 2)?;
+    let n = number_arg(args, 0, ctx)?;
+    let sig = opt_number_arg(args, 1, 1.0, ctx)?.abs();
+    Ok(CellValue::Number(round_to(n, sig, f64::ceil)))
+}
+
+/// FLOOR.PRECISE(number, [significance]) - round down regardless of the
+/// signs
+pub fn fn_floor_precise(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("FLOOR.PRECISE", args, 1, 2)?;
+    let n = number_arg(args, 0, ctx)?;
+    let sig = opt_number_arg(args, 1, 1.0, ctx)?.abs();
+    Ok(CellValue::Number(round_to(n, sig, f64::floor)))
+}
+
+/// Collect the numbers of all arguments truncated to integers, or `None` if
+/// any is negative.
+fn non_negative_integers(args: &[Expr], ctx: &mut Evaluator) -> Result<Option<Vec<f64>>> {
+    let nums = ctx.collect_numbers(args)?;
+    if nums.iter().any(|n| *n < 0.0) {
+        return Ok(None);
+    }
+    Ok(Some(nums.into_iter().map(f64::trunc).collect()))
+}
+
+fn gcd(mut a: f64, mut b: f64) -> f64 {
+    while b != 0.0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// GCD(number1, ...) - greatest common divisor
+pub fn fn_gcd(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("GCD", args, 1, 255)?;
+    let Some(nums) = non_negative_integers(args, ctx)? else {
+        return Ok(num_error());
+    };
+    Ok(CellValue::Number(nums.into_iter().fold(0.0, gcd)))
+}
+
+/// LCM(number1, ...) - least common multiple
+pub fn fn_lcm(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("LCM", args, 1, 255)?;
+    let Some(nums) = non_negative_integers(args, ctx)? else {
+        return Ok(num_error());
+    };
+    let lcm = nums.into_iter().fold(1.0, |acc, n| {
+        if acc == 0.0 || n == 0.0 {
+            0.0
+        } else {
+            acc / gcd(acc, n) * n
+        }
+    });
+    Ok(CellValue::Number(lcm))
+}
+
+/// Number of ways to choose `k` items from `n`, ignoring order.
+fn combinations(n: f64, k: f64) -> f64 {
+    let k = k.min(n - k);
+    (0..k as u64).fold(1.0, |acc, i| acc * (n - i as f64) / (i as f64 + 1.0))
+}
+
+/// Evaluate the two integer arguments of COMBIN and friends, truncated.
+/// Returns `None` if either is negative or `n < k`.
+fn choose_args(args: &[Expr], ctx: &mut Evaluator) -> Result<Option<(f64, f64)>> {
+    let n = number_arg(args, 0, ctx)?.trunc();
+    let k = number_arg(args, 1, ctx)?.trunc();
+    Ok((n >= 0.0 && k >= 0.0 && n >= k).then_some((n, k)))
+}
+
+/// COMBIN(number, number_chosen) - combinations without repetition
+pub fn fn_combin(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("COMBIN", args, 2, 2)?;
+    let Some((n, k)) = choose_args(args, ctx)? else {
+        return Ok(num_error());
+    };
+    Ok(finite_number(combinations(n, k).round()))
+}
+
+/// COMBINA(number, number_chosen) - combinations with repetition
+pub fn fn_combina(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("COMBINA", args, 2, 2)?;
+    let n = number_arg(args, 0, ctx)?.trunc();
+    let k = number_arg(args, 1, ctx)?.trunc();
+    if n < 0.0 || k < 0.0 || (n == 0.0 && k > 0.0) {
+        return Ok(num_error());
+    }
+    if k == 0.0 {
+        return Ok(CellValue::Number(1.0));
+    }
+    Ok(finite_number(combinations(n + k - 1.0, k).round()))
+}
+
+/// PERMUT(number, number_chosen) - permutations without repetition
+pub fn fn_permut(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("PERMUT", args, 2, 2)?;
+    let Some((n, k)) = choose_args(args, ctx)? else {
+        return Ok(num_error());
+    };
+    let result = (0..k as u64).fold(1.0, |acc, i| acc * (n - i as f64));
+    Ok(finite_number(result))
+}
+
+/// MULTINOMIAL(number1, ...) - factorial of the sum divided by the product
+/// of the factorials
+pub fn fn_multinomial(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("MULTINOMIAL", args, 1, 255)?;
+    let Some(nums) = non_negative_integers(args, ctx)? else {
+        return Ok(num_error());
+    };
+    // Build the result as a product of binomial coefficients to keep the
+    // intermediate values small.
+    let mut total = 0.0;
+    let mut result = 1.0;
+    for n in nums {
+        total += n;
+        result *= combinations(total, n);
+    }
+    Ok(finite_number(result.round()))
+}
+
+/// FACTDOUBLE(number) - double factorial
+pub fn fn_factdouble(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("FACTDOUBLE", args, 1, 1)?;
+    let n = number_arg(args, 0, ctx)?.trunc();
+    if n < -1.0 {
+        return Ok(num_error());
+    }
+    let mut result = 1.0;
+    let mut i = n;
+    while i > 1.0 {
+        result *= i;
+        i -= 2.0;
+    }
+    Ok(finite_number(result))
+}
+
+/// SERIESSUM(x, n, m, coefficients) - sum of a1*x^n + a2*x^(n+m) + ...
+pub fn fn_seriessum(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("SERIESSUM", args, 4, 4)?;
+    let x = number_arg(args, 0, ctx)?;
+    let n = number_arg(args, 1, ctx)?;
+    let m = number_arg(args, 2, ctx)?;
+    let coefficients = ctx.eval_array(&args[3])?;
+    let mut total = 0.0;
+    for (i, a) in coefficients.values.iter().enumerate() {
+        if let CellValue::Error(e) = a {
+            return Ok(CellValue::Error(e.clone()));
+        }
+        let Ok(a) = coerce_to_number(a) else {
+            return Ok(CellValue::Error("#VALUE!".to_string()));
+        };
+        total += a * x.powf(n + i as f64 * m);
+    }
+    Ok(finite_number(total))
+}
+
+/// SUMSQ(number1, ...) - sum of squares
+pub fn fn_sumsq(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("SUMSQ", args, 1, 255)?;
+    let nums = ctx.collect_numbers(args)?;
+    Ok(CellValue::Number(nums.iter().map(|n| n * n).sum()))
+}
+
+/// SUMPRODUCT(array1, ...) - sum of the products of corresponding entries.
+/// Non-numeric entries count as zero.
+pub fn fn_sumproduct(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("SUMPRODUCT", args, 1, 255)?;
+    let mut arrays = Vec::with_capacity(args.len());
+    for arg in args {
+        arrays.push(ctx.eval_array(arg)?);
+    }
+    let (rows, cols) = (arrays[0].rows, arrays[0].cols);
+    if arrays.iter().any(|a| a.rows != rows || a.cols != cols) {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    let mut total = 0.0;
+    for i in 0..rows * cols {
+        let mut product = 1.0;
+        for array in &arrays {
+            match &array.values[i] {
+                CellValue::Error(e) => return Ok(CellValue::Error(e.clone())),
+                CellValue::Number(n) | CellValue::Date(n) => product *= n,
+                _ => product = 0.0,
+            }
+        }
+        total += product;
+    }
+    Ok(CellValue::Number(total))
+}
+
+/// Apply `f` to the pairs of corresponding numbers of two arrays and sum
+/// the results. Pairs where either entry is not a number are skipped.
+fn sum_pairs(
+    name: &str,
+    args: &[Expr],
+    ctx: &mut Evaluator,
+    f: fn(f64, f64) -> f64,
+) -> Result<CellValue> {
+    check_arg_count(name, args, 2, 2)?;
+    let xs = ctx.eval_array(&args[0])?;
+    let ys = ctx.eval_array(&args[1])?;
+    if xs.values.len() != ys.values.len() {
+        return Ok(CellValue::Error("#N/A".to_string()));
+    }
+    let mut total = 0.0;
+    for (x, y) in xs.values.iter().zip(&ys.values) {
+        match (x, y) {
+            (CellValue::Error(e), _) | (_, CellValue::Error(e)) => {
+                return Ok(CellValue::Error(e.clone()));
+            }
+            (
+                CellValue::Number(x) | CellValue::Date(x),
+                CellValue::Number(y) | CellValue::Date(y),
+            ) => total += f(*x, *y),
+            _ => {}
+        }
+    }
+    Ok(CellValue::Number(total))
+}
+
+/// SUMX2MY2(array_x, array_y) - sum of x^2 - y^2
+pub fn fn_sumx2my2(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    sum_pairs("SUMX2MY2", args, ctx, |x, y| x * x - y * y)
+}
+
+/// SUMX2PY2(array_x, array_y) - sum of x^2 + y^2
+pub fn fn_sumx2py2(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    sum_pairs("SUMX2PY2", args, ctx, |x, y| x * x + y * y)
+}
+
+/// SUMXMY2(array_x, array_y) - sum of (x - y)^2
+pub fn fn_sumxmy2(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    sum_pairs("SUMXMY2", args, ctx, |x, y| (x - y) * (x - y))
+}
+
+/// Convert an array to a row-major matrix of numbers. Empty cells, text and
+/// booleans yield `#VALUE!`, and error values are returned as they are.
+pub(crate) fn numeric_matrix(array: &ArrayValue) -> std::result::Result<Vec<Vec<f64>>, String> {
+    let mut matrix = Vec::with_capacity(array.rows);
+    for r in 0..array.rows {
+        let mut row = Vec::with_capacity(array.cols);
+        for v in array.row(r) {
+            match v {
+                CellValue::Number(n) | CellValue::Date(n) => row.push(*n),
+                CellValue::Error(e) => return Err(e.clone()),
+                _ => return Err("#VALUE!".to_string()),
+            }
+        }
+        matrix.push(row);
+    }
+    Ok(matrix)
+}
+
+/// Evaluate a square numeric matrix argument.
+fn square_matrix_arg(
+    arg: &Expr,
+    ctx: &mut Evaluator,
+) -> Result<std::result::Result<Vec<Vec<f64>>, String>> {
+    let array = ctx.eval_array(arg)?;
+    if array.rows != array.cols {
+        return Ok(Err("#VALUE!".to_string()));
+    }
+    Ok(numeric_matrix(&array))
+}
+
+/// Reduce `matrix` to the identity with Gauss-Jordan elimination, applying
+/// the same row operations to `other`. Returns the determinant of `matrix`.
+pub(crate) fn gauss_jordan(matrix: &mut [Vec<f64>], other: &mut [Vec<f64>]) -> f64 {
+    let n = matrix.len();
+    let mut det = 1.0;
+    for col in 0..n {
+        // Partial pivoting: use the row with the largest entry.
+        let pivot = (col..n)
+            .max_by(|&a, &b| matrix[a][col].abs().total_cmp(&matrix[b][col].abs()))
+            .unwrap_or(col);
+        if matrix[pivot][col] == 0.0 {
+            return 0.0;
+        }
+        if pivot != col {
+            matrix.swap(pivot, col);
+            other.swap(pivot, col);
+            det = -det;
+        }
+        let p = matrix[col][col];
+        det *= p;
+        matrix[col].iter_mut().for_each(|v| *v /= p);
+        other[col].iter_mut().for_each(|v| *v /= p);
+        let (pivot_row, pivot_other) = (matrix[col].clone(), other[col].clone());
+        for r in (0..n).filter(|&r| r != col) {
+            let factor = matrix[r][col];
+            if factor == 0.0 {
+                continue;
+            }
+            for (v, pv) in matrix[r].iter_mut().zip(&pivot_row) {
+                *v -= factor * pv;
+            }
+            for (v, pv) in other[r].iter_mut().zip(&pivot_other) {
+                *v -= factor * pv;
+            }
+        }
+    }
+    det
+}
+
+/// MDETERM(array) - determinant of a square matrix
+pub fn fn_mdeterm(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("MDETERM", args, 1, 1)?;
+    let mut matrix = match square_matrix_arg(&args[0], ctx)? {
+        Ok(m) => m,
+        Err(e) => return Ok(CellValue::Error(e)),
+    };
+    let mut none: Vec<Vec<f64>> = vec![Vec::new(); matrix.len()];
+    Ok(CellValue::Number(gauss_jordan(&mut matrix, &mut none)))
+}
+
+fn matrix_to_array(matrix: Vec<Vec<f64>>) -> ArrayValue {
+    let rows = matrix.len();
+    let cols = matrix.first().map_or(0, Vec::len);
+    let values = matrix
+        .into_iter()
+        .flatten()
+        .map(CellValue::Number)
+        .collect();
+    ArrayValue::new(rows, cols, values)
+}
+
+fn array_error(code: &str) -> ArrayValue {
+    ArrayValue::scalar(CellValue::Error(code.to_string()))
+}
+
+/// MINVERSE(array) - inverse of a square matrix
+pub fn fn_minverse(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("MINVERSE", args, 1, 1)?;
+    let mut matrix = match square_matrix_arg(&args[0], ctx)? {
+        Ok(m) => m,
+        Err(e) => return Ok(array_error(&e)),
+    };
+    let n = matrix.len();
+    let mut inverse: Vec<Vec<f64>> = (0..n)
+        .map(|r| (0..n).map(|c| if r == c { 1.0 } else { 0.0 }).collect())
+        .collect();
+    if gauss_jordan(&mut matrix, &mut inverse) == 0.0 {
+        return Ok(array_error("#NUM!"));
+    }
+    Ok(matrix_to_array(inverse))
+}
+
+/// MMULT(array1, array2) - matrix product
+pub fn fn_mmult(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("MMULT", args, 2, 2)?;
+    let a = ctx.eval_array(&args[0])?;
+    let b = ctx.eval_array(&args[1])?;
+    if a.cols != b.rows {
+        return Ok(array_error("#VALUE!"));
+    }
+    let (a, b) = match (numeric_matrix(&a), numeric_matrix(&b)) {
+        (Ok(a), Ok(b)) => (a, b),
+        (Err(e), _) | (_, Err(e)) => return Ok(array_error(&e)),
+    };
+    let product = a
+        .iter()
+        .map(|row| {
+            (0..b[0].len())
+                .map(|c| row.iter().zip(&b).map(|(x, b_row)| x * b_row[c]).sum())
+                .collect()
+        })
+        .collect();
+    Ok(matrix_to_array(product))
+}
+
+/// MUNIT(dimension) - identity matrix
+pub fn fn_munit(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("MUNIT", args, 1, 1)?;
+    let n = number_arg(args, 0, ctx)?.trunc();
+    if n < 1.0 {
+        return Ok(array_error("#VALUE!"));
+    }
+    let n = n as usize;
+    let values = (0..n * n)
+        .map(|i| CellValue::Number(if i / n == i % n { 1.0 } else { 0.0 }))
+        .collect();
+    Ok(ArrayValue::new(n, n, values))
+}
+
+const ROMAN_NUMERALS: [(u32, &str); 13] = [
+    (1000, "M"),
+    (900, "CM"),
+    (500, "D"),
+    (400, "CD"),
+    (100, "C"),
+    (90, "XC"),
+    (50, "L"),
+    (40, "XL"),
+    (10, "X"),
+    (9, "IX"),
+    (5, "V"),
+    (4, "IV"),
+    (1, "I"),
+];
+
+/// ROMAN(number, [form]) - Arabic to classic Roman numerals; the concise
+/// forms are not supported and also produce the classic form
+pub fn fn_roman(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("ROMAN", args, 1, 2)?;
+    let n = number_arg(args, 0, ctx)?.trunc();
+    if args.len() > 1 {
+        ctx.eval_expr(&args[1])?;
+    }
+    if !(0.0..=3999.0).contains(&n) {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    let mut n = n as u32;
+    let mut result = String::new();
+    for (value, numeral) in ROMAN_NUMERALS {
+        while n >= value {
+            result.push_str(numeral);
+            n -= value;
+        }
+    }
+    Ok(CellValue::String(result))
+}
+
+/// ARABIC(text) - Roman numerals to an Arabic number
+pub fn fn_arabic(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("ARABIC", args, 1, 1)?;
+    let text = coerce_to_string(&ctx.eval_expr(&args[0])?);
+    let text = text.trim();
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let mut total = 0.0;
+    let mut previous = 0.0;
+    for ch in digits.chars().rev() {
+        let value = match ch.to_ascii_uppercase() {
+            'I' => 1.0,
+            'V' => 5.0,
+            'X' => 10.0,
+            'L' => 50.0,
+            'C' => 100.0,
+            'D' => 500.0,
+            'M' => 1000.0,
+            _ => return Ok(CellValue::Error("#VALUE!".to_string())),
+        };
+        if value < previous {
+            total -= value;
+        } else {
+            total += value;
+            previous = value;
+        }
+    }
+    Ok(CellValue::Number(if negative { -total } else { total }))
+}
+
+/// BASE(number, radix, [min_length]) - a number as text in the given radix
+pub fn fn_base(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("BASE", args, 2, 3)?;
+    let n = number_arg(args, 0, ctx)?.trunc();
+    let radix = number_arg(args, 1, ctx)?.trunc();
+    let min_length = opt_number_arg(args, 2, 0.0, ctx)?.trunc();
+    if !(0.0..2f64.powi(53)).contains(&n)
+        || !(2.0..=36.0).contains(&radix)
+        || !(0.0..=255.0).contains(&min_length)
+    {
+        return Ok(num_error());
+    }
+    let (mut n, radix) = (n as u64, radix as u64);
+    let mut digits = Vec::new();
+    loop {
+        digits.push(char::from_digit((n % radix) as u32, radix as u32).unwrap_or('0'));
+        n /= radix;
+        if n == 0 {
+            break;
+        }
+    }
+    while digits.len() < min_length as usize {
+        digits.push('0');
+    }
+    let text: String = digits.iter().rev().collect();
+    Ok(CellValue::String(text.to_ascii_uppercase()))
+}
+
+/// DECIMAL(text, radix) - text in the given radix to a number
+pub fn fn_decimal(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("DECIMAL", args, 2, 2)?;
+    let text = coerce_to_string(&ctx.eval_expr(&args[0])?);
+    let radix = number_arg(args, 1, ctx)?.trunc();
+    if !(2.0..=36.0).contains(&radix) {
+        return Ok(num_error());
+    }
+    let mut total = 0.0;
+    for ch in text.chars() {
+        match ch.to_digit(radix as u32) {
+            Some(d) => total = total * radix + d as f64,
+            None => return Ok(num_error()),
+        }
+    }
+    Ok(CellValue::Number(total))
+}
+
+/// SUBTOTAL(function_num, ref1, ...) - AVERAGE, COUNT, ..., VARP (1-11) of
+/// the references. The evaluator does not know which rows are hidden or
+/// which cells hold SUBTOTAL formulas, so 101-111 behave like 1-11 and
+/// nested subtotals are included.
+pub fn fn_subtotal(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("SUBTOTAL", args, 2, 255)?;
+    let code = number_arg(args, 0, ctx)?.trunc();
+    let code = if code > 100.0 { code - 100.0 } else { code };
+    if !(1.0..=11.0).contains(&code) {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    aggregate(code as usize, &args[1..], 0.0, false, ctx)
+}
+
+/// AGGREGATE(function_num, options, ref1, ...) or
+/// AGGREGATE(function_num, options, array, k) - AVERAGE, COUNT, ...,
+/// QUARTILE.EXC (1-19), ignoring error values when options is 2, 3, 6 or
+/// 7. As with SUBTOTAL, the options that skip hidden rows and nested
+/// subtotals have no further effect.
+pub fn fn_aggregate(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("AGGREGATE", args, 3, 255)?;
+    let code = number_arg(args, 0, ctx)?.trunc();
+    let options = number_arg(args, 1, ctx)?.trunc();
+    let takes_k = (14.0..=19.0).contains(&code);
+    if !(1.0..=19.0).contains(&code)
+        || !(0.0..=7.0).contains(&options)
+        || (takes_k && args.len() != 4)
+    {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    let ignore_errors = matches!(options as u8, 2 | 3 | 6 | 7);
+    if takes_k {
+        let k = number_arg(args, 3, ctx)?;
+        aggregate(code as usize, &args[2..3], k, ignore_errors, ctx)
+    } else {
+        aggregate(code as usize, &args[2..], 0.0, ignore_errors, ctx)
+    }
+}
+
+/// Apply AGGREGATE function `code` to the values of `refs`. The first error
+/// value among them is the result unless `ignore_errors` is set, in which
+/// case errors are skipped.
+fn aggregate(
+    code: usize,
+    refs: &[Expr],
+    k: f64,
+    ignore_errors: bool,
+    ctx: &mut Evaluator,
+) -> Result<CellValue> {
+    let mut values = ctx.flatten_args_to_values(refs)?;
+    if !ignore_errors {
+        if let Some(e) = values.iter().find(|v| matches!(v, CellValue::Error(_))) {
+            return Ok(e.clone());
+        }
+    }
+    values.retain(|v| !matches!(v, CellValue::Error(_)));
+    Ok(aggregate_values(code, &values, k))
+}
+
+/// Apply AGGREGATE function `code` to values that hold no errors. `k` is
+/// the second argument of functions 14-19.
+fn aggregate_values(code: usize, values: &[CellValue], k: f64) -> CellValue {
+    let nums: Vec<f64> = values
+        .iter()
+        .filter_map(|v| match v {
+            CellValue::Number(n) | CellValue::Date(n) => Some(*n),
+            _ => None,
+        })
+        .collect();
+    let mut sorted = nums.clone();
+    sorted.sort_by(f64::total_cmp);
+    let n = nums.len();
+    let div_error = || CellValue::Error("#DIV/0!".to_string());
+    let variance = |sample: bool| {
+        let divisor = if sample { n as f64 - 1.0 } else { n as f64 };
+        (divisor >= 1.0).then(|| statistical::sum_sq_dev(&nums) / divisor)
+    };
+    let or_num_error = |v: Option<f64>| v.map_or_else(num_error, CellValue::Number);
+    match code {
+        1 if n == 0 => div_error(),
+        1 => CellValue::Number(nums.iter().sum::<f64>() / n as f64),
+        2 => CellValue::Number(n as f64),
+        3 => CellValue::Number(
+            values
+                .iter()
+                .filter(|v| !matches!(v, CellValue::Empty))
+                .count() as f64,
+        ),
+        4 => CellValue::Number(sorted.last().copied().unwrap_or(0.0)),
+        5 => CellValue::Number(sorted.first().copied().unwrap_or(0.0)),
+        6 if n == 0 => CellValue::Number(0.0),
+        6 => finite_number(nums.iter().product()),
+        7 | 8 => variance(code == 7).map_or_else(div_error, |v| CellValue::Number(v.sqrt())),
+        9 => CellValue::Number(nums.iter().sum()),
+        10 | 11 => variance(code == 10).map_or_else(div_error, CellValue::Number),
+        12 => or_num_error(
+            (n > 0)
+                .then(|| statistical::percentile_inc(&sorted, 0.5))
+                .flatten(),
+        ),
+        13 => mode(&nums).map_or_else(|| CellValue::Error("#N/A".to_string()), CellValue::Number),
+        14 | 15 => {
+            let k = k.trunc();
+            if k < 1.0 || k > n as f64 {
+                return num_error();
+            }
+            let index = if code == 14 {
+                n - k as usize
+            } else {
+                k as usize - 1
+            };
+            CellValue::Number(sorted[index])
+        }
+        _ if n == 0 => num_error(),
+        16 => or_num_error(statistical::percentile_inc(&sorted, k)),
+        17 => or_num_error(statistical::percentile_inc(&sorted, k.trunc() / 4.0)),
+        18 => or_num_error(statistical::percentile_exc(&sorted, k)),
+        _ => or_num_error(statistical::percentile_exc(&sorted, k.trunc() / 4.0)),
+    }
+}
+
+/// The most frequent of `nums`, preferring the earliest on ties, or `None`
+/// if no value repeats.
+fn mode(nums: &[f64]) -> Option<f64> {
+    let mut counts: HashMap<u64, usize> = HashMap::new();
+    for n in nums {
+        *counts.entry(n.to_bits()).or_default() += 1;
+    }
+    let max = counts.values().copied().max().filter(|c| *c > 1)?;
+    nums.iter().copied().find(|n| counts[&n.to_bits()] == max)
 }
 
 #[cfg(test)]
 #[allow(clippy::manual_range_contains)]
 mod tests {
     use crate::cell::CellValue;
-    use crate::formula::eval::{evaluate, CellSnapshot};
+    use crate::formula::eval::{evaluate, ArrayValue, CellSnapshot, Evaluator};
     use crate::formula::parser::parse_formula;
 
     fn eval_with_data(formula: &str, data: &[(&str, u32, u32, CellValue)]) -> CellValue {
@@ -509,6 +1426,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn exp_overflow() {
+        assert_eq!(eval("EXP(1000)"), CellValue::Error("#NUM!".to_string()));
+        assert_eq!(eval("POWER(10,400)"), CellValue::Error("#NUM!".to_string()));
+        assert_eq!(eval("POWER(-8,0.5)"), CellValue::Error("#NUM!".to_string()));
+        assert_eq!(eval("POWER(0,-1)"), CellValue::Error("#DIV/0!".to_string()));
+        assert_eq!(eval("10^400"), CellValue::Error("#NUM!".to_string()));
+        assert_eq!(eval("0^-1"), CellValue::Error("#DIV/0!".to_string()));
+        assert_eq!(eval("POWER(2,10)"), CellValue::Number(1024.0));
+    }
+
     #[test]
     fn exp_one() {
         if let CellValue::Number(n) = eval("EXP(1)") {
@@ -592,4 +1520,409 @@ mod tests {
             CellValue::Error("#NUM!".to_string())
         );
     }
+
+    fn num(formula: &str) -> f64 {
+        match eval(formula) {
+            CellValue::Number(n) => n,
+            other => panic!("{formula}: expected number, got {other:?}"),
+        }
+    }
+
+    fn err(code: &str) -> CellValue {
+        CellValue::Error(code.to_string())
+    }
+
+    /// A1:B2 = [[1, 2], [3, 4]], C1:C2 = [5, 6].
+    fn matrix_data() -> Vec<(&'static str, u32, u32, CellValue)> {
+        vec![
+            ("Sheet1", 1, 1, CellValue::Number(1.0)),
+            ("Sheet1", 2, 1, CellValue::Number(2.0)),
+            ("Sheet1", 1, 2, CellValue::Number(3.0)),
+            ("Sheet1", 2, 2, CellValue::Number(4.0)),
+            ("Sheet1", 3, 1, CellValue::Number(5.0)),
+            ("Sheet1", 3, 2, CellValue::Number(6.0)),
+        ]
+    }
+
+    fn eval_array(formula: &str, data: &[(&str, u32, u32, CellValue)]) -> ArrayValue {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        for (sheet, col, row, val) in data {
+            snap.set_cell(sheet, *col, *row, val.clone());
+        }
+        let expr = parse_formula(formula).unwrap();
+        Evaluator::new(&snap).eval_array(&expr).unwrap()
+    }
+
+    // Trigonometric and hyperbolic tests
+
+    #[test]
+    fn trig_functions() {
+        assert!(approx_eq(num("SIN(PI()/2)"), 1.0));
+        assert!(approx_eq(num("COS(0)"), 1.0));
+        assert!(approx_eq(num("TAN(PI()/4)"), 1.0));
+        assert!(approx_eq(num("ASIN(1)"), std::f64::consts::FRAC_PI_2));
+        assert!(approx_eq(num("ACOS(1)"), 0.0));
+        assert!(approx_eq(num("ATAN(1)"), std::f64::consts::FRAC_PI_4));
+        assert!(approx_eq(num("ACOT(0)"), std::f64::consts::FRAC_PI_2));
+        assert!(approx_eq(num("COT(PI()/4)"), 1.0));
+        assert!(approx_eq(num("CSC(PI()/2)"), 1.0));
+        assert!(approx_eq(num("SEC(0)"), 1.0));
+        assert_eq!(eval("ASIN(2)"), err("#NUM!"));
+        assert_eq!(eval("COT(0)"), err("#DIV/0!"));
+    }
+
+    #[test]
+    fn atan2_takes_x_first() {
+        assert!(approx_eq(num("ATAN2(-1,0)"), std::f64::consts::PI));
+        assert!(approx_eq(num("ATAN2(0,1)"), std::f64::consts::FRAC_PI_2));
+        assert_eq!(eval("ATAN2(0,0)"), err("#DIV/0!"));
+    }
+
+    #[test]
+    fn hyperbolic_functions() {
+        assert!(approx_eq(num("SINH(0)"), 0.0));
+        assert!(approx_eq(num("COSH(0)"), 1.0));
+        assert!(approx_eq(num("TANH(0.5)"), 0.5f64.tanh()));
+        assert!(approx_eq(num("ASINH(SINH(2))"), 2.0));
+        assert!(approx_eq(num("ACOSH(COSH(2))"), 2.0));
+        assert!(approx_eq(num("ATANH(TANH(0.5))"), 0.5));
+        assert!(approx_eq(num("ACOTH(COTH(2))"), 2.0));
+        assert!(approx_eq(num("SECH(0)"), 1.0));
+        assert!(approx_eq(num("CSCH(1)"), 1.0 / 1f64.sinh()));
+        assert_eq!(eval("ACOSH(0.5)"), err("#NUM!"));
+        assert_eq!(eval("ATANH(1)"), err("#NUM!"));
+        assert_eq!(eval("COTH(0)"), err("#DIV/0!"));
+    }
+
+    #[test]
+    fn degrees_radians_sqrtpi() {
+        assert!(approx_eq(num("DEGREES(PI())"), 180.0));
+        assert!(approx_eq(num("RADIANS(180)"), std::f64::consts::PI));
+        assert!(approx_eq(num("SQRTPI(1)"), std::f64::consts::PI.sqrt()));
+        assert_eq!(eval("SQRTPI(-1)"), err("#NUM!"));
+    }
+
+    // Rounding tests
+
+    #[test]
+    fn trunc_even_odd() {
+        assert_eq!(num("TRUNC(8.9)"), 8.0);
+        assert_eq!(num("TRUNC(-8.9)"), -8.0);
+        assert!(approx_eq(num("TRUNC(2.71828,2)"), 2.71));
+        assert_eq!(num("EVEN(1.5)"), 2.0);
+        assert_eq!(num("EVEN(3)"), 4.0);
+        assert_eq!(num("EVEN(-1)"), -2.0);
+        assert_eq!(num("EVEN(0)"), 0.0);
+        assert_eq!(num("ODD(1.5)"), 3.0);
+        assert_eq!(num("ODD(2)"), 3.0);
+        assert_eq!(num("ODD(-1)"), -1.0);
+        assert_eq!(num("ODD(0)"), 1.0);
+    }
+
+    #[test]
+    fn mround() {
+        assert_eq!(num("MROUND(10,3)"), 9.0);
+        assert_eq!(num("MROUND(-10,-3)"), -9.0);
+        assert!(approx_eq(num("MROUND(1.3,0.2)"), 1.4));
+        assert_eq!(num("MROUND(5,0)"), 0.0);
+        assert_eq!(eval("MROUND(5,-2)"), err("#NUM!"));
+    }
+
+    #[test]
+    fn ceiling_and_floor_variants() {
+        assert_eq!(num("CEILING.MATH(24.3,5)"), 25.0);
+        assert_eq!(num("CEILING.MATH(6.7)"), 7.0);
+        assert_eq!(num("CEILING.MATH(-8.1,2)"), -8.0);
+        assert_eq!(num("CEILING.MATH(-5.5,2,-1)"), -6.0);
+        assert_eq!(num("FLOOR.MATH(24.3,5)"), 20.0);
+        assert_eq!(num("FLOOR.MATH(-8.1,2)"), -10.0);
+        assert_eq!(num("FLOOR.MATH(-5.5,2,-1)"), -4.0);
+        assert_eq!(num("CEILING.PRECISE(-4.3)"), -4.0);
+        assert_eq!(num("CEILING.PRECISE(4.3,-2)"), 6.0);
+        assert_eq!(num("ISO.CEILING(4.3,2)"), 6.0);
+        assert_eq!(num("FLOOR.PRECISE(-3.2,-1)"), -4.0);
+        assert_eq!(num("FLOOR.PRECISE(3.2,0)"), 0.0);
+    }
+
+    // Combinatorics tests
+
+    #[test]
+    fn gcd_lcm() {
+        assert_eq!(num("GCD(5,2)"), 1.0);
+        assert_eq!(num("GCD(24,36,60)"), 12.0);
+        assert_eq!(num("GCD(7.9,0)"), 7.0);
+        assert_eq!(num("LCM(24,36)"), 72.0);
+        assert_eq!(num("LCM(3,5,0)"), 0.0);
+        assert_eq!(eval("GCD(-1,2)"), err("#NUM!"));
+        assert_eq!(eval("LCM(-1,2)"), err("#NUM!"));
+    }
+
+    #[test]
+    fn combin_permut() {
+        assert_eq!(num("COMBIN(8,2)"), 28.0);
+        assert_eq!(num("COMBIN(10,0)"), 1.0);
+        assert_eq!(num("COMBINA(4,3)"), 20.0);
+        assert_eq!(num("COMBINA(0,0)"), 1.0);
+        assert_eq!(num("PERMUT(100,3)"), 970200.0);
+        assert_eq!(num("PERMUT(3,0)"), 1.0);
+        assert_eq!(eval("COMBIN(2,3)"), err("#NUM!"));
+        assert_eq!(eval("PERMUT(-1,1)"), err("#NUM!"));
+        assert_eq!(eval("COMBIN(2000,1000)"), err("#NUM!"));
+        assert_eq!(eval("PERMUT(400,200)"), err("#NUM!"));
+    }
+
+    #[test]
+    fn multinomial_factdouble() {
+        assert_eq!(num("MULTINOMIAL(2,3,4)"), 1260.0);
+        assert_eq!(num("MULTINOMIAL(0)"), 1.0);
+        assert_eq!(eval("MULTINOMIAL(-1)"), err("#NUM!"));
+        assert_eq!(num("FACTDOUBLE(6)"), 48.0);
+        assert_eq!(num("FACTDOUBLE(7)"), 105.0);
+        assert_eq!(num("FACTDOUBLE(0)"), 1.0);
+        assert_eq!(num("FACTDOUBLE(-1)"), 1.0);
+        assert_eq!(eval("FACTDOUBLE(-2)"), err("#NUM!"));
+        // Results too large for a double are #NUM!, not infinity.
+        assert_eq!(eval("FACTDOUBLE(400)"), err("#NUM!"));
+        assert_eq!(eval("MULTINOMIAL(1000,1000)"), err("#NUM!"));
+        assert_eq!(eval("FACT(171)"), err("#NUM!"));
+    }
+
+    // Sum of products and squares tests
+
+    #[test]
+    fn seriessum() {
+        // Approximates cos(pi/4) with its first four terms.
+        let data = vec![
+            ("Sheet1", 1, 1, CellValue::Number(1.0)),
+            ("Sheet1", 1, 2, CellValue::Number(-1.0 / 2.0)),
+            ("Sheet1", 1, 3, CellValue::Number(1.0 / 24.0)),
+            ("Sheet1", 1, 4, CellValue::Number(-1.0 / 720.0)),
+        ];
+        match eval_with_data("SERIESSUM(PI()/4,0,2,A1:A4)", &data) {
+            CellValue::Number(n) => assert!((n - 0.707103).abs() < 1e-6),
+            other => panic!("expected number, got {other:?}"),
+        }
+        assert_eq!(
+            eval_with_data("SERIESSUM(10,400,1,A1:A4)", &data),
+            err("#NUM!")
+        );
+    }
+
+    #[test]
+    fn sumproduct() {
+        let data = matrix_data();
+        assert_eq!(
+            eval_with_data("SUMPRODUCT(A1:A2,B1:B2)", &data),
+            CellValue::Number(14.0)
+        );
+        assert_eq!(
+            eval_with_data("SUMPRODUCT(A1:B2)", &data),
+            CellValue::Number(10.0)
+        );
+        assert_eq!(
+            eval_with_data("SUMPRODUCT((A1:A2>1)*B1:B2)", &data),
+            CellValue::Number(4.0)
+        );
+        assert_eq!(
+            eval_with_data("SUMPRODUCT(A1:A2,A1:B1)", &data),
+            err("#VALUE!")
+        );
+    }
+
+    #[test]
+    fn sumproduct_treats_text_as_zero() {
+        let mut data = matrix_data();
+        data.push(("Sheet1", 1, 1, CellValue::String("x".to_string())));
+        assert_eq!(
+            eval_with_data("SUMPRODUCT(A1:A2,B1:B2)", &data),
+            CellValue::Number(12.0)
+        );
+    }
+
+    #[test]
+    fn sums_of_squares() {
+        let data = matrix_data();
+        assert_eq!(eval("SUMSQ(3,4)"), CellValue::Number(25.0));
+        assert_eq!(
+            eval_with_data("SUMSQ(A1:B2)", &data),
+            CellValue::Number(30.0)
+        );
+        assert_eq!(
+            eval_with_data("SUMX2MY2(A1:A2,B1:B2)", &data),
+            CellValue::Number(-10.0)
+        );
+        assert_eq!(
+            eval_with_data("SUMX2PY2(A1:A2,B1:B2)", &data),
+            CellValue::Number(30.0)
+        );
+        assert_eq!(
+            eval_with_data("SUMXMY2(A1:A2,B1:B2)", &data),
+            CellValue::Number(2.0)
+        );
+        assert_eq!(eval_with_data("SUMXMY2(A1:A2,A1:C1)", &data), err("#N/A"));
+    }
+
+    // Matrix tests
+
+    #[test]
+    fn mdeterm() {
+        let data = matrix_data();
+        match eval_with_data("MDETERM(A1:B2)", &data) {
+            CellValue::Number(n) => assert!(approx_eq(n, -2.0)),
+            other => panic!("expected number, got {other:?}"),
+        }
+        assert_eq!(eval_with_data("MDETERM(A1:C2)", &data), err("#VALUE!"));
+        assert_eq!(eval("MDETERM(A1:B2)"), err("#VALUE!"));
+    }
+
+    #[test]
+    fn mmult_returns_array() {
+        let data = matrix_data();
+        let result = eval_array("MMULT(A1:B2,C1:C2)", &data);
+        assert_eq!((result.rows, result.cols), (2, 1));
+        assert_eq!(
+            result.values,
+            vec![CellValue::Number(17.0), CellValue::Number(39.0)]
+        );
+        let result = eval_array("MMULT(A1:B2,A1:B2)", &data);
+        assert_eq!((result.rows, result.cols), (2, 2));
+        assert_eq!(eval_with_data("MMULT(A1:B2,A1:B1)", &data), err("#VALUE!"));
+    }
+
+    #[test]
+    fn mmult_array_constants() {
+        let result = eval_array("MMULT({1,2;3,4},{5;6})", &[]);
+        assert_eq!((result.rows, result.cols), (2, 1));
+        assert_eq!(
+            result.values,
+            vec![CellValue::Number(17.0), CellValue::Number(39.0)]
+        );
+        assert_eq!(num("MDETERM({1,2;3,4})"), -2.0);
+        assert_eq!(num("SUMPRODUCT({1,2,3},{4,5,6})"), 32.0);
+    }
+
+    #[test]
+    fn minverse_returns_array() {
+        let data = matrix_data();
+        let result = eval_array("MINVERSE(A1:B2)", &data);
+        assert_eq!((result.rows, result.cols), (2, 2));
+        let expected = [-2.0, 1.0, 1.5, -0.5];
+        for (v, e) in result.values.iter().zip(expected) {
+            match v {
+                CellValue::Number(n) => assert!(approx_eq(*n, e)),
+                other => panic!("expected number, got {other:?}"),
+            }
+        }
+        // The product of a matrix and its inverse is the identity.
+        let identity = eval_array("MMULT(A1:B2,MINVERSE(A1:B2))", &data);
+        for (v, e) in identity.values.iter().zip([1.0, 0.0, 0.0, 1.0]) {
+            match v {
+                CellValue::Number(n) => assert!(approx_eq(*n, e)),
+                other => panic!("expected number, got {other:?}"),
+            }
+        }
+
+        let singular = vec![
+            ("Sheet1", 1, 1, CellValue::Number(1.0)),
+            ("Sheet1", 2, 1, CellValue::Number(2.0)),
+            ("Sheet1", 1, 2, CellValue::Number(2.0)),
+            ("Sheet1", 2, 2, CellValue::Number(4.0)),
+        ];
+        assert_eq!(eval_with_data("MINVERSE(A1:B2)", &singular), err("#NUM!"));
+    }
+
+    #[test]
+    fn munit() {
+        let result = eval_array("MUNIT(3)", &[]);
+        assert_eq!((result.rows, result.cols), (3, 3));
+        assert_eq!(result.values[0], CellValue::Number(1.0));
+        assert_eq!(result.values[1], CellValue::Number(0.0));
+        assert_eq!(result.values[4], CellValue::Number(1.0));
+        assert_eq!(eval("MUNIT(0)"), err("#VALUE!"));
+    }
+
+    // Base conversion tests
+
+    #[test]
+    fn roman_arabic() {
+        assert_eq!(
+            eval("ROMAN(1999)"),
+            CellValue::String("MCMXCIX".to_string())
+        );
+        assert_eq!(eval("ROMAN(0)"), CellValue::String(String::new()));
+        assert_eq!(eval("ROMAN(4000)"), err("#VALUE!"));
+        assert_eq!(num("ARABIC(\"MCMXCIX\")"), 1999.0);
+        assert_eq!(num("ARABIC(\"-xiv\")"), -14.0);
+        assert_eq!(eval("ARABIC(\"ABC\")"), err("#VALUE!"));
+    }
+
+    #[test]
+    fn base_decimal() {
+        assert_eq!(eval("BASE(7,2)"), CellValue::String("111".to_string()));
+        assert_eq!(
+            eval("BASE(100,16,4)"),
+            CellValue::String("0064".to_string())
+        );
+        assert_eq!(eval("BASE(-1,2)"), err("#NUM!"));
+        assert_eq!(num("DECIMAL(\"FF\",16)"), 255.0);
+        assert_eq!(num("DECIMAL(\"zap\",36)"), 45745.0);
+        assert_eq!(eval("DECIMAL(\"12\",2)"), err("#NUM!"));
+    }
+
+    // SUBTOTAL and AGGREGATE tests
+
+    /// A1:A6 = [1, 2, #DIV/0!, 4, 4, "x"].
+    fn aggregate_data() -> Vec<(&'static str, u32, u32, CellValue)> {
+        vec![
+            ("Sheet1", 1, 1, CellValue::Number(1.0)),
+            ("Sheet1", 1, 2, CellValue::Number(2.0)),
+            ("Sheet1", 1, 3, err("#DIV/0!")),
+            ("Sheet1", 1, 4, CellValue::Number(4.0)),
+            ("Sheet1", 1, 5, CellValue::Number(4.0)),
+            ("Sheet1", 1, 6, CellValue::String("x".to_string())),
+        ]
+    }
+
+    #[test]
+    fn subtotal() {
+        let data = aggregate_data();
+        let eval = |formula: &str| eval_with_data(formula, &data);
+        assert_eq!(eval("SUBTOTAL(9,A1:A2)"), CellValue::Number(3.0));
+        assert_eq!(eval("SUBTOTAL(109,A1:A2,A4)"), CellValue::Number(7.0));
+        assert_eq!(eval("SUBTOTAL(1,A1:A2)"), CellValue::Number(1.5));
+        assert_eq!(eval("SUBTOTAL(3,A4:A6)"), CellValue::Number(3.0));
+        assert_eq!(eval("SUBTOTAL(9,A1:A6)"), err("#DIV/0!"));
+        assert_eq!(eval("SUBTOTAL(12,A1:A2)"), err("#VALUE!"));
+    }
+
+    #[test]
+    fn aggregate_ignoring_errors() {
+        let data = aggregate_data();
+        let eval = |formula: &str| eval_with_data(formula, &data);
+        let cases = [
+            ("AGGREGATE(1,6,A1:A6)", 2.75),
+            ("AGGREGATE(2,6,A1:A6)", 4.0),
+            ("AGGREGATE(3,6,A1:A6)", 5.0),
+            ("AGGREGATE(4,6,A1:A6)", 4.0),
+            ("AGGREGATE(5,6,A1:A6)", 1.0),
+            ("AGGREGATE(6,6,A1:A6)", 32.0),
+            ("AGGREGATE(9,6,A1:A6)", 11.0),
+            ("AGGREGATE(11,6,A1:A6)", 1.6875),
+            ("AGGREGATE(12,6,A1:A6)", 3.0),
+            ("AGGREGATE(13,6,A1:A6)", 4.0),
+            ("AGGREGATE(14,6,A1:A6,2)", 4.0),
+            ("AGGREGATE(15,6,A1:A6,2)", 2.0),
+            ("AGGREGATE(16,6,A1:A6,0.5)", 3.0),
+            ("AGGREGATE(17,6,A1:A6,4)", 4.0),
+        ];
+        for (formula, expected) in cases {
+            assert_eq!(eval(formula), CellValue::Number(expected), "{formula}");
+        }
+        assert_eq!(eval("AGGREGATE(9,0,A1:A6)"), err("#DIV/0!"));
+        assert_eq!(eval("AGGREGATE(9,4,A1:A2)"), CellValue::Number(3.0));
+        assert_eq!(eval("AGGREGATE(14,6,A1:A6)"), err("#VALUE!"));
+        assert_eq!(eval("AGGREGATE(14,6,A1:A6,5)"), err("#NUM!"));
+        assert_eq!(eval("AGGREGATE(20,0,A1:A2)"), err("#VALUE!"));
+        assert_eq!(eval("AGGREGATE(9,8,A1:A2)"), err("#VALUE!"));
+    }
 }
@@ -1,12 +1,17 @@
 //! Built-in Excel function dispatch.
 //!
 //! Provides [`lookup_function`] to resolve a function name to its implementation,
-//! and helper utilities used by individual function implementations.
+//! [`lookup_array_function`] for functions that return arrays, and helper
+//! utilities used by individual function implementations.
 
+pub mod database;
 pub mod date_time;
+pub mod distribution;
+pub mod dynamic_array;
 pub mod engineering;
 pub mod financial;
 pub mod information;
+pub mod lambda;
 pub mod logical;
 pub mod lookup;
 pub mod math;
@@ -16,7 +21,7 @@ pub mod text;
 use crate::cell::CellValue;
 use crate::error::{Error, Result};
 use crate::formula::ast::Expr;
-use crate::formula::eval::{coerce_to_number, coerce_to_string, Evaluator};
+use crate::formula::eval::{coerce_to_number, coerce_to_string, ArrayValue, Evaluator};
 
 /// Signature for a built-in function implementation.
 ///
@@ -24,9 +29,39 @@ use crate::formula::eval::{coerce_to_number, coerce_to_string, Evaluator};
 /// allowing short-circuit evaluation (e.g., IF) and range expansion (e.g., SUM).
 pub type FunctionFn = fn(&[Expr], &mut Evaluator) -> Result<CellValue>;
 
+/// Signature for a function that returns an array of values (dynamic array
+/// functions such as FILTER and SEQUENCE).
+pub type ArrayFunctionFn = fn(&[Expr], &mut Evaluator) -> Result<ArrayValue>;
+
+/// Signature for a function that returns a reference (OFFSET, INDIRECT,
+/// and the reference form of INDEX).
+///
+/// The result is a resolved reference such as [`Expr::Range`], or an
+/// [`Expr::Error`]. `None` means the call does not yield a reference, e.g.
+/// INDEX over an array, and is evaluated as a value function instead.
+pub type ReferenceFunctionFn = fn(&[Expr], &mut Evaluator) -> Result<Option<Expr>>;
+
+/// Strip the `_xlfn.` / `_xlws.` prefixes Excel writes in front of functions
+/// introduced after the original file format, e.g. `_xlfn._xlws.FILTER`.
+pub(crate) fn strip_future_function_prefix(name: &str) -> &str {
+    let mut name = name;
+    for prefix in ["_XLFN.", "_XLWS."] {
+        if name
+            .get(..prefix.len())
+            .is_some_and(|p| p.eq_ignore_ascii_case(prefix))
+        {
+            name = &name[prefix.len()..];
+        }
+    }
+    name
+}
+
 /// Resolve a function name (case-insensitive) to its implementation.
 pub fn lookup_function(name: &str) -> Option<FunctionFn> {
-    match name.to_ascii_uppercase().as_str() {
+    match strip_future_function_prefix(name)
+        .to_ascii_uppercase()
+        .as_str()
+    {
         "SUM" => Some(fn_sum),
         "AVERAGE" => Some(fn_average),
         "COUNT" => Some(fn_count),
@@ -74,6 +109,59 @@ pub fn lookup_function(name: &str) -> Option<FunctionFn> {
         "PRODUCT" => Some(math::fn_product),
         "QUOTIENT" => Some(math::fn_quotient),
         "FACT" => Some(math::fn_fact),
+        "SIN" => Some(math::fn_sin),
+        "COS" => Some(math::fn_cos),
+        "TAN" => Some(math::fn_tan),
+        "ASIN" => Some(math::fn_asin),
+        "ACOS" => Some(math::fn_acos),
+        "ATAN" => Some(math::fn_atan),
+        "ATAN2" => Some(math::fn_atan2),
+        "ACOT" => Some(math::fn_acot),
+        "COT" => Some(math::fn_cot),
+        "CSC" => Some(math::fn_csc),
+        "SEC" => Some(math::fn_sec),
+        "SINH" => Some(math::fn_sinh),
+        "COSH" => Some(math::fn_cosh),
+        "TANH" => Some(math::fn_tanh),
+        "ASINH" => Some(math::fn_asinh),
+        "ACOSH" => Some(math::fn_acosh),
+        "ATANH" => Some(math::fn_atanh),
+        "ACOTH" => Some(math::fn_acoth),
+        "COTH" => Some(math::fn_coth),
+        "CSCH" => Some(math::fn_csch),
+        "SECH" => Some(math::fn_sech),
+        "DEGREES" => Some(math::fn_degrees),
+        "RADIANS" => Some(math::fn_radians),
+        "SQRTPI" => Some(math::fn_sqrtpi),
+        "TRUNC" => Some(math::fn_trunc),
+        "EVEN" => Some(math::fn_even),
+        "ODD" => Some(math::fn_odd),
+        "MROUND" => Some(math::fn_mround),
+        "CEILING.MATH" => Some(math::fn_ceiling_math),
+        "FLOOR.MATH" => Some(math::fn_floor_math),
+        "CEILING.PRECISE" => Some(math::fn_ceiling_precise),
+        "ISO.CEILING" => Some(math::fn_ceiling_precise),
+        "FLOOR.PRECISE" => Some(math::fn_floor_precise),
+        "GCD" => Some(math::fn_gcd),
+        "LCM" => Some(math::fn_lcm),
+        "COMBIN" => Some(math::fn_combin),
+        "COMBINA" => Some(math::fn_combina),
+        "PERMUT" => Some(math::fn_permut),
+        "MULTINOMIAL" => Some(math::fn_multinomial),
+        "FACTDOUBLE" => Some(math::fn_factdouble),
+        "SERIESSUM" => Some(math::fn_seriessum),
+        "SUMSQ" => Some(math::fn_sumsq),
+        "SUMPRODUCT" => Some(math::fn_sumproduct),
+        "SUMX2MY2" => Some(math::fn_sumx2my2),
+        "SUMX2PY2" => Some(math::fn_sumx2py2),
+        "SUMXMY2" => Some(math::fn_sumxmy2),
+        "MDETERM" => Some(math::fn_mdeterm),
+        "ROMAN" => Some(math::fn_roman),
+        "ARABIC" => Some(math::fn_arabic),
+        "BASE" => Some(math::fn_base),
+        "DECIMAL" => Some(math::fn_decimal),
+        "SUBTOTAL" => Some(math::fn_subtotal),
+        "AGGREGATE" => Some(math::fn_aggregate),
         "AVERAGEIF" => Some(statistical::fn_averageif),
         "AVERAGEIFS" => Some(statistical::fn_averageifs),
         "COUNTBLANK" => Some(statistical::fn_countblank),
@@ -84,6 +172,113 @@ pub fn lookup_function(name: &str) -> Option<FunctionFn> {
         "LARGE" => Some(statistical::fn_large),
         "SMALL" => Some(statistical::fn_small),
         "RANK" => Some(statistical::fn_rank),
+        "MODE.SNGL" => Some(statistical::fn_mode_sngl),
+        "RANK.EQ" => Some(statistical::fn_rank),
+        "RANK.AVG" => Some(statistical::fn_rank_avg),
+        "STDEV" => Some(statistical::fn_stdev),
+        "STDEV.S" => Some(statistical::fn_stdev),
+        "STDEVA" => Some(statistical::fn_stdeva),
+        "STDEVP" => Some(statistical::fn_stdevp),
+        "STDEV.P" => Some(statistical::fn_stdevp),
+        "STDEVPA" => Some(statistical::fn_stdevpa),
+        "VAR" => Some(statistical::fn_var),
+        "VAR.S" => Some(statistical::fn_var),
+        "VARA" => Some(statistical::fn_vara),
+        "VARP" => Some(statistical::fn_varp),
+        "VAR.P" => Some(statistical::fn_varp),
+        "VARPA" => Some(statistical::fn_varpa),
+        "AVEDEV" => Some(statistical::fn_avedev),
+        "DEVSQ" => Some(statistical::fn_devsq),
+        "GEOMEAN" => Some(statistical::fn_geomean),
+        "HARMEAN" => Some(statistical::fn_harmean),
+        "SKEW" => Some(statistical::fn_skew),
+        "KURT" => Some(statistical::fn_kurt),
+        "STANDARDIZE" => Some(statistical::fn_standardize),
+        "PERCENTILE" => Some(statistical::fn_percentile_inc),
+        "PERCENTILE.INC" => Some(statistical::fn_percentile_inc),
+        "PERCENTILE.EXC" => Some(statistical::fn_percentile_exc),
+        "QUARTILE" => Some(statistical::fn_quartile_inc),
+        "QUARTILE.INC" => Some(statistical::fn_quartile_inc),
+        "QUARTILE.EXC" => Some(statistical::fn_quartile_exc),
+        "PERCENTRANK" => Some(statistical::fn_percentrank_inc),
+        "PERCENTRANK.INC" => Some(statistical::fn_percentrank_inc),
+        "PERCENTRANK.EXC" => Some(statistical::fn_percentrank_exc),
+        "CORREL" => Some(statistical::fn_correl),
+        "PEARSON" => Some(statistical::fn_correl),
+        "RSQ" => Some(statistical::fn_rsq),
+        "COVAR" => Some(statistical::fn_covariance_p),
+        "COVARIANCE.P" => Some(statistical::fn_covariance_p),
+        "COVARIANCE.S" => Some(statistical::fn_covariance_s),
+        "SLOPE" => Some(statistical::fn_slope),
+        "INTERCEPT" => Some(statistical::fn_intercept),
+        "STEYX" => Some(statistical::fn_steyx),
+        "FORECAST" => Some(statistical::fn_forecast),
+        "FORECAST.LINEAR" => Some(statistical::fn_forecast),
+        "MAXIFS" => Some(statistical::fn_maxifs),
+        "MINIFS" => Some(statistical::fn_minifs),
+        "NORM.DIST" => Some(distribution::fn_norm_dist),
+        "NORMDIST" => Some(distribution::fn_norm_dist),
+        "NORM.INV" => Some(distribution::fn_norm_inv),
+        "NORMINV" => Some(distribution::fn_norm_inv),
+        "NORM.S.DIST" => Some(distribution::fn_norm_s_dist),
+        "NORMSDIST" => Some(distribution::fn_normsdist),
+        "NORM.S.INV" => Some(distribution::fn_norm_s_inv),
+        "NORMSINV" => Some(distribution::fn_norm_s_inv),
+        "LOGNORM.DIST" => Some(distribution::fn_lognorm_dist),
+        "LOGNORMDIST" => Some(distribution::fn_lognormdist),
+        "LOGNORM.INV" => Some(distribution::fn_lognorm_inv),
+        "LOGINV" => Some(distribution::fn_lognorm_inv),
+        "PHI" => Some(distribution::fn_phi),
+        "GAUSS" => Some(distribution::fn_gauss),
+        "CONFIDENCE.NORM" => Some(distribution::fn_confidence_norm),
+        "CONFIDENCE" => Some(distribution::fn_confidence_norm),
+        "CONFIDENCE.T" => Some(distribution::fn_confidence_t),
+        "FISHER" => Some(distribution::fn_fisher),
+        "FISHERINV" => Some(distribution::fn_fisherinv),
+        "T.DIST" => Some(distribution::fn_t_dist),
+        "T.DIST.2T" => Some(distribution::fn_t_dist_2t),
+        "T.DIST.RT" => Some(distribution::fn_t_dist_rt),
+        "TDIST" => Some(distribution::fn_tdist),
+        "T.INV" => Some(distribution::fn_t_inv),
+        "T.INV.2T" => Some(distribution::fn_t_inv_2t),
+        "TINV" => Some(distribution::fn_t_inv_2t),
+        "CHISQ.DIST" => Some(distribution::fn_chisq_dist),
+        "CHISQ.DIST.RT" => Some(distribution::fn_chisq_dist_rt),
+        "CHIDIST" => Some(distribution::fn_chisq_dist_rt),
+        "CHISQ.INV" => Some(distribution::fn_chisq_inv),
+        "CHISQ.INV.RT" => Some(distribution::fn_chisq_inv_rt),
+        "CHIINV" => Some(distribution::fn_chisq_inv_rt),
+        "F.DIST" => Some(distribution::fn_f_dist),
+        "F.DIST.RT" => Some(distribution::fn_f_dist_rt),
+        "FDIST" => Some(distribution::fn_f_dist_rt),
+        "F.INV" => Some(distribution::fn_f_inv),
+        "F.INV.RT" => Some(distribution::fn_f_inv_rt),
+        "FINV" => Some(distribution::fn_f_inv_rt),
+        "BINOM.DIST" => Some(distribution::fn_binom_dist),
+        "BINOMDIST" => Some(distribution::fn_binom_dist),
+        "BINOM.INV" => Some(distribution::fn_binom_inv),
+        "CRITBINOM" => Some(distribution::fn_binom_inv),
+        "NEGBINOM.DIST" => Some(distribution::fn_negbinom_dist),
+        "NEGBINOMDIST" => Some(distribution::fn_negbinomdist),
+        "HYPGEOM.DIST" => Some(distribution::fn_hypgeom_dist),
+        "HYPGEOMDIST" => Some(distribution::fn_hypgeom_dist),
+        "POISSON.DIST" => Some(distribution::fn_poisson_dist),
+        "POISSON" => Some(distribution::fn_poisson_dist),
+        "EXPON.DIST" => Some(distribution::fn_expon_dist),
+        "EXPONDIST" => Some(distribution::fn_expon_dist),
+        "GAMMA" => Some(distribution::fn_gamma),
+        "GAMMALN" => Some(distribution::fn_gammaln),
+        "GAMMALN.PRECISE" => Some(distribution::fn_gammaln),
+        "GAMMA.DIST" => Some(distribution::fn_gamma_dist),
+        "GAMMADIST" => Some(distribution::fn_gamma_dist),
+        "GAMMA.INV" => Some(distribution::fn_gamma_inv),
+        "GAMMAINV" => Some(distribution::fn_gamma_inv),
+        "BETA.DIST" => Some(distribution::fn_beta_dist),
+        "BETADIST" => Some(distribution::fn_betadist),
+        "BETA.INV" => Some(distribution::fn_beta_inv),
+        "BETAINV" => Some(distribution::fn_beta_inv),
+        "WEIBULL.DIST" => Some(distribution::fn_weibull_dist),
+        "WEIBULL" => Some(distribution::fn_weibull_dist),
         "ISERR" => Some(information::fn_iserr),
         "ISNA" => Some(information::fn_isna),
         "ISLOGICAL" => Some(information::fn_islogical),
@@ -102,6 +297,26 @@ pub fn lookup_function(name: &str) -> Option<FunctionFn> {
         "EXACT" => Some(text::fn_exact),
         "T" => Some(text::fn_t),
         "PROPER" => Some(text::fn_proper),
+        "TEXTJOIN" => Some(text::fn_textjoin),
+        "TEXTBEFORE" => Some(text::fn_textbefore),
+        "TEXTAFTER" => Some(text::fn_textafter),
+        "CHAR" => Some(text::fn_char),
+        "CODE" => Some(text::fn_code),
+        "UNICHAR" => Some(text::fn_unichar),
+        "UNICODE" => Some(text::fn_unicode),
+        "CLEAN" => Some(text::fn_clean),
+        "FIXED" => Some(text::fn_fixed),
+        "DOLLAR" => Some(text::fn_dollar),
+        "NUMBERVALUE" => Some(text::fn_numbervalue),
+        "VALUETOTEXT" => Some(text::fn_valuetotext),
+        "ARRAYTOTEXT" => Some(text::fn_arraytotext),
+        "LENB" => Some(text::fn_lenb),
+        "LEFTB" => Some(text::fn_leftb),
+        "RIGHTB" => Some(text::fn_rightb),
+        "MIDB" => Some(text::fn_midb),
+        "FINDB" => Some(text::fn_findb),
+        "SEARCHB" => Some(text::fn_searchb),
+        "REPLACEB" => Some(text::fn_replaceb),
         "TRUE" => Some(logical::fn_true),
         "FALSE" => Some(logical::fn_false),
         "IFERROR" => Some(logical::fn_iferror),
@@ -137,6 +352,10 @@ pub fn lookup_function(name: &str) -> Option<FunctionFn> {
         "COLUMNS" => Some(lookup::fn_columns),
         "CHOOSE" => Some(lookup::fn_choose),
         "ADDRESS" => Some(lookup::fn_address),
+        "XMATCH" => Some(lookup::fn_xmatch),
+        "HYPERLINK" => Some(lookup::fn_hyperlink),
+        "AREAS" => Some(lookup::fn_areas),
+        "GETPIVOTDATA" => Some(lookup::fn_getpivotdata),
         "FV" => Some(financial::fn_fv),
         "PV" => Some(financial::fn_pv),
         "NPV" => Some(financial::fn_npv),
@@ -191,10 +410,92 @@ pub fn lookup_function(name: &str) -> Option<FunctionFn> {
         "BESSELJ" => Some(engineering::fn_besselj),
         "BESSELK" => Some(engineering::fn_besselk),
         "BESSELY" => Some(engineering::fn_bessely),
+        "LAMBDA" => Some(lambda::fn_lambda),
+        "DAVERAGE" => Some(database::fn_daverage),
+        "DCOUNT" => Some(database::fn_dcount),
+        "DCOUNTA" => Some(database::fn_dcounta),
+        "DGET" => Some(database::fn_dget),
+        "DMAX" => Some(database::fn_dmax),
+        "DMIN" => Some(database::fn_dmin),
+        "DPRODUCT" => Some(database::fn_dproduct),
+        "DSTDEV" => Some(database::fn_dstdev),
+        "DSTDEVP" => Some(database::fn_dstdevp),
+        "DSUM" => Some(database::fn_dsum),
+        "DVAR" => Some(database::fn_dvar),
+        "DVARP" => Some(database::fn_dvarp),
         _ => None,
     }
 }
 
+/// Resolve an array-returning function name (case-insensitive) to its
+/// implementation.
+pub fn lookup_array_function(name: &str) -> Option<ArrayFunctionFn> {
+    match strip_future_function_prefix(name)
+        .to_ascii_uppercase()
+        .as_str()
+    {
+        "FILTER" => Some(dynamic_array::fn_filter),
+        "SORT" => Some(dynamic_array::fn_sort),
+        "SORTBY" => Some(dynamic_array::fn_sortby),
+        "UNIQUE" => Some(dynamic_array::fn_unique),
+        "SEQUENCE" => Some(dynamic_array::fn_sequence),
+        "RANDARRAY" => Some(dynamic_array::fn_randarray),
+        "MMULT" => Some(math::fn_mmult),
+        "MINVERSE" => Some(math::fn_minverse),
+        "MUNIT" => Some(math::fn_munit),
+        "LINEST" => Some(statistical::fn_linest),
+        "TREND" => Some(statistical::fn_trend),
+        "GROWTH" => Some(statistical::fn_growth),
+        "XLOOKUP" => Some(lookup::fn_xlookup),
+        "TRANSPOSE" => Some(lookup::fn_transpose),
+        "TEXTSPLIT" => Some(text::fn_textsplit),
+        "LET" => Some(lambda::fn_let),
+        "MAP" => Some(lambda::fn_map),
+        "REDUCE" => Some(lambda::fn_reduce),
+        "SCAN" => Some(lambda::fn_scan),
+        "BYROW" => Some(lambda::fn_byrow),
+        "BYCOL" => Some(lambda::fn_bycol),
+        "MAKEARRAY" => Some(lambda::fn_makearray),
+        _ => None,
+    }
+}
+
+/// Resolve a reference-returning function name (case-insensitive) to its
+/// implementation.
+pub fn lookup_reference_function(name: &str) -> Option<ReferenceFunctionFn> {
+    match strip_future_function_prefix(name)
+        .to_ascii_uppercase()
+        .as_str()
+    {
+        "OFFSET" => Some(lookup::fn_offset),
+        "INDIRECT" => Some(lookup::fn_indirect),
+        "INDEX" => Some(lookup::fn_index_ref),
+        "ANCHORARRAY" => Some(lookup::fn_anchorarray),
+        _ => None,
+    }
+}
+
+/// Return `true` if `name` is a built-in function of any kind. Defined
+/// names holding a LAMBDA cannot shadow these.
+pub fn is_builtin_function(name: &str) -> bool {
+    lookup_function(name).is_some()
+        || lookup_array_function(name).is_some()
+        || lookup_reference_function(name).is_some()
+}
+
+/// Return `true` for volatile functions, whose result can change without
+/// any change to their arguments' precedents: INDIRECT and OFFSET, whose
+/// precedents are only known when evaluated, and NOW, TODAY, and the random
+/// number functions.
+pub fn is_volatile_function(name: &str) -> bool {
+    matches!(
+        strip_future_function_prefix(name)
+            .to_ascii_uppercase()
+            .as_str(),
+        "INDIRECT" | "OFFSET" | "NOW" | "TODAY" | "RAND" | "RANDBETWEEN" | "RANDARRAY"
+    )
+}
+
 /// Verify that `args` has between `min` and `max` entries (inclusive).
 pub fn check_arg_count(name: &str, args: &[Expr], min: usize, max: usize) -> Result<()> {
     if args.len() < min || args.len() > max {
@@ -280,7 +581,7 @@ pub fn matches_criteria(cell_value: &CellValue, criteria: &str) -> bool {
     }
 }
 
-fn wildcard_match(text: &str, pattern: &str) -> bool {
+pub(crate) fn wildcard_match(text: &str, pattern: &str) -> bool {
     let t: Vec<char> = text.chars().collect();
     let p: Vec<char> = pattern.chars().collect();
     let (tlen, plen) = (t.len(), p.len());
@@ -305,10 +606,12 @@ fn wildcard_match(text: &str, pattern: &str) -> bool {
 
 /// Expand a single argument expression into a flat list of CellValues.
 pub fn collect_criteria_range_values(arg: &Expr, ctx: &mut Evaluator) -> Result<Vec<CellValue>> {
-    match arg {
+    let arg = ctx.resolve_ref(arg)?;
+    match arg.as_ref() {
         Expr::Range { start, end } => ctx.expand_range(start, end),
+        _ if ctx.is_array_arg(&arg) => Ok(ctx.eval_array(&arg)?.values),
         _ => {
-            let v = ctx.eval_expr(arg)?;
+            let v = ctx.eval_expr(&arg)?;
             Ok(vec![v])
         }
     }
@@ -468,7 +771,10 @@ fn fn_power(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("POWER", args, 2, 2)?;
     let base = crate::formula::eval::coerce_to_number(&ctx.eval_expr(&args[0])?)?;
     let exp = crate::formula::eval::coerce_to_number(&ctx.eval_expr(&args[1])?)?;
-    Ok(CellValue::Number(base.powf(exp)))
+    if base == 0.0 && exp < 0.0 {
+        return Ok(CellValue::Error("#DIV/0!".to_string()));
+    }
+    Ok(math::finite_number(base.powf(exp)))
 }
 
 fn fn_sqrt(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
@@ -486,7 +792,7 @@ fn fn_len(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("LEN", args, 1, 1)?;
     let v = ctx.eval_expr(&args[0])?;
     let s = crate::formula::eval::coerce_to_string(&v);
-    Ok(CellValue::Number(s.len() as f64))
+    Ok(CellValue::Number(s.chars().count() as f64))
 }
 
 fn fn_lower(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
@@ -606,9 +912,27 @@ fn fn_value(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
 fn fn_text(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     check_arg_count("TEXT", args, 2, 2)?;
     let v = ctx.eval_expr(&args[0])?;
-    let _fmt = ctx.eval_expr(&args[1])?;
-    // Simplified: just convert to string representation (full format codes not implemented).
-    Ok(CellValue::String(crate::formula::eval::coerce_to_string(
-        &v,
-    )))
+    let fmt = crate::formula::eval::coerce_to_string(&ctx.eval_expr(&args[1])?);
+    let number = match &v {
+        CellValue::Error(_) => return Ok(v),
+        CellValue::Number(n) | CellValue::Date(n) => Some(*n),
+        CellValue::Empty => Some(0.0),
+        CellValue::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    };
+    match number {
+        Some(n) if !n.is_finite() => Ok(CellValue::Error("#NUM!".to_string())),
+        Some(n) => Ok(CellValue::String(
+            crate::numfmt::format_number_with_date_system(n, &fmt, ctx.date_system()),
+        )),
+        None => {
+            // Text uses the fourth section of the format, with `@` standing
+            // for the text itself, or is returned unchanged.
+            let text = crate::formula::eval::coerce_to_string(&v);
+            Ok(CellValue::String(match fmt.split(';').nth(3) {
+                Some(section) => section.replace('@', &text).replace('"', ""),
+                None => text,
+            }))
+        }
+    }
 }
@@ -1,10 +1,16 @@
 //! Statistical formula functions: AVERAGEIF, AVERAGEIFS, COUNTBLANK, COUNTIF,
-//! COUNTIFS, MEDIAN, MODE, LARGE, SMALL, RANK.
+//! COUNTIFS, MEDIAN, MODE, LARGE, SMALL, RANK, the STDEV and VAR families,
+//! percentiles and quartiles, correlation and linear regression (including
+//! the array functions LINEST, TREND and GROWTH), MAXIFS and MINIFS.
+//! Probability distributions live in the `distribution` module.
 
 use crate::cell::CellValue;
 use crate::error::Result;
 use crate::formula::ast::Expr;
-use crate::formula::eval::{coerce_to_number, coerce_to_string, Evaluator};
+use crate::formula::eval::{
+    coerce_to_bool, coerce_to_number, coerce_to_string, is_reference, ArrayValue, Evaluator,
+};
+use crate::formula::functions::math::{gauss_jordan, numeric_matrix};
 use crate::formula::functions::{check_arg_count, collect_criteria_range_values, matches_criteria};
 
 /// AVERAGEIF(range, criteria, [average_range])
@@ -240,10 +246,903 @@ pub fn fn_rank(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     Ok(CellValue::Number(rank as f64))
 }
 
+/// MODE.SNGL(args...) - most frequently occurring value
+pub fn fn_mode_sngl(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    fn_mode(args, ctx)
+}
+
+/// RANK.AVG(number, ref, [order]) - rank of a number in a list, averaging
+/// the ranks of ties.
+pub fn fn_rank_avg(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("RANK.AVG", args, 2, 3)?;
+    let number = coerce_to_number(&ctx.eval_expr(&args[0])?)?;
+    let ref_vals = collect_criteria_range_values(&args[1], ctx)?;
+    let order = if args.len() > 2 {
+        coerce_to_number(&ctx.eval_expr(&args[2])?)? as i64
+    } else {
+        0
+    };
+    let nums: Vec<f64> = ref_vals
+        .iter()
+        .filter_map(|v| coerce_to_number(v).ok())
+        .collect();
+    let ties = nums.iter().filter(|&&n| n == number).count();
+    if ties == 0 {
+        return Ok(CellValue::Error("#N/A".to_string()));
+    }
+    let before = if order == 0 {
+        nums.iter().filter(|&&n| n > number).count()
+    } else {
+        nums.iter().filter(|&&n| n < number).count()
+    };
+    Ok(CellValue::Number(before as f64 + (ties as f64 + 1.0) / 2.0))
+}
+
+// -- Dispersion --
+
+/// Collect the numbers of a statistical function's arguments. Cells and
+/// array elements count only when they hold numbers, unless `include_text`
+/// is set (the `A` variants such as STDEVA), in which case text and FALSE
+/// count as 0 and TRUE as 1. Scalar arguments are converted to numbers.
+/// Error values are returned as the `Err` variant.
+fn sample_numbers(
+    args: &[Expr],
+    ctx: &mut Evaluator,
+    include_text: bool,
+) -> Result<std::result::Result<Vec<f64>, String>> {
+    let mut nums = Vec::new();
+    for arg in args {
+        let arg = ctx.resolve_ref(arg)?;
+        let values = if is_reference(&arg) {
+            ctx.reference_values(&arg)?
+        } else if ctx.is_array_arg(&arg) {
+            ctx.eval_array(&arg)?.values
+        } else {
+            match ctx.eval_expr(&arg)? {
+                CellValue::Error(e) => return Ok(Err(e)),
+                v => nums.push(coerce_to_number(&v)?),
+            }
+            continue;
+        };
+        for v in values {
+            match v {
+                CellValue::Number(n) | CellValue::Date(n) => nums.push(n),
+                CellValue::Error(e) => return Ok(Err(e)),
+                CellValue::Bool(b) if include_text => nums.push(if b { 1.0 } else { 0.0 }),
+                CellValue::String(_) | CellValue::RichString(_) if include_text => nums.push(0.0),
+                _ => {}
+            }
+        }
+    }
+    Ok(Ok(nums))
+}
+
+fn mean(nums: &[f64]) -> f64 {
+    nums.iter().sum::<f64>() / nums.len() as f64
+}
+
+/// Sum of squared deviations from the mean.
+pub(super) fn sum_sq_dev(nums: &[f64]) -> f64 {
+    let m = mean(nums);
+    nums.iter().map(|x| (x - m) * (x - m)).sum()
+}
+
+/// Shared implementation of the VAR and STDEV families: the sample
+/// (`sample = true`) or population variance, or its square root.
+fn variance(
+    name: &str,
+    args: &[Expr],
+    ctx: &mut Evaluator,
+    include_text: bool,
+    sample: bool,
+    sqrt: bool,
+) -> Result<CellValue> {
+    check_arg_count(name, args, 1, 255)?;
+    let nums = match sample_numbers(args, ctx, include_text)? {
+        Ok(nums) => nums,
+        Err(e) => return Ok(CellValue::Error(e)),
+    };
+    let n = nums.len() as f64;
+    let divisor = if sample { n - 1.0 } else { n };
+    if divisor < 1.0 {
+        return Ok(CellValue::Error("#DIV/0!".to_string()));
+    }
+    let var = sum_sq_dev(&nums) / divisor;
+    Ok(CellValue::Number(if sqrt { var.sqrt() } else { var }))
+}
+
+/// STDEV(args...) and STDEV.S - sample standard deviation
+pub fn fn_stdev(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    variance("STDEV", args, ctx, false, true, true)
+}
+
+/// STDEVA(args...) - sample standard deviation counting text and booleans
+pub fn fn_stdeva(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    variance("STDEVA", args, ctx, true, true, true)
+}
+
+/// STDEVP(args...) and STDEV.P - population standard deviation
+pub fn fn_stdevp(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    variance("STDEVP", args, ctx, false, false, true)
+}
+
+/// STDEVPA(args...) - population standard deviation counting text and
+/// booleans
+pub fn fn_stdevpa(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    variance("STDEVPA", args, ctx, true, false, true)
+}
+
+/// VAR(args...) and VAR.S - sample variance
+pub fn fn_var(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    variance("VAR", args, ctx, false, true, false)
+}
+
+/// VARA(args...) - sample variance counting text and booleans
+pub fn fn_vara(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    variance("VARA", args, ctx, true, true, false)
+}
+
+/// VARP(args...) and VAR.P - population variance
+pub fn fn_varp(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    variance("VARP", args, ctx, false, false, false)
+}
+
+/// VARPA(args...) - population variance counting text and booleans
+pub fn fn_varpa(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    variance("VARPA", args, ctx, true, false, false)
+}
+
+/// Evaluate the numbers of `args`, returning `#NUM!` when there are fewer
+/// than `min`.
+fn numbers_at_least(
+    args: &[Expr],
+    ctx: &mut Evaluator,
+    min: usize,
+) -> Result<std::result::Result<Vec<f64>, CellValue>> {
+    Ok(match sample_numbers(args, ctx, false)? {
+        Ok(nums) if nums.len() >= min => Ok(nums),
+        Ok(_) => Err(CellValue::Error("#NUM!".to_string())),
+        Err(e) => Err(CellValue::Error(e)),
+    })
+}
+
+/// AVEDEV(args...) - average absolute deviation from the mean
+pub fn fn_avedev(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("AVEDEV", args, 1, 255)?;
+    let nums = match numbers_at_least(args, ctx, 1)? {
+        Ok(nums) => nums,
+        Err(e) => return Ok(e),
+    };
+    let m = mean(&nums);
+    Ok(CellValue::Number(
+        nums.iter().map(|x| (x - m).abs()).sum::<f64>() / nums.len() as f64,
+    ))
+}
+
+/// DEVSQ(args...) - sum of squared deviations from the mean
+pub fn fn_devsq(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("DEVSQ", args, 1, 255)?;
+    Ok(match numbers_at_least(args, ctx, 1)? {
+        Ok(nums) => CellValue::Number(sum_sq_dev(&nums)),
+        Err(e) => e,
+    })
+}
+
+/// GEOMEAN(args...) - geometric mean of positive numbers
+pub fn fn_geomean(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("GEOMEAN", args, 1, 255)?;
+    let nums = match numbers_at_least(args, ctx, 1)? {
+        Ok(nums) => nums,
+        Err(e) => return Ok(e),
+    };
+    if nums.iter().any(|&x| x <= 0.0) {
+        return Ok(CellValue::Error("#NUM!".to_string()));
+    }
+    let log_mean = nums.iter().map(|x| x.ln()).sum::<f64>() / nums.len() as f64;
+    Ok(CellValue::Number(log_mean.exp()))
+}
+
+/// HARMEAN(args...) - harmonic mean of positive numbers
+pub fn fn_harmean(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("HARMEAN", args, 1, 255)?;
+    let nums = match numbers_at_least(args, ctx, 1)? {
+        Ok(nums) => nums,
+        Err(e) => return Ok(e),
+    };
+    if nums.iter().any(|&x| x <= 0.0) {
+        return Ok(CellValue::Error("#NUM!".to_string()));
+    }
+    let reciprocal_sum: f64 = nums.iter().map(|x| 1.0 / x).sum();
+    Ok(CellValue::Number(nums.len() as f64 / reciprocal_sum))
+}
+
+/// Standardized moments sum((x - mean) / s)^power, with `s` the sample
+/// standard deviation. Returns `None` when there are fewer than `min`
+/// numbers or they are all equal.
+fn standardized_moment_sum(nums: &[f64], min: usize, power: i32) -> Option<f64> {
+    if nums.len() < min {
+        return None;
+    }
+    let m = mean(nums);
+    let s = (sum_sq_dev(nums) / (nums.len() as f64 - 1.0)).sqrt();
+    if s == 0.0 {
+        return None;
+    }
+    Some(nums.iter().map(|x| ((x - m) / s).powi(power)).sum())
+}
+
+/// SKEW(args...) - sample skewness
+pub fn fn_skew(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("SKEW", args, 1, 255)?;
+    let nums = match sample_numbers(args, ctx, false)? {
+        Ok(nums) => nums,
+        Err(e) => return Ok(CellValue::Error(e)),
+    };
+    let n = nums.len() as f64;
+    Ok(match standardized_moment_sum(&nums, 3, 3) {
+        Some(sum) => CellValue::Number(n / ((n - 1.0) * (n - 2.0)) * sum),
+        None => CellValue::Error("#DIV/0!".to_string()),
+    })
+}
+
+/// KURT(args...) - sample excess kurtosis
+pub fn fn_kurt(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("KURT", args, 1, 255)?;
+    let nums = match sample_numbers(args, ctx, false)? {
+        Ok(nums) => nums,
+        Err(e) => return Ok(CellValue::Error(e)),
+    };
+    let n = nums.len() as f64;
+    Ok(match standardized_moment_sum(&nums, 4, 4) {
+        Some(sum) => CellValue::Number(
+            n * (n + 1.0) / ((n - 1.0) * (n - 2.0) * (n - 3.0)) * sum
+                - 3.0 * (n - 1.0) * (n - 1.0) / ((n - 2.0) * (n - 3.0)),
+        ),
+        None => CellValue::Error("#DIV/0!".to_string()),
+    })
+}
+
+/// STANDARDIZE(x, mean, standard_dev) - normalized value
+pub fn fn_standardize(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("STANDARDIZE", args, 3, 3)?;
+    let x = coerce_to_number(&ctx.eval_expr(&args[0])?)?;
+    let m = coerce_to_number(&ctx.eval_expr(&args[1])?)?;
+    let sd = coerce_to_number(&ctx.eval_expr(&args[2])?)?;
+    if sd <= 0.0 {
+        return Ok(CellValue::Error("#NUM!".to_string()));
+    }
+    Ok(CellValue::Number((x - m) / sd))
+}
+
+// -- Percentiles --
+
+/// Evaluate an array argument and the number argument that follows it.
+/// Returns the array's numbers in ascending order, or `#NUM!` when there
+/// are none.
+fn sorted_numbers_and_arg(
+    args: &[Expr],
+    ctx: &mut Evaluator,
+) -> Result<std::result::Result<(Vec<f64>, f64), CellValue>> {
+    let mut nums = match numbers_at_least(&args[..1], ctx, 1)? {
+        Ok(nums) => nums,
+        Err(e) => return Ok(Err(e)),
+    };
+    nums.sort_by(f64::total_cmp);
+    let k = coerce_to_number(&ctx.eval_expr(&args[1])?)?;
+    Ok(Ok((nums, k)))
+}
+
+/// Linearly interpolate a sorted slice at the zero-based fractional
+/// position `rank`.
+fn interpolate(sorted: &[f64], rank: f64) -> f64 {
+    let lower = rank.floor() as usize;
+    let fraction = rank - rank.floor();
+    match sorted.get(lower + 1) {
+        Some(upper) if fraction > 0.0 => sorted[lower] + fraction * (upper - sorted[lower]),
+        _ => sorted[lower],
+    }
+}
+
+/// Inclusive percentile (k in 0..=1) of sorted numbers.
+pub(super) fn percentile_inc(sorted: &[f64], k: f64) -> Option<f64> {
+    (0.0..=1.0)
+        .contains(&k)
+        .then(|| interpolate(sorted, k * (sorted.len() - 1) as f64))
+}
+
+/// Exclusive percentile (k strictly between 1/(n+1) and n/(n+1)) of sorted
+/// numbers.
+pub(super) fn percentile_exc(sorted: &[f64], k: f64) -> Option<f64> {
+    let rank = k * (sorted.len() + 1) as f64;
+    (k > 0.0 && k < 1.0 && rank >= 1.0 && rank <= sorted.len() as f64)
+        .then(|| interpolate(sorted, rank - 1.0))
+}
+
+fn num_or_error(value: Option<f64>) -> CellValue {
+    value.map_or_else(|| CellValue::Error("#NUM!".to_string()), CellValue::Number)
+}
+
+/// PERCENTILE(array, k) and PERCENTILE.INC
+pub fn fn_percentile_inc(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("PERCENTILE.INC", args, 2, 2)?;
+    Ok(match sorted_numbers_and_arg(args, ctx)? {
+        Ok((nums, k)) => num_or_error(percentile_inc(&nums, k)),
+        Err(e) => e,
+    })
+}
+
+/// PERCENTILE.EXC(array, k)
+pub fn fn_percentile_exc(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("PERCENTILE.EXC", args, 2, 2)?;
+    Ok(match sorted_numbers_and_arg(args, ctx)? {
+        Ok((nums, k)) => num_or_error(percentile_exc(&nums, k)),
+        Err(e) => e,
+    })
+}
+
+/// QUARTILE(array, quart) and QUARTILE.INC - quart 0 to 4
+pub fn fn_quartile_inc(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("QUARTILE.INC", args, 2, 2)?;
+    Ok(match sorted_numbers_and_arg(args, ctx)? {
+        Ok((nums, quart)) => num_or_error(percentile_inc(&nums, quart.trunc() / 4.0)),
+        Err(e) => e,
+    })
+}
+
+/// QUARTILE.EXC(array, quart) - quart 1 to 3
+pub fn fn_quartile_exc(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("QUARTILE.EXC", args, 2, 2)?;
+    Ok(match sorted_numbers_and_arg(args, ctx)? {
+        Ok((nums, quart)) => num_or_error(percentile_exc(&nums, quart.trunc() / 4.0)),
+        Err(e) => e,
+    })
+}
+
+/// Shared implementation of PERCENTRANK.INC and PERCENTRANK.EXC. `position`
+/// maps the zero-based fractional position of x among the n sorted numbers
+/// to its rank.
+fn percentrank(
+    name: &str,
+    args: &[Expr],
+    ctx: &mut Evaluator,
+    position: fn(f64, usize) -> f64,
+) -> Result<CellValue> {
+    check_arg_count(name, args, 2, 3)?;
+    let (nums, x) = match sorted_numbers_and_arg(args, ctx)? {
+        Ok(v) => v,
+        Err(e) => return Ok(e),
+    };
+    let significance = if args.len() > 2 {
+        coerce_to_number(&ctx.eval_expr(&args[2])?)?.trunc()
+    } else {
+        3.0
+    };
+    if significance < 1.0 {
+        return Ok(CellValue::Error("#NUM!".to_string()));
+    }
+    if x < nums[0] || x > nums[nums.len() - 1] {
+        return Ok(CellValue::Error("#N/A".to_string()));
+    }
+    let below = nums.iter().filter(|&&n| n < x).count();
+    let index = if nums[below] == x {
+        below as f64
+    } else {
+        let lower = nums[below - 1];
+        (below - 1) as f64 + (x - lower) / (nums[below] - lower)
+    };
+    let rank = position(index, nums.len());
+    // The result is truncated, not rounded, to `significance` digits.
+    let factor = 10f64.powf(significance);
+    Ok(CellValue::Number((rank * factor + 1e-9).floor() / factor))
+}
+
+/// PERCENTRANK(array, x, [significance]) and PERCENTRANK.INC
+pub fn fn_percentrank_inc(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    percentrank("PERCENTRANK.INC", args, ctx, |index, n| {
+        if n == 1 {
+            1.0
+        } else {
+            index / (n - 1) as f64
+        }
+    })
+}
+
+/// PERCENTRANK.EXC(array, x, [significance])
+pub fn fn_percentrank_exc(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    percentrank("PERCENTRANK.EXC", args, ctx, |index, n| {
+        (index + 1.0) / (n + 1) as f64
+    })
+}
+
+// -- Correlation and simple linear regression --
+
+/// Sums of a set of (x, y) pairs, centered on their means.
+struct PairSums {
+    n: f64,
+    mean_x: f64,
+    mean_y: f64,
+    sxx: f64,
+    syy: f64,
+    sxy: f64,
+}
+
+/// Evaluate two equally sized arrays and return the sums of the pairs in
+/// which both values are numbers. Arrays of different sizes yield `#N/A`.
+fn pair_sums(
+    x_arg: &Expr,
+    y_arg: &Expr,
+    ctx: &mut Evaluator,
+) -> Result<std::result::Result<PairSums, CellValue>> {
+    let xs = collect_criteria_range_values(x_arg, ctx)?;
+    let ys = collect_criteria_range_values(y_arg, ctx)?;
+    if xs.len() != ys.len() {
+        return Ok(Err(CellValue::Error("#N/A".to_string())));
+    }
+    let mut pairs = Vec::with_capacity(xs.len());
+    for (x, y) in xs.iter().zip(&ys) {
+        match (x, y) {
+            (CellValue::Error(e), _) | (_, CellValue::Error(e)) => {
+                return Ok(Err(CellValue::Error(e.clone())))
+            }
+            (
+                CellValue::Number(x) | CellValue::Date(x),
+                CellValue::Number(y) | CellValue::Date(y),
+            ) => pairs.push((*x, *y)),
+            _ => {}
+        }
+    }
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+    let mut sums = PairSums {
+        n,
+        mean_x,
+        mean_y,
+        sxx: 0.0,
+        syy: 0.0,
+        sxy: 0.0,
+    };
+    for (x, y) in pairs {
+        sums.sxx += (x - mean_x) * (x - mean_x);
+        sums.syy += (y - mean_y) * (y - mean_y);
+        sums.sxy += (x - mean_x) * (y - mean_y);
+    }
+    Ok(Ok(sums))
+}
+
+/// Shared implementation of the functions of two arrays of pairs. `f`
+/// returns `None` where the result would divide by zero.
+fn paired_stat(
+    name: &str,
+    args: &[Expr],
+    ctx: &mut Evaluator,
+    y_first: bool,
+    f: impl Fn(&PairSums) -> Option<f64>,
+) -> Result<CellValue> {
+    check_arg_count(name, args, 2, 2)?;
+    let (x_arg, y_arg) = if y_first {
+        (&args[1], &args[0])
+    } else {
+        (&args[0], &args[1])
+    };
+    let sums = match pair_sums(x_arg, y_arg, ctx)? {
+        Ok(sums) => sums,
+        Err(e) => return Ok(e),
+    };
+    Ok(match f(&sums).filter(|v| v.is_finite()) {
+        Some(v) => CellValue::Number(v),
+        None => CellValue::Error("#DIV/0!".to_string()),
+    })
+}
+
+fn correlation(s: &PairSums) -> Option<f64> {
+    (s.sxx > 0.0 && s.syy > 0.0).then(|| s.sxy / (s.sxx * s.syy).sqrt())
+}
+
+fn slope(s: &PairSums) -> Option<f64> {
+    (s.sxx > 0.0).then(|| s.sxy / s.sxx)
+}
+
+/// CORREL(array1, array2) and PEARSON - correlation coefficient
+pub fn fn_correl(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    paired_stat("CORREL", args, ctx, false, correlation)
+}
+
+/// RSQ(known_y's, known_x's) - square of the correlation coefficient
+pub fn fn_rsq(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    paired_stat("RSQ", args, ctx, true, |s| correlation(s).map(|r| r * r))
+}
+
+/// COVARIANCE.P(array1, array2) and COVAR - population covariance
+pub fn fn_covariance_p(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    paired_stat("COVARIANCE.P", args, ctx, false, |s| {
+        (s.n > 0.0).then(|| s.sxy / s.n)
+    })
+}
+
+/// COVARIANCE.S(array1, array2) - sample covariance
+pub fn fn_covariance_s(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    paired_stat("COVARIANCE.S", args, ctx, false, |s| {
+        (s.n > 1.0).then(|| s.sxy / (s.n - 1.0))
+    })
+}
+
+/// SLOPE(known_y's, known_x's) - slope of the regression line
+pub fn fn_slope(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    paired_stat("SLOPE", args, ctx, true, slope)
+}
+
+/// INTERCEPT(known_y's, known_x's) - intercept of the regression line
+pub fn fn_intercept(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    paired_stat("INTERCEPT", args, ctx, true, |s| {
+        slope(s).map(|m| s.mean_y - m * s.mean_x)
+    })
+}
+
+/// STEYX(known_y's, known_x's) - standard error of the predicted y values
+pub fn fn_steyx(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    paired_stat("STEYX", args, ctx, true, |s| {
+        (s.n > 2.0 && s.sxx > 0.0).then(|| ((s.syy - s.sxy * s.sxy / s.sxx) / (s.n - 2.0)).sqrt())
+    })
+}
+
+/// FORECAST(x, known_y's, known_x's) and FORECAST.LINEAR - value of the
+/// regression line at x
+pub fn fn_forecast(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("FORECAST.LINEAR", args, 3, 3)?;
+    let x = coerce_to_number(&ctx.eval_expr(&args[0])?)?;
+    paired_stat("FORECAST.LINEAR", &args[1..], ctx, true, |s| {
+        slope(s).map(|m| s.mean_y + m * (x - s.mean_x))
+    })
+}
+
+// -- Conditional extremes --
+
+/// Shared implementation of MAXIFS and MINIFS: the `pick` of the numbers in
+/// the first range whose cells meet all criteria, or 0 if there are none.
+fn extreme_ifs(
+    name: &str,
+    args: &[Expr],
+    ctx: &mut Evaluator,
+    pick: fn(f64, f64) -> f64,
+) -> Result<CellValue> {
+    check_arg_count(name, args, 3, 255)?;
+    if !(args.len() - 1).is_multiple_of(2) {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    let values = collect_criteria_range_values(&args[0], ctx)?;
+    let pair_count = (args.len() - 1) / 2;
+    let mut criteria_ranges: Vec<Vec<CellValue>> = Vec::with_capacity(pair_count);
+    let mut criteria_strings: Vec<String> = Vec::with_capacity(pair_count);
+    for i in 0..pair_count {
+        let range_vals = collect_criteria_range_values(&args[1 + i * 2], ctx)?;
+        if range_vals.len() != values.len() {
+            return Ok(CellValue::Error("#VALUE!".to_string()));
+        }
+        let crit_val = ctx.eval_expr(&args[2 + i * 2])?;
+        criteria_ranges.push(range_vals);
+        criteria_strings.push(coerce_to_string(&crit_val));
+    }
+    let mut result: Option<f64> = None;
+    for (idx, v) in values.iter().enumerate() {
+        let n = match v {
+            CellValue::Number(n) | CellValue::Date(n) => *n,
+            _ => continue,
+        };
+        let all_match = criteria_ranges
+            .iter()
+            .zip(criteria_strings.iter())
+            .all(|(range_vals, crit)| matches_criteria(&range_vals[idx], crit));
+        if all_match {
+            result = Some(result.map_or(n, |r| pick(r, n)));
+        }
+    }
+    Ok(CellValue::Number(result.unwrap_or(0.0)))
+}
+
+/// MAXIFS(max_range, criteria_range1, criteria1, ...)
+pub fn fn_maxifs(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    extreme_ifs("MAXIFS", args, ctx, f64::max)
+}
+
+/// MINIFS(min_range, criteria_range1, criteria1, ...)
+pub fn fn_minifs(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    extreme_ifs("MINIFS", args, ctx, f64::min)
+}
+
+// -- Multiple linear regression --
+
+/// Observations of a regression: one y value and one row of x values each.
+struct RegressionData {
+    ys: Vec<f64>,
+    xs: Vec<Vec<f64>>,
+    /// Whether the known y's form a single row, in which case each row
+    /// (rather than each column) of a multi-variable x array is a variable.
+    by_row: bool,
+}
+
+fn array_error(code: &str) -> ArrayValue {
+    ArrayValue::scalar(CellValue::Error(code.to_string()))
+}
+
+/// Evaluate the known y's and, if given, known x's of LINEST, TREND and
+/// GROWTH. Without known x's, x is 1, 2, 3, ... With known x's the same
+/// shape as the y's there is one variable; otherwise a column of y's takes
+/// each column of the x's as a variable, and a row of y's each row.
+fn regression_data(
+    y_arg: &Expr,
+    x_arg: Option<&Expr>,
+    ctx: &mut Evaluator,
+) -> Result<std::result::Result<RegressionData, String>> {
+    let y_array = ctx.eval_array(y_arg)?;
+    let ys: Vec<f64> = match numeric_matrix(&y_array) {
+        Ok(m) => m.into_iter().flatten().collect(),
+        Err(e) => return Ok(Err(e)),
+    };
+    let by_row = y_array.rows == 1 && y_array.cols > 1;
+    let Some(x_arg) = x_arg else {
+        let xs = (1..=ys.len()).map(|i| vec![i as f64]).collect();
+        return Ok(Ok(RegressionData { ys, xs, by_row }));
+    };
+    let x_array = ctx.eval_array(x_arg)?;
+    let x_matrix = match numeric_matrix(&x_array) {
+        Ok(m) => m,
+        Err(e) => return Ok(Err(e)),
+    };
+    let xs = if x_array.rows == y_array.rows && x_array.cols == y_array.cols {
+        x_matrix.into_iter().flatten().map(|x| vec![x]).collect()
+    } else if !by_row && x_array.rows == ys.len() {
+        x_matrix
+    } else if by_row && x_array.cols == ys.len() {
+        (0..x_array.cols)
+            .map(|c| x_matrix.iter().map(|row| row[c]).collect())
+            .collect()
+    } else {
+        return Ok(Err("#REF!".to_string()));
+    };
+    Ok(Ok(RegressionData { ys, xs, by_row }))
+}
+
+/// A least-squares fit y = m1*x1 + ... + mk*xk + b.
+struct LinearFit {
+    coefficients: Vec<f64>,
+    intercept: f64,
+    /// Inverse of the (centered, with a constant) normal-equations matrix,
+    /// for the standard errors.
+    inverse: Vec<Vec<f64>>,
+    /// Means of the x variables (zero without a constant).
+    x_means: Vec<f64>,
+}
+
+impl LinearFit {
+    fn predict(&self, xs: &[f64]) -> f64 {
+        self.intercept
+            + self
+                .coefficients
+                .iter()
+                .zip(xs)
+                .map(|(m, x)| m * x)
+                .sum::<f64>()
+    }
+}
+
+/// Fit `data` by least squares, with an intercept if `constant` is set.
+/// Returns `None` when the x variables are linearly dependent.
+fn linear_fit(data: &RegressionData, constant: bool) -> Option<LinearFit> {
+    let k = data.xs.first().map_or(0, Vec::len);
+    let n = data.ys.len() as f64;
+    let (x_means, y_mean) = if constant {
+        let x_means = (0..k)
+            .map(|j| data.xs.iter().map(|row| row[j]).sum::<f64>() / n)
+            .collect();
+        (x_means, mean(&data.ys))
+    } else {
+        (vec![0.0; k], 0.0)
+    };
+    let mut normal = vec![vec![0.0; k]; k];
+    let mut rhs = vec![vec![0.0]; k];
+    for (row, y) in data.xs.iter().zip(&data.ys) {
+        for i in 0..k {
+            let xi = row[i] - x_means[i];
+            rhs[i][0] += xi * (y - y_mean);
+            for j in 0..k {
+                normal[i][j] += xi * (row[j] - x_means[j]);
+            }
+        }
+    }
+    let mut inverse: Vec<Vec<f64>> = (0..k)
+        .map(|r| (0..k).map(|c| if r == c { 1.0 } else { 0.0 }).collect())
+        .collect();
+    if gauss_jordan(&mut normal.clone(), &mut inverse) == 0.0 {
+        return None;
+    }
+    let coefficients: Vec<f64> = inverse
+        .iter()
+        .map(|row| row.iter().zip(&rhs).map(|(a, r)| a * r[0]).sum())
+        .collect();
+    let intercept = y_mean
+        - coefficients
+            .iter()
+            .zip(&x_means)
+            .map(|(m, x)| m * x)
+            .sum::<f64>();
+    Some(LinearFit {
+        coefficients,
+        intercept,
+        inverse,
+        x_means,
+    })
+}
+
+/// Evaluate an optional boolean argument.
+fn opt_bool_arg(args: &[Expr], index: usize, default: bool, ctx: &mut Evaluator) -> Result<bool> {
+    if index < args.len() {
+        coerce_to_bool(&ctx.eval_expr(&args[index])?)
+    } else {
+        Ok(default)
+    }
+}
+
+/// LINEST(known_y's, [known_x's], [const], [stats]) - coefficients of the
+/// least-squares line, as a row mk, ..., m1, b. With `stats` four more rows
+/// follow: the standard errors, then r² and the standard error of y, the F
+/// statistic and the degrees of freedom, and the regression and residual
+/// sums of squares.
+pub fn fn_linest(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("LINEST", args, 1, 4)?;
+    let data = match regression_data(&args[0], args.get(1), ctx)? {
+        Ok(data) => data,
+        Err(e) => return Ok(array_error(&e)),
+    };
+    let constant = opt_bool_arg(args, 2, true, ctx)?;
+    let stats = opt_bool_arg(args, 3, false, ctx)?;
+    let Some(fit) = linear_fit(&data, constant) else {
+        return Ok(array_error("#NUM!"));
+    };
+    let k = fit.coefficients.len();
+    let cols = k + 1;
+    let mut values: Vec<CellValue> = fit
+        .coefficients
+        .iter()
+        .rev()
+        .chain(std::iter::once(&fit.intercept))
+        .map(|&v| CellValue::Number(v))
+        .collect();
+    if !stats {
+        return Ok(ArrayValue::new(1, cols, values));
+    }
+
+    let n = data.ys.len() as f64;
+    let ss_resid: f64 = data
+        .xs
+        .iter()
+        .zip(&data.ys)
+        .map(|(xs, y)| (y - fit.predict(xs)).powi(2))
+        .sum();
+    let ss_total = if constant {
+        sum_sq_dev(&data.ys)
+    } else {
+        data.ys.iter().map(|y| y * y).sum()
+    };
+    let ss_reg = ss_total - ss_resid;
+    let df = n - k as f64 - if constant { 1.0 } else { 0.0 };
+    let variance = ss_resid / df;
+    let stat = |v: f64| {
+        if v.is_finite() {
+            CellValue::Number(v)
+        } else {
+            CellValue::Error("#NUM!".to_string())
+        }
+    };
+    let na = || CellValue::Error("#N/A".to_string());
+
+    values.extend(
+        (0..k)
+            .rev()
+            .map(|j| stat((variance * fit.inverse[j][j]).sqrt())),
+    );
+    values.push(if constant {
+        let spread: f64 = (0..k)
+            .flat_map(|i| (0..k).map(move |j| (i, j)))
+            .map(|(i, j)| fit.x_means[i] * fit.inverse[i][j] * fit.x_means[j])
+            .sum();
+        stat((variance * (1.0 / n + spread)).sqrt())
+    } else {
+        na()
+    });
+    for (first, second) in [
+        (ss_reg / ss_total, variance.sqrt()),
+        ((ss_reg / k as f64) / variance, df),
+        (ss_reg, ss_resid),
+    ] {
+        values.push(stat(first));
+        values.push(stat(second));
+        values.extend((2..cols).map(|_| na()));
+    }
+    Ok(ArrayValue::new(5, cols, values))
+}
+
+/// Shared implementation of TREND and GROWTH. GROWTH fits the logarithms of
+/// the y's and exponentiates the predictions.
+fn predict_trend(
+    name: &str,
+    args: &[Expr],
+    ctx: &mut Evaluator,
+    exponential: bool,
+) -> Result<ArrayValue> {
+    check_arg_count(name, args, 1, 4)?;
+    let mut data = match regression_data(&args[0], args.get(1), ctx)? {
+        Ok(data) => data,
+        Err(e) => return Ok(array_error(&e)),
+    };
+    if exponential {
+        if data.ys.iter().any(|&y| y <= 0.0) {
+            return Ok(array_error("#NUM!"));
+        }
+        data.ys.iter_mut().for_each(|y| *y = y.ln());
+    }
+    let constant = opt_bool_arg(args, 3, true, ctx)?;
+    let Some(fit) = linear_fit(&data, constant) else {
+        return Ok(array_error("#NUM!"));
+    };
+    let k = fit.coefficients.len();
+
+    // The new x's default to the known x's, in the shape of the known y's.
+    let new_x = match args.get(2).or(args.get(1)) {
+        Some(arg) => ctx.eval_array(arg)?,
+        None => {
+            let n = data.ys.len();
+            let values = (1..=n).map(|i| CellValue::Number(i as f64)).collect();
+            if data.by_row {
+                ArrayValue::new(1, n, values)
+            } else {
+                ArrayValue::new(n, 1, values)
+            }
+        }
+    };
+    let matrix = match numeric_matrix(&new_x) {
+        Ok(m) => m,
+        Err(e) => return Ok(array_error(&e)),
+    };
+    let (rows, cols, points): (usize, usize, Vec<Vec<f64>>) = if k == 1 {
+        let points = matrix.into_iter().flatten().map(|x| vec![x]).collect();
+        (new_x.rows, new_x.cols, points)
+    } else if !data.by_row && new_x.cols == k {
+        (new_x.rows, 1, matrix)
+    } else if data.by_row && new_x.rows == k {
+        let points = (0..new_x.cols)
+            .map(|c| matrix.iter().map(|row| row[c]).collect())
+            .collect();
+        (1, new_x.cols, points)
+    } else {
+        return Ok(array_error("#REF!"));
+    };
+    let values = points
+        .iter()
+        .map(|xs| {
+            let y = fit.predict(xs);
+            CellValue::Number(if exponential { y.exp() } else { y })
+        })
+        .collect();
+    Ok(ArrayValue::new(rows, cols, values))
+}
+
+/// TREND(known_y's, [known_x's], [new_x's], [const]) - values along the
+/// least-squares line
+pub fn fn_trend(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    predict_trend("TREND", args, ctx, false)
+}
+
+/// GROWTH(known_y's, [known_x's], [new_x's], [const]) - values along the
+/// least-squares exponential curve
+pub fn fn_growth(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    predict_trend("GROWTH", args, ctx, true)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::cell::CellValue;
-    use crate::formula::eval::{evaluate, CellSnapshot};
+    use crate::formula::eval::{evaluate, ArrayValue, CellSnapshot, Evaluator};
     use crate::formula::parser::parse_formula;
 
     fn eval_with_data(formula: &str, data: &[(&str, u32, u32, CellValue)]) -> CellValue {
@@ -473,4 +1372,351 @@ mod tests {
         let result = eval_with_data("RANK(99,A1:A2)", &data);
         assert_eq!(result, CellValue::Error("#N/A".to_string()));
     }
+
+    /// Cells in column `col` from row 1 down holding `values`.
+    fn column(col: u32, values: &[f64]) -> Vec<(&'static str, u32, u32, CellValue)> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &v)| ("Sheet1", col, i as u32 + 1, CellValue::Number(v)))
+            .collect()
+    }
+
+    fn num_with_data(formula: &str, data: &[(&str, u32, u32, CellValue)]) -> f64 {
+        match eval_with_data(formula, data) {
+            CellValue::Number(n) => n,
+            other => panic!("{formula}: expected number, got {other:?}"),
+        }
+    }
+
+    fn number(value: &CellValue) -> f64 {
+        match value {
+            CellValue::Number(n) => *n,
+            other => panic!("expected number, got {other:?}"),
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() <= 1e-9 * expected.abs().max(1.0),
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    fn eval_array(formula: &str, data: &[(&str, u32, u32, CellValue)]) -> ArrayValue {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        for (sheet, col, row, val) in data {
+            snap.set_cell(sheet, *col, *row, val.clone());
+        }
+        let expr = parse_formula(formula).unwrap();
+        Evaluator::new(&snap).eval_array(&expr).unwrap()
+    }
+
+    // RANK.AVG tests
+
+    #[test]
+    fn rank_avg_ties() {
+        let data = column(1, &[89.0, 88.0, 92.0, 101.0, 94.0, 97.0, 95.0, 94.0]);
+        assert_eq!(
+            eval_with_data("RANK.AVG(94,A1:A8)", &data),
+            CellValue::Number(4.5)
+        );
+        assert_eq!(
+            eval_with_data("RANK.EQ(94,A1:A8)", &data),
+            CellValue::Number(4.0)
+        );
+    }
+
+    // STDEV and VAR tests
+
+    #[test]
+    fn stdev_and_var() {
+        let data = column(
+            1,
+            &[
+                1345.0, 1301.0, 1368.0, 1322.0, 1310.0, 1370.0, 1318.0, 1350.0, 1303.0, 1299.0,
+            ],
+        );
+        assert_close(num_with_data("STDEV(A1:A10)", &data), 27.463_915_719_843_1);
+        assert_close(
+            num_with_data("STDEV.S(A1:A10)", &data),
+            27.463_915_719_843_1,
+        );
+        assert_close(
+            num_with_data("STDEV.P(A1:A10)", &data),
+            26.054_558_142_482_5,
+        );
+        assert_close(num_with_data("VAR.S(A1:A10)", &data), 754.266_666_666_667);
+        assert_close(num_with_data("VARP(A1:A10)", &data), 678.84);
+    }
+
+    #[test]
+    fn stdev_ignores_text_in_ranges_unless_a_variant() {
+        let data = vec![
+            ("Sheet1", 1, 1, CellValue::Number(2.0)),
+            ("Sheet1", 1, 2, CellValue::Number(4.0)),
+            ("Sheet1", 1, 3, CellValue::String("n/a".to_string())),
+            ("Sheet1", 1, 4, CellValue::Bool(true)),
+        ];
+        assert_close(num_with_data("VAR.S(A1:A4)", &data), 2.0);
+        // VARA counts the text as 0 and TRUE as 1: [2, 4, 0, 1].
+        assert_close(num_with_data("VARA(A1:A4)", &data), 8.75 / 3.0);
+        assert_close(num_with_data("VARPA(A1:A4)", &data), 2.1875);
+        assert_close(num_with_data("STDEVPA(A1:A4)", &data), 2.1875f64.sqrt());
+    }
+
+    #[test]
+    fn var_too_few_values() {
+        assert_eq!(eval("VAR.S(5)"), CellValue::Error("#DIV/0!".to_string()));
+        assert_eq!(eval("VAR.P(5)"), CellValue::Number(0.0));
+    }
+
+    // Dispersion and shape tests
+
+    #[test]
+    fn avedev_devsq_and_means() {
+        let data = column(1, &[4.0, 5.0, 6.0, 7.0, 5.0, 4.0, 3.0]);
+        assert_close(num_with_data("AVEDEV(A1:A7)", &data), 1.020_408_163_265_31);
+        assert_close(num_with_data("DEVSQ(A1:A7)", &data), 10.857_142_857_142_9);
+        let data = column(1, &[4.0, 5.0, 8.0, 7.0, 11.0, 4.0, 3.0]);
+        assert_close(num_with_data("GEOMEAN(A1:A7)", &data), 5.476_986_969_656_96);
+        assert_close(num_with_data("HARMEAN(A1:A7)", &data), 5.028_375_962_061_4);
+        assert_eq!(eval("GEOMEAN(4,-1)"), CellValue::Error("#NUM!".to_string()));
+    }
+
+    #[test]
+    fn skew_and_kurt() {
+        let data = column(1, &[3.0, 4.0, 5.0, 2.0, 3.0, 4.0, 5.0, 6.0, 4.0, 7.0]);
+        assert_close(num_with_data("SKEW(A1:A10)", &data), 0.359_543_071_407_307);
+        assert_close(num_with_data("KURT(A1:A10)", &data), -0.151_799_637_208_43);
+        assert_eq!(eval("SKEW(1,2)"), CellValue::Error("#DIV/0!".to_string()));
+    }
+
+    #[test]
+    fn standardize() {
+        assert_close(
+            num_with_data("STANDARDIZE(42,40,1.5)", &[]),
+            1.333_333_333_333_33,
+        );
+        assert_eq!(
+            eval("STANDARDIZE(42,40,0)"),
+            CellValue::Error("#NUM!".to_string())
+        );
+    }
+
+    // PERCENTILE, QUARTILE and PERCENTRANK tests
+
+    #[test]
+    fn percentile_inc_and_exc() {
+        let data = column(1, &[1.0, 2.0, 3.0, 4.0]);
+        assert_close(num_with_data("PERCENTILE(A1:A4,0.3)", &data), 1.9);
+        assert_close(num_with_data("PERCENTILE.INC(A1:A4,1)", &data), 4.0);
+        let data = column(1, &[1.0, 2.0, 3.0, 6.0, 6.0, 6.0, 7.0, 8.0, 9.0]);
+        assert_close(num_with_data("PERCENTILE.EXC(A1:A9,0.25)", &data), 2.5);
+        assert_eq!(
+            eval_with_data("PERCENTILE.EXC(A1:A9,0.05)", &data),
+            CellValue::Error("#NUM!".to_string())
+        );
+        assert_eq!(
+            eval_with_data("PERCENTILE.INC(A1:A9,1.5)", &data),
+            CellValue::Error("#NUM!".to_string())
+        );
+    }
+
+    #[test]
+    fn quartile_inc_and_exc() {
+        let data = column(1, &[1.0, 2.0, 4.0, 7.0, 8.0, 9.0, 10.0, 12.0]);
+        assert_close(num_with_data("QUARTILE(A1:A8,1)", &data), 3.5);
+        assert_close(num_with_data("QUARTILE.INC(A1:A8,4)", &data), 12.0);
+        let data = column(
+            1,
+            &[
+                6.0, 7.0, 15.0, 36.0, 39.0, 40.0, 41.0, 42.0, 43.0, 47.0, 49.0,
+            ],
+        );
+        assert_close(num_with_data("QUARTILE.EXC(A1:A11,1)", &data), 15.0);
+        assert_close(num_with_data("QUARTILE.EXC(A1:A11,3)", &data), 43.0);
+        assert_eq!(
+            eval_with_data("QUARTILE.EXC(A1:A11,4)", &data),
+            CellValue::Error("#NUM!".to_string())
+        );
+    }
+
+    #[test]
+    fn percentrank_inc_and_exc() {
+        let data = column(1, &[13.0, 12.0, 11.0, 8.0, 4.0, 3.0, 2.0, 1.0, 1.0, 1.0]);
+        assert_close(num_with_data("PERCENTRANK.INC(A1:A10,2)", &data), 0.333);
+        assert_close(num_with_data("PERCENTRANK(A1:A10,4)", &data), 0.555);
+        assert_close(num_with_data("PERCENTRANK.INC(A1:A10,8)", &data), 0.666);
+        assert_close(num_with_data("PERCENTRANK.INC(A1:A10,5)", &data), 0.583);
+        assert_close(num_with_data("PERCENTRANK.INC(A1:A10,5,1)", &data), 0.5);
+        assert_eq!(
+            eval_with_data("PERCENTRANK.INC(A1:A10,20)", &data),
+            CellValue::Error("#N/A".to_string())
+        );
+        let data = column(1, &[1.0, 2.0, 3.0, 6.0, 6.0, 6.0, 7.0, 8.0, 9.0]);
+        assert_close(num_with_data("PERCENTRANK.EXC(A1:A9,7)", &data), 0.7);
+        assert_close(num_with_data("PERCENTRANK.EXC(A1:A9,5.43)", &data), 0.381);
+    }
+
+    // Correlation and regression tests
+
+    #[test]
+    fn correlation_and_covariance() {
+        let mut data = column(1, &[3.0, 2.0, 4.0, 5.0, 6.0]);
+        data.extend(column(2, &[9.0, 7.0, 12.0, 15.0, 17.0]));
+        assert_close(
+            num_with_data("CORREL(A1:A5,B1:B5)", &data),
+            0.997_054_485_501_581,
+        );
+        assert_close(
+            num_with_data("PEARSON(A1:A5,B1:B5)", &data),
+            0.997_054_485_501_581,
+        );
+        assert_close(num_with_data("COVARIANCE.P(A1:A5,B1:B5)", &data), 5.2);
+        assert_close(num_with_data("COVAR(A1:A5,B1:B5)", &data), 5.2);
+        assert_close(num_with_data("COVARIANCE.S(A1:A5,B1:B5)", &data), 6.5);
+        assert_eq!(
+            eval_with_data("CORREL(A1:A5,B1:B4)", &data),
+            CellValue::Error("#N/A".to_string())
+        );
+    }
+
+    #[test]
+    fn simple_regression() {
+        let mut data = column(1, &[2.0, 3.0, 9.0, 1.0, 8.0, 7.0, 5.0]);
+        data.extend(column(2, &[6.0, 5.0, 11.0, 7.0, 5.0, 4.0, 4.0]));
+        assert_close(
+            num_with_data("SLOPE(A1:A7,B1:B7)", &data),
+            0.305_555_555_555_556,
+        );
+        assert_close(
+            num_with_data("INTERCEPT(A1:A7,B1:B7)", &data),
+            3.166_666_666_666_67,
+        );
+        assert_close(
+            num_with_data("RSQ(A1:A7,B1:B7)", &data),
+            0.057_950_191_570_881_2,
+        );
+        assert_close(
+            num_with_data("STEYX(A1:A7,B1:B7)", &data),
+            3.305_718_950_210_41,
+        );
+        assert_close(
+            num_with_data("FORECAST.LINEAR(10,A1:A7,B1:B7)", &data),
+            6.222_222_222_222_22,
+        );
+        assert_close(
+            num_with_data("FORECAST(10,A1:A7,B1:B7)", &data),
+            6.222_222_222_222_22,
+        );
+        assert_eq!(eval("SLOPE(1,2)"), CellValue::Error("#DIV/0!".to_string()));
+    }
+
+    #[test]
+    fn linest_with_statistics() {
+        let mut data = column(1, &[1.0, 9.0, 5.0, 7.0, 12.0, 14.0]);
+        data.extend(column(2, &[0.0, 4.0, 2.0, 3.0, 5.0, 7.0]));
+        data.extend(column(3, &[1.0, 0.0, 3.0, 2.0, 2.0, 1.0]));
+        let result = eval_array("LINEST(A1:A6,B1:C6,TRUE,TRUE)", &data);
+        assert_eq!((result.rows, result.cols), (5, 3));
+        let expected = [
+            [
+                0.157_051_282_051_282,
+                1.945_512_820_512_82,
+                0.955_128_205_128_205,
+            ],
+            [
+                0.330_604_999_644_667,
+                0.142_751_195_411_336,
+                0.830_146_767_439_234,
+            ],
+            [0.984_518_086_080_586, 0.760_257_491_243_232, f64::NAN],
+            [95.387_245_841_035_1, 3.0, f64::NAN],
+            [110.266_025_641_026, 1.733_974_358_974_36, f64::NAN],
+        ];
+        for (r, row) in expected.iter().enumerate() {
+            for (c, &v) in row.iter().enumerate() {
+                match result.get(r, c) {
+                    CellValue::Number(n) => assert_close(*n, v),
+                    other => {
+                        assert!(v.is_nan(), "({r}, {c}): got {other:?}");
+                        assert_eq!(*other, CellValue::Error("#N/A".to_string()));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn linest_simple_and_without_constant() {
+        let mut data = column(1, &[1.0, 9.0, 5.0, 7.0]);
+        data.extend(column(2, &[0.0, 4.0, 2.0, 3.0]));
+        let result = eval_array("LINEST(A1:A4,B1:B4)", &data);
+        assert_eq!((result.rows, result.cols), (1, 2));
+        assert_close(number(&result.values[0]), 2.0);
+        assert_close(number(&result.values[1]), 1.0);
+        let result = eval_array("LINEST(A1:A4,B1:B4,FALSE)", &data);
+        assert_close(number(&result.values[0]), 67.0 / 29.0);
+        assert_eq!(result.values[1], CellValue::Number(0.0));
+    }
+
+    #[test]
+    fn trend_and_growth() {
+        let mut data = column(1, &[1.0, 9.0, 5.0, 7.0]);
+        data.extend(column(2, &[0.0, 4.0, 2.0, 3.0]));
+        data.extend(column(3, &[5.0, 6.0]));
+        let result = eval_array("TREND(A1:A4,B1:B4,C1:C2)", &data);
+        assert_eq!((result.rows, result.cols), (2, 1));
+        assert_close(number(&result.values[0]), 11.0);
+        assert_close(number(&result.values[1]), 13.0);
+        // Without new x's, the fitted values at the known x's.
+        let result = eval_array("TREND(A1:A4,B1:B4)", &data);
+        assert_eq!(result.rows, 4);
+        assert_close(number(&result.values[1]), 9.0);
+
+        let mut data = column(1, &[2.0, 4.0, 8.0, 16.0]);
+        data.extend(column(2, &[1.0, 2.0, 3.0, 4.0]));
+        let result = eval_array("GROWTH(A1:A4,B1:B4,6)", &data);
+        assert_close(number(&result.values[0]), 64.0);
+        assert_close(num_with_data("GROWTH(A1:A4)", &data), 2.0);
+        data.push(("Sheet1", 1, 5, CellValue::Number(0.0)));
+        data.push(("Sheet1", 2, 5, CellValue::Number(5.0)));
+        assert_eq!(
+            eval_with_data("GROWTH(A1:A5,B1:B5)", &data),
+            CellValue::Error("#NUM!".to_string())
+        );
+    }
+
+    // MAXIFS and MINIFS tests
+
+    #[test]
+    fn maxifs_and_minifs() {
+        let data = vec![
+            ("Sheet1", 1, 1, CellValue::Number(89.0)),
+            ("Sheet1", 1, 2, CellValue::Number(93.0)),
+            ("Sheet1", 1, 3, CellValue::Number(96.0)),
+            ("Sheet1", 1, 4, CellValue::Number(85.0)),
+            ("Sheet1", 2, 1, CellValue::String("a".to_string())),
+            ("Sheet1", 2, 2, CellValue::String("b".to_string())),
+            ("Sheet1", 2, 3, CellValue::String("a".to_string())),
+            ("Sheet1", 2, 4, CellValue::String("b".to_string())),
+        ];
+        assert_eq!(
+            eval_with_data("MAXIFS(A1:A4,B1:B4,\"b\")", &data),
+            CellValue::Number(93.0)
+        );
+        assert_eq!(
+            eval_with_data("MINIFS(A1:A4,B1:B4,\"a\",A1:A4,\">90\")", &data),
+            CellValue::Number(96.0)
+        );
+        assert_eq!(
+            eval_with_data("MAXIFS(A1:A4,B1:B4,\"c\")", &data),
+            CellValue::Number(0.0)
+        );
+        assert_eq!(
+            eval_with_data("MAXIFS(A1:A4,B1:B3,\"a\")", &data),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
 }
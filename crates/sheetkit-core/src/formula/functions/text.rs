@@ -1,10 +1,16 @@
-//! Text formula functions: CONCAT, FIND, SEARCH, SUBSTITUTE, REPLACE, REPT, EXACT, T, PROPER.
+//! Text formula functions: CONCAT, FIND, SEARCH, SUBSTITUTE, REPLACE, REPT, EXACT, T, PROPER,
+//! TEXTJOIN, TEXTBEFORE, TEXTAFTER, TEXTSPLIT, CHAR, CODE, UNICHAR, UNICODE, CLEAN, FIXED,
+//! DOLLAR, NUMBERVALUE, VALUETOTEXT, ARRAYTOTEXT, and the byte variants LENB, LEFTB, RIGHTB,
+//! MIDB, FINDB, SEARCHB, REPLACEB.
 
 use crate::cell::CellValue;
 use crate::error::Result;
 use crate::formula::ast::Expr;
-use crate::formula::eval::{coerce_to_number, coerce_to_string, Evaluator};
-use crate::formula::functions::check_arg_count;
+use crate::formula::eval::{
+    coerce_to_bool, coerce_to_number, coerce_to_string, ArrayValue, Evaluator,
+};
+use crate::formula::functions::{check_arg_count, collect_criteria_range_values};
+use crate::numfmt::format_number;
 
 /// CONCAT(text1, [text2], ...) - concatenates multiple values.
 pub fn fn_concat(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
@@ -204,6 +210,641 @@ pub fn fn_proper(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
     Ok(CellValue::String(result))
 }
 
+/// TEXTJOIN(delimiter, ignore_empty, text1, [text2], ...) - joins text
+/// items, including every cell of ranges and arrays, with a delimiter. An
+/// array of delimiters is used in turn.
+pub fn fn_textjoin(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("TEXTJOIN", args, 3, 255)?;
+    let delimiters: Vec<String> = collect_criteria_range_values(&args[0], ctx)?
+        .iter()
+        .map(coerce_to_string)
+        .collect();
+    let ignore_empty = coerce_to_bool(&ctx.eval_expr(&args[1])?)?;
+    let mut items = Vec::new();
+    for arg in &args[2..] {
+        for value in collect_criteria_range_values(arg, ctx)? {
+            if let CellValue::Error(_) = value {
+                return Ok(value);
+            }
+            let text = coerce_to_string(&value);
+            if !(ignore_empty && text.is_empty()) {
+                items.push(text);
+            }
+        }
+    }
+    let mut result = String::new();
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 && !delimiters.is_empty() {
+            result.push_str(&delimiters[(i - 1) % delimiters.len()]);
+        }
+        result.push_str(item);
+    }
+    if result.chars().count() > MAX_TEXT_LEN {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    Ok(CellValue::String(result))
+}
+
+/// Maximum length of a text value, in characters.
+const MAX_TEXT_LEN: usize = 32767;
+
+/// TEXTBEFORE(text, delimiter, [instance_num], [match_mode], [match_end],
+/// [if_not_found]) - returns the text before a delimiter.
+pub fn fn_textbefore(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    text_around("TEXTBEFORE", args, ctx, true)
+}
+
+/// TEXTAFTER(text, delimiter, [instance_num], [match_mode], [match_end],
+/// [if_not_found]) - returns the text after a delimiter.
+pub fn fn_textafter(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    text_around("TEXTAFTER", args, ctx, false)
+}
+
+/// Shared implementation of TEXTBEFORE and TEXTAFTER. A negative
+/// `instance_num` counts delimiters from the end of the text; `match_mode`
+/// 1 ignores case; `match_end` treats the end (or, counting from the end,
+/// the start) of the text as a delimiter.
+fn text_around(name: &str, args: &[Expr], ctx: &mut Evaluator, before: bool) -> Result<CellValue> {
+    check_arg_count(name, args, 2, 6)?;
+    let text: Vec<char> = coerce_to_string(&ctx.eval_expr(&args[0])?)
+        .chars()
+        .collect();
+    let delimiters = delimiter_list(&args[1], ctx)?;
+    let instance = opt_number(args, 2, 1.0, ctx)?.trunc() as i64;
+    let ignore_case = opt_number(args, 3, 0.0, ctx)? != 0.0;
+    let match_end = opt_number(args, 4, 0.0, ctx)? != 0.0;
+    if instance == 0 || instance.unsigned_abs() as usize > text.len().max(1) {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+
+    let found = if delimiters.iter().any(Vec::is_empty) {
+        // An empty delimiter matches at the start, or from the end, at the end.
+        let at = if instance > 0 { 0 } else { text.len() };
+        Some((at, at))
+    } else {
+        let mut matches = find_delimiters(&text, &delimiters, ignore_case);
+        if match_end {
+            if instance > 0 {
+                matches.push((text.len(), text.len()));
+            } else {
+                matches.insert(0, (0, 0));
+            }
+        }
+        let index = if instance > 0 {
+            usize::try_from(instance - 1).ok()
+        } else {
+            matches.len().checked_sub(instance.unsigned_abs() as usize)
+        };
+        index.and_then(|i| matches.get(i).copied())
+    };
+    match found {
+        Some((start, end)) => {
+            let part = if before { &text[..start] } else { &text[end..] };
+            Ok(CellValue::String(part.iter().collect()))
+        }
+        None => match args.get(5) {
+            Some(Expr::Missing) | None => Ok(CellValue::Error("#N/A".to_string())),
+            Some(if_not_found) => ctx.eval_expr(if_not_found),
+        },
+    }
+}
+
+/// TEXTSPLIT(text, col_delimiter, [row_delimiter], [ignore_empty],
+/// [match_mode], [pad_with]) - splits text into an array of rows and
+/// columns. Short rows are padded with `pad_with` (default `#N/A`).
+pub fn fn_textsplit(args: &[Expr], ctx: &mut Evaluator) -> Result<ArrayValue> {
+    check_arg_count("TEXTSPLIT", args, 2, 6)?;
+    let text: Vec<char> = coerce_to_string(&ctx.eval_expr(&args[0])?)
+        .chars()
+        .collect();
+    let col_delimiters = delimiter_list(&args[1], ctx)?;
+    let row_delimiters = match args.get(2) {
+        Some(Expr::Missing) | None => Vec::new(),
+        Some(arg) => delimiter_list(arg, ctx)?,
+    };
+    let ignore_empty = match args.get(3) {
+        Some(Expr::Missing) | None => false,
+        Some(arg) => coerce_to_bool(&ctx.eval_expr(arg)?)?,
+    };
+    let ignore_case = opt_number(args, 4, 0.0, ctx)? != 0.0;
+    let pad = match args.get(5) {
+        Some(Expr::Missing) | None => CellValue::Error("#N/A".to_string()),
+        Some(arg) => ctx.eval_expr(arg)?,
+    };
+
+    let split = |text: &[char], delimiters: &[Vec<char>]| -> Vec<Vec<char>> {
+        let delimiters: Vec<Vec<char>> = delimiters
+            .iter()
+            .filter(|d| !d.is_empty())
+            .cloned()
+            .collect();
+        let mut parts = Vec::new();
+        let mut from = 0;
+        for (start, end) in find_delimiters(text, &delimiters, ignore_case) {
+            parts.push(text[from..start].to_vec());
+            from = end;
+        }
+        parts.push(text[from..].to_vec());
+        if ignore_empty {
+            parts.retain(|p| !p.is_empty());
+        }
+        parts
+    };
+    let rows: Vec<Vec<CellValue>> = split(&text, &row_delimiters)
+        .iter()
+        .map(|row| {
+            split(row, &col_delimiters)
+                .into_iter()
+                .map(|part| CellValue::String(part.into_iter().collect()))
+                .collect()
+        })
+        .collect();
+    let cols = rows.iter().map(Vec::len).max().unwrap_or(0);
+    if rows.is_empty() || cols == 0 {
+        return Ok(ArrayValue::scalar(CellValue::Error("#CALC!".to_string())));
+    }
+    let num_rows = rows.len();
+    let values = rows
+        .into_iter()
+        .flat_map(|mut row| {
+            row.resize(cols, pad.clone());
+            row
+        })
+        .collect();
+    Ok(ArrayValue::new(num_rows, cols, values))
+}
+
+/// Evaluate a delimiter argument, which may be a single text or an array of
+/// alternative delimiters.
+fn delimiter_list(arg: &Expr, ctx: &mut Evaluator) -> Result<Vec<Vec<char>>> {
+    Ok(collect_criteria_range_values(arg, ctx)?
+        .iter()
+        .map(|v| coerce_to_string(v).chars().collect())
+        .collect())
+}
+
+/// Find the non-overlapping occurrences of any of the non-empty
+/// `delimiters` in `text`, scanning left to right, as `(start, end)` char
+/// offsets.
+fn find_delimiters(
+    text: &[char],
+    delimiters: &[Vec<char>],
+    ignore_case: bool,
+) -> Vec<(usize, usize)> {
+    let same = |a: char, b: char| {
+        if ignore_case {
+            a.to_lowercase().eq(b.to_lowercase())
+        } else {
+            a == b
+        }
+    };
+    let mut matches = Vec::new();
+    let mut i = 0;
+    while i < text.len() {
+        let hit = delimiters.iter().find(|d| {
+            !d.is_empty()
+                && i + d.len() <= text.len()
+                && d.iter().zip(&text[i..]).all(|(&a, &b)| same(a, b))
+        });
+        match hit {
+            Some(d) => {
+                matches.push((i, i + d.len()));
+                i += d.len();
+            }
+            None => i += 1,
+        }
+    }
+    matches
+}
+
+/// Evaluate the optional numeric argument at `index`, or return `default`.
+fn opt_number(args: &[Expr], index: usize, default: f64, ctx: &mut Evaluator) -> Result<f64> {
+    match args.get(index) {
+        Some(Expr::Missing) | None => Ok(default),
+        Some(arg) => coerce_to_number(&ctx.eval_expr(arg)?),
+    }
+}
+
+/// Characters of Windows code page 1252 for bytes 0x80-0x9F. Unassigned
+/// bytes map to the control character of the same value.
+const CP1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{81}', '\u{201A}', '\u{192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2C6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8D}', '\u{17D}', '\u{8F}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2DC}', '\u{2122}', '\u{161}', '\u{203A}', '\u{153}', '\u{9D}', '\u{17E}', '\u{178}',
+];
+
+/// CHAR(number) - returns the character for a code (1-255) in the Windows
+/// character set.
+pub fn fn_char(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("CHAR", args, 1, 1)?;
+    let code = coerce_to_number(&ctx.eval_expr(&args[0])?)?.trunc();
+    if !(1.0..=255.0).contains(&code) {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    let code = code as u32;
+    let ch = match code {
+        0x80..=0x9F => CP1252_HIGH[(code - 0x80) as usize],
+        _ => char::from_u32(code).unwrap_or('?'),
+    };
+    Ok(CellValue::String(ch.to_string()))
+}
+
+/// CODE(text) - returns the Windows character set code of the first
+/// character of text. Characters outside the set yield 63 (`?`).
+pub fn fn_code(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("CODE", args, 1, 1)?;
+    let text = coerce_to_string(&ctx.eval_expr(&args[0])?);
+    let Some(ch) = text.chars().next() else {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    };
+    let code = match ch as u32 {
+        c @ (0..=0x7F | 0xA0..=0xFF) => c,
+        _ => CP1252_HIGH
+            .iter()
+            .position(|&c| c == ch)
+            .map_or(63, |i| 0x80 + i as u32),
+    };
+    Ok(CellValue::Number(f64::from(code)))
+}
+
+/// UNICHAR(number) - returns the Unicode character for a code point.
+pub fn fn_unichar(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("UNICHAR", args, 1, 1)?;
+    let code = coerce_to_number(&ctx.eval_expr(&args[0])?)?.trunc();
+    match (1.0..=f64::from(u32::MAX))
+        .contains(&code)
+        .then(|| char::from_u32(code as u32))
+        .flatten()
+    {
+        Some(ch) => Ok(CellValue::String(ch.to_string())),
+        None => Ok(CellValue::Error("#VALUE!".to_string())),
+    }
+}
+
+/// UNICODE(text) - returns the code point of the first character of text.
+pub fn fn_unicode(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("UNICODE", args, 1, 1)?;
+    let text = coerce_to_string(&ctx.eval_expr(&args[0])?);
+    match text.chars().next() {
+        Some(ch) => Ok(CellValue::Number(f64::from(ch as u32))),
+        None => Ok(CellValue::Error("#VALUE!".to_string())),
+    }
+}
+
+/// CLEAN(text) - removes the non-printable characters (codes 0-31).
+pub fn fn_clean(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("CLEAN", args, 1, 1)?;
+    let text = coerce_to_string(&ctx.eval_expr(&args[0])?);
+    Ok(CellValue::String(
+        text.chars().filter(|&c| c as u32 >= 32).collect(),
+    ))
+}
+
+/// FIXED(number, [decimals], [no_commas]) - formats a number as text with a
+/// fixed number of decimals, with thousands separators unless `no_commas`.
+pub fn fn_fixed(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("FIXED", args, 1, 3)?;
+    let number = coerce_to_number(&ctx.eval_expr(&args[0])?)?;
+    let decimals = opt_number(args, 1, 2.0, ctx)?.trunc();
+    let no_commas = match args.get(2) {
+        Some(arg) => coerce_to_bool(&ctx.eval_expr(arg)?)?,
+        None => false,
+    };
+    if decimals > 127.0 {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    if !number.is_finite() {
+        return Ok(CellValue::Error("#NUM!".to_string()));
+    }
+    let pattern = number_pattern(decimals, !no_commas);
+    Ok(CellValue::String(format_number(
+        round_decimals(number, decimals),
+        &pattern,
+    )))
+}
+
+/// DOLLAR(number, [decimals]) - formats a number as currency text, with
+/// negative amounts in parentheses.
+pub fn fn_dollar(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("DOLLAR", args, 1, 2)?;
+    let number = coerce_to_number(&ctx.eval_expr(&args[0])?)?;
+    let decimals = opt_number(args, 1, 2.0, ctx)?.trunc();
+    if decimals > 127.0 {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    if !number.is_finite() {
+        return Ok(CellValue::Error("#NUM!".to_string()));
+    }
+    let pattern = number_pattern(decimals, true);
+    Ok(CellValue::String(format_number(
+        round_decimals(number, decimals),
+        &format!("\"$\"{pattern};(\"$\"{pattern})"),
+    )))
+}
+
+/// Round half away from zero to `decimals` places, which may be negative.
+fn round_decimals(number: f64, decimals: f64) -> f64 {
+    let factor = 10f64.powi(decimals as i32);
+    let scaled = number * factor;
+    if !scaled.is_finite() {
+        // Numbers this large have no digits to round away.
+        return number;
+    }
+    scaled.round() / factor
+}
+
+/// Build a number format code with `decimals` places (none if negative) and
+/// optional thousands separators.
+fn number_pattern(decimals: f64, grouping: bool) -> String {
+    let mut pattern = if grouping { "#,##0" } else { "0" }.to_string();
+    if decimals > 0.0 {
+        pattern.push('.');
+        pattern.push_str(&"0".repeat(decimals as usize));
+    }
+    pattern
+}
+
+/// NUMBERVALUE(text, [decimal_separator], [group_separator]) - converts
+/// text to a number using the given separators instead of the locale's.
+pub fn fn_numbervalue(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("NUMBERVALUE", args, 1, 3)?;
+    let value = ctx.eval_expr(&args[0])?;
+    if let CellValue::Number(_) | CellValue::Error(_) = value {
+        return Ok(value);
+    }
+    let separator = |index: usize, default: char, ctx: &mut Evaluator| -> Result<Option<char>> {
+        match args.get(index) {
+            Some(arg) => Ok(coerce_to_string(&ctx.eval_expr(arg)?).chars().next()),
+            None => Ok(Some(default)),
+        }
+    };
+    let (Some(decimal_sep), Some(group_sep)) = (separator(1, '.', ctx)?, separator(2, ',', ctx)?)
+    else {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    };
+    let value_error = || Ok(CellValue::Error("#VALUE!".to_string()));
+    let text: String = coerce_to_string(&value)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let trimmed = text.trim_end_matches('%');
+    let percents = (text.len() - trimmed.len()) as i32;
+    if text.is_empty() {
+        return Ok(CellValue::Number(0.0));
+    }
+    let mut normalized = String::with_capacity(trimmed.len());
+    let mut seen_decimal = false;
+    for c in trimmed.chars() {
+        if c == decimal_sep {
+            if seen_decimal {
+                return value_error();
+            }
+            seen_decimal = true;
+            normalized.push('.');
+        } else if c == group_sep {
+            if seen_decimal {
+                return value_error();
+            }
+        } else {
+            normalized.push(c);
+        }
+    }
+    match normalized.parse::<f64>() {
+        Ok(n) if n.is_finite() => Ok(CellValue::Number(n / 100f64.powi(percents))),
+        _ if normalized.is_empty() && percents == 0 => Ok(CellValue::Number(0.0)),
+        _ => value_error(),
+    }
+}
+
+/// VALUETOTEXT(value, [format]) - returns the text of a value. In the strict
+/// format (1) text is quoted so that it can be parsed back.
+pub fn fn_valuetotext(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("VALUETOTEXT", args, 1, 2)?;
+    let value = ctx.eval_expr(&args[0])?;
+    let Some(strict) = text_format(args, 1, ctx)? else {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    };
+    Ok(CellValue::String(value_text(&value, strict)))
+}
+
+/// ARRAYTOTEXT(array, [format]) - returns the text of an array: its values
+/// separated by commas in the concise format (0), or as an array constant
+/// such as `{1,"a";2,"b"}` in the strict format (1).
+pub fn fn_arraytotext(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("ARRAYTOTEXT", args, 1, 2)?;
+    let array = ctx.eval_array(&args[0])?;
+    let Some(strict) = text_format(args, 1, ctx)? else {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    };
+    let text = if strict {
+        let rows: Vec<String> = array
+            .values
+            .chunks(array.cols)
+            .map(|row| {
+                row.iter()
+                    .map(|v| value_text(v, true))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect();
+        format!("{{{}}}", rows.join(";"))
+    } else {
+        array
+            .values
+            .iter()
+            .map(|v| value_text(v, false))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    Ok(CellValue::String(text))
+}
+
+/// Evaluate the optional format argument of VALUETOTEXT and ARRAYTOTEXT:
+/// `Some(false)` for concise, `Some(true)` for strict, `None` if invalid.
+fn text_format(args: &[Expr], index: usize, ctx: &mut Evaluator) -> Result<Option<bool>> {
+    Ok(match opt_number(args, index, 0.0, ctx)?.trunc() {
+        0.0 => Some(false),
+        1.0 => Some(true),
+        _ => None,
+    })
+}
+
+/// Render a value as text; strict text is quoted with inner quotes doubled.
+fn value_text(value: &CellValue, strict: bool) -> String {
+    match value {
+        CellValue::String(s) if strict => format!("\"{}\"", s.replace('"', "\"\"")),
+        other => coerce_to_string(other),
+    }
+}
+
+// -- Byte variants --
+//
+// The byte variants count characters outside ASCII as two bytes, as Excel
+// does under a double-byte (DBCS) language such as Korean, Japanese, or
+// Chinese.
+
+/// Width of a character in bytes.
+fn byte_width(ch: char) -> usize {
+    if ch.is_ascii() {
+        1
+    } else {
+        2
+    }
+}
+
+/// Convert a 1-based byte position to a char index: the first char that
+/// starts at or after it.
+fn char_index_at_byte(chars: &[char], byte_pos: usize) -> usize {
+    let mut bytes = 0;
+    for (i, &ch) in chars.iter().enumerate() {
+        if bytes + 1 >= byte_pos {
+            return i;
+        }
+        bytes += byte_width(ch);
+    }
+    chars.len()
+}
+
+/// Number of bytes in `chars`.
+fn byte_len(chars: &[char]) -> usize {
+    chars.iter().map(|&c| byte_width(c)).sum()
+}
+
+/// Take chars from the front while they fit in `max_bytes`.
+fn take_bytes(chars: &[char], max_bytes: usize) -> usize {
+    let mut bytes = 0;
+    chars
+        .iter()
+        .take_while(|&&c| {
+            bytes += byte_width(c);
+            bytes <= max_bytes
+        })
+        .count()
+}
+
+/// LENB(text) - returns the number of bytes in text.
+pub fn fn_lenb(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("LENB", args, 1, 1)?;
+    let chars: Vec<char> = coerce_to_string(&ctx.eval_expr(&args[0])?)
+        .chars()
+        .collect();
+    Ok(CellValue::Number(byte_len(&chars) as f64))
+}
+
+/// LEFTB(text, [num_bytes]) - returns the leading characters that fit in
+/// `num_bytes`.
+pub fn fn_leftb(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("LEFTB", args, 1, 2)?;
+    let chars: Vec<char> = coerce_to_string(&ctx.eval_expr(&args[0])?)
+        .chars()
+        .collect();
+    let n = opt_number(args, 1, 1.0, ctx)?;
+    if n < 0.0 {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    let count = take_bytes(&chars, n as usize);
+    Ok(CellValue::String(chars[..count].iter().collect()))
+}
+
+/// RIGHTB(text, [num_bytes]) - returns the trailing characters that fit in
+/// `num_bytes`.
+pub fn fn_rightb(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("RIGHTB", args, 1, 2)?;
+    let mut chars: Vec<char> = coerce_to_string(&ctx.eval_expr(&args[0])?)
+        .chars()
+        .collect();
+    let n = opt_number(args, 1, 1.0, ctx)?;
+    if n < 0.0 {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    chars.reverse();
+    let count = take_bytes(&chars, n as usize);
+    Ok(CellValue::String(chars[..count].iter().rev().collect()))
+}
+
+/// MIDB(text, start_num, num_bytes) - returns the characters that fit in
+/// `num_bytes` starting at byte `start_num`.
+pub fn fn_midb(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("MIDB", args, 3, 3)?;
+    let chars: Vec<char> = coerce_to_string(&ctx.eval_expr(&args[0])?)
+        .chars()
+        .collect();
+    let start = coerce_to_number(&ctx.eval_expr(&args[1])?)?;
+    let n = coerce_to_number(&ctx.eval_expr(&args[2])?)?;
+    if start < 1.0 || n < 0.0 {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    let from = char_index_at_byte(&chars, start as usize);
+    let count = take_bytes(&chars[from..], n as usize);
+    Ok(CellValue::String(
+        chars[from..from + count].iter().collect(),
+    ))
+}
+
+/// REPLACEB(old_text, start_num, num_bytes, new_text) - replaces the
+/// characters in a byte range.
+pub fn fn_replaceb(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    check_arg_count("REPLACEB", args, 4, 4)?;
+    let chars: Vec<char> = coerce_to_string(&ctx.eval_expr(&args[0])?)
+        .chars()
+        .collect();
+    let start = coerce_to_number(&ctx.eval_expr(&args[1])?)?;
+    let n = coerce_to_number(&ctx.eval_expr(&args[2])?)?;
+    let new_text = coerce_to_string(&ctx.eval_expr(&args[3])?);
+    if start < 1.0 || n < 0.0 {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    let from = char_index_at_byte(&chars, start as usize);
+    let to = from + take_bytes(&chars[from..], n as usize);
+    let mut result: String = chars[..from].iter().collect();
+    result.push_str(&new_text);
+    result.extend(&chars[to..]);
+    Ok(CellValue::String(result))
+}
+
+/// FINDB(find_text, within_text, [start_num]) - case-sensitive search that
+/// returns a byte position.
+pub fn fn_findb(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    find_bytes("FINDB", args, ctx, false)
+}
+
+/// SEARCHB(find_text, within_text, [start_num]) - case-insensitive search
+/// with wildcards that returns a byte position.
+pub fn fn_searchb(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+    find_bytes("SEARCHB", args, ctx, true)
+}
+
+/// Shared implementation of FINDB and SEARCHB.
+fn find_bytes(name: &str, args: &[Expr], ctx: &mut Evaluator, search: bool) -> Result<CellValue> {
+    check_arg_count(name, args, 2, 3)?;
+    let mut find_text = coerce_to_string(&ctx.eval_expr(&args[0])?);
+    let mut within_text = coerce_to_string(&ctx.eval_expr(&args[1])?);
+    if search {
+        find_text = find_text.to_lowercase();
+        within_text = within_text.to_lowercase();
+    }
+    let chars: Vec<char> = within_text.chars().collect();
+    let start = opt_number(args, 2, 1.0, ctx)?;
+    if start < 1.0 || start as usize > byte_len(&chars) + 1 {
+        return Ok(CellValue::Error("#VALUE!".to_string()));
+    }
+    let from = char_index_at_byte(&chars, start as usize);
+    let wildcard = search && (find_text.contains('*') || find_text.contains('?'));
+    let found = (from..=chars.len()).find(|&i| {
+        let rest: String = chars[i..].iter().collect();
+        if wildcard {
+            wildcard_match_prefix(&find_text, &rest)
+        } else {
+            rest.starts_with(&find_text)
+        }
+    });
+    match found {
+        Some(i) => Ok(CellValue::Number((byte_len(&chars[..i]) + 1) as f64)),
+        None => Ok(CellValue::Error("#VALUE!".to_string())),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -216,6 +857,15 @@ mod tests {
         evaluate(&expr, &snap).unwrap()
     }
 
+    fn eval_with_data(formula: &str, snap: &CellSnapshot) -> CellValue {
+        let expr = parse_formula(formula).unwrap();
+        evaluate(&expr, snap).unwrap()
+    }
+
+    fn text(s: &str) -> CellValue {
+        CellValue::String(s.to_string())
+    }
+
     #[test]
     fn test_concat() {
         assert_eq!(
@@ -314,4 +964,237 @@ mod tests {
             CellValue::String("Hello World".to_string())
         );
     }
+
+    // TEXT / LEN tests
+
+    #[test]
+    fn test_text_format_codes() {
+        assert_eq!(eval(r##"TEXT(1234.567,"#,##0.00")"##), text("1,234.57"));
+        assert_eq!(eval(r#"TEXT(0.256,"0.0%")"#), text("25.6%"));
+        assert_eq!(eval(r#"TEXT(45000,"yyyy-mm-dd")"#), text("2023-03-15"));
+        assert_eq!(eval(r#"TEXT(-5,"0;(0)")"#), text("(5)"));
+        assert_eq!(eval(r#"TEXT("42","000")"#), text("042"));
+    }
+
+    #[test]
+    fn test_text_formats_of_non_finite_numbers() {
+        let num_error = CellValue::Error("#NUM!".to_string());
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        snap.set_cell("Sheet1", 1, 1, CellValue::Number(f64::INFINITY));
+        snap.set_cell("Sheet1", 1, 2, CellValue::Number(f64::NAN));
+        assert_eq!(eval_with_data(r#"TEXT(A1,"0.00")"#, &snap), num_error);
+        assert_eq!(eval_with_data(r##"TEXT(A2,"#,##0")"##, &snap), num_error);
+        assert_eq!(eval_with_data("FIXED(A1,2)", &snap), num_error);
+        assert_eq!(eval_with_data("DOLLAR(-A1)", &snap), num_error);
+        // Rounding very large numbers must not overflow to infinity.
+        assert!(matches!(eval("FIXED(10^307,2)"), CellValue::String(s) if s.ends_with(".00")));
+    }
+
+    #[test]
+    fn test_text_with_text_value() {
+        assert_eq!(eval(r#"TEXT("abc","0.00")"#), text("abc"));
+        assert_eq!(eval(r#"TEXT("abc","0;-0;0;""<""@"">""")"#), text("<abc>"));
+    }
+
+    #[test]
+    fn test_len_counts_characters() {
+        assert_eq!(eval(r#"LEN("한글")"#), CellValue::Number(2.0));
+    }
+
+    // TEXTJOIN / TEXTBEFORE / TEXTAFTER / TEXTSPLIT tests
+
+    #[test]
+    fn test_textjoin() {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        snap.set_cell("Sheet1", 1, 1, text("a"));
+        snap.set_cell("Sheet1", 1, 3, text("c"));
+        snap.set_cell("Sheet1", 1, 4, CellValue::Number(4.0));
+        assert_eq!(
+            eval_with_data(r#"TEXTJOIN(", ",TRUE,A1:A4,"e")"#, &snap),
+            text("a, c, 4, e")
+        );
+        assert_eq!(
+            eval_with_data(r#"TEXTJOIN("-",FALSE,A1:A4)"#, &snap),
+            text("a--c-4")
+        );
+    }
+
+    #[test]
+    fn test_textjoin_propagates_errors() {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        snap.set_cell("Sheet1", 1, 1, CellValue::Error("#DIV/0!".to_string()));
+        assert_eq!(
+            eval_with_data(r#"TEXTJOIN(",",TRUE,"x",A1)"#, &snap),
+            CellValue::Error("#DIV/0!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_textbefore_textafter() {
+        assert_eq!(eval(r#"TEXTBEFORE("a-b-c","-")"#), text("a"));
+        assert_eq!(eval(r#"TEXTBEFORE("a-b-c","-",2)"#), text("a-b"));
+        assert_eq!(eval(r#"TEXTBEFORE("a-b-c","-",-1)"#), text("a-b"));
+        assert_eq!(eval(r#"TEXTAFTER("a-b-c","-")"#), text("b-c"));
+        assert_eq!(eval(r#"TEXTAFTER("a-b-c","-",-1)"#), text("c"));
+        assert_eq!(eval(r#"TEXTAFTER("Red X Blue","x",1,1)"#), text(" Blue"));
+        assert_eq!(
+            eval(r#"TEXTAFTER("Red X Blue","x")"#),
+            CellValue::Error("#N/A".to_string())
+        );
+        assert_eq!(eval(r#"TEXTAFTER("a-b","-",2,0,1)"#), text(""));
+        assert_eq!(eval(r#"TEXTBEFORE("abc","/",1,0,0,"none")"#), text("none"));
+        assert_eq!(
+            eval(r#"TEXTBEFORE("abc","b",0)"#),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_textsplit() {
+        let snap = CellSnapshot::new("Sheet1".to_string());
+        let expr = parse_formula(r#"TEXTSPLIT("a,b;c","," ,";")"#).unwrap();
+        let array = crate::formula::eval::Evaluator::new(&snap)
+            .eval_array(&expr)
+            .unwrap();
+        assert_eq!((array.rows, array.cols), (2, 2));
+        assert_eq!(
+            array.values,
+            vec![
+                text("a"),
+                text("b"),
+                text("c"),
+                CellValue::Error("#N/A".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_textsplit_ignore_empty_and_pad() {
+        let snap = CellSnapshot::new("Sheet1".to_string());
+        let expr = parse_formula(r#"TEXTSPLIT("a,,b;c",",",";",TRUE,0,"-")"#).unwrap();
+        let array = crate::formula::eval::Evaluator::new(&snap)
+            .eval_array(&expr)
+            .unwrap();
+        assert_eq!((array.rows, array.cols), (2, 2));
+        assert_eq!(
+            array.values,
+            vec![text("a"), text("b"), text("c"), text("-")]
+        );
+
+        let expr = parse_formula(r#"TEXTSPLIT("a,,b",",",,TRUE)"#).unwrap();
+        let array = crate::formula::eval::Evaluator::new(&snap)
+            .eval_array(&expr)
+            .unwrap();
+        assert_eq!(array.values, vec![text("a"), text("b")]);
+    }
+
+    // CHAR / CODE / UNICHAR / UNICODE / CLEAN tests
+
+    #[test]
+    fn test_char_code() {
+        assert_eq!(eval("CHAR(65)"), text("A"));
+        assert_eq!(eval("CHAR(128)"), text("\u{20AC}"));
+        assert_eq!(eval("CHAR(0)"), CellValue::Error("#VALUE!".to_string()));
+        assert_eq!(eval(r#"CODE("Apple")"#), CellValue::Number(65.0));
+        assert_eq!(eval("CODE(CHAR(150))"), CellValue::Number(150.0));
+        assert_eq!(eval(r#"CODE("")"#), CellValue::Error("#VALUE!".to_string()));
+    }
+
+    #[test]
+    fn test_unichar_unicode() {
+        assert_eq!(eval("UNICHAR(54620)"), text("한"));
+        assert_eq!(eval(r#"UNICODE("한글")"#), CellValue::Number(54620.0));
+        assert_eq!(eval("UNICHAR(0)"), CellValue::Error("#VALUE!".to_string()));
+    }
+
+    #[test]
+    fn test_clean() {
+        assert_eq!(eval(r#"CLEAN("a"&CHAR(9)&"b"&CHAR(10))"#), text("ab"));
+    }
+
+    // FIXED / DOLLAR / NUMBERVALUE tests
+
+    #[test]
+    fn test_fixed() {
+        assert_eq!(eval("FIXED(1234.567)"), text("1,234.57"));
+        assert_eq!(eval("FIXED(1234.567,1,TRUE)"), text("1234.6"));
+        assert_eq!(eval("FIXED(1234.567,-1)"), text("1,230"));
+        assert_eq!(eval("FIXED(-1234.567,0)"), text("-1,235"));
+    }
+
+    #[test]
+    fn test_dollar() {
+        assert_eq!(eval("DOLLAR(1234.567)"), text("$1,234.57"));
+        assert_eq!(eval("DOLLAR(-1234.567,-2)"), text("($1,200)"));
+        assert_eq!(eval("DOLLAR(0.125,1)"), text("$0.1"));
+    }
+
+    #[test]
+    fn test_numbervalue() {
+        assert_eq!(
+            eval(r#"NUMBERVALUE("2.500,27",",",".")"#),
+            CellValue::Number(2500.27)
+        );
+        assert_eq!(eval(r#"NUMBERVALUE("3.5%")"#), CellValue::Number(0.035));
+        assert_eq!(eval(r#"NUMBERVALUE(" 1 000 ")"#), CellValue::Number(1000.0));
+        assert_eq!(eval(r#"NUMBERVALUE("")"#), CellValue::Number(0.0));
+        assert_eq!(
+            eval(r#"NUMBERVALUE("1.2.3")"#),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
+
+    // VALUETOTEXT / ARRAYTOTEXT tests
+
+    #[test]
+    fn test_valuetotext() {
+        assert_eq!(eval(r#"VALUETOTEXT("a""b")"#), text("a\"b"));
+        assert_eq!(eval(r#"VALUETOTEXT("a""b",1)"#), text("\"a\"\"b\""));
+        assert_eq!(eval("VALUETOTEXT(TRUE,1)"), text("TRUE"));
+        assert_eq!(eval("VALUETOTEXT(1.5)"), text("1.5"));
+    }
+
+    #[test]
+    fn test_arraytotext() {
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        snap.set_cell("Sheet1", 1, 1, CellValue::Number(1.0));
+        snap.set_cell("Sheet1", 2, 1, text("a"));
+        snap.set_cell("Sheet1", 1, 2, CellValue::Bool(true));
+        snap.set_cell("Sheet1", 2, 2, CellValue::Number(2.5));
+        assert_eq!(
+            eval_with_data("ARRAYTOTEXT(A1:B2)", &snap),
+            text("1, a, TRUE, 2.5")
+        );
+        assert_eq!(
+            eval_with_data("ARRAYTOTEXT(A1:B2,1)", &snap),
+            text("{1,\"a\";TRUE,2.5}")
+        );
+    }
+
+    // Byte variant tests
+
+    #[test]
+    fn test_lenb_leftb_rightb() {
+        assert_eq!(eval(r#"LENB("ab한글")"#), CellValue::Number(6.0));
+        assert_eq!(eval(r#"LEFTB("ab한글",4)"#), text("ab한"));
+        assert_eq!(eval(r#"LEFTB("ab한글",3)"#), text("ab"));
+        assert_eq!(eval(r#"RIGHTB("ab한글",2)"#), text("글"));
+        assert_eq!(eval(r#"LENB("abc")"#), CellValue::Number(3.0));
+    }
+
+    #[test]
+    fn test_midb_replaceb() {
+        assert_eq!(eval(r#"MIDB("ab한글c",3,4)"#), text("한글"));
+        assert_eq!(eval(r#"REPLACEB("ab한글c",3,2,"X")"#), text("abX글c"));
+    }
+
+    #[test]
+    fn test_findb_searchb() {
+        assert_eq!(eval(r#"FINDB("글","한글")"#), CellValue::Number(3.0));
+        assert_eq!(eval(r#"SEARCHB("B?","한a글bc")"#), CellValue::Number(6.0));
+        assert_eq!(
+            eval(r#"FINDB("z","한글")"#),
+            CellValue::Error("#VALUE!".to_string())
+        );
+    }
 }
@@ -1,16 +1,23 @@
 //! Excel formula parser and evaluation engine.
 //!
-//! Parses formula strings into an AST representation and evaluates them
-//! against cell data.
+//! Parses formula strings into an AST representation, evaluates them
+//! against cell data, and prints them back as formula text.
 
 pub mod ast;
 pub mod eval;
 pub mod functions;
 pub mod parser;
+pub mod printer;
+pub mod registry;
+pub mod visit;
 
-pub use ast::{BinaryOperator, CellReference, Expr, UnaryOperator};
+pub use ast::{BinaryOperator, CellReference, Expr, StructuredReference, TableItem, UnaryOperator};
 pub use eval::{
-    build_dependency_graph, evaluate, topological_sort, CellCoord, CellDataProvider, CellSnapshot,
-    Evaluator,
+    build_dependency_graph, evaluate, is_array_expr, strongly_connected_components,
+    topological_sort, ArrayValue, CellCoord, CellDataProvider, CellSnapshot, DependencyGraph,
+    EvalStep, Evaluator, RefArea, TableRange,
 };
 pub use parser::parse_formula;
+pub use printer::format_formula;
+pub use registry::{CustomFunction, FunctionRegistry};
+pub use visit::{fold_children, walk_expr, Fold, Visit};
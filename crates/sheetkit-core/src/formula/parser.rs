@@ -9,20 +9,29 @@
 //! 4. Multiplication / Division (*, /)
 //! 5. Exponentiation (^)
 //! 6. Unary (+, -, %)
-//! 7. Primary (literals, references, functions, parens)
+//! 7. Reference operators: intersection (space), then range (:)
+//! 8. Primary (literals, references, functions, parens)
+//!
+//! The union operator (,) is only recognized inside parentheses, such as
+//! `SUM((A1:A3,C1:C3))`, where it cannot be confused with an argument
+//! separator.
 
 use nom::{
     branch::alt,
     bytes::complete::{tag, tag_no_case, take_while1},
-    character::complete::{alpha1, char, multispace0},
-    combinator::{map, opt, recognize, value},
-    multi::{many0, separated_list0},
+    character::complete::{alpha1, char, multispace0, multispace1},
+    combinator::{map, map_opt, opt, recognize, value},
+    multi::{many0, separated_list0, separated_list1},
     sequence::{delimited, pair, preceded, terminated},
     IResult,
 };
 
-use super::ast::{BinaryOperator, CellReference, Expr, UnaryOperator};
+use super::ast::{
+    BinaryOperator, CellReference, Expr, StructuredReference, TableItem, UnaryOperator,
+};
 use crate::error::{Error, Result};
+use crate::utils::cell_ref::column_name_to_number;
+use crate::utils::constants::MAX_ROWS;
 
 /// Parse an Excel formula string into an AST expression.
 ///
@@ -141,8 +150,8 @@ fn parse_unary(input: &str) -> IResult<&str, Expr> {
             },
         ));
     }
-    // Otherwise parse primary and check for postfix %
-    let (input, expr) = parse_primary(input)?;
+    // Otherwise parse a reference expression and check for postfix %
+    let (input, expr) = parse_reference_expr(input)?;
     let (input, pcts) = many0(ws(value(UnaryOperator::Percent, tag("%"))))(input)?;
     let result = pcts.into_iter().fold(expr, |acc, op| Expr::UnaryOp {
         op,
@@ -151,16 +160,52 @@ fn parse_unary(input: &str) -> IResult<&str, Expr> {
     Ok((input, result))
 }
 
+/// Reference intersection: range expressions separated by whitespace, such
+/// as `B1:D4 C2:C9`.
+fn parse_reference_expr(input: &str) -> IResult<&str, Expr> {
+    let (input, left) = parse_range_expr(parse_primary, input)?;
+    let (input, rest) = many0(pair(
+        value(BinaryOperator::Intersect, multispace1),
+        |input| parse_range_expr(parse_reference_operand, input),
+    ))(input)?;
+    Ok((input, fold_binary(left, rest)))
+}
+
+/// Range operator between references that are not plain cell ranges, such
+/// as `A1:INDEX(B1:B10, 3)`. `first` parses the left-most operand.
+fn parse_range_expr(first: fn(&str) -> IResult<&str, Expr>, input: &str) -> IResult<&str, Expr> {
+    let (input, left) = first(input)?;
+    let (input, rest) = many0(pair(
+        value(BinaryOperator::Range, char(':')),
+        parse_reference_operand,
+    ))(input)?;
+    Ok((input, fold_binary(left, rest)))
+}
+
+/// Operands of reference operators: expressions that may yield a reference.
+fn parse_reference_operand(input: &str) -> IResult<&str, Expr> {
+    alt((
+        parse_paren_expr,
+        parse_function_call,
+        parse_cell_ref_or_range,
+        parse_structured_ref,
+        parse_defined_name,
+    ))(input)
+}
+
 /// Primary expressions: literals, references, function calls, parenthesized expressions.
 fn parse_primary(input: &str) -> IResult<&str, Expr> {
     let input = input.trim_start();
     alt((
         parse_paren_expr,
+        parse_array_constant,
         parse_string_literal,
         parse_error_literal,
         parse_bool_literal,
         parse_function_call,
         parse_cell_ref_or_range,
+        parse_structured_ref,
+        parse_defined_name,
         parse_number_literal,
     ))(input)
 }
@@ -177,6 +222,44 @@ fn parse_number_literal(input: &str) -> IResult<&str, Expr> {
     Ok((input, Expr::Number(n)))
 }
 
+/// Parse an array constant such as `{1,2;3,4}`: rows separated by `;` and
+/// elements by `,`. Elements are numbers, strings, booleans or errors, and
+/// every row must have the same number of elements.
+fn parse_array_constant(input: &str) -> IResult<&str, Expr> {
+    let (rest, rows) = delimited(
+        char('{'),
+        separated_list1(
+            ws(char(';')),
+            separated_list1(ws(char(',')), ws(parse_array_element)),
+        ),
+        char('}'),
+    )(input)?;
+    if rows.iter().any(|row| row.len() != rows[0].len()) {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Verify,
+        )));
+    }
+    Ok((rest, Expr::Array(rows)))
+}
+
+/// Parse one element of an array constant. Signed numbers are folded into
+/// the literal.
+fn parse_array_element(input: &str) -> IResult<&str, Expr> {
+    alt((
+        map(
+            pair(opt(alt((char('-'), char('+')))), parse_number_literal),
+            |(sign, number)| match number {
+                Expr::Number(n) if sign == Some('-') => Expr::Number(-n),
+                number => number,
+            },
+        ),
+        parse_string_literal,
+        parse_bool_literal,
+        parse_error_literal,
+    ))(input)
+}
+
 /// Parse a string literal with `"` delimiters. `""` is an escaped quote (Excel convention).
 fn parse_string_literal(input: &str) -> IResult<&str, Expr> {
     let (input, _) = tag("\"")(input)?;
@@ -246,55 +329,143 @@ fn parse_error_literal(input: &str) -> IResult<&str, Expr> {
         tag("#NUM!"),
         tag("#NULL!"),
         tag("#N/A"),
+        tag("#SPILL!"),
+        tag("#CALC!"),
     ))(input)?;
     Ok((input, Expr::Error(err.to_string())))
 }
 
-/// Parse a cell reference (possibly with a sheet prefix) or a range (A1:B10).
+/// Parse a cell reference or a range (A1:B10, A:C, 1:3), optionally
+/// prefixed with a sheet name or a 3D sheet span (Jan:Dec!B5). A cell
+/// followed by `#` is a spill reference (E1#).
 fn parse_cell_ref_or_range(input: &str) -> IResult<&str, Expr> {
-    let (input, first) = parse_single_cell_ref(input)?;
-    // Check for range operator ':'
-    if let Ok((input, _)) = tag::<&str, &str, nom::error::Error<&str>>(":")(input) {
-        let (input, second) = parse_single_cell_ref(input)?;
-        Ok((
+    if let Ok((input, (first_sheet, last_sheet))) = parse_sheet_span_prefix(input) {
+        let (input, (start, end)) = parse_area(input)?;
+        let end = end.unwrap_or_else(|| start.clone());
+        return Ok((
             input,
-            Expr::Range {
-                start: first,
-                end: second,
+            Expr::Ref3D {
+                first_sheet,
+                last_sheet,
+                start,
+                end,
             },
-        ))
-    } else {
-        Ok((input, Expr::CellRef(first)))
+        ));
+    }
+    let (input, (start, end)) = parse_area(input)?;
+    match end {
+        Some(end) => Ok((input, Expr::Range { start, end })),
+        None => match char::<&str, nom::error::Error<&str>>('#')(input) {
+            Ok((input, _)) => Ok((input, Expr::SpillRef(start))),
+            Err(_) => Ok((input, Expr::CellRef(start))),
+        },
     }
 }
 
-/// Parse a single cell reference, optionally prefixed with a sheet name.
-fn parse_single_cell_ref(input: &str) -> IResult<&str, CellReference> {
-    // Try to parse sheet prefix first
+/// Parse a cell, a cell range, a whole-column range, or a whole-row range.
+/// Returns the start reference and, for ranges, the end reference.
+fn parse_area(input: &str) -> IResult<&str, (CellReference, Option<CellReference>)> {
+    alt((
+        map(parse_column_range, |(start, end)| (start, Some(end))),
+        map(parse_row_range, |(start, end)| (start, Some(end))),
+        pair(
+            parse_single_cell_ref,
+            opt(preceded(char(':'), parse_single_cell_ref)),
+        ),
+    ))(input)
+}
+
+/// Parse a whole-column range such as `A:C` or `Sheet1!$B:$B`. Rows span
+/// the whole sheet and are marked absolute.
+fn parse_column_range(input: &str) -> IResult<&str, (CellReference, CellReference)> {
+    let (input, sheet) = opt(parse_sheet_prefix)(input)?;
+    let (input, (abs_start, start_col)) = parse_column_letters(input)?;
+    let (input, _) = char(':')(input)?;
+    let (input, (abs_end, end_col)) = parse_column_letters(input)?;
+    let (input, _) = not_name_char(input)?;
+    let bound = |col: String, row: u32, abs_col: bool, sheet: Option<String>| CellReference {
+        col,
+        row,
+        abs_col,
+        abs_row: true,
+        sheet,
+    };
+    Ok((
+        input,
+        (
+            bound(start_col, 1, abs_start, sheet),
+            bound(end_col, MAX_ROWS, abs_end, None),
+        ),
+    ))
+}
+
+/// Parse a whole-row range such as `2:5` or `Sheet1!$3:$3`. Columns span
+/// the whole sheet and are marked absolute.
+fn parse_row_range(input: &str) -> IResult<&str, (CellReference, CellReference)> {
     let (input, sheet) = opt(parse_sheet_prefix)(input)?;
-    // Parse optional $ for abs col
-    let (input, abs_col) = map(opt(tag("$")), |o| o.is_some())(input)?;
-    // Parse column letters
+    let (input, (abs_start, start_row)) = parse_row_number(input)?;
+    let (input, _) = char(':')(input)?;
+    let (input, (abs_end, end_row)) = parse_row_number(input)?;
+    let (input, _) = not_name_char(input)?;
+    let bound = |col: &str, row: u32, abs_row: bool, sheet: Option<String>| CellReference {
+        col: col.to_string(),
+        row,
+        abs_col: true,
+        abs_row,
+        sheet,
+    };
+    Ok((
+        input,
+        (
+            bound("A", start_row, abs_start, sheet),
+            bound("XFD", end_row, abs_end, None),
+        ),
+    ))
+}
+
+/// Parse column letters with an optional `$` marker. The letters must name a
+/// column within XFD, so that names such as `TAXRATE1` are not mistaken for
+/// cell references.
+fn parse_column_letters(input: &str) -> IResult<&str, (bool, String)> {
+    let (input, abs) = map(opt(tag("$")), |o| o.is_some())(input)?;
     let (input, col) = alpha1(input)?;
-    // Make sure column letters are A-Z only
     let col_upper = col.to_uppercase();
-    if !col_upper.chars().all(|c| c.is_ascii_uppercase()) {
+    if !col_upper.chars().all(|c| c.is_ascii_uppercase())
+        || column_name_to_number(&col_upper).is_err()
+    {
         return Err(nom::Err::Error(nom::error::Error::new(
             input,
             nom::error::ErrorKind::Alpha,
         )));
     }
-    // Parse optional $ for abs row
-    let (input, abs_row) = map(opt(tag("$")), |o| o.is_some())(input)?;
-    // Parse row digits
-    let (input, row_str) = take_while1(|c: char| c.is_ascii_digit())(input)?;
-    let row: u32 = row_str.parse().map_err(|_| {
-        nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Digit))
-    })?;
+    Ok((input, (abs, col_upper)))
+}
+
+/// Parse a row number within 1..=1048576 with an optional `$` marker.
+fn parse_row_number(input: &str) -> IResult<&str, (bool, u32)> {
+    let (input, abs) = map(opt(tag("$")), |o| o.is_some())(input)?;
+    let (rest, row_str) = take_while1(|c: char| c.is_ascii_digit())(input)?;
+    match row_str.parse::<u32>() {
+        Ok(row) if (1..=MAX_ROWS).contains(&row) => Ok((rest, (abs, row))),
+        _ => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Digit,
+        ))),
+    }
+}
+
+/// Parse a single cell reference, optionally prefixed with a sheet name.
+fn parse_single_cell_ref(input: &str) -> IResult<&str, CellReference> {
+    // Try to parse sheet prefix first
+    let (input, sheet) = opt(parse_sheet_prefix)(input)?;
+    let (input, (abs_col, col)) = parse_column_letters(input)?;
+    let (input, (abs_row, row)) = parse_row_number(input)?;
+    // A reference cannot be followed by name characters (`Q1_Sales`).
+    let (input, _) = not_name_char(input)?;
     Ok((
         input,
         CellReference {
-            col: col_upper,
+            col,
             row,
             abs_col,
             abs_row,
@@ -303,6 +474,151 @@ fn parse_single_cell_ref(input: &str) -> IResult<&str, CellReference> {
     ))
 }
 
+/// Succeeds if the next character cannot continue a defined name.
+fn not_name_char(input: &str) -> IResult<&str, ()> {
+    match input.chars().next() {
+        Some(c) if c.is_alphanumeric() || c == '_' || c == '.' => Err(nom::Err::Error(
+            nom::error::Error::new(input, nom::error::ErrorKind::Alpha),
+        )),
+        _ => Ok((input, ())),
+    }
+}
+
+/// Parse a name identifier: a letter, underscore, or backslash followed by
+/// letters, digits, underscores, periods, and backslashes.
+fn parse_name_identifier(input: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        take_while1(|c: char| c.is_alphabetic() || c == '_' || c == '\\'),
+        opt(take_while1(|c: char| {
+            c.is_alphanumeric() || c == '_' || c == '.' || c == '\\'
+        })),
+    ))(input)
+}
+
+/// Parse a defined name such as `TaxRate` or `Sheet1!Local_Name`.
+fn parse_defined_name(input: &str) -> IResult<&str, Expr> {
+    let (input, sheet) = opt(parse_sheet_prefix)(input)?;
+    let (input, name) = parse_name_identifier(input)?;
+    Ok((
+        input,
+        Expr::Name {
+            name: name.to_string(),
+            sheet,
+        },
+    ))
+}
+
+/// Parse a structured table reference such as `Sales[Amount]`,
+/// `Sales[[#Totals],[Amount]]`, `Sales[[Qty]:[Price]]`, or `[@Qty]`.
+fn parse_structured_ref(input: &str) -> IResult<&str, Expr> {
+    let (input, table) = opt(parse_name_identifier)(input)?;
+    let (input, _) = char('[')(input)?;
+    let (input, _) = multispace0(input)?;
+    let mut items = Vec::new();
+    let mut columns = None;
+
+    let input = if let Some(rest) = input.strip_prefix('@') {
+        // `[@Col]`, `[@[Col Name]]`, or `[@]`.
+        items.push(TableItem::ThisRow);
+        let (rest, column) = if rest.starts_with('[') {
+            map(parse_bracketed_specifier, Some)(rest)?
+        } else {
+            opt(parse_column_name)(rest)?
+        };
+        columns = column.map(|c| (c.clone(), c));
+        rest
+    } else if input.starts_with('[') {
+        // `[[#Headers],[Col1]:[Col2]]`: comma-separated bracketed parts.
+        let mut rest = input;
+        loop {
+            let (r, spec) = parse_bracketed_specifier(rest)?;
+            if let Some(item) = table_item(&spec) {
+                items.push(item);
+                rest = r;
+            } else {
+                let (r, last) = opt(preceded(char(':'), parse_bracketed_specifier))(r)?;
+                columns = Some((spec.clone(), last.unwrap_or(spec)));
+                rest = r;
+            }
+            match ws(char(','))(rest) {
+                Ok((r, _)) => rest = r,
+                Err(_) => break,
+            }
+        }
+        rest
+    } else {
+        // `[Col]`, `[#All]`, or `[]`.
+        let (rest, spec) = opt(parse_column_name)(input)?;
+        if let Some(spec) = spec {
+            match table_item(&spec) {
+                Some(item) => items.push(item),
+                None => columns = Some((spec.clone(), spec)),
+            }
+        }
+        rest
+    };
+
+    let (input, _) = preceded(multispace0, char(']'))(input)?;
+    Ok((
+        input,
+        Expr::StructuredRef(StructuredReference {
+            table: table.map(str::to_string),
+            items,
+            columns,
+        }),
+    ))
+}
+
+/// Parse `[text]` inside a structured reference, returning the text.
+fn parse_bracketed_specifier(input: &str) -> IResult<&str, String> {
+    let (input, _) = char('[')(input)?;
+    let (input, name) = parse_column_name(input)?;
+    let (input, _) = char(']')(input)?;
+    Ok((input, name))
+}
+
+/// Parse a column name or special item up to the closing `]`. A `'`
+/// escapes the next character, as in `Price'[USD']`.
+fn parse_column_name(input: &str) -> IResult<&str, String> {
+    let mut result = String::new();
+    let mut chars = input.char_indices();
+    let mut end = input.len();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' => {
+                if let Some((_, escaped)) = chars.next() {
+                    result.push(escaped);
+                }
+            }
+            '[' | ']' => {
+                end = i;
+                break;
+            }
+            _ => result.push(c),
+        }
+    }
+    let result = result.trim().to_string();
+    if result.is_empty() {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Tag,
+        )));
+    }
+    Ok((&input[end..], result))
+}
+
+/// Map a special item specifier such as `#Data` to a [`TableItem`].
+fn table_item(spec: &str) -> Option<TableItem> {
+    match spec.to_ascii_uppercase().as_str() {
+        "#ALL" => Some(TableItem::All),
+        "#DATA" => Some(TableItem::Data),
+        "#HEADERS" => Some(TableItem::Headers),
+        "#TOTALS" => Some(TableItem::Totals),
+        "#THIS ROW" => Some(TableItem::ThisRow),
+        _ => None,
+    }
+}
+
 /// Parse a sheet prefix like `Sheet1!` or `'Sheet Name'!`.
 fn parse_sheet_prefix(input: &str) -> IResult<&str, String> {
     alt((parse_quoted_sheet_prefix, parse_unquoted_sheet_prefix))(input)
@@ -310,14 +626,30 @@ fn parse_sheet_prefix(input: &str) -> IResult<&str, String> {
 
 /// Parse an unquoted sheet prefix: `SheetName!`
 fn parse_unquoted_sheet_prefix(input: &str) -> IResult<&str, String> {
-    let (input, name) = terminated(
-        recognize(pair(
-            take_while1(|c: char| c.is_alphanumeric() || c == '_'),
-            many0(take_while1(|c: char| c.is_alphanumeric() || c == '_')),
-        )),
-        tag("!"),
-    )(input)?;
-    Ok((input, name.to_string()))
+    terminated(parse_unquoted_sheet_name, tag("!"))(input)
+}
+
+/// Parse an unquoted sheet name: letters, digits, and underscores.
+fn parse_unquoted_sheet_name(input: &str) -> IResult<&str, String> {
+    map(
+        take_while1(|c: char| c.is_alphanumeric() || c == '_'),
+        str::to_string,
+    )(input)
+}
+
+/// Parse a 3D sheet span prefix like `Jan:Dec!` or `'Jan 2024:Dec 2024'!`.
+/// Sheet names cannot contain `:`, so a quoted prefix with a colon is a span.
+fn parse_sheet_span_prefix(input: &str) -> IResult<&str, (String, String)> {
+    alt((
+        map_opt(parse_quoted_sheet_prefix, |name| {
+            let (first, last) = name.split_once(':')?;
+            Some((first.to_string(), last.to_string()))
+        }),
+        pair(
+            terminated(parse_unquoted_sheet_name, char(':')),
+            parse_unquoted_sheet_prefix,
+        ),
+    ))(input)
 }
 
 /// Parse a quoted sheet prefix: `'Sheet Name'!`
@@ -348,7 +680,8 @@ fn parse_quoted_sheet_prefix(input: &str) -> IResult<&str, String> {
     Ok((remaining, result))
 }
 
-/// Parse a function call: `FUNCNAME(arg1, arg2, ...)`.
+/// Parse a function call: `FUNCNAME(arg1, arg2, ...)`, optionally followed
+/// by calls of its result such as `LAMBDA(x, x + 1)(2)`.
 fn parse_function_call(input: &str) -> IResult<&str, Expr> {
     // Function name: letters, digits, underscores, dots (e.g., _xlfn.CONCAT)
     let (input, name) = recognize(pair(
@@ -361,16 +694,36 @@ fn parse_function_call(input: &str) -> IResult<&str, Expr> {
     // Must be followed by '('
     let (input, _) = preceded(multispace0, char('('))(input)?;
     let (input, _) = multispace0(input)?;
-    // Parse arguments separated by commas
-    let (input, args) = separated_list0(ws(char(',')), parse_expr)(input)?;
+    let (input, args) = parse_args(input)?;
     let (input, _) = preceded(multispace0, char(')'))(input)?;
-    Ok((
-        input,
-        Expr::Function {
-            name: name.to_uppercase(),
-            args,
-        },
-    ))
+    let (input, calls) = many0(delimited(
+        pair(char('('), multispace0),
+        parse_args,
+        preceded(multispace0, char(')')),
+    ))(input)?;
+    let function = Expr::Function {
+        name: name.to_uppercase(),
+        args,
+    };
+    let expr = calls.into_iter().fold(function, |callee, args| Expr::Call {
+        callee: Box::new(callee),
+        args,
+    });
+    Ok((input, expr))
+}
+
+/// Parse function arguments separated by commas. An omitted argument, as in
+/// `IF(A1,,1)`, parses as [`Expr::Missing`]; `F()` has no arguments.
+fn parse_args(input: &str) -> IResult<&str, Vec<Expr>> {
+    let (input, args) = separated_list0(ws(char(',')), opt(parse_expr))(input)?;
+    if let [None] = args.as_slice() {
+        return Ok((input, Vec::new()));
+    }
+    let args = args
+        .into_iter()
+        .map(|arg| arg.unwrap_or(Expr::Missing))
+        .collect();
+    Ok((input, args))
 }
 
 /// Parse a parenthesized expression.
@@ -378,8 +731,13 @@ fn parse_paren_expr(input: &str) -> IResult<&str, Expr> {
     let (input, _) = char('(')(input)?;
     let (input, _) = multispace0(input)?;
     let (input, expr) = parse_expr(input)?;
+    // A comma inside parentheses is the union operator: `(A1:A3,C1:C3)`.
+    let (input, rest) = many0(pair(
+        ws(value(BinaryOperator::Union, char(','))),
+        parse_expr,
+    ))(input)?;
     let (input, _) = preceded(multispace0, char(')'))(input)?;
-    Ok((input, Expr::Paren(Box::new(expr))))
+    Ok((input, Expr::Paren(Box::new(fold_binary(expr, rest)))))
 }
 
 /// Fold a left-associative chain of binary operations into a single AST node.
@@ -626,6 +984,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_function_omitted_args() {
+        let result = parse_formula("IF(A1>0, ,1)").unwrap();
+        let Expr::Function { args, .. } = result else {
+            panic!("expected function, got {result:?}");
+        };
+        assert_eq!(args.len(), 3);
+        assert_eq!(args[1], Expr::Missing);
+        assert_eq!(args[2], Expr::Number(1.0));
+
+        let result = parse_formula("XMATCH(3,A1:A5,,)").unwrap();
+        let Expr::Function { args, .. } = result else {
+            panic!("expected function, got {result:?}");
+        };
+        assert_eq!(args[2..], [Expr::Missing, Expr::Missing]);
+    }
+
     #[test]
     fn test_parse_function_one_arg() {
         let result = parse_formula("ABS(-5)").unwrap();
@@ -701,6 +1076,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_call_of_function_result() {
+        let result = parse_formula("LAMBDA(x,x+1)(2)").unwrap();
+        match result {
+            Expr::Call { callee, args } => {
+                assert!(matches!(*callee, Expr::Function { ref name, .. } if name == "LAMBDA"));
+                assert_eq!(args, vec![Expr::Number(2.0)]);
+            }
+            other => panic!("expected a call, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_parse_equal() {
         let result = parse_formula("A1=B1").unwrap();
@@ -869,4 +1256,272 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_parse_whole_column_and_row_ranges() {
+        let cell = |col: &str, row: u32, abs_col: bool, abs_row: bool, sheet: Option<&str>| {
+            CellReference {
+                col: col.to_string(),
+                row,
+                abs_col,
+                abs_row,
+                sheet: sheet.map(str::to_string),
+            }
+        };
+        assert_eq!(
+            parse_formula("A:C").unwrap(),
+            Expr::Range {
+                start: cell("A", 1, false, true, None),
+                end: cell("C", 1_048_576, false, true, None),
+            }
+        );
+        assert_eq!(
+            parse_formula("Sheet2!$B:$B").unwrap(),
+            Expr::Range {
+                start: cell("B", 1, true, true, Some("Sheet2")),
+                end: cell("B", 1_048_576, true, true, None),
+            }
+        );
+        assert_eq!(
+            parse_formula("SUM(2:$5)").unwrap(),
+            Expr::Function {
+                name: "SUM".to_string(),
+                args: vec![Expr::Range {
+                    start: cell("A", 2, true, false, None),
+                    end: cell("XFD", 5, true, true, None),
+                }],
+            }
+        );
+        assert!(parse_formula("0:1").is_err());
+    }
+
+    #[test]
+    fn test_parse_3d_and_quoted_sheet_refs() {
+        let cell = |col: &str, row: u32, sheet: Option<&str>| CellReference {
+            col: col.to_string(),
+            row,
+            abs_col: false,
+            abs_row: false,
+            sheet: sheet.map(str::to_string),
+        };
+        assert_eq!(
+            parse_formula("Jan:Dec!B5").unwrap(),
+            Expr::Ref3D {
+                first_sheet: "Jan".to_string(),
+                last_sheet: "Dec".to_string(),
+                start: cell("B", 5, None),
+                end: cell("B", 5, None),
+            }
+        );
+        assert_eq!(
+            parse_formula("'Jan 2024:Dec 2024'!A1:B2").unwrap(),
+            Expr::Ref3D {
+                first_sheet: "Jan 2024".to_string(),
+                last_sheet: "Dec 2024".to_string(),
+                start: cell("A", 1, None),
+                end: cell("B", 2, None),
+            }
+        );
+        assert_eq!(
+            parse_formula("'Bob''s Data'!A1").unwrap(),
+            Expr::CellRef(cell("A", 1, Some("Bob's Data")))
+        );
+        assert_eq!(
+            parse_formula("'My Sheet'!A1:B2").unwrap(),
+            Expr::Range {
+                start: cell("A", 1, Some("My Sheet")),
+                end: cell("B", 2, None),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_reference_operators() {
+        let cell = |col: &str, row: u32| {
+            Expr::CellRef(CellReference {
+                col: col.to_string(),
+                row,
+                abs_col: false,
+                abs_row: false,
+                sheet: None,
+            })
+        };
+        let binary = |op, left, right| Expr::BinaryOp {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        };
+        assert_eq!(
+            parse_formula("A1 B2").unwrap(),
+            binary(BinaryOperator::Intersect, cell("A", 1), cell("B", 2))
+        );
+        assert_eq!(
+            parse_formula("(A1,B2, C3)").unwrap(),
+            Expr::Paren(Box::new(binary(
+                BinaryOperator::Union,
+                binary(BinaryOperator::Union, cell("A", 1), cell("B", 2)),
+                cell("C", 3)
+            )))
+        );
+        let index = Expr::Function {
+            name: "INDEX".to_string(),
+            args: vec![cell("B", 1), Expr::Number(1.0)],
+        };
+        assert_eq!(
+            parse_formula("A1:INDEX(B1,1)").unwrap(),
+            binary(BinaryOperator::Range, cell("A", 1), index)
+        );
+        // Intersection binds tighter than arithmetic operators.
+        assert_eq!(
+            parse_formula("-A1 B2").unwrap(),
+            Expr::UnaryOp {
+                op: UnaryOperator::Neg,
+                operand: Box::new(binary(
+                    BinaryOperator::Intersect,
+                    cell("A", 1),
+                    cell("B", 2)
+                )),
+            }
+        );
+        // Whitespace around other operators is not an intersection.
+        assert_eq!(
+            parse_formula("A1 + B2").unwrap(),
+            binary(BinaryOperator::Add, cell("A", 1), cell("B", 2))
+        );
+        assert!(matches!(
+            parse_formula("SUM(A1 , B2 )").unwrap(),
+            Expr::Function { ref args, .. } if args.len() == 2
+        ));
+    }
+
+    #[test]
+    fn test_parse_defined_name() {
+        let name = |name: &str, sheet: Option<&str>| Expr::Name {
+            name: name.to_string(),
+            sheet: sheet.map(str::to_string),
+        };
+        assert_eq!(parse_formula("TaxRate").unwrap(), name("TaxRate", None));
+        assert_eq!(
+            parse_formula("Sales_2024").unwrap(),
+            name("Sales_2024", None)
+        );
+        // Letters beyond column XFD or trailing name characters make a name.
+        assert_eq!(parse_formula("TAXRATE1").unwrap(), name("TAXRATE1", None));
+        assert_eq!(parse_formula("Q1_Sales").unwrap(), name("Q1_Sales", None));
+        assert_eq!(
+            parse_formula("'My Sheet'!Local.Name").unwrap(),
+            name("Local.Name", Some("My Sheet"))
+        );
+        assert!(matches!(parse_formula("XFD1").unwrap(), Expr::CellRef(_)));
+    }
+
+    #[test]
+    fn test_parse_structured_ref() {
+        let sref = |table: Option<&str>, items: Vec<TableItem>, cols: Option<(&str, &str)>| {
+            Expr::StructuredRef(StructuredReference {
+                table: table.map(str::to_string),
+                items,
+                columns: cols.map(|(a, b)| (a.to_string(), b.to_string())),
+            })
+        };
+        assert_eq!(
+            parse_formula("Sales[Amount]").unwrap(),
+            sref(Some("Sales"), vec![], Some(("Amount", "Amount")))
+        );
+        assert_eq!(
+            parse_formula("Sales[[#Totals],[Amount]]").unwrap(),
+            sref(
+                Some("Sales"),
+                vec![TableItem::Totals],
+                Some(("Amount", "Amount"))
+            )
+        );
+        assert_eq!(
+            parse_formula("Sales[[#Headers],[Qty]:[Unit Price]]").unwrap(),
+            sref(
+                Some("Sales"),
+                vec![TableItem::Headers],
+                Some(("Qty", "Unit Price"))
+            )
+        );
+        assert_eq!(
+            parse_formula("Sales[#All]").unwrap(),
+            sref(Some("Sales"), vec![TableItem::All], None)
+        );
+        assert_eq!(
+            parse_formula("Sales[]").unwrap(),
+            sref(Some("Sales"), vec![], None)
+        );
+        assert_eq!(
+            parse_formula("[@Qty]*[@[Unit Price]]").unwrap(),
+            Expr::BinaryOp {
+                op: BinaryOperator::Mul,
+                left: Box::new(sref(None, vec![TableItem::ThisRow], Some(("Qty", "Qty")))),
+                right: Box::new(sref(
+                    None,
+                    vec![TableItem::ThisRow],
+                    Some(("Unit Price", "Unit Price"))
+                )),
+            }
+        );
+        assert_eq!(
+            parse_formula("Sales['#Count]").unwrap(),
+            sref(Some("Sales"), vec![], Some(("#Count", "#Count")))
+        );
+    }
+
+    #[test]
+    fn test_parse_spill_ref() {
+        let cell = |sheet: Option<&str>| CellReference {
+            col: "E".to_string(),
+            row: 1,
+            abs_col: false,
+            abs_row: false,
+            sheet: sheet.map(str::to_string),
+        };
+        assert_eq!(
+            parse_formula("SUM(E1#)").unwrap(),
+            Expr::Function {
+                name: "SUM".to_string(),
+                args: vec![Expr::SpillRef(cell(None))],
+            }
+        );
+        assert_eq!(
+            parse_formula("Sheet2!E1#").unwrap(),
+            Expr::SpillRef(cell(Some("Sheet2")))
+        );
+        // Error literals are unaffected.
+        assert_eq!(
+            parse_formula("#REF!").unwrap(),
+            Expr::Error("#REF!".to_string())
+        );
+        assert!(parse_formula("A1:B2#").is_err());
+    }
+
+    #[test]
+    fn test_parse_array_constant() {
+        assert_eq!(
+            parse_formula("{1,2;3,4}").unwrap(),
+            Expr::Array(vec![
+                vec![Expr::Number(1.0), Expr::Number(2.0)],
+                vec![Expr::Number(3.0), Expr::Number(4.0)],
+            ])
+        );
+        assert_eq!(
+            parse_formula("SORT({ -1.5, \"a\", TRUE, #N/A })").unwrap(),
+            Expr::Function {
+                name: "SORT".to_string(),
+                args: vec![Expr::Array(vec![vec![
+                    Expr::Number(-1.5),
+                    Expr::String("a".to_string()),
+                    Expr::Bool(true),
+                    Expr::Error("#N/A".to_string()),
+                ]])],
+            }
+        );
+        // Rows of different lengths and non-literal elements are rejected.
+        assert!(parse_formula("{1,2;3}").is_err());
+        assert!(parse_formula("{A1,2}").is_err());
+        assert!(parse_formula("{}").is_err());
+    }
 }
@@ -0,0 +1,645 @@
+//! Formula printer: the inverse of [`parse_formula`](super::parse_formula).
+//!
+//! [`format_formula`] turns an [`Expr`] back into formula text in the form
+//! Excel stores it, without the leading `=`: no spaces around operators or
+//! after argument separators, sheet names quoted only when they must be,
+//! `$` markers kept, and whole-column and whole-row ranges written as `A:C`
+//! and `2:5`. Function names keep the `_xlfn.` and `_xlws.` prefixes of
+//! the parsed formula, written in lowercase as Excel stores them.
+//!
+//! Parentheses are added where the AST nesting differs from the operator
+//! precedence, so expressions built or rewritten in code print correctly
+//! even without [`Expr::Paren`] nodes.
+
+use std::fmt::{self, Write as _};
+
+use super::ast::{
+    BinaryOperator, CellReference, Expr, StructuredReference, TableItem, UnaryOperator,
+};
+use crate::utils::cell_ref::column_name_to_number;
+use crate::utils::constants::{MAX_COLUMNS, MAX_ROWS};
+
+/// Precedence of comparison operators, the loosest binding.
+const PREC_COMPARISON: u8 = 1;
+/// Precedence of `&`.
+const PREC_CONCAT: u8 = 2;
+/// Precedence of `+` and `-`.
+const PREC_ADDITIVE: u8 = 3;
+/// Precedence of `*` and `/`.
+const PREC_MULTIPLICATIVE: u8 = 4;
+/// Precedence of `^`.
+const PREC_POWER: u8 = 5;
+/// Precedence of prefix `-` and `+`.
+const PREC_PREFIX: u8 = 6;
+/// Precedence of postfix `%`.
+const PREC_PERCENT: u8 = 7;
+/// Precedence of the intersection operator (a space).
+const PREC_INTERSECT: u8 = 8;
+/// Precedence of the range operator `:`.
+const PREC_RANGE: u8 = 9;
+/// Precedence of literals, references, calls, and parenthesized expressions.
+const PREC_PRIMARY: u8 = 10;
+
+/// Format an expression as formula text, without the leading `=`.
+///
+/// Formulas parsed with [`parse_formula`](super::parse_formula) print in
+/// Excel's canonical form, so `sum( a1 , 'Sheet1'!B2 )` becomes
+/// `SUM(A1,Sheet1!B2)`.
+pub fn format_formula(expr: &Expr) -> String {
+    expr.to_string()
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "{n}"),
+            Expr::String(s) => {
+                f.write_char('"')?;
+                f.write_str(&s.replace('"', "\"\""))?;
+                f.write_char('"')
+            }
+            Expr::Bool(b) => f.write_str(if *b { "TRUE" } else { "FALSE" }),
+            Expr::Error(e) => f.write_str(e),
+            Expr::Missing => Ok(()),
+            Expr::CellRef(cell) => write!(f, "{cell}"),
+            Expr::SpillRef(cell) => write!(f, "{cell}#"),
+            Expr::Array(rows) => {
+                f.write_char('{')?;
+                for (i, row) in rows.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(';')?;
+                    }
+                    for (j, element) in row.iter().enumerate() {
+                        if j > 0 {
+                            f.write_char(',')?;
+                        }
+                        write!(f, "{element}")?;
+                    }
+                }
+                f.write_char('}')
+            }
+            Expr::Range { start, end } => {
+                if let Some(sheet) = &start.sheet {
+                    write_sheet_prefix(f, sheet)?;
+                }
+                write_area(f, start, end)
+            }
+            Expr::Ref3D {
+                first_sheet,
+                last_sheet,
+                start,
+                end,
+            } => {
+                if needs_quotes(first_sheet) || needs_quotes(last_sheet) {
+                    write!(
+                        f,
+                        "'{}:{}'!",
+                        first_sheet.replace('\'', "''"),
+                        last_sheet.replace('\'', "''")
+                    )?;
+                } else {
+                    write!(f, "{first_sheet}:{last_sheet}!")?;
+                }
+                if start == end {
+                    write_cell(f, start)
+                } else {
+                    write_area(f, start, end)
+                }
+            }
+            Expr::Name { name, sheet } => {
+                if let Some(sheet) = sheet {
+                    write_sheet_prefix(f, sheet)?;
+                }
+                f.write_str(name)
+            }
+            Expr::StructuredRef(sref) => write!(f, "{sref}"),
+            Expr::Function { name, args } => {
+                write_function_name(f, name)?;
+                write_args(f, args)
+            }
+            Expr::Call { callee, args } => {
+                match callee.as_ref() {
+                    Expr::Function { .. } | Expr::Call { .. } | Expr::Name { .. } => {
+                        write!(f, "{callee}")?
+                    }
+                    other => write!(f, "({other})")?,
+                }
+                write_args(f, args)
+            }
+            Expr::BinaryOp {
+                op: BinaryOperator::Union,
+                ..
+            } => {
+                // The union operator only parses inside parentheses.
+                f.write_char('(')?;
+                write_union(f, self)?;
+                f.write_char(')')
+            }
+            Expr::BinaryOp { op, left, right } => {
+                let prec = binary_precedence(*op);
+                write_operand(f, left, prec)?;
+                f.write_str(binary_symbol(*op))?;
+                write_operand(f, right, prec + 1)
+            }
+            Expr::UnaryOp {
+                op: UnaryOperator::Percent,
+                operand,
+            } => {
+                write_operand(f, operand, PREC_PERCENT)?;
+                f.write_char('%')
+            }
+            Expr::UnaryOp { op, operand } => {
+                f.write_char(if *op == UnaryOperator::Neg { '-' } else { '+' })?;
+                write_operand(f, operand, PREC_PREFIX)
+            }
+            Expr::Paren(inner) => {
+                f.write_char('(')?;
+                match inner.as_ref() {
+                    Expr::BinaryOp {
+                        op: BinaryOperator::Union,
+                        ..
+                    } => write_union(f, inner)?,
+                    other => write!(f, "{other}")?,
+                }
+                f.write_char(')')
+            }
+        }
+    }
+}
+
+impl fmt::Display for CellReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(sheet) = &self.sheet {
+            write_sheet_prefix(f, sheet)?;
+        }
+        write_cell(f, self)
+    }
+}
+
+impl fmt::Display for StructuredReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(table) = &self.table {
+            f.write_str(table)?;
+        }
+        f.write_char('[')?;
+        match (self.items.as_slice(), &self.columns) {
+            ([], None) => {}
+            ([TableItem::ThisRow], None) => f.write_char('@')?,
+            ([TableItem::ThisRow], Some((first, last))) if first == last => {
+                f.write_char('@')?;
+                if is_plain_column_name(first) && !first.contains(' ') {
+                    f.write_str(first)?;
+                } else {
+                    write_bracketed_column(f, first)?;
+                }
+            }
+            ([item], None) => f.write_str(table_item_name(*item))?,
+            ([], Some((first, last))) if first == last && is_plain_column_name(first) => {
+                f.write_str(first)?
+            }
+            (items, columns) => {
+                let mut first_part = true;
+                for item in items {
+                    if !first_part {
+                        f.write_char(',')?;
+                    }
+                    first_part = false;
+                    write!(f, "[{}]", table_item_name(*item))?;
+                }
+                if let Some((first, last)) = columns {
+                    if !first_part {
+                        f.write_char(',')?;
+                    }
+                    write_bracketed_column(f, first)?;
+                    if first != last {
+                        f.write_char(':')?;
+                        write_bracketed_column(f, last)?;
+                    }
+                }
+            }
+        }
+        f.write_char(']')
+    }
+}
+
+/// Write a function name, with the `_xlfn.`, `_xlws.`, and `_xll.` prefixes
+/// in the lowercase form Excel stores.
+fn write_function_name(f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+    let mut rest = name;
+    for prefix in ["_xlfn.", "_xlws.", "_xll."] {
+        if rest
+            .get(..prefix.len())
+            .is_some_and(|p| p.eq_ignore_ascii_case(prefix))
+        {
+            f.write_str(prefix)?;
+            rest = &rest[prefix.len()..];
+        }
+    }
+    f.write_str(rest)
+}
+
+/// Write a function or call argument list in parentheses.
+fn write_args(f: &mut fmt::Formatter<'_>, args: &[Expr]) -> fmt::Result {
+    f.write_char('(')?;
+    for (i, arg) in args.iter().enumerate() {
+        if i > 0 {
+            f.write_char(',')?;
+        }
+        // A union prints in its own parentheses, so it stays one argument.
+        write!(f, "{arg}")?;
+    }
+    f.write_char(')')
+}
+
+/// Write the operands of a chain of union operators, without parentheses.
+fn write_union(f: &mut fmt::Formatter<'_>, expr: &Expr) -> fmt::Result {
+    match expr {
+        Expr::BinaryOp {
+            op: BinaryOperator::Union,
+            left,
+            right,
+        } => {
+            write_union(f, left)?;
+            f.write_char(',')?;
+            // A union nested on the right keeps its parentheses.
+            write!(f, "{right}")
+        }
+        other => write!(f, "{other}"),
+    }
+}
+
+/// Write an operand, in parentheses if it binds more loosely than
+/// `min_prec`.
+fn write_operand(f: &mut fmt::Formatter<'_>, expr: &Expr, min_prec: u8) -> fmt::Result {
+    if precedence(expr) < min_prec {
+        write!(f, "({expr})")
+    } else {
+        write!(f, "{expr}")
+    }
+}
+
+/// Return how tightly an expression binds when printed.
+fn precedence(expr: &Expr) -> u8 {
+    match expr {
+        Expr::BinaryOp { op, .. } => binary_precedence(*op),
+        Expr::UnaryOp {
+            op: UnaryOperator::Percent,
+            ..
+        } => PREC_PERCENT,
+        Expr::UnaryOp { .. } => PREC_PREFIX,
+        // A negative number prints with a leading minus sign.
+        Expr::Number(n) if n.is_sign_negative() => PREC_PREFIX,
+        _ => PREC_PRIMARY,
+    }
+}
+
+fn binary_precedence(op: BinaryOperator) -> u8 {
+    match op {
+        BinaryOperator::Eq
+        | BinaryOperator::Ne
+        | BinaryOperator::Lt
+        | BinaryOperator::Le
+        | BinaryOperator::Gt
+        | BinaryOperator::Ge => PREC_COMPARISON,
+        BinaryOperator::Concat => PREC_CONCAT,
+        BinaryOperator::Add | BinaryOperator::Sub => PREC_ADDITIVE,
+        BinaryOperator::Mul | BinaryOperator::Div => PREC_MULTIPLICATIVE,
+        BinaryOperator::Pow => PREC_POWER,
+        BinaryOperator::Intersect => PREC_INTERSECT,
+        BinaryOperator::Range => PREC_RANGE,
+        // Printed in its own parentheses.
+        BinaryOperator::Union => PREC_PRIMARY,
+    }
+}
+
+fn binary_symbol(op: BinaryOperator) -> &'static str {
+    match op {
+        BinaryOperator::Add => "+",
+        BinaryOperator::Sub => "-",
+        BinaryOperator::Mul => "*",
+        BinaryOperator::Div => "/",
+        BinaryOperator::Pow => "^",
+        BinaryOperator::Concat => "&",
+        BinaryOperator::Eq => "=",
+        BinaryOperator::Ne => "<>",
+        BinaryOperator::Lt => "<",
+        BinaryOperator::Le => "<=",
+        BinaryOperator::Gt => ">",
+        BinaryOperator::Ge => ">=",
+        BinaryOperator::Range => ":",
+        BinaryOperator::Intersect => " ",
+        BinaryOperator::Union => ",",
+    }
+}
+
+/// Write a cell reference without its sheet name.
+fn write_cell(f: &mut fmt::Formatter<'_>, cell: &CellReference) -> fmt::Result {
+    write_col(f, cell)?;
+    write_row(f, cell)
+}
+
+fn write_col(f: &mut fmt::Formatter<'_>, cell: &CellReference) -> fmt::Result {
+    if cell.abs_col {
+        f.write_char('$')?;
+    }
+    f.write_str(&cell.col)
+}
+
+fn write_row(f: &mut fmt::Formatter<'_>, cell: &CellReference) -> fmt::Result {
+    if cell.abs_row {
+        f.write_char('$')?;
+    }
+    write!(f, "{}", cell.row)
+}
+
+/// Write a range without the sheet name of its start. Ranges spanning all
+/// columns are written as rows (`2:5`), and ranges spanning all rows as
+/// columns (`A:C`).
+fn write_area(
+    f: &mut fmt::Formatter<'_>,
+    start: &CellReference,
+    end: &CellReference,
+) -> fmt::Result {
+    let all_columns = column_name_to_number(&start.col).ok() == Some(1)
+        && column_name_to_number(&end.col).ok() == Some(MAX_COLUMNS);
+    let all_rows = start.row == 1 && end.row == MAX_ROWS;
+    if let Some(sheet) = &end.sheet {
+        if start.sheet.as_ref() != Some(sheet) {
+            write_cell(f, start)?;
+            f.write_char(':')?;
+            return write!(f, "{end}");
+        }
+    }
+    if all_columns {
+        write_row(f, start)?;
+        f.write_char(':')?;
+        write_row(f, end)
+    } else if all_rows {
+        write_col(f, start)?;
+        f.write_char(':')?;
+        write_col(f, end)
+    } else {
+        write_cell(f, start)?;
+        f.write_char(':')?;
+        write_cell(f, end)
+    }
+}
+
+/// Write `Sheet!` or `'Sheet name'!`.
+fn write_sheet_prefix(f: &mut fmt::Formatter<'_>, sheet: &str) -> fmt::Result {
+    if needs_quotes(sheet) {
+        write!(f, "'{}'!", sheet.replace('\'', "''"))
+    } else {
+        write!(f, "{sheet}!")
+    }
+}
+
+/// Return `true` if a sheet name must be quoted in a formula: it is empty,
+/// contains characters other than letters, digits, and underscores, starts
+/// with a digit, or reads as an A1 or R1C1 cell reference.
+fn needs_quotes(sheet: &str) -> bool {
+    let Some(first) = sheet.chars().next() else {
+        return true;
+    };
+    first.is_ascii_digit()
+        || !sheet.chars().all(|c| c.is_alphanumeric() || c == '_')
+        || is_a1_reference(sheet)
+        || is_r1c1_reference(sheet)
+}
+
+/// Return `true` for names such as `A1` or `xfd10`.
+fn is_a1_reference(name: &str) -> bool {
+    let digits_at = name
+        .find(|c: char| c.is_ascii_digit())
+        .unwrap_or(name.len());
+    let (letters, digits) = name.split_at(digits_at);
+    !letters.is_empty()
+        && letters.chars().all(|c| c.is_ascii_alphabetic())
+        && column_name_to_number(&letters.to_ascii_uppercase()).is_ok()
+        && !digits.is_empty()
+        && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Return `true` for names such as `R`, `C`, `RC`, or `R1C2`.
+fn is_r1c1_reference(name: &str) -> bool {
+    let upper = name.to_ascii_uppercase();
+    let rest = match upper.strip_prefix('R') {
+        Some(rest) => rest.trim_start_matches(|c: char| c.is_ascii_digit()),
+        None => upper.as_str(),
+    };
+    let rest = match rest.strip_prefix('C') {
+        Some(rest) => rest.trim_start_matches(|c: char| c.is_ascii_digit()),
+        None => rest,
+    };
+    rest.is_empty() && !upper.is_empty() && (upper.starts_with('R') || upper.starts_with('C'))
+}
+
+fn table_item_name(item: TableItem) -> &'static str {
+    match item {
+        TableItem::All => "#All",
+        TableItem::Data => "#Data",
+        TableItem::Headers => "#Headers",
+        TableItem::Totals => "#Totals",
+        TableItem::ThisRow => "#This Row",
+    }
+}
+
+/// Return `true` if a column name can be written without its own brackets,
+/// as in `Sales[Amount]`.
+fn is_plain_column_name(name: &str) -> bool {
+    name.chars()
+        .all(|c| c.is_alphanumeric() || c == '_' || c == ' ')
+        && name.chars().next().is_some_and(|c| c != ' ')
+}
+
+/// Write `[name]`, escaping the characters that end or change the meaning
+/// of a column specifier with `'`.
+fn write_bracketed_column(f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+    f.write_char('[')?;
+    for (i, c) in name.chars().enumerate() {
+        if matches!(c, '[' | ']' | '\'' | '#') || (i == 0 && c == '@') {
+            f.write_char('\'')?;
+        }
+        f.write_char(c)?;
+    }
+    f.write_char(']')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formula::parser::parse_formula;
+
+    /// Parse and print a formula.
+    fn reprint(formula: &str) -> String {
+        format_formula(&parse_formula(formula).unwrap())
+    }
+
+    fn cell(col: &str, row: u32) -> Box<Expr> {
+        Box::new(Expr::CellRef(CellReference {
+            col: col.to_string(),
+            row,
+            abs_col: false,
+            abs_row: false,
+            sheet: None,
+        }))
+    }
+
+    #[test]
+    fn test_print_canonical_form() {
+        assert_eq!(reprint("sum( a1 , $B$2:c$3 )"), "SUM(A1,$B$2:C$3)");
+        assert_eq!(reprint("1 + 2 * 3 - -4%"), "1+2*3--4%");
+        assert_eq!(reprint("(1+2)*3"), "(1+2)*3");
+        assert_eq!(
+            reprint("IF(A1>=10,\"say \"\"hi\"\"\",FALSE)"),
+            "IF(A1>=10,\"say \"\"hi\"\"\",FALSE)"
+        );
+        assert_eq!(reprint("#N/A"), "#N/A");
+        assert_eq!(reprint("0.5&\"x\"<>\"y\""), "0.5&\"x\"<>\"y\"");
+        assert_eq!(
+            reprint("_xlfn.XLOOKUP(1,A1:A3,B1:B3)"),
+            "_xlfn.XLOOKUP(1,A1:A3,B1:B3)"
+        );
+        assert_eq!(
+            reprint("_xlfn.LAMBDA(_xlpm.x,_xlpm.x+1)(2)"),
+            "_xlfn.LAMBDA(_xlpm.x,_xlpm.x+1)(2)"
+        );
+    }
+
+    #[test]
+    fn test_print_sheet_names() {
+        assert_eq!(reprint("Sheet1!A1"), "Sheet1!A1");
+        assert_eq!(reprint("'Sheet1'!A1"), "Sheet1!A1");
+        assert_eq!(reprint("'My Sheet'!A1:B2"), "'My Sheet'!A1:B2");
+        assert_eq!(reprint("'Bob''s'!A1"), "'Bob''s'!A1");
+        assert_eq!(reprint("'2024'!A1"), "'2024'!A1");
+        assert_eq!(reprint("'A1'!B2"), "'A1'!B2");
+        assert_eq!(reprint("'R1C1'!B2"), "'R1C1'!B2");
+        assert_eq!(reprint("Sheet1!Local_Name"), "Sheet1!Local_Name");
+        assert_eq!(reprint("SUM(Jan:Dec!B5)"), "SUM(Jan:Dec!B5)");
+        assert_eq!(
+            reprint("SUM('Jan 2024:Dec 2024'!A1:C3)"),
+            "SUM('Jan 2024:Dec 2024'!A1:C3)"
+        );
+    }
+
+    #[test]
+    fn test_print_whole_columns_and_rows() {
+        assert_eq!(reprint("SUM(A:A)"), "SUM(A:A)");
+        assert_eq!(reprint("SUM(Sheet1!$B:C)"), "SUM(Sheet1!$B:C)");
+        assert_eq!(reprint("SUM(2:$5)"), "SUM(2:$5)");
+        assert_eq!(reprint("SUM(A1:A1048576)"), "SUM(A:A)");
+        assert_eq!(reprint("SUM(Jan:Dec!C:C)"), "SUM(Jan:Dec!C:C)");
+    }
+
+    #[test]
+    fn test_print_reference_operators() {
+        assert_eq!(reprint("SUM(B1:D4 C2:C9)"), "SUM(B1:D4 C2:C9)");
+        assert_eq!(reprint("SUM((A1:A3,C1:C3))"), "SUM((A1:A3,C1:C3))");
+        assert_eq!(
+            reprint("SUM(A1:INDEX(B1:B10, 3))"),
+            "SUM(A1:INDEX(B1:B10,3))"
+        );
+    }
+
+    #[test]
+    fn test_print_spill_refs_and_array_constants() {
+        assert_eq!(reprint("SUM(E1#)"), "SUM(E1#)");
+        assert_eq!(reprint("'My Sheet'!$E$1#*2"), "'My Sheet'!$E$1#*2");
+        assert_eq!(reprint("{ 1 , 2 ; 3 , -4 }"), "{1,2;3,-4}");
+        assert_eq!(
+            reprint("SORT({\"b\",\"a\";TRUE,#N/A})"),
+            "SORT({\"b\",\"a\";TRUE,#N/A})"
+        );
+    }
+
+    #[test]
+    fn test_print_structured_references() {
+        for formula in [
+            "Sales[Amount]",
+            "Sales[]",
+            "Sales[#Totals]",
+            "Sales[[#Totals],[Amount]]",
+            "Sales[[Qty]:[Price]]",
+            "Sales[[#Headers],[#Data],[Qty]:[Price]]",
+            "[@Qty]",
+            "[@[Unit Price]]",
+            "Sales[@]",
+            "Sales[[Price'[USD']]]",
+        ] {
+            assert_eq!(reprint(formula), formula);
+        }
+        assert_eq!(reprint("Sales[[#This Row],[Qty]]"), "Sales[@Qty]");
+        assert_eq!(reprint("Sales[ Amount ]"), "Sales[Amount]");
+    }
+
+    #[test]
+    fn test_print_adds_needed_parentheses() {
+        // (1+2)*3 built without a Paren node.
+        let expr = Expr::BinaryOp {
+            op: BinaryOperator::Mul,
+            left: Box::new(Expr::BinaryOp {
+                op: BinaryOperator::Add,
+                left: cell("A", 1),
+                right: cell("B", 1),
+            }),
+            right: Box::new(Expr::Number(3.0)),
+        };
+        assert_eq!(format_formula(&expr), "(A1+B1)*3");
+
+        // A1-(B1-C1) keeps its grouping; (A1-B1)-C1 needs none.
+        let sub = |left: Box<Expr>, right: Box<Expr>| Expr::BinaryOp {
+            op: BinaryOperator::Sub,
+            left,
+            right,
+        };
+        let right_nested = sub(cell("A", 1), Box::new(sub(cell("B", 1), cell("C", 1))));
+        assert_eq!(format_formula(&right_nested), "A1-(B1-C1)");
+        let left_nested = sub(Box::new(sub(cell("A", 1), cell("B", 1))), cell("C", 1));
+        assert_eq!(format_formula(&left_nested), "A1-B1-C1");
+
+        // (-A1)% and 2^-1.
+        let percent = Expr::UnaryOp {
+            op: UnaryOperator::Percent,
+            operand: Box::new(Expr::UnaryOp {
+                op: UnaryOperator::Neg,
+                operand: cell("A", 1),
+            }),
+        };
+        assert_eq!(format_formula(&percent), "(-A1)%");
+        let pow = Expr::BinaryOp {
+            op: BinaryOperator::Pow,
+            left: Box::new(Expr::Number(2.0)),
+            right: Box::new(Expr::Number(-1.0)),
+        };
+        assert_eq!(format_formula(&pow), "2^-1");
+
+        // A union outside parentheses.
+        let union = Expr::Function {
+            name: "SUM".to_string(),
+            args: vec![Expr::BinaryOp {
+                op: BinaryOperator::Union,
+                left: cell("A", 1),
+                right: cell("C", 3),
+            }],
+        };
+        assert_eq!(format_formula(&union), "SUM((A1,C3))");
+    }
+
+    #[test]
+    fn test_print_round_trips_through_parser() {
+        for formula in [
+            "IF(AND(A1>0,B1<>\"\"),VLOOKUP(A1,'Price List'!$A$2:$C$100,3,FALSE),#N/A)",
+            "-A1^2+B1%*(C1-D1)/E1&\"!\"",
+            "SUMPRODUCT((Sales[Region]=\"East\")*Sales[Amount])",
+            "LET(x,A1*2,y,x+1,x*y)",
+            "SUM(Sheet2:Sheet3!A1:B2,(A1:A3,C1:C3),B1:D4 C2:C9)",
+            "MAP(A1:A3,LAMBDA(v,v*2))",
+            "XLOOKUP(99,A1:A5,B1:B5,,1)",
+        ] {
+            let expr = parse_formula(formula).unwrap();
+            let printed = format_formula(&expr);
+            assert_eq!(parse_formula(&printed).unwrap(), expr, "{printed}");
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Caller-registered formula functions.
+//!
+//! A [`FunctionRegistry`] maps function names to closures with the same
+//! signature as the built-in functions (see
+//! [`FunctionFn`](crate::formula::functions::FunctionFn)): they receive the
+//! unevaluated arguments and the evaluator. Registered functions take
+//! precedence over built-in functions of the same name, so a registry can
+//! add domain functions such as `FXRATE` or replace volatile built-ins such
+//! as `TODAY` and `RAND` with deterministic ones.
+//!
+//! Names bound by LET and LAMBDA parameters take precedence over registered
+//! functions, and registered functions over defined names holding a LAMBDA.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::cell::CellValue;
+use crate::error::{Error, Result};
+use crate::formula::ast::Expr;
+use crate::formula::eval::Evaluator;
+use crate::formula::functions::strip_future_function_prefix;
+
+/// A registered function implementation.
+pub type CustomFunction = Arc<dyn Fn(&[Expr], &mut Evaluator) -> Result<CellValue> + Send + Sync>;
+
+#[derive(Clone)]
+struct Entry {
+    func: CustomFunction,
+    volatile: bool,
+}
+
+/// A table of caller-registered functions, looked up by case-insensitive
+/// name. The `_xlfn.`, `_xlws.`, and `_xll.` prefixes Excel writes in front
+/// of newer built-ins and add-in functions are ignored.
+#[derive(Clone, Default)]
+pub struct FunctionRegistry {
+    functions: HashMap<String, Entry>,
+}
+
+impl fmt::Debug for FunctionRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.names()).finish()
+    }
+}
+
+/// Normalize a function name for lookup.
+fn key(name: &str) -> String {
+    let name = strip_future_function_prefix(name);
+    let name = match name.get(..5) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("_xll.") => &name[5..],
+        _ => name,
+    };
+    name.to_ascii_uppercase()
+}
+
+/// Return `true` if formulas can call a function named `name`: a letter or
+/// underscore followed by letters, digits, underscores, and dots.
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+impl FunctionRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `func` under `name`, replacing any function registered
+    /// under the same name. Formulas calling it are not volatile.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidArgument`] if `name` is not a valid function
+    /// name.
+    pub fn register<F>(&mut self, name: &str, func: F) -> Result<()>
+    where
+        F: Fn(&[Expr], &mut Evaluator) -> Result<CellValue> + Send + Sync + 'static,
+    {
+        self.insert(name, Arc::new(func), false)
+    }
+
+    /// Register `func` under `name` as a volatile function: formulas calling
+    /// it are recalculated by every [`recalculate_dirty`], like formulas
+    /// calling NOW or RAND.
+    ///
+    /// [`recalculate_dirty`]: crate::workbook::Workbook::recalculate_dirty
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidArgument`] if `name` is not a valid function
+    /// name.
+    pub fn register_volatile<F>(&mut self, name: &str, func: F) -> Result<()>
+    where
+        F: Fn(&[Expr], &mut Evaluator) -> Result<CellValue> + Send + Sync + 'static,
+    {
+        self.insert(name, Arc::new(func), true)
+    }
+
+    fn insert(&mut self, name: &str, func: CustomFunction, volatile: bool) -> Result<()> {
+        if !is_valid_name(name) {
+            return Err(Error::InvalidArgument(format!(
+                "invalid function name '{name}'"
+            )));
+        }
+        self.functions.insert(key(name), Entry { func, volatile });
+        Ok(())
+    }
+
+    /// Remove the function registered under `name`. Returns `true` if one
+    /// was registered.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.functions.remove(&key(name)).is_some()
+    }
+
+    /// Return the function registered under `name`.
+    pub fn get(&self, name: &str) -> Option<&CustomFunction> {
+        self.functions.get(&key(name)).map(|entry| &entry.func)
+    }
+
+    /// Return `true` if a function is registered under `name`.
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.contains_key(&key(name))
+    }
+
+    /// Return whether the function registered under `name` is volatile, or
+    /// `None` if no function is registered under `name`.
+    pub fn is_volatile(&self, name: &str) -> Option<bool> {
+        self.functions.get(&key(name)).map(|entry| entry.volatile)
+    }
+
+    /// Return the registered names, uppercase and sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.functions.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Return `true` if no functions are registered.
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formula::eval::{coerce_to_number, evaluate, CellSnapshot};
+    use crate::formula::parser::parse_formula;
+
+    fn eval(formula: &str, snap: &CellSnapshot) -> Result<CellValue> {
+        evaluate(&parse_formula(formula).unwrap(), snap)
+    }
+
+    fn fxrate(args: &[Expr], ctx: &mut Evaluator) -> Result<CellValue> {
+        crate::formula::functions::check_arg_count("FXRATE", args, 2, 2)?;
+        let from = crate::formula::eval::coerce_to_string(&ctx.eval_expr(&args[0])?);
+        let to = crate::formula::eval::coerce_to_string(&ctx.eval_expr(&args[1])?);
+        Ok(match (from.as_str(), to.as_str()) {
+            ("USD", "KRW") => CellValue::Number(1400.0),
+            ("KRW", "USD") => CellValue::Number(1.0 / 1400.0),
+            _ => CellValue::Error("#N/A".to_string()),
+        })
+    }
+
+    #[test]
+    fn test_register_and_call() {
+        let mut functions = FunctionRegistry::new();
+        functions.register("FxRate", fxrate).unwrap();
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        snap.set_cell("Sheet1", 1, 1, CellValue::Number(10.0));
+        snap.set_functions(functions);
+
+        let result = eval("A1*FXRATE(\"USD\",\"KRW\")", &snap).unwrap();
+        assert_eq!(result, CellValue::Number(14000.0));
+        let result = eval("_xll.fxrate(\"USD\",\"EUR\")", &snap).unwrap();
+        assert_eq!(result, CellValue::Error("#N/A".to_string()));
+        assert!(matches!(
+            eval("FXRATE(\"USD\")", &snap),
+            Err(Error::WrongArgCount { .. })
+        ));
+    }
+
+    #[test]
+    fn test_registered_function_overrides_builtin() {
+        let mut functions = FunctionRegistry::new();
+        functions
+            .register("TODAY", |_args: &[Expr], _ctx: &mut Evaluator| {
+                Ok(CellValue::Number(45292.0))
+            })
+            .unwrap();
+        functions
+            .register("DOUBLE", |args: &[Expr], ctx: &mut Evaluator| {
+                let n = coerce_to_number(&ctx.eval_expr(&args[0])?)?;
+                Ok(CellValue::Number(n * 2.0))
+            })
+            .unwrap();
+        let mut snap = CellSnapshot::new("Sheet1".to_string());
+        snap.set_functions(functions.clone());
+
+        assert_eq!(
+            eval("TODAY()+1", &snap).unwrap(),
+            CellValue::Number(45293.0)
+        );
+        assert_eq!(eval("DOUBLE(21)", &snap).unwrap(), CellValue::Number(42.0));
+        // LET names shadow registered functions.
+        assert_eq!(
+            eval("LET(DOUBLE,LAMBDA(x,x*3),DOUBLE(2))", &snap).unwrap(),
+            CellValue::Number(6.0)
+        );
+
+        // An evaluator may also be given a registry directly.
+        let plain = CellSnapshot::new("Sheet1".to_string());
+        let expr = parse_formula("DOUBLE(4)").unwrap();
+        assert!(evaluate(&expr, &plain).is_err());
+        let value = Evaluator::with_functions(&plain, &functions)
+            .eval_expr(&expr)
+            .unwrap();
+        assert_eq!(value, CellValue::Number(8.0));
+    }
+
+    #[test]
+    fn test_registry_names_and_volatility() {
+        let mut functions = FunctionRegistry::new();
+        let constant = |_args: &[Expr], _ctx: &mut Evaluator| Ok(CellValue::Number(1.0));
+        functions.register("_xlfn.Stable", constant).unwrap();
+        functions.register_volatile("TICK", constant).unwrap();
+        assert_eq!(functions.names(), vec!["STABLE", "TICK"]);
+        assert_eq!(functions.is_volatile("stable"), Some(false));
+        assert_eq!(functions.is_volatile("tick"), Some(true));
+        assert_eq!(functions.is_volatile("RAND"), None);
+        assert!(functions.contains("Stable"));
+        assert!(functions.unregister("STABLE"));
+        assert!(!functions.unregister("STABLE"));
+        assert!(matches!(
+            functions.register("1BAD", constant),
+            Err(Error::InvalidArgument(_))
+        ));
+        assert!(functions.register("", constant).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+//! Traversal and transformation of formula ASTs.
+//!
+//! [`Visit`] walks an [`Expr`] by reference and [`Fold`] rebuilds it by
+//! value. Both have one method per kind of node of interest, with default
+//! implementations that recurse into the children through [`walk_expr`] and
+//! [`fold_children`], so an implementation overrides only the methods it
+//! needs. Sheet names in cell references, ranges, 3D references, and
+//! sheet-scoped defined names all pass through `visit_sheet_name` and
+//! `fold_sheet_name`, which makes sheet renames a one-method fold.
+//!
+//! For one-off traversals, [`Expr::walk`] and [`Expr::rewrite`] take a
+//! closure instead of a trait implementation.
+
+use super::ast::{CellReference, Expr};
+
+/// A read-only traversal of an expression tree.
+pub trait Visit {
+    /// Visit an expression. The default visits its children.
+    fn visit_expr(&mut self, expr: &Expr) {
+        walk_expr(self, expr);
+    }
+
+    /// Visit a cell reference, including the corners of ranges and 3D
+    /// references. The default visits its sheet name.
+    fn visit_cell_ref(&mut self, cell: &CellReference) {
+        if let Some(sheet) = &cell.sheet {
+            self.visit_sheet_name(sheet);
+        }
+    }
+
+    /// Visit a sheet name. The default does nothing.
+    fn visit_sheet_name(&mut self, _sheet: &str) {}
+}
+
+/// Visit the children of `expr`: the sheet names and cell references it
+/// holds and its subexpressions.
+pub fn walk_expr<V: Visit + ?Sized>(visitor: &mut V, expr: &Expr) {
+    match expr {
+        Expr::Number(_)
+        | Expr::String(_)
+        | Expr::Bool(_)
+        | Expr::Error(_)
+        | Expr::Missing
+        | Expr::StructuredRef(_) => {}
+        Expr::CellRef(cell) | Expr::SpillRef(cell) => visitor.visit_cell_ref(cell),
+        Expr::Array(rows) => {
+            for element in rows.iter().flatten() {
+                visitor.visit_expr(element);
+            }
+        }
+        Expr::Range { start, end } => {
+            visitor.visit_cell_ref(start);
+            visitor.visit_cell_ref(end);
+        }
+        Expr::Ref3D {
+            first_sheet,
+            last_sheet,
+            start,
+            end,
+        } => {
+            visitor.visit_sheet_name(first_sheet);
+            visitor.visit_sheet_name(last_sheet);
+            visitor.visit_cell_ref(start);
+            visitor.visit_cell_ref(end);
+        }
+        Expr::Name { sheet, .. } => {
+            if let Some(sheet) = sheet {
+                visitor.visit_sheet_name(sheet);
+            }
+        }
+        Expr::Function { args, .. } => {
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        }
+        Expr::Call { callee, args } => {
+            visitor.visit_expr(callee);
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        }
+        Expr::BinaryOp { left, right, .. } => {
+            visitor.visit_expr(left);
+            visitor.visit_expr(right);
+        }
+        Expr::UnaryOp { operand, .. } => visitor.visit_expr(operand),
+        Expr::Paren(inner) => visitor.visit_expr(inner),
+    }
+}
+
+/// A transformation of an expression tree that rebuilds it by value.
+pub trait Fold {
+    /// Fold an expression. The default folds its children.
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        fold_children(self, expr)
+    }
+
+    /// Fold a cell reference, including the corners of ranges and 3D
+    /// references. The default folds its sheet name.
+    fn fold_cell_ref(&mut self, cell: CellReference) -> CellReference {
+        CellReference {
+            sheet: cell.sheet.map(|sheet| self.fold_sheet_name(sheet)),
+            ..cell
+        }
+    }
+
+    /// Fold a sheet name. The default returns it unchanged.
+    fn fold_sheet_name(&mut self, sheet: String) -> String {
+        sheet
+    }
+}
+
+/// Fold the children of `expr`: the sheet names and cell references it
+/// holds and its subexpressions.
+pub fn fold_children<F: Fold + ?Sized>(folder: &mut F, expr: Expr) -> Expr {
+    match expr {
+        Expr::Number(_)
+        | Expr::String(_)
+        | Expr::Bool(_)
+        | Expr::Error(_)
+        | Expr::Missing
+        | Expr::StructuredRef(_) => expr,
+        Expr::CellRef(cell) => Expr::CellRef(folder.fold_cell_ref(cell)),
+        Expr::SpillRef(cell) => Expr::SpillRef(folder.fold_cell_ref(cell)),
+        Expr::Array(rows) => Expr::Array(
+            rows.into_iter()
+                .map(|row| row.into_iter().map(|e| folder.fold_expr(e)).collect())
+                .collect(),
+        ),
+        Expr::Range { start, end } => Expr::Range {
+            start: folder.fold_cell_ref(start),
+            end: folder.fold_cell_ref(end),
+        },
+        Expr::Ref3D {
+            first_sheet,
+            last_sheet,
+            start,
+            end,
+        } => Expr::Ref3D {
+            first_sheet: folder.fold_sheet_name(first_sheet),
+            last_sheet: folder.fold_sheet_name(last_sheet),
+            start: folder.fold_cell_ref(start),
+            end: folder.fold_cell_ref(end),
+        },
+        Expr::Name { name, sheet } => Expr::Name {
+            name,
+            sheet: sheet.map(|sheet| folder.fold_sheet_name(sheet)),
+        },
+        Expr::Function { name, args } => Expr::Function {
+            name,
+            args: args.into_iter().map(|arg| folder.fold_expr(arg)).collect(),
+        },
+        Expr::Call { callee, args } => Expr::Call {
+            callee: Box::new(folder.fold_expr(*callee)),
+            args: args.into_iter().map(|arg| folder.fold_expr(arg)).collect(),
+        },
+        Expr::BinaryOp { op, left, right } => Expr::BinaryOp {
+            op,
+            left: Box::new(folder.fold_expr(*left)),
+            right: Box::new(folder.fold_expr(*right)),
+        },
+        Expr::UnaryOp { op, operand } => Expr::UnaryOp {
+            op,
+            operand: Box::new(folder.fold_expr(*operand)),
+        },
+        Expr::Paren(inner) => Expr::Paren(Box::new(folder.fold_expr(*inner))),
+    }
+}
+
+struct WalkFn<F>(F);
+
+impl<F: FnMut(&Expr)> Visit for WalkFn<F> {
+    fn visit_expr(&mut self, expr: &Expr) {
+        (self.0)(expr);
+        walk_expr(self, expr);
+    }
+}
+
+struct RewriteFn<F>(F);
+
+impl<F: FnMut(Expr) -> Expr> Fold for RewriteFn<F> {
+    fn fold_expr(&mut self, expr: Expr) -> Expr {
+        let expr = fold_children(self, expr);
+        (self.0)(expr)
+    }
+}
+
+impl Expr {
+    /// Call `f` on this expression and every subexpression, parents before
+    /// children.
+    pub fn walk<F: FnMut(&Expr)>(&self, f: F) {
+        WalkFn(f).visit_expr(self);
+    }
+
+    /// Rebuild this expression by calling `f` on every subexpression,
+    /// children before parents, and replacing each with the result.
+    pub fn rewrite<F: FnMut(Expr) -> Expr>(self, f: F) -> Expr {
+        RewriteFn(f).fold_expr(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formula::parser::parse_formula;
+    use crate::formula::printer::format_formula;
+
+    /// Collects every sheet name a formula refers to.
+    #[derive(Default)]
+    struct SheetNames(Vec<String>);
+
+    impl Visit for SheetNames {
+        fn visit_sheet_name(&mut self, sheet: &str) {
+            self.0.push(sheet.to_string());
+        }
+    }
+
+    /// Renames one sheet.
+    struct RenameSheet<'a> {
+        from: &'a str,
+        to: &'a str,
+    }
+
+    impl Fold for RenameSheet<'_> {
+        fn fold_sheet_name(&mut self, sheet: String) -> String {
+            if sheet.eq_ignore_ascii_case(self.from) {
+                self.to.to_string()
+            } else {
+                sheet
+            }
+        }
+    }
+
+    #[test]
+    fn test_visit_sheet_names() {
+        let expr =
+            parse_formula("SUM(Data!A1:B2,Jan:Dec!C3)+'My Sheet'!D4*Data!Rate+LAMBDA(x,x)(A1)")
+                .unwrap();
+        let mut names = SheetNames::default();
+        names.visit_expr(&expr);
+        assert_eq!(names.0, vec!["Data", "Jan", "Dec", "My Sheet", "Data"]);
+    }
+
+    #[test]
+    fn test_fold_renames_sheet() {
+        let expr = parse_formula("SUM(Data!A1:B2,Data:Summary!C3)+data!Rate+Other!A1").unwrap();
+        let renamed = RenameSheet {
+            from: "Data",
+            to: "Raw Data",
+        }
+        .fold_expr(expr);
+        assert_eq!(
+            format_formula(&renamed),
+            "SUM('Raw Data'!A1:B2,'Raw Data:Summary'!C3)+'Raw Data'!Rate+Other!A1"
+        );
+    }
+
+    #[test]
+    fn test_walk_counts_functions() {
+        let expr = parse_formula("IF(SUM(A1:A3)>0,MAX(B1,ROUND(C1,2)),0)").unwrap();
+        let mut functions = Vec::new();
+        expr.walk(|e| {
+            if let Expr::Function { name, .. } = e {
+                functions.push(name.clone());
+            }
+        });
+        assert_eq!(functions, vec!["IF", "SUM", "MAX", "ROUND"]);
+    }
+
+    #[test]
+    fn test_rewrite_replaces_functions_and_references() {
+        let expr = parse_formula("CONCATENATE(A1,\"-\",CONCATENATE(B1,C1))").unwrap();
+        let rewritten = expr.rewrite(|e| match e {
+            Expr::Function { name, args } if name == "CONCATENATE" => Expr::Function {
+                name: "_xlfn.CONCAT".to_string(),
+                args,
+            },
+            Expr::CellRef(mut cell) => {
+                cell.abs_col = true;
+                cell.abs_row = true;
+                Expr::CellRef(cell)
+            }
+            other => other,
+        });
+        assert_eq!(
+            format_formula(&rewritten),
+            "_xlfn.CONCAT($A$1,\"-\",_xlfn.CONCAT($B$1,$C$1))"
+        );
+    }
+}
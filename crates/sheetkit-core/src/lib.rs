@@ -1,7 +1,9 @@
 //! sheetkit-core: Core spreadsheet engine -- reading, writing, and manipulating workbooks.
 
+pub(crate) mod array_formula;
 pub mod cell;
 pub(crate) mod cell_ref_shift;
+pub mod cell_shift;
 pub mod chart;
 pub mod col;
 pub mod comment;
@@ -20,6 +22,7 @@ pub mod numfmt;
 pub mod page_layout;
 pub mod pivot;
 pub mod protection;
+pub mod range_copy;
 pub mod raw_transfer;
 pub mod raw_transfer_v2;
 pub mod raw_transfer_write;
@@ -27,6 +30,7 @@ pub mod render;
 pub mod rich_text;
 pub mod row;
 pub mod shape;
+pub(crate) mod shared_formula;
 pub mod sheet;
 pub mod slicer;
 pub mod sparkline;
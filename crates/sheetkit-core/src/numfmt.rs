@@ -1,12 +1,13 @@
 //! Number format renderer: converts a (value, format_code) pair into display text.
 //!
 //! Supports Excel built-in format IDs 0-49, custom numeric patterns
-//! (`0`, `#`, `,`, `.`, `%`, `E+`), date/time patterns (`y`, `m`, `d`,
-//! `h`, `s`, `AM/PM`), multi-section formats (up to 4 sections separated
-//! by `;`), color codes (`[Red]`, `[Blue]`, etc.), conditional sections
-//! (`[>100]`), text format (`@`), and fraction formats (`# ?/?`).
+//! (`0`, `#`, `?`, `,`, `.`, `%`, `E+`, literals between digits), date/time
+//! patterns (`y`, `m`, `d`, `h`, `s`, `AM/PM`), multi-section formats (up
+//! to 4 sections separated by `;`), color codes (`[Red]`, `[Blue]`, etc.),
+//! conditional sections (`[>100]`), currency symbols (`[$€-407]`), text
+//! format (`@`), and fraction formats (`# ?/?`).
 
-use crate::cell::serial_to_date;
+use crate::cell::DateSystem;
 
 /// Map a built-in number format ID (0-49) to its format code string.
 pub fn builtin_format_code(id: u32) -> Option<&'static str> {
@@ -54,8 +55,23 @@ pub fn builtin_format_code(id: u32) -> Option<&'static str> {
 /// Format a numeric value using the given format code string.
 ///
 /// Returns the formatted display text. For format codes that contain date/time
-/// tokens, the value is interpreted as an Excel serial number.
+/// tokens, the value is interpreted as an Excel serial number in the 1900
+/// date system. Infinite and NaN values, which Excel cannot store, are shown
+/// as `#NUM!`.
 pub fn format_number(value: f64, format_code: &str) -> String {
+    format_number_with_date_system(value, format_code, DateSystem::Date1900)
+}
+
+/// Format a numeric value like [`format_number`], interpreting values shown
+/// with date/time tokens as serial numbers in `date_system`.
+pub fn format_number_with_date_system(
+    value: f64,
+    format_code: &str,
+    date_system: DateSystem,
+) -> String {
+    if !value.is_finite() {
+        return "#NUM!".to_string();
+    }
     if format_code.is_empty() || format_code.eq_ignore_ascii_case("General") {
         return format_general(value);
     }
@@ -84,7 +100,7 @@ pub fn format_number(value: f64, format_code: &str) -> String {
     }
 
     if is_date_time_format(&cleaned) {
-        return format_date_time(effective_value, &cleaned);
+        return format_date_time(effective_value, &cleaned, date_system);
     }
 
     if cleaned.contains('?') && cleaned.contains('/') {
@@ -349,11 +365,16 @@ fn strip_color_and_condition(section: &str) -> (String, Option<String>) {
             } else if parse_condition(&bracket_content).is_some() {
                 // Conditional predicate -- strip from format string
                 // (condition is evaluated during section selection)
-            } else if lower.starts_with("dbnum")
-                || lower.starts_with("$")
-                || lower.starts_with("natnum")
-            {
-                // Locale or special -- skip
+            } else if let Some(currency) = bracket_content.strip_prefix('$') {
+                // Locale and currency such as [$€-407]: keep the symbol
+                let symbol = currency.split('-').next().unwrap_or("");
+                if !symbol.is_empty() {
+                    result.push('"');
+                    result.push_str(symbol);
+                    result.push('"');
+                }
+            } else if lower.starts_with("dbnum") || lower.starts_with("natnum") {
+                // Special -- skip
             } else {
                 // Unknown bracket, preserve
                 result.push('[');
@@ -423,7 +444,7 @@ fn is_date_time_format(format: &str) -> bool {
     false
 }
 
-fn format_date_time(value: f64, format: &str) -> String {
+fn format_date_time(value: f64, format: &str, date_system: DateSystem) -> String {
     let int_part = value.floor() as i64;
     let frac = value.fract().abs();
     let total_seconds = (frac * 86_400.0).round() as u64;
@@ -432,12 +453,15 @@ fn format_date_time(value: f64, format: &str) -> String {
     let seconds = (total_seconds % 60) as u32;
     let subsec_frac = (frac * 86_400.0) - (total_seconds as f64);
 
-    let date_opt = serial_to_date(value);
-    let (year, month, day) = if let Some(date) = date_opt {
-        (date.year() as u32, date.month(), date.day())
-    } else {
-        (1900, 1, 1)
+    let ymd = date_system.serial_to_ymd(value);
+    let (year, month, day) = match ymd {
+        Some((year, month, day)) => (year as u32, month, day),
+        None => (1900, 1, 1),
     };
+    // Count weekdays from serial 0 in the 1900 date system, a Saturday, like
+    // WEEKDAY, so that serials up to the phantom 1900-02-29 match Excel.
+    let weekday =
+        ymd.map(|_| (date_system.to_1900_serial(value).floor() as i64 + 5).rem_euclid(7) as usize);
 
     let has_ampm = {
         let lower = format.to_ascii_lowercase();
@@ -566,14 +590,13 @@ fn format_date_time(value: f64, format: &str) -> String {
                 1 => result.push_str(&format!("{}", day)),
                 2 => result.push_str(&format!("{:02}", day)),
                 3 => {
-                    if let Some(date) = date_opt {
+                    if let Some(wd) = weekday {
                         let day_names = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
-                        let wd = date.weekday().num_days_from_monday() as usize;
                         result.push_str(day_names[wd]);
                     }
                 }
                 _ => {
-                    if let Some(date) = date_opt {
+                    if let Some(wd) = weekday {
                         let day_names = [
                             "Monday",
                             "Tuesday",
@@ -583,7 +606,6 @@ fn format_date_time(value: f64, format: &str) -> String {
                             "Saturday",
                             "Sunday",
                         ];
-                        let wd = date.weekday().num_days_from_monday() as usize;
                         result.push_str(day_names[wd]);
                     }
                 }
@@ -756,164 +778,213 @@ fn count_char(chars: &[char], start: usize, target: char) -> usize {
     count
 }
 
-fn format_numeric(value: f64, format: &str) -> String {
-    let is_negative = value < 0.0;
-    let abs_val = value.abs();
-
-    // Parse the format to understand: digit placeholders, decimal places,
-    // comma grouping, percent, underscore/star padding, and literal text.
-    let has_percent = format_has_unquoted_char(format, '%');
-    let display_val = if has_percent {
-        abs_val * 100.0
-    } else {
-        abs_val
-    };
-
-    // Count decimal places from the format
-    let decimal_places = count_decimal_places(format);
-
-    // Check for thousands separator (comma grouping)
-    let has_comma_grouping = has_thousands_separator(format);
-
-    // Check for trailing commas (divide by 1000 per comma)
-    let trailing_comma_count = count_trailing_commas(format);
-    let display_val = display_val / 1000f64.powi(trailing_comma_count as i32);
-
-    // Round to the number of decimal places
-    let rounded = if decimal_places > 0 {
-        let factor = 10f64.powi(decimal_places as i32);
-        (display_val * factor).round() / factor
-    } else {
-        display_val.round()
-    };
-
-    let int_part = rounded.trunc() as u64;
-    let frac_part =
-        ((rounded - rounded.trunc()).abs() * 10f64.powi(decimal_places as i32)).round() as u64;
-
-    // Format integer part
-    let int_str = format!("{}", int_part);
-    let int_display = if has_comma_grouping {
-        add_thousands_separators(&int_str)
-    } else {
-        int_str.clone()
-    };
+/// One token of a numeric format section.
+#[derive(Debug, Clone, PartialEq)]
+enum NumToken {
+    /// A digit placeholder: `0` (always shown), `#` (shown if significant),
+    /// or `?` (a space if not significant).
+    Digit(char),
+    /// The decimal point.
+    Point,
+    /// Literal output text.
+    Literal(String),
+}
 
-    // Count required integer digits (from '0' placeholders in integer part)
-    let min_int_digits = count_integer_zeros(format);
-    let padded_int = if int_display.len() < min_int_digits && int_part == 0 {
-        let needed = min_int_digits - int_display.len();
-        let mut s = "0".repeat(needed);
-        s.push_str(&int_display);
-        if has_comma_grouping {
-            add_thousands_separators(&s)
+/// Split a numeric format section into digit placeholders, the decimal
+/// point, and literal text. Returns the tokens, whether the integer digits
+/// are grouped by thousands, the number of scaling commas (each divides by
+/// 1000), and the number of percent signs (each multiplies by 100).
+fn tokenize_numeric(format: &str) -> (Vec<NumToken>, bool, usize, usize) {
+    let chars: Vec<char> = format.chars().collect();
+    let mut tokens = Vec::new();
+    let mut grouping = false;
+    let mut scaling = 0;
+    let mut percents = 0;
+    let mut i = 0;
+    let push_literal = |tokens: &mut Vec<NumToken>, text: &str| {
+        if let Some(NumToken::Literal(last)) = tokens.last_mut() {
+            last.push_str(text);
         } else {
-            s
+            tokens.push(NumToken::Literal(text.to_string()));
         }
-    } else {
-        int_display
     };
-
-    // Build the output by walking through the format pattern
-    // For simplicity, output the formatted number with appropriate decorations
-    let mut output = String::with_capacity(format.len() + 10);
-    let chars: Vec<char> = format.chars().collect();
-    let len = chars.len();
-    let mut i = 0;
-    let mut in_quotes = false;
-    let mut number_placed = false;
-
-    while i < len {
+    while i < chars.len() {
         let ch = chars[i];
-
-        if ch == '"' {
-            in_quotes = !in_quotes;
-            i += 1;
-            continue;
-        }
-        if in_quotes {
-            output.push(ch);
-            i += 1;
-            continue;
-        }
-        if ch == '\\' && i + 1 < len {
-            output.push(chars[i + 1]);
-            i += 2;
-            continue;
-        }
-        if ch == '_' && i + 1 < len {
-            output.push(' ');
-            i += 2;
-            continue;
-        }
-        if ch == '*' && i + 1 < len {
-            i += 2;
-            continue;
-        }
-
-        if (ch == '0' || ch == '#' || ch == ',') && !number_placed {
-            // Find the end of the numeric pattern
-            let num_end = find_numeric_end(&chars, i);
-            // Place the formatted number
-            let num_str = if decimal_places > 0 {
-                let frac_str = format!("{:0>width$}", frac_part, width = decimal_places);
-                format!("{}.{}", padded_int, frac_str)
-            } else {
-                padded_int.clone()
-            };
-
-            if is_negative {
-                output.push('-');
+        match ch {
+            '"' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&c| c == '"')
+                    .map_or(chars.len(), |p| i + 1 + p);
+                let text: String = chars[i + 1..end].iter().collect();
+                push_literal(&mut tokens, &text);
+                i = end + 1;
+                continue;
             }
-            output.push_str(&num_str);
-            number_placed = true;
-            i = num_end;
-            continue;
+            '\\' if i + 1 < chars.len() => {
+                push_literal(&mut tokens, &chars[i + 1].to_string());
+                i += 2;
+                continue;
+            }
+            '_' if i + 1 < chars.len() => {
+                push_literal(&mut tokens, " ");
+                i += 2;
+                continue;
+            }
+            '*' if i + 1 < chars.len() => {
+                i += 2;
+                continue;
+            }
+            '0' | '#' | '?' => tokens.push(NumToken::Digit(ch)),
+            '.' if !tokens.contains(&NumToken::Point) => tokens.push(NumToken::Point),
+            ',' => {
+                let digit_before = tokens.iter().any(|t| matches!(t, NumToken::Digit(_)));
+                let digit_after = chars[i + 1..]
+                    .iter()
+                    .take_while(|&&c| c != '.' && c != '"')
+                    .any(|&c| matches!(c, '0' | '#' | '?'));
+                if digit_before && digit_after {
+                    if !tokens.contains(&NumToken::Point) {
+                        grouping = true;
+                    }
+                } else if digit_before {
+                    scaling += 1;
+                } else {
+                    push_literal(&mut tokens, ",");
+                }
+            }
+            '%' => {
+                percents += 1;
+                push_literal(&mut tokens, "%");
+            }
+            _ => push_literal(&mut tokens, &ch.to_string()),
         }
+        i += 1;
+    }
+    (tokens, grouping, scaling, percents)
+}
 
-        if ch == '.' && !number_placed {
-            // This dot is part of the numeric pattern, handle together
-            continue;
+fn format_numeric(value: f64, format: &str) -> String {
+    let (tokens, grouping, scaling, percents) = tokenize_numeric(format);
+    let point = tokens.iter().position(|t| *t == NumToken::Point);
+    let is_int_digit =
+        |i: usize, t: &NumToken| matches!(t, NumToken::Digit(_)) && point.is_none_or(|p| i < p);
+    let int_placeholders: Vec<char> = tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(i, t)| match t {
+            NumToken::Digit(c) if is_int_digit(i, t) => Some(*c),
+            _ => None,
+        })
+        .collect();
+    let frac_placeholders: Vec<char> = tokens
+        .iter()
+        .enumerate()
+        .filter_map(|(i, t)| match t {
+            NumToken::Digit(c) if !is_int_digit(i, t) => Some(*c),
+            _ => None,
+        })
+        .collect();
+
+    // Pure literal sections such as `"-"` show no number at all.
+    if int_placeholders.is_empty() && frac_placeholders.is_empty() {
+        return tokens
+            .iter()
+            .filter_map(|t| match t {
+                NumToken::Literal(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect();
+    }
+
+    let scaled = value.abs() * 100f64.powi(percents as i32) / 1000f64.powi(scaling as i32);
+    let digits = format!("{:.*}", frac_placeholders.len(), scaled);
+    let (int_digits, frac_digits) = digits.split_once('.').unwrap_or((&digits, ""));
+    let int_digits = int_digits.trim_start_matches('0');
+
+    // Integer part: the significant digits, padded on the left for `0` and
+    // `?` placeholders beyond them.
+    let mut int_text: Vec<char> = int_digits.chars().collect();
+    let pad = int_placeholders.len().saturating_sub(int_text.len());
+    let padding: Vec<char> = int_placeholders[..pad]
+        .iter()
+        .filter_map(|&c| match c {
+            '0' => Some('0'),
+            '?' => Some(' '),
+            _ => None,
+        })
+        .collect();
+    int_text.splice(0..0, padding);
+    if grouping {
+        let (spaces, digits): (String, String) = int_text.iter().partition(|c| **c == ' ');
+        int_text = spaces
+            .chars()
+            .chain(add_thousands_separators(&digits).chars())
+            .collect();
+    }
+
+    // Fraction part: trailing zeros are dropped for `#` and blanked for `?`.
+    let mut frac_text: Vec<char> = frac_digits.chars().collect();
+    for (i, &placeholder) in frac_placeholders.iter().enumerate().rev() {
+        if frac_text[i] != '0' || placeholder == '0' {
+            break;
         }
-
-        if ch == '%' {
-            output.push('%');
-            i += 1;
-            continue;
+        if placeholder == '?' {
+            frac_text[i] = ' ';
+        } else {
+            frac_text.pop();
         }
+    }
 
-        if ch == '(' || ch == ')' || ch == '-' || ch == '+' || ch == ' ' || ch == ':' || ch == '/' {
-            output.push(ch);
-            i += 1;
-            continue;
+    // Distribute the integer text over its placeholders from the right; the
+    // leftmost placeholder takes any excess. Grouped digits are written as
+    // one block at the first placeholder.
+    let mut int_slots: Vec<String> = vec![String::new(); int_placeholders.len()];
+    if grouping {
+        if let Some(first) = int_slots.first_mut() {
+            *first = int_text.iter().collect();
         }
-
-        if ch == '0' || ch == '#' || ch == ',' || ch == '.' {
-            i += 1;
-            continue;
+    } else if !int_slots.is_empty() {
+        let mut rest = int_text.as_slice();
+        for slot in int_slots.iter_mut().skip(1).rev() {
+            if let Some((last, head)) = rest.split_last() {
+                *slot = last.to_string();
+                rest = head;
+            }
         }
-
-        output.push(ch);
-        i += 1;
+        int_slots[0] = rest.iter().collect();
+    } else if !int_text.is_empty() {
+        // No integer placeholders: the integer digits lead the number.
+        int_slots.push(int_text.iter().collect());
     }
 
-    // If no number was placed, check whether the format actually has digit placeholders.
-    // Formats like `"-"` (pure literal text) should not emit a number at all.
-    if !number_placed {
-        let has_digit_placeholder = format.chars().any(|c| c == '0' || c == '#');
-        if has_digit_placeholder {
-            if is_negative {
-                output.push('-');
+    let mut output = String::with_capacity(format.len() + 10);
+    if value < 0.0 {
+        output.push('-');
+    }
+    let mut int_index = 0;
+    let mut frac_index = 0;
+    let mut leading = int_placeholders.is_empty();
+    for (i, token) in tokens.iter().enumerate() {
+        if leading && (matches!(token, NumToken::Digit(_)) || *token == NumToken::Point) {
+            output.push_str(&int_slots.concat());
+            leading = false;
+        }
+        match token {
+            NumToken::Literal(s) => output.push_str(s),
+            NumToken::Point => output.push('.'),
+            NumToken::Digit(_) if is_int_digit(i, token) => {
+                output.push_str(&int_slots[int_index]);
+                int_index += 1;
             }
-            if decimal_places > 0 {
-                let frac_str = format!("{:0>width$}", frac_part, width = decimal_places);
-                output.push_str(&format!("{}.{}", padded_int, frac_str));
-            } else {
-                output.push_str(&padded_int);
+            NumToken::Digit(_) => {
+                if let Some(c) = frac_text.get(frac_index) {
+                    output.push(*c);
+                }
+                frac_index += 1;
             }
         }
     }
-
     output
 }
 
@@ -975,113 +1046,6 @@ fn count_decimal_places(format: &str) -> usize {
     count
 }
 
-fn has_thousands_separator(format: &str) -> bool {
-    let mut in_quotes = false;
-    let mut prev_backslash = false;
-    let chars: Vec<char> = format.chars().collect();
-
-    for (i, &ch) in chars.iter().enumerate() {
-        if prev_backslash {
-            prev_backslash = false;
-            continue;
-        }
-        if ch == '\\' {
-            prev_backslash = true;
-            continue;
-        }
-        if ch == '"' {
-            in_quotes = !in_quotes;
-            continue;
-        }
-        if in_quotes {
-            continue;
-        }
-        // A comma is a thousands separator if it appears between digit placeholders
-        if ch == ',' {
-            let has_digit_before = chars[..i].iter().rev().any(|&c| c == '0' || c == '#');
-            let has_digit_after = chars[i + 1..].iter().any(|&c| c == '0' || c == '#');
-            if has_digit_before && has_digit_after {
-                return true;
-            }
-        }
-    }
-    false
-}
-
-fn count_trailing_commas(format: &str) -> usize {
-    let mut in_quotes = false;
-    let mut prev_backslash = false;
-    let chars: Vec<char> = format.chars().collect();
-    let mut count = 0;
-
-    // Find the last digit placeholder, then count commas after it
-    let mut last_digit_pos = None;
-    for (i, &ch) in chars.iter().enumerate() {
-        if prev_backslash {
-            prev_backslash = false;
-            continue;
-        }
-        if ch == '\\' {
-            prev_backslash = true;
-            continue;
-        }
-        if ch == '"' {
-            in_quotes = !in_quotes;
-            continue;
-        }
-        if in_quotes {
-            continue;
-        }
-        if ch == '0' || ch == '#' {
-            last_digit_pos = Some(i);
-        }
-    }
-
-    if let Some(pos) = last_digit_pos {
-        for &ch in &chars[pos + 1..] {
-            if ch == ',' {
-                count += 1;
-            } else {
-                break;
-            }
-        }
-    }
-    count
-}
-
-fn count_integer_zeros(format: &str) -> usize {
-    let mut in_quotes = false;
-    let mut prev_backslash = false;
-    let mut count = 0;
-    let mut found_dot = false;
-
-    for ch in format.chars() {
-        if prev_backslash {
-            prev_backslash = false;
-            continue;
-        }
-        if ch == '\\' {
-            prev_backslash = true;
-            continue;
-        }
-        if ch == '"' {
-            in_quotes = !in_quotes;
-            continue;
-        }
-        if in_quotes {
-            continue;
-        }
-        if ch == '.' {
-            found_dot = true;
-            continue;
-        }
-        if !found_dot && ch == '0' {
-            count += 1;
-        }
-    }
-    count
-}
-
 fn add_thousands_separators(s: &str) -> String {
     let bytes = s.as_bytes();
     let len = bytes.len();
@@ -1105,29 +1069,6 @@ fn add_thousands_separators(s: &str) -> String {
     result
 }
 
-fn find_numeric_end(chars: &[char], start: usize) -> usize {
-    let mut i = start;
-    let mut in_quotes = false;
-    while i < chars.len() {
-        let ch = chars[i];
-        if ch == '"' {
-            in_quotes = !in_quotes;
-            i += 1;
-            continue;
-        }
-        if in_quotes {
-            i += 1;
-            continue;
-        }
-        if ch == '0' || ch == '#' || ch == ',' || ch == '.' {
-            i += 1;
-        } else {
-            break;
-        }
-    }
-    i
-}
-
 fn format_scientific(value: f64, format: &str) -> String {
     let decimal_places = count_decimal_places(format);
     let formatted = format!("{:.*E}", decimal_places, value.abs());
@@ -1255,8 +1196,6 @@ fn best_fraction(value: f64, max_denom: u64) -> (u64, u64) {
     (best_num, best_den)
 }
 
-use chrono::Datelike;
-
 #[cfg(test)]
 #[allow(clippy::approx_constant)]
 mod tests {
@@ -1380,6 +1319,20 @@ mod tests {
         assert_eq!(format_number(serial, "m/d/yyyy"), "1/15/2024");
     }
 
+    #[test]
+    fn test_format_date_1904_date_system() {
+        // 2024-01-15 = serial 43844 in the 1904 date system
+        assert_eq!(
+            format_number_with_date_system(43844.0, "yyyy-mm-dd", DateSystem::Date1904),
+            "2024-01-15"
+        );
+        assert_eq!(
+            format_number_with_date_system(0.5, "yyyy-mm-dd hh:mm", DateSystem::Date1904),
+            "1904-01-01 12:00"
+        );
+        assert_eq!(format_number(43844.0, "yyyy-mm-dd"), "2020-01-14");
+    }
+
     #[test]
     fn test_format_date_dmy() {
         let serial =
@@ -1570,6 +1523,22 @@ mod tests {
         assert_eq!(format_number(serial, "mm:ss"), "05:30");
     }
 
+    #[test]
+    fn test_format_non_finite() {
+        for code in [
+            "General",
+            "0.00",
+            "#,##0",
+            "0.00E+00",
+            "yyyy-mm-dd",
+            "# ?/?",
+        ] {
+            assert_eq!(format_number(f64::INFINITY, code), "#NUM!");
+            assert_eq!(format_number(f64::NEG_INFINITY, code), "#NUM!");
+            assert_eq!(format_number(f64::NAN, code), "#NUM!");
+        }
+    }
+
     #[test]
     fn test_format_general_negative_decimal() {
         let result = format_number(-3.14, "General");
@@ -1591,6 +1560,39 @@ mod tests {
         assert_eq!(add_thousands_separators("1000000"), "1,000,000");
     }
 
+    #[test]
+    fn test_format_zero_padding() {
+        assert_eq!(format_number(42.0, "000"), "042");
+        assert_eq!(format_number(1234.5, "00000.00"), "01234.50");
+        assert_eq!(format_number(0.5, "#.00"), ".50");
+    }
+
+    #[test]
+    fn test_format_optional_decimals() {
+        assert_eq!(format_number(1.5, "0.##"), "1.5");
+        assert_eq!(format_number(1.0, "0.##"), "1.");
+        assert_eq!(format_number(1.25, "0.0#"), "1.25");
+        assert_eq!(format_number(12.0, "?0.0?"), "12.0 ");
+    }
+
+    #[test]
+    fn test_format_literals_between_digits() {
+        assert_eq!(format_number(5551234.0, "000-0000"), "555-1234");
+        assert_eq!(format_number(1234.5, "#,##0.00 \"USD\""), "1,234.50 USD");
+    }
+
+    #[test]
+    fn test_format_sign_precedes_literals() {
+        assert_eq!(format_number(-1234.567, "$#,##0.00"), "-$1,234.57");
+        assert_eq!(format_number(1234.0, "[$€-407]#,##0"), "€1,234");
+    }
+
+    #[test]
+    fn test_format_scaling_comma() {
+        assert_eq!(format_number(1234567.0, "#,##0,"), "1,235");
+        assert_eq!(format_number(1234567.0, "0.0,,"), "1.2");
+    }
+
     #[test]
     fn test_parse_sections_single() {
         let sections = parse_sections("0.00");
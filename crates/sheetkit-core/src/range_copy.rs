@@ -0,0 +1,622 @@
+//! Copying and moving rectangular ranges of cells.
+//!
+//! A range is read into a [`RangeBlock`]: its cells, with shared formulas
+//! expanded, the parts of the sheet's data validations and conditional
+//! formats that cover it, and the merged areas inside it. The block is then
+//! pasted at another position on the same or another sheet, optionally
+//! transposed, with [`CopyRangeOptions`] choosing what to paste like Excel's
+//! Paste Special. The [`Workbook`](crate::workbook::Workbook) wrapper copies
+//! comments and, for moves, redirects references to the moved cells.
+
+use sheetkit_xml::worksheet::{
+    Cell, CellFormula, CellTypeTag, ConditionalFormatting, DataValidation, DataValidations,
+    MergeCell, MergeCells, Row, WorksheetXml,
+};
+
+use crate::array_formula::legacy_array_areas;
+use crate::cell_ref_shift::{qualify_references, shift_formula_by_offset};
+use crate::cell_shift::CellRange;
+use crate::error::{Error, Result};
+use crate::row::find_or_create_row;
+use crate::shared_formula::{
+    collect_shared_masters, effective_formula, shared_master_si, unshare_formulas,
+};
+use crate::utils::cell_ref::{cell_name_to_coordinates, coordinates_to_cell_name};
+use crate::utils::constants::{MAX_COLUMNS, MAX_ROWS};
+
+/// What [`Workbook::copy_range`](crate::workbook::Workbook::copy_range)
+/// pastes, like the choices of Excel's Paste Special. The default pastes
+/// everything, like a plain paste.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CopyRangeOptions {
+    /// Paste constant values. Without `formulas`, formula cells paste their
+    /// last calculated results instead ("Paste Values").
+    pub values: bool,
+    /// Paste formulas, with their relative references adjusted to the new
+    /// position.
+    pub formulas: bool,
+    /// Paste cell styles (number formats, fonts, fills, borders).
+    pub styles: bool,
+    /// Paste cell comments.
+    pub comments: bool,
+    /// Paste data validation rules.
+    pub validations: bool,
+    /// Paste conditional formatting rules.
+    pub conditional_formats: bool,
+    /// Paste merged cells.
+    pub merges: bool,
+    /// Swap rows and columns: the first row of the source becomes the first
+    /// column of the destination.
+    pub transpose: bool,
+    /// Leave destination cells alone where the source cell is blank.
+    pub skip_blanks: bool,
+}
+
+impl Default for CopyRangeOptions {
+    fn default() -> Self {
+        Self {
+            values: true,
+            formulas: true,
+            styles: true,
+            comments: true,
+            validations: true,
+            conditional_formats: true,
+            merges: true,
+            transpose: false,
+            skip_blanks: false,
+        }
+    }
+}
+
+/// Where the cells of a source range land when pasted.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Placement {
+    pub(crate) src: CellRange,
+    col: u32,
+    row: u32,
+    transpose: bool,
+}
+
+impl Placement {
+    /// Paste `src` with its top-left cell at `dst_cell`. Returns an error if
+    /// the pasted range would extend past the edge of the sheet.
+    pub(crate) fn new(src: CellRange, dst_cell: &str, transpose: bool) -> Result<Self> {
+        let (col, row) = cell_name_to_coordinates(dst_cell)?;
+        let (width, height) = if transpose {
+            (src.height(), src.width())
+        } else {
+            (src.width(), src.height())
+        };
+        if col + width - 1 > MAX_COLUMNS {
+            return Err(Error::InvalidColumnNumber(col + width - 1));
+        }
+        if row + height - 1 > MAX_ROWS {
+            return Err(Error::InvalidRowNumber(row + height - 1));
+        }
+        Ok(Self {
+            src,
+            col,
+            row,
+            transpose,
+        })
+    }
+
+    /// The destination of the source cell at `(col, row)`.
+    pub(crate) fn map(&self, col: u32, row: u32) -> (u32, u32) {
+        let (dc, dr) = (col - self.src.min_col, row - self.src.min_row);
+        if self.transpose {
+            (self.col + dr, self.row + dc)
+        } else {
+            (self.col + dc, self.row + dr)
+        }
+    }
+
+    /// The destination of a part of the source range.
+    pub(crate) fn map_area(&self, area: CellRange) -> CellRange {
+        CellRange::from_corners(
+            self.map(area.min_col, area.min_row),
+            self.map(area.max_col, area.max_row),
+        )
+    }
+
+    /// The range the source is pasted over.
+    pub(crate) fn dst(&self) -> CellRange {
+        self.map_area(self.src)
+    }
+
+    /// How far the top-left cell moves, as a column and row offset.
+    fn offset(&self) -> (i64, i64) {
+        (
+            self.col as i64 - self.src.min_col as i64,
+            self.row as i64 - self.src.min_row as i64,
+        )
+    }
+}
+
+/// The contents of a range, read from a sheet to be pasted elsewhere.
+#[derive(Debug, Clone)]
+pub(crate) struct RangeBlock {
+    /// Cells with their coordinates. Formulas are standalone: shared
+    /// formula children carry their expanded text.
+    cells: Vec<(u32, u32, Cell)>,
+    /// Validations covering the range, with `sqref` clipped to it.
+    validations: Vec<DataValidation>,
+    /// Conditional formats covering the range, with `sqref` clipped to it.
+    conditional_formats: Vec<ConditionalFormatting>,
+    /// Merged areas lying entirely inside the range.
+    merges: Vec<CellRange>,
+}
+
+impl RangeBlock {
+    /// Read the range `area` of `ws`. Returns an error if `area` holds part
+    /// of a legacy array formula range but not all of it.
+    pub(crate) fn read(ws: &WorksheetXml, area: CellRange) -> Result<Self> {
+        for array in legacy_array_areas(ws) {
+            let range = CellRange::from_corners(
+                (array.min_col, array.min_row),
+                (array.max_col, array.max_row),
+            );
+            if area.intersect(&range).is_some_and(|cut| cut != range) {
+                return Err(Error::ArrayFormulaPart {
+                    range: array.to_reference()?,
+                });
+            }
+        }
+        let masters = collect_shared_masters(ws);
+        let mut cells = Vec::new();
+        for row in rows_in(ws, area) {
+            for cell in &row.cells {
+                let col = cell_col(cell);
+                if col < area.min_col || col > area.max_col {
+                    continue;
+                }
+                let formula = effective_formula(cell, col, row.r, &masters)?;
+                let mut cell = cell.clone();
+                if let Some(f) = cell.f.take() {
+                    let array = f.t.as_deref() == Some("array");
+                    cell.f = formula.map(|formula| {
+                        Box::new(CellFormula {
+                            t: array.then(|| "array".to_string()),
+                            reference: f.reference.filter(|_| array),
+                            si: None,
+                            value: Some(formula),
+                        })
+                    });
+                }
+                cells.push((col, row.r, cell));
+            }
+        }
+
+        let validations = ws
+            .data_validations
+            .iter()
+            .flat_map(|dvs| dvs.data_validations.iter())
+            .filter_map(|dv| {
+                let sqref = clip_sqref(&dv.sqref, area)?;
+                Some(DataValidation {
+                    sqref,
+                    ..dv.clone()
+                })
+            })
+            .collect();
+        let conditional_formats = ws
+            .conditional_formatting
+            .iter()
+            .filter_map(|cf| {
+                let sqref = clip_sqref(&cf.sqref, area)?;
+                Some(ConditionalFormatting {
+                    sqref,
+                    cf_rules: cf.cf_rules.clone(),
+                })
+            })
+            .collect();
+        let merges = ws
+            .merge_cells
+            .iter()
+            .flat_map(|mc| mc.merge_cells.iter())
+            .filter_map(|mc| CellRange::parse(&mc.reference).ok())
+            .filter(|merge| merge.intersect(&area) == Some(*merge))
+            .collect();
+
+        Ok(Self {
+            cells,
+            validations,
+            conditional_formats,
+            merges,
+        })
+    }
+
+    /// Qualify the unqualified references in the block's formulas with
+    /// `sheet`, so they keep pointing there after a move to another sheet.
+    pub(crate) fn qualify_formulas(&mut self, sheet: &str) -> Result<()> {
+        for (_, _, cell) in &mut self.cells {
+            if let Some(value) = cell.f.as_mut().and_then(|f| f.value.as_mut()) {
+                *value = qualify_references(value, sheet)?;
+            }
+        }
+        for dv in &mut self.validations {
+            for f in [dv.formula1.as_mut(), dv.formula2.as_mut()]
+                .into_iter()
+                .flatten()
+            {
+                *f = qualify_references(f, sheet)?;
+            }
+        }
+        for cf in &mut self.conditional_formats {
+            for f in cf.cf_rules.iter_mut().flat_map(|r| r.formulas.iter_mut()) {
+                *f = qualify_references(f, sheet)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the cells of `area` from `ws`, along with the parts of
+    /// validations and conditional formats covering it and the merged areas
+    /// inside it.
+    pub(crate) fn clear(ws: &mut WorksheetXml, area: CellRange) -> Result<()> {
+        unshare_masters_in(ws, area)?;
+        for row in rows_in_mut(ws, area) {
+            let r = row.r;
+            row.cells.retain(|c| !area.contains(cell_col(c), r));
+        }
+        subtract_validations(ws, area);
+        subtract_conditional_formats(ws, area);
+        remove_merges(ws, |merge| merge.intersect(&area) == Some(merge));
+        Ok(())
+    }
+
+    /// Paste the block into `ws` at `placement`. When `adjust_formulas` is
+    /// set, relative references in formulas move with the cells, as in a
+    /// copy; a move leaves them as they are.
+    pub(crate) fn paste(
+        &self,
+        ws: &mut WorksheetXml,
+        placement: &Placement,
+        options: &CopyRangeOptions,
+        adjust_formulas: bool,
+    ) -> Result<()> {
+        let dst = placement.dst();
+        let content = options.values || options.formulas;
+        if content {
+            unshare_masters_in(ws, dst)?;
+        }
+
+        // Clear the destination first, unless blank source cells are to be
+        // skipped, in which case only the pasted cells are overwritten.
+        if !options.skip_blanks && (content || options.styles) {
+            for row in rows_in_mut(ws, dst) {
+                let r = row.r;
+                for cell in row.cells.iter_mut() {
+                    if dst.contains(cell_col(cell), r) {
+                        if content {
+                            clear_content(cell);
+                        }
+                        if options.styles {
+                            cell.s = None;
+                        }
+                    }
+                }
+                row.cells.retain(|c| !is_empty(c));
+            }
+        }
+
+        for (col, row, src) in &self.cells {
+            if options.skip_blanks && is_blank(src) {
+                continue;
+            }
+            let (tc, tr) = placement.map(*col, *row);
+            let target = find_or_create_cell(ws, tc, tr)?;
+            if content {
+                paste_content(
+                    target,
+                    src,
+                    options,
+                    adjust_formulas.then(|| (tc as i64 - *col as i64, tr as i64 - *row as i64)),
+                    placement,
+                )?;
+            }
+            if options.styles {
+                target.s = src.s;
+            }
+        }
+        for row in rows_in_mut(ws, dst) {
+            row.cells.retain(|c| !is_empty(c));
+        }
+
+        let (col_offset, row_offset) = placement.offset();
+        let adjust = |formula: &str| -> Result<String> {
+            if adjust_formulas {
+                shift_formula_by_offset(formula, col_offset, row_offset)
+            } else {
+                Ok(formula.to_string())
+            }
+        };
+
+        if options.validations {
+            subtract_validations(ws, dst);
+            let mut pasted = Vec::new();
+            for dv in &self.validations {
+                pasted.push(DataValidation {
+                    sqref: map_sqref(&dv.sqref, placement)?,
+                    formula1: dv.formula1.as_deref().map(adjust).transpose()?,
+                    formula2: dv.formula2.as_deref().map(adjust).transpose()?,
+                    ..dv.clone()
+                });
+            }
+            if !pasted.is_empty() {
+                let dvs = ws.data_validations.get_or_insert_with(|| DataValidations {
+                    count: Some(0),
+                    disable_prompts: None,
+                    x_window: None,
+                    y_window: None,
+                    data_validations: Vec::new(),
+                });
+                dvs.data_validations.extend(pasted);
+                dvs.count = Some(dvs.data_validations.len() as u32);
+            }
+        }
+
+        if options.conditional_formats {
+            subtract_conditional_formats(ws, dst);
+            let mut priority = crate::conditional::next_priority(ws);
+            for cf in &self.conditional_formats {
+                let mut cf = ConditionalFormatting {
+                    sqref: map_sqref(&cf.sqref, placement)?,
+                    cf_rules: cf.cf_rules.clone(),
+                };
+                for rule in &mut cf.cf_rules {
+                    rule.priority = priority;
+                    priority += 1;
+                    for f in &mut rule.formulas {
+                        *f = adjust(f)?;
+                    }
+                }
+                ws.conditional_formatting.push(cf);
+            }
+        }
+
+        if options.merges {
+            remove_merges(ws, |merge| merge.intersect(&dst).is_some());
+            for merge in &self.merges {
+                let reference = placement.map_area(*merge).to_reference()?;
+                let merges = ws.merge_cells.get_or_insert_with(|| MergeCells {
+                    count: None,
+                    merge_cells: Vec::new(),
+                    cached_coords: Vec::new(),
+                });
+                merges.merge_cells.push(MergeCell { reference });
+                merges.cached_coords.clear();
+                merges.count = Some(merges.merge_cells.len() as u32);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn cell_col(cell: &Cell) -> u32 {
+    cell_name_to_coordinates(cell.r.as_str()).map_or(cell.col, |(col, _)| col)
+}
+
+fn rows_in(ws: &WorksheetXml, area: CellRange) -> impl Iterator<Item = &Row> {
+    ws.sheet_data
+        .rows
+        .iter()
+        .filter(move |row| row.r >= area.min_row && row.r <= area.max_row)
+}
+
+fn rows_in_mut(ws: &mut WorksheetXml, area: CellRange) -> impl Iterator<Item = &mut Row> {
+    ws.sheet_data
+        .rows
+        .iter_mut()
+        .filter(move |row| row.r >= area.min_row && row.r <= area.max_row)
+}
+
+/// Whether a cell has no value or formula. It may still have a style.
+fn is_blank(cell: &Cell) -> bool {
+    cell.v.is_none() && cell.f.is_none() && cell.is.is_none()
+}
+
+fn is_empty(cell: &Cell) -> bool {
+    is_blank(cell) && cell.s.is_none()
+}
+
+fn clear_content(cell: &mut Cell) {
+    cell.t = CellTypeTag::None;
+    cell.v = None;
+    cell.f = None;
+    cell.is = None;
+}
+
+/// Write the value or formula of `src` into `target`. `offset` moves the
+/// relative references of a pasted formula.
+fn paste_content(
+    target: &mut Cell,
+    src: &Cell,
+    options: &CopyRangeOptions,
+    offset: Option<(i64, i64)>,
+    placement: &Placement,
+) -> Result<()> {
+    match src.f.as_deref() {
+        Some(f) if options.formulas => {
+            let mut f = f.clone();
+            let mut changed = false;
+            if let (Some(value), Some((col_offset, row_offset))) = (f.value.as_mut(), offset) {
+                let shifted = shift_formula_by_offset(value, col_offset, row_offset)?;
+                changed = shifted != *value;
+                *value = shifted;
+            }
+            if let Some(reference) = f.reference.as_mut() {
+                if let Ok(area) = CellRange::parse(reference) {
+                    *reference = placement.map_area(area).to_reference()?;
+                }
+            }
+            clear_content(target);
+            target.f = Some(Box::new(f));
+            // A cached result only stays valid if the formula is unchanged.
+            if !changed {
+                target.t = src.t;
+                target.v = src.v.clone();
+                target.is = src.is.clone();
+            }
+        }
+        Some(_) if !options.values => {}
+        _ if options.values => {
+            clear_content(target);
+            target.t = src.t;
+            target.v = src.v.clone();
+            target.is = src.is.clone();
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn find_or_create_cell(ws: &mut WorksheetXml, col: u32, row: u32) -> Result<&mut Cell> {
+    let r = coordinates_to_cell_name(col, row)?;
+    let xml_row = find_or_create_row(ws, row);
+    let idx = match xml_row.cells.binary_search_by_key(&col, cell_col) {
+        Ok(idx) => idx,
+        Err(pos) => {
+            xml_row.cells.insert(
+                pos,
+                Cell {
+                    r: r.into(),
+                    col,
+                    s: None,
+                    t: CellTypeTag::None,
+                    v: None,
+                    f: None,
+                    is: None,
+                },
+            );
+            pos
+        }
+    };
+    Ok(&mut xml_row.cells[idx])
+}
+
+/// Convert the shared formula groups whose master lies in `area` into
+/// standalone formulas, so that overwriting or removing the master does
+/// not orphan the rest of the group.
+fn unshare_masters_in(ws: &mut WorksheetXml, area: CellRange) -> Result<()> {
+    let groups: Vec<u32> = rows_in(ws, area)
+        .flat_map(|row| {
+            row.cells
+                .iter()
+                .filter(move |c| area.contains(cell_col(c), row.r))
+        })
+        .filter_map(shared_master_si)
+        .collect();
+    for si in groups {
+        unshare_formulas(ws, Some(si))?;
+    }
+    Ok(())
+}
+
+/// The parts of the areas of `sqref` inside `area`, or `None` if there are
+/// none. Areas that cannot be parsed are ignored.
+fn clip_sqref(sqref: &str, area: CellRange) -> Option<String> {
+    let parts: Vec<String> = sqref
+        .split_whitespace()
+        .filter_map(|part| CellRange::parse(part).ok()?.intersect(&area))
+        .filter_map(|part| part.to_reference().ok())
+        .collect();
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// The areas of `sqref` with `area` cut out, or `None` if nothing is left.
+/// Areas that cannot be parsed are kept as they are.
+fn subtract_sqref(sqref: &str, area: CellRange) -> Option<String> {
+    let mut parts = Vec::new();
+    for part in sqref.split_whitespace() {
+        match CellRange::parse(part) {
+            Ok(range) => parts.extend(
+                range
+                    .subtract(&area)
+                    .into_iter()
+                    .filter_map(|piece| piece.to_reference().ok()),
+            ),
+            Err(_) => parts.push(part.to_string()),
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Move the areas of a clipped `sqref` to their pasted position.
+fn map_sqref(sqref: &str, placement: &Placement) -> Result<String> {
+    let parts = sqref
+        .split_whitespace()
+        .map(|part| placement.map_area(CellRange::parse(part)?).to_reference())
+        .collect::<Result<Vec<_>>>()?;
+    Ok(parts.join(" "))
+}
+
+fn subtract_validations(ws: &mut WorksheetXml, area: CellRange) {
+    if let Some(dvs) = ws.data_validations.as_mut() {
+        dvs.data_validations
+            .retain_mut(|dv| match subtract_sqref(&dv.sqref, area) {
+                Some(sqref) => {
+                    dv.sqref = sqref;
+                    true
+                }
+                None => false,
+            });
+        dvs.count = Some(dvs.data_validations.len() as u32);
+        if dvs.data_validations.is_empty() {
+            ws.data_validations = None;
+        }
+    }
+}
+
+fn subtract_conditional_formats(ws: &mut WorksheetXml, area: CellRange) {
+    ws.conditional_formatting
+        .retain_mut(|cf| match subtract_sqref(&cf.sqref, area) {
+            Some(sqref) => {
+                cf.sqref = sqref;
+                true
+            }
+            None => false,
+        });
+}
+
+fn remove_merges(ws: &mut WorksheetXml, remove: impl Fn(CellRange) -> bool) {
+    if let Some(merges) = ws.merge_cells.as_mut() {
+        merges
+            .merge_cells
+            .retain(|mc| CellRange::parse(&mc.reference).map_or(true, |merge| !remove(merge)));
+        merges.cached_coords.clear();
+        merges.count = Some(merges.merge_cells.len() as u32);
+        if merges.merge_cells.is_empty() {
+            ws.merge_cells = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placement_map() {
+        let src = CellRange::parse("B2:C4").unwrap();
+        let placement = Placement::new(src, "E1", false).unwrap();
+        assert_eq!(placement.map(2, 2), (5, 1));
+        assert_eq!(placement.map(3, 4), (6, 3));
+        assert_eq!(placement.dst(), CellRange::parse("E1:F3").unwrap());
+
+        let transposed = Placement::new(src, "E1", true).unwrap();
+        assert_eq!(transposed.map(3, 2), (5, 2));
+        assert_eq!(transposed.dst(), CellRange::parse("E1:G2").unwrap());
+    }
+
+    #[test]
+    fn test_placement_rejects_overflow() {
+        let src = CellRange::parse("A1:A3").unwrap();
+        assert!(matches!(
+            Placement::new(src, "A1048575", false),
+            Err(Error::InvalidRowNumber(_))
+        ));
+        assert!(Placement::new(src, "XFD1", true).is_err());
+        assert!(Placement::new(src, "XFD1", false).is_ok());
+    }
+}
@@ -34,7 +34,9 @@
 
 use std::collections::HashMap;
 
+use crate::cell::DateSystem;
 use crate::error::Result;
+use crate::shared_formula::{collect_shared_masters, effective_formula};
 use crate::sst::SharedStringTable;
 use crate::utils::cell_ref::cell_name_to_coordinates;
 use sheetkit_xml::worksheet::{CellTypeTag, WorksheetXml};
@@ -64,7 +66,12 @@ const SPARSE_CELL_STRIDE: usize = 11;
 /// Reads cell data directly from `WorksheetXml` sheet data, resolving shared
 /// string references via `sst`. The resulting buffer uses either dense or
 /// sparse layout depending on cell density relative to the bounding rectangle.
-pub fn sheet_to_raw_buffer(ws: &WorksheetXml, sst: &SharedStringTable) -> Result<Vec<u8>> {
+/// Date serials stored in `date_system` are converted to the 1900 date system.
+pub fn sheet_to_raw_buffer(
+    ws: &WorksheetXml,
+    sst: &SharedStringTable,
+    date_system: DateSystem,
+) -> Result<Vec<u8>> {
     let rows = &ws.sheet_data.rows;
 
     if rows.is_empty() {
@@ -86,7 +93,7 @@ pub fn sheet_to_raw_buffer(ws: &WorksheetXml, sst: &SharedStringTable) -> Result
 
     let mut string_table = StringTableBuilder::from_sst(sst);
 
-    let cell_entries = collect_cell_entries(ws, sst, min_col, &mut string_table)?;
+    let cell_entries = collect_cell_entries(ws, sst, date_system, min_col, &mut string_table)?;
 
     let flags: u32 = (if sparse { FLAG_SPARSE } else { 0 }) | ((min_col & 0xFFFF) << 16);
 
@@ -176,9 +183,11 @@ fn resolve_col(cell: &sheetkit_xml::worksheet::Cell) -> Result<u32> {
 fn collect_cell_entries(
     ws: &WorksheetXml,
     sst: &SharedStringTable,
+    date_system: DateSystem,
     min_col: u32,
     string_table: &mut StringTableBuilder,
 ) -> Result<Vec<RowEntries>> {
+    let masters = collect_shared_masters(ws);
     let mut result = Vec::with_capacity(ws.sheet_data.rows.len());
 
     for row in &ws.sheet_data.rows {
@@ -190,7 +199,9 @@ fn collect_cell_entries(
         for cell in &row.cells {
             let col = resolve_col(cell)?;
             let relative_col = col - min_col;
-            let (type_tag, payload) = encode_cell_value(cell, sst, string_table)?;
+            let formula = effective_formula(cell, col, row.r, &masters)?;
+            let (type_tag, payload) =
+                encode_cell_value(cell, formula.as_deref(), sst, date_system, string_table)?;
             cells.push(CellEntry {
                 col: relative_col,
                 type_tag,
@@ -209,17 +220,15 @@ fn collect_cell_entries(
 
 fn encode_cell_value(
     cell: &sheetkit_xml::worksheet::Cell,
+    formula: Option<&str>,
     sst: &SharedStringTable,
+    date_system: DateSystem,
     string_table: &mut StringTableBuilder,
 ) -> Result<(u8, [u8; 8])> {
     let mut payload = [0u8; 8];
 
     if cell.f.is_some() {
-        let formula_expr = cell
-            .f
-            .as_ref()
-            .and_then(|f| f.value.as_deref())
-            .unwrap_or("");
+        let formula_expr = formula.unwrap_or("");
         let idx = string_table.intern(formula_expr);
         payload[..4].copy_from_slice(&(idx as u32).to_le_bytes());
         return Ok((TYPE_FORMULA, payload));
@@ -270,7 +279,8 @@ fn encode_cell_value(
         CellTypeTag::Date => {
             if let Some(ref v) = cell.v {
                 if let Ok(n) = v.parse::<f64>() {
-                    payload.copy_from_slice(&n.to_le_bytes());
+                    let serial = date_system.to_1900_serial(n);
+                    payload.copy_from_slice(&serial.to_le_bytes());
                     return Ok((TYPE_DATE, payload));
                 }
             }
@@ -580,7 +590,7 @@ mod tests {
     fn test_empty_sheet() {
         let ws = make_worksheet(vec![]);
         let sst = SharedStringTable::new();
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         assert_eq!(buf.len(), HEADER_SIZE);
         assert_eq!(read_u32_le(&buf, 0), MAGIC);
@@ -597,7 +607,7 @@ mod tests {
             vec![make_cell("A1", 1, CellTypeTag::None, Some("42.5"))],
         )]);
         let sst = SharedStringTable::new();
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         assert_eq!(read_u32_le(&buf, 0), MAGIC);
         let row_count = read_u32_le(&buf, 6);
@@ -631,7 +641,7 @@ mod tests {
             1,
             vec![make_cell("A1", 1, CellTypeTag::SharedString, Some("1"))],
         )]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let (st_start, _, cd_start, flags) = parse_sections(&buf);
         let is_sparse = flags & FLAG_SPARSE != 0;
@@ -666,7 +676,7 @@ mod tests {
                 make_cell("B1", 2, CellTypeTag::Boolean, Some("0")),
             ],
         )]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let col_count = read_u16_le(&buf, 10) as usize;
         assert_eq!(col_count, 2);
@@ -698,7 +708,7 @@ mod tests {
             1,
             vec![make_cell("A1", 1, CellTypeTag::Error, Some("#DIV/0!"))],
         )]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let (st_start, _, cd_start, flags) = parse_sections(&buf);
         let is_sparse = flags & FLAG_SPARSE != 0;
@@ -730,7 +740,7 @@ mod tests {
             value: Some("A2+B2".to_string()),
         }));
         let ws = make_worksheet(vec![make_row(1, vec![cell])]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let (st_start, _, cd_start, flags) = parse_sections(&buf);
         let is_sparse = flags & FLAG_SPARSE != 0;
@@ -750,6 +760,29 @@ mod tests {
         assert_eq!(formula, "A2+B2");
     }
 
+    #[test]
+    fn test_shared_formula_child_is_expanded() {
+        let sst = SharedStringTable::new();
+        let shared = |value: Option<&str>| {
+            Some(Box::new(CellFormula {
+                t: Some("shared".to_string()),
+                reference: value.map(|_| "B1:B2".to_string()),
+                si: Some(0),
+                value: value.map(str::to_string),
+            }))
+        };
+        let mut master = make_cell("B1", 2, CellTypeTag::None, None);
+        master.f = shared(Some("A1*2"));
+        let mut child = make_cell("B2", 2, CellTypeTag::None, None);
+        child.f = shared(None);
+        let ws = make_worksheet(vec![make_row(1, vec![master]), make_row(2, vec![child])]);
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
+
+        let (st_start, _, _, _) = parse_sections(&buf);
+        assert_eq!(read_string(&buf, st_start, 0), "A1*2");
+        assert_eq!(read_string(&buf, st_start, 1), "A2*2");
+    }
+
     #[test]
     fn test_inline_string_cell() {
         let sst = SharedStringTable::new();
@@ -758,7 +791,7 @@ mod tests {
             t: Some("Inline Text".to_string()),
         }));
         let ws = make_worksheet(vec![make_row(1, vec![cell])]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let (st_start, _, cd_start, flags) = parse_sections(&buf);
         let is_sparse = flags & FLAG_SPARSE != 0;
@@ -785,7 +818,7 @@ mod tests {
             1,
             vec![make_cell("A1", 1, CellTypeTag::Date, Some("44927.0"))],
         )]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let (_, _, cd_start, flags) = parse_sections(&buf);
         let is_sparse = flags & FLAG_SPARSE != 0;
@@ -803,6 +836,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_date_cell_1904_date_system() {
+        let sst = SharedStringTable::new();
+        let ws = make_worksheet(vec![make_row(
+            1,
+            vec![make_cell("A1", 1, CellTypeTag::Date, Some("43465"))],
+        )]);
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1904).unwrap();
+
+        let (_, _, cd_start, flags) = parse_sections(&buf);
+        let payload = if flags & FLAG_SPARSE != 0 {
+            read_sparse_row(&buf, cd_start)[0].2
+        } else {
+            read_cell_at(&buf, cd_start, false, 0).1
+        };
+        let val = f64::from_le_bytes(payload.try_into().unwrap());
+        assert!((val - 44927.0).abs() < f64::EPSILON);
+    }
+
     #[test]
     fn test_mixed_types_row() {
         let sst = make_sst(&["Hello"]);
@@ -814,7 +866,7 @@ mod tests {
                 make_cell("C1", 3, CellTypeTag::Boolean, Some("1")),
             ],
         )]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let col_count = read_u16_le(&buf, 10) as usize;
         assert_eq!(col_count, 3);
@@ -869,7 +921,7 @@ mod tests {
             rows.push(make_row(r, cells));
         }
         let ws = make_worksheet(rows);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let flags = read_u32_le(&buf, 12);
         assert_eq!(
@@ -917,7 +969,7 @@ mod tests {
             ),
         ];
         let ws = make_worksheet(rows);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let flags = read_u32_le(&buf, 12);
         assert_ne!(
@@ -945,7 +997,7 @@ mod tests {
             ),
             make_row(5, vec![make_cell("C5", 3, CellTypeTag::None, Some("30"))]),
         ]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         assert_eq!(read_u32_le(&buf, 0), MAGIC);
         assert_eq!(read_u16_le(&buf, 4), VERSION);
@@ -965,7 +1017,7 @@ mod tests {
                 make_cell("B1", 2, CellTypeTag::SharedString, Some("2")),
             ],
         )]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let (st_start, _, _, _) = parse_sections(&buf);
         let string_count = read_u32_le(&buf, st_start) as usize;
@@ -998,7 +1050,7 @@ mod tests {
             rows.push(make_row(r, cells));
         }
         let ws = make_worksheet(rows);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let row_count = read_u32_le(&buf, 6);
         assert_eq!(row_count, 1000, "rows 1-1000 = 1000 rows");
@@ -1020,7 +1072,7 @@ mod tests {
             make_row(1, vec![make_cell("A1", 1, CellTypeTag::None, Some("1"))]),
             make_row(3, vec![make_cell("A3", 1, CellTypeTag::None, Some("3"))]),
         ]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let row_count = read_u32_le(&buf, 6) as usize;
         assert_eq!(row_count, 3);
@@ -1052,7 +1104,7 @@ mod tests {
                 make_cell("B1", 2, CellTypeTag::Error, Some("#N/A")),
             ],
         )]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let (st_start, _, _, _) = parse_sections(&buf);
         let string_count = read_u32_le(&buf, st_start) as usize;
@@ -1076,7 +1128,7 @@ mod tests {
                 Some("computed"),
             )],
         )]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let (_, _, cd_start, flags) = parse_sections(&buf);
         let is_sparse = flags & FLAG_SPARSE != 0;
@@ -1097,7 +1149,7 @@ mod tests {
             1,
             vec![make_cell("A1", 1, CellTypeTag::Number, Some("99.9"))],
         )]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let (_, _, cd_start, flags) = parse_sections(&buf);
         let is_sparse = flags & FLAG_SPARSE != 0;
@@ -1121,7 +1173,7 @@ mod tests {
         let mut cell = make_cell("C5", 0, CellTypeTag::None, Some("42"));
         cell.col = 0;
         let ws = make_worksheet(vec![make_row(5, vec![cell])]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let row_count = read_u32_le(&buf, 6);
         assert_eq!(row_count, 1);
@@ -1158,7 +1210,7 @@ mod tests {
             1,
             vec![make_cell("A1", 1, CellTypeTag::SharedString, Some("1"))],
         )]);
-        let buf = sheet_to_raw_buffer(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let (st_start, _, cd_start, flags) = parse_sections(&buf);
         let is_sparse = flags & FLAG_SPARSE != 0;
@@ -33,7 +33,9 @@
 //!     0x07 (rich):   4 bytes (len u32 LE) + N bytes (UTF-8)
 //! ```
 
+use crate::cell::DateSystem;
 use crate::error::Result;
+use crate::shared_formula::{collect_shared_masters, effective_formula};
 use crate::sst::SharedStringTable;
 use crate::utils::cell_ref::cell_name_to_coordinates;
 use sheetkit_xml::worksheet::{CellTypeTag, WorksheetXml};
@@ -115,8 +117,13 @@ impl RowEntriesV2 {
 ///
 /// The v2 format inlines all string data with each cell, eliminating the global
 /// string table. This enables incremental row-by-row decoding without eagerly
-/// decoding all strings.
-pub fn sheet_to_raw_buffer_v2(ws: &WorksheetXml, sst: &SharedStringTable) -> Result<Vec<u8>> {
+/// decoding all strings. Date serials stored in `date_system` are converted to
+/// the 1900 date system.
+pub fn sheet_to_raw_buffer_v2(
+    ws: &WorksheetXml,
+    sst: &SharedStringTable,
+    date_system: DateSystem,
+) -> Result<Vec<u8>> {
     let rows = &ws.sheet_data.rows;
 
     if rows.is_empty() {
@@ -132,7 +139,7 @@ pub fn sheet_to_raw_buffer_v2(ws: &WorksheetXml, sst: &SharedStringTable) -> Res
     let row_count = (max_row - min_row + 1) as usize;
     let col_count = (max_col - min_col + 1) as usize;
 
-    let cell_entries = collect_cell_entries_v2(ws, sst, min_col)?;
+    let cell_entries = collect_cell_entries_v2(ws, sst, date_system, min_col)?;
 
     let flags: u32 = (min_col & 0xFFFF) << 16;
 
@@ -206,8 +213,10 @@ fn resolve_col(cell: &sheetkit_xml::worksheet::Cell) -> Result<u32> {
 fn collect_cell_entries_v2(
     ws: &WorksheetXml,
     sst: &SharedStringTable,
+    date_system: DateSystem,
     min_col: u32,
 ) -> Result<Vec<RowEntriesV2>> {
+    let masters = collect_shared_masters(ws);
     let mut result = Vec::with_capacity(ws.sheet_data.rows.len());
 
     for row in &ws.sheet_data.rows {
@@ -219,7 +228,9 @@ fn collect_cell_entries_v2(
         for cell in &row.cells {
             let col = resolve_col(cell)?;
             let relative_col = (col - min_col) as u16;
-            let (type_tag, payload) = encode_cell_value_v2(cell, sst)?;
+            let formula = effective_formula(cell, col, row.r, &masters)?;
+            let (type_tag, payload) =
+                encode_cell_value_v2(cell, formula.as_deref(), sst, date_system)?;
             cells.push(CellEntryV2 {
                 col: relative_col,
                 type_tag,
@@ -238,14 +249,12 @@ fn collect_cell_entries_v2(
 
 fn encode_cell_value_v2(
     cell: &sheetkit_xml::worksheet::Cell,
+    formula: Option<&str>,
     sst: &SharedStringTable,
+    date_system: DateSystem,
 ) -> Result<(u8, CellPayload)> {
     if cell.f.is_some() {
-        let formula_expr = cell
-            .f
-            .as_ref()
-            .and_then(|f| f.value.as_deref())
-            .unwrap_or("");
+        let formula_expr = formula.unwrap_or("");
         return Ok((TYPE_FORMULA, CellPayload::Str(formula_expr.to_string())));
     }
 
@@ -290,7 +299,8 @@ fn encode_cell_value_v2(
         CellTypeTag::Date => {
             if let Some(ref v) = cell.v {
                 if let Ok(n) = v.parse::<f64>() {
-                    return Ok((TYPE_DATE, CellPayload::Number(n)));
+                    let serial = date_system.to_1900_serial(n);
+                    return Ok((TYPE_DATE, CellPayload::Number(serial)));
                 }
             }
             Ok((TYPE_EMPTY, CellPayload::Empty))
@@ -485,7 +495,7 @@ mod tests {
     fn test_empty_sheet() {
         let ws = make_worksheet(vec![]);
         let sst = SharedStringTable::new();
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         assert_eq!(buf.len(), HEADER_SIZE);
         assert_eq!(read_u32_le(&buf, 0), MAGIC_V2);
@@ -502,7 +512,7 @@ mod tests {
             vec![make_cell("A1", 1, CellTypeTag::None, Some("42.5"))],
         )]);
         let sst = SharedStringTable::new();
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         assert_eq!(read_u32_le(&buf, 0), MAGIC_V2);
         assert_eq!(read_u32_le(&buf, 6), 1); // row_count
@@ -529,7 +539,7 @@ mod tests {
             1,
             vec![make_cell("A1", 1, CellTypeTag::SharedString, Some("1"))],
         )]);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let cell_data_start = HEADER_SIZE + 8;
         let row1_offset = read_u32_le(&buf, HEADER_SIZE + 4);
@@ -550,7 +560,7 @@ mod tests {
                 make_cell("B1", 2, CellTypeTag::Boolean, Some("0")),
             ],
         )]);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let cell_data_start = HEADER_SIZE + 8;
         let row1_offset = read_u32_le(&buf, HEADER_SIZE + 4);
@@ -570,7 +580,7 @@ mod tests {
             1,
             vec![make_cell("A1", 1, CellTypeTag::Error, Some("#DIV/0!"))],
         )]);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let cell_data_start = HEADER_SIZE + 8;
         let row1_offset = read_u32_le(&buf, HEADER_SIZE + 4);
@@ -592,7 +602,7 @@ mod tests {
             value: Some("A2+B2".to_string()),
         }));
         let ws = make_worksheet(vec![make_row(1, vec![cell])]);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let cell_data_start = HEADER_SIZE + 8;
         let row1_offset = read_u32_le(&buf, HEADER_SIZE + 4);
@@ -603,6 +613,32 @@ mod tests {
         assert_eq!(cells[0].2, "A2+B2");
     }
 
+    #[test]
+    fn test_shared_formula_child_is_expanded() {
+        let sst = SharedStringTable::new();
+        let shared = |value: Option<&str>| {
+            Some(Box::new(CellFormula {
+                t: Some("shared".to_string()),
+                reference: value.map(|_| "B1:B2".to_string()),
+                si: Some(0),
+                value: value.map(str::to_string),
+            }))
+        };
+        let mut master = make_cell("B1", 2, CellTypeTag::None, None);
+        master.f = shared(Some("A1*2"));
+        let mut child = make_cell("B2", 2, CellTypeTag::None, None);
+        child.f = shared(None);
+        let ws = make_worksheet(vec![make_row(1, vec![master]), make_row(2, vec![child])]);
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
+
+        let cell_data_start = HEADER_SIZE + 2 * 8;
+        let row2_offset = read_u32_le(&buf, HEADER_SIZE + 8 + 4);
+        let cells = read_v2_row_cells(&buf, cell_data_start + row2_offset as usize);
+
+        assert_eq!(cells[0].1, TYPE_FORMULA);
+        assert_eq!(cells[0].2, "A2*2");
+    }
+
     #[test]
     fn test_inline_string_cell() {
         let sst = SharedStringTable::new();
@@ -611,7 +647,7 @@ mod tests {
             t: Some("Inline Text".to_string()),
         }));
         let ws = make_worksheet(vec![make_row(1, vec![cell])]);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let cell_data_start = HEADER_SIZE + 8;
         let row1_offset = read_u32_le(&buf, HEADER_SIZE + 4);
@@ -629,7 +665,7 @@ mod tests {
             1,
             vec![make_cell("A1", 1, CellTypeTag::Date, Some("44927.0"))],
         )]);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let cell_data_start = HEADER_SIZE + 8;
         let row1_offset = read_u32_le(&buf, HEADER_SIZE + 4);
@@ -641,6 +677,24 @@ mod tests {
         assert!((val - 44927.0).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn test_date_cell_1904_date_system() {
+        let sst = SharedStringTable::new();
+        let ws = make_worksheet(vec![make_row(
+            1,
+            vec![make_cell("A1", 1, CellTypeTag::Date, Some("43465"))],
+        )]);
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1904).unwrap();
+
+        let cell_data_start = HEADER_SIZE + 8;
+        let row1_offset = read_u32_le(&buf, HEADER_SIZE + 4);
+        let cells = read_v2_row_cells(&buf, cell_data_start + row1_offset as usize);
+
+        assert_eq!(cells[0].1, TYPE_DATE);
+        let val: f64 = cells[0].2.parse().unwrap();
+        assert!((val - 44927.0).abs() < f64::EPSILON);
+    }
+
     #[test]
     fn test_mixed_types_row() {
         let sst = make_sst(&["Hello"]);
@@ -652,7 +706,7 @@ mod tests {
                 make_cell("C1", 3, CellTypeTag::Boolean, Some("1")),
             ],
         )]);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let cell_data_start = HEADER_SIZE + 8;
         let row1_offset = read_u32_le(&buf, HEADER_SIZE + 4);
@@ -676,7 +730,7 @@ mod tests {
             ),
         ];
         let ws = make_worksheet(rows);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let row_count = read_u32_le(&buf, 6) as usize;
         assert_eq!(row_count, 100);
@@ -703,7 +757,7 @@ mod tests {
             make_row(1, vec![make_cell("A1", 1, CellTypeTag::None, Some("1"))]),
             make_row(3, vec![make_cell("A3", 1, CellTypeTag::None, Some("3"))]),
         ]);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let row_count = read_u32_le(&buf, 6) as usize;
         assert_eq!(row_count, 3);
@@ -737,7 +791,7 @@ mod tests {
             ),
             make_row(5, vec![make_cell("C5", 3, CellTypeTag::None, Some("30"))]),
         ]);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         assert_eq!(read_u32_le(&buf, 0), MAGIC_V2);
         assert_eq!(read_u16_le(&buf, 4), VERSION_V2);
@@ -757,7 +811,7 @@ mod tests {
                 make_cell("B1", 2, CellTypeTag::SharedString, Some("1")),
             ],
         )]);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         // v2 has no string table section: after header + row_index comes cell data directly
         let cell_data_start = HEADER_SIZE + 8; // 1 row * 8 bytes
@@ -786,7 +840,7 @@ mod tests {
         )]);
         // InlineString uses `is` field; set it via the cell is field
         // Actually InlineString with v.as_deref() fallback will use `v` if `is` is None
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let cell_data_start = HEADER_SIZE + 8;
         let row1_offset = read_u32_le(&buf, HEADER_SIZE + 4);
@@ -809,7 +863,7 @@ mod tests {
                 Some("computed"),
             )],
         )]);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let cell_data_start = HEADER_SIZE + 8;
         let row1_offset = read_u32_le(&buf, HEADER_SIZE + 4);
@@ -848,7 +902,7 @@ mod tests {
             1,
             vec![make_cell("A1", 1, CellTypeTag::SharedString, Some("1"))],
         )]);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let cell_data_start = HEADER_SIZE + 8;
         let row1_offset = read_u32_le(&buf, HEADER_SIZE + 4);
@@ -877,7 +931,7 @@ mod tests {
                 ],
             ),
         ]);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let row_count = read_u32_le(&buf, 6) as usize;
         assert_eq!(row_count, 2);
@@ -905,7 +959,7 @@ mod tests {
         let mut cell = make_cell("C5", 0, CellTypeTag::None, Some("42"));
         cell.col = 0;
         let ws = make_worksheet(vec![make_row(5, vec![cell])]);
-        let buf = sheet_to_raw_buffer_v2(&ws, &sst).unwrap();
+        let buf = sheet_to_raw_buffer_v2(&ws, &sst, DateSystem::Date1900).unwrap();
 
         let row_count = read_u32_le(&buf, 6);
         assert_eq!(row_count, 1);
@@ -16,12 +16,14 @@ use crate::utils::constants::{MAX_ROWS, MAX_ROW_HEIGHT};
 ///
 /// Returns a Vec of `(row_number, Vec<(column_number, CellValue)>)` tuples.
 /// Column numbers are 1-based (A=1, B=2, ...). Only rows that contain at
-/// least one cell are included (sparse).
+/// least one cell are included (sparse). Shared formula children are
+/// returned with their expanded formula text.
 #[allow(clippy::type_complexity)]
 pub fn get_rows(
     ws: &WorksheetXml,
     sst: &SharedStringTable,
 ) -> Result<Vec<(u32, Vec<(u32, CellValue)>)>> {
+    let masters = crate::shared_formula::collect_shared_masters(ws);
     let mut result = Vec::new();
 
     for row in &ws.sheet_data.rows {
@@ -36,7 +38,14 @@ pub fn get_rows(
             } else {
                 cell_name_to_coordinates(cell.r.as_str())?.0
             };
-            let value = resolve_cell_value(cell, sst);
+            let mut value = resolve_cell_value(cell, sst);
+            if let CellValue::Formula { ref mut expr, .. } = value {
+                if let Some(text) =
+                    crate::shared_formula::effective_formula(cell, col_num, row.r, &masters)?
+                {
+                    *expr = text;
+                }
+            }
             cells.push((col_num, value));
         }
 
@@ -60,7 +69,8 @@ pub fn resolve_sst_value(sst: &SharedStringTable, index: &str) -> CellValue {
 /// Resolve a cell's typed value from its raw XML components.
 ///
 /// Handles all cell type tags (shared string, boolean, error, inline string,
-/// formula string, number) and SST lookup. This is the core dispatch that both
+/// formula string, date, number) and SST lookup. A date serial is returned
+/// as stored, in the date system of the workbook. This is the core dispatch that both
 /// `resolve_cell_value` and future buffer-pack paths share.
 pub fn parse_cell_type_value(
     cell_type: CellTypeTag,
@@ -95,6 +105,10 @@ pub fn parse_cell_type_value(
             CellValue::String(s)
         }
         (CellTypeTag::FormulaString, Some(v)) => CellValue::String(v.to_string()),
+        (CellTypeTag::Date, Some(v)) => match v.parse::<f64>() {
+            Ok(n) => CellValue::Date(n),
+            Err(_) => CellValue::Empty,
+        },
         (CellTypeTag::None | CellTypeTag::Number, Some(v)) => match v.parse::<f64>() {
             Ok(n) => CellValue::Number(n),
             Err(_) => CellValue::Empty,
@@ -152,19 +166,28 @@ pub fn insert_rows(ws: &mut WorksheetXml, start_row: u32, count: u32) -> Result<
 
 /// Remove a single row, shifting rows below it upward by one.
 pub fn remove_row(ws: &mut WorksheetXml, row: u32) -> Result<()> {
-    if row == 0 {
+    remove_rows(ws, row, 1)
+}
+
+/// Remove `count` rows starting at `start_row`, shifting rows below them
+/// upward by `count` in a single pass.
+pub fn remove_rows(ws: &mut WorksheetXml, start_row: u32, count: u32) -> Result<()> {
+    if start_row == 0 {
         return Err(Error::InvalidRowNumber(0));
     }
-
-    // Remove the target row via binary search.
-    if let Ok(idx) = ws.sheet_data.rows.binary_search_by_key(&row, |r| r.r) {
-        ws.sheet_data.rows.remove(idx);
+    if count == 0 {
+        return Ok(());
     }
+    let end_row = start_row.saturating_add(count);
+
+    ws.sheet_data
+        .rows
+        .retain(|r| r.r < start_row || r.r >= end_row);
 
-    // Shift rows above `row` upward.
+    // Shift rows below the removed ones upward.
     for r in ws.sheet_data.rows.iter_mut() {
-        if r.r > row {
-            let new_row_num = r.r - 1;
+        if r.r >= end_row {
+            let new_row_num = r.r - count;
             shift_row_cells(r, new_row_num)?;
             r.r = new_row_num;
         }
@@ -341,7 +364,7 @@ fn shift_row_cells(row: &mut Row, new_row_num: u32) -> Result<()> {
 
 /// Find an existing row or create a new empty one, keeping rows sorted.
 /// Uses binary search for O(log n) lookup instead of linear scan.
-fn find_or_create_row(ws: &mut WorksheetXml, row: u32) -> &mut Row {
+pub(crate) fn find_or_create_row(ws: &mut WorksheetXml, row: u32) -> &mut Row {
     match ws.sheet_data.rows.binary_search_by_key(&row, |r| r.r) {
         Ok(idx) => &mut ws.sheet_data.rows[idx],
         Err(pos) => {
@@ -554,6 +577,26 @@ mod tests {
         assert_eq!(ws.sheet_data.rows[2].r, 4); // row 5 -> 4
     }
 
+    #[test]
+    fn test_remove_rows_shifts_up_by_count() {
+        let mut ws = sample_ws();
+        remove_rows(&mut ws, 2, 3).unwrap();
+
+        // Rows 2-4 are gone; row 5 shifted to 2.
+        assert_eq!(ws.sheet_data.rows.len(), 2);
+        assert_eq!(ws.sheet_data.rows[0].r, 1);
+        assert_eq!(ws.sheet_data.rows[1].r, 2);
+        assert_eq!(ws.sheet_data.rows[1].cells[0].r, "C2");
+    }
+
+    #[test]
+    fn test_remove_rows_zero_count_is_noop() {
+        let mut ws = sample_ws();
+        remove_rows(&mut ws, 1, 0).unwrap();
+        assert_eq!(ws.sheet_data.rows.len(), 3);
+        assert!(remove_rows(&mut ws, 0, 1).is_err());
+    }
+
     #[test]
     fn test_remove_row_zero_returns_error() {
         let mut ws = sample_ws();
@@ -1052,6 +1095,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_get_rows_expands_shared_formula_children() {
+        let shared_cell = |r: &str, value: Option<&str>| Cell {
+            r: r.into(),
+            col: 2,
+            s: None,
+            t: CellTypeTag::None,
+            v: None,
+            f: Some(Box::new(sheetkit_xml::worksheet::CellFormula {
+                t: Some("shared".to_string()),
+                reference: value.map(|_| "B1:B2".to_string()),
+                si: Some(0),
+                value: value.map(str::to_string),
+            })),
+            is: None,
+        };
+        let mut ws = WorksheetXml::default();
+        ws.sheet_data = SheetData {
+            rows: [(1, Some("A1*2")), (2, None)]
+                .into_iter()
+                .map(|(r, value)| Row {
+                    r,
+                    spans: None,
+                    s: None,
+                    custom_format: None,
+                    ht: None,
+                    hidden: None,
+                    custom_height: None,
+                    outline_level: None,
+                    cells: vec![shared_cell(&format!("B{r}"), value)],
+                })
+                .collect(),
+        };
+
+        let sst = SharedStringTable::new();
+        let rows = get_rows(&ws, &sst).unwrap();
+        match &rows[1].1[0].1 {
+            CellValue::Formula { expr, .. } => assert_eq!(expr, "A2*2"),
+            other => panic!("expected Formula, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_get_rows_with_inline_string() {
         let mut ws = WorksheetXml::default();
@@ -0,0 +1,372 @@
+//! Shared formula support.
+//!
+//! Excel stores a block of similar formulas once, on the top-left "master"
+//! cell of the block (`<f t="shared" ref="B2:B10" si="0">A2*2</f>`), and
+//! marks every other cell of the block with only the group index
+//! (`<f t="shared" si="0"/>`). A child's effective formula is the master
+//! text with its relative references shifted by the child's offset from
+//! the master.
+//!
+//! This module expands child cells into their effective formula text and
+//! converts between the shared and standalone representations.
+
+use std::collections::{BTreeMap, HashMap};
+
+use sheetkit_xml::worksheet::{Cell, CellFormula, WorksheetXml};
+
+use crate::cell_ref_shift::shift_formula_by_offset;
+use crate::error::Result;
+use crate::utils::cell_ref::{cell_name_to_coordinates, coordinates_to_cell_name};
+
+/// The master cell of a shared formula group.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SharedFormulaMaster {
+    pub col: u32,
+    pub row: u32,
+    pub formula: String,
+}
+
+impl SharedFormulaMaster {
+    /// Translate the master formula to the cell at `(col, row)`.
+    pub fn expand(&self, col: u32, row: u32) -> Result<String> {
+        shift_formula_by_offset(
+            &self.formula,
+            col as i64 - self.col as i64,
+            row as i64 - self.row as i64,
+        )
+    }
+}
+
+/// Return `true` if the formula element is part of a shared formula group.
+pub(crate) fn is_shared(f: &CellFormula) -> bool {
+    f.t.as_deref() == Some("shared") && f.si.is_some()
+}
+
+/// Return the group index of a shared formula child, i.e. a cell that
+/// carries `t="shared"` and `si` but no formula text of its own.
+pub(crate) fn shared_child_si(cell: &Cell) -> Option<u32> {
+    let f = cell.f.as_ref()?;
+    if is_shared(f) && f.value.as_deref().unwrap_or_default().is_empty() {
+        f.si
+    } else {
+        None
+    }
+}
+
+/// Return the group index if `cell` is the master of a shared formula group.
+pub(crate) fn shared_master_si(cell: &Cell) -> Option<u32> {
+    let f = cell.f.as_ref()?;
+    if is_shared(f) && !f.value.as_deref().unwrap_or_default().is_empty() {
+        f.si
+    } else {
+        None
+    }
+}
+
+fn cell_coordinates(cell: &Cell) -> Option<(u32, u32)> {
+    if cell.col > 0 {
+        let (_, row) = cell_name_to_coordinates(cell.r.as_str()).ok()?;
+        Some((cell.col, row))
+    } else {
+        cell_name_to_coordinates(cell.r.as_str()).ok()
+    }
+}
+
+/// Collect the master cell of every shared formula group on the sheet,
+/// keyed by group index.
+pub(crate) fn collect_shared_masters(ws: &WorksheetXml) -> HashMap<u32, SharedFormulaMaster> {
+    let mut masters = HashMap::new();
+    for row in &ws.sheet_data.rows {
+        for cell in &row.cells {
+            let Some(si) = shared_master_si(cell) else {
+                continue;
+            };
+            let Some((col, row)) = cell_coordinates(cell) else {
+                continue;
+            };
+            let formula = cell
+                .f
+                .as_ref()
+                .and_then(|f| f.value.clone())
+                .unwrap_or_default();
+            masters
+                .entry(si)
+                .or_insert(SharedFormulaMaster { col, row, formula });
+        }
+    }
+    masters
+}
+
+/// Return the effective formula text of a cell, expanding shared formula
+/// children against `masters`. Returns `None` for cells without a formula
+/// or whose shared master cannot be found.
+pub(crate) fn effective_formula(
+    cell: &Cell,
+    col: u32,
+    row: u32,
+    masters: &HashMap<u32, SharedFormulaMaster>,
+) -> Result<Option<String>> {
+    let Some(f) = cell.f.as_ref() else {
+        return Ok(None);
+    };
+    if let Some(si) = shared_child_si(cell) {
+        return match masters.get(&si) {
+            Some(master) => master.expand(col, row).map(Some),
+            None => Ok(None),
+        };
+    }
+    Ok(f.value.clone().filter(|v| !v.is_empty()))
+}
+
+/// Convert shared formulas into standalone formulas on every cell of the
+/// group. When `only_si` is `Some`, only that group is converted.
+///
+/// Returns the number of cells rewritten.
+pub(crate) fn unshare_formulas(ws: &mut WorksheetXml, only_si: Option<u32>) -> Result<usize> {
+    let masters = collect_shared_masters(ws);
+    let mut rewritten = 0;
+    for row in &mut ws.sheet_data.rows {
+        for cell in &mut row.cells {
+            let Some(f) = cell.f.as_ref() else {
+                continue;
+            };
+            if !is_shared(f) {
+                continue;
+            }
+            let si = f.si.unwrap_or_default();
+            if only_si.is_some_and(|only| only != si) {
+                continue;
+            }
+            let Some((col, r)) = cell_coordinates(cell) else {
+                continue;
+            };
+            let text = match f.value.as_deref() {
+                Some(v) if !v.is_empty() => v.to_string(),
+                _ => match masters.get(&si) {
+                    Some(master) => master.expand(col, r)?,
+                    // Orphaned child: there is no text to recover.
+                    None => continue,
+                },
+            };
+            cell.f = Some(Box::new(CellFormula {
+                t: None,
+                reference: None,
+                si: None,
+                value: Some(text),
+            }));
+            rewritten += 1;
+        }
+    }
+    Ok(rewritten)
+}
+
+/// Group vertical runs of equivalent formulas into shared formulas.
+///
+/// Existing shared groups are expanded first. Afterwards, every run of at
+/// least two consecutive cells in a column whose formulas are relative
+/// copies of the first cell's formula becomes one shared group. Array
+/// formulas are left untouched.
+///
+/// Returns the number of shared groups created.
+pub(crate) fn share_formulas(ws: &mut WorksheetXml) -> Result<usize> {
+    unshare_formulas(ws, None)?;
+
+    // column -> [(row, row_index, cell_index, formula)], ordered by row.
+    let mut by_col: BTreeMap<u32, Vec<(u32, usize, usize, String)>> = BTreeMap::new();
+    for (ri, row) in ws.sheet_data.rows.iter().enumerate() {
+        for (ci, cell) in row.cells.iter().enumerate() {
+            let Some(f) = cell.f.as_ref() else {
+                continue;
+            };
+            if f.t.is_some() {
+                continue;
+            }
+            let Some(text) = f.value.as_deref().filter(|v| !v.is_empty()) else {
+                continue;
+            };
+            let Some((col, r)) = cell_coordinates(cell) else {
+                continue;
+            };
+            by_col
+                .entry(col)
+                .or_default()
+                .push((r, ri, ci, text.to_string()));
+        }
+    }
+
+    let mut next_si = 0u32;
+    for (col, cells) in by_col {
+        let mut start = 0;
+        while start < cells.len() {
+            let (master_row, _, _, ref master_text) = cells[start];
+            let master = SharedFormulaMaster {
+                col,
+                row: master_row,
+                formula: master_text.clone(),
+            };
+            let mut end = start + 1;
+            while end < cells.len() {
+                let (r, _, _, ref text) = cells[end];
+                if r != cells[end - 1].0 + 1 || master.expand(col, r)? != *text {
+                    break;
+                }
+                end += 1;
+            }
+            if end - start >= 2 {
+                let last_row = cells[end - 1].0;
+                let reference = format!(
+                    "{}:{}",
+                    coordinates_to_cell_name(col, master_row)?,
+                    coordinates_to_cell_name(col, last_row)?
+                );
+                for (i, (_, ri, ci, _)) in cells[start..end].iter().enumerate() {
+                    let f = ws.sheet_data.rows[*ri].cells[*ci].f.as_mut().unwrap();
+                    f.t = Some("shared".to_string());
+                    f.si = Some(next_si);
+                    if i == 0 {
+                        f.reference = Some(reference.clone());
+                    } else {
+                        f.value = None;
+                    }
+                }
+                next_si += 1;
+            }
+            start = end;
+        }
+    }
+    Ok(next_si as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sheetkit_xml::worksheet::{CellTypeTag, Row};
+
+    fn formula_cell(r: &str, f: CellFormula) -> Cell {
+        let (col, _) = cell_name_to_coordinates(r).unwrap();
+        Cell {
+            r: r.into(),
+            col,
+            s: None,
+            t: CellTypeTag::None,
+            v: None,
+            f: Some(Box::new(f)),
+            is: None,
+        }
+    }
+
+    fn shared(value: Option<&str>, reference: Option<&str>, si: u32) -> CellFormula {
+        CellFormula {
+            t: Some("shared".to_string()),
+            reference: reference.map(str::to_string),
+            si: Some(si),
+            value: value.map(str::to_string),
+        }
+    }
+
+    fn plain(value: &str) -> CellFormula {
+        CellFormula {
+            t: None,
+            reference: None,
+            si: None,
+            value: Some(value.to_string()),
+        }
+    }
+
+    fn sheet_with(cells: Vec<(u32, Cell)>) -> WorksheetXml {
+        let mut ws = WorksheetXml::default();
+        for (r, cell) in cells {
+            ws.sheet_data.rows.push(Row {
+                r,
+                spans: None,
+                s: None,
+                custom_format: None,
+                ht: None,
+                hidden: None,
+                custom_height: None,
+                outline_level: None,
+                cells: vec![cell],
+            });
+        }
+        ws
+    }
+
+    fn formula_text(ws: &WorksheetXml, row_idx: usize) -> Option<String> {
+        ws.sheet_data.rows[row_idx].cells[0]
+            .f
+            .as_ref()
+            .and_then(|f| f.value.clone())
+    }
+
+    #[test]
+    fn test_expand_shared_child() {
+        let ws = sheet_with(vec![
+            (
+                2,
+                formula_cell("B2", shared(Some("A2*$C$1"), Some("B2:B4"), 0)),
+            ),
+            (3, formula_cell("B3", shared(None, None, 0))),
+            (4, formula_cell("B4", shared(None, None, 0))),
+        ]);
+        let masters = collect_shared_masters(&ws);
+        let child = &ws.sheet_data.rows[2].cells[0];
+        assert_eq!(shared_child_si(child), Some(0));
+        assert_eq!(
+            effective_formula(child, 2, 4, &masters).unwrap().as_deref(),
+            Some("A4*$C$1")
+        );
+        assert_eq!(masters[&0].expand(3, 3).unwrap(), "B3*$C$1");
+    }
+
+    #[test]
+    fn test_expand_shared_child_with_digit_function_name() {
+        let ws = sheet_with(vec![
+            (
+                1,
+                formula_cell("B1", shared(Some("LOG10(A1)"), Some("B1:B3"), 0)),
+            ),
+            (3, formula_cell("B3", shared(None, None, 0))),
+        ]);
+        let masters = collect_shared_masters(&ws);
+        assert_eq!(masters[&0].expand(2, 3).unwrap(), "LOG10(A3)");
+    }
+
+    #[test]
+    fn test_unshare_formulas() {
+        let mut ws = sheet_with(vec![
+            (
+                1,
+                formula_cell("A1", shared(Some("B1+1"), Some("A1:A2"), 0)),
+            ),
+            (2, formula_cell("A2", shared(None, None, 0))),
+        ]);
+        assert_eq!(unshare_formulas(&mut ws, None).unwrap(), 2);
+        assert_eq!(formula_text(&ws, 0).as_deref(), Some("B1+1"));
+        assert_eq!(formula_text(&ws, 1).as_deref(), Some("B2+1"));
+        assert!(ws.sheet_data.rows[1].cells[0]
+            .f
+            .as_ref()
+            .unwrap()
+            .t
+            .is_none());
+    }
+
+    #[test]
+    fn test_share_formulas_groups_vertical_runs() {
+        let mut ws = sheet_with(vec![
+            (1, formula_cell("A1", plain("B1*2"))),
+            (2, formula_cell("A2", plain("B2*2"))),
+            (3, formula_cell("A3", plain("B3*2"))),
+            (4, formula_cell("A4", plain("SUM(B1:B3)"))),
+        ]);
+        assert_eq!(share_formulas(&mut ws).unwrap(), 1);
+        let master = ws.sheet_data.rows[0].cells[0].f.as_ref().unwrap();
+        assert_eq!(master.reference.as_deref(), Some("A1:A3"));
+        assert_eq!(master.value.as_deref(), Some("B1*2"));
+        assert_eq!(shared_child_si(&ws.sheet_data.rows[2].cells[0]), Some(0));
+        assert_eq!(formula_text(&ws, 3).as_deref(), Some("SUM(B1:B3)"));
+
+        assert_eq!(unshare_formulas(&mut ws, None).unwrap(), 3);
+        assert_eq!(formula_text(&ws, 2).as_deref(), Some("B3*2"));
+    }
+}
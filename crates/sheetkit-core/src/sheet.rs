@@ -1,7 +1,7 @@
 //! Sheet management utilities.
 //!
 //! Contains validation helpers and internal functions used by [`crate::workbook::Workbook`]
-//! for creating, deleting, renaming, and copying worksheets.
+//! for creating, deleting, renaming, copying, and moving worksheets.
 
 use std::sync::OnceLock;
 
@@ -221,6 +221,66 @@ pub fn copy_sheet(
     )
 }
 
+/// Move a sheet to a new 0-based position, returning its previous index.
+///
+/// Reorders the sheet list and keeps the indices stored in the workbook
+/// part in step: `localSheetId` on defined names, and the active and first
+/// visible tab of each book view. Relationship targets are left as they
+/// are, so each sheet keeps its part path.
+pub fn move_sheet(
+    workbook_xml: &mut WorkbookXml,
+    worksheets: &mut Vec<(String, OnceLock<WorksheetXml>)>,
+    name: &str,
+    new_index: usize,
+) -> Result<usize> {
+    let idx = find_sheet_index(worksheets, name).ok_or_else(|| Error::SheetNotFound {
+        name: name.to_string(),
+    })?;
+    if new_index >= worksheets.len() {
+        return Err(Error::InvalidArgument(format!(
+            "sheet index {new_index} is out of range for {} sheets",
+            worksheets.len()
+        )));
+    }
+    if idx == new_index {
+        return Ok(idx);
+    }
+
+    let entry = worksheets.remove(idx);
+    worksheets.insert(new_index, entry);
+    let entry = workbook_xml.sheets.sheets.remove(idx);
+    workbook_xml.sheets.sheets.insert(new_index, entry);
+
+    let remap = |i: u32| moved_sheet_index(i as usize, idx, new_index) as u32;
+    if let Some(defined_names) = workbook_xml.defined_names.as_mut() {
+        for dn in &mut defined_names.defined_names {
+            dn.local_sheet_id = dn.local_sheet_id.map(remap);
+        }
+    }
+    if let Some(book_views) = workbook_xml.book_views.as_mut() {
+        for view in &mut book_views.workbook_views {
+            view.active_tab = view.active_tab.map(remap);
+            view.first_sheet = view.first_sheet.map(remap);
+        }
+    }
+
+    Ok(idx)
+}
+
+/// Map a sheet index from before moving the sheet at `from` to `to` onto
+/// its index afterwards.
+pub(crate) fn moved_sheet_index(idx: usize, from: usize, to: usize) -> usize {
+    if idx == from {
+        to
+    } else if from < to && idx > from && idx <= to {
+        idx - 1
+    } else if from > to && idx >= to && idx < from {
+        idx + 1
+    } else {
+        idx
+    }
+}
+
 /// Get the active sheet index (0-based) from bookViews, defaulting to 0.
 pub fn active_sheet_index(workbook_xml: &WorkbookXml) -> usize {
     workbook_xml
@@ -241,6 +301,7 @@ pub fn set_active_sheet_index(workbook_xml: &mut WorkbookXml, index: u32) {
             y_window: None,
             window_width: None,
             window_height: None,
+            first_sheet: None,
             active_tab: Some(0),
         }],
     });
@@ -25,7 +25,7 @@
 
 use std::io::{BufWriter, Write as _};
 
-use crate::cell::CellValue;
+use crate::cell::{CellValue, DateSystem};
 use crate::error::{Error, Result};
 use crate::utils::cell_ref::cell_name_to_coordinates;
 use crate::utils::constants::{MAX_COLUMNS, MAX_ROWS, MAX_ROW_HEIGHT};
@@ -109,6 +109,8 @@ pub struct StreamWriter {
     merge_cells: Vec<String>,
     /// Freeze pane: (x_split, y_split, top_left_cell).
     freeze_pane: Option<(u32, u32, String)>,
+    /// Date system that date values are written in.
+    date_system: DateSystem,
 }
 
 // StreamWriter contains a BufWriter<NamedTempFile> which is not Send by
@@ -143,9 +145,18 @@ impl StreamWriter {
             col_options: Vec::new(),
             merge_cells: Vec::new(),
             freeze_pane: None,
+            date_system: DateSystem::Date1900,
         }
     }
 
+    /// Set the date system [`CellValue::Date`] values are converted to when
+    /// written. Writers created by
+    /// [`Workbook::new_stream_writer`](crate::workbook::Workbook::new_stream_writer)
+    /// use the date system of the workbook.
+    pub fn set_date_system(&mut self, date_system: DateSystem) {
+        self.date_system = date_system;
+    }
+
     /// Get the sheet name.
     pub fn sheet_name(&self) -> &str {
         &self.sheet_name
@@ -493,7 +504,7 @@ impl StreamWriter {
         self.last_row = row;
 
         // Build row XML directly and write to temp file.
-        let xml = build_row_xml(row, values, cell_style_id, options);
+        let xml = build_row_xml(row, values, cell_style_id, options, self.date_system);
         let bytes = xml.as_bytes();
         self.writer.write_all(bytes)?;
         self.bytes_written += bytes.len() as u64;
@@ -571,6 +582,7 @@ fn build_row_xml(
     values: &[CellValue],
     cell_style_id: Option<u32>,
     options: Option<&StreamRowOptions>,
+    date_system: DateSystem,
 ) -> String {
     let mut xml = String::with_capacity(128 + values.len() * 64);
     xml.push_str("<row r=\"");
@@ -636,7 +648,7 @@ fn build_row_xml(
             }
             CellValue::Date(serial) => {
                 xml.push_str("><v>");
-                xml.push_str(&serial.to_string());
+                xml.push_str(&date_system.from_1900_serial(*serial).to_string());
                 xml.push_str("</v></c>");
             }
             CellValue::Bool(b) => {
@@ -678,7 +690,7 @@ fn build_row_xml(
                         }
                         CellValue::Date(d) => {
                             xml.push_str("<v>");
-                            xml.push_str(&d.to_string());
+                            xml.push_str(&date_system.from_1900_serial(*d).to_string());
                             xml.push_str("</v>");
                         }
                         CellValue::Error(e) => {
@@ -1617,6 +1629,16 @@ mod tests {
         assert!(xml.contains("<v>45306.5</v>"));
     }
 
+    #[test]
+    fn test_date_value_1904_date_system() {
+        let mut sw = StreamWriter::new("Sheet1");
+        sw.set_date_system(DateSystem::Date1904);
+        sw.write_row(1, &[CellValue::Date(45306.0)]).unwrap();
+        let xml = finish_and_get_xml(sw);
+
+        assert!(xml.contains("<v>43844</v>"));
+    }
+
     #[test]
     fn test_rich_string_to_inline_plain_text() {
         use crate::rich_text::RichTextRun;
@@ -9,13 +9,15 @@
 //! [`SharedStringTable`]. Cell types (string, number, boolean, date, formula,
 //! error, inline string) are handled according to the OOXML specification.
 
+use std::collections::HashMap;
 use std::io::BufRead;
 
 use quick_xml::events::Event;
 use quick_xml::name::QName;
 
-use crate::cell::CellValue;
+use crate::cell::{CellValue, DateSystem};
 use crate::error::{Error, Result};
+use crate::shared_formula::SharedFormulaMaster;
 use crate::sst::SharedStringTable;
 use crate::utils::cell_ref::cell_name_to_coordinates;
 
@@ -39,6 +41,8 @@ pub struct SheetStreamReader<'a, R: BufRead> {
     done: bool,
     row_limit: Option<u32>,
     rows_emitted: u32,
+    shared_masters: HashMap<u32, SharedFormulaMaster>,
+    date_system: DateSystem,
 }
 
 impl<'a, R: BufRead> SheetStreamReader<'a, R> {
@@ -56,9 +60,19 @@ impl<'a, R: BufRead> SheetStreamReader<'a, R> {
             done: false,
             row_limit,
             rows_emitted: 0,
+            shared_masters: HashMap::new(),
+            date_system: DateSystem::Date1900,
         }
     }
 
+    /// Set the date system that date serials in the sheet are stored in.
+    /// They are returned converted to the 1900 date system. Readers created
+    /// by [`Workbook::open_sheet_reader`](crate::workbook::Workbook::open_sheet_reader)
+    /// use the date system of the workbook.
+    pub fn set_date_system(&mut self, date_system: DateSystem) {
+        self.date_system = date_system;
+    }
+
     /// Read the next batch of rows. Returns an empty `Vec` when there are no
     /// more rows to read.
     pub fn next_batch(&mut self, batch_size: usize) -> Result<Vec<StreamRow>> {
@@ -131,7 +145,7 @@ impl<'a, R: BufRead> SheetStreamReader<'a, R> {
                 Event::Start(ref e) if e.name() == QName(b"c") => {
                     let (col, cell_type) = extract_cell_attrs(e)?;
                     if let Some(col) = col {
-                        let cv = self.parse_cell_body(cell_type.as_deref())?;
+                        let cv = self.parse_cell_body(col, row_number, cell_type.as_deref())?;
                         cells.push((col, cv));
                     } else {
                         self.skip_to_end_of(b"c")?;
@@ -140,8 +154,14 @@ impl<'a, R: BufRead> SheetStreamReader<'a, R> {
                 Event::Empty(ref e) if e.name() == QName(b"c") => {
                     let (col, cell_type) = extract_cell_attrs(e)?;
                     if let Some(col) = col {
-                        let cv =
-                            resolve_cell_value(self.sst, cell_type.as_deref(), None, None, None)?;
+                        let cv = resolve_cell_value(
+                            self.sst,
+                            self.date_system,
+                            cell_type.as_deref(),
+                            None,
+                            None,
+                            None,
+                        )?;
                         cells.push((col, cv));
                     }
                 }
@@ -159,9 +179,15 @@ impl<'a, R: BufRead> SheetStreamReader<'a, R> {
 
     /// Parse the body of a `<c>` element (its child `<v>`, `<f>`, `<is>`
     /// elements) after the cell attributes have been extracted.
-    fn parse_cell_body(&mut self, cell_type: Option<&str>) -> Result<CellValue> {
+    fn parse_cell_body(
+        &mut self,
+        col: u32,
+        row_number: u32,
+        cell_type: Option<&str>,
+    ) -> Result<CellValue> {
         let mut value_text: Option<String> = None;
         let mut formula_text: Option<String> = None;
+        let mut shared_si: Option<u32> = None;
         let mut inline_string: Option<String> = None;
         let mut buf = Vec::with_capacity(512);
         let mut in_is = false;
@@ -178,6 +204,7 @@ impl<'a, R: BufRead> SheetStreamReader<'a, R> {
                     if local.as_ref() == b"v" {
                         value_text = Some(self.read_text_content(b"v")?);
                     } else if local.as_ref() == b"f" {
+                        shared_si = extract_shared_si(e)?;
                         formula_text = Some(self.read_text_content(b"f")?);
                     } else if local.as_ref() == b"is" {
                         in_is = true;
@@ -189,6 +216,10 @@ impl<'a, R: BufRead> SheetStreamReader<'a, R> {
                         }
                     }
                 }
+                Event::Empty(ref e) if e.local_name().as_ref() == b"f" => {
+                    shared_si = extract_shared_si(e)?;
+                    formula_text = Some(String::new());
+                }
                 Event::End(ref e) => {
                     let local = e.local_name();
                     if local.as_ref() == b"c" {
@@ -206,8 +237,18 @@ impl<'a, R: BufRead> SheetStreamReader<'a, R> {
             }
         }
 
+        if let Some(si) = shared_si {
+            formula_text = resolve_shared_formula(
+                &mut self.shared_masters,
+                si,
+                formula_text,
+                col,
+                row_number,
+            )?;
+        }
         resolve_cell_value(
             self.sst,
+            self.date_system,
             cell_type,
             value_text.as_deref(),
             formula_text,
@@ -284,6 +325,8 @@ pub struct OwnedSheetStreamReader {
     done: bool,
     row_limit: Option<u32>,
     rows_emitted: u32,
+    shared_masters: HashMap<u32, SharedFormulaMaster>,
+    date_system: DateSystem,
 }
 
 impl OwnedSheetStreamReader {
@@ -303,9 +346,19 @@ impl OwnedSheetStreamReader {
             done: false,
             row_limit,
             rows_emitted: 0,
+            shared_masters: HashMap::new(),
+            date_system: DateSystem::Date1900,
         }
     }
 
+    /// Set the date system that date serials in the sheet are stored in.
+    /// They are returned converted to the 1900 date system. Readers created
+    /// by [`Workbook::open_sheet_reader`](crate::workbook::Workbook::open_sheet_reader)
+    /// use the date system of the workbook.
+    pub fn set_date_system(&mut self, date_system: DateSystem) {
+        self.date_system = date_system;
+    }
+
     /// Read the next batch of rows. Returns an empty `Vec` when there are no
     /// more rows to read.
     pub fn next_batch(&mut self, batch_size: usize) -> Result<Vec<StreamRow>> {
@@ -376,7 +429,7 @@ impl OwnedSheetStreamReader {
                 Event::Start(ref e) if e.name() == QName(b"c") => {
                     let (col, cell_type) = extract_cell_attrs(e)?;
                     if let Some(col) = col {
-                        let cv = self.parse_cell_body(cell_type.as_deref())?;
+                        let cv = self.parse_cell_body(col, row_number, cell_type.as_deref())?;
                         cells.push((col, cv));
                     } else {
                         self.skip_to_end_of(b"c")?;
@@ -385,8 +438,14 @@ impl OwnedSheetStreamReader {
                 Event::Empty(ref e) if e.name() == QName(b"c") => {
                     let (col, cell_type) = extract_cell_attrs(e)?;
                     if let Some(col) = col {
-                        let cv =
-                            resolve_cell_value(&self.sst, cell_type.as_deref(), None, None, None)?;
+                        let cv = resolve_cell_value(
+                            &self.sst,
+                            self.date_system,
+                            cell_type.as_deref(),
+                            None,
+                            None,
+                            None,
+                        )?;
                         cells.push((col, cv));
                     }
                 }
@@ -402,9 +461,15 @@ impl OwnedSheetStreamReader {
         Ok(StreamRow { row_number, cells })
     }
 
-    fn parse_cell_body(&mut self, cell_type: Option<&str>) -> Result<CellValue> {
+    fn parse_cell_body(
+        &mut self,
+        col: u32,
+        row_number: u32,
+        cell_type: Option<&str>,
+    ) -> Result<CellValue> {
         let mut value_text: Option<String> = None;
         let mut formula_text: Option<String> = None;
+        let mut shared_si: Option<u32> = None;
         let mut inline_string: Option<String> = None;
         let mut buf = Vec::with_capacity(512);
         let mut in_is = false;
@@ -421,6 +486,7 @@ impl OwnedSheetStreamReader {
                     if local.as_ref() == b"v" {
                         value_text = Some(self.read_text_content(b"v")?);
                     } else if local.as_ref() == b"f" {
+                        shared_si = extract_shared_si(e)?;
                         formula_text = Some(self.read_text_content(b"f")?);
                     } else if local.as_ref() == b"is" {
                         in_is = true;
@@ -432,6 +498,10 @@ impl OwnedSheetStreamReader {
                         }
                     }
                 }
+                Event::Empty(ref e) if e.local_name().as_ref() == b"f" => {
+                    shared_si = extract_shared_si(e)?;
+                    formula_text = Some(String::new());
+                }
                 Event::End(ref e) => {
                     let local = e.local_name();
                     if local.as_ref() == b"c" {
@@ -449,8 +519,18 @@ impl OwnedSheetStreamReader {
             }
         }
 
+        if let Some(si) = shared_si {
+            formula_text = resolve_shared_formula(
+                &mut self.shared_masters,
+                si,
+                formula_text,
+                col,
+                row_number,
+            )?;
+        }
         resolve_cell_value(
             &self.sst,
+            self.date_system,
             cell_type,
             value_text.as_deref(),
             formula_text,
@@ -564,9 +644,54 @@ fn extract_cell_attrs(
     Ok((col, cell_type))
 }
 
+/// Return the group index of a shared `<f t="shared" si="..">` element.
+fn extract_shared_si(start: &quick_xml::events::BytesStart<'_>) -> Result<Option<u32>> {
+    let mut shared = false;
+    let mut si = None;
+    for attr in start.attributes().flatten() {
+        match attr.key {
+            QName(b"t") => shared = attr.value.as_ref() == b"shared",
+            QName(b"si") => {
+                let val =
+                    std::str::from_utf8(&attr.value).map_err(|e| Error::XmlParse(e.to_string()))?;
+                si = val.parse::<u32>().ok();
+            }
+            _ => {}
+        }
+    }
+    Ok(si.filter(|_| shared))
+}
+
+/// Record a shared formula master, or expand a child against the master
+/// seen earlier in the sheet. The master is the top-left cell of its group,
+/// so it is always read before its children.
+fn resolve_shared_formula(
+    masters: &mut HashMap<u32, SharedFormulaMaster>,
+    si: u32,
+    formula_text: Option<String>,
+    col: u32,
+    row: u32,
+) -> Result<Option<String>> {
+    match formula_text {
+        Some(formula) if !formula.is_empty() => {
+            masters.entry(si).or_insert(SharedFormulaMaster {
+                col,
+                row,
+                formula: formula.clone(),
+            });
+            Ok(Some(formula))
+        }
+        _ => match masters.get(&si) {
+            Some(master) => master.expand(col, row).map(Some),
+            None => Ok(None),
+        },
+    }
+}
+
 /// Resolve cell type, value text, formula, and inline string into a `CellValue`.
 fn resolve_cell_value(
     sst: &SharedStringTable,
+    date_system: DateSystem,
     cell_type: Option<&str>,
     value_text: Option<&str>,
     formula_text: Option<String>,
@@ -607,7 +732,7 @@ fn resolve_cell_value(
             let n: f64 = v
                 .parse()
                 .map_err(|_| Error::Internal(format!("invalid date value: {v}")))?;
-            Ok(CellValue::Date(n))
+            Ok(CellValue::Date(date_system.to_1900_serial(n)))
         }
         (Some("n") | None, Some(v)) => {
             let n: f64 = v
@@ -732,6 +857,16 @@ mod tests {
         assert_eq!(cells[6], (7, CellValue::Date(45000.0)));
     }
 
+    #[test]
+    fn test_date_cell_1904_date_system() {
+        let sst = SharedStringTable::new();
+        let xml = worksheet_xml(r#"<row r="1"><c r="A1" t="d"><v>43465</v></c></row>"#);
+        let mut reader = SheetStreamReader::new(Cursor::new(xml.into_bytes()), &sst, None);
+        reader.set_date_system(DateSystem::Date1904);
+        let rows = reader.next_batch(10).unwrap();
+        assert_eq!(rows[0].cells[0].1, CellValue::Date(44927.0));
+    }
+
     #[test]
     fn test_boolean_false() {
         let sst = SharedStringTable::new();
@@ -929,6 +1064,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_shared_formula_children_are_expanded() {
+        let sst = SharedStringTable::new();
+        let xml = worksheet_xml(concat!(
+            r#"<row r="1"><c r="B1"><f t="shared" ref="B1:B3" si="0">LOG10(A1)</f><v>0</v></c></row>"#,
+            r#"<row r="2"><c r="B2"><f t="shared" si="0"/><v>1</v></c></row>"#,
+            r#"<row r="3"><c r="B3"><f t="shared" si="0"></f><v>2</v></c></row>"#,
+        ));
+
+        let rows = read_all(&xml, &sst, None);
+        let exprs: Vec<_> = rows
+            .iter()
+            .map(|row| match &row.cells[0].1 {
+                CellValue::Formula { expr, .. } => expr.clone(),
+                other => panic!("expected Formula, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(exprs, ["LOG10(A1)", "LOG10(A2)", "LOG10(A3)"]);
+    }
+
     #[test]
     fn test_inline_string_with_rich_text_runs() {
         let sst = SharedStringTable::new();
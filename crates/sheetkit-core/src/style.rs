@@ -1172,6 +1172,108 @@ pub fn get_style(stylesheet: &StyleSheet, style_id: u32) -> Option<Style> {
     })
 }
 
+/// Copy the cell format `style_id` of stylesheet `src` into `dst`, reusing
+/// identical fonts, fills, borders, number formats, and formats already in
+/// `dst`. Returns the style ID in `dst`.
+pub(crate) fn import_style(dst: &mut StyleSheet, src: &StyleSheet, style_id: u32) -> Result<u32> {
+    let xf = src
+        .cell_xfs
+        .xfs
+        .get(style_id as usize)
+        .ok_or(Error::StyleNotFound { id: style_id })?;
+
+    let font_id = xf
+        .font_id
+        .and_then(|id| src.fonts.fonts.get(id as usize))
+        .map(|font| {
+            let id = find_or_push(&mut dst.fonts.fonts, font);
+            dst.fonts.count = Some(dst.fonts.fonts.len() as u32);
+            id
+        });
+    let fill_id = xf
+        .fill_id
+        .and_then(|id| src.fills.fills.get(id as usize))
+        .map(|fill| {
+            let id = find_or_push(&mut dst.fills.fills, fill);
+            dst.fills.count = Some(dst.fills.fills.len() as u32);
+            id
+        });
+    let border_id = xf
+        .border_id
+        .and_then(|id| src.borders.borders.get(id as usize))
+        .map(|border| {
+            let id = find_or_push(&mut dst.borders.borders, border);
+            dst.borders.count = Some(dst.borders.borders.len() as u32);
+            id
+        });
+    let num_fmt_id = xf.num_fmt_id.map(|id| import_num_fmt(dst, src, id));
+
+    let xf = Xf {
+        num_fmt_id,
+        font_id,
+        fill_id,
+        border_id,
+        // Named cell styles are not carried over.
+        xf_id: Some(0),
+        ..xf.clone()
+    };
+    if let Some(i) = dst.cell_xfs.xfs.iter().position(|existing| *existing == xf) {
+        return Ok(i as u32);
+    }
+    if dst.cell_xfs.xfs.len() >= MAX_CELL_XFS {
+        return Err(Error::CellStylesExceeded { max: MAX_CELL_XFS });
+    }
+    dst.cell_xfs.xfs.push(xf);
+    dst.cell_xfs.count = Some(dst.cell_xfs.xfs.len() as u32);
+    Ok(dst.cell_xfs.xfs.len() as u32 - 1)
+}
+
+/// Copy the differential format `dxf_id` of stylesheet `src`, used by
+/// conditional formatting, into `dst`. Returns the ID in `dst`, or `None`
+/// if `src` has no such format.
+pub(crate) fn import_dxf(dst: &mut StyleSheet, src: &StyleSheet, dxf_id: u32) -> Option<u32> {
+    let mut dxf = src.dxfs.as_ref()?.dxfs.get(dxf_id as usize)?.clone();
+    if let Some(num_fmt) = dxf.num_fmt.as_mut() {
+        if num_fmt.num_fmt_id >= CUSTOM_NUM_FMT_BASE {
+            num_fmt.num_fmt_id = add_or_find_num_fmt(dst, &num_fmt.format_code);
+        }
+    }
+    let dxfs = dst.dxfs.get_or_insert_with(|| sheetkit_xml::styles::Dxfs {
+        count: Some(0),
+        dxfs: Vec::new(),
+    });
+    let id = find_or_push(&mut dxfs.dxfs, &dxf);
+    dxfs.count = Some(dxfs.dxfs.len() as u32);
+    Some(id)
+}
+
+/// Map a number format ID of `src` to `dst`, adding custom formats by
+/// their format code.
+fn import_num_fmt(dst: &mut StyleSheet, src: &StyleSheet, id: u32) -> u32 {
+    if id < CUSTOM_NUM_FMT_BASE {
+        return id;
+    }
+    let code = src
+        .num_fmts
+        .as_ref()
+        .and_then(|nfs| nfs.num_fmts.iter().find(|nf| nf.num_fmt_id == id));
+    match code {
+        Some(nf) => add_or_find_num_fmt(dst, &nf.format_code),
+        None => 0,
+    }
+}
+
+/// Return the index of `item` in `items`, appending a copy if absent.
+fn find_or_push<T: Clone + PartialEq>(items: &mut Vec<T>, item: &T) -> u32 {
+    match items.iter().position(|existing| existing == item) {
+        Some(i) => i as u32,
+        None => {
+            items.push(item.clone());
+            items.len() as u32 - 1
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2261,4 +2363,46 @@ mod tests {
             Some(HorizontalAlign::Center)
         );
     }
+
+    #[test]
+    fn test_import_style_remaps_components() {
+        let mut src = default_stylesheet();
+        let italic = add_style(
+            &mut src,
+            &StyleBuilder::new()
+                .italic(true)
+                .num_format_custom("0.000")
+                .build(),
+        )
+        .unwrap();
+        let bold = add_style(
+            &mut src,
+            &StyleBuilder::new()
+                .bold(true)
+                .num_format_custom("#,##0.0")
+                .build(),
+        )
+        .unwrap();
+
+        let mut dst = default_stylesheet();
+        let existing = add_style(
+            &mut dst,
+            &StyleBuilder::new()
+                .bold(true)
+                .num_format_custom("#,##0.0")
+                .build(),
+        )
+        .unwrap();
+
+        // An identical format is reused.
+        assert_eq!(import_style(&mut dst, &src, bold).unwrap(), existing);
+        let imported = import_style(&mut dst, &src, italic).unwrap();
+        let style = get_style(&dst, imported).unwrap();
+        assert!(style.font.unwrap().italic);
+        assert!(matches!(style.num_fmt, Some(NumFmtStyle::Custom(ref code)) if code == "0.000"));
+        assert!(matches!(
+            import_style(&mut dst, &src, 99),
+            Err(Error::StyleNotFound { id: 99 })
+        ));
+    }
 }
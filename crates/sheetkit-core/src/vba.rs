@@ -475,10 +475,8 @@ fn parse_dir_stream(data: &[u8]) -> Result<DirInfo> {
 
         match record_id {
             // PROJECTCODEPAGE
-            0x0003 => {
-                if record_size >= 2 {
-                    codepage = u16::from_le_bytes([record_data[0], record_data[1]]);
-                }
+            0x0003 if record_size >= 2 => {
+                codepage = u16::from_le_bytes([record_data[0], record_data[1]]);
             }
             // MODULENAME
             0x0019 => {
@@ -503,22 +501,20 @@ fn parse_dir_stream(data: &[u8]) -> Result<DirInfo> {
                 current_type = VbaModuleType::Standard;
             }
             // MODULENAMEUNICODE
-            0x0047 => {
+            0x0047 if record_size >= 2 => {
                 // UTF-16LE encoded name, prefer this over the ANSI name.
                 // Use only the even portion of the data; an odd trailing
                 // byte indicates a truncated record and is safely ignored.
-                if record_size >= 2 {
-                    let even_len = record_data.len() & !1;
-                    let u16_data: Vec<u16> = record_data[..even_len]
-                        .chunks_exact(2)
-                        .map(|c| u16::from_le_bytes([c[0], c[1]]))
-                        .collect();
-                    let name = String::from_utf16_lossy(&u16_data);
-                    // Remove trailing null if present
-                    let name = name.trim_end_matches('\0').to_string();
-                    if !name.is_empty() {
-                        current_name = Some(name);
-                    }
+                let even_len = record_data.len() & !1;
+                let u16_data: Vec<u16> = record_data[..even_len]
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect();
+                let name = String::from_utf16_lossy(&u16_data);
+                // Remove trailing null if present
+                let name = name.trim_end_matches('\0').to_string();
+                if !name.is_empty() {
+                    current_name = Some(name);
                 }
             }
             // MODULESTREAMNAME
@@ -543,15 +539,13 @@ fn parse_dir_stream(data: &[u8]) -> Result<DirInfo> {
                 }
             }
             // MODULEOFFSET
-            0x0031 => {
-                if record_size >= 4 {
-                    current_offset = u32::from_le_bytes([
-                        record_data[0],
-                        record_data[1],
-                        record_data[2],
-                        record_data[3],
-                    ]);
-                }
+            0x0031 if record_size >= 4 => {
+                current_offset = u32::from_le_bytes([
+                    record_data[0],
+                    record_data[1],
+                    record_data[2],
+                    record_data[3],
+                ]);
             }
             // MODULETYPE procedural (0x0021)
             0x0021 => {
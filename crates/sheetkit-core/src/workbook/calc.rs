@@ -0,0 +1,1451 @@
+use super::*;
+
+use crate::array_formula::ArrayArea;
+use crate::formula::ast::Expr;
+use crate::formula::eval::{
+    is_array_expr, recover_error_value, strongly_connected_components, ArrayValue, CellCoord,
+    CellDataProvider, CellSnapshot, DependencyGraph, EvalStep,
+};
+
+/// A cell found by [`Workbook::get_precedents`] or
+/// [`Workbook::get_dependents`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TracedCell {
+    /// Sheet holding the cell.
+    pub sheet: String,
+    /// Cell reference (e.g., "B2").
+    pub cell: String,
+    /// Number of formula links from the traced cell: 1 for a direct
+    /// precedent or dependent, 2 for one reached through another formula,
+    /// and so on.
+    pub level: usize,
+}
+
+/// Calculation state kept between calculations, so that
+/// [`Workbook::recalculate_dirty`] only re-evaluates the formulas an edit
+/// affects instead of rebuilding everything.
+pub(crate) struct CalcCache {
+    /// Values of all cells as of the last calculation, with formula cells
+    /// holding their computed results.
+    snapshot: CellSnapshot,
+    /// Parsed formula of every formula cell.
+    formulas: HashMap<CellCoord, Expr>,
+    /// Dependencies between formula cells, including the links from cells
+    /// reading an array formula result to the array formula's anchor.
+    graph: DependencyGraph,
+    /// Legacy (CSE) array formulas, whose range is fixed, keyed by anchor.
+    fixed_arrays: HashMap<CellCoord, ArrayArea>,
+    /// Current spill range of each dynamic array formula, keyed by anchor.
+    spills: HashMap<CellCoord, ArrayArea>,
+    /// Dynamic array formulas whose result could not spill. Clearing the
+    /// cells in the way is not an edit of one of their precedents, so they
+    /// are recalculated every time.
+    blocked: HashSet<CellCoord>,
+}
+
+impl CalcCache {
+    /// Rebuild the dependency graph from the cached formulas.
+    fn rebuild_graph(&mut self) {
+        self.graph = DependencyGraph::default();
+        for (coord, expr) in &self.formulas {
+            self.graph.add_formula(coord.clone(), expr, &self.snapshot);
+        }
+        let coords: Vec<CellCoord> = self.formulas.keys().cloned().collect();
+        for coord in &coords {
+            self.link_array_reader(coord);
+        }
+    }
+
+    /// Make the formula cell `cell` also depend on the anchor of every array
+    /// formula whose result it reads.
+    fn link_array_reader(&mut self, cell: &CellCoord) {
+        if self.spills.is_empty() && self.fixed_arrays.is_empty() {
+            return;
+        }
+        let Some(deps) = self.graph.deps.get(cell) else {
+            return;
+        };
+        let anchors: Vec<CellCoord> = deps
+            .iter()
+            .flat_map(|dep| {
+                self.spills
+                    .iter()
+                    .chain(&self.fixed_arrays)
+                    .filter(move |(anchor, area)| {
+                        anchor.sheet == dep.sheet && area.contains(dep.col, dep.row)
+                    })
+            })
+            .map(|(anchor, _)| anchor.clone())
+            .filter(|anchor| anchor != cell)
+            .collect();
+        for anchor in anchors {
+            self.graph.add_dependency(cell, anchor);
+        }
+    }
+
+    /// Return `true` if `coord` lies in the range of an array formula.
+    fn in_array_range(&self, coord: &CellCoord) -> bool {
+        self.spills
+            .iter()
+            .chain(&self.fixed_arrays)
+            .any(|(anchor, area)| {
+                anchor.sheet == coord.sheet && area.contains(coord.col, coord.row)
+            })
+    }
+}
+
+impl Workbook {
+    /// Allow calculation to evaluate independent formulas on several
+    /// threads. This only takes effect when the `parallel` cargo feature is
+    /// enabled, and is off by default. The thread count comes from the
+    /// workbook's `concurrent_manual_count` calculation setting, with one
+    /// thread per CPU when it is unset; a workbook whose `concurrent_calc`
+    /// setting is `Some(false)` is always calculated on one thread.
+    pub fn set_parallel_calculation(&mut self, enabled: bool) {
+        self.parallel_calc = enabled;
+    }
+
+    /// Return whether parallel calculation is enabled, see
+    /// [`Workbook::set_parallel_calculation`].
+    pub fn parallel_calculation(&self) -> bool {
+        self.parallel_calc
+    }
+
+    /// Recalculate every formula cell across all sheets and store the
+    /// computed result back into each cell. Uses a dependency graph and
+    /// topological sort so formulas are evaluated after their dependencies.
+    ///
+    /// Dynamic array formulas (FILTER, SORT, SEQUENCE, ...) spill their
+    /// result into the cells below and to the right: the formula cell is
+    /// marked `t="array"` with a `ref` covering the result, and the other
+    /// cells receive the values. If any of those cells is already occupied,
+    /// the formula cell evaluates to `#SPILL!` instead.
+    ///
+    /// Legacy array formulas (see [`Workbook::set_cell_array_formula`]) fill
+    /// their fixed range instead, padding with `#N/A` where the result is
+    /// smaller.
+    ///
+    /// The dependency graph and the computed values are kept for later
+    /// calls to [`Workbook::recalculate_dirty`].
+    ///
+    /// With iterative calculation enabled (see [`Workbook::set_calc_settings`]),
+    /// a circular reference still changing after the last iteration keeps
+    /// the values of that iteration, as in Excel, and is reported by
+    /// [`Workbook::iteration_not_converged`].
+    pub fn calculate_all(&mut self) -> Result<()> {
+        self.invalidate_calc_cache();
+        self.not_converged = None;
+        let Some(mut cache) = self.build_calc_cache()? else {
+            return Ok(());
+        };
+        let coords: Vec<CellCoord> = cache.formulas.keys().cloned().collect();
+        let components = strongly_connected_components(&coords, &cache.graph.deps);
+        if self.evaluate_formulas(&mut cache, &components)? {
+            // Cells that joined a spill range now depend on its anchor.
+            cache.rebuild_graph();
+        }
+        self.calc_cache = Some(cache);
+        Ok(())
+    }
+
+    /// Recalculate only the formulas affected by the cells edited since the
+    /// last calculation, plus volatile formulas (`NOW`, `RAND`, `OFFSET`,
+    /// `INDIRECT`, ...) and the formulas that depend on them.
+    ///
+    /// Edits made through [`Workbook::set_cell_value`],
+    /// [`Workbook::set_cell_formula`], [`Workbook::set_cell_values`] and
+    /// [`Workbook::set_sheet_data`] are tracked cell by cell. Other changes,
+    /// such as inserting rows, adding or renaming sheets, or changing
+    /// defined names and tables, discard the cached dependency graph, as
+    /// does editing a cell inside an array formula range; the next call
+    /// then performs a full [`Workbook::calculate_all`]. Formatting changes
+    /// such as styles, row heights, column widths and merged cells keep the
+    /// cached graph.
+    pub fn recalculate_dirty(&mut self) -> Result<()> {
+        let Some(mut cache) = self.calc_cache.take() else {
+            return self.calculate_all();
+        };
+        let mut changed: Vec<CellCoord> = self.dirty_cells.drain().collect();
+        if !self.apply_edits(&mut cache, &changed)? {
+            return self.calculate_all();
+        }
+        changed.extend(cache.blocked.iter().cloned());
+        let affected: Vec<CellCoord> = cache.graph.affected_cells(&changed).into_iter().collect();
+        let deps: HashMap<CellCoord, Vec<CellCoord>> = affected
+            .iter()
+            .map(|coord| (coord.clone(), cache.graph.deps[coord].clone()))
+            .collect();
+        let components = strongly_connected_components(&affected, &deps);
+        if self.evaluate_formulas(&mut cache, &components)? {
+            // A spill range changed, so formulas outside `affected` may now
+            // read different cells.
+            return self.calculate_all();
+        }
+        self.calc_cache = Some(cache);
+        Ok(())
+    }
+
+    /// Return the first circular reference that was still changing after the
+    /// maximum number of iterations in the last calculation, as the cell
+    /// (e.g. `"Sheet1!A1"`) and the number of iterations, or `None` if every
+    /// circular reference converged.
+    pub fn iteration_not_converged(&self) -> Option<(&str, u32)> {
+        self.not_converged
+            .as_ref()
+            .map(|(cell, iterations)| (cell.as_str(), *iterations))
+    }
+
+    /// Recalculate the given `(sheet, cell)` formulas and every formula that
+    /// depends on them, along with any pending edits (see
+    /// [`Workbook::recalculate_dirty`]).
+    pub fn calculate_cells(&mut self, cells: &[(&str, &str)]) -> Result<()> {
+        let mut coords = Vec::with_capacity(cells.len());
+        for (sheet, cell) in cells {
+            let idx = self.sheet_index(sheet)?;
+            let (col, row) = cell_name_to_coordinates(cell)?;
+            coords.push(CellCoord {
+                sheet: self.worksheets[idx].0.clone(),
+                col,
+                row,
+            });
+        }
+        if self.calc_cache.is_some() {
+            self.dirty_cells.extend(coords);
+        }
+        self.recalculate_dirty()
+    }
+
+    /// Return the cells the formula in `cell` reads, directly and through
+    /// other formulas, like Excel's Trace Precedents. References through
+    /// defined names, tables, and other sheets are followed, and ranges
+    /// contribute each of their cells. The result is ordered by level, then
+    /// by sheet, row, and column. A cell without a formula has no
+    /// precedents.
+    pub fn get_precedents(&mut self, sheet: &str, cell: &str) -> Result<Vec<TracedCell>> {
+        self.trace_links(sheet, cell, false)
+    }
+
+    /// Return the formula cells that read `cell`, directly and through
+    /// other formulas, like Excel's Trace Dependents. Ordered like
+    /// [`Workbook::get_precedents`].
+    pub fn get_dependents(&mut self, sheet: &str, cell: &str) -> Result<Vec<TracedCell>> {
+        self.trace_links(sheet, cell, true)
+    }
+
+    /// Evaluate the formula in `cell` step by step, like Excel's Evaluate
+    /// Formula, and return the value of every subexpression in evaluation
+    /// order. The last step is the whole formula at depth 0. Referenced
+    /// cells contribute their current values, so formulas they hold are not
+    /// recalculated. A cell without a formula has no steps.
+    pub fn trace_formula(&self, sheet: &str, cell: &str) -> Result<Vec<EvalStep>> {
+        let CellValue::Formula { expr, .. } = self.get_cell_value(sheet, cell)? else {
+            return Ok(Vec::new());
+        };
+        let parsed = crate::formula::parser::parse_formula(&expr)?;
+        let idx = self.sheet_index(sheet)?;
+        let mut snapshot = self.build_cell_snapshot(&self.worksheets[idx].0)?;
+        snapshot.set_current_cell(Some(cell_name_to_coordinates(cell)?));
+        let mut evaluator = crate::formula::eval::Evaluator::new(&snapshot);
+        evaluator.start_trace();
+        evaluator.eval_expr(&parsed)?;
+        Ok(evaluator.take_trace())
+    }
+
+    /// Walk the dependency graph from `cell`, towards the formulas reading
+    /// it if `dependents` is set and towards the cells it reads otherwise.
+    fn trace_links(
+        &mut self,
+        sheet: &str,
+        cell: &str,
+        dependents: bool,
+    ) -> Result<Vec<TracedCell>> {
+        let idx = self.sheet_index(sheet)?;
+        let (col, row) = cell_name_to_coordinates(cell)?;
+        let start = CellCoord {
+            sheet: self.worksheets[idx].0.clone(),
+            col,
+            row,
+        };
+        // The cached graph is current unless cells were edited since the
+        // last calculation.
+        let built;
+        let graph = match &self.calc_cache {
+            Some(cache) if self.dirty_cells.is_empty() => &cache.graph,
+            _ => match self.build_calc_cache()? {
+                Some(cache) => {
+                    built = cache;
+                    &built.graph
+                }
+                None => return Ok(Vec::new()),
+            },
+        };
+        let links = if dependents {
+            &graph.dependents
+        } else {
+            &graph.deps
+        };
+
+        let mut found: Vec<(usize, CellCoord)> = Vec::new();
+        let mut visited: HashSet<&CellCoord> = HashSet::from([&start]);
+        let mut frontier = vec![&start];
+        let mut level = 0;
+        while !frontier.is_empty() {
+            level += 1;
+            let mut next = Vec::new();
+            for coord in frontier {
+                for linked in links.get(coord).into_iter().flatten() {
+                    if visited.insert(linked) {
+                        found.push((level, linked.clone()));
+                        next.push(linked);
+                    }
+                }
+            }
+            frontier = next;
+        }
+
+        let sheet_order: HashMap<&str, usize> = self
+            .worksheets
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| (name.as_str(), i))
+            .collect();
+        found.sort_by_key(|(level, coord)| {
+            let sheet = sheet_order.get(coord.sheet.as_str()).copied();
+            (*level, sheet, coord.row, coord.col)
+        });
+        found
+            .into_iter()
+            .map(|(level, coord)| {
+                Ok(TracedCell {
+                    cell: crate::utils::cell_ref::coordinates_to_cell_name(coord.col, coord.row)?,
+                    sheet: coord.sheet,
+                    level,
+                })
+            })
+            .collect()
+    }
+
+    /// Record an edit of the cell at `(col, row)` for the next
+    /// [`Workbook::recalculate_dirty`].
+    pub(crate) fn mark_cell_dirty(&mut self, sheet_idx: usize, col: u32, row: u32) {
+        if self.calc_cache.is_some() {
+            self.dirty_cells.insert(CellCoord {
+                sheet: self.worksheets[sheet_idx].0.clone(),
+                col,
+                row,
+            });
+        }
+    }
+
+    /// Discard the cached dependency graph after a change that cell-level
+    /// dirty tracking cannot describe.
+    pub(crate) fn invalidate_calc_cache(&mut self) {
+        self.calc_cache = None;
+        self.dirty_cells.clear();
+    }
+
+    /// Collect and parse all formula cells and build their dependency graph.
+    /// Returns `None` if the workbook has no formulas.
+    fn build_calc_cache(&mut self) -> Result<Option<CalcCache>> {
+        let sheet_names: Vec<String> = self.sheet_names().iter().map(|s| s.to_string()).collect();
+
+        let mut formulas: HashMap<CellCoord, Expr> = HashMap::new();
+        // Spill ranges written by a previous calculation, keyed by anchor.
+        let mut spills: HashMap<CellCoord, ArrayArea> = HashMap::new();
+        let mut fixed_arrays: HashMap<CellCoord, ArrayArea> = HashMap::new();
+        for (idx, sn) in sheet_names.iter().enumerate() {
+            self.ensure_hydrated(idx)?;
+            let ws = self.worksheets[idx].1.get().unwrap();
+            let shared_masters = self.shared_masters(idx)?;
+            for row in &ws.sheet_data.rows {
+                for cell in &row.cells {
+                    let Some(f) = cell.f.as_ref() else {
+                        continue;
+                    };
+                    let Ok((c, r)) = cell_name_to_coordinates(cell.r.as_str()) else {
+                        continue;
+                    };
+                    if let Some(formula_str) =
+                        crate::shared_formula::effective_formula(cell, c, r, shared_masters)?
+                    {
+                        let coord = CellCoord {
+                            sheet: sn.clone(),
+                            col: c,
+                            row: r,
+                        };
+                        let expr = crate::formula::parser::parse_formula(&formula_str)?;
+                        if let Some(area) = crate::array_formula::array_area(f) {
+                            if is_array_expr(&expr) {
+                                spills.insert(coord.clone(), area);
+                            } else {
+                                fixed_arrays.insert(coord.clone(), area);
+                            }
+                        }
+                        formulas.insert(coord, expr);
+                    }
+                }
+            }
+        }
+
+        if formulas.is_empty() {
+            return Ok(None);
+        }
+
+        // Build a snapshot of all cell data and defined names.
+        let first_sheet = sheet_names.first().cloned().unwrap_or_default();
+        let snapshot = self.build_cell_snapshot(&first_sheet)?;
+        let mut cache = CalcCache {
+            snapshot,
+            formulas,
+            graph: DependencyGraph::default(),
+            fixed_arrays,
+            spills,
+            blocked: HashSet::new(),
+        };
+        cache.rebuild_graph();
+        Ok(Some(cache))
+    }
+
+    /// Bring the cached values and dependency graph up to date with the
+    /// edited cells. Returns `false` if an edit involves an array formula
+    /// range, which requires a full calculation.
+    fn apply_edits(&self, cache: &mut CalcCache, edited: &[CellCoord]) -> Result<bool> {
+        let mut extent_grew = false;
+        for coord in edited {
+            if cache.in_array_range(coord) {
+                return Ok(false);
+            }
+            let idx = self.sheet_index(&coord.sheet)?;
+            let ws = self.worksheet_ref_by_index(idx)?;
+            let cell = ws
+                .sheet_data
+                .rows
+                .binary_search_by_key(&coord.row, |r| r.r)
+                .ok()
+                .and_then(|ri| {
+                    let row = &ws.sheet_data.rows[ri];
+                    let ci = row.cells.binary_search_by_key(&coord.col, |c| c.col).ok()?;
+                    Some(&row.cells[ci])
+                });
+            let (value, formula) = match cell {
+                Some(cell) => {
+                    let formula = crate::shared_formula::effective_formula(
+                        cell,
+                        coord.col,
+                        coord.row,
+                        self.shared_masters(idx)?,
+                    )?;
+                    if cell
+                        .f
+                        .as_deref()
+                        .and_then(crate::array_formula::array_area)
+                        .is_some()
+                    {
+                        return Ok(false);
+                    }
+                    (self.xml_cell_to_value(cell)?, formula)
+                }
+                None => (CellValue::Empty, None),
+            };
+
+            cache.formulas.remove(coord);
+            cache.graph.remove_formula(coord);
+            if let Some(formula_str) = formula {
+                let expr = crate::formula::parser::parse_formula(&formula_str)?;
+                if is_array_expr(&expr) {
+                    return Ok(false);
+                }
+                cache
+                    .graph
+                    .add_formula(coord.clone(), &expr, &cache.snapshot);
+                cache.formulas.insert(coord.clone(), expr);
+                cache.link_array_reader(coord);
+            }
+
+            if value != CellValue::Empty
+                || cache.snapshot.get_cell(&coord.sheet, coord.col, coord.row) != CellValue::Empty
+            {
+                let (max_col, max_row) =
+                    cache.snapshot.used_extent(&coord.sheet).unwrap_or_default();
+                extent_grew |= coord.col > max_col || coord.row > max_row;
+                cache
+                    .snapshot
+                    .set_cell(&coord.sheet, coord.col, coord.row, value);
+            }
+        }
+        // Whole-column and whole-row references are bounded by the used
+        // range, so they now cover more cells.
+        if extent_grew {
+            cache.rebuild_graph();
+        }
+        Ok(true)
+    }
+
+    /// Evaluate the formula cells in `components`, which are in dependency
+    /// order, against the cached snapshot and store the results into the
+    /// cells, spilling dynamic array results. Circular components are
+    /// iterated when iterative calculation is enabled and are an error
+    /// otherwise. Returns `true` if the spill range of a dynamic array
+    /// formula changed.
+    fn evaluate_formulas(
+        &mut self,
+        cache: &mut CalcCache,
+        components: &[Vec<CellCoord>],
+    ) -> Result<bool> {
+        // Previous spill values of the formulas about to be evaluated must
+        // neither be read nor block a spill.
+        let old_spills: HashMap<CellCoord, ArrayArea> = components
+            .iter()
+            .flatten()
+            .filter_map(|coord| cache.spills.get(coord).map(|area| (coord.clone(), *area)))
+            .collect();
+        for (anchor, area) in &old_spills {
+            for (c, r) in area.cells() {
+                if (c, r) != (anchor.col, anchor.row) {
+                    cache
+                        .snapshot
+                        .set_cell(&anchor.sheet, c, r, CellValue::Empty);
+                }
+            }
+            cache
+                .snapshot
+                .set_spill(&anchor.sheet, anchor.col, anchor.row, None);
+        }
+
+        // Volatile formulas such as INDIRECT and OFFSET read cells that are
+        // not edges of the dependency graph, so they and their dependents
+        // are evaluated after every other formula.
+        let volatile_cells = cache.graph.affected_cells(&[]);
+        let (stable, volatile): (Vec<&Vec<CellCoord>>, Vec<&Vec<CellCoord>>) = components
+            .iter()
+            .partition(|component| !component.iter().any(|c| volatile_cells.contains(c)));
+
+        // Evaluate in dependency order, updating the snapshot progressively
+        // so later formulas see already-computed results.
+        let fixed_arrays = &cache.fixed_arrays;
+        let formulas = &cache.formulas;
+        let deps = &cache.graph.deps;
+        let blocked = &mut cache.blocked;
+        let snapshot = &mut cache.snapshot;
+        let iteration = self.iteration_settings();
+        let mut results: Vec<(CellCoord, CellValue)> = Vec::new();
+        let mut spills: HashMap<CellCoord, (ArrayArea, Vec<CellValue>)> = HashMap::new();
+        // Cells that dynamic arrays cannot spill into. Legacy array formula
+        // ranges and the ranges of spills not being recalculated are
+        // reserved up front.
+        let mut spilled_cells: HashSet<(String, u32, u32)> = fixed_arrays
+            .iter()
+            .chain(
+                cache
+                    .spills
+                    .iter()
+                    .filter(|(anchor, _)| !old_spills.contains_key(*anchor)),
+            )
+            .flat_map(|(anchor, area)| area.cells().map(|(c, r)| (anchor.sheet.clone(), c, r)))
+            .collect();
+        let mut eval_cell = |snapshot: &mut CellSnapshot,
+                             coord: &CellCoord|
+         -> Result<Option<CellValue>> {
+            let Some(parsed) = formulas.get(coord) else {
+                return Ok(None);
+            };
+            // A formula evaluated again during iteration replaces its
+            // previous spill.
+            if let Some((area, _)) = spills.remove(coord) {
+                for (c, r) in area.cells().skip(1) {
+                    snapshot.set_cell(&coord.sheet, c, r, CellValue::Empty);
+                    spilled_cells.remove(&(coord.sheet.clone(), c, r));
+                }
+                snapshot.set_spill(&coord.sheet, coord.col, coord.row, None);
+            }
+            snapshot.set_current_sheet(&coord.sheet);
+            snapshot.set_current_cell(Some((coord.col, coord.row)));
+            let mut evaluator = crate::formula::eval::Evaluator::new(&*snapshot);
+            let result = if let Some(area) = fixed_arrays.get(coord) {
+                // Fill the fixed range, broadcasting single rows and
+                // columns and padding with #N/A like Excel.
+                let array =
+                    recover_error_value(evaluator.eval_array_formula(parsed), ArrayValue::scalar)?;
+                let values: Vec<CellValue> = area
+                    .cells()
+                    .map(|(c, r)| {
+                        array
+                            .broadcast_get((r - area.min_row) as usize, (c - area.min_col) as usize)
+                    })
+                    .collect();
+                for ((c, r), v) in area.cells().zip(&values).skip(1) {
+                    snapshot.set_cell(&coord.sheet, c, r, v.clone());
+                }
+                let top_left = values[0].clone();
+                spills.insert(coord.clone(), (*area, values));
+                top_left
+            } else if is_array_expr(parsed) {
+                let array = recover_error_value(evaluator.eval_array(parsed), ArrayValue::scalar)?;
+                blocked.remove(coord);
+                if array.is_scalar() {
+                    array.into_scalar()
+                } else {
+                    let area = ArrayArea::spill(coord.col, coord.row, array.rows, array.cols)
+                        .filter(|area| {
+                            area.cells().all(|(c, r)| {
+                                (c, r) == (coord.col, coord.row)
+                                    || (snapshot.get_cell(&coord.sheet, c, r) == CellValue::Empty
+                                        && !spilled_cells.contains(&(coord.sheet.clone(), c, r)))
+                            })
+                        });
+                    match area {
+                        Some(area) => {
+                            for ((c, r), v) in area.cells().zip(&array.values).skip(1) {
+                                snapshot.set_cell(&coord.sheet, c, r, v.clone());
+                                spilled_cells.insert((coord.sheet.clone(), c, r));
+                            }
+                            let top_left = array.values[0].clone();
+                            let end = Some((area.max_col, area.max_row));
+                            snapshot.set_spill(&coord.sheet, coord.col, coord.row, end);
+                            spills.insert(coord.clone(), (area, array.values));
+                            top_left
+                        }
+                        None => {
+                            blocked.insert(coord.clone());
+                            CellValue::Error("#SPILL!".to_string())
+                        }
+                    }
+                }
+            } else {
+                recover_error_value(evaluator.eval_expr(parsed), |value| value)?
+            };
+            snapshot.set_cell(&coord.sheet, coord.col, coord.row, result.clone());
+            Ok(Some(result))
+        };
+
+        // With parallel calculation enabled, independent formulas are
+        // grouped into dependency levels, and the plain formulas of a level
+        // are evaluated in parallel.
+        #[cfg(feature = "parallel")]
+        let pool = match self.parallel_threads() {
+            Some(threads) => Some(thread_pool(threads)?),
+            None => None,
+        };
+        #[cfg(feature = "parallel")]
+        let levels = match pool {
+            Some(_) => {
+                let mut levels = dependency_levels(&stable, deps);
+                levels.extend(dependency_levels(&volatile, deps));
+                levels
+            }
+            None => vec![stable.into_iter().chain(volatile).collect()],
+        };
+        #[cfg(not(feature = "parallel"))]
+        let levels: [Vec<&Vec<CellCoord>>; 1] = [stable.into_iter().chain(volatile).collect()];
+
+        let mut not_converged = None;
+        for level in levels {
+            #[cfg(feature = "parallel")]
+            let level = if let Some(pool) = &pool {
+                let (plain, mut rest): (Vec<_>, Vec<_>) =
+                    level.into_iter().partition(|component| {
+                        let coord = &component[0];
+                        component.len() == 1
+                            && !deps.get(coord).is_some_and(|d| d.contains(coord))
+                            && !fixed_arrays.contains_key(coord)
+                            && formulas.get(coord).is_some_and(|e| !is_array_expr(e))
+                    });
+                if plain.len() > 1 {
+                    let values = evaluate_parallel(pool, snapshot, formulas, &plain)?;
+                    for (coord, value) in values {
+                        snapshot.set_cell(&coord.sheet, coord.col, coord.row, value.clone());
+                        results.push((coord, value));
+                    }
+                } else {
+                    rest.splice(0..0, plain);
+                }
+                rest
+            } else {
+                level
+            };
+            for component in level {
+                let circular = component.len() > 1
+                    || deps
+                        .get(&component[0])
+                        .is_some_and(|cell_deps| cell_deps.contains(&component[0]));
+                if !circular {
+                    if let Some(result) = eval_cell(snapshot, &component[0])? {
+                        results.push((component[0].clone(), result));
+                    }
+                    continue;
+                }
+                let Some((max_iterations, max_change)) = iteration else {
+                    return Err(Error::CircularReference {
+                        cell: coord_name(&component[0])?,
+                    });
+                };
+                // Iterate the cycle, starting from the cells' previous results,
+                // until no value changes by more than the maximum change.
+                for coord in component {
+                    if let CellValue::Formula { result, .. } =
+                        snapshot.get_cell(&coord.sheet, coord.col, coord.row)
+                    {
+                        let value = result.map_or(CellValue::Number(0.0), |r| *r);
+                        snapshot.set_cell(&coord.sheet, coord.col, coord.row, value);
+                    }
+                }
+                let mut iterations = 0;
+                let mut converged = false;
+                while !converged && iterations < max_iterations {
+                    iterations += 1;
+                    let mut change = 0.0_f64;
+                    for coord in component {
+                        let old = snapshot.get_cell(&coord.sheet, coord.col, coord.row);
+                        if let Some(new) = eval_cell(snapshot, coord)? {
+                            change = change.max(value_change(&old, &new));
+                        }
+                    }
+                    converged = change <= max_change;
+                }
+                if !converged && not_converged.is_none() {
+                    not_converged = Some((coord_name(&component[0])?, iterations));
+                }
+                for coord in component {
+                    if formulas.contains_key(coord) {
+                        let result = snapshot.get_cell(&coord.sheet, coord.col, coord.row);
+                        results.push((coord.clone(), result));
+                    }
+                }
+            }
+        }
+
+        // Write results back directly to the XML cells, preserving the
+        // formula element and storing the computed value in the v/t fields.
+        for (coord, result) in results {
+            let cell_ref = crate::utils::cell_ref::coordinates_to_cell_name(coord.col, coord.row)?;
+            let Ok(sheet_idx) = self.sheet_index(&coord.sheet) else {
+                continue;
+            };
+            let Some(ws) = self.worksheets[sheet_idx].1.get_mut() else {
+                continue;
+            };
+            if let Some(row) = ws.sheet_data.rows.iter_mut().find(|r| r.r == coord.row) {
+                if let Some(cell) = row.cells.iter_mut().find(|c| c.r == *cell_ref) {
+                    // Dynamic array anchors carry the current spill range.
+                    let mut changed = false;
+                    if !fixed_arrays.contains_key(&coord)
+                        && (old_spills.contains_key(&coord) || spills.contains_key(&coord))
+                    {
+                        let new_ref = match spills.get(&coord) {
+                            Some((area, _)) => Some(area.to_reference()?),
+                            None => None,
+                        };
+                        if let Some(f) = cell.f.as_mut() {
+                            if f.reference != new_ref {
+                                f.t = new_ref.as_ref().map(|_| "array".to_string());
+                                f.reference = new_ref;
+                                changed = true;
+                            }
+                        }
+                    }
+
+                    let (new_v, new_t) = match &result {
+                        CellValue::Number(n) => (Some(n.to_string()), CellTypeTag::None),
+                        CellValue::String(s) => (Some(s.clone()), CellTypeTag::FormulaString),
+                        CellValue::Bool(b) => (
+                            Some(if *b { "1".to_string() } else { "0".to_string() }),
+                            CellTypeTag::Boolean,
+                        ),
+                        CellValue::Error(e) => (Some(e.clone()), CellTypeTag::Error),
+                        CellValue::Date(n) => (Some(n.to_string()), CellTypeTag::None),
+                        _ => (cell.v.clone(), cell.t),
+                    };
+
+                    if cell.v != new_v || cell.t != new_t {
+                        cell.v = new_v;
+                        cell.t = new_t;
+                        changed = true;
+                    }
+                    if changed {
+                        self.mark_sheet_dirty(sheet_idx);
+                    }
+                }
+            }
+        }
+
+        // Replace the previous spill values with the new ones.
+        for (anchor, area) in &old_spills {
+            let Ok(sheet_idx) = self.sheet_index(&anchor.sheet) else {
+                continue;
+            };
+            for (c, r) in area.cells() {
+                if (c, r) != (anchor.col, anchor.row) {
+                    self.write_spill_cell(sheet_idx, c, r, CellValue::Empty)?;
+                }
+            }
+        }
+        let mut spills_changed = false;
+        for coord in components.iter().flatten() {
+            if fixed_arrays.contains_key(coord) {
+                continue;
+            }
+            let new_area = spills.get(coord).map(|(area, _)| *area);
+            spills_changed |= old_spills.get(coord).copied() != new_area;
+            match new_area {
+                Some(area) => cache.spills.insert(coord.clone(), area),
+                None => cache.spills.remove(coord),
+            };
+        }
+        for (anchor, (area, values)) in spills {
+            let Ok(sheet_idx) = self.sheet_index(&anchor.sheet) else {
+                continue;
+            };
+            for ((c, r), value) in area.cells().zip(values).skip(1) {
+                self.write_spill_cell(sheet_idx, c, r, value)?;
+            }
+        }
+
+        self.not_converged = not_converged;
+        Ok(spills_changed)
+    }
+
+    /// Return the maximum number of iterations and the maximum change for
+    /// circular references, or `None` if iterative calculation is disabled.
+    /// Excel's defaults are 100 iterations and a maximum change of 0.001.
+    fn iteration_settings(&self) -> Option<(u32, f64)> {
+        let calc_pr = self.workbook_xml.calc_pr.as_ref()?;
+        if calc_pr.iterate != Some(true) {
+            return None;
+        }
+        Some((
+            calc_pr.iterate_count.unwrap_or(100).max(1),
+            calc_pr.iterate_delta.unwrap_or(0.001),
+        ))
+    }
+
+    /// The thread count for parallel calculation when it is enabled, with
+    /// `0` meaning one thread per CPU.
+    #[cfg(feature = "parallel")]
+    fn parallel_threads(&self) -> Option<usize> {
+        if !self.parallel_calc {
+            return None;
+        }
+        let calc_pr = self.workbook_xml.calc_pr.as_ref();
+        if calc_pr.and_then(|c| c.concurrent_calc) == Some(false) {
+            return None;
+        }
+        Some(calc_pr.and_then(|c| c.concurrent_manual_count).unwrap_or(0) as usize)
+    }
+}
+
+/// Group components, given in dependency order, into levels whose
+/// components do not depend on each other. Every component comes after the
+/// levels of the formulas it depends on, and the input order is kept within
+/// a level.
+#[cfg(feature = "parallel")]
+fn dependency_levels<'a>(
+    components: &[&'a Vec<CellCoord>],
+    deps: &HashMap<CellCoord, Vec<CellCoord>>,
+) -> Vec<Vec<&'a Vec<CellCoord>>> {
+    let mut level_of: HashMap<&CellCoord, usize> = HashMap::new();
+    let mut levels: Vec<Vec<&Vec<CellCoord>>> = Vec::new();
+    for &component in components {
+        let level = component
+            .iter()
+            .filter_map(|coord| deps.get(coord))
+            .flatten()
+            .filter_map(|dep| level_of.get(dep).map(|l| l + 1))
+            .max()
+            .unwrap_or(0);
+        for coord in component {
+            level_of.insert(coord, level);
+        }
+        if level == levels.len() {
+            levels.push(Vec::new());
+        }
+        levels[level].push(component);
+    }
+    levels
+}
+
+/// The thread pool for parallel calculation with `threads` threads (`0`
+/// for one per CPU). Pools are built on first use and shared by all
+/// workbooks.
+#[cfg(feature = "parallel")]
+fn thread_pool(threads: usize) -> Result<std::sync::Arc<rayon::ThreadPool>> {
+    use std::sync::{Arc, Mutex, PoisonError};
+
+    static POOLS: OnceLock<Mutex<HashMap<usize, Arc<rayon::ThreadPool>>>> = OnceLock::new();
+    let mut pools = POOLS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(pool) = pools.get(&threads) {
+        return Ok(Arc::clone(pool));
+    }
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .map_err(|e| Error::Internal(e.to_string()))?;
+    let pool = Arc::new(pool);
+    pools.insert(threads, Arc::clone(&pool));
+    Ok(pool)
+}
+
+/// Evaluate independent single-cell formulas in parallel against the
+/// snapshot, on `pool`.
+#[cfg(feature = "parallel")]
+fn evaluate_parallel(
+    pool: &rayon::ThreadPool,
+    snapshot: &CellSnapshot,
+    formulas: &HashMap<CellCoord, Expr>,
+    components: &[&Vec<CellCoord>],
+) -> Result<Vec<(CellCoord, CellValue)>> {
+    use rayon::prelude::*;
+
+    pool.install(|| {
+        components
+            .par_iter()
+            .map(|component| {
+                let coord = &component[0];
+                let context = FormulaContext { snapshot, coord };
+                let mut evaluator = crate::formula::eval::Evaluator::new(&context);
+                let value =
+                    recover_error_value(evaluator.eval_expr(&formulas[coord]), |value| value)?;
+                Ok((coord.clone(), value))
+            })
+            .collect()
+    })
+}
+
+/// A read-only view of the snapshot from one formula cell. Unlike setting
+/// the current cell on the snapshot itself, any number of these can be used
+/// from several threads at once.
+#[cfg(feature = "parallel")]
+struct FormulaContext<'a> {
+    snapshot: &'a CellSnapshot,
+    coord: &'a CellCoord,
+}
+
+#[cfg(feature = "parallel")]
+impl CellDataProvider for FormulaContext<'_> {
+    fn get_cell(&self, sheet: &str, col: u32, row: u32) -> CellValue {
+        self.snapshot.get_cell(sheet, col, row)
+    }
+
+    fn current_sheet(&self) -> &str {
+        &self.coord.sheet
+    }
+
+    fn defined_name(&self, name: &str, sheet: &str) -> Option<String> {
+        self.snapshot.defined_name(name, sheet)
+    }
+
+    fn current_cell(&self) -> Option<(u32, u32)> {
+        Some((self.coord.col, self.coord.row))
+    }
+
+    fn table(&self, name: &str) -> Option<crate::formula::eval::TableRange> {
+        self.snapshot.table(name)
+    }
+
+    fn table_at(
+        &self,
+        sheet: &str,
+        col: u32,
+        row: u32,
+    ) -> Option<crate::formula::eval::TableRange> {
+        self.snapshot.table_at(sheet, col, row)
+    }
+
+    fn sheet_names(&self) -> Vec<String> {
+        self.snapshot.sheet_names()
+    }
+
+    fn used_extent(&self, sheet: &str) -> Option<(u32, u32)> {
+        self.snapshot.used_extent(sheet)
+    }
+
+    fn pivot_table_at(
+        &self,
+        sheet: &str,
+        col: u32,
+        row: u32,
+    ) -> Option<crate::formula::eval::PivotTableSource> {
+        self.snapshot.pivot_table_at(sheet, col, row)
+    }
+
+    fn functions(&self) -> Option<&crate::formula::registry::FunctionRegistry> {
+        self.snapshot.functions()
+    }
+
+    fn date_system(&self) -> crate::cell::DateSystem {
+        self.snapshot.date_system()
+    }
+
+    fn spill_range(&self, sheet: &str, col: u32, row: u32) -> Option<(u32, u32)> {
+        self.snapshot.spill_range(sheet, col, row)
+    }
+}
+
+/// Format a cell coordinate as `Sheet!A1` for error messages.
+fn coord_name(coord: &CellCoord) -> Result<String> {
+    let cell = crate::utils::cell_ref::coordinates_to_cell_name(coord.col, coord.row)?;
+    Ok(format!("{}!{cell}", coord.sheet))
+}
+
+/// How much a value changed between two iterations: the difference for
+/// numbers, and infinity if a non-numeric value changed.
+fn value_change(old: &CellValue, new: &CellValue) -> f64 {
+    match (old, new) {
+        (CellValue::Number(a) | CellValue::Date(a), CellValue::Number(b) | CellValue::Date(b)) => {
+            (a - b).abs()
+        }
+        (CellValue::Empty, CellValue::Number(b)) => b.abs(),
+        _ if old == new => 0.0,
+        _ => f64::INFINITY,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn formula_result(wb: &Workbook, cell: &str) -> CellValue {
+        match wb.get_cell_value("Sheet1", cell).unwrap() {
+            CellValue::Formula { result, .. } => *result.unwrap(),
+            other => panic!("{cell} should be a formula cell, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_recalculate_dirty_updates_dependents() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "A1", 1.0).unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "A1*2").unwrap();
+        wb.set_cell_formula("Sheet1", "C1", "B1+1").unwrap();
+        wb.set_cell_formula("Sheet1", "D1", "10*2").unwrap();
+        wb.calculate_all().unwrap();
+        assert!(wb.calc_cache.is_some());
+
+        wb.set_cell_value("Sheet1", "A1", 5.0).unwrap();
+        assert_eq!(wb.dirty_cells.len(), 1);
+        wb.recalculate_dirty().unwrap();
+        assert!(wb.calc_cache.is_some());
+        assert!(wb.dirty_cells.is_empty());
+        assert_eq!(formula_result(&wb, "B1"), CellValue::Number(10.0));
+        assert_eq!(formula_result(&wb, "C1"), CellValue::Number(11.0));
+        assert_eq!(formula_result(&wb, "D1"), CellValue::Number(20.0));
+    }
+
+    #[test]
+    fn test_recalculate_dirty_formula_edits() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "A1", 1.0).unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "A1*2").unwrap();
+        wb.set_cell_formula("Sheet1", "C1", "B1+1").unwrap();
+        wb.calculate_all().unwrap();
+
+        // A new formula is added to the graph.
+        wb.set_cell_formula("Sheet1", "D1", "C1*10").unwrap();
+        wb.recalculate_dirty().unwrap();
+        assert_eq!(formula_result(&wb, "D1"), CellValue::Number(30.0));
+
+        // Replacing a formula with a value updates the formulas reading it,
+        // and the replaced formula no longer depends on A1.
+        wb.set_cell_value("Sheet1", "B1", 7.0).unwrap();
+        wb.recalculate_dirty().unwrap();
+        assert_eq!(formula_result(&wb, "C1"), CellValue::Number(8.0));
+        assert_eq!(formula_result(&wb, "D1"), CellValue::Number(80.0));
+        let cache = wb.calc_cache.as_ref().unwrap();
+        let a1 = CellCoord {
+            sheet: "Sheet1".to_string(),
+            col: 1,
+            row: 1,
+        };
+        assert!(cache.graph.affected_cells(&[a1]).is_empty());
+
+        wb.set_cell_formula("Sheet1", "B1", "C1").unwrap();
+        assert!(matches!(
+            wb.recalculate_dirty(),
+            Err(Error::CircularReference { .. })
+        ));
+    }
+
+    #[test]
+    fn test_recalculate_dirty_volatile_after_dynamic_target() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "Z1", 0.0).unwrap();
+        wb.set_cell_formula("Sheet1", "Y1", "Z1+10").unwrap();
+        wb.set_cell_formula("Sheet1", "A1", "INDIRECT(\"Y1\")+1")
+            .unwrap();
+        wb.calculate_all().unwrap();
+        assert_eq!(formula_result(&wb, "A1"), CellValue::Number(11.0));
+
+        wb.set_cell_value("Sheet1", "Z1", 10.0).unwrap();
+        wb.recalculate_dirty().unwrap();
+        assert_eq!(formula_result(&wb, "Y1"), CellValue::Number(20.0));
+        assert_eq!(formula_result(&wb, "A1"), CellValue::Number(21.0));
+    }
+
+    #[test]
+    fn test_layout_changes_keep_calc_cache() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "A1", 1.0).unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "A1*2").unwrap();
+        wb.calculate_all().unwrap();
+
+        let style = wb
+            .add_style(&crate::style::StyleBuilder::new().bold(true).build())
+            .unwrap();
+        wb.set_cell_style("Sheet1", "B1", style).unwrap();
+        wb.set_row_height("Sheet1", 1, 30.0).unwrap();
+        wb.set_col_width("Sheet1", "B", 20.0).unwrap();
+        wb.merge_cells("Sheet1", "C1", "D2").unwrap();
+        assert!(wb.calc_cache.is_some());
+
+        wb.insert_rows("Sheet1", 1, 1).unwrap();
+        assert!(wb.calc_cache.is_none());
+    }
+
+    #[test]
+    fn test_recalculate_dirty_whole_column_growth() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "A1", 1.0).unwrap();
+        wb.set_cell_value("Sheet1", "A2", 2.0).unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "SUM(A:A)").unwrap();
+        wb.calculate_all().unwrap();
+        assert_eq!(formula_result(&wb, "B1"), CellValue::Number(3.0));
+
+        // A5 lies outside the used range B1 was bounded by.
+        wb.set_cell_value("Sheet1", "A5", 10.0).unwrap();
+        wb.recalculate_dirty().unwrap();
+        assert_eq!(formula_result(&wb, "B1"), CellValue::Number(13.0));
+    }
+
+    #[test]
+    fn test_recalculate_dirty_after_structural_change() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "A1", 1.0).unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "A1+1").unwrap();
+        wb.calculate_all().unwrap();
+
+        wb.insert_rows("Sheet1", 1, 1).unwrap();
+        assert!(wb.calc_cache.is_none());
+        wb.set_cell_value("Sheet1", "A2", 4.0).unwrap();
+        assert!(wb.dirty_cells.is_empty());
+        wb.recalculate_dirty().unwrap();
+        assert!(wb.calc_cache.is_some());
+        match wb.get_cell_value("Sheet1", "B2").unwrap() {
+            CellValue::Formula { expr, result } => {
+                assert_eq!(expr, "A2+1");
+                assert_eq!(*result.unwrap(), CellValue::Number(5.0));
+            }
+            other => panic!("B2 should be a formula cell, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_recalculate_dirty_resizes_spill() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "A1", 2.0).unwrap();
+        wb.set_cell_formula("Sheet1", "C1", "SEQUENCE(A1)").unwrap();
+        wb.set_cell_formula("Sheet1", "D1", "SUM(C1:C5)").unwrap();
+        wb.calculate_all().unwrap();
+        assert_eq!(formula_result(&wb, "D1"), CellValue::Number(3.0));
+
+        wb.set_cell_value("Sheet1", "A1", 3.0).unwrap();
+        wb.recalculate_dirty().unwrap();
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "C3").unwrap(),
+            CellValue::Number(3.0)
+        );
+        assert_eq!(formula_result(&wb, "D1"), CellValue::Number(6.0));
+
+        // Clearing the cell that blocks a spill lets it spill again.
+        wb.set_cell_value("Sheet1", "C5", "x").unwrap();
+        wb.set_cell_value("Sheet1", "A1", 5.0).unwrap();
+        wb.recalculate_dirty().unwrap();
+        assert_eq!(
+            formula_result(&wb, "C1"),
+            CellValue::Error("#SPILL!".to_string())
+        );
+        wb.set_cell_value("Sheet1", "C5", CellValue::Empty).unwrap();
+        wb.recalculate_dirty().unwrap();
+        assert_eq!(formula_result(&wb, "C1"), CellValue::Number(1.0));
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "C5").unwrap(),
+            CellValue::Number(5.0)
+        );
+    }
+
+    fn enable_iteration(wb: &mut Workbook, count: u32) {
+        wb.set_calc_settings(&crate::doc_props::CalcSettings {
+            iterate: Some(true),
+            iterate_count: Some(count),
+            iterate_delta: Some(1e-9),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn test_circular_reference_without_iteration() {
+        let mut wb = Workbook::new();
+        wb.set_cell_formula("Sheet1", "A1", "B1+1").unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "A1").unwrap();
+        match wb.calculate_all() {
+            Err(Error::CircularReference { cell }) => assert_eq!(cell, "Sheet1!A1"),
+            other => panic!("expected a circular reference error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_iterative_calculation_converges() {
+        let mut wb = Workbook::new();
+        enable_iteration(&mut wb, 100);
+        assert_eq!(wb.get_calc_settings().iterate, Some(true));
+
+        // Interest on the average of the opening and closing balance.
+        wb.set_cell_value("Sheet1", "A1", 1000.0).unwrap();
+        wb.set_cell_formula("Sheet1", "A2", "(A1+A3)/2*0.1")
+            .unwrap();
+        wb.set_cell_formula("Sheet1", "A3", "A1+A2").unwrap();
+        wb.set_cell_formula("Sheet1", "A4", "ROUND(A3,2)").unwrap();
+        wb.calculate_all().unwrap();
+        let CellValue::Number(interest) = formula_result(&wb, "A2") else {
+            panic!("A2 should be a number");
+        };
+        assert!((interest - 2000.0 / 19.0).abs() < 1e-6);
+        assert_eq!(formula_result(&wb, "A4"), CellValue::Number(1105.26));
+
+        wb.set_cell_value("Sheet1", "A1", 2000.0).unwrap();
+        wb.recalculate_dirty().unwrap();
+        assert_eq!(formula_result(&wb, "A4"), CellValue::Number(2210.53));
+    }
+
+    #[test]
+    fn test_iterative_calculation_not_converged() {
+        let mut wb = Workbook::new();
+        enable_iteration(&mut wb, 10);
+        wb.set_cell_formula("Sheet1", "A1", "A1+1").unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "A1*2").unwrap();
+        wb.calculate_all().unwrap();
+        assert_eq!(wb.iteration_not_converged(), Some(("Sheet1!A1", 10)));
+        // The values of the last iteration are kept, along with the cache.
+        assert_eq!(formula_result(&wb, "A1"), CellValue::Number(10.0));
+        assert_eq!(formula_result(&wb, "B1"), CellValue::Number(20.0));
+        assert!(wb.calc_cache.is_some());
+
+        // Recalculation continues from the last values.
+        wb.calculate_cells(&[("Sheet1", "A1")]).unwrap();
+        assert_eq!(formula_result(&wb, "A1"), CellValue::Number(20.0));
+        assert_eq!(formula_result(&wb, "B1"), CellValue::Number(40.0));
+
+        wb.set_cell_formula("Sheet1", "A1", "5").unwrap();
+        wb.calculate_all().unwrap();
+        assert_eq!(wb.iteration_not_converged(), None);
+    }
+
+    #[test]
+    fn test_calculate_cells() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "A1", 3.0).unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "A1*A1").unwrap();
+
+        // Without a previous calculation everything is calculated.
+        wb.calculate_cells(&[("Sheet1", "B1")]).unwrap();
+        assert_eq!(formula_result(&wb, "B1"), CellValue::Number(9.0));
+        assert!(wb.calc_cache.is_some());
+
+        wb.set_cell_formula("Sheet1", "C1", "B1+1").unwrap();
+        wb.calculate_cells(&[("Sheet1", "B1")]).unwrap();
+        assert_eq!(formula_result(&wb, "C1"), CellValue::Number(10.0));
+
+        assert!(wb.calculate_cells(&[("Missing", "A1")]).is_err());
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_dependency_levels() {
+        let coord = |col| CellCoord {
+            sheet: "Sheet1".to_string(),
+            col,
+            row: 1,
+        };
+        // B1 and C1 read A1, D1 reads B1 and C1, E1 reads nothing.
+        let components: Vec<Vec<CellCoord>> = (1..=5).map(|col| vec![coord(col)]).collect();
+        let mut deps = HashMap::new();
+        deps.insert(coord(2), vec![coord(1)]);
+        deps.insert(coord(3), vec![coord(1)]);
+        deps.insert(coord(4), vec![coord(2), coord(3)]);
+        let components: Vec<&Vec<CellCoord>> = components.iter().collect();
+        let levels = dependency_levels(&components, &deps);
+        let cols: Vec<Vec<u32>> = levels
+            .iter()
+            .map(|level| level.iter().map(|c| c[0].col).collect())
+            .collect();
+        assert_eq!(cols, vec![vec![1, 5], vec![2, 3], vec![4]]);
+    }
+
+    #[test]
+    fn test_concurrent_calculation() {
+        let mut wb = Workbook::new();
+        wb.set_parallel_calculation(true);
+        wb.set_calc_settings(&crate::doc_props::CalcSettings {
+            concurrent_calc: Some(true),
+            concurrent_manual_count: Some(2),
+            ..Default::default()
+        });
+        for row in 1..=50 {
+            let cell = format!("A{row}");
+            wb.set_cell_value("Sheet1", &cell, row as f64).unwrap();
+            wb.set_cell_formula("Sheet1", &format!("B{row}"), &format!("A{row}*2"))
+                .unwrap();
+            wb.set_cell_formula("Sheet1", &format!("C{row}"), &format!("B{row}+A{row}"))
+                .unwrap();
+        }
+        wb.set_cell_formula("Sheet1", "D1", "SUM(C1:C50)").unwrap();
+        wb.set_cell_formula("Sheet1", "E1", "SEQUENCE(2)").unwrap();
+        wb.set_cell_formula("Sheet1", "F1", "E2*D1").unwrap();
+        wb.calculate_all().unwrap();
+        assert_eq!(formula_result(&wb, "C50"), CellValue::Number(150.0));
+        assert_eq!(formula_result(&wb, "D1"), CellValue::Number(3825.0));
+        assert_eq!(formula_result(&wb, "F1"), CellValue::Number(7650.0));
+
+        wb.set_cell_value("Sheet1", "A1", 101.0).unwrap();
+        wb.recalculate_dirty().unwrap();
+        assert_eq!(formula_result(&wb, "D1"), CellValue::Number(4125.0));
+
+        wb.set_cell_formula("Sheet1", "A2", "C2").unwrap();
+        assert!(matches!(
+            wb.calculate_all(),
+            Err(Error::CircularReference { .. })
+        ));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_parallel_calculation_matches_serial() {
+        let build = || {
+            let mut wb = Workbook::new();
+            wb.set_calc_settings(&crate::doc_props::CalcSettings {
+                concurrent_calc: Some(true),
+                concurrent_manual_count: Some(4),
+                ..Default::default()
+            });
+            for row in 1..=200 {
+                let formulas = [
+                    ("B", format!("A{row}*2")),
+                    ("C", format!("B{row}+A{row}")),
+                    ("D", format!("IF(MOD(A{row},3)=0,C{row},-C{row})")),
+                    ("E", format!("SUM($D$1:D{row})")),
+                    ("F", format!("VLOOKUP(201-A{row},$A$1:$C$200,3,FALSE)")),
+                    ("G", format!("INDIRECT(\"E\"&A{row})+F{row}")),
+                ];
+                wb.set_cell_value("Sheet1", &format!("A{row}"), row as f64)
+                    .unwrap();
+                for (col, formula) in formulas {
+                    wb.set_cell_formula("Sheet1", &format!("{col}{row}"), &formula)
+                        .unwrap();
+                }
+            }
+            wb.set_cell_formula("Sheet1", "H1", "SUM(G1:G200)").unwrap();
+            wb
+        };
+        let values = |wb: &Workbook| -> Vec<CellValue> {
+            (1..=200)
+                .flat_map(|row| {
+                    ["B", "C", "D", "E", "F", "G", "H"].map(|col| format!("{col}{row}"))
+                })
+                .map(|cell| wb.get_cell_value("Sheet1", &cell).unwrap())
+                .collect()
+        };
+
+        let mut serial = build();
+        let mut parallel = build();
+        parallel.set_parallel_calculation(true);
+        assert!(parallel.parallel_calculation());
+        serial.calculate_all().unwrap();
+        parallel.calculate_all().unwrap();
+        assert_eq!(values(&parallel), values(&serial));
+
+        for wb in [&mut serial, &mut parallel] {
+            wb.set_cell_value("Sheet1", "A7", 1000.0).unwrap();
+            wb.recalculate_dirty().unwrap();
+        }
+        assert_eq!(values(&parallel), values(&serial));
+    }
+
+    fn traced(cells: &[TracedCell]) -> Vec<(String, usize)> {
+        cells
+            .iter()
+            .map(|c| (format!("{}!{}", c.sheet, c.cell), c.level))
+            .collect()
+    }
+
+    #[test]
+    fn test_get_precedents_and_dependents() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Rates").unwrap();
+        wb.set_cell_value("Rates", "A1", 0.1).unwrap();
+        wb.set_defined_name("TaxRate", "Rates!$A$1", None, None)
+            .unwrap();
+        wb.set_cell_value("Sheet1", "A1", 10.0).unwrap();
+        wb.set_cell_value("Sheet1", "A2", 20.0).unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "SUM(A1:A2)").unwrap();
+        wb.set_cell_formula("Sheet1", "C1", "B1*(1+TaxRate)")
+            .unwrap();
+        wb.set_cell_formula("Sheet1", "D1", "C1-B1").unwrap();
+
+        let expected = [
+            ("Sheet1!B1", 1),
+            ("Sheet1!C1", 1),
+            ("Sheet1!A1", 2),
+            ("Sheet1!A2", 2),
+            ("Rates!A1", 2),
+        ];
+        let precedents = wb.get_precedents("Sheet1", "D1").unwrap();
+        assert_eq!(
+            traced(&precedents),
+            expected.map(|(c, l)| (c.to_string(), l))
+        );
+        assert!(wb.get_precedents("Sheet1", "A1").unwrap().is_empty());
+
+        let dependents = wb.get_dependents("Rates", "A1").unwrap();
+        assert_eq!(
+            traced(&dependents),
+            [("Sheet1!C1".to_string(), 1), ("Sheet1!D1".to_string(), 2)]
+        );
+        let dependents = wb.get_dependents("Sheet1", "A2").unwrap();
+        assert_eq!(dependents.len(), 3);
+        assert!(wb.get_dependents("Sheet1", "D1").unwrap().is_empty());
+
+        // The cached graph is used after a calculation, and rebuilt after
+        // an edit.
+        wb.calculate_all().unwrap();
+        assert_eq!(wb.get_dependents("Sheet1", "A2").unwrap().len(), 3);
+        wb.set_cell_formula("Sheet1", "E1", "A2").unwrap();
+        assert_eq!(wb.get_dependents("Sheet1", "A2").unwrap().len(), 4);
+        assert!(wb.get_precedents("Missing", "A1").is_err());
+    }
+
+    #[test]
+    fn test_trace_formula() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "A1", 10.0).unwrap();
+        wb.set_cell_value("Sheet1", "A2", 20.0).unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "IF(A1>5,SUM(A1:A2)*2,0)")
+            .unwrap();
+        let steps = wb.trace_formula("Sheet1", "B1").unwrap();
+        let summary: Vec<(usize, &str, &CellValue)> = steps
+            .iter()
+            .map(|s| (s.depth, s.expr.as_str(), &s.value))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (2, "A1", &CellValue::Number(10.0)),
+                (1, "A1>5", &CellValue::Bool(true)),
+                (2, "SUM(A1:A2)", &CellValue::Number(30.0)),
+                (1, "SUM(A1:A2)*2", &CellValue::Number(60.0)),
+                (0, "IF(A1>5,SUM(A1:A2)*2,0)", &CellValue::Number(60.0)),
+            ]
+        );
+        assert!(wb.trace_formula("Sheet1", "A1").unwrap().is_empty());
+    }
+}
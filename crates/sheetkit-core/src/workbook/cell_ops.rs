@@ -5,8 +5,18 @@ impl Workbook {
     ///
     /// Returns [`CellValue::Empty`] for cells that have no value or do not
     /// exist in the sheet data.
+    ///
+    /// An array formula is returned as [`CellValue::Formula`] on the first
+    /// cell of its range, while the other cells return their cached values.
+    /// Use [`get_cell_array_formula`](Self::get_cell_array_formula) to find
+    /// out whether a cell belongs to an array formula.
+    ///
+    /// A number with a date format is returned as [`CellValue::Date`],
+    /// converted to the 1900 date system if the workbook uses the 1904 date
+    /// system.
     pub fn get_cell_value(&self, sheet: &str, cell: &str) -> Result<CellValue> {
-        let ws = self.worksheet_ref(sheet)?;
+        let sheet_idx = self.sheet_index(sheet)?;
+        let ws = self.worksheet_ref_by_index(sheet_idx)?;
 
         let (col, row) = cell_name_to_coordinates(cell)?;
 
@@ -22,15 +32,31 @@ impl Workbook {
             Err(_) => return Ok(CellValue::Empty),
         };
 
-        self.xml_cell_to_value(xml_cell)
+        let mut value = self.xml_cell_to_value(xml_cell)?;
+        if let Some(si) = crate::shared_formula::shared_child_si(xml_cell) {
+            if let (CellValue::Formula { expr, .. }, Some(master)) =
+                (&mut value, self.shared_masters(sheet_idx)?.get(&si))
+            {
+                *expr = master.expand(col, row)?;
+            }
+        }
+        if let CellValue::Date(serial) = value {
+            value = CellValue::Date(self.date_system().to_1900_serial(serial));
+        }
+        Ok(value)
     }
 
     /// Set the value of a cell.
     ///
     /// The value can be any type that implements `Into<CellValue>`, including
-    /// `&str`, `String`, `f64`, `i32`, `i64`, and `bool`.
+    /// `&str`, `String`, `f64`, `i32`, `i64`, `bool`, and chrono dates.
+    /// A [`CellValue::Date`] is converted to the date system of the workbook.
     ///
     /// Setting a cell to [`CellValue::Empty`] removes the cell from the row.
+    /// Setting the first cell of an array formula range replaces the whole
+    /// array formula, clearing the values in its other cells. The other
+    /// cells of a legacy array formula cannot be set on their own and
+    /// return [`Error::ArrayFormulaPart`].
     pub fn set_cell_value(
         &mut self,
         sheet: &str,
@@ -52,10 +78,39 @@ impl Workbook {
         let sheet_idx = self.sheet_index(sheet)?;
         self.invalidate_streamed(sheet_idx);
         self.ensure_hydrated(sheet_idx)?;
+        let (col, row_num) = cell_name_to_coordinates(cell)?;
+        self.check_array_part(sheet_idx, col, row_num)?;
+        // A value cannot start a legacy array formula, so the sheet's array
+        // formula ranges stay valid unless the value replaces an anchor.
+        let legacy_arrays = self.legacy_arrays[sheet_idx].take();
         self.mark_sheet_dirty(sheet_idx);
 
-        let (col, row_num) = cell_name_to_coordinates(cell)?;
         let cell_ref = crate::utils::cell_ref::coordinates_to_cell_name(col, row_num)?;
+        self.mark_cell_dirty(sheet_idx, col, row_num);
+        let date_system = self.date_system();
+
+        // Overwriting the anchor of an array formula replaces the whole
+        // array, so the values it filled or spilled into its other cells go
+        // too.
+        let ws = self.worksheet_ref_by_index(sheet_idx)?;
+        let old_array = ws
+            .sheet_data
+            .rows
+            .binary_search_by_key(&row_num, |r| r.r)
+            .ok()
+            .and_then(|idx| {
+                let row = &ws.sheet_data.rows[idx];
+                let cell = &row.cells[row.cells.binary_search_by_key(&col, |c| c.col).ok()?];
+                crate::array_formula::array_area(cell.f.as_deref()?)
+            });
+        if let Some(area) = old_array {
+            for (c, r) in area.cells().skip(1) {
+                self.write_spill_cell(sheet_idx, c, r, CellValue::Empty)?;
+            }
+            self.invalidate_calc_cache();
+        } else if let Some(areas) = legacy_arrays {
+            let _ = self.legacy_arrays[sheet_idx].set(areas);
+        }
 
         let ws = self.worksheets[sheet_idx].1.get_mut().unwrap();
 
@@ -68,6 +123,19 @@ impl Workbook {
             }
         };
 
+        // Overwriting the master of a shared formula group would orphan the
+        // other cells of the group, so turn them into standalone formulas.
+        let master_si = ws.sheet_data.rows[row_idx]
+            .cells
+            .binary_search_by_key(&col, |c| c.col)
+            .ok()
+            .and_then(|idx| {
+                crate::shared_formula::shared_master_si(&ws.sheet_data.rows[row_idx].cells[idx])
+            });
+        if let Some(si) = master_si {
+            crate::shared_formula::unshare_formulas(ws, Some(si))?;
+        }
+
         let row = &mut ws.sheet_data.rows[row_idx];
 
         // Handle Empty: remove the cell if present.
@@ -99,12 +167,13 @@ impl Workbook {
         };
 
         let xml_cell = &mut row.cells[cell_idx];
-        value_to_xml_cell(&mut self.sst_runtime, xml_cell, value);
+        value_to_xml_cell(&mut self.sst_runtime, xml_cell, value, date_system);
 
         Ok(())
     }
 
-    /// Convert an XML Cell to a CellValue.
+    /// Convert an XML Cell to a CellValue. A [`CellValue::Date`] holds the
+    /// stored serial number, in the date system of the workbook.
     pub(crate) fn xml_cell_to_value(&self, xml_cell: &Cell) -> Result<CellValue> {
         // Check for formula first.
         if let Some(ref formula) = xml_cell.f {
@@ -153,6 +222,11 @@ impl Workbook {
             }
             // Formula string (cached string result)
             (CellTypeTag::FormulaString, Some(v)) => Ok(CellValue::String(v.to_string())),
+            // Date serial
+            (CellTypeTag::Date, Some(v)) => match v.parse::<f64>() {
+                Ok(n) => Ok(CellValue::Date(n)),
+                Err(_) => Ok(CellValue::Empty),
+            },
             // Number (explicit or default type) -- may be a date if styled.
             (CellTypeTag::None | CellTypeTag::Number, Some(v)) => {
                 let n: f64 = v
@@ -236,7 +310,11 @@ impl Workbook {
 
         if let Some(val) = numeric_val {
             if let Some(format_code) = self.cell_format_code(xml_cell) {
-                return Ok(crate::numfmt::format_number(val, &format_code));
+                return Ok(crate::numfmt::format_number_with_date_system(
+                    val,
+                    &format_code,
+                    self.date_system(),
+                ));
             }
         }
 
@@ -396,6 +474,94 @@ impl Workbook {
         )
     }
 
+    /// Set a legacy array formula (entered with Ctrl+Shift+Enter in Excel)
+    /// on a range of cells.
+    ///
+    /// The formula is stored on the top-left cell of `range` and evaluated
+    /// element-wise, so `"SUM(A1:A10*B1:B10)"` on a single cell sums the
+    /// pairwise products and `"A1:A10*B1:B10"` on `"C1:C10"` fills each cell
+    /// with one product. Values in the other cells of the range are cleared;
+    /// they receive their part of the result on the next
+    /// [`calculate_all`](Self::calculate_all).
+    pub fn set_cell_array_formula(
+        &mut self,
+        sheet: &str,
+        range: &str,
+        formula: &str,
+    ) -> Result<()> {
+        let area = crate::array_formula::ArrayArea::parse(range)
+            .ok_or_else(|| Error::InvalidCellReference(format!("invalid range: {range}")))?;
+        let anchor = crate::utils::cell_ref::coordinates_to_cell_name(area.min_col, area.min_row)?;
+        self.set_cell_formula(sheet, &anchor, formula)?;
+
+        let sheet_idx = self.sheet_index(sheet)?;
+        self.invalidate_calc_cache();
+        let ws = self.worksheet_mut_by_index(sheet_idx)?;
+        // Clearing the master of a shared formula group would orphan the
+        // other cells of the group, so turn them into standalone formulas.
+        let master_sis: Vec<u32> = ws
+            .sheet_data
+            .rows
+            .iter()
+            .filter(|r| (area.min_row..=area.max_row).contains(&r.r))
+            .flat_map(|r| r.cells.iter().map(move |c| (c, r.r)))
+            .filter(|(c, r)| {
+                area.contains(c.col, *r) && (c.col, *r) != (area.min_col, area.min_row)
+            })
+            .filter_map(|(c, _)| crate::shared_formula::shared_master_si(c))
+            .collect();
+        for si in master_sis {
+            crate::shared_formula::unshare_formulas(ws, Some(si))?;
+        }
+        for (col, row) in area.cells().skip(1) {
+            self.write_spill_cell(sheet_idx, col, row, CellValue::Empty)?;
+        }
+
+        let reference = area.to_reference()?;
+        let ws = self.worksheets[sheet_idx].1.get_mut().unwrap();
+        let formula_cell = ws
+            .sheet_data
+            .rows
+            .iter_mut()
+            .find(|r| r.r == area.min_row)
+            .and_then(|r| r.cells.iter_mut().find(|c| c.col == area.min_col))
+            .and_then(|c| c.f.as_mut());
+        if let Some(f) = formula_cell {
+            f.t = Some("array".to_string());
+            f.reference = Some(reference);
+        }
+        Ok(())
+    }
+
+    /// Get the array formula covering a cell as `(range, formula)`, e.g.
+    /// `("C1:C10", "A1:A10*B1:B10")`.
+    ///
+    /// Every cell of the range reports the same array formula. Returns
+    /// `None` if the cell is not part of an array formula.
+    pub fn get_cell_array_formula(
+        &self,
+        sheet: &str,
+        cell: &str,
+    ) -> Result<Option<(String, String)>> {
+        let ws = self.worksheet_ref(sheet)?;
+        let (col, row) = cell_name_to_coordinates(cell)?;
+        let Some(((anchor_col, anchor_row), area)) =
+            crate::array_formula::find_array_formula(ws, col, row)
+        else {
+            return Ok(None);
+        };
+        let formula = ws
+            .sheet_data
+            .rows
+            .iter()
+            .find(|r| r.r == anchor_row)
+            .and_then(|r| r.cells.iter().find(|c| c.col == anchor_col))
+            .and_then(|c| c.f.as_ref())
+            .and_then(|f| f.value.clone())
+            .unwrap_or_default();
+        Ok(Some((area.to_reference()?, formula)))
+    }
+
     /// Fill a range of cells with a formula, adjusting row references for each
     /// row relative to the first cell in the range.
     ///
@@ -441,6 +607,28 @@ impl Workbook {
         Ok(())
     }
 
+    /// Convert every shared formula on the sheet into standalone formulas.
+    ///
+    /// Files written by Excel store runs of similar formulas once on a master
+    /// cell and mark the other cells with only a shared group index. After
+    /// this call each cell carries its own formula text, which makes the
+    /// sheet easier to edit and readable by tools without shared formula
+    /// support. Returns the number of cells rewritten.
+    pub fn unshare_formulas(&mut self, sheet: &str) -> Result<usize> {
+        let ws = self.worksheet_mut(sheet)?;
+        crate::shared_formula::unshare_formulas(ws, None)
+    }
+
+    /// Compact vertical runs of relatively-copied formulas on the sheet into
+    /// shared formulas, the representation Excel itself writes.
+    ///
+    /// Existing shared groups are rebuilt. Returns the number of shared
+    /// groups on the sheet afterwards.
+    pub fn share_formulas(&mut self, sheet: &str) -> Result<usize> {
+        let ws = self.worksheet_mut(sheet)?;
+        crate::shared_formula::share_formulas(ws)
+    }
+
     /// Set a cell to a rich text value (multiple formatted runs).
     pub fn set_cell_rich_text(
         &mut self,
@@ -497,7 +685,12 @@ impl Workbook {
         let sheet_idx = self.sheet_index(sheet)?;
         self.invalidate_streamed(sheet_idx);
         self.ensure_hydrated(sheet_idx)?;
+        for (cell, _) in &entries {
+            let (col, row) = cell_name_to_coordinates(cell)?;
+            self.check_array_part(sheet_idx, col, row)?;
+        }
         self.mark_sheet_dirty(sheet_idx);
+        let date_system = self.date_system();
 
         for (cell, value) in entries {
             if let CellValue::String(ref s) = value {
@@ -511,6 +704,7 @@ impl Workbook {
 
             let (col, row_num) = cell_name_to_coordinates(&cell)?;
             let cell_ref = crate::utils::cell_ref::coordinates_to_cell_name(col, row_num)?;
+            self.mark_cell_dirty(sheet_idx, col, row_num);
 
             let row_idx = {
                 let ws = self.worksheets[sheet_idx].1.get_mut().unwrap();
@@ -570,7 +764,7 @@ impl Workbook {
                 .sheet_data
                 .rows[row_idx]
                 .cells[cell_idx];
-            value_to_xml_cell(&mut self.sst_runtime, xml_cell, value);
+            value_to_xml_cell(&mut self.sst_runtime, xml_cell, value, date_system);
         }
 
         Ok(())
@@ -593,7 +787,17 @@ impl Workbook {
     ) -> Result<()> {
         let sheet_idx = self.sheet_index(sheet)?;
         self.ensure_hydrated(sheet_idx)?;
+        for (row_offset, row_values) in data.iter().enumerate() {
+            for col_offset in 0..row_values.len() {
+                self.check_array_part(
+                    sheet_idx,
+                    start_col + col_offset as u32,
+                    start_row + row_offset as u32,
+                )?;
+            }
+        }
         self.mark_sheet_dirty(sheet_idx);
+        let date_system = self.date_system();
 
         // Pre-compute column names for the widest row.
         let max_cols = data.iter().map(|r| r.len()).max().unwrap_or(0) as u32;
@@ -617,6 +821,7 @@ impl Workbook {
 
             for (col_offset, value) in row_values.into_iter().enumerate() {
                 let col = start_col + col_offset as u32;
+                self.mark_cell_dirty(sheet_idx, col, row_num);
 
                 if let CellValue::String(ref s) = value {
                     if s.len() > MAX_CELL_CHARS {
@@ -676,7 +881,7 @@ impl Workbook {
                     .sheet_data
                     .rows[row_idx]
                     .cells[cell_idx];
-                value_to_xml_cell(&mut self.sst_runtime, xml_cell, value);
+                value_to_xml_cell(&mut self.sst_runtime, xml_cell, value, date_system);
             }
         }
 
@@ -696,6 +901,137 @@ impl Workbook {
     ) -> Result<()> {
         self.set_sheet_data(sheet, vec![values], row_num, start_col)
     }
+
+    /// Copy the cells of `src_range` (e.g. `"A1:C10"`) on `src_sheet` to
+    /// `dst_sheet`, with the top-left cell landing on `dst_cell`.
+    ///
+    /// `options` chooses what is pasted, like Excel's Paste Special: values,
+    /// formulas (with relative references adjusted to the new position, and
+    /// references pushed off the sheet replaced by `#REF!`), styles,
+    /// comments, data validations, conditional formats, and merged cells,
+    /// optionally transposed or skipping blank source cells. The source and
+    /// destination may overlap.
+    ///
+    /// Returns [`Error::ArrayFormulaPart`] if `src_range` holds part of a
+    /// legacy array formula range but not all of it.
+    pub fn copy_range(
+        &mut self,
+        src_sheet: &str,
+        src_range: &str,
+        dst_sheet: &str,
+        dst_cell: &str,
+        options: &CopyRangeOptions,
+    ) -> Result<()> {
+        let src_idx = self.sheet_index(src_sheet)?;
+        let dst_idx = self.sheet_index(dst_sheet)?;
+        let area = CellRange::parse(src_range)?;
+        let placement = Placement::new(area, dst_cell, options.transpose)?;
+        let block = RangeBlock::read(self.worksheet_ref_by_index(src_idx)?, area)?;
+
+        self.invalidate_calc_cache();
+        let ws = self.worksheet_mut(dst_sheet)?;
+        block.paste(ws, &placement, options, true)?;
+        if options.comments {
+            let comments = self.take_comments(src_idx, area, false);
+            self.paste_comments(dst_idx, &placement, comments)?;
+        }
+        Ok(())
+    }
+
+    /// Move the cells of `src_range` on `src_sheet` to `dst_sheet`, with the
+    /// top-left cell landing on `dst_cell`, like Excel's cut and paste.
+    ///
+    /// Everything moves: values, formulas, styles, comments, data
+    /// validations, conditional formats, and merged cells. Formulas keep
+    /// referring to the same cells, and references to the moved cells
+    /// anywhere in the workbook follow them, while references to the cells
+    /// they were pasted over become `#REF!`.
+    ///
+    /// Returns [`Error::ArrayFormulaPart`] if `src_range` holds part of a
+    /// legacy array formula range but not all of it.
+    pub fn move_range(
+        &mut self,
+        src_sheet: &str,
+        src_range: &str,
+        dst_sheet: &str,
+        dst_cell: &str,
+    ) -> Result<()> {
+        let src_idx = self.sheet_index(src_sheet)?;
+        let dst_idx = self.sheet_index(dst_sheet)?;
+        let from_sheet = self.worksheets[src_idx].0.clone();
+        let to_sheet = self.worksheets[dst_idx].0.clone();
+        let area = CellRange::parse(src_range)?;
+        let placement = Placement::new(area, dst_cell, false)?;
+        let mut block = RangeBlock::read(self.worksheet_ref_by_index(src_idx)?, area)?;
+        if src_idx != dst_idx {
+            block.qualify_formulas(&from_sheet)?;
+        }
+
+        let comments = self.take_comments(src_idx, area, true);
+        self.invalidate_calc_cache();
+        RangeBlock::clear(self.worksheet_mut(src_sheet)?, area)?;
+        let ws = self.worksheet_mut(dst_sheet)?;
+        block.paste(ws, &placement, &CopyRangeOptions::default(), false)?;
+        self.paste_comments(dst_idx, &placement, comments)?;
+
+        let mv = RefMove {
+            from_sheet: &from_sheet,
+            from: area,
+            to_sheet: &to_sheet,
+            to: placement.dst(),
+        };
+        self.update_formula_references(|text, host| move_formula_references(text, host, mv))
+    }
+
+    /// Return the comments on the cells of `area`, removing them from the
+    /// sheet if `remove` is set.
+    fn take_comments(
+        &mut self,
+        sheet_idx: usize,
+        area: CellRange,
+        remove: bool,
+    ) -> Vec<((u32, u32), CommentConfig)> {
+        self.hydrate_comments(sheet_idx);
+        let comments: Vec<_> = crate::comment::get_all_comments(&self.sheet_comments[sheet_idx])
+            .into_iter()
+            .filter_map(|comment| {
+                let (col, row) = cell_name_to_coordinates(&comment.cell).ok()?;
+                area.contains(col, row).then_some(((col, row), comment))
+            })
+            .collect();
+        if remove && !comments.is_empty() {
+            for (_, comment) in &comments {
+                crate::comment::remove_comment(&mut self.sheet_comments[sheet_idx], &comment.cell);
+            }
+            if let Some(vml) = self.sheet_vml.get_mut(sheet_idx) {
+                *vml = None;
+            }
+        }
+        comments
+    }
+
+    /// Replace the comments on the cells a range is pasted over with the
+    /// comments taken from the source.
+    fn paste_comments(
+        &mut self,
+        sheet_idx: usize,
+        placement: &Placement,
+        comments: Vec<((u32, u32), CommentConfig)>,
+    ) -> Result<()> {
+        let overwritten = self.take_comments(sheet_idx, placement.dst(), true);
+        if comments.is_empty() && overwritten.is_empty() {
+            return Ok(());
+        }
+        for ((col, row), mut comment) in comments {
+            let (col, row) = placement.map(col, row);
+            comment.cell = crate::utils::cell_ref::coordinates_to_cell_name(col, row)?;
+            crate::comment::add_comment(&mut self.sheet_comments[sheet_idx], &comment);
+        }
+        if let Some(vml) = self.sheet_vml.get_mut(sheet_idx) {
+            *vml = None;
+        }
+        Ok(())
+    }
 }
 
 /// Write a CellValue into an XML Cell (mutating it in place).
@@ -703,6 +1039,7 @@ pub(crate) fn value_to_xml_cell(
     sst: &mut SharedStringTable,
     xml_cell: &mut Cell,
     value: CellValue,
+    date_system: DateSystem,
 ) {
     // Clear previous values.
     xml_cell.t = CellTypeTag::None;
@@ -722,7 +1059,7 @@ pub(crate) fn value_to_xml_cell(
         CellValue::Date(serial) => {
             // Dates are stored as numbers in Excel. The style must apply a
             // date number format for correct display.
-            xml_cell.v = Some(serial.to_string());
+            xml_cell.v = Some(date_system.from_1900_serial(serial).to_string());
         }
         CellValue::Bool(b) => {
             xml_cell.t = CellTypeTag::Boolean;
@@ -984,6 +1321,100 @@ mod tests {
         assert_eq!(val, CellValue::Date(serial));
     }
 
+    #[test]
+    fn test_readers_convert_1904_date_cells() {
+        let mut wb = Workbook::new();
+        wb.set_date_system(crate::cell::DateSystem::Date1904);
+        wb.set_cell_value("Sheet1", "A1", 43465.0).unwrap();
+        let ws = wb.worksheet_mut("Sheet1").unwrap();
+        ws.sheet_data.rows[0].cells[0].t = CellTypeTag::Date;
+        let buf = wb.save_to_buffer().unwrap();
+
+        let wb2 = Workbook::open_from_buffer(&buf).unwrap();
+        let expected = CellValue::Date(44927.0);
+        assert_eq!(wb2.get_cell_value("Sheet1", "A1").unwrap(), expected);
+        assert_eq!(wb2.get_rows("Sheet1").unwrap()[0].1[0].1, expected);
+        assert_eq!(wb2.get_cols("Sheet1").unwrap()[0].1[0].1, expected);
+        let rows = wb2
+            .open_sheet_reader("Sheet1")
+            .unwrap()
+            .next_batch(10)
+            .unwrap();
+        assert_eq!(rows[0].cells[0].1, expected);
+        let rows = wb2
+            .open_sheet_reader_owned("Sheet1")
+            .unwrap()
+            .next_batch(10)
+            .unwrap();
+        assert_eq!(rows[0].cells[0].1, expected);
+    }
+
+    #[test]
+    fn test_date_values_in_1904_date_system() {
+        use crate::cell::DateSystem;
+        use crate::style::{builtin_num_fmts, NumFmtStyle, Style};
+
+        let mut wb = Workbook::new();
+        wb.set_date_system(DateSystem::Date1904);
+        assert_eq!(wb.date_system(), DateSystem::Date1904);
+        assert_eq!(wb.get_workbook_settings().date1904, Some(true));
+        let style_id = wb
+            .add_style(&Style {
+                num_fmt: Some(NumFmtStyle::Custom("yyyy-mm-dd".to_string())),
+                ..Style::default()
+            })
+            .unwrap();
+        let datetime_style = wb
+            .add_style(&Style {
+                num_fmt: Some(NumFmtStyle::Builtin(builtin_num_fmts::DATETIME)),
+                ..Style::default()
+            })
+            .unwrap();
+
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
+        wb.set_cell_value("Sheet1", "A1", date).unwrap();
+        wb.set_cell_style("Sheet1", "A1", style_id).unwrap();
+        wb.set_cell_value("Sheet1", "A2", 43844.5).unwrap();
+        wb.set_cell_style("Sheet1", "A2", datetime_style).unwrap();
+
+        let val = wb.get_cell_value("Sheet1", "A1").unwrap();
+        assert_eq!(val, CellValue::from(date));
+        assert_eq!(
+            wb.get_cell_formatted_value("Sheet1", "A1").unwrap(),
+            "2024-01-15"
+        );
+        assert_eq!(
+            wb.get_cell_formatted_value("Sheet1", "A2").unwrap(),
+            "1/15/2024 12:00"
+        );
+        assert_eq!(
+            wb.evaluate_formula("Sheet1", "YEAR(A1)").unwrap(),
+            CellValue::Number(2024.0)
+        );
+        assert_eq!(
+            wb.evaluate_formula("Sheet1", "A1-DATE(2024,1,1)").unwrap(),
+            CellValue::Number(14.0)
+        );
+        assert_eq!(
+            wb.evaluate_formula("Sheet1", "DATE(2024,1,15)").unwrap(),
+            val
+        );
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("date1904.xlsx");
+        wb.save(&path).unwrap();
+        let mut wb2 = Workbook::open(&path).unwrap();
+        assert_eq!(wb2.date_system(), DateSystem::Date1904);
+        assert_eq!(wb2.get_cell_value("Sheet1", "A1").unwrap(), val);
+
+        // Switching the date system keeps the stored serial numbers.
+        wb2.set_date_system(DateSystem::Date1900);
+        assert_eq!(
+            wb2.get_cell_value("Sheet1", "A1").unwrap(),
+            CellValue::Date(43844.0)
+        );
+    }
+
     #[test]
     fn test_date_from_naive_date_conversion() {
         let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
@@ -1441,6 +1872,79 @@ mod tests {
         assert!(wb.fill_formula("Sheet1", "A1:B5", "C1").is_err());
     }
 
+    #[test]
+    fn test_set_cell_array_formula() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "D2", "stale").unwrap();
+        wb.set_cell_array_formula("Sheet1", "D1:D3", "A1:A3*B1:B3")
+            .unwrap();
+
+        match wb.get_cell_value("Sheet1", "D1").unwrap() {
+            CellValue::Formula { expr, .. } => assert_eq!(expr, "A1:A3*B1:B3"),
+            other => panic!("expected Formula, got {:?}", other),
+        }
+        assert_eq!(wb.get_cell_value("Sheet1", "D2").unwrap(), CellValue::Empty);
+        let expected = Some(("D1:D3".to_string(), "A1:A3*B1:B3".to_string()));
+        assert_eq!(wb.get_cell_array_formula("Sheet1", "D1").unwrap(), expected);
+        assert_eq!(wb.get_cell_array_formula("Sheet1", "D3").unwrap(), expected);
+        assert_eq!(wb.get_cell_array_formula("Sheet1", "D4").unwrap(), None);
+
+        // The array formula survives a save/open round trip.
+        let buf = wb.save_to_buffer().unwrap();
+        let wb2 = Workbook::open_from_buffer(&buf).unwrap();
+        assert_eq!(
+            wb2.get_cell_array_formula("Sheet1", "D2").unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_set_cell_in_array_formula_range() {
+        let mut wb = Workbook::new();
+        wb.set_cell_array_formula("Sheet1", "D1:D3", "A1:A3*B1:B3")
+            .unwrap();
+        wb.set_cell_formula("Sheet1", "F1", "SEQUENCE(2)").unwrap();
+        wb.calculate_all().unwrap();
+
+        let part = |result: Result<()>| matches!(result, Err(Error::ArrayFormulaPart { range }) if range == "D1:D3");
+        assert!(part(wb.set_cell_value("Sheet1", "D2", "x")));
+        assert!(part(wb.set_cell_value("Sheet1", "D3", CellValue::Empty)));
+        assert!(part(wb.set_cell_values(
+            "Sheet1",
+            vec![
+                ("E1".to_string(), 1.0.into()),
+                ("D3".to_string(), 2.0.into())
+            ],
+        )));
+        assert!(part(wb.set_sheet_data(
+            "Sheet1",
+            vec![vec![1.0.into()]],
+            2,
+            4
+        )));
+        assert_eq!(wb.get_cell_value("Sheet1", "E1").unwrap(), CellValue::Empty);
+
+        // Cells outside the range and dynamic array spills can be written.
+        wb.set_cell_value("Sheet1", "D4", "x").unwrap();
+        wb.set_cell_value("Sheet1", "F2", "x").unwrap();
+        assert!(part(wb.set_cell_value("Sheet1", "D2", "x")));
+
+        let buf = wb.save_to_buffer().unwrap();
+        let mut wb2 = Workbook::open_from_buffer(&buf).unwrap();
+        assert!(part(wb2.set_cell_value("Sheet1", "D2", "x")));
+
+        // Writing the anchor replaces the whole array.
+        wb.set_cell_value("Sheet1", "D1", 1.0).unwrap();
+        wb.set_cell_value("Sheet1", "D2", "x").unwrap();
+        assert_eq!(wb.get_cell_array_formula("Sheet1", "D3").unwrap(), None);
+    }
+
+    #[test]
+    fn test_set_cell_array_formula_invalid_range() {
+        let mut wb = Workbook::new();
+        assert!(wb.set_cell_array_formula("Sheet1", "bad", "1").is_err());
+    }
+
     #[test]
     fn test_set_cell_values_batch() {
         let mut wb = Workbook::new();
@@ -1678,4 +2182,246 @@ mod tests {
             CellValue::String("new".to_string())
         );
     }
+
+    fn formula_at(wb: &Workbook, sheet: &str, cell: &str) -> String {
+        match wb.get_cell_value(sheet, cell).unwrap() {
+            CellValue::Formula { expr, .. } => expr,
+            other => panic!("expected formula, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_copy_range_adjusts_relative_references() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "A1", 1.0).unwrap();
+        wb.set_cell_value("Sheet1", "A2", 2.0).unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "A1*2").unwrap();
+        wb.set_cell_formula("Sheet1", "B2", "$A$2+A1").unwrap();
+        let style = wb
+            .add_style(&crate::style::StyleBuilder::new().bold(true).build())
+            .unwrap();
+        wb.set_cell_style("Sheet1", "A1", style).unwrap();
+
+        wb.copy_range(
+            "Sheet1",
+            "A1:B2",
+            "Sheet1",
+            "D5",
+            &CopyRangeOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "D5").unwrap(),
+            CellValue::Number(1.0)
+        );
+        assert_eq!(wb.get_cell_style("Sheet1", "D5").unwrap(), Some(style));
+        assert_eq!(formula_at(&wb, "Sheet1", "E5"), "D5*2");
+        assert_eq!(formula_at(&wb, "Sheet1", "E6"), "$A$2+D5");
+        // The source is unchanged.
+        assert_eq!(formula_at(&wb, "Sheet1", "B1"), "A1*2");
+    }
+
+    #[test]
+    fn test_copy_range_with_part_of_array_formula() {
+        let mut wb = Workbook::new();
+        wb.set_cell_array_formula("Sheet1", "C1:C3", "A1:A3*2")
+            .unwrap();
+        let options = CopyRangeOptions::default();
+
+        for src in ["C1:C2", "C2:C3", "B2:D2"] {
+            let result = wb.copy_range("Sheet1", src, "Sheet1", "H1", &options);
+            assert!(
+                matches!(result, Err(Error::ArrayFormulaPart { ref range }) if range == "C1:C3"),
+                "{src}: {result:?}"
+            );
+        }
+        assert!(matches!(
+            wb.move_range("Sheet1", "C1:C2", "Sheet1", "H1"),
+            Err(Error::ArrayFormulaPart { .. })
+        ));
+        assert_eq!(wb.get_cell_value("Sheet1", "H1").unwrap(), CellValue::Empty);
+
+        // The whole array formula can be copied.
+        wb.copy_range("Sheet1", "B1:C3", "Sheet1", "G1", &options)
+            .unwrap();
+        let ws = wb.worksheet_ref("Sheet1").unwrap();
+        let f = ws.sheet_data.rows[0].cells[1].f.as_deref().unwrap();
+        assert_eq!(f.reference.as_deref(), Some("H1:H3"));
+    }
+
+    #[test]
+    fn test_copy_range_keeps_function_names_and_flags_off_sheet_refs() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "C3", 100.0).unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "LOG10(A1)").unwrap();
+        wb.set_cell_formula("Sheet1", "B5", "A1+A5").unwrap();
+        let options = CopyRangeOptions::default();
+
+        wb.copy_range("Sheet1", "B1", "Sheet1", "D3", &options)
+            .unwrap();
+        assert_eq!(formula_at(&wb, "Sheet1", "D3"), "LOG10(C3)");
+        wb.calculate_all().unwrap();
+        match wb.get_cell_value("Sheet1", "D3").unwrap() {
+            CellValue::Formula { result, .. } => {
+                assert_eq!(*result.unwrap(), CellValue::Number(2.0))
+            }
+            other => panic!("expected formula, got {other:?}"),
+        }
+
+        wb.copy_range("Sheet1", "B5", "Sheet1", "B1", &options)
+            .unwrap();
+        assert_eq!(formula_at(&wb, "Sheet1", "B1"), "#REF!+A1");
+    }
+
+    #[test]
+    fn test_copy_range_paste_values_transposed() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Out").unwrap();
+        wb.set_cell_value("Sheet1", "A1", 1.0).unwrap();
+        wb.set_cell_value("Sheet1", "B1", 2.0).unwrap();
+        wb.set_cell_formula("Sheet1", "C1", "A1+B1").unwrap();
+        wb.calculate_all().unwrap();
+
+        let options = CopyRangeOptions {
+            formulas: false,
+            transpose: true,
+            ..Default::default()
+        };
+        wb.copy_range("Sheet1", "A1:C1", "Out", "B2", &options)
+            .unwrap();
+
+        for (cell, value) in [("B2", 1.0), ("B3", 2.0), ("B4", 3.0)] {
+            assert_eq!(
+                wb.get_cell_value("Out", cell).unwrap(),
+                CellValue::Number(value)
+            );
+        }
+        assert_eq!(wb.get_cell_value("Out", "C2").unwrap(), CellValue::Empty);
+    }
+
+    #[test]
+    fn test_copy_range_skip_blanks() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "A2", "new").unwrap();
+        wb.set_cell_value("Sheet1", "D1", "keep").unwrap();
+        wb.set_cell_value("Sheet1", "D2", "old").unwrap();
+
+        let options = CopyRangeOptions {
+            skip_blanks: true,
+            ..Default::default()
+        };
+        wb.copy_range("Sheet1", "A1:A2", "Sheet1", "D1", &options)
+            .unwrap();
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "D1").unwrap(),
+            CellValue::String("keep".to_string())
+        );
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "D2").unwrap(),
+            CellValue::String("new".to_string())
+        );
+
+        // Without skipping blanks, the blank source cell clears D1.
+        wb.copy_range(
+            "Sheet1",
+            "A1:A2",
+            "Sheet1",
+            "D1",
+            &CopyRangeOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(wb.get_cell_value("Sheet1", "D1").unwrap(), CellValue::Empty);
+    }
+
+    #[test]
+    fn test_copy_range_validations_merges_and_comments() {
+        let mut wb = Workbook::new();
+        wb.add_data_validation(
+            "Sheet1",
+            &crate::validation::DataValidationConfig::whole_number("A1:A5", 1, 9),
+        )
+        .unwrap();
+        wb.merge_cells("Sheet1", "A1", "B1").unwrap();
+        wb.add_comment(
+            "Sheet1",
+            &CommentConfig {
+                cell: "A2".to_string(),
+                author: "Ann".to_string(),
+                text: "note".to_string(),
+            },
+        )
+        .unwrap();
+
+        wb.copy_range(
+            "Sheet1",
+            "A1:B2",
+            "Sheet1",
+            "D1",
+            &CopyRangeOptions::default(),
+        )
+        .unwrap();
+
+        let sqrefs: Vec<String> = wb
+            .get_data_validations("Sheet1")
+            .unwrap()
+            .into_iter()
+            .map(|dv| dv.sqref)
+            .collect();
+        assert_eq!(sqrefs, vec!["A1:A5", "D1:D2"]);
+        assert_eq!(
+            wb.get_merge_cells("Sheet1").unwrap(),
+            vec!["A1:B1", "D1:E1"]
+        );
+        let cells: Vec<String> = wb
+            .get_comments("Sheet1")
+            .unwrap()
+            .into_iter()
+            .map(|c| c.cell)
+            .collect();
+        assert_eq!(cells, vec!["A2", "D2"]);
+    }
+
+    #[test]
+    fn test_move_range_redirects_references() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Report").unwrap();
+        wb.set_cell_value("Sheet1", "A1", 5.0).unwrap();
+        wb.set_cell_formula("Sheet1", "A2", "A1*2").unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "A2+1").unwrap();
+        wb.set_cell_formula("Sheet1", "E1", "C4").unwrap();
+        wb.set_cell_formula("Report", "A1", "SUM(Sheet1!A1:A2)")
+            .unwrap();
+
+        wb.move_range("Sheet1", "A1:A2", "Sheet1", "C3").unwrap();
+
+        assert_eq!(wb.get_cell_value("Sheet1", "A1").unwrap(), CellValue::Empty);
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "C3").unwrap(),
+            CellValue::Number(5.0)
+        );
+        assert_eq!(formula_at(&wb, "Sheet1", "C4"), "C3*2");
+        assert_eq!(formula_at(&wb, "Sheet1", "B1"), "C4+1");
+        // E1 pointed at a cell the block was pasted over.
+        assert_eq!(formula_at(&wb, "Sheet1", "E1"), "#REF!");
+        assert_eq!(formula_at(&wb, "Report", "A1"), "SUM(Sheet1!C3:C4)");
+    }
+
+    #[test]
+    fn test_move_range_to_other_sheet() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Other").unwrap();
+        wb.set_cell_value("Sheet1", "A1", 1.0).unwrap();
+        wb.set_cell_formula("Sheet1", "A2", "A1+Z9").unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "A1").unwrap();
+
+        wb.move_range("Sheet1", "A1:A2", "Other", "B2").unwrap();
+
+        assert_eq!(
+            wb.get_cell_value("Other", "B2").unwrap(),
+            CellValue::Number(1.0)
+        );
+        assert_eq!(formula_at(&wb, "Other", "B3"), "Other!B2+Sheet1!Z9");
+        assert_eq!(formula_at(&wb, "Sheet1", "B1"), "Other!B2");
+    }
 }
@@ -125,6 +125,45 @@ impl Workbook {
 
     /// Get information about all pivot tables in the workbook.
     pub fn get_pivot_tables(&self) -> Vec<PivotTableInfo> {
+        let (pivot_tables, pivot_cache_defs) = self.pivot_parts();
+        pivot_tables
+            .iter()
+            .map(|(path, pt)| {
+                let (source_sheet, source_range) = self
+                    .pivot_cache_for(&pivot_cache_defs, pt)
+                    .and_then(|pcd| {
+                        pcd.cache_source
+                            .worksheet_source
+                            .as_ref()
+                            .map(|ws| (ws.sheet.clone(), ws.reference.clone()))
+                    })
+                    .unwrap_or_default();
+
+                // Determine target sheet from the pivot table path.
+                let target_sheet = self
+                    .find_pivot_table_target_sheet_by_path(path)
+                    .unwrap_or_default();
+
+                PivotTableInfo {
+                    name: pt.name.clone(),
+                    source_sheet,
+                    source_range,
+                    target_sheet,
+                    location: pt.location.reference.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Return all pivot table and pivot cache definitions, including parts
+    /// whose parsing was deferred.
+    #[allow(clippy::type_complexity)]
+    fn pivot_parts(
+        &self,
+    ) -> (
+        Vec<(String, sheetkit_xml::pivot_table::PivotTableDefinition)>,
+        Vec<(String, sheetkit_xml::pivot_cache::PivotCacheDefinition)>,
+    ) {
         use crate::workbook::aux::AuxCategory;
 
         let mut pivot_tables = self.pivot_tables.clone();
@@ -163,43 +202,27 @@ impl Workbook {
             }
         }
 
-        pivot_tables
-            .iter()
-            .map(|(path, pt)| {
-                // Find the matching cache definition by cache_id.
-                let (source_sheet, source_range) = pivot_cache_defs
-                    .iter()
-                    .enumerate()
-                    .find(|(i, _)| {
-                        self.workbook_xml
-                            .pivot_caches
-                            .as_ref()
-                            .and_then(|pc| pc.caches.iter().find(|e| e.cache_id == pt.cache_id))
-                            .is_some()
-                            || *i == pt.cache_id as usize
-                    })
-                    .and_then(|(_, (_, pcd))| {
-                        pcd.cache_source
-                            .worksheet_source
-                            .as_ref()
-                            .map(|ws| (ws.sheet.clone(), ws.reference.clone()))
-                    })
-                    .unwrap_or_default();
-
-                // Determine target sheet from the pivot table path.
-                let target_sheet = self
-                    .find_pivot_table_target_sheet_by_path(path)
-                    .unwrap_or_default();
+        (pivot_tables, pivot_cache_defs)
+    }
 
-                PivotTableInfo {
-                    name: pt.name.clone(),
-                    source_sheet,
-                    source_range,
-                    target_sheet,
-                    location: pt.location.reference.clone(),
-                }
+    /// Find the cache definition of a pivot table by its cache id.
+    fn pivot_cache_for<'a>(
+        &self,
+        pivot_cache_defs: &'a [(String, sheetkit_xml::pivot_cache::PivotCacheDefinition)],
+        pt: &sheetkit_xml::pivot_table::PivotTableDefinition,
+    ) -> Option<&'a sheetkit_xml::pivot_cache::PivotCacheDefinition> {
+        pivot_cache_defs
+            .iter()
+            .enumerate()
+            .find(|(i, _)| {
+                self.workbook_xml
+                    .pivot_caches
+                    .as_ref()
+                    .and_then(|pc| pc.caches.iter().find(|e| e.cache_id == pt.cache_id))
+                    .is_some()
+                    || *i == pt.cache_id as usize
             })
-            .collect()
+            .map(|(_, (_, pcd))| pcd)
     }
 
     /// Delete a pivot table by name.
@@ -337,140 +360,285 @@ impl Workbook {
     /// Evaluate a single formula string in the context of `sheet`.
     ///
     /// A [`CellSnapshot`] is built from the current workbook state so
-    /// that cell references within the formula can be resolved.
+    /// that cell references within the formula can be resolved. A
+    /// [`CellValue::Date`] result is converted to the 1900 date system.
     pub fn evaluate_formula(&self, sheet: &str, formula: &str) -> Result<CellValue> {
         // Validate the sheet exists.
         let _ = self.sheet_index(sheet)?;
         let parsed = crate::formula::parser::parse_formula(formula)?;
         let snapshot = self.build_cell_snapshot(sheet)?;
-        crate::formula::eval::evaluate(&parsed, &snapshot)
-    }
-
-    /// Recalculate every formula cell across all sheets and store the
-    /// computed result back into each cell. Uses a dependency graph and
-    /// topological sort so formulas are evaluated after their dependencies.
-    pub fn calculate_all(&mut self) -> Result<()> {
-        use crate::formula::eval::{build_dependency_graph, topological_sort, CellCoord};
-
-        let sheet_names: Vec<String> = self.sheet_names().iter().map(|s| s.to_string()).collect();
-
-        // Collect all formula cells with their coordinates and formula strings.
-        let mut formula_cells: Vec<(CellCoord, String)> = Vec::new();
-        for (idx, sn) in sheet_names.iter().enumerate() {
-            self.ensure_hydrated(idx)?;
-            let ws = self.worksheets[idx].1.get().unwrap();
-            for row in &ws.sheet_data.rows {
-                for cell in &row.cells {
-                    if let Some(ref f) = cell.f {
-                        let formula_str = f.value.clone().unwrap_or_default();
-                        if !formula_str.is_empty() {
-                            if let Ok((c, r)) = cell_name_to_coordinates(cell.r.as_str()) {
-                                formula_cells.push((
-                                    CellCoord {
-                                        sheet: sn.clone(),
-                                        col: c,
-                                        row: r,
-                                    },
-                                    formula_str,
-                                ));
-                            }
-                        }
-                    }
-                }
+        match crate::formula::eval::evaluate(&parsed, &snapshot)? {
+            CellValue::Date(serial) => {
+                Ok(CellValue::Date(self.date_system().to_1900_serial(serial)))
             }
+            value => Ok(value),
         }
+    }
 
-        if formula_cells.is_empty() {
-            return Ok(());
-        }
-
-        // Build dependency graph and determine evaluation order.
-        let deps = build_dependency_graph(&formula_cells)?;
-        let coords: Vec<CellCoord> = formula_cells.iter().map(|(c, _)| c.clone()).collect();
-        let eval_order = topological_sort(&coords, &deps)?;
-
-        // Build a lookup from coord to formula string.
-        let formula_map: HashMap<CellCoord, String> = formula_cells.into_iter().collect();
+    /// Register a formula function under `name`, for use by
+    /// [`calculate_all`](Self::calculate_all), [`evaluate_formula`](Self::evaluate_formula),
+    /// and the other calculation methods.
+    ///
+    /// `func` receives the unevaluated arguments and the evaluator, like the
+    /// built-in functions. Registered functions take precedence over built-in
+    /// functions of the same name, so this can also replace volatile
+    /// functions such as `TODAY` or `RAND` with deterministic ones. Names
+    /// bound by `LET` and `LAMBDA` take precedence over registered functions.
+    /// Registering a function under a name already registered replaces it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidArgument`] if `name` is not a valid function
+    /// name.
+    pub fn register_function<F>(&mut self, name: &str, func: F) -> Result<()>
+    where
+        F: Fn(
+                &[crate::formula::ast::Expr],
+                &mut crate::formula::eval::Evaluator,
+            ) -> Result<CellValue>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.functions.register(name, func)?;
+        self.invalidate_calc_cache();
+        Ok(())
+    }
 
-        // Build a snapshot of all cell data.
-        let first_sheet = sheet_names.first().cloned().unwrap_or_default();
-        let mut snapshot = self.build_cell_snapshot(&first_sheet)?;
+    /// Register a volatile formula function under `name`. Formulas calling
+    /// it are recalculated by every [`recalculate_dirty`](Self::recalculate_dirty),
+    /// like formulas calling `NOW`. See [`register_function`](Self::register_function).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidArgument`] if `name` is not a valid function
+    /// name.
+    pub fn register_volatile_function<F>(&mut self, name: &str, func: F) -> Result<()>
+    where
+        F: Fn(
+                &[crate::formula::ast::Expr],
+                &mut crate::formula::eval::Evaluator,
+            ) -> Result<CellValue>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.functions.register_volatile(name, func)?;
+        self.invalidate_calc_cache();
+        Ok(())
+    }
 
-        // Evaluate in dependency order, updating the snapshot progressively
-        // so later formulas see already-computed results.
-        let mut results: Vec<(CellCoord, String, CellValue)> = Vec::new();
-        for coord in &eval_order {
-            if let Some(formula_str) = formula_map.get(coord) {
-                snapshot.set_current_sheet(&coord.sheet);
-                let parsed = crate::formula::parser::parse_formula(formula_str)?;
-                let mut evaluator = crate::formula::eval::Evaluator::new(&snapshot);
-                let result = evaluator.eval_expr(&parsed)?;
-                snapshot.set_cell(&coord.sheet, coord.col, coord.row, result.clone());
-                results.push((coord.clone(), formula_str.clone(), result));
-            }
+    /// Remove the formula function registered under `name`, restoring the
+    /// built-in function of that name if there is one. Returns `true` if a
+    /// function was registered.
+    pub fn unregister_function(&mut self, name: &str) -> bool {
+        let removed = self.functions.unregister(name);
+        if removed {
+            self.invalidate_calc_cache();
         }
+        removed
+    }
 
-        // Write results back directly to the XML cells, preserving the
-        // formula element and storing the computed value in the v/t fields.
-        let sheet_name_to_idx: HashMap<String, usize> = sheet_names
-            .iter()
-            .enumerate()
-            .map(|(idx, name)| (name.clone(), idx))
-            .collect();
-
-        for (coord, _formula_str, result) in results {
-            let cell_ref = crate::utils::cell_ref::coordinates_to_cell_name(coord.col, coord.row)?;
-            let Some(&sheet_idx) = sheet_name_to_idx.get(&coord.sheet) else {
-                continue;
-            };
-            let Some(ws) = self.worksheets[sheet_idx].1.get_mut() else {
-                continue;
-            };
-            if let Some(row) = ws.sheet_data.rows.iter_mut().find(|r| r.r == coord.row) {
-                if let Some(cell) = row.cells.iter_mut().find(|c| c.r == *cell_ref) {
-                    let (new_v, new_t) = match &result {
-                        CellValue::Number(n) => (Some(n.to_string()), CellTypeTag::None),
-                        CellValue::String(s) => (Some(s.clone()), CellTypeTag::FormulaString),
-                        CellValue::Bool(b) => (
-                            Some(if *b { "1".to_string() } else { "0".to_string() }),
-                            CellTypeTag::Boolean,
-                        ),
-                        CellValue::Error(e) => (Some(e.clone()), CellTypeTag::Error),
-                        CellValue::Date(n) => (Some(n.to_string()), CellTypeTag::None),
-                        _ => continue,
-                    };
+    /// Return the registered formula functions.
+    pub fn function_registry(&self) -> &crate::formula::registry::FunctionRegistry {
+        &self.functions
+    }
 
-                    let changed = cell.v != new_v || cell.t != new_t;
-                    if changed {
-                        cell.v = new_v;
-                        cell.t = new_t;
-                        self.mark_sheet_dirty(sheet_idx);
-                    }
-                }
+    /// Store an array formula result value in a plain (non-formula) cell,
+    /// creating the row and cell as needed. An empty value clears the cell
+    /// but keeps its style.
+    pub(crate) fn write_spill_cell(
+        &mut self,
+        sheet_idx: usize,
+        col: u32,
+        row_num: u32,
+        value: CellValue,
+    ) -> Result<()> {
+        let ws = self.worksheets[sheet_idx].1.get_mut().unwrap();
+        let row_idx = match ws.sheet_data.rows.binary_search_by_key(&row_num, |r| r.r) {
+            Ok(idx) => idx,
+            Err(_) if value == CellValue::Empty => return Ok(()),
+            Err(idx) => {
+                ws.sheet_data
+                    .rows
+                    .insert(idx, super::cell_ops::new_row(row_num));
+                idx
             }
+        };
+        let row = &mut ws.sheet_data.rows[row_idx];
+        let cell_idx = match row.cells.binary_search_by_key(&col, |c| c.col) {
+            Ok(idx) => idx,
+            Err(_) if value == CellValue::Empty => return Ok(()),
+            Err(pos) => {
+                let cell_ref = crate::utils::cell_ref::coordinates_to_cell_name(col, row_num)?;
+                row.cells.insert(
+                    pos,
+                    Cell {
+                        r: cell_ref.into(),
+                        col,
+                        s: None,
+                        t: CellTypeTag::None,
+                        v: None,
+                        f: None,
+                        is: None,
+                    },
+                );
+                pos
+            }
+        };
+        if value == CellValue::Empty && row.cells[cell_idx].s.is_none() {
+            row.cells.remove(cell_idx);
+        } else {
+            // Evaluated dates are already serial numbers in the workbook's
+            // date system, so they are stored unconverted.
+            super::cell_ops::value_to_xml_cell(
+                &mut self.sst_runtime,
+                &mut row.cells[cell_idx],
+                value,
+                DateSystem::Date1900,
+            );
         }
-
+        self.mark_sheet_dirty(sheet_idx);
         Ok(())
     }
 
     /// Build a [`CellSnapshot`] for formula evaluation, with the given
     /// sheet as the current-sheet context.
-    fn build_cell_snapshot(
+    pub(crate) fn build_cell_snapshot(
         &self,
         current_sheet: &str,
     ) -> Result<crate::formula::eval::CellSnapshot> {
         let mut snapshot = crate::formula::eval::CellSnapshot::new(current_sheet.to_string());
+        snapshot.set_sheet_names(self.worksheets.iter().map(|(sn, _)| sn.clone()).collect());
+        snapshot.set_functions(self.functions.clone());
+        snapshot.set_date_system(self.date_system());
         for (idx, (sn, _)) in self.worksheets.iter().enumerate() {
             let ws = self.worksheet_ref_by_index(idx)?;
+            let shared_masters = self.shared_masters(idx)?;
             for row in &ws.sheet_data.rows {
                 for cell in &row.cells {
                     if let Ok((c, r)) = cell_name_to_coordinates(cell.r.as_str()) {
-                        let cv = self.xml_cell_to_value(cell)?;
+                        let mut cv = self.xml_cell_to_value(cell)?;
+                        if let CellValue::Formula { ref mut expr, .. } = cv {
+                            if let Some(text) = crate::shared_formula::effective_formula(
+                                cell,
+                                c,
+                                r,
+                                shared_masters,
+                            )? {
+                                *expr = text;
+                            }
+                            // Spill references read the range a dynamic
+                            // array formula last spilled into.
+                            let area = cell.f.as_deref().and_then(crate::array_formula::array_area);
+                            if let Some(area) = area.filter(|_| {
+                                crate::formula::parser::parse_formula(expr)
+                                    .is_ok_and(|e| crate::formula::eval::is_array_expr(&e))
+                            }) {
+                                snapshot.set_spill(sn, c, r, Some((area.max_col, area.max_row)));
+                            }
+                        }
                         snapshot.set_cell(sn, c, r, cv);
                     }
                 }
             }
         }
+        for dn in crate::defined_names::get_all_defined_names(&self.workbook_xml) {
+            let scope = match dn.scope {
+                crate::defined_names::DefinedNameScope::Workbook => None,
+                crate::defined_names::DefinedNameScope::Sheet(idx) => {
+                    match self.worksheets.get(idx as usize) {
+                        Some((sn, _)) => Some(sn.as_str()),
+                        None => continue,
+                    }
+                }
+            };
+            snapshot.set_defined_name(&dn.name, scope, &dn.value);
+        }
+        for (_, table_xml, idx) in self.collect_tables() {
+            let Some((sn, _)) = self.worksheets.get(idx) else {
+                continue;
+            };
+            let Some((start, end)) = table_xml.reference.split_once(':') else {
+                continue;
+            };
+            let (Ok((min_col, min_row)), Ok((max_col, max_row))) = (
+                cell_name_to_coordinates(start),
+                cell_name_to_coordinates(end),
+            ) else {
+                continue;
+            };
+            snapshot.add_table(crate::formula::eval::TableRange {
+                name: table_xml.display_name,
+                sheet: sn.clone(),
+                min_col,
+                min_row,
+                max_col,
+                max_row,
+                has_headers: table_xml.header_row_count != Some(0),
+                has_totals: table_xml.totals_row_count.unwrap_or(0) > 0,
+                columns: table_xml
+                    .table_columns
+                    .columns
+                    .into_iter()
+                    .map(|c| c.name)
+                    .collect(),
+            });
+        }
+        let (pivot_tables, pivot_cache_defs) = self.pivot_parts();
+        for (path, pt) in &pivot_tables {
+            let Some(target_sheet) = self.find_pivot_table_target_sheet_by_path(path) else {
+                continue;
+            };
+            let Some(pcd) = self.pivot_cache_for(&pivot_cache_defs, pt) else {
+                continue;
+            };
+            let Some(ws_source) = pcd.cache_source.worksheet_source.as_ref() else {
+                continue;
+            };
+            let (Some(location), Some(source)) = (
+                crate::formula::eval::RefArea::from_a1(&target_sheet, &pt.location.reference),
+                crate::formula::eval::RefArea::from_a1(&ws_source.sheet, &ws_source.reference),
+            ) else {
+                continue;
+            };
+            let field_name = |index: usize| -> Option<String> {
+                pcd.cache_fields.fields.get(index).map(|f| f.name.clone())
+            };
+            let axis_fields = |list: &Option<sheetkit_xml::pivot_table::FieldList>| {
+                list.iter()
+                    .flat_map(|l| &l.fields)
+                    .filter_map(|f| usize::try_from(f.index).ok().and_then(field_name))
+                    .collect::<Vec<_>>()
+            };
+            let data_fields = pt
+                .data_fields
+                .iter()
+                .flat_map(|d| &d.fields)
+                .filter_map(|d| {
+                    let field = field_name(d.field_index as usize)?;
+                    let function = d
+                        .subtotal
+                        .as_deref()
+                        .map_or(Some(crate::pivot::AggregateFunction::Sum), |s| {
+                            crate::pivot::AggregateFunction::from_xml_str(s)
+                        })?;
+                    Some(crate::formula::eval::PivotValueField {
+                        name: d.name.clone().unwrap_or_else(|| field.clone()),
+                        field,
+                        function,
+                    })
+                })
+                .collect();
+            snapshot.add_pivot_table(crate::formula::eval::PivotTableSource {
+                name: pt.name.clone(),
+                location,
+                source,
+                row_fields: axis_fields(&pt.row_fields),
+                col_fields: axis_fields(&pt.col_fields),
+                data_fields,
+            });
+        }
         Ok(snapshot)
     }
 
@@ -591,6 +759,71 @@ impl Workbook {
             .unwrap_or_default()
     }
 
+    /// Set the calculation properties (`calcPr` in `xl/workbook.xml`), such
+    /// as the calculation mode and the iterative calculation settings used
+    /// by [`Workbook::calculate_all`].
+    pub fn set_calc_settings(&mut self, settings: &crate::doc_props::CalcSettings) {
+        self.workbook_xml.calc_pr = Some(settings.to_calc_pr());
+    }
+
+    /// Get the calculation properties. All fields are `None` if the
+    /// workbook has no `calcPr` element.
+    pub fn get_calc_settings(&self) -> crate::doc_props::CalcSettings {
+        self.workbook_xml
+            .calc_pr
+            .as_ref()
+            .map(crate::doc_props::CalcSettings::from)
+            .unwrap_or_default()
+    }
+
+    /// Set the workbook properties (`workbookPr` in `xl/workbook.xml`).
+    /// See [`set_date_system`](Self::set_date_system) for the effect of
+    /// changing `date1904`.
+    pub fn set_workbook_settings(&mut self, settings: &crate::doc_props::WorkbookSettings) {
+        self.workbook_xml.workbook_pr = Some(settings.to_workbook_pr());
+        self.invalidate_calc_cache();
+    }
+
+    /// Get the workbook properties. All fields are `None` if the workbook
+    /// has no `workbookPr` element.
+    pub fn get_workbook_settings(&self) -> crate::doc_props::WorkbookSettings {
+        self.workbook_xml
+            .workbook_pr
+            .as_ref()
+            .map(crate::doc_props::WorkbookSettings::from)
+            .unwrap_or_default()
+    }
+
+    /// Return the date system of the workbook, selected by the `date1904`
+    /// workbook property.
+    ///
+    /// [`CellValue::Date`] values are always in the 1900 date system: they
+    /// are converted to and from this date system when cells are read and
+    /// written. Formulas evaluate serial numbers in this date system.
+    pub fn date_system(&self) -> crate::cell::DateSystem {
+        let date1904 = self
+            .workbook_xml
+            .workbook_pr
+            .as_ref()
+            .and_then(|pr| pr.date1904)
+            .unwrap_or(false);
+        crate::cell::DateSystem::from_date1904(date1904)
+    }
+
+    /// Switch the workbook to the given date system.
+    ///
+    /// Stored serial numbers are not rewritten, so dates already in the
+    /// workbook move by 1462 days, as they do when the option is changed in
+    /// Excel.
+    pub fn set_date_system(&mut self, date_system: crate::cell::DateSystem) {
+        let pr = self
+            .workbook_xml
+            .workbook_pr
+            .get_or_insert_with(|| crate::doc_props::WorkbookSettings::default().to_workbook_pr());
+        pr.date1904 = date_system.is_date1904().then_some(true);
+        self.invalidate_calc_cache();
+    }
+
     /// Set a custom property by name. If a property with the same name already
     /// exists, its value is replaced.
     pub fn set_custom_property(
@@ -1132,6 +1365,41 @@ mod tests {
         assert_eq!(wb.pivot_tables[0].1.cache_id, 0);
     }
 
+    #[test]
+    fn test_getpivotdata_reads_pivot_table() {
+        use crate::pivot::{AggregateFunction, PivotDataField, PivotField};
+        let mut wb = make_pivot_workbook();
+        let mut config = basic_pivot_config();
+        config.rows = vec![PivotField {
+            name: "Region".to_string(),
+        }];
+        config.data.push(PivotDataField {
+            name: "Sales".to_string(),
+            function: AggregateFunction::Average,
+            display_name: Some("Avg Sales".to_string()),
+        });
+        wb.add_pivot_table(&config).unwrap();
+
+        let eval = |formula: &str| wb.evaluate_formula("Sheet1", formula).unwrap();
+        assert_eq!(eval("GETPIVOTDATA(\"Sales\",E1)"), CellValue::Number(450.0));
+        assert_eq!(
+            eval("GETPIVOTDATA(\"Sum of Sales\",E1,\"Region\",\"North\")"),
+            CellValue::Number(250.0)
+        );
+        assert_eq!(
+            eval("GETPIVOTDATA(\"Avg Sales\",E1,\"Region\",\"North\")"),
+            CellValue::Number(125.0)
+        );
+        assert_eq!(
+            eval("GETPIVOTDATA(\"Sales\",E1,\"Name\",\"Bob\")"),
+            CellValue::Error("#REF!".to_string())
+        );
+        assert_eq!(
+            eval("GETPIVOTDATA(\"Sales\",A1)"),
+            CellValue::Error("#REF!".to_string())
+        );
+    }
+
     #[test]
     fn test_add_pivot_table_with_columns() {
         use crate::pivot::{AggregateFunction, PivotDataField, PivotField};
@@ -1732,6 +2000,511 @@ mod tests {
         );
     }
 
+    /// Turn B1:B3 into a shared formula group with `master` on B1.
+    fn make_shared_formula_group(wb: &mut Workbook, master: &str) {
+        wb.set_cell_formula("Sheet1", "B1", master).unwrap();
+        wb.set_cell_formula("Sheet1", "B2", "0").unwrap();
+        wb.set_cell_formula("Sheet1", "B3", "0").unwrap();
+        let ws = wb.worksheet_mut("Sheet1").unwrap();
+        for (row_idx, row) in ws.sheet_data.rows.iter_mut().enumerate() {
+            let cell = row.cells.iter_mut().find(|c| c.col == 2).unwrap();
+            let f = cell.f.as_mut().unwrap();
+            f.t = Some("shared".to_string());
+            f.si = Some(0);
+            if row_idx == 0 {
+                f.reference = Some("B1:B3".to_string());
+            } else {
+                f.value = None;
+            }
+        }
+    }
+
+    #[test]
+    fn test_calculate_all_expands_shared_formulas() {
+        let mut wb = Workbook::new();
+        for (i, v) in [1.0, 2.0, 3.0].iter().enumerate() {
+            wb.set_cell_value("Sheet1", &format!("A{}", i + 1), *v)
+                .unwrap();
+        }
+        make_shared_formula_group(&mut wb, "A1*10");
+
+        wb.calculate_all().unwrap();
+
+        match wb.get_cell_value("Sheet1", "B3").unwrap() {
+            CellValue::Formula { expr, result } => {
+                assert_eq!(expr, "A3*10");
+                assert_eq!(*result.unwrap(), CellValue::Number(30.0));
+            }
+            other => panic!("B3 should be a formula cell, got {other:?}"),
+        }
+        assert_eq!(
+            wb.evaluate_formula("Sheet1", "B2+1").unwrap(),
+            CellValue::Number(21.0)
+        );
+    }
+
+    #[test]
+    fn test_overwrite_shared_master_keeps_children() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "A2", 5.0).unwrap();
+        make_shared_formula_group(&mut wb, "A1+1");
+
+        wb.set_cell_value("Sheet1", "B1", 0.0).unwrap();
+        match wb.get_cell_value("Sheet1", "B2").unwrap() {
+            CellValue::Formula { expr, .. } => assert_eq!(expr, "A2+1"),
+            other => panic!("B2 should be a formula cell, got {other:?}"),
+        }
+        wb.calculate_all().unwrap();
+        match wb.get_cell_value("Sheet1", "B2").unwrap() {
+            CellValue::Formula { result, .. } => {
+                assert_eq!(*result.unwrap(), CellValue::Number(6.0))
+            }
+            other => panic!("B2 should be a formula cell, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_shared_masters_cache_follows_edits() {
+        let mut wb = Workbook::new();
+        make_shared_formula_group(&mut wb, "A1+1");
+        match wb.get_cell_value("Sheet1", "B3").unwrap() {
+            CellValue::Formula { expr, .. } => assert_eq!(expr, "A3+1"),
+            other => panic!("B3 should be a formula cell, got {other:?}"),
+        }
+
+        let ws = wb.worksheet_mut("Sheet1").unwrap();
+        ws.sheet_data.rows[0].cells[0].f.as_mut().unwrap().value = Some("A1*2".to_string());
+        match wb.get_cell_value("Sheet1", "B3").unwrap() {
+            CellValue::Formula { expr, .. } => assert_eq!(expr, "A3*2"),
+            other => panic!("B3 should be a formula cell, got {other:?}"),
+        }
+        let rows = wb.get_rows("Sheet1").unwrap();
+        match &rows[1].1[0].1 {
+            CellValue::Formula { expr, .. } => assert_eq!(expr, "A2*2"),
+            other => panic!("B2 should be a formula cell, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_share_and_unshare_formulas_roundtrip() {
+        let mut wb = Workbook::new();
+        for row in 1..=4 {
+            wb.set_cell_formula("Sheet1", &format!("B{row}"), &format!("A{row}*2"))
+                .unwrap();
+        }
+        assert_eq!(wb.share_formulas("Sheet1").unwrap(), 1);
+
+        let buf = wb.save_to_buffer().unwrap();
+        let mut wb2 = Workbook::open_from_buffer(&buf).unwrap();
+        let ws = wb2.worksheet_ref("Sheet1").unwrap();
+        let b4 = &ws.sheet_data.rows[3].cells[0];
+        assert_eq!(crate::shared_formula::shared_child_si(b4), Some(0));
+        match wb2.get_cell_value("Sheet1", "B4").unwrap() {
+            CellValue::Formula { expr, .. } => assert_eq!(expr, "A4*2"),
+            other => panic!("B4 should be a formula cell, got {other:?}"),
+        }
+
+        assert_eq!(wb2.unshare_formulas("Sheet1").unwrap(), 4);
+        let ws = wb2.worksheet_ref("Sheet1").unwrap();
+        let f = ws.sheet_data.rows[3].cells[0].f.as_ref().unwrap();
+        assert!(f.t.is_none());
+        assert_eq!(f.value.as_deref(), Some("A4*2"));
+    }
+
+    fn formula_cell_ref(wb: &Workbook, cell: &str) -> (Option<String>, Option<String>) {
+        let (col, row) = cell_name_to_coordinates(cell).unwrap();
+        let ws = wb.worksheet_ref("Sheet1").unwrap();
+        let row = ws.sheet_data.rows.iter().find(|r| r.r == row).unwrap();
+        let f = row.cells.iter().find(|c| c.col == col).unwrap().f.as_ref();
+        let f = f.unwrap();
+        (f.t.clone(), f.reference.clone())
+    }
+
+    #[test]
+    fn test_calculate_all_spills_dynamic_array() {
+        let mut wb = Workbook::new();
+        for (i, v) in [3.0, 1.0, 2.0].iter().enumerate() {
+            wb.set_cell_value("Sheet1", &format!("A{}", i + 1), *v)
+                .unwrap();
+        }
+        wb.set_cell_formula("Sheet1", "C1", "SORT(A1:A3)").unwrap();
+        wb.set_cell_formula("Sheet1", "D1", "SUM(C1:C3)").unwrap();
+
+        wb.calculate_all().unwrap();
+
+        assert_eq!(
+            formula_cell_ref(&wb, "C1"),
+            (Some("array".to_string()), Some("C1:C3".to_string()))
+        );
+        match wb.get_cell_value("Sheet1", "C1").unwrap() {
+            CellValue::Formula { result, .. } => {
+                assert_eq!(*result.unwrap(), CellValue::Number(1.0))
+            }
+            other => panic!("C1 should be a formula cell, got {other:?}"),
+        }
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "C3").unwrap(),
+            CellValue::Number(3.0)
+        );
+
+        // Shrinking the result clears the cells it no longer covers, and
+        // formulas reading the spill range see the new values.
+        let ws = wb.worksheet_mut("Sheet1").unwrap();
+        let c1 = ws.sheet_data.rows[0]
+            .cells
+            .iter_mut()
+            .find(|c| c.col == 3)
+            .unwrap();
+        c1.f.as_mut().unwrap().value = Some("SEQUENCE(2,1,10)".to_string());
+        wb.calculate_all().unwrap();
+        assert_eq!(formula_cell_ref(&wb, "C1").1.as_deref(), Some("C1:C2"));
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "C2").unwrap(),
+            CellValue::Number(11.0)
+        );
+        assert_eq!(wb.get_cell_value("Sheet1", "C3").unwrap(), CellValue::Empty);
+        match wb.get_cell_value("Sheet1", "D1").unwrap() {
+            CellValue::Formula { result, .. } => {
+                assert_eq!(*result.unwrap(), CellValue::Number(21.0))
+            }
+            other => panic!("D1 should be a formula cell, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_calculate_all_spill_collision() {
+        let mut wb = Workbook::new();
+        wb.set_cell_formula("Sheet1", "A1", "SEQUENCE(3)").unwrap();
+        wb.set_cell_value("Sheet1", "A3", "blocker").unwrap();
+
+        wb.calculate_all().unwrap();
+
+        match wb.get_cell_value("Sheet1", "A1").unwrap() {
+            CellValue::Formula { result, .. } => {
+                assert_eq!(*result.unwrap(), CellValue::Error("#SPILL!".to_string()))
+            }
+            other => panic!("A1 should be a formula cell, got {other:?}"),
+        }
+        assert_eq!(formula_cell_ref(&wb, "A1"), (None, None));
+        assert_eq!(wb.get_cell_value("Sheet1", "A2").unwrap(), CellValue::Empty);
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "A3").unwrap(),
+            CellValue::String("blocker".to_string())
+        );
+
+        // Once the blocker is gone the formula spills normally.
+        wb.set_cell_value("Sheet1", "A3", CellValue::Empty).unwrap();
+        wb.calculate_all().unwrap();
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "A3").unwrap(),
+            CellValue::Number(3.0)
+        );
+    }
+
+    #[test]
+    fn test_calculate_all_spill_reference() {
+        let result = |wb: &Workbook, cell: &str| match wb.get_cell_value("Sheet1", cell).unwrap() {
+            CellValue::Formula { result, .. } => *result.unwrap(),
+            other => panic!("{cell} should be a formula cell, got {other:?}"),
+        };
+        let mut wb = Workbook::new();
+        for (i, v) in [3.0, 1.0, 2.0].iter().enumerate() {
+            wb.set_cell_value("Sheet1", &format!("A{}", i + 1), *v)
+                .unwrap();
+        }
+        wb.set_cell_formula("Sheet1", "C1", "SORT(A1:A3)").unwrap();
+        wb.set_cell_formula("Sheet1", "D1", "SUM(C1#)").unwrap();
+        wb.set_cell_formula("Sheet1", "E1", "C1#*10").unwrap();
+        wb.set_cell_formula("Sheet1", "F1", "ROWS(_xlfn.ANCHORARRAY(C1))")
+            .unwrap();
+        wb.set_cell_formula("Sheet1", "G1", "SUM(A1#)").unwrap();
+
+        wb.calculate_all().unwrap();
+
+        assert_eq!(result(&wb, "D1"), CellValue::Number(6.0));
+        assert_eq!(formula_cell_ref(&wb, "E1").1.as_deref(), Some("E1:E3"));
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "E3").unwrap(),
+            CellValue::Number(30.0)
+        );
+        assert_eq!(result(&wb, "F1"), CellValue::Number(3.0));
+        assert_eq!(result(&wb, "G1"), CellValue::Error("#REF!".to_string()));
+        assert_eq!(
+            wb.evaluate_formula("Sheet1", "SUM(C1#)").unwrap(),
+            CellValue::Number(6.0)
+        );
+
+        // Resizing the anchor's spill resizes every reference to it.
+        wb.set_cell_formula("Sheet1", "C1", "SEQUENCE(2,1,10)")
+            .unwrap();
+        wb.calculate_all().unwrap();
+        assert_eq!(result(&wb, "D1"), CellValue::Number(21.0));
+        assert_eq!(result(&wb, "F1"), CellValue::Number(2.0));
+        assert_eq!(formula_cell_ref(&wb, "E1").1.as_deref(), Some("E1:E2"));
+        assert_eq!(wb.get_cell_value("Sheet1", "E3").unwrap(), CellValue::Empty);
+    }
+
+    #[test]
+    fn test_set_formula_on_spill_anchor_clears_old_spill() {
+        let result = |wb: &Workbook, cell: &str| match wb.get_cell_value("Sheet1", cell).unwrap() {
+            CellValue::Formula { result, .. } => *result.unwrap(),
+            other => panic!("{cell} should be a formula cell, got {other:?}"),
+        };
+        let mut wb = Workbook::new();
+        wb.set_cell_formula("Sheet1", "A1", "SEQUENCE(3)").unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "SUM(A1#)").unwrap();
+        wb.calculate_all().unwrap();
+
+        wb.set_cell_formula("Sheet1", "A1", "SEQUENCE(3)").unwrap();
+        assert_eq!(formula_cell_ref(&wb, "A1"), (None, None));
+        assert_eq!(wb.get_cell_value("Sheet1", "A3").unwrap(), CellValue::Empty);
+        wb.calculate_all().unwrap();
+        assert_eq!(result(&wb, "A1"), CellValue::Number(1.0));
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "A3").unwrap(),
+            CellValue::Number(3.0)
+        );
+        assert_eq!(result(&wb, "B1"), CellValue::Number(6.0));
+
+        wb.set_cell_formula("Sheet1", "A1", "SEQUENCE(2)").unwrap();
+        wb.recalculate_dirty().unwrap();
+        assert_eq!(wb.get_cell_value("Sheet1", "A3").unwrap(), CellValue::Empty);
+        assert_eq!(result(&wb, "B1"), CellValue::Number(3.0));
+
+        // A value on the anchor leaves nothing for the reference to read.
+        wb.set_cell_value("Sheet1", "A1", 5.0).unwrap();
+        wb.recalculate_dirty().unwrap();
+        assert_eq!(wb.get_cell_value("Sheet1", "A2").unwrap(), CellValue::Empty);
+        assert_eq!(result(&wb, "B1"), CellValue::Error("#REF!".to_string()));
+    }
+
+    #[test]
+    fn test_calculate_all_legacy_array_formula() {
+        let mut wb = Workbook::new();
+        for (i, (a, b)) in [(1.0, 4.0), (2.0, 5.0), (3.0, 6.0)].iter().enumerate() {
+            let row = i + 1;
+            wb.set_cell_value("Sheet1", &format!("A{row}"), *a).unwrap();
+            wb.set_cell_value("Sheet1", &format!("B{row}"), *b).unwrap();
+        }
+        wb.set_cell_array_formula("Sheet1", "C1", "SUM(A1:A3*B1:B3)")
+            .unwrap();
+        wb.set_cell_array_formula("Sheet1", "D1:D4", "A1:A3*B1:B3")
+            .unwrap();
+        wb.set_cell_formula("Sheet1", "E1", "D2+1").unwrap();
+
+        wb.calculate_all().unwrap();
+
+        match wb.get_cell_value("Sheet1", "C1").unwrap() {
+            CellValue::Formula { result, .. } => {
+                assert_eq!(*result.unwrap(), CellValue::Number(32.0))
+            }
+            other => panic!("C1 should be a formula cell, got {other:?}"),
+        }
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "D3").unwrap(),
+            CellValue::Number(18.0)
+        );
+        // Cells beyond the result are padded with #N/A.
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "D4").unwrap(),
+            CellValue::Error("#N/A".to_string())
+        );
+        // The range stays fixed and dependents see the element values.
+        assert_eq!(formula_cell_ref(&wb, "D1").1.as_deref(), Some("D1:D4"));
+        match wb.get_cell_value("Sheet1", "E1").unwrap() {
+            CellValue::Formula { result, .. } => {
+                assert_eq!(*result.unwrap(), CellValue::Number(11.0))
+            }
+            other => panic!("E1 should be a formula cell, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_calculate_all_whole_column_and_3d_refs() {
+        let mut wb = Workbook::new();
+        for sheet in ["Jan", "Feb", "Mar"] {
+            wb.new_sheet(sheet).unwrap();
+        }
+        wb.set_cell_value("Jan", "B5", 1.0).unwrap();
+        wb.set_cell_value("Mar", "B5", 3.0).unwrap();
+        wb.set_cell_value("Feb", "A1", 4.0).unwrap();
+        // Feb!B5 is a formula, so the totals must be evaluated after it.
+        wb.set_cell_formula("Feb", "B5", "A1/2").unwrap();
+        wb.set_cell_formula("Sheet1", "A1", "SUM(Jan:Mar!B5)")
+            .unwrap();
+        wb.set_cell_formula("Sheet1", "A2", "SUM(Feb!B:B)+SUM('Feb'!5:5)")
+            .unwrap();
+
+        wb.calculate_all().unwrap();
+
+        for (cell, expected) in [("A1", 6.0), ("A2", 4.0)] {
+            match wb.get_cell_value("Sheet1", cell).unwrap() {
+                CellValue::Formula { result, .. } => {
+                    assert_eq!(*result.unwrap(), CellValue::Number(expected), "{cell}");
+                }
+                other => panic!("{cell} should be a formula cell, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_calculate_all_structured_refs() {
+        use crate::table::{TableColumn, TableConfig};
+
+        let mut wb = Workbook::new();
+        let columns = ["Qty", "Price", "Amount"]
+            .iter()
+            .map(|name| TableColumn {
+                name: name.to_string(),
+                totals_row_function: None,
+                totals_row_label: None,
+            })
+            .collect();
+        let config = TableConfig {
+            name: "Sales".to_string(),
+            display_name: "Sales".to_string(),
+            range: "A1:C4".to_string(),
+            columns,
+            ..TableConfig::default()
+        };
+        wb.add_table("Sheet1", &config).unwrap();
+        for (i, (qty, price)) in [(2.0, 5.0), (3.0, 4.0), (1.0, 10.0)].iter().enumerate() {
+            let row = i + 2;
+            wb.set_cell_value("Sheet1", &format!("A{row}"), *qty)
+                .unwrap();
+            wb.set_cell_value("Sheet1", &format!("B{row}"), *price)
+                .unwrap();
+            wb.set_cell_formula("Sheet1", &format!("C{row}"), "[@Qty]*[@Price]")
+                .unwrap();
+        }
+        wb.set_cell_formula("Sheet1", "E1", "SUM(Sales[Amount])")
+            .unwrap();
+
+        wb.calculate_all().unwrap();
+
+        // The total depends on every row formula in the Amount column.
+        for (cell, expected) in [("C2", 10.0), ("C3", 12.0), ("C4", 10.0), ("E1", 32.0)] {
+            match wb.get_cell_value("Sheet1", cell).unwrap() {
+                CellValue::Formula { result, .. } => {
+                    assert_eq!(*result.unwrap(), CellValue::Number(expected), "{cell}");
+                }
+                other => panic!("{cell} should be a formula cell, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_calculate_all_defined_names() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Rates").unwrap();
+        wb.set_cell_value("Sheet1", "A1", 100.0).unwrap();
+        wb.set_cell_formula("Sheet1", "A2", "A1*2").unwrap();
+        wb.set_cell_value("Rates", "A1", 0.5).unwrap();
+        wb.set_defined_name("Sales_2024", "Sheet1!$A$1:$A$2", None, None)
+            .unwrap();
+        wb.set_defined_name("TaxRate", "0.1", None, None).unwrap();
+        wb.set_defined_name("TaxRate", "Rates!$A$1", Some("Rates"), None)
+            .unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "SUM(Sales_2024)*TaxRate")
+            .unwrap();
+        wb.set_cell_formula("Rates", "B1", "SUM(Sales_2024)*TaxRate")
+            .unwrap();
+
+        wb.calculate_all().unwrap();
+
+        // A2 is evaluated before B1 because the name covers it, and the
+        // sheet-scoped TaxRate wins on the Rates sheet.
+        for (sheet, expected) in [("Sheet1", 30.0), ("Rates", 150.0)] {
+            match wb.get_cell_value(sheet, "B1").unwrap() {
+                CellValue::Formula { result, .. } => {
+                    let n = match *result.unwrap() {
+                        CellValue::Number(n) => n,
+                        other => panic!("expected a number, got {other:?}"),
+                    };
+                    assert!((n - expected).abs() < 1e-9, "{sheet}: {n}");
+                }
+                other => panic!("B1 should be a formula cell, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_calculate_all_named_lambda() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "A1", 200.0).unwrap();
+        wb.set_cell_formula("Sheet1", "C1", "0.25*2").unwrap();
+        wb.set_defined_name("TaxRate", "Sheet1!$C$1", None, None)
+            .unwrap();
+        wb.set_defined_name(
+            "WithTax",
+            "_xlfn.LAMBDA(_xlpm.amount,_xlpm.amount*(1+TaxRate))",
+            None,
+            None,
+        )
+        .unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "WithTax(A1)").unwrap();
+
+        wb.calculate_all().unwrap();
+
+        // C1 is evaluated first because the lambda body reads it.
+        match wb.get_cell_value("Sheet1", "B1").unwrap() {
+            CellValue::Formula { result, .. } => {
+                assert_eq!(*result.unwrap(), CellValue::Number(300.0));
+            }
+            other => panic!("B1 should be a formula cell, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_registered_functions() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "A1", 10.0).unwrap();
+        wb.set_cell_formula("Sheet1", "B1", "A1*FXRATE(\"USD\")")
+            .unwrap();
+        wb.set_cell_formula("Sheet1", "C1", "TODAY()").unwrap();
+        wb.set_cell_formula("Sheet1", "D1", "TICK()").unwrap();
+        wb.register_function("FXRATE", |_args, _ctx| Ok(CellValue::Number(1.5)))
+            .unwrap();
+        wb.register_function("TODAY", |_args, _ctx| Ok(CellValue::Number(45292.0)))
+            .unwrap();
+        let ticks = Arc::new(AtomicU32::new(0));
+        let counter = Arc::clone(&ticks);
+        wb.register_volatile_function("TICK", move |_args, _ctx| {
+            Ok(CellValue::Number(
+                f64::from(counter.fetch_add(1, Ordering::SeqCst)) + 1.0,
+            ))
+        })
+        .unwrap();
+
+        let result = |wb: &Workbook, cell: &str| match wb.get_cell_value("Sheet1", cell).unwrap() {
+            CellValue::Formula { result, .. } => *result.unwrap(),
+            other => panic!("{cell} should be a formula cell, got {other:?}"),
+        };
+        wb.calculate_all().unwrap();
+        assert_eq!(result(&wb, "B1"), CellValue::Number(15.0));
+        assert_eq!(result(&wb, "C1"), CellValue::Number(45292.0));
+        assert_eq!(result(&wb, "D1"), CellValue::Number(1.0));
+        assert_eq!(
+            wb.evaluate_formula("Sheet1", "FXRATE(\"EUR\")*2").unwrap(),
+            CellValue::Number(3.0)
+        );
+
+        // Only the volatile function is recalculated without an edit.
+        wb.recalculate_dirty().unwrap();
+        assert_eq!(result(&wb, "D1"), CellValue::Number(2.0));
+
+        assert!(wb.unregister_function("FXRATE"));
+        assert!(!wb.unregister_function("FXRATE"));
+        assert_eq!(wb.function_registry().names(), vec!["TICK", "TODAY"]);
+        assert!(wb.evaluate_formula("Sheet1", "FXRATE(\"EUR\")").is_err());
+        assert!(wb
+            .register_function("BAD NAME", |_args, _ctx| Ok(CellValue::Empty))
+            .is_err());
+    }
+
     #[test]
     fn test_set_get_doc_props() {
         let mut wb = Workbook::new();
@@ -67,7 +67,7 @@ impl Workbook {
     ///
     /// This is called automatically before any comment mutation or query to
     /// ensure pre-existing comments are preserved.
-    fn hydrate_comments(&mut self, sheet_idx: usize) {
+    pub(crate) fn hydrate_comments(&mut self, sheet_idx: usize) {
         use crate::workbook::aux::AuxCategory;
 
         if !self.deferred_parts.has_category(AuxCategory::Comments)
@@ -411,6 +411,7 @@ impl Workbook {
 
         self.tables.push((table_path, table_xml, sheet_idx));
         self.deferred_parts.mark_dirty(AuxCategory::Tables);
+        self.invalidate_calc_cache();
         Ok(())
     }
 
@@ -418,9 +419,21 @@ impl Workbook {
     ///
     /// Returns metadata for each table associated with the given sheet.
     pub fn get_tables(&self, sheet: &str) -> Result<Vec<crate::table::TableInfo>> {
+        let sheet_idx = self.sheet_index(sheet)?;
+        let infos = self
+            .collect_tables()
+            .iter()
+            .filter(|(_, _, idx)| *idx == sheet_idx)
+            .map(|(_, table_xml, _)| crate::table::table_xml_to_info(table_xml))
+            .collect();
+        Ok(infos)
+    }
+
+    /// Collect all table definitions with their sheet indices, including
+    /// tables that have not been hydrated yet, without mutating the workbook.
+    pub(crate) fn collect_tables(&self) -> Vec<(String, sheetkit_xml::table::TableXml, usize)> {
         use crate::workbook::aux::AuxCategory;
 
-        let sheet_idx = self.sheet_index(sheet)?;
         let mut tables = self.tables.clone();
 
         let resolve_table_sheet_idx = |table_path: &str| -> usize {
@@ -454,12 +467,7 @@ impl Workbook {
             }
         }
 
-        let infos = tables
-            .iter()
-            .filter(|(_, _, idx)| *idx == sheet_idx)
-            .map(|(_, table_xml, _)| crate::table::table_xml_to_info(table_xml))
-            .collect();
-        Ok(infos)
+        tables
     }
 
     /// Delete a table from a sheet by name.
@@ -480,6 +488,7 @@ impl Workbook {
                 self.tables.remove(i);
                 self.deferred_parts.mark_dirty(AuxCategory::Tables);
                 self.mark_sheet_dirty(sheet_idx);
+                self.invalidate_calc_cache();
                 Ok(())
             }
             None => Err(Error::TableNotFound {
@@ -763,6 +772,7 @@ impl Workbook {
         comment: Option<&str>,
     ) -> Result<()> {
         let dn_scope = self.resolve_defined_name_scope(scope)?;
+        self.invalidate_calc_cache();
         crate::defined_names::set_defined_name(
             &mut self.workbook_xml,
             name,
@@ -800,6 +810,7 @@ impl Workbook {
     /// Returns an error if the name does not exist for the given scope.
     pub fn delete_defined_name(&mut self, name: &str, scope: Option<&str>) -> Result<()> {
         let dn_scope = self.resolve_defined_name_scope(scope)?;
+        self.invalidate_calc_cache();
         crate::defined_names::delete_defined_name(&mut self.workbook_xml, name, dn_scope)
     }
 
@@ -0,0 +1,831 @@
+use super::*;
+
+use crate::cell_ref_shift::{detach_references, referenced_names, rename_references};
+use crate::workbook::aux::AuxCategory;
+use crate::workbook::sheet_ops::update_formulas;
+
+impl Workbook {
+    /// Import a sheet from another workbook as a new sheet named `new_name`,
+    /// returning its 0-based index.
+    ///
+    /// Cell styles, number formats, and conditional formatting formats are
+    /// added to this workbook's stylesheet, and shared strings to its string
+    /// table. Comments, sparklines, tables, images, charts, and hyperlinks
+    /// are copied along with the cells. Tables whose name is already used
+    /// in this workbook are renamed, and formulas on the sheet follow the
+    /// new name. References to the source workbook's other sheets, such as
+    /// `Other!A1`, become `#REF!` rather than pointing at a sheet of the
+    /// same name in this workbook. Names scoped to the sheet are copied, as
+    /// are workbook-level names the sheet's formulas use that this
+    /// workbook does not define.
+    pub fn import_sheet(
+        &mut self,
+        source: &Workbook,
+        source_sheet: &str,
+        new_name: &str,
+    ) -> Result<usize> {
+        let src_idx = source.sheet_index(source_sheet)?;
+        if source.streamed_sheets.contains_key(&src_idx) {
+            return Err(Error::InvalidArgument(format!(
+                "sheet '{source_sheet}' holds streamed data and cannot be imported before it is saved"
+            )));
+        }
+        crate::sheet::validate_sheet_name(new_name)?;
+        if self.sheet_name_index.contains_key(new_name) {
+            return Err(Error::SheetAlreadyExists {
+                name: new_name.to_string(),
+            });
+        }
+        let mut ws = source.worksheet_ref_by_index(src_idx)?.clone();
+
+        self.hydrate_tables();
+        self.hydrate_drawings();
+        self.hydrate_threaded_comments();
+
+        // Tables, renamed where their name is taken.
+        let mut tables: Vec<sheetkit_xml::table::TableXml> = source
+            .collect_tables()
+            .into_iter()
+            .filter(|(_, _, idx)| *idx == src_idx)
+            .map(|(_, table, _)| table)
+            .collect();
+        let mut table_renames = Vec::new();
+        for i in 0..tables.len() {
+            let name = tables[i].name.clone();
+            let taken = |candidate: &str| {
+                self.tables
+                    .iter()
+                    .any(|(_, t, _)| t.name.eq_ignore_ascii_case(candidate))
+                    || tables[..i]
+                        .iter()
+                        .any(|t| t.name.eq_ignore_ascii_case(candidate))
+            };
+            if taken(&name) {
+                let unique = (2..)
+                    .map(|n| format!("{name}_{n}"))
+                    .find(|candidate| !taken(candidate))
+                    .unwrap();
+                tables[i].name = unique.clone();
+                tables[i].display_name = unique.clone();
+                table_renames.push((name, unique));
+            }
+        }
+
+        // Styles, shared strings, and conditional formatting formats.
+        let mut styles = HashMap::new();
+        let mut import_style = |stylesheet: &mut StyleSheet, id: u32| -> Result<u32> {
+            if let Some(&mapped) = styles.get(&id) {
+                return Ok(mapped);
+            }
+            let mapped = crate::style::import_style(stylesheet, &source.stylesheet, id)?;
+            styles.insert(id, mapped);
+            Ok(mapped)
+        };
+        let mut strings = HashMap::new();
+        for row in &mut ws.sheet_data.rows {
+            if let Some(s) = row.s {
+                row.s = Some(import_style(&mut self.stylesheet, s)?);
+            }
+            for cell in &mut row.cells {
+                if let Some(s) = cell.s {
+                    cell.s = Some(import_style(&mut self.stylesheet, s)?);
+                }
+                if cell.t != CellTypeTag::SharedString {
+                    continue;
+                }
+                let Some(index) = cell.v.as_deref().and_then(|v| v.parse::<usize>().ok()) else {
+                    continue;
+                };
+                let mapped = *strings.entry(index).or_insert_with(|| {
+                    match source.sst_runtime.get_rich_text(index) {
+                        Some(runs) => self.sst_runtime.add_rich_text(&runs),
+                        None => self
+                            .sst_runtime
+                            .add(source.sst_runtime.get(index).unwrap_or_default()),
+                    }
+                });
+                cell.v = Some(mapped.to_string());
+            }
+        }
+        if let Some(cols) = ws.cols.as_mut() {
+            for col in &mut cols.cols {
+                if let Some(style) = col.style {
+                    col.style = Some(import_style(&mut self.stylesheet, style)?);
+                }
+            }
+        }
+        for cf in &mut ws.conditional_formatting {
+            for rule in &mut cf.cf_rules {
+                if let Some(dxf_id) = rule.dxf_id {
+                    rule.dxf_id =
+                        crate::style::import_dxf(&mut self.stylesheet, &source.stylesheet, dxf_id);
+                }
+            }
+        }
+
+        // References to the sheet itself follow the new name.
+        let mut used_names = Vec::new();
+        update_formulas(&mut ws, |text| {
+            used_names.extend(referenced_names(text));
+            let detached = detach_references(text, source_sheet)?;
+            rename_references(&detached, source_sheet, new_name, &table_renames)
+        })?;
+
+        // Parts linked through relationships are attached again below.
+        ws.drawing = None;
+        ws.legacy_drawing = None;
+        ws.table_parts = None;
+        if let Some(page_setup) = ws.page_setup.as_mut() {
+            page_setup.r_id = None;
+        }
+        if let Some(views) = ws.sheet_views.as_mut() {
+            for view in &mut views.sheet_views {
+                view.tab_selected = None;
+            }
+        }
+
+        let idx = self.new_sheet(new_name)?;
+        *self.worksheets[idx].1.get_mut().unwrap() = ws;
+
+        // External hyperlinks keep their relationship IDs.
+        let hyperlinks: Vec<Relationship> = source
+            .worksheet_rels
+            .get(&src_idx)
+            .map(|rels| {
+                rels.relationships
+                    .iter()
+                    .filter(|r| r.rel_type == rel_types::HYPERLINK)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        if !hyperlinks.is_empty() {
+            let mut rels = default_relationships();
+            rels.relationships = hyperlinks;
+            self.worksheet_rels.insert(idx, rels);
+        }
+
+        let mut sparklines = source
+            .sheet_sparklines
+            .get(src_idx)
+            .cloned()
+            .unwrap_or_default();
+        for sparkline in &mut sparklines {
+            let detached = detach_references(&sparkline.data_range, source_sheet)?;
+            sparkline.data_range = rename_references(&detached, source_sheet, new_name, &[])?;
+        }
+        self.sheet_sparklines[idx] = sparklines;
+
+        self.sheet_comments[idx] = source
+            .sheet_comments
+            .get(src_idx)
+            .cloned()
+            .flatten()
+            .or_else(|| {
+                let path = source.sheet_linked_part(src_idx, rel_types::COMMENTS)?;
+                source.parse_deferred(AuxCategory::Comments, &path)
+            });
+        self.import_threaded_comments(source, src_idx, idx);
+
+        for mut table in tables {
+            table.id = self.tables.iter().map(|(_, t, _)| t.id).max().unwrap_or(0) + 1;
+            let table_num = self
+                .tables
+                .iter()
+                .filter_map(|(path, _, _)| {
+                    path.trim_start_matches("xl/tables/table")
+                        .trim_end_matches(".xml")
+                        .parse::<u32>()
+                        .ok()
+                })
+                .max()
+                .unwrap_or(0)
+                + 1;
+            self.tables
+                .push((format!("xl/tables/table{table_num}.xml"), table, idx));
+            self.deferred_parts.mark_dirty(AuxCategory::Tables);
+        }
+
+        self.import_drawing(source, src_idx, idx, source_sheet, new_name)?;
+        self.import_defined_names(source, src_idx, idx, source_sheet, new_name, &used_names)?;
+        Ok(idx)
+    }
+
+    /// Copy the threaded comments of sheet `src_idx` in `source` to sheet
+    /// `idx`, adding their authors to the person list.
+    fn import_threaded_comments(&mut self, source: &Workbook, src_idx: usize, idx: usize) {
+        let threaded = source
+            .sheet_threaded_comments
+            .get(src_idx)
+            .cloned()
+            .flatten()
+            .or_else(|| {
+                let path = source.sheet_linked_part(
+                    src_idx,
+                    sheetkit_xml::threaded_comment::REL_TYPE_THREADED_COMMENT,
+                )?;
+                source.parse_deferred(AuxCategory::ThreadedComments, &path)
+            });
+        let Some(threaded) = threaded else {
+            return;
+        };
+        let persons = if source.person_list.persons.is_empty() {
+            source
+                .parse_deferred::<sheetkit_xml::threaded_comment::PersonList>(
+                    AuxCategory::PersonList,
+                    "xl/persons/person.xml",
+                )
+                .unwrap_or_default()
+                .persons
+        } else {
+            source.person_list.persons.clone()
+        };
+        for comment in &threaded.comments {
+            if self
+                .person_list
+                .persons
+                .iter()
+                .any(|p| p.id == comment.person_id)
+            {
+                continue;
+            }
+            if let Some(person) = persons.iter().find(|p| p.id == comment.person_id) {
+                self.person_list.persons.push(person.clone());
+            }
+        }
+        self.sheet_threaded_comments[idx] = Some(threaded);
+    }
+
+    /// Copy the drawing of sheet `src_idx` in `source`, with the charts and
+    /// images it shows, to sheet `idx`.
+    fn import_drawing(
+        &mut self,
+        source: &Workbook,
+        src_idx: usize,
+        idx: usize,
+        source_sheet: &str,
+        new_name: &str,
+    ) -> Result<()> {
+        let Some(src_path) = source.sheet_linked_part(src_idx, rel_types::DRAWING) else {
+            return Ok(());
+        };
+        let (drawing, src_rels) = match source.drawings.iter().position(|(p, _)| *p == src_path) {
+            Some(di) => (
+                source.drawings[di].1.clone(),
+                source.drawing_rels.get(&di).cloned(),
+            ),
+            None => {
+                let Some(drawing) = source.parse_deferred::<WsDr>(AuxCategory::Drawings, &src_path)
+                else {
+                    return Ok(());
+                };
+                let rels = source
+                    .parse_deferred(AuxCategory::DrawingRels, &relationship_part_path(&src_path));
+                (drawing, rels)
+            }
+        };
+
+        let drawing_idx = self.ensure_drawing_for_sheet(idx);
+        let drawing_path = self.drawings[drawing_idx].0.clone();
+        self.drawings[drawing_idx].1 = drawing;
+
+        // Anchors refer to charts and images by relationship ID, so the
+        // copied relationships keep their IDs.
+        let mut rels = default_relationships();
+        for rel in src_rels.map(|r| r.relationships).unwrap_or_default() {
+            if rel.target_mode.is_some() {
+                rels.relationships.push(rel);
+                continue;
+            }
+            let part = resolve_relationship_target(&src_path, &rel.target);
+            let new_part = if rel.rel_type == rel_types::CHART {
+                self.import_chart(source, &part, source_sheet, new_name)
+            } else if rel.rel_type == rel_types::IMAGE {
+                self.import_image(source, &part)
+            } else {
+                None
+            };
+            if let Some(new_part) = new_part {
+                rels.relationships.push(Relationship {
+                    target: relative_relationship_target(&drawing_path, &new_part),
+                    ..rel
+                });
+            }
+        }
+        self.drawing_rels.insert(drawing_idx, rels);
+        Ok(())
+    }
+
+    /// Copy the chart part at `path` in `source`, returning its new path.
+    fn import_chart(
+        &mut self,
+        source: &Workbook,
+        path: &str,
+        source_sheet: &str,
+        new_name: &str,
+    ) -> Option<String> {
+        let typed = source
+            .charts
+            .iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, chart)| chart.clone())
+            .or_else(|| source.parse_deferred::<ChartSpace>(AuxCategory::Charts, path));
+        let raw = match typed {
+            Some(_) => None,
+            None => Some(
+                source
+                    .raw_charts
+                    .iter()
+                    .find(|(p, _)| p == path)
+                    .map(|(_, bytes)| bytes.clone())
+                    .or_else(|| {
+                        source
+                            .deferred_parts
+                            .get_path(AuxCategory::Charts, path)
+                            .map(<[u8]>::to_vec)
+                    })?,
+            ),
+        };
+
+        let taken = |p: &str| {
+            self.charts.iter().any(|(c, _)| c == p) || self.raw_charts.iter().any(|(c, _)| c == p)
+        };
+        let new_path = (self.charts.len() + self.raw_charts.len() + 1..)
+            .map(|n| format!("xl/charts/chart{n}.xml"))
+            .find(|p| !taken(p))
+            .unwrap();
+        match (typed, raw) {
+            (Some(mut chart), _) => {
+                for f in crate::chart::series_formulas_mut(&mut chart) {
+                    let renamed = detach_references(f, source_sheet)
+                        .and_then(|f| rename_references(&f, source_sheet, new_name, &[]));
+                    if let Ok(renamed) = renamed {
+                        *f = renamed;
+                    }
+                }
+                self.charts.push((new_path.clone(), chart));
+            }
+            (None, Some(bytes)) => self.raw_charts.push((new_path.clone(), bytes)),
+            (None, None) => return None,
+        }
+        self.content_types.overrides.push(ContentTypeOverride {
+            part_name: format!("/{new_path}"),
+            content_type: mime_types::CHART.to_string(),
+        });
+        Some(new_path)
+    }
+
+    /// Copy the image part at `path` in `source`, returning its new path.
+    fn import_image(&mut self, source: &Workbook, path: &str) -> Option<String> {
+        let data = source
+            .images
+            .iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, bytes)| bytes.clone())
+            .or_else(|| {
+                source
+                    .deferred_parts
+                    .get_path(AuxCategory::Images, path)
+                    .map(<[u8]>::to_vec)
+            })?;
+        let ext = path
+            .rsplit_once('.')
+            .map_or("png", |(_, ext)| ext)
+            .to_string();
+
+        let new_path = (self.images.len() + 1..)
+            .find(|n| {
+                let prefix = format!("xl/media/image{n}.");
+                !self.images.iter().any(|(p, _)| p.starts_with(&prefix))
+            })
+            .map(|n| format!("xl/media/image{n}.{ext}"))
+            .unwrap();
+        self.images.push((new_path.clone(), data));
+
+        if !self
+            .content_types
+            .defaults
+            .iter()
+            .any(|d| d.extension.eq_ignore_ascii_case(&ext))
+        {
+            if let Some(default) = source
+                .content_types
+                .defaults
+                .iter()
+                .find(|d| d.extension.eq_ignore_ascii_case(&ext))
+            {
+                self.content_types.defaults.push(default.clone());
+            }
+        }
+        Some(new_path)
+    }
+
+    /// Copy the names scoped to sheet `src_idx` in `source` to sheet `idx`,
+    /// and the workbook-level names in `used` that this workbook lacks.
+    fn import_defined_names(
+        &mut self,
+        source: &Workbook,
+        src_idx: usize,
+        idx: usize,
+        source_sheet: &str,
+        new_name: &str,
+        used: &[String],
+    ) -> Result<()> {
+        let Some(source_names) = source.workbook_xml.defined_names.as_ref() else {
+            return Ok(());
+        };
+        let mut imported = Vec::new();
+        for dn in &source_names.defined_names {
+            let scope = match dn.local_sheet_id {
+                Some(id) if id as usize == src_idx => Some(idx as u32),
+                Some(_) => continue,
+                None => {
+                    let wanted = used.iter().any(|n| n.eq_ignore_ascii_case(&dn.name));
+                    let defined = self
+                        .workbook_xml
+                        .defined_names
+                        .as_ref()
+                        .is_some_and(|names| {
+                            names.defined_names.iter().any(|existing| {
+                                existing.local_sheet_id.is_none()
+                                    && existing.name.eq_ignore_ascii_case(&dn.name)
+                            })
+                        });
+                    if !wanted || defined {
+                        continue;
+                    }
+                    None
+                }
+            };
+            imported.push(sheetkit_xml::workbook::DefinedName {
+                local_sheet_id: scope,
+                value: rename_references(
+                    &detach_references(&dn.value, source_sheet)?,
+                    source_sheet,
+                    new_name,
+                    &[],
+                )?,
+                ..dn.clone()
+            });
+        }
+        if !imported.is_empty() {
+            self.workbook_xml
+                .defined_names
+                .get_or_insert_with(|| sheetkit_xml::workbook::DefinedNames {
+                    defined_names: Vec::new(),
+                })
+                .defined_names
+                .extend(imported);
+            self.invalidate_calc_cache();
+        }
+        Ok(())
+    }
+
+    /// The path of the part that the sheet at `sheet_idx` links to with a
+    /// relationship of type `rel_type`.
+    fn sheet_linked_part(&self, sheet_idx: usize, rel_type: &str) -> Option<String> {
+        let rel = self
+            .worksheet_rels
+            .get(&sheet_idx)?
+            .relationships
+            .iter()
+            .find(|r| r.rel_type == rel_type && r.target_mode.is_none())?;
+        Some(resolve_relationship_target(
+            &self.sheet_part_path(sheet_idx),
+            &rel.target,
+        ))
+    }
+
+    /// Parse a part that was deferred at open and has not been loaded yet.
+    fn parse_deferred<T: serde::de::DeserializeOwned>(
+        &self,
+        category: AuxCategory,
+        path: &str,
+    ) -> Option<T> {
+        let bytes = self.deferred_parts.get_path(category, path)?;
+        quick_xml::de::from_str(&String::from_utf8_lossy(bytes)).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chart::{ChartSeries, ChartType};
+    use crate::conditional::{ConditionalFormatType, ConditionalStyle};
+    use crate::defined_names::DefinedNameScope;
+    use crate::image::ImageFormat;
+    use crate::style::{FontStyle, StyleBuilder};
+    use crate::table::{TableColumn, TableConfig};
+
+    fn formula_at(wb: &Workbook, sheet: &str, cell: &str) -> String {
+        match wb.get_cell_value(sheet, cell).unwrap() {
+            CellValue::Formula { expr, .. } => expr,
+            other => panic!("expected formula, got {other:?}"),
+        }
+    }
+
+    fn template() -> Workbook {
+        let mut wb = Workbook::new();
+        wb.set_sheet_name("Sheet1", "Template").unwrap();
+        wb.set_cell_value("Template", "A1", "Amount").unwrap();
+        wb.set_cell_value("Template", "A2", 4.0).unwrap();
+        wb.set_cell_value("Template", "A3", 6.0).unwrap();
+        wb
+    }
+
+    fn image() -> ImageConfig {
+        ImageConfig {
+            data: vec![0x89, 0x50, 0x4E, 0x47],
+            format: ImageFormat::Png,
+            from_cell: "D2".to_string(),
+            width_px: 40,
+            height_px: 30,
+        }
+    }
+
+    #[test]
+    fn test_import_sheet_remaps_styles_and_strings() {
+        let mut src = template();
+        let style = src
+            .add_style(
+                &StyleBuilder::new()
+                    .italic(true)
+                    .num_format_custom("0.000")
+                    .build(),
+            )
+            .unwrap();
+        src.set_cell_style("Template", "A2", style).unwrap();
+        src.set_cell_formula("Template", "B2", "Template!A2*2+A3")
+            .unwrap();
+
+        let mut dst = Workbook::new();
+        dst.set_cell_value("Sheet1", "A1", "other").unwrap();
+        let bold = dst
+            .add_style(&StyleBuilder::new().bold(true).build())
+            .unwrap();
+        dst.set_cell_style("Sheet1", "A1", bold).unwrap();
+
+        let idx = dst.import_sheet(&src, "Template", "Report").unwrap();
+        assert_eq!(idx, 1);
+        assert_eq!(dst.sheet_names(), vec!["Sheet1", "Report"]);
+        assert_eq!(
+            dst.get_cell_value("Report", "A1").unwrap(),
+            CellValue::String("Amount".to_string())
+        );
+        assert_eq!(
+            dst.get_cell_value("Sheet1", "A1").unwrap(),
+            CellValue::String("other".to_string())
+        );
+        let imported = dst.get_cell_style("Report", "A2").unwrap().unwrap();
+        let imported = crate::style::get_style(&dst.stylesheet, imported).unwrap();
+        assert!(imported.font.unwrap().italic);
+        assert!(matches!(
+            imported.num_fmt,
+            Some(crate::style::NumFmtStyle::Custom(ref code)) if code == "0.000"
+        ));
+        assert_eq!(formula_at(&dst, "Report", "B2"), "Report!A2*2+A3");
+
+        // The source workbook is unchanged.
+        assert_eq!(formula_at(&src, "Template", "B2"), "Template!A2*2+A3");
+
+        assert!(matches!(
+            dst.import_sheet(&src, "Template", "Report"),
+            Err(Error::SheetAlreadyExists { .. })
+        ));
+        assert!(matches!(
+            dst.import_sheet(&src, "Missing", "Other"),
+            Err(Error::SheetNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_import_sheet_conditional_formats() {
+        let mut src = template();
+        let rule = |formula: &str, font: FontStyle| ConditionalFormatRule {
+            rule_type: ConditionalFormatType::Expression {
+                formula: formula.to_string(),
+            },
+            format: Some(ConditionalStyle {
+                font: Some(font),
+                ..Default::default()
+            }),
+            priority: Some(1),
+            stop_if_true: false,
+        };
+        src.set_conditional_format(
+            "Template",
+            "A2:A3",
+            &[rule(
+                "Template!$A2>5",
+                FontStyle {
+                    bold: true,
+                    ..Default::default()
+                },
+            )],
+        )
+        .unwrap();
+
+        let mut dst = Workbook::new();
+        dst.set_conditional_format(
+            "Sheet1",
+            "A1",
+            &[rule(
+                "A1>0",
+                FontStyle {
+                    italic: true,
+                    ..Default::default()
+                },
+            )],
+        )
+        .unwrap();
+        dst.import_sheet(&src, "Template", "Report").unwrap();
+
+        let formats = dst.get_conditional_formats("Report").unwrap();
+        assert_eq!(formats.len(), 1);
+        let rule = &formats[0].1[0];
+        match &rule.rule_type {
+            ConditionalFormatType::Expression { formula } => {
+                assert_eq!(formula, "Report!$A2>5")
+            }
+            other => panic!("unexpected rule {other:?}"),
+        }
+        let font = rule.format.as_ref().unwrap().font.as_ref().unwrap();
+        assert!(font.bold);
+        assert!(!font.italic);
+    }
+
+    #[test]
+    fn test_import_sheet_renames_conflicting_table() {
+        let table = |name: &str, range: &str| TableConfig {
+            name: name.to_string(),
+            display_name: name.to_string(),
+            range: range.to_string(),
+            columns: vec![TableColumn {
+                name: "Amount".to_string(),
+                totals_row_function: None,
+                totals_row_label: None,
+            }],
+            ..Default::default()
+        };
+        let mut src = template();
+        src.add_table("Template", &table("Sales", "A1:A3")).unwrap();
+        src.set_cell_formula("Template", "C1", "SUM(Sales[Amount])")
+            .unwrap();
+
+        let mut dst = Workbook::new();
+        dst.add_table("Sheet1", &table("Sales", "A1:A2")).unwrap();
+        dst.import_sheet(&src, "Template", "Report").unwrap();
+
+        let tables = dst.get_tables("Report").unwrap();
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].name, "Sales_2");
+        assert_eq!(tables[0].range, "A1:A3");
+        assert_eq!(formula_at(&dst, "Report", "C1"), "SUM(Sales_2[Amount])");
+        assert_eq!(dst.get_tables("Sheet1").unwrap()[0].name, "Sales");
+
+        dst.calculate_all().unwrap();
+        match dst.get_cell_value("Report", "C1").unwrap() {
+            CellValue::Formula { result, .. } => {
+                assert_eq!(result.as_deref(), Some(&CellValue::Number(10.0)))
+            }
+            other => panic!("expected formula, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_import_sheet_drawings_and_comments() {
+        let mut src = template();
+        src.add_image("Template", &image()).unwrap();
+        let config = ChartConfig {
+            chart_type: ChartType::Col,
+            title: None,
+            series: vec![ChartSeries {
+                name: "Amount".to_string(),
+                categories: "Template!$A$2:$A$3".to_string(),
+                values: "Template!$A$2:$A$3".to_string(),
+                x_values: None,
+                bubble_sizes: None,
+            }],
+            show_legend: false,
+            view_3d: None,
+        };
+        src.add_chart("Template", "F1", "K10", &config).unwrap();
+        src.add_comment(
+            "Template",
+            &CommentConfig {
+                cell: "A1".to_string(),
+                author: "Ann".to_string(),
+                text: "Totals".to_string(),
+            },
+        )
+        .unwrap();
+
+        let mut dst = Workbook::new();
+        dst.add_image("Sheet1", &image()).unwrap();
+        dst.import_sheet(&src, "Template", "Report").unwrap();
+
+        assert_eq!(dst.get_picture_cells("Report").unwrap(), vec!["D2"]);
+        assert_eq!(dst.get_picture_cells("Sheet1").unwrap(), vec!["D2"]);
+        let pictures = dst.get_pictures("Report", "D2").unwrap();
+        assert_eq!(pictures[0].data, image().data);
+        assert_eq!(dst.images.len(), 2);
+        assert_ne!(dst.images[0].0, dst.images[1].0);
+        let comments = dst.get_comments("Report").unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].text, "Totals");
+        assert_eq!(dst.charts.len(), 1);
+        let formulas = crate::chart::series_formulas_mut(&mut dst.charts[0].1);
+        assert!(formulas.iter().all(|f| f.starts_with("Report!")));
+
+        let buf = dst.save_to_buffer().unwrap();
+        let archive = zip::ZipArchive::new(std::io::Cursor::new(buf)).unwrap();
+        let names: Vec<&str> = archive.file_names().collect();
+        assert!(names.contains(&"xl/media/image1.png"));
+        assert!(names.contains(&"xl/media/image2.png"));
+        assert!(names.contains(&"xl/charts/chart1.xml"));
+        assert!(names.contains(&"xl/comments2.xml"));
+    }
+
+    #[test]
+    fn test_import_sheet_from_lazily_opened_workbook() {
+        let mut src = template();
+        src.add_image("Template", &image()).unwrap();
+        src.add_comment(
+            "Template",
+            &CommentConfig {
+                cell: "A2".to_string(),
+                author: "Ann".to_string(),
+                text: "Checked".to_string(),
+            },
+        )
+        .unwrap();
+        let buf = src.save_to_buffer().unwrap();
+        let src = Workbook::open_from_buffer(&buf).unwrap();
+
+        let mut dst = Workbook::new();
+        dst.import_sheet(&src, "Template", "Report").unwrap();
+        assert_eq!(
+            dst.get_cell_value("Report", "A1").unwrap(),
+            CellValue::String("Amount".to_string())
+        );
+        assert_eq!(dst.images.len(), 1);
+        assert_eq!(dst.images[0].1, image().data);
+        assert!(dst.worksheet_drawings.contains_key(&1));
+        assert_eq!(dst.get_comments("Report").unwrap()[0].text, "Checked");
+    }
+
+    #[test]
+    fn test_import_sheet_defined_names() {
+        let mut src = template();
+        src.new_sheet("Other").unwrap();
+        src.set_defined_name("Rate", "Template!$A$2", None, None)
+            .unwrap();
+        src.set_defined_name("Unused", "Template!$A$3", None, None)
+            .unwrap();
+        src.set_defined_name("Local", "Template!$A$1:$A$3", Some("Template"), None)
+            .unwrap();
+        src.set_defined_name("OtherLocal", "Other!$A$1", Some("Other"), None)
+            .unwrap();
+        src.set_cell_formula("Template", "B1", "Rate*2").unwrap();
+
+        let mut dst = Workbook::new();
+        dst.import_sheet(&src, "Template", "Report").unwrap();
+
+        let rate = dst.get_defined_name("Rate", None).unwrap().unwrap();
+        assert_eq!(rate.value, "Report!$A$2");
+        let local = dst.get_defined_name("Local", Some("Report")).unwrap();
+        assert_eq!(local.unwrap().value, "Report!$A$1:$A$3");
+        assert_eq!(local_scope(&dst, "Local"), Some(DefinedNameScope::Sheet(1)));
+        assert!(dst.get_defined_name("Unused", None).unwrap().is_none());
+        assert_eq!(local_scope(&dst, "OtherLocal"), None);
+    }
+
+    #[test]
+    fn test_import_sheet_detaches_other_sheet_references() {
+        let mut src = template();
+        src.new_sheet("Other").unwrap();
+        src.set_defined_name("Base", "Other!$A$1", None, None)
+            .unwrap();
+        src.set_cell_formula("Template", "B1", "Template!A2+Other!A1")
+            .unwrap();
+        src.set_cell_formula("Template", "B2", "SUM(Other!A1:A3)+A3+Base")
+            .unwrap();
+
+        // A sheet of the same name in the destination is not picked up.
+        let mut dst = Workbook::new();
+        dst.new_sheet("Other").unwrap();
+        dst.import_sheet(&src, "Template", "Report").unwrap();
+
+        assert_eq!(formula_at(&dst, "Report", "B1"), "Report!A2+#REF!");
+        assert_eq!(formula_at(&dst, "Report", "B2"), "SUM(#REF!)+A3+Base");
+        let base = dst.get_defined_name("Base", None).unwrap().unwrap();
+        assert_eq!(base.value, "#REF!");
+    }
+
+    fn local_scope(wb: &Workbook, name: &str) -> Option<DefinedNameScope> {
+        crate::defined_names::get_all_defined_names(&wb.workbook_xml)
+            .into_iter()
+            .find(|info| info.name == name)
+            .map(|info| info.scope)
+    }
+}
@@ -46,6 +46,7 @@ impl Workbook {
                     y_window: None,
                     window_width: None,
                     window_height: None,
+                    first_sheet: None,
                     active_tab: Some(0),
                 }],
             }),
@@ -101,6 +102,13 @@ impl Workbook {
             package_source: None,
             read_mode: ReadMode::default(),
             sheet_rows_limit: None,
+            calc_cache: None,
+            dirty_cells: HashSet::new(),
+            shared_masters: vec![OnceLock::new()],
+            legacy_arrays: vec![OnceLock::new()],
+            functions: crate::formula::registry::FunctionRegistry::default(),
+            parallel_calc: false,
+            not_converged: None,
         }
     }
 
@@ -635,6 +643,12 @@ impl Workbook {
         for (i, (name, _)) in worksheets.iter().enumerate() {
             sheet_name_index.insert(name.clone(), i);
         }
+        let shared_masters = std::iter::repeat_with(OnceLock::new)
+            .take(worksheets.len())
+            .collect();
+        let legacy_arrays = std::iter::repeat_with(OnceLock::new)
+            .take(worksheets.len())
+            .collect();
 
         // Collect remaining ZIP entries. In Lazy/Stream mode, unhandled entries
         // go into deferred_parts (typed index); in Eager mode, they go into
@@ -729,6 +743,13 @@ impl Workbook {
             package_source: None,
             read_mode: options.read_mode,
             sheet_rows_limit: options.sheet_rows,
+            calc_cache: None,
+            dirty_cells: HashSet::new(),
+            shared_masters,
+            legacy_arrays,
+            functions: crate::formula::registry::FunctionRegistry::default(),
+            parallel_calc: false,
+            not_converged: None,
         })
     }
 
@@ -1141,9 +1162,13 @@ impl Workbook {
                     let rels = worksheet_rels
                         .entry(i)
                         .or_insert_with(default_relationships);
-                    if !rels.relationships.iter().any(|r| {
+                    // Point an existing relationship at the part path, which
+                    // changes when the sheet moves.
+                    if let Some(rel) = rels.relationships.iter_mut().find(|r| {
                         r.rel_type == sheetkit_xml::threaded_comment::REL_TYPE_THREADED_COMMENT
                     }) {
+                        rel.target = target;
+                    } else {
                         let rid = crate::sheet::next_rid(&rels.relationships);
                         rels.relationships.push(Relationship {
                             id: rid,
@@ -82,8 +82,12 @@ use sheetkit_xml::worksheet::{Cell, CellFormula, CellTypeTag, DrawingRef, Row, W
 use zip::write::SimpleFileOptions;
 use zip::CompressionMethod;
 
-use crate::cell::CellValue;
-use crate::cell_ref_shift::shift_cell_references_in_text;
+use crate::cell::{CellValue, DateSystem};
+use crate::cell_ref_shift::{
+    move_formula_references, refers_to_sheet, shift_cell_name, shift_formula_references,
+    shift_sqref, RefMove, RefShift,
+};
+use crate::cell_shift::{CellRange, DeleteShift, InsertShift};
 use crate::chart::ChartConfig;
 use crate::comment::CommentConfig;
 use crate::conditional::ConditionalFormatRule;
@@ -91,6 +95,7 @@ use crate::error::{Error, Result};
 use crate::image::ImageConfig;
 use crate::pivot::{PivotTableConfig, PivotTableInfo};
 use crate::protection::WorkbookProtectionConfig;
+use crate::range_copy::{CopyRangeOptions, Placement, RangeBlock};
 use crate::sst::SharedStringTable;
 use crate::threaded_comment::{PersonData, PersonInput, ThreadedCommentData, ThreadedCommentInput};
 use crate::utils::cell_ref::{cell_name_to_coordinates, column_name_to_number};
@@ -103,15 +108,18 @@ use crate::workbook_paths::{
 
 #[path = "aux_parts.rs"]
 pub(crate) mod aux;
+mod calc;
 mod cell_ops;
 mod data;
 mod drawing;
 mod features;
+mod import;
 mod io;
 mod open_options;
 mod sheet_ops;
 mod source;
 
+pub use calc::TracedCell;
 pub use open_options::{AuxParts, OpenOptions, ReadMode};
 pub(crate) use source::PackageSource;
 
@@ -227,6 +235,27 @@ pub struct Workbook {
     /// Optional row limit from `OpenOptions::sheet_rows`, applied during
     /// on-demand hydration of deferred sheets.
     sheet_rows_limit: Option<u32>,
+    /// Dependency graph and values kept from the last calculation, used by
+    /// [`Workbook::recalculate_dirty`]. `None` before the first calculation
+    /// and after a change that cell-level dirty tracking cannot follow.
+    calc_cache: Option<calc::CalcCache>,
+    /// Cells edited since the last calculation, tracked only while
+    /// `calc_cache` is present.
+    dirty_cells: HashSet<crate::formula::eval::CellCoord>,
+    /// Shared formula masters per sheet index, collected on first use and
+    /// reset whenever the sheet is modified.
+    shared_masters: Vec<OnceLock<HashMap<u32, crate::shared_formula::SharedFormulaMaster>>>,
+    /// Ranges of the legacy array formulas per sheet index, collected on
+    /// first use and reset whenever the sheet is modified.
+    legacy_arrays: Vec<OnceLock<Vec<crate::array_formula::ArrayArea>>>,
+    /// Caller-registered formula functions, see [`Workbook::register_function`].
+    functions: crate::formula::registry::FunctionRegistry,
+    /// Whether calculation may evaluate formulas on several threads, see
+    /// [`Workbook::set_parallel_calculation`].
+    parallel_calc: bool,
+    /// The first circular reference that did not converge in the last
+    /// calculation, see [`Workbook::iteration_not_converged`].
+    not_converged: Option<(String, u32)>,
 }
 
 impl Workbook {
@@ -265,6 +294,54 @@ impl Workbook {
         if idx < self.sheet_dirty.len() {
             self.sheet_dirty[idx] = true;
         }
+        if let Some(masters) = self.shared_masters.get_mut(idx) {
+            masters.take();
+        }
+        if let Some(areas) = self.legacy_arrays.get_mut(idx) {
+            areas.take();
+        }
+    }
+
+    /// Shared formula masters of the sheet at `idx`, keyed by group index.
+    /// Collected once and reused until the sheet is modified.
+    pub(crate) fn shared_masters(
+        &self,
+        idx: usize,
+    ) -> Result<&HashMap<u32, crate::shared_formula::SharedFormulaMaster>> {
+        let ws = self.worksheet_ref_by_index(idx)?;
+        match self.shared_masters.get(idx) {
+            Some(masters) => {
+                Ok(masters.get_or_init(|| crate::shared_formula::collect_shared_masters(ws)))
+            }
+            None => Err(Error::Internal(format!(
+                "sheet at index {idx} has no shared formula cache"
+            ))),
+        }
+    }
+
+    /// Return an error if `(col, row)` lies inside a legacy array formula
+    /// range of the sheet at `idx` without being its first cell. Such
+    /// cells hold part of the array's result and change only with the
+    /// whole array.
+    pub(crate) fn check_array_part(&self, idx: usize, col: u32, row: u32) -> Result<()> {
+        let ws = self.worksheet_ref_by_index(idx)?;
+        let areas = match self.legacy_arrays.get(idx) {
+            Some(areas) => areas.get_or_init(|| crate::array_formula::legacy_array_areas(ws)),
+            None => {
+                return Err(Error::Internal(format!(
+                    "sheet at index {idx} has no array formula cache"
+                )))
+            }
+        };
+        match areas
+            .iter()
+            .find(|a| a.contains(col, row) && (a.min_col, a.min_row) != (col, row))
+        {
+            Some(area) => Err(Error::ArrayFormulaPart {
+                range: area.to_reference()?,
+            }),
+            None => Ok(()),
+        }
     }
 
     /// Check whether a sheet has been marked dirty since opening.
@@ -278,6 +355,10 @@ impl Workbook {
     /// If the sheet has streamed data (from [`apply_stream_writer`]), the
     /// streamed entry is removed so that subsequent edits are not silently
     /// ignored on save. Deferred sheets are hydrated on demand.
+    ///
+    /// The calculation cache is kept, so callers that change cell values or
+    /// formulas must also call [`mark_cell_dirty`](Self::mark_cell_dirty) or
+    /// [`invalidate_calc_cache`](Self::invalidate_calc_cache).
     pub(crate) fn worksheet_mut(&mut self, sheet: &str) -> Result<&mut WorksheetXml> {
         let idx = self.sheet_index(sheet)?;
         self.invalidate_streamed(idx);
@@ -328,6 +409,13 @@ impl Workbook {
     /// Rebuild the sheet name -> index lookup after any structural change
     /// to the worksheets vector.
     pub(crate) fn rebuild_sheet_index(&mut self) {
+        self.invalidate_calc_cache();
+        self.shared_masters = std::iter::repeat_with(OnceLock::new)
+            .take(self.worksheets.len())
+            .collect();
+        self.legacy_arrays = std::iter::repeat_with(OnceLock::new)
+            .take(self.worksheets.len())
+            .collect();
         self.sheet_name_index.clear();
         for (i, (name, _ws_lock)) in self.worksheets.iter().enumerate() {
             self.sheet_name_index.insert(name.clone(), i);
@@ -349,6 +437,12 @@ impl Workbook {
                 }
                 *self.worksheets[idx].1.get_mut().unwrap() = ws;
                 self.raw_sheet_xml[idx] = None;
+                if let Some(masters) = self.shared_masters.get_mut(idx) {
+                    masters.take();
+                }
+                if let Some(areas) = self.legacy_arrays.get_mut(idx) {
+                    areas.take();
+                }
             }
             return Ok(());
         }
@@ -369,7 +463,9 @@ impl Workbook {
     }
 
     /// Hydrate if needed and return a mutable reference to the worksheet
-    /// at the given index. Callers must hold `&mut self`.
+    /// at the given index. Callers must hold `&mut self`, and like
+    /// [`worksheet_mut`](Self::worksheet_mut) must record value or formula
+    /// changes for the calculation cache themselves.
     pub(crate) fn worksheet_mut_by_index(&mut self, idx: usize) -> Result<&mut WorksheetXml> {
         self.ensure_hydrated(idx)?;
         self.mark_sheet_dirty(idx);
@@ -396,8 +492,8 @@ impl Workbook {
     ///
     /// The reader processes worksheet XML row-by-row without materializing the
     /// full DOM, enabling bounded-memory processing of large worksheets. The
-    /// workbook's shared string table and optional `sheet_rows` limit are
-    /// passed through to the reader.
+    /// workbook's shared string table, date system and optional `sheet_rows`
+    /// limit are passed through to the reader.
     ///
     /// The XML bytes come from `raw_sheet_xml` (deferred sheets in Lazy/Stream
     /// mode) or from a freshly hydrated worksheet serialized back to bytes.
@@ -411,11 +507,13 @@ impl Workbook {
         let xml_bytes = self.sheet_xml_bytes(idx)?;
         let cursor = std::io::Cursor::new(xml_bytes);
         let buf_reader = std::io::BufReader::new(cursor);
-        Ok(crate::stream_reader::SheetStreamReader::new(
+        let mut reader = crate::stream_reader::SheetStreamReader::new(
             buf_reader,
             &self.sst_runtime,
             self.sheet_rows_limit,
-        ))
+        );
+        reader.set_date_system(self.date_system());
+        Ok(reader)
     }
 
     /// Create an owned forward-only streaming reader for the named sheet.
@@ -431,11 +529,13 @@ impl Workbook {
         let idx = self.sheet_index(sheet)?;
         let xml_bytes = self.sheet_xml_bytes(idx)?;
         let sst_snapshot = self.sst_runtime.clone_for_read();
-        Ok(crate::stream_reader::OwnedSheetStreamReader::new(
+        let mut reader = crate::stream_reader::OwnedSheetStreamReader::new(
             xml_bytes,
             sst_snapshot,
             self.sheet_rows_limit,
-        ))
+        );
+        reader.set_date_system(self.date_system());
+        Ok(reader)
     }
 
     /// Get the raw XML bytes for a sheet by index.
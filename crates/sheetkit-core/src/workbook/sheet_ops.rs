@@ -173,6 +173,75 @@ impl Workbook {
         Ok(idx)
     }
 
+    /// Move a sheet to the 0-based position `new_index`, shifting the sheets
+    /// in between by one.
+    ///
+    /// Everything tied to a sheet position moves with the sheet: comments,
+    /// sparklines, tables, drawings, and other per-sheet data, the scope of
+    /// sheet-level defined names, and the active and first visible tab.
+    pub fn move_sheet(&mut self, name: &str, new_index: usize) -> Result<()> {
+        self.assert_parallel_vecs_in_sync();
+        if self.get_sheet_index(name).is_none() {
+            return Err(Error::SheetNotFound {
+                name: name.to_string(),
+            });
+        }
+        // Comment, VML, and threaded comment parts are saved under names
+        // derived from the sheet position. Load any that are still deferred
+        // so none is left at a path that another sheet's part now takes.
+        for idx in 0..self.worksheets.len() {
+            self.hydrate_comments(idx);
+        }
+        self.hydrate_threaded_comments();
+
+        let idx = crate::sheet::move_sheet(
+            &mut self.workbook_xml,
+            &mut self.worksheets,
+            name,
+            new_index,
+        )?;
+        if idx == new_index {
+            return Ok(());
+        }
+
+        fn move_entry<T>(items: &mut Vec<T>, from: usize, to: usize) {
+            let item = items.remove(from);
+            items.insert(to, item);
+        }
+        move_entry(&mut self.sheet_comments, idx, new_index);
+        move_entry(&mut self.sheet_sparklines, idx, new_index);
+        move_entry(&mut self.sheet_vml, idx, new_index);
+        move_entry(&mut self.raw_sheet_xml, idx, new_index);
+        move_entry(&mut self.sheet_dirty, idx, new_index);
+        move_entry(&mut self.sheet_threaded_comments, idx, new_index);
+        move_entry(&mut self.sheet_form_controls, idx, new_index);
+
+        let remap = |i: usize| crate::sheet::moved_sheet_index(i, idx, new_index);
+        for (_, _, si) in &mut self.tables {
+            *si = remap(*si);
+        }
+        self.worksheet_rels = self
+            .worksheet_rels
+            .drain()
+            .map(|(i, rels)| (remap(i), rels))
+            .collect();
+        self.worksheet_drawings = self
+            .worksheet_drawings
+            .drain()
+            .map(|(i, drawing_idx)| (remap(i), drawing_idx))
+            .collect();
+        self.streamed_sheets = self
+            .streamed_sheets
+            .drain()
+            .map(|(i, data)| (remap(i), data))
+            .collect();
+
+        // 3-D references such as `Sheet1:Sheet3!A1` depend on sheet order.
+        self.invalidate_calc_cache();
+        self.rebuild_sheet_index();
+        Ok(())
+    }
+
     /// Get a sheet's 0-based index by name. Returns `None` if not found.
     pub fn get_sheet_index(&self, name: &str) -> Option<usize> {
         crate::sheet::find_sheet_index(&self.worksheets, name)
@@ -209,7 +278,9 @@ impl Workbook {
                 name: sheet_name.to_string(),
             });
         }
-        Ok(crate::stream::StreamWriter::new(sheet_name))
+        let mut writer = crate::stream::StreamWriter::new(sheet_name);
+        writer.set_date_system(self.date_system());
+        Ok(writer)
     }
 
     /// Apply a completed [`StreamWriter`](crate::stream::StreamWriter) to the
@@ -270,37 +341,45 @@ impl Workbook {
     /// Insert `count` empty rows starting at `start_row` in the named sheet.
     pub fn insert_rows(&mut self, sheet: &str, start_row: u32, count: u32) -> Result<()> {
         let sheet_idx = self.sheet_index(sheet)?;
+        self.unshare_shifted_formulas(sheet_idx)?;
         {
             let ws = self.worksheet_mut_by_index(sheet_idx)?;
             crate::row::insert_rows(ws, start_row, count)?;
         }
-        self.apply_reference_shift_for_sheet(sheet_idx, |col, row| {
-            if row >= start_row {
-                (col, row + count)
-            } else {
-                (col, row)
-            }
-        })
+        self.apply_reference_shift(sheet_idx, RefShift::insert_rows(start_row, count))
     }
 
     /// Remove a single row from the named sheet, shifting rows below it up.
     pub fn remove_row(&mut self, sheet: &str, row: u32) -> Result<()> {
         let sheet_idx = self.sheet_index(sheet)?;
+        self.unshare_shifted_formulas(sheet_idx)?;
         {
             let ws = self.worksheet_mut_by_index(sheet_idx)?;
             crate::row::remove_row(ws, row)?;
         }
-        self.apply_reference_shift_for_sheet(sheet_idx, |col, r| {
-            if r > row {
-                (col, r - 1)
-            } else {
-                (col, r)
-            }
-        })
+        self.apply_reference_shift(sheet_idx, RefShift::delete_rows(row, 1))
+    }
+
+    /// Remove `count` rows starting at `start_row` from the named sheet,
+    /// shifting rows below them up. References are updated in one pass,
+    /// so this is much faster than calling [`remove_row`](Self::remove_row)
+    /// `count` times.
+    pub fn remove_rows(&mut self, sheet: &str, start_row: u32, count: u32) -> Result<()> {
+        let sheet_idx = self.sheet_index(sheet)?;
+        self.unshare_shifted_formulas(sheet_idx)?;
+        {
+            let ws = self.worksheet_mut_by_index(sheet_idx)?;
+            crate::row::remove_rows(ws, start_row, count)?;
+        }
+        if count == 0 {
+            return Ok(());
+        }
+        self.apply_reference_shift(sheet_idx, RefShift::delete_rows(start_row, count))
     }
 
     /// Duplicate a row, inserting the copy directly below.
     pub fn duplicate_row(&mut self, sheet: &str, row: u32) -> Result<()> {
+        self.invalidate_calc_cache();
         let ws = self.worksheet_mut(sheet)?;
         crate::row::duplicate_row(ws, row)
     }
@@ -362,21 +441,42 @@ impl Workbook {
     ///
     /// Returns a Vec of `(row_number, Vec<(column_number, CellValue)>)` tuples.
     /// Column numbers are 1-based (A=1, B=2, ...). Only rows that contain at
-    /// least one cell are included (sparse).
+    /// least one cell are included (sparse). Dates are converted to the 1900
+    /// date system, like [`get_cell_value`](Self::get_cell_value).
     #[allow(clippy::type_complexity)]
     pub fn get_rows(&self, sheet: &str) -> Result<Vec<(u32, Vec<(u32, CellValue)>)>> {
         let ws = self.worksheet_ref(sheet)?;
-        crate::row::get_rows(ws, &self.sst_runtime)
+        let mut rows = crate::row::get_rows(ws, &self.sst_runtime)?;
+        for (_, cells) in &mut rows {
+            for (_, value) in cells {
+                self.date_to_1900(value);
+            }
+        }
+        Ok(rows)
     }
 
     /// Get all columns with their data from a sheet.
     ///
     /// Returns a Vec of `(column_name, Vec<(row_number, CellValue)>)` tuples.
-    /// Only columns that have data are included (sparse).
+    /// Only columns that have data are included (sparse). Dates are
+    /// converted to the 1900 date system.
     #[allow(clippy::type_complexity)]
     pub fn get_cols(&self, sheet: &str) -> Result<Vec<(String, Vec<(u32, CellValue)>)>> {
         let ws = self.worksheet_ref(sheet)?;
-        crate::col::get_cols(ws, &self.sst_runtime)
+        let mut cols = crate::col::get_cols(ws, &self.sst_runtime)?;
+        for (_, cells) in &mut cols {
+            for (_, value) in cells {
+                self.date_to_1900(value);
+            }
+        }
+        Ok(cols)
+    }
+
+    /// Convert a date read from the sheet to the 1900 date system.
+    fn date_to_1900(&self, value: &mut CellValue) {
+        if let CellValue::Date(serial) = value {
+            *serial = self.date_system().to_1900_serial(*serial);
+        }
     }
 
     /// Set the width of a column.
@@ -435,35 +535,86 @@ impl Workbook {
     /// Insert `count` columns starting at `col` in the named sheet.
     pub fn insert_cols(&mut self, sheet: &str, col: &str, count: u32) -> Result<()> {
         let sheet_idx = self.sheet_index(sheet)?;
+        self.unshare_shifted_formulas(sheet_idx)?;
         let start_col = column_name_to_number(col)?;
         {
             let ws = self.worksheet_mut_by_index(sheet_idx)?;
             crate::col::insert_cols(ws, col, count)?;
         }
-        self.apply_reference_shift_for_sheet(sheet_idx, |c, row| {
-            if c >= start_col {
-                (c + count, row)
-            } else {
-                (c, row)
-            }
-        })
+        self.apply_reference_shift(sheet_idx, RefShift::insert_cols(start_col, count))
     }
 
     /// Remove a single column from the named sheet.
     pub fn remove_col(&mut self, sheet: &str, col: &str) -> Result<()> {
         let sheet_idx = self.sheet_index(sheet)?;
+        self.unshare_shifted_formulas(sheet_idx)?;
         let col_num = column_name_to_number(col)?;
         {
             let ws = self.worksheet_mut_by_index(sheet_idx)?;
             crate::col::remove_col(ws, col)?;
         }
-        self.apply_reference_shift_for_sheet(sheet_idx, |c, row| {
-            if c > col_num {
-                (c - 1, row)
-            } else {
-                (c, row)
+        self.apply_reference_shift(sheet_idx, RefShift::delete_cols(col_num, 1))
+    }
+
+    /// Remove `count` columns starting at `col` from the named sheet,
+    /// shifting columns to their right left. References are updated in one
+    /// pass.
+    pub fn remove_cols(&mut self, sheet: &str, col: &str, count: u32) -> Result<()> {
+        let sheet_idx = self.sheet_index(sheet)?;
+        self.unshare_shifted_formulas(sheet_idx)?;
+        let col_num = column_name_to_number(col)?;
+        {
+            let ws = self.worksheet_mut_by_index(sheet_idx)?;
+            crate::col::remove_cols(ws, col, count)?;
+        }
+        if count == 0 {
+            return Ok(());
+        }
+        self.apply_reference_shift(sheet_idx, RefShift::delete_cols(col_num, count))
+    }
+
+    /// Insert empty cells over `range` (e.g. `"B2:C4"`) in the named sheet,
+    /// shifting the cells in their way right or down, like Excel's "Insert
+    /// cells". Only the rows or columns of the range move; references to the
+    /// moved cells are updated, while ranges that reach outside them are left
+    /// as they are.
+    pub fn insert_cells(&mut self, sheet: &str, range: &str, shift: InsertShift) -> Result<()> {
+        let sheet_idx = self.sheet_index(sheet)?;
+        self.unshare_shifted_formulas(sheet_idx)?;
+        let area = CellRange::parse(range)?;
+        {
+            let ws = self.worksheet_mut_by_index(sheet_idx)?;
+            crate::cell_shift::insert_cells(ws, range, shift)?;
+        }
+        let ref_shift = match shift {
+            InsertShift::Down => RefShift::insert_rows(area.min_row, area.height())
+                .within(area.min_col, area.max_col),
+            InsertShift::Right => {
+                RefShift::insert_cols(area.min_col, area.width()).within(area.min_row, area.max_row)
             }
-        })
+        };
+        self.apply_reference_shift(sheet_idx, ref_shift)
+    }
+
+    /// Delete the cells in `range` (e.g. `"B2:C4"`) from the named sheet,
+    /// shifting the cells below it up or to its right left, like Excel's
+    /// "Delete cells". References to deleted cells become `#REF!`.
+    pub fn delete_cells(&mut self, sheet: &str, range: &str, shift: DeleteShift) -> Result<()> {
+        let sheet_idx = self.sheet_index(sheet)?;
+        self.unshare_shifted_formulas(sheet_idx)?;
+        let area = CellRange::parse(range)?;
+        {
+            let ws = self.worksheet_mut_by_index(sheet_idx)?;
+            crate::cell_shift::delete_cells(ws, range, shift)?;
+        }
+        let ref_shift = match shift {
+            DeleteShift::Up => RefShift::delete_rows(area.min_row, area.height())
+                .within(area.min_col, area.max_col),
+            DeleteShift::Left => {
+                RefShift::delete_cols(area.min_col, area.width()).within(area.min_row, area.max_row)
+            }
+        };
+        self.apply_reference_shift(sheet_idx, ref_shift)
     }
 
     /// Reindex per-sheet maps after deleting a sheet.
@@ -497,109 +648,144 @@ impl Workbook {
             .collect();
     }
 
-    /// Apply a cell-reference shift transformation to sheet-scoped structures.
-    pub(crate) fn apply_reference_shift_for_sheet<F>(
-        &mut self,
-        sheet_idx: usize,
-        shift_cell: F,
-    ) -> Result<()>
-    where
-        F: Fn(u32, u32) -> (u32, u32) + Copy,
-    {
-        {
-            let ws = self.worksheet_mut_by_index(sheet_idx)?;
-
-            // Cell formulas.
-            for row in &mut ws.sheet_data.rows {
-                for cell in &mut row.cells {
-                    if let Some(ref mut f) = cell.f {
-                        if let Some(ref mut expr) = f.value {
-                            *expr = shift_cell_references_in_text(expr, shift_cell)?;
-                        }
-                    }
-                }
+    /// Turn the shared formula groups that inserting or deleting cells on
+    /// the sheet at `sheet_idx` could break into standalone formulas. The
+    /// cells of a group take their formula from its first cell, which the
+    /// shift can move or delete apart from the rest, so every group on the
+    /// sheet and every group elsewhere that refers to it is expanded.
+    fn unshare_shifted_formulas(&mut self, sheet_idx: usize) -> Result<()> {
+        let sheet = self.worksheets[sheet_idx].0.clone();
+        for idx in 0..self.worksheets.len() {
+            if self.streamed_sheets.contains_key(&idx) {
+                continue;
             }
-
-            // Merged ranges.
-            if let Some(ref mut merges) = ws.merge_cells {
-                for mc in &mut merges.merge_cells {
-                    mc.reference = shift_cell_references_in_text(&mc.reference, shift_cell)?;
-                }
-                // Invalidate the coordinate cache since references changed.
-                merges.cached_coords.clear();
+            let groups: Vec<u32> =
+                crate::shared_formula::collect_shared_masters(self.worksheet_ref_by_index(idx)?)
+                    .into_iter()
+                    .filter(|(_, master)| {
+                        idx == sheet_idx || refers_to_sheet(&master.formula, &sheet)
+                    })
+                    .map(|(si, _)| si)
+                    .collect();
+            if groups.is_empty() {
+                continue;
             }
-
-            // Auto-filter.
-            if let Some(ref mut af) = ws.auto_filter {
-                af.reference = shift_cell_references_in_text(&af.reference, shift_cell)?;
+            let ws = self.worksheet_mut_by_index(idx)?;
+            for si in groups {
+                crate::shared_formula::unshare_formulas(ws, Some(si))?;
             }
+        }
+        Ok(())
+    }
 
-            // Data validations.
-            if let Some(ref mut dvs) = ws.data_validations {
-                for dv in &mut dvs.data_validations {
-                    dv.sqref = shift_cell_references_in_text(&dv.sqref, shift_cell)?;
-                    if let Some(ref mut f1) = dv.formula1 {
-                        *f1 = shift_cell_references_in_text(f1, shift_cell)?;
-                    }
-                    if let Some(ref mut f2) = dv.formula2 {
-                        *f2 = shift_cell_references_in_text(f2, shift_cell)?;
-                    }
+    /// Move every reference into the sheet at `sheet_idx` after rows or
+    /// columns were inserted into or deleted from it: formulas, data
+    /// validation and conditional formatting rules on every sheet, defined
+    /// names (including print areas), chart series, pivot cache sources,
+    /// and sparklines, as well as the sheet's own merged cells, filters,
+    /// tables, hyperlinks, comments, selection, and drawing anchors.
+    /// References into deleted cells become `#REF!`; ranges shrink, and
+    /// areas that were deleted entirely are removed.
+    pub(crate) fn apply_reference_shift(
+        &mut self,
+        sheet_idx: usize,
+        shift: RefShift,
+    ) -> Result<()> {
+        self.invalidate_calc_cache();
+        let sheet = self.worksheets[sheet_idx].0.clone();
+        let sheet = sheet.as_str();
+
+        self.update_formula_references(|text, host| {
+            shift_formula_references(text, host, sheet, shift)
+        })?;
+        let ws = self.worksheet_mut_by_index(sheet_idx)?;
+        shift_sheet_references(ws, shift)?;
+
+        // Tables on the sheet. A table whose rows or columns were all
+        // deleted keeps its range.
+        self.hydrate_tables();
+        for (_, table, table_sheet) in &mut self.tables {
+            if *table_sheet != sheet_idx {
+                continue;
+            }
+            if let Some(reference) = shift_sqref(&table.reference, shift) {
+                table.reference = reference;
+            }
+            if let Some(af) = table.auto_filter.as_mut() {
+                if let Some(reference) = shift_sqref(&af.reference, shift) {
+                    af.reference = reference;
                 }
             }
+        }
 
-            // Conditional formatting ranges/formulas.
-            for cf in &mut ws.conditional_formatting {
-                cf.sqref = shift_cell_references_in_text(&cf.sqref, shift_cell)?;
-                for rule in &mut cf.cf_rules {
-                    for f in &mut rule.formulas {
-                        *f = shift_cell_references_in_text(f, shift_cell)?;
+        // Pivot cache sources on the sheet.
+        self.hydrate_pivot_tables();
+        for (_, cache) in &mut self.pivot_cache_defs {
+            if let Some(source) = cache.cache_source.worksheet_source.as_mut() {
+                if source.sheet == sheet {
+                    if let Some(reference) = shift_sqref(&source.reference, shift) {
+                        source.reference = reference;
                     }
                 }
             }
+        }
 
-            // Hyperlinks.
-            if let Some(ref mut hyperlinks) = ws.hyperlinks {
-                for hl in &mut hyperlinks.hyperlinks {
-                    hl.reference = shift_cell_references_in_text(&hl.reference, shift_cell)?;
-                    if let Some(ref mut loc) = hl.location {
-                        *loc = shift_cell_references_in_text(loc, shift_cell)?;
+        // Sparklines located in deleted cells are removed with them.
+        if let Some(sparklines) = self.sheet_sparklines.get_mut(sheet_idx) {
+            sparklines.retain_mut(
+                |sparkline| match shift_cell_name(&sparkline.location, shift) {
+                    Some(location) => {
+                        sparkline.location = location;
+                        true
                     }
-                }
-            }
+                    None => false,
+                },
+            );
+        }
 
-            // Pane/selection references.
-            if let Some(ref mut views) = ws.sheet_views {
-                for view in &mut views.sheet_views {
-                    if let Some(ref mut pane) = view.pane {
-                        if let Some(ref mut top_left) = pane.top_left_cell {
-                            *top_left = shift_cell_references_in_text(top_left, shift_cell)?;
-                        }
+        // Comments on deleted cells are removed with them.
+        self.hydrate_comments(sheet_idx);
+        if let Some(comments) = self.sheet_comments[sheet_idx].as_mut() {
+            comments.comment_list.comments.retain_mut(|comment| {
+                match shift_cell_name(&comment.r#ref, shift) {
+                    Some(reference) => {
+                        comment.r#ref = reference;
+                        true
                     }
-                    for sel in &mut view.selection {
-                        if let Some(ref mut ac) = sel.active_cell {
-                            *ac = shift_cell_references_in_text(ac, shift_cell)?;
-                        }
-                        if let Some(ref mut sqref) = sel.sqref {
-                            *sqref = shift_cell_references_in_text(sqref, shift_cell)?;
-                        }
+                    None => false,
+                }
+            });
+            // The VML drawing is regenerated from the comments on save.
+            self.sheet_vml[sheet_idx] = None;
+        }
+        self.hydrate_threaded_comments();
+        if let Some(threaded) = self.sheet_threaded_comments[sheet_idx].as_mut() {
+            threaded.comments.retain_mut(|comment| {
+                match shift_cell_name(&comment.cell_ref, shift) {
+                    Some(reference) => {
+                        comment.cell_ref = reference;
+                        true
                     }
+                    None => false,
                 }
-            }
+            });
         }
 
         // Drawing anchors attached to this sheet.
         if let Some(&drawing_idx) = self.worksheet_drawings.get(&sheet_idx) {
             if let Some((_, drawing)) = self.drawings.get_mut(drawing_idx) {
                 for anchor in &mut drawing.one_cell_anchors {
-                    let (new_col, new_row) = shift_cell(anchor.from.col + 1, anchor.from.row + 1);
+                    let (new_col, new_row) =
+                        shift.clamp_cell(anchor.from.col + 1, anchor.from.row + 1);
                     anchor.from.col = new_col - 1;
                     anchor.from.row = new_row - 1;
                 }
                 for anchor in &mut drawing.two_cell_anchors {
-                    let (from_col, from_row) = shift_cell(anchor.from.col + 1, anchor.from.row + 1);
+                    let (from_col, from_row) =
+                        shift.clamp_cell(anchor.from.col + 1, anchor.from.row + 1);
                     anchor.from.col = from_col - 1;
                     anchor.from.row = from_row - 1;
-                    let (to_col, to_row) = shift_cell(anchor.to.col + 1, anchor.to.row + 1);
+                    let (to_col, to_row) = shift.clamp_cell(anchor.to.col + 1, anchor.to.row + 1);
                     anchor.to.col = to_col - 1;
                     anchor.to.row = to_row - 1;
                 }
@@ -609,6 +795,51 @@ impl Workbook {
         Ok(())
     }
 
+    /// Rewrite every formula in the workbook that can refer to cells:
+    /// formulas, data validation and conditional formatting rules, and
+    /// internal hyperlinks on every sheet, defined names, chart series, and
+    /// sparkline data ranges. `update` receives the formula text and the
+    /// sheet holding it (`None` for defined names and charts), and returns
+    /// the new text. Sheets are only marked dirty if a formula changed.
+    pub(crate) fn update_formula_references(
+        &mut self,
+        mut update: impl FnMut(&str, Option<&str>) -> Result<String>,
+    ) -> Result<()> {
+        for idx in 0..self.worksheets.len() {
+            if self.streamed_sheets.contains_key(&idx) {
+                continue;
+            }
+            self.ensure_hydrated(idx)?;
+            let host = self.worksheets[idx].0.clone();
+            let ws = self.worksheets[idx].1.get_mut().unwrap();
+            if update_formulas(ws, |text| update(text, Some(&host)))? {
+                self.mark_sheet_dirty(idx);
+            }
+        }
+
+        // Defined names, including print areas and print titles.
+        if let Some(names) = self.workbook_xml.defined_names.as_mut() {
+            for dn in &mut names.defined_names {
+                dn.value = update(&dn.value, None)?;
+            }
+        }
+
+        self.hydrate_drawings();
+        for (_, chart) in &mut self.charts {
+            for f in crate::chart::series_formulas_mut(chart) {
+                *f = update(f, None)?;
+            }
+        }
+
+        for (idx, sparklines) in self.sheet_sparklines.iter_mut().enumerate() {
+            let host = self.worksheets[idx].0.as_str();
+            for sparkline in sparklines.iter_mut() {
+                sparkline.data_range = update(&sparkline.data_range, Some(host))?;
+            }
+        }
+        Ok(())
+    }
+
     /// Ensure a drawing exists for the given sheet index, creating one if needed.
     /// Returns the drawing index.
     pub(crate) fn ensure_drawing_for_sheet(&mut self, sheet_idx: usize) -> usize {
@@ -679,6 +910,168 @@ impl Workbook {
     }
 }
 
+/// Rewrite the formulas of `ws` with `update`: cell formulas, data
+/// validation and conditional formatting rules, and internal hyperlink
+/// locations. Returns `true` if any formula changed.
+pub(crate) fn update_formulas(
+    ws: &mut WorksheetXml,
+    mut update: impl FnMut(&str) -> Result<String>,
+) -> Result<bool> {
+    let mut changed = false;
+    let mut update = |text: &mut String| -> Result<()> {
+        let updated = update(text)?;
+        if updated != *text {
+            *text = updated;
+            changed = true;
+        }
+        Ok(())
+    };
+
+    for row in &mut ws.sheet_data.rows {
+        for cell in &mut row.cells {
+            if let Some(expr) = cell.f.as_mut().and_then(|f| f.value.as_mut()) {
+                update(expr)?;
+            }
+        }
+    }
+    if let Some(dvs) = ws.data_validations.as_mut() {
+        for dv in &mut dvs.data_validations {
+            for f in [dv.formula1.as_mut(), dv.formula2.as_mut()]
+                .into_iter()
+                .flatten()
+            {
+                update(f)?;
+            }
+        }
+    }
+    for cf in &mut ws.conditional_formatting {
+        for rule in &mut cf.cf_rules {
+            for f in &mut rule.formulas {
+                update(f)?;
+            }
+        }
+    }
+    if let Some(hyperlinks) = ws.hyperlinks.as_mut() {
+        for location in hyperlinks
+            .hyperlinks
+            .iter_mut()
+            .filter_map(|hl| hl.location.as_mut())
+        {
+            update(location)?;
+        }
+    }
+    Ok(changed)
+}
+
+/// Move the cells and areas of the shifted sheet `ws` itself: its shared
+/// and array formula ranges, merged cells, filter, validations, conditional
+/// formats, hyperlinks, and selection.
+fn shift_sheet_references(ws: &mut WorksheetXml, shift: RefShift) -> Result<()> {
+    // Shared and array formula ranges.
+    for row in &mut ws.sheet_data.rows {
+        for cell in &mut row.cells {
+            if let Some(reference) = cell.f.as_mut().and_then(|f| f.reference.as_mut()) {
+                if let Some(shifted) = shift_sqref(reference, shift) {
+                    *reference = shifted;
+                }
+            }
+        }
+    }
+
+    // Merged ranges that were deleted or shrank to a single cell go away.
+    if let Some(merges) = ws.merge_cells.as_mut() {
+        merges.merge_cells.retain_mut(|mc| {
+            match shift_sqref(&mc.reference, shift).filter(|r| r.contains(':')) {
+                Some(reference) => {
+                    mc.reference = reference;
+                    true
+                }
+                None => false,
+            }
+        });
+        // Invalidate the coordinate cache since references changed.
+        merges.cached_coords.clear();
+        merges.count = Some(merges.merge_cells.len() as u32);
+        if merges.merge_cells.is_empty() {
+            ws.merge_cells = None;
+        }
+    }
+
+    if let Some(af) = ws.auto_filter.as_mut() {
+        match shift_sqref(&af.reference, shift) {
+            Some(reference) => af.reference = reference,
+            None => ws.auto_filter = None,
+        }
+    }
+
+    if let Some(dvs) = ws.data_validations.as_mut() {
+        dvs.data_validations
+            .retain_mut(|dv| match shift_sqref(&dv.sqref, shift) {
+                Some(sqref) => {
+                    dv.sqref = sqref;
+                    true
+                }
+                None => false,
+            });
+        dvs.count = Some(dvs.data_validations.len() as u32);
+        if dvs.data_validations.is_empty() {
+            ws.data_validations = None;
+        }
+    }
+
+    ws.conditional_formatting
+        .retain_mut(|cf| match shift_sqref(&cf.sqref, shift) {
+            Some(sqref) => {
+                cf.sqref = sqref;
+                true
+            }
+            None => false,
+        });
+
+    if let Some(hyperlinks) = ws.hyperlinks.as_mut() {
+        hyperlinks
+            .hyperlinks
+            .retain_mut(|hl| match shift_sqref(&hl.reference, shift) {
+                Some(reference) => {
+                    hl.reference = reference;
+                    true
+                }
+                None => false,
+            });
+        if hyperlinks.hyperlinks.is_empty() {
+            ws.hyperlinks = None;
+        }
+    }
+
+    // Pane and selection positions stay on the sheet.
+    let clamp = |cell: &str| match cell_name_to_coordinates(cell) {
+        Ok((col, row)) => {
+            let (col, row) = shift.clamp_cell(col, row);
+            crate::utils::cell_ref::coordinates_to_cell_name(col, row)
+        }
+        Err(_) => Ok(cell.to_string()),
+    };
+    if let Some(views) = ws.sheet_views.as_mut() {
+        for view in &mut views.sheet_views {
+            if let Some(top_left) = view.pane.as_mut().and_then(|p| p.top_left_cell.as_mut()) {
+                *top_left = clamp(top_left)?;
+            }
+            for sel in &mut view.selection {
+                if let Some(ac) = sel.active_cell.as_mut() {
+                    *ac = clamp(ac)?;
+                }
+                if let Some(sqref) = sel.sqref.as_mut() {
+                    *sqref = match shift_sqref(sqref, shift) {
+                        Some(shifted) => shifted,
+                        None => sel.active_cell.clone().unwrap_or_else(|| "A1".to_string()),
+                    };
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 #[allow(clippy::approx_constant)]
 mod tests {
@@ -796,6 +1189,153 @@ mod tests {
         assert_eq!(wb.sheet_names(), vec!["Sheet1", "Sheet1 Copy"]);
     }
 
+    fn note(cell: &str, text: &str) -> crate::comment::CommentConfig {
+        crate::comment::CommentConfig {
+            cell: cell.to_string(),
+            author: "Test".to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    fn table_on(range: &str) -> crate::table::TableConfig {
+        crate::table::TableConfig {
+            name: "Totals".to_string(),
+            display_name: "Totals".to_string(),
+            range: range.to_string(),
+            columns: vec![crate::table::TableColumn {
+                name: "Amount".to_string(),
+                totals_row_function: None,
+                totals_row_label: None,
+            }],
+            show_header_row: true,
+            style_name: None,
+            auto_filter: false,
+            show_first_column: false,
+            show_last_column: false,
+            show_row_stripes: false,
+            show_column_stripes: false,
+        }
+    }
+
+    #[test]
+    fn test_move_sheet_reorders_per_sheet_data() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Sheet2").unwrap();
+        wb.new_sheet("Sheet3").unwrap();
+        wb.add_comment("Sheet1", &note("A1", "first")).unwrap();
+        wb.add_sparkline(
+            "Sheet3",
+            &crate::sparkline::SparklineConfig::new("Sheet3!A1:A5", "B1"),
+        )
+        .unwrap();
+        wb.set_cell_value("Sheet3", "A1", CellValue::from("Amount"))
+            .unwrap();
+        wb.add_table("Sheet3", &table_on("A1:A5")).unwrap();
+        wb.set_defined_name("Local", "Sheet3!$A$1", Some("Sheet3"), None)
+            .unwrap();
+        wb.set_active_sheet("Sheet2").unwrap();
+
+        wb.move_sheet("Sheet3", 0).unwrap();
+
+        assert_eq!(wb.sheet_names(), vec!["Sheet3", "Sheet1", "Sheet2"]);
+        assert_eq!(wb.get_sheet_index("Sheet2"), Some(2));
+        assert_eq!(wb.get_active_sheet(), "Sheet2");
+        assert_eq!(wb.get_comments("Sheet1").unwrap()[0].text, "first");
+        assert!(wb.get_comments("Sheet3").unwrap().is_empty());
+        assert_eq!(wb.get_sparklines("Sheet3").unwrap().len(), 1);
+        assert!(wb.get_sparklines("Sheet1").unwrap().is_empty());
+        assert_eq!(wb.get_tables("Sheet3").unwrap().len(), 1);
+        assert!(wb.get_tables("Sheet1").unwrap().is_empty());
+        let local = wb.get_defined_name("Local", Some("Sheet3")).unwrap();
+        assert_eq!(local.unwrap().value, "Sheet3!$A$1");
+
+        // Moving to the current position is a no-op.
+        wb.move_sheet("Sheet1", 1).unwrap();
+        assert_eq!(wb.sheet_names(), vec!["Sheet3", "Sheet1", "Sheet2"]);
+
+        wb.move_sheet("Sheet3", 2).unwrap();
+        assert_eq!(wb.sheet_names(), vec!["Sheet1", "Sheet2", "Sheet3"]);
+        assert_eq!(wb.get_active_sheet(), "Sheet2");
+        assert!(wb
+            .get_defined_name("Local", Some("Sheet3"))
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_move_sheet_updates_first_visible_tab() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Sheet2").unwrap();
+        wb.new_sheet("Sheet3").unwrap();
+        let views = wb.workbook_xml.book_views.as_mut().unwrap();
+        views.workbook_views[0].first_sheet = Some(1);
+
+        wb.move_sheet("Sheet2", 2).unwrap();
+        let views = wb.workbook_xml.book_views.as_ref().unwrap();
+        assert_eq!(views.workbook_views[0].first_sheet, Some(2));
+    }
+
+    #[test]
+    fn test_move_sheet_invalid_arguments() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Sheet2").unwrap();
+        assert!(matches!(
+            wb.move_sheet("NoSuchSheet", 0).unwrap_err(),
+            Error::SheetNotFound { .. }
+        ));
+        assert!(matches!(
+            wb.move_sheet("Sheet1", 2).unwrap_err(),
+            Error::InvalidArgument(_)
+        ));
+        assert_eq!(wb.sheet_names(), vec!["Sheet1", "Sheet2"]);
+    }
+
+    #[test]
+    fn test_move_sheet_roundtrip_lazy_open() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Sheet2").unwrap();
+        wb.new_sheet("Sheet3").unwrap();
+        wb.set_cell_value("Sheet1", "A1", CellValue::from("one"))
+            .unwrap();
+        wb.set_cell_value("Sheet3", "A1", CellValue::from("Amount"))
+            .unwrap();
+        wb.add_comment("Sheet1", &note("A1", "first")).unwrap();
+        wb.add_comment("Sheet3", &note("B2", "third")).unwrap();
+        wb.add_table("Sheet3", &table_on("A1:A5")).unwrap();
+        let reply = crate::threaded_comment::ThreadedCommentInput {
+            author: "Test".to_string(),
+            text: "thread".to_string(),
+            parent_id: None,
+        };
+        wb.add_threaded_comment("Sheet3", "D4", &reply).unwrap();
+        let buf = wb.save_to_buffer().unwrap();
+
+        let mut wb = Workbook::open_from_buffer(&buf).unwrap();
+        wb.move_sheet("Sheet3", 0).unwrap();
+        wb.add_comment("Sheet2", &note("C3", "second")).unwrap();
+        let buf = wb.save_to_buffer().unwrap();
+
+        let mut wb = Workbook::open_from_buffer(&buf).unwrap();
+        assert_eq!(wb.sheet_names(), vec!["Sheet3", "Sheet1", "Sheet2"]);
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "A1").unwrap(),
+            CellValue::from("one")
+        );
+        assert_eq!(
+            wb.get_cell_value("Sheet3", "A1").unwrap(),
+            CellValue::from("Amount")
+        );
+        assert_eq!(wb.get_comments("Sheet1").unwrap()[0].text, "first");
+        assert_eq!(wb.get_comments("Sheet2").unwrap()[0].text, "second");
+        assert_eq!(wb.get_comments("Sheet3").unwrap()[0].text, "third");
+        assert_eq!(wb.get_tables("Sheet3").unwrap()[0].name, "Totals");
+        assert_eq!(
+            wb.get_threaded_comments("Sheet3").unwrap()[0].text,
+            "thread"
+        );
+        assert!(wb.get_threaded_comments("Sheet1").unwrap().is_empty());
+    }
+
     #[test]
     fn test_get_sheet_index() {
         let mut wb = Workbook::new();
@@ -1089,6 +1629,288 @@ mod tests {
         assert!(matches!(result.unwrap_err(), Error::SheetNotFound { .. }));
     }
 
+    fn formula_at(wb: &Workbook, sheet: &str, cell: &str) -> String {
+        match wb.get_cell_value(sheet, cell).unwrap() {
+            CellValue::Formula { expr, .. } => expr,
+            other => panic!("expected formula, got {other:?}"),
+        }
+    }
+
+    fn set_formula(wb: &mut Workbook, sheet: &str, cell: &str, expr: &str) {
+        wb.set_cell_value(
+            sheet,
+            cell,
+            CellValue::Formula {
+                expr: expr.to_string(),
+                result: None,
+            },
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_remove_row_updates_references_on_other_sheets() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Data").unwrap();
+        set_formula(&mut wb, "Sheet1", "A1", "SUM(Data!B2:B10)+Data!B5");
+        set_formula(&mut wb, "Sheet1", "A2", "A5+Data!B12");
+        set_formula(&mut wb, "Data", "C1", "B12*Sheet1!A9");
+        wb.set_defined_name("Prices", "Data!$B$2:$B$10", None, None)
+            .unwrap();
+
+        wb.remove_row("Data", 5).unwrap();
+
+        assert_eq!(formula_at(&wb, "Sheet1", "A1"), "SUM(Data!B2:B9)+#REF!");
+        assert_eq!(formula_at(&wb, "Sheet1", "A2"), "A5+Data!B11");
+        assert_eq!(formula_at(&wb, "Data", "C1"), "B11*Sheet1!A9");
+        let name = wb.get_defined_name("Prices", None).unwrap().unwrap();
+        assert_eq!(name.value, "Data!$B$2:$B$9");
+    }
+
+    #[test]
+    fn test_insert_rows_inside_shared_formula_block() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Data").unwrap();
+        for r in 1..=4 {
+            set_formula(
+                &mut wb,
+                "Sheet1",
+                &format!("B{r}"),
+                &format!("A{r}+C{}", r + 8),
+            );
+            set_formula(&mut wb, "Data", &format!("B{r}"), &format!("Sheet1!A{r}"));
+        }
+        assert_eq!(wb.share_formulas("Sheet1").unwrap(), 1);
+        assert_eq!(wb.share_formulas("Data").unwrap(), 1);
+
+        wb.insert_rows("Sheet1", 3, 1).unwrap();
+
+        assert_eq!(formula_at(&wb, "Sheet1", "B1"), "A1+C10");
+        assert_eq!(formula_at(&wb, "Sheet1", "B2"), "A2+C11");
+        assert_eq!(formula_at(&wb, "Sheet1", "B4"), "A4+C12");
+        assert_eq!(formula_at(&wb, "Sheet1", "B5"), "A5+C13");
+        assert_eq!(formula_at(&wb, "Data", "B2"), "Sheet1!A2");
+        assert_eq!(formula_at(&wb, "Data", "B3"), "Sheet1!A4");
+        assert_eq!(formula_at(&wb, "Data", "B4"), "Sheet1!A5");
+    }
+
+    #[test]
+    fn test_delete_shared_formula_master() {
+        let mut wb = Workbook::new();
+        for r in 1..=4 {
+            wb.set_cell_value("Sheet1", &format!("A{r}"), r as f64)
+                .unwrap();
+            set_formula(&mut wb, "Sheet1", &format!("B{r}"), &format!("A{r}*2"));
+            set_formula(&mut wb, "Sheet1", &format!("C{r}"), &format!("A{r}*3"));
+        }
+        assert_eq!(wb.share_formulas("Sheet1").unwrap(), 2);
+
+        wb.remove_row("Sheet1", 1).unwrap();
+        wb.delete_cells("Sheet1", "C1", DeleteShift::Up).unwrap();
+        wb.calculate_all().unwrap();
+
+        assert_eq!(formula_at(&wb, "Sheet1", "B1"), "A1*2");
+        assert_eq!(formula_at(&wb, "Sheet1", "B3"), "A3*2");
+        assert_eq!(formula_at(&wb, "Sheet1", "C1"), "A2*3");
+        assert_eq!(formula_at(&wb, "Sheet1", "C2"), "A3*3");
+        match wb.get_cell_value("Sheet1", "B3").unwrap() {
+            CellValue::Formula { result, .. } => {
+                assert_eq!(*result.unwrap(), CellValue::Number(8.0))
+            }
+            other => panic!("expected formula, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_insert_cols_updates_references_on_other_sheets() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Data").unwrap();
+        set_formula(&mut wb, "Sheet1", "A1", "SUM(Data!A1:C1)*'Data'!$D$4");
+        wb.set_defined_name("_xlnm.Print_Area", "Data!$A$1:$D$20", Some("Data"), None)
+            .unwrap();
+
+        wb.insert_cols("Data", "B", 2).unwrap();
+
+        assert_eq!(formula_at(&wb, "Sheet1", "A1"), "SUM(Data!A1:E1)*Data!$F$4");
+        let area = wb
+            .get_defined_name("_xlnm.Print_Area", Some("Data"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(area.value, "Data!$A$1:$F$20");
+    }
+
+    #[test]
+    fn test_remove_row_updates_chart_series_and_tables() {
+        use crate::chart::{ChartConfig, ChartSeries, ChartType};
+        let mut wb = Workbook::new();
+        wb.new_sheet("Data").unwrap();
+        wb.add_table(
+            "Data",
+            &crate::table::TableConfig {
+                name: "Sales".to_string(),
+                display_name: "Sales".to_string(),
+                range: "A1:B10".to_string(),
+                columns: vec![
+                    crate::table::TableColumn {
+                        name: "Month".to_string(),
+                        totals_row_function: None,
+                        totals_row_label: None,
+                    },
+                    crate::table::TableColumn {
+                        name: "Total".to_string(),
+                        totals_row_function: None,
+                        totals_row_label: None,
+                    },
+                ],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let config = ChartConfig {
+            chart_type: ChartType::Col,
+            title: None,
+            series: vec![ChartSeries {
+                name: "Data!$B$1".to_string(),
+                categories: "Data!$A$2:$A$10".to_string(),
+                values: "Data!$B$2:$B$10".to_string(),
+                x_values: None,
+                bubble_sizes: None,
+            }],
+            show_legend: false,
+            view_3d: None,
+        };
+        wb.add_chart("Sheet1", "D1", "K15", &config).unwrap();
+
+        wb.remove_row("Data", 3).unwrap();
+
+        let formulas: Vec<String> = crate::chart::series_formulas_mut(&mut wb.charts[0].1)
+            .into_iter()
+            .map(|f| f.clone())
+            .collect();
+        assert!(formulas.contains(&"Data!$A$2:$A$9".to_string()));
+        assert!(formulas.contains(&"Data!$B$2:$B$9".to_string()));
+        let tables = wb.get_tables("Data").unwrap();
+        assert_eq!(tables[0].range, "A1:B9");
+    }
+
+    #[test]
+    fn test_remove_row_updates_comments_and_sparklines() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Data").unwrap();
+        for (cell, text) in [("A2", "removed"), ("A4", "kept")] {
+            wb.add_comment(
+                "Data",
+                &crate::comment::CommentConfig {
+                    cell: cell.to_string(),
+                    author: "Ann".to_string(),
+                    text: text.to_string(),
+                },
+            )
+            .unwrap();
+        }
+        wb.add_sparkline(
+            "Sheet1",
+            &crate::sparkline::SparklineConfig::new("Data!A1:E5", "F1"),
+        )
+        .unwrap();
+
+        wb.remove_row("Data", 2).unwrap();
+
+        let comments = wb.get_comments("Data").unwrap();
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].cell, "A3");
+        assert_eq!(comments[0].text, "kept");
+        let sparklines = wb.get_sparklines("Sheet1").unwrap();
+        assert_eq!(sparklines[0].data_range, "Data!A1:E4");
+        assert_eq!(sparklines[0].location, "F1");
+    }
+
+    #[test]
+    fn test_workbook_remove_rows_in_one_pass() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Data").unwrap();
+        for row in 1..=10 {
+            wb.set_cell_value("Data", &format!("A{row}"), row as f64)
+                .unwrap();
+        }
+        set_formula(&mut wb, "Sheet1", "A1", "SUM(Data!A1:A10)+Data!A3");
+        wb.merge_cells("Data", "B8", "C9").unwrap();
+
+        wb.remove_rows("Data", 2, 5).unwrap();
+
+        assert_eq!(
+            wb.get_cell_value("Data", "A2").unwrap(),
+            CellValue::Number(7.0)
+        );
+        assert_eq!(wb.get_cell_value("Data", "A6").unwrap(), CellValue::Empty);
+        assert_eq!(formula_at(&wb, "Sheet1", "A1"), "SUM(Data!A1:A5)+#REF!");
+        assert_eq!(wb.get_merge_cells("Data").unwrap(), vec!["B3:C4"]);
+    }
+
+    #[test]
+    fn test_workbook_remove_cols_in_one_pass() {
+        let mut wb = Workbook::new();
+        for (cell, value) in [("A1", 1.0), ("B1", 2.0), ("C1", 3.0), ("D1", 4.0)] {
+            wb.set_cell_value("Sheet1", cell, value).unwrap();
+        }
+        set_formula(&mut wb, "Sheet1", "A2", "SUM(A1:D1)*D1");
+
+        wb.remove_cols("Sheet1", "B", 2).unwrap();
+
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "B1").unwrap(),
+            CellValue::Number(4.0)
+        );
+        assert_eq!(formula_at(&wb, "Sheet1", "A2"), "SUM(A1:B1)*B1");
+    }
+
+    #[test]
+    fn test_workbook_insert_cells_shift_down() {
+        let mut wb = Workbook::new();
+        wb.set_cell_value("Sheet1", "B2", "moved").unwrap();
+        wb.set_cell_value("Sheet1", "C2", "stays").unwrap();
+        set_formula(&mut wb, "Sheet1", "E1", "B2&C2&SUM(B2:B5)&SUM(B2:C5)");
+
+        wb.insert_cells("Sheet1", "B2:B3", InsertShift::Down)
+            .unwrap();
+
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "B4").unwrap(),
+            CellValue::String("moved".to_string())
+        );
+        assert_eq!(
+            wb.get_cell_value("Sheet1", "C2").unwrap(),
+            CellValue::String("stays".to_string())
+        );
+        // Ranges reaching outside column B are left alone.
+        assert_eq!(
+            formula_at(&wb, "Sheet1", "E1"),
+            "B4&C2&SUM(B4:B7)&SUM(B2:C5)"
+        );
+    }
+
+    #[test]
+    fn test_workbook_delete_cells_shift_left() {
+        let mut wb = Workbook::new();
+        wb.new_sheet("Data").unwrap();
+        for (cell, value) in [("A1", 1.0), ("B1", 2.0), ("C1", 3.0), ("B2", 5.0)] {
+            wb.set_cell_value("Data", cell, value).unwrap();
+        }
+        set_formula(&mut wb, "Sheet1", "A1", "Data!B1+Data!C1+Data!B2");
+
+        wb.delete_cells("Data", "B1", DeleteShift::Left).unwrap();
+
+        assert_eq!(
+            wb.get_cell_value("Data", "B1").unwrap(),
+            CellValue::Number(3.0)
+        );
+        assert_eq!(
+            wb.get_cell_value("Data", "B2").unwrap(),
+            CellValue::Number(5.0)
+        );
+        assert_eq!(formula_at(&wb, "Sheet1", "A1"), "#REF!+Data!B1+Data!B2");
+    }
+
     #[test]
     fn test_new_stream_writer_validates_name() {
         let wb = Workbook::new();
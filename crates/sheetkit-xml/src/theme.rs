@@ -54,19 +54,17 @@ pub fn parse_theme_colors(xml_bytes: &[u8]) -> ThemeColors {
                     }
                 }
             }
-            Ok(Event::Empty(ref e)) => {
-                if in_color_scheme {
-                    let local_name = e.local_name();
-                    let name = std::str::from_utf8(local_name.as_ref()).unwrap_or("");
-                    if let Some(idx) = ThemeColors::SLOT_NAMES.iter().position(|&s| s == name) {
-                        current_slot = Some(idx);
-                    }
-                    if let Some(slot) = current_slot {
-                        extract_color_from_element(e, &mut colors, slot);
-                    }
-                    if ThemeColors::SLOT_NAMES.contains(&name) {
-                        current_slot = None;
-                    }
+            Ok(Event::Empty(ref e)) if in_color_scheme => {
+                let local_name = e.local_name();
+                let name = std::str::from_utf8(local_name.as_ref()).unwrap_or("");
+                if let Some(idx) = ThemeColors::SLOT_NAMES.iter().position(|&s| s == name) {
+                    current_slot = Some(idx);
+                }
+                if let Some(slot) = current_slot {
+                    extract_color_from_element(e, &mut colors, slot);
+                }
+                if ThemeColors::SLOT_NAMES.contains(&name) {
+                    current_slot = None;
                 }
             }
             Ok(Event::End(ref e)) => {
@@ -155,6 +155,9 @@ pub struct WorkbookView {
     #[serde(rename = "@windowHeight", skip_serializing_if = "Option::is_none")]
     pub window_height: Option<u32>,
 
+    #[serde(rename = "@firstSheet", skip_serializing_if = "Option::is_none")]
+    pub first_sheet: Option<u32>,
+
     #[serde(rename = "@activeTab", skip_serializing_if = "Option::is_none")]
     pub active_tab: Option<u32>,
 }
@@ -415,6 +418,7 @@ mod tests {
                     y_window: Some(0),
                     window_width: Some(28800),
                     window_height: Some(12210),
+                    first_sheet: None,
                     active_tab: Some(0),
                 }],
             }),
@@ -685,6 +689,7 @@ mod tests {
                     y_window: Some(0),
                     window_width: Some(28800),
                     window_height: Some(12210),
+                    first_sheet: None,
                     active_tab: None,
                 }],
             }),
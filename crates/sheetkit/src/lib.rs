@@ -10,17 +10,22 @@
 //! ```
 
 pub use sheetkit_core::defined_names::{DefinedNameInfo, DefinedNameScope};
-pub use sheetkit_core::doc_props::{AppProperties, CustomPropertyValue, DocProperties};
+pub use sheetkit_core::doc_props::{
+    AppProperties, CalcSettings, CustomPropertyValue, DocProperties, WorkbookSettings,
+};
 pub use sheetkit_core::error::{Error, Result};
 pub use sheetkit_core::protection::WorkbookProtectionConfig;
 pub use sheetkit_core::sheet::SheetProtectionConfig;
 pub use sheetkit_core::stream::StreamWriter;
-pub use sheetkit_core::workbook::{AuxParts, OpenOptions, ReadMode, Workbook, WorkbookFormat};
+pub use sheetkit_core::workbook::{
+    AuxParts, OpenOptions, ReadMode, TracedCell, Workbook, WorkbookFormat,
+};
 
 pub use sheetkit_core::cell::{
     date_to_serial, datetime_to_serial, is_date_format_code, is_date_num_fmt, serial_to_date,
-    serial_to_datetime, CellValue,
+    serial_to_datetime, CellValue, DateSystem,
 };
+pub use sheetkit_core::cell_shift::{DeleteShift, InsertShift};
 pub use sheetkit_core::chart::{ChartConfig, ChartSeries, ChartType, View3DConfig};
 pub use sheetkit_core::comment::CommentConfig;
 pub use sheetkit_core::conditional::{
@@ -34,6 +39,7 @@ pub use sheetkit_core::page_layout::{Orientation, PageMarginsConfig, PaperSize};
 pub use sheetkit_core::pivot::{
     AggregateFunction, PivotDataField, PivotField, PivotTableConfig, PivotTableInfo,
 };
+pub use sheetkit_core::range_copy::CopyRangeOptions;
 pub use sheetkit_core::render::RenderOptions;
 pub use sheetkit_core::rich_text::{rich_text_to_plain, RichTextRun};
 pub use sheetkit_core::shape::{ShapeConfig, ShapeType};
@@ -52,6 +58,21 @@ pub use sheetkit_core::validation::{
 };
 pub use sheetkit_core::vba::{VbaModule, VbaModuleType, VbaProject};
 
+/// Formula types used by functions registered with
+/// [`Workbook::register_function`], by evaluation traces, and for parsing,
+/// printing, and rewriting formulas.
+pub mod formula {
+    pub use sheetkit_core::formula::eval::{
+        coerce_to_bool, coerce_to_number, coerce_to_string, ArrayValue, EvalStep, Evaluator,
+    };
+    pub use sheetkit_core::formula::functions::check_arg_count;
+    pub use sheetkit_core::formula::{
+        fold_children, format_formula, parse_formula, walk_expr, BinaryOperator, CellReference,
+        CustomFunction, Expr, Fold, FunctionRegistry, StructuredReference, TableItem,
+        UnaryOperator, Visit,
+    };
+}
+
 /// Utility functions for cell reference conversion.
 pub mod utils {
     pub use sheetkit_core::utils::cell_ref::{
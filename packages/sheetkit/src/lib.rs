@@ -1349,8 +1349,9 @@ impl Workbook {
             .worksheet_xml_ref(&sheet)
             .map_err(|e| Error::from_reason(e.to_string()))?;
         let sst = self.inner.sst_ref();
-        let buf = sheetkit_core::raw_transfer::sheet_to_raw_buffer(ws, sst)
-            .map_err(|e| Error::from_reason(e.to_string()))?;
+        let buf =
+            sheetkit_core::raw_transfer::sheet_to_raw_buffer(ws, sst, self.inner.date_system())
+                .map_err(|e| Error::from_reason(e.to_string()))?;
         Ok(Buffer::from(buf))
     }
 
@@ -1364,8 +1365,12 @@ impl Workbook {
             .worksheet_xml_ref(&sheet)
             .map_err(|e| Error::from_reason(e.to_string()))?;
         let sst = self.inner.sst_ref();
-        let buf = sheetkit_core::raw_transfer_v2::sheet_to_raw_buffer_v2(ws, sst)
-            .map_err(|e| Error::from_reason(e.to_string()))?;
+        let buf = sheetkit_core::raw_transfer_v2::sheet_to_raw_buffer_v2(
+            ws,
+            sst,
+            self.inner.date_system(),
+        )
+        .map_err(|e| Error::from_reason(e.to_string()))?;
         Ok(Buffer::from(buf))
     }
 